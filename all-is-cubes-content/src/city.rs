@@ -55,10 +55,15 @@ pub(crate) async fn demo_city(
     // We do this once so that if multiple exhibits end up wanting them there are no conflicts.
     // TODO: We want a "module loading" system that allows expressing dependencies.
     let ui_blocks_progress = p.start_and_cut(0.05, "UiBlocks").await;
-    all_is_cubes_ui::vui::blocks::UiBlocks::new(universe, ui_blocks_progress)
-        .await
-        .install(universe)
-        .unwrap();
+    all_is_cubes_ui::vui::blocks::UiBlocks::new(
+        universe,
+        all_is_cubes_ui::i18n::Language::English.catalog(),
+        &all_is_cubes::camera::UiPaletteOption::Standard,
+        ui_blocks_progress,
+    )
+    .await
+    .install(universe)
+    .unwrap();
     let icons_blocks_progress = p.start_and_cut(0.05, "Icons").await;
     all_is_cubes::inv::Icons::new(universe, icons_blocks_progress)
         .await
@@ -297,6 +302,7 @@ pub(crate) async fn demo_city(
     {
         exhibit_progress.set_label(format!("Exhibit “{name}”", name = exhibit.name));
         exhibit_progress.progress(0.0).await;
+        exhibit_progress.check_cancelled()?;
         let start_exhibit_time = Instant::now();
 
         // Execute the exhibit factory function.
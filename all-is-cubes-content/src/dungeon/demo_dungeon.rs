@@ -593,10 +593,10 @@ pub(crate) async fn demo_dungeon(
     space.set_physics(physics);
     light_progress.progress(0.01).await;
     // Make a rough lighting pass so that we don't have completely black rooms on start.
-    space.evaluate_light(254, |_i| {
+    space.evaluate_light_cancellable(254, light_progress.cancellation(), |_i| {
         // TODO: report progress
         // light_progress.progress(i.max_queue_priority as f32 / 255.0)
-    });
+    })?;
     light_progress.finish().await;
 
     Ok(space)
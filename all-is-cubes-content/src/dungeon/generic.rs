@@ -130,6 +130,7 @@ pub async fn build_dungeon<Room, ThemeT: Theme<Room>>(
                 pass = pass + 1
             ));
             progress.progress(0.0).await;
+            progress.check_cancelled()?;
             theme.place_room(
                 space,
                 pass,
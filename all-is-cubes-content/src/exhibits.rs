@@ -36,6 +36,7 @@ use all_is_cubes::math::{
     Face6, FaceMap, FreeCoordinate, GridAab, GridCoordinate, GridMatrix, GridPoint, GridRotation,
     GridVector, NotNan, Rgb, Rgba,
 };
+use all_is_cubes::physics::CollisionGroups;
 use all_is_cubes::space::{SetCubeError, Space, SpacePhysics, SpaceTransaction};
 use all_is_cubes::transaction::{self, Transaction as _};
 use all_is_cubes::universe::Universe;
@@ -1041,7 +1042,7 @@ async fn DASHED_BOXES(_: &Exhibit, universe: &mut Universe) {
 #[macro_rules_attribute::apply(exhibit!)]
 #[exhibit(
     name: "Swimming Pool",
-    subtitle: "Transparent blocks that can be passed through",
+    subtitle: "Transparent blocks that can be swum through",
 )]
 async fn SWIMMING_POOL(_: &Exhibit, _: &mut Universe) {
     let width = 6;
@@ -1053,7 +1054,7 @@ async fn SWIMMING_POOL(_: &Exhibit, _: &mut Universe) {
         Block::builder()
             .display_name("Not entirely unlike water")
             .color(Rgba::new(0.96, 0.96, 1.0, 0.1))
-            .collision(BlockCollision::None)
+            .collision_groups(CollisionGroups::FLUID)
             .build(),
     )?;
     Ok(space)
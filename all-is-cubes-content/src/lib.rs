@@ -69,7 +69,11 @@ mod landscape;
 pub use landscape::*;
 mod menu;
 mod noise;
+mod physics_lab;
+mod procedural_city;
 mod tree;
+mod weather;
+pub use weather::*;
 
 // Reexport the content parts that are implemented in the core crate.
 pub use all_is_cubes::content::*;
@@ -21,7 +21,7 @@ use all_is_cubes::{
     inv::Tool,
     linking::InGenError,
     math::{Face6, GridAab, GridMatrix, GridVector},
-    space::{Space, SpaceBuilder, SpacePhysics, SpaceTransaction},
+    space::{Sky, Space, SpaceBuilder, SpacePhysics, SpaceTransaction},
     transaction::{self, Merge, Transaction as _},
     universe::Universe,
 };
@@ -179,7 +179,7 @@ pub(crate) fn template_menu(universe: &mut Universe) -> Result<Space, InGenError
     let mut space = Space::builder(bounds)
         .physics({
             let mut p = SpacePhysics::default();
-            p.sky_color = palette::MENU_BACK.to_rgb();
+            p.sky = Sky::Uniform(palette::MENU_BACK.to_rgb());
             p.gravity = Vector3::zero();
             p
         })
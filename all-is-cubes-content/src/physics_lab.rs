@@ -0,0 +1,473 @@
+//! A space containing a set of stations for exercising physics and other game
+//! mechanics: stairs, slopes, water, moving platforms, and signal circuits.
+//!
+//! The positions of the stations are given by the constants and functions in this
+//! module, so that tests can find them.
+//!
+//! TODO: Add ladders once there is climbing physics.
+
+use all_is_cubes::behavior::{Behavior, BehaviorContext};
+use all_is_cubes::block::{Block, BlockSignal, Resolution, AIR};
+use all_is_cubes::character::Spawn;
+use all_is_cubes::content::{free_editing_starter_inventory, make_slab, palette};
+use all_is_cubes::drawing::VoxelBrush;
+use all_is_cubes::inv::{Slot, Tool};
+use all_is_cubes::linking::InGenError;
+use all_is_cubes::math::{Face6, GridAab, GridCoordinate, GridPoint, GridVector, Rgb, Rgba};
+use all_is_cubes::physics::CollisionGroups;
+use all_is_cubes::space::{Space, SpaceTransaction};
+use all_is_cubes::time::Tick;
+use all_is_cubes::transaction::{self, Merge, Transaction as _};
+use all_is_cubes::universe::{RefVisitor, Universe, UniverseTransaction, VisitRefs};
+
+/// Bounds of the lab space. The floor is at y = 0.
+fn lab_bounds() -> GridAab {
+    GridAab::from_lower_upper([-24, -4, -16], [24, 12, 16])
+}
+
+/// Width of each lane of stairs or slope.
+const LANE_WIDTH: GridCoordinate = 3;
+
+/// Lower corner of the first lane of stairs; further lanes are in the +X direction.
+const STAIRS_ORIGIN: [GridCoordinate; 3] = [-22, 0, -6];
+/// Height of each step of each lane of stairs, in quarter blocks.
+const STAIR_RISES: [GridCoordinate; 4] = [1, 2, 3, 4];
+/// Number of steps in each lane of stairs.
+const STAIR_STEPS: GridCoordinate = 6;
+
+/// Lower corner of the first slope; further slopes are in the +X direction.
+const SLOPES_ORIGIN: [GridCoordinate; 3] = [-6, 0, -6];
+/// Height of each step of each slope, in sixteenths of a block.
+const SLOPE_RISES: [GridCoordinate; 2] = [1, 2];
+/// Number of steps in each slope.
+const SLOPE_STEPS: GridCoordinate = 16;
+
+/// Region of the water pool, which is sunk into the floor.
+fn pool() -> GridAab {
+    GridAab::from_lower_upper([2, -3, -6], [8, 0, 0])
+}
+
+/// Region through which the elevator platform moves upward and back down.
+fn elevator_track() -> GridAab {
+    GridAab::from_lower_upper([11, 0, -6], [14, 8, -3])
+}
+/// Tower whose top the elevator reaches.
+fn elevator_tower() -> GridAab {
+    GridAab::from_lower_upper([14, 0, -6], [17, 8, -3])
+}
+/// Region through which the horizontal platform moves back and forth.
+fn shuttle_track() -> GridAab {
+    GridAab::from_lower_upper([11, 3, 2], [23, 4, 5])
+}
+
+/// Position of the power source of the signal circuit which lights a lamp.
+/// The wire runs in the +X direction to the lamp at [`CIRCUIT_LAMP`].
+const CIRCUIT_SOURCE: [GridCoordinate; 3] = [2, 0, 8];
+/// Position of the lamp lit by the circuit starting at [`CIRCUIT_SOURCE`].
+const CIRCUIT_LAMP: [GridCoordinate; 3] = [9, 0, 8];
+/// Position of the power source of a circuit which is broken, for the player to repair.
+const BROKEN_CIRCUIT_SOURCE: [GridCoordinate; 3] = [2, 0, 11];
+/// Position of the lamp of the broken circuit.
+const BROKEN_CIRCUIT_LAMP: [GridCoordinate; 3] = [9, 0, 11];
+/// Position of the gap in the broken circuit.
+const BROKEN_CIRCUIT_GAP: [GridCoordinate; 3] = [6, 0, 11];
+
+/// Time, in seconds, between each one-cube movement of a [`MovingPlatform`].
+const PLATFORM_MOVE_INTERVAL: f64 = 0.5;
+
+pub(crate) fn physics_lab(universe: &mut Universe) -> Result<Space, InGenError> {
+    let floor = Block::builder()
+        .display_name("Lab Floor")
+        .color(palette::STONE.with_alpha_one())
+        .build();
+    let tower = Block::builder()
+        .display_name("Lab Wall")
+        .color(palette::STEEL.with_alpha_one())
+        .build();
+    let platform = Block::builder()
+        .display_name("Moving Platform")
+        .color(Rgba::new(0.9, 0.6, 0.1, 1.0))
+        .build();
+    let water = Block::builder()
+        .display_name("Water")
+        .color(Rgba::new(0.2, 0.4, 0.9, 0.5))
+        .collision_groups(CollisionGroups::FLUID)
+        .build();
+    let source = Block::builder()
+        .display_name("Power Source")
+        .color(Rgba::new(0.8, 0.1, 0.1, 1.0))
+        .light_emission(Rgb::new(0.5, 0.0, 0.0))
+        .signal(BlockSignal::Source)
+        .build();
+    let wire = Block::builder()
+        .display_name("Wire")
+        .color(Rgba::new(0.4, 0.1, 0.1, 1.0))
+        .signal(BlockSignal::Wire)
+        .build();
+    let lamp_off = Block::builder()
+        .display_name("Lamp")
+        .color(Rgba::new(0.3, 0.3, 0.2, 1.0))
+        .build();
+    let lamp_on = Block::builder()
+        .display_name("Lamp")
+        .color(Rgba::new(1.0, 1.0, 0.8, 1.0))
+        .light_emission(Rgb::new(6.0, 6.0, 5.0))
+        .signal(BlockSignal::Sink {
+            powered: None,
+            unpowered: Some(VoxelBrush::single(lamp_off)),
+        })
+        .build();
+    let lamp_waiting = Block::builder()
+        .display_name("Lamp")
+        .color(Rgba::new(0.3, 0.3, 0.2, 1.0))
+        .signal(BlockSignal::Sink {
+            powered: Some(VoxelBrush::single(lamp_on)),
+            unpowered: None,
+        })
+        .build();
+
+    let bounds = lab_bounds();
+    let mut space = Space::builder(bounds)
+        .spawn({
+            let mut spawn = Spawn::default_for_new_space(bounds);
+            spawn.set_eye_position([0.5, 2.5, -13.5]);
+            spawn.set_look_direction([0.0, -0.2, 1.0]);
+            let mut inventory = free_editing_starter_inventory(true);
+            for block in [&wire, &source, &lamp_waiting, &water] {
+                inventory.push(Slot::stack(40, Tool::Block(block.clone())));
+            }
+            spawn.set_inventory(inventory);
+            spawn
+        })
+        .build();
+
+    space.fill_uniform(
+        GridAab::from_lower_upper(
+            bounds.lower_bounds(),
+            [bounds.upper_bounds().x, 0, bounds.upper_bounds().z],
+        ),
+        &floor,
+    )?;
+
+    // Stairs and slopes
+    let quarter_slabs: Vec<Block> = (1..4)
+        .map(|n| make_slab(universe, n, Resolution::R4))
+        .collect();
+    for (i, &rise) in STAIR_RISES.iter().enumerate() {
+        staircase(
+            &mut space,
+            &floor,
+            &quarter_slabs,
+            lane_origin(STAIRS_ORIGIN, i),
+            STAIR_STEPS,
+            rise,
+        )?;
+    }
+    let sixteenth_slabs: Vec<Block> = (1..16)
+        .map(|n| make_slab(universe, n, Resolution::R16))
+        .collect();
+    for (i, &rise) in SLOPE_RISES.iter().enumerate() {
+        staircase(
+            &mut space,
+            &floor,
+            &sixteenth_slabs,
+            lane_origin(SLOPES_ORIGIN, i),
+            SLOPE_STEPS,
+            rise,
+        )?;
+    }
+
+    // Water
+    space.fill_uniform(pool(), &water)?;
+
+    // Moving platforms
+    space.fill_uniform(elevator_tower(), &tower)?;
+    for (track, direction, length) in [
+        (elevator_track(), Face6::PY, 1),
+        (shuttle_track(), Face6::PX, 3),
+    ] {
+        let moving_platform = MovingPlatform::new(platform.clone(), direction, length);
+        space.fill_uniform(moving_platform.platform_bounds(track), &platform)?;
+        SpaceTransaction::add_behavior(track, moving_platform)
+            .execute(&mut space, &mut transaction::no_outputs)?;
+    }
+
+    // Signal circuits
+    for (source_cube, lamp_cube) in [
+        (CIRCUIT_SOURCE, CIRCUIT_LAMP),
+        (BROKEN_CIRCUIT_SOURCE, BROKEN_CIRCUIT_LAMP),
+    ] {
+        let source_cube = GridPoint::from(source_cube);
+        let lamp_cube = GridPoint::from(lamp_cube);
+        space.set(source_cube, &source)?;
+        space.fill_uniform(
+            GridAab::from_lower_upper(
+                source_cube + GridVector::unit_x(),
+                lamp_cube + GridVector::new(0, 1, 1),
+            ),
+            &wire,
+        )?;
+        space.set(lamp_cube, &lamp_waiting)?;
+    }
+    space.set(BROKEN_CIRCUIT_GAP, &AIR)?;
+
+    Ok(space)
+}
+
+/// Returns the lower corner of the `index`th lane of a set of lanes starting at `origin`.
+fn lane_origin(origin: [GridCoordinate; 3], index: usize) -> GridPoint {
+    GridPoint::from(origin) + GridVector::unit_x() * (index as GridCoordinate * (LANE_WIDTH + 1))
+}
+
+/// Builds a lane of stairs ascending in the +Z direction, whose steps rise by
+/// `rise` / `slabs.len() + 1` blocks each.
+///
+/// `slabs[n - 1]` should be a block filled to height `n` / `slabs.len() + 1`.
+fn staircase(
+    space: &mut Space,
+    solid: &Block,
+    slabs: &[Block],
+    origin: GridPoint,
+    steps: GridCoordinate,
+    rise: GridCoordinate,
+) -> Result<(), InGenError> {
+    let denominator = slabs.len() as GridCoordinate + 1;
+    for step in 0..steps {
+        let height = (step + 1) * rise;
+        let full_blocks = height / denominator;
+        let remainder = height.rem_euclid(denominator);
+        let column_origin = origin + GridVector::new(0, 0, step);
+        space.fill_uniform(
+            GridAab::from_lower_size(column_origin, [LANE_WIDTH, full_blocks, 1]),
+            solid,
+        )?;
+        if remainder > 0 {
+            space.fill_uniform(
+                GridAab::from_lower_size(
+                    column_origin + GridVector::new(0, full_blocks, 0),
+                    [LANE_WIDTH, 1, 1],
+                ),
+                &slabs[(remainder - 1) as usize],
+            )?;
+        }
+    }
+    Ok(())
+}
+
+/// A [`Behavior`] which moves a platform of blocks back and forth along the region it is
+/// attached to, one cube at a time.
+///
+/// The platform fills the region except along the direction of motion, where it is
+/// `length` cubes long. It starts at the end of the region opposite `direction`.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct MovingPlatform {
+    block: Block,
+    direction: Face6,
+    length: GridCoordinate,
+    /// Distance the platform has moved from its starting position.
+    offset: GridCoordinate,
+    /// Whether the platform is currently moving in `direction` rather than back.
+    forward: bool,
+    /// Seconds until the platform next moves.
+    until_move: f64,
+}
+
+impl MovingPlatform {
+    pub(crate) fn new(block: Block, direction: Face6, length: GridCoordinate) -> Self {
+        Self {
+            block,
+            direction,
+            length,
+            offset: 0,
+            forward: true,
+            until_move: PLATFORM_MOVE_INTERVAL,
+        }
+    }
+
+    /// Returns the cubes currently occupied by the platform, given the region the
+    /// behavior is attached to.
+    fn platform_bounds(&self, track: GridAab) -> GridAab {
+        track
+            .abut(self.direction.opposite(), -self.length)
+            .unwrap()
+            .translate(self.direction.normal_vector::<GridCoordinate>() * self.offset)
+    }
+}
+
+impl Behavior<Space> for MovingPlatform {
+    fn step(&self, context: &BehaviorContext<'_, Space>, tick: Tick) -> UniverseTransaction {
+        if tick.paused() {
+            return UniverseTransaction::default();
+        }
+        let mut mut_self = self.clone();
+        mut_self.until_move -= tick.delta_t().as_secs_f64();
+        let track = *context.attachment;
+        let travel = track.size()[self.direction.axis_number()] - self.length;
+        if mut_self.until_move > 0.0 || travel <= 0 {
+            return context.replace_self(mut_self);
+        }
+        mut_self.until_move = PLATFORM_MOVE_INTERVAL;
+
+        if mut_self.offset >= travel {
+            mut_self.forward = false;
+        } else if mut_self.offset <= 0 {
+            mut_self.forward = true;
+        }
+        mut_self.offset += if mut_self.forward { 1 } else { -1 };
+
+        let old_bounds = self.platform_bounds(track);
+        let new_bounds = mut_self.platform_bounds(track);
+        let mut txn = SpaceTransaction::default();
+        for cube in old_bounds.interior_iter() {
+            if !new_bounds.contains_cube(cube) {
+                txn.set(cube, None, Some(AIR)).unwrap();
+            }
+        }
+        for cube in new_bounds.interior_iter() {
+            if !old_bounds.contains_cube(cube) {
+                txn.set(cube, None, Some(self.block.clone())).unwrap();
+            }
+        }
+
+        context
+            .replace_self(mut_self)
+            .merge(context.bind_host(txn))
+            .unwrap()
+    }
+
+    fn alive(&self, _context: &BehaviorContext<'_, Space>) -> bool {
+        true
+    }
+
+    fn ephemeral(&self) -> bool {
+        false
+    }
+}
+
+impl VisitRefs for MovingPlatform {
+    fn visit_refs(&self, visitor: &mut dyn RefVisitor) {
+        self.block.visit_refs(visitor);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use all_is_cubes::cgmath::Point3;
+    use all_is_cubes::entity::Entity;
+    use all_is_cubes::math::{Aab, FreeCoordinate};
+    use all_is_cubes::physics::Body;
+    use all_is_cubes::time::practically_infinite_deadline;
+
+    fn lab() -> (Universe, Space) {
+        let mut universe = Universe::new();
+        let space = physics_lab(&mut universe).unwrap();
+        (universe, space)
+    }
+
+    /// A small body which fits on one step of a lane.
+    fn test_body(position: impl Into<Point3<FreeCoordinate>>) -> Body {
+        Body::new_minimal(position, Aab::new(-0.25, 0.25, -0.25, 0.25, -0.25, 0.25))
+    }
+
+    /// Returns the y coordinate of the bottom of the body after it has been allowed to
+    /// fall onto whatever is below it.
+    fn landing_height(space: &Space, x: FreeCoordinate, z: FreeCoordinate) -> FreeCoordinate {
+        let mut body = test_body([x, 8.0, z]);
+        for _ in 0..100 {
+            body.step(Tick::from_seconds(0.05), Some(space), |_| {});
+        }
+        body.collision_box_abs().lower_bounds_p().y
+    }
+
+    #[test]
+    fn bodies_land_on_stairs_and_slopes() {
+        let (_universe, space) = lab();
+        let lanes = STAIR_RISES
+            .iter()
+            .enumerate()
+            .map(|(i, &rise)| (lane_origin(STAIRS_ORIGIN, i), STAIR_STEPS, rise, 4))
+            .chain(
+                SLOPE_RISES
+                    .iter()
+                    .enumerate()
+                    .map(|(i, &rise)| (lane_origin(SLOPES_ORIGIN, i), SLOPE_STEPS, rise, 16)),
+            );
+        for (origin, steps, rise, denominator) in lanes {
+            for step in [0, steps - 1] {
+                let expected =
+                    FreeCoordinate::from((step + 1) * rise) / FreeCoordinate::from(denominator);
+                let height = landing_height(
+                    &space,
+                    FreeCoordinate::from(origin.x) + 1.5,
+                    FreeCoordinate::from(origin.z + step) + 0.5,
+                );
+                assert!(
+                    (height - expected).abs() < 0.01,
+                    "lane at {origin:?} step {step}: expected {expected}, got {height}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn water_slows_falling() {
+        let (_universe, space) = lab();
+        let pool_center = pool().center();
+        let mut in_water = test_body(pool_center);
+        let mut in_air = test_body([pool_center.x, 10.0, pool_center.z]);
+        for _ in 0..5 {
+            in_water.step(Tick::from_seconds(0.05), Some(&space), |_| {});
+            in_air.step(Tick::from_seconds(0.05), Some(&space), |_| {});
+        }
+        assert!(
+            in_water.velocity.y.abs() < in_air.velocity.y.abs(),
+            "in water {:?}, in air {:?}",
+            in_water.velocity,
+            in_air.velocity
+        );
+    }
+
+    #[test]
+    fn elevator_lifts_entity() {
+        let (mut universe, space) = lab();
+        let space = universe.insert_anonymous(space);
+        let track = elevator_track();
+        let start = Point3::new(
+            FreeCoordinate::from(track.lower_bounds().x) + 1.5,
+            1.3,
+            FreeCoordinate::from(track.lower_bounds().z) + 1.5,
+        );
+        let entity = universe.insert_anonymous(Entity::new(
+            space,
+            test_body(start),
+            Block::from(Rgba::WHITE),
+        ));
+
+        for _ in 0..40 {
+            universe.step(Tick::from_seconds(0.05), practically_infinite_deadline());
+        }
+        let position = entity.read().unwrap().body.position;
+        assert!(position.y > start.y + 2.0, "{position:?}");
+    }
+
+    #[test]
+    fn signal_lights_lamp() {
+        let (mut universe, space) = lab();
+        let space = universe.insert_anonymous(space);
+        universe.step(Tick::from_seconds(0.05), practically_infinite_deadline());
+
+        let space = space.read().unwrap();
+        assert_ne!(
+            space.get_evaluated(CIRCUIT_LAMP).attributes.light_emission,
+            Rgb::ZERO
+        );
+        assert_eq!(
+            space
+                .get_evaluated(BROKEN_CIRCUIT_LAMP)
+                .attributes
+                .light_emission,
+            Rgb::ZERO
+        );
+    }
+}
@@ -0,0 +1,300 @@
+//! A procedurally generated city of roads, lots, and buildings, with no particular
+//! purpose other than being large and detailed.
+//!
+//! Each building is constructed in its own [`Space`] and then copied into the city with
+//! [`space_to_space_copy`], rotated to face the road.
+
+use rand::{Rng, SeedableRng as _};
+use rand_xoshiro::Xoshiro256Plus;
+
+use all_is_cubes::block::{Block, AIR};
+use all_is_cubes::cgmath::Vector3;
+use all_is_cubes::character::Spawn;
+use all_is_cubes::content::free_editing_starter_inventory;
+use all_is_cubes::drawing::VoxelBrush;
+use all_is_cubes::linking::{BlockProvider, InGenError};
+use all_is_cubes::math::{
+    Face6, FaceMap, GridAab, GridCoordinate, GridMatrix, GridPoint, GridRotation, GridVector, Rgba,
+};
+use all_is_cubes::space::{LightPhysics, Space, SpacePhysics};
+use all_is_cubes::universe::Universe;
+use all_is_cubes::util::YieldProgress;
+
+use crate::{space_to_space_copy, DemoBlocks, LandscapeBlocks, TemplateParameters};
+
+/// Width of each road.
+const ROAD_WIDTH: GridCoordinate = 5;
+/// Distance between the starts of adjacent roads; the size of a city block plus its road.
+const BLOCK_PITCH: GridCoordinate = 40;
+/// Width of the sidewalk around each city block.
+const SIDEWALK_WIDTH: GridCoordinate = 1;
+/// Number of cubes from one floor of a building to the next.
+const FLOOR_HEIGHT: GridCoordinate = 4;
+/// Depth of the ground below the streets.
+const GROUND_DEPTH: GridCoordinate = 4;
+
+pub(crate) async fn procedural_city(
+    universe: &mut Universe,
+    p: YieldProgress,
+    params: TemplateParameters,
+) -> Result<Space, InGenError> {
+    let landscape_blocks = BlockProvider::<LandscapeBlocks>::using(universe)?;
+    let demo_blocks = BlockProvider::<DemoBlocks>::using(universe)?;
+    use DemoBlocks::*;
+    use LandscapeBlocks::*;
+
+    let TemplateParameters { size, seed } = params;
+    let size = size.unwrap_or(Vector3::new(192, 48, 192));
+    let mut rng = Xoshiro256Plus::seed_from_u64(seed.unwrap_or(0));
+
+    let bounds = GridAab::from_lower_size(
+        [size.x / -2, -GROUND_DEPTH, size.z / -2],
+        [size.x, size.y, size.z],
+    );
+    if bounds.upper_bounds().y < FLOOR_HEIGHT + 2 {
+        return Err(InGenError::Other(
+            format!("height {} is too small for a city", size.y).into(),
+        ));
+    }
+    // Tallest building which fits, leaving room for the roof.
+    let max_floors = ((bounds.upper_bounds().y - 2) / FLOOR_HEIGHT).max(1);
+
+    let ground_layer = GridAab::from_lower_upper(
+        [bounds.lower_bounds().x, 0, bounds.lower_bounds().z],
+        [bounds.upper_bounds().x, 1, bounds.upper_bounds().z],
+    );
+
+    // Lay out a grid of roads, centered in the space, with city blocks between them.
+    let blocks_x = ((size.x - ROAD_WIDTH) / BLOCK_PITCH).max(0);
+    let blocks_z = ((size.z - ROAD_WIDTH) / BLOCK_PITCH).max(0);
+    let city_origin = GridPoint::new(
+        bounds.lower_bounds().x + (size.x - (blocks_x * BLOCK_PITCH + ROAD_WIDTH)) / 2,
+        0,
+        bounds.lower_bounds().z + (size.z - (blocks_z * BLOCK_PITCH + ROAD_WIDTH)) / 2,
+    );
+    let city_footprint = GridAab::from_lower_size(
+        city_origin,
+        [
+            blocks_x * BLOCK_PITCH + ROAD_WIDTH,
+            1,
+            blocks_z * BLOCK_PITCH + ROAD_WIDTH,
+        ],
+    )
+    .intersection(ground_layer)
+    .ok_or_else(|| {
+        InGenError::Other(
+            format!(
+                "width {} and depth {} are too small for a city",
+                size.x, size.z
+            )
+            .into(),
+        )
+    })?;
+
+    let center_intersection =
+        city_origin + GridVector::new(blocks_x / 2 * BLOCK_PITCH, 1, blocks_z / 2 * BLOCK_PITCH);
+
+    let mut space = Space::builder(bounds)
+        .light_physics(LightPhysics::None) // disable until we are done with bulk updates
+        .spawn({
+            let mut spawn = Spawn::default_for_new_space(bounds);
+            spawn.set_inventory(free_editing_starter_inventory(true));
+            spawn.set_bounds(GridAab::from_lower_size(
+                center_intersection,
+                [ROAD_WIDTH, FLOOR_HEIGHT, ROAD_WIDTH],
+            ));
+            spawn
+        })
+        .build();
+
+    let lamp_brush = VoxelBrush::new([
+        ((0, 0, 0), &demo_blocks[LamppostBase]),
+        ((0, 1, 0), &demo_blocks[LamppostSegment]),
+        ((0, 2, 0), &demo_blocks[LamppostTop]),
+        ((0, 3, 0), &demo_blocks[Lamp]),
+    ]);
+    let wall_blocks: Vec<Block> = [
+        Rgba::new(0.62, 0.60, 0.56, 1.0),
+        Rgba::new(0.55, 0.30, 0.22, 1.0),
+        Rgba::new(0.80, 0.76, 0.64, 1.0),
+        Rgba::new(0.35, 0.38, 0.42, 1.0),
+    ]
+    .into_iter()
+    .map(|color| {
+        Block::builder()
+            .display_name("Building Wall")
+            .color(color)
+            .build()
+    })
+    .collect();
+
+    // Ground and roads.
+    space.fill_uniform(
+        GridAab::from_lower_upper(
+            bounds.lower_bounds(),
+            [bounds.upper_bounds().x, 0, bounds.upper_bounds().z],
+        ),
+        &landscape_blocks[Stone],
+    )?;
+    space.fill_uniform(ground_layer, &landscape_blocks[Grass])?;
+    space.fill_uniform(city_footprint, &demo_blocks[Road])?;
+    p.check_cancelled()?;
+
+    let mut city_blocks_progress =
+        p.split_evenly(usize::try_from(blocks_x * blocks_z).unwrap_or(0).max(1));
+    for bx in 0..blocks_x {
+        for bz in 0..blocks_z {
+            let block_area = GridAab::from_lower_size(
+                city_origin
+                    + GridVector::new(
+                        bx * BLOCK_PITCH + ROAD_WIDTH,
+                        0,
+                        bz * BLOCK_PITCH + ROAD_WIDTH,
+                    ),
+                [BLOCK_PITCH - ROAD_WIDTH, 1, BLOCK_PITCH - ROAD_WIDTH],
+            );
+            let lot_area = block_area.expand(FaceMap::repeat(-SIDEWALK_WIDTH));
+
+            // Sidewalk and lawns
+            space.fill_uniform(block_area, &landscape_blocks[Stone])?;
+            space.fill_uniform(lot_area, &landscape_blocks[Grass])?;
+
+            // Lamps at the corners
+            for corner in [
+                block_area.lower_bounds(),
+                block_area.upper_bounds() - GridVector::new(1, 1, 1),
+            ] {
+                lamp_brush.paint(&mut space, corner + GridVector::new(0, 1, 0))?;
+            }
+
+            // Four lots, each containing one building facing one of the two roads
+            // adjacent to it.
+            let lot_size = (lot_area.size().x - 1) / 2;
+            for (lx, lz) in [(0, 0), (0, 1), (1, 0), (1, 1)] {
+                let lot = GridAab::from_lower_size(
+                    lot_area.lower_bounds()
+                        + GridVector::new(lx * (lot_size + 1), 1, lz * (lot_size + 1)),
+                    [lot_size, bounds.upper_bounds().y - 1, lot_size],
+                );
+                let facing = if rng.gen_bool(0.5) {
+                    if lx == 0 {
+                        Face6::NX
+                    } else {
+                        Face6::PX
+                    }
+                } else if lz == 0 {
+                    Face6::NZ
+                } else {
+                    Face6::PZ
+                };
+
+                let building_size = GridVector::new(
+                    rng.gen_range(7..=lot_size),
+                    rng.gen_range(1..=max_floors) * FLOOR_HEIGHT + 1,
+                    rng.gen_range(7..=lot_size),
+                );
+                let building = building(&mut rng, &demo_blocks, &wall_blocks, building_size)?;
+                let rotation = GridRotation::from_to(Face6::NZ, facing, Face6::PY).unwrap();
+                let rotated_bounds = building
+                    .bounds()
+                    .transform(rotation.to_rotation_matrix())
+                    .unwrap();
+                let placed_bounds = place_against(lot, rotated_bounds.size(), facing);
+                space_to_space_copy(
+                    &building,
+                    building.bounds(),
+                    &mut space,
+                    GridMatrix::from_translation(
+                        placed_bounds.lower_bounds() - rotated_bounds.lower_bounds(),
+                    ) * rotation.to_rotation_matrix(),
+                )?;
+            }
+
+            let block_progress = city_blocks_progress.next().unwrap();
+            block_progress.progress(1.0).await;
+            block_progress.check_cancelled()?;
+        }
+    }
+
+    // Enable light computation
+    space.set_physics({
+        let mut physics = space.physics().clone();
+        physics.light = SpacePhysics::default().light;
+        physics
+    });
+
+    Ok(space)
+}
+
+/// Constructs a building whose front door is on the [`Face6::NZ`] side.
+///
+/// `size` should be at least 3 wide and deep, and one more than a multiple of
+/// [`FLOOR_HEIGHT`] high.
+fn building(
+    rng: &mut impl Rng,
+    demo_blocks: &BlockProvider<DemoBlocks>,
+    wall_blocks: &[Block],
+    size: GridVector,
+) -> Result<Space, InGenError> {
+    let bounds = GridAab::from_lower_size([0, 0, 0], size);
+    let mut space = Space::builder(bounds)
+        .light_physics(LightPhysics::None)
+        .build();
+
+    let wall = &wall_blocks[rng.gen_range(0..wall_blocks.len())];
+    let window = &demo_blocks[DemoBlocks::GlassBlock];
+    let lamp = &demo_blocks[DemoBlocks::Lamp];
+    let top = bounds.upper_bounds().y - 1;
+    let door_x = size.x / 2;
+
+    space.fill(bounds, |cube| {
+        let local_y = cube.y.rem_euclid(FLOOR_HEIGHT);
+        let on_x_wall = cube.x == 0 || cube.x == size.x - 1;
+        let on_z_wall = cube.z == 0 || cube.z == size.z - 1;
+
+        let block = if cube.y == top || local_y == 0 {
+            // Floors and roof
+            wall
+        } else if on_x_wall && on_z_wall {
+            // Corners
+            wall
+        } else if on_x_wall || on_z_wall {
+            let along = if on_x_wall { cube.z } else { cube.x };
+            if cube.z == 0 && cube.x == door_x && cube.y < FLOOR_HEIGHT && local_y < 3 {
+                &AIR
+            } else if local_y == 2 && along.rem_euclid(2) == 1 {
+                window
+            } else {
+                wall
+            }
+        } else if local_y == FLOOR_HEIGHT - 1 && cube.x == door_x && cube.z == size.z / 2 {
+            lamp
+        } else {
+            &AIR
+        };
+        Some(block)
+    })?;
+
+    Ok(space)
+}
+
+/// Returns a box of the given `size` (ignoring its height) within `lot`, centered in the
+/// lot except that it is moved against the side of the lot given by `facing`.
+fn place_against(lot: GridAab, size: GridVector, facing: Face6) -> GridAab {
+    let lower = lot.lower_bounds();
+    let upper = lot.upper_bounds();
+    let lot_size = lot.size();
+    let mut position = GridPoint::new(
+        lower.x + (lot_size.x - size.x) / 2,
+        lower.y,
+        lower.z + (lot_size.z - size.z) / 2,
+    );
+    match facing {
+        Face6::NX => position.x = lower.x,
+        Face6::PX => position.x = upper.x - size.x,
+        Face6::NZ => position.z = lower.z,
+        Face6::PZ => position.z = upper.z - size.z,
+        Face6::NY | Face6::PY => {}
+    }
+    GridAab::from_lower_size(position, size)
+}
@@ -5,18 +5,20 @@ use paste::paste;
 
 use all_is_cubes::block::Block;
 use all_is_cubes::cgmath::{EuclideanSpace as _, Point3, Vector3};
-use all_is_cubes::character::{Character, Spawn};
+use all_is_cubes::character::{Character, PlayerStats, Spawn};
 use all_is_cubes::content::free_editing_starter_inventory;
 use all_is_cubes::linking::{BlockProvider, GenError, InGenError};
 use all_is_cubes::math::{
     Face6, FaceMap, FreeCoordinate, GridAab, GridCoordinate, GridVector, Rgb, Rgba,
 };
-use all_is_cubes::space::{LightPhysics, Space};
+use all_is_cubes::space::{LightPhysics, Sky, Space};
 use all_is_cubes::universe::{Name, URef, Universe};
 use all_is_cubes::util::YieldProgress;
 
 use crate::fractal::menger_sponge;
 use crate::menu::template_menu;
+use crate::physics_lab::physics_lab;
+use crate::procedural_city::procedural_city;
 use crate::{atrium::atrium, demo_city, dungeon::demo_dungeon, install_demo_blocks};
 use crate::{wavy_landscape, LandscapeBlocks};
 
@@ -77,6 +79,10 @@ pub enum UniverseTemplate {
     /// Space with assorted “exhibits” demonstrating or testing various features of All is Cubes.
     DemoCity,
 
+    /// Procedurally generated city of roads and buildings, for exploring or for testing
+    /// performance with a large, detailed space.
+    City,
+
     /// Randomly generated connected rooms.
     /// Someday this might have challenges or become a tutorial.
     Dungeon,
@@ -102,6 +108,10 @@ pub enum UniverseTemplate {
     /// A test scene containing various shapes and colors to exercise the lighting algorithm.
     LightingBench,
 
+    /// A collection of stairs, slopes, water, moving platforms, and signal circuits,
+    /// for manually and automatically testing physics and other game mechanics.
+    PhysicsLab,
+
     /// Use entirely random choices.
     ///
     /// TODO: This doesn't yet produce anything even visible — we need more sanity constraints.
@@ -116,9 +126,8 @@ impl UniverseTemplate {
     pub fn include_in_lists(&self) -> bool {
         use UniverseTemplate::*;
         match self {
-            DemoCity | Dungeon | Atrium | Islands | CornellBox | MengerSponge | LightingBench => {
-                true
-            }
+            DemoCity | City | Dungeon | Atrium | Islands | CornellBox | MengerSponge
+            | LightingBench | PhysicsLab => true,
 
             // Itself a list of templates!
             Menu => false,
@@ -131,7 +140,107 @@ impl UniverseTemplate {
         }
     }
 
+    /// Returns a description of this template and how it responds to
+    /// [`TemplateParameters`], for users choosing a template.
+    pub fn info(&self) -> TemplateInfo {
+        use TemplateBuildTime::*;
+        use UniverseTemplate::*;
+        let (description, uses_seed, uses_size, default_size, build_time) = match self {
+            Menu => (
+                "Provides an interactive menu of other templates.",
+                false,
+                false,
+                None,
+                Fast,
+            ),
+            Blank => ("New universe with no contents at all.", false, false, None, Fast),
+            Fail => (
+                "Always produces an error, for testing error-handling functionality.",
+                false,
+                false,
+                None,
+                Fast,
+            ),
+            DemoCity => (
+                "Space with assorted “exhibits” demonstrating or testing various features.",
+                true,
+                true,
+                Some([160, 60, 160]),
+                Moderate,
+            ),
+            City => (
+                "Procedurally generated city of roads and buildings.",
+                true,
+                true,
+                Some([192, 48, 192]),
+                Moderate,
+            ),
+            Dungeon => (
+                "Randomly generated connected rooms.",
+                true,
+                true,
+                Some([135, 40, 135]),
+                Moderate,
+            ),
+            Islands => (
+                "Large space with separate floating islands.",
+                false,
+                true,
+                Some([1000, 400, 1000]),
+                Slow,
+            ),
+            Atrium => (
+                "A procedural voxel version of the classic Sponza Atrium rendering test scene.",
+                false,
+                false,
+                None,
+                Moderate,
+            ),
+            CornellBox => (
+                "A procedural voxel version of the classic Cornell Box rendering test scene.",
+                false,
+                false,
+                Some([57, 57, 57]),
+                Fast,
+            ),
+            MengerSponge => (
+                "A Menger sponge fractal.",
+                false,
+                false,
+                Some([81, 81, 81]),
+                Fast,
+            ),
+            LightingBench => (
+                "A test scene containing various shapes and colors to exercise the lighting algorithm.",
+                false,
+                true,
+                Some([54, 16, 54]),
+                Fast,
+            ),
+            PhysicsLab => (
+                "Stairs, slopes, water, moving platforms, and signal circuits for testing game mechanics.",
+                false,
+                false,
+                Some([48, 16, 32]),
+                Fast,
+            ),
+            #[cfg(feature = "arbitrary")]
+            Random => ("Use entirely random choices.", true, false, None, Fast),
+        };
+        TemplateInfo {
+            description,
+            uses_seed,
+            uses_size,
+            default_size: default_size.map(Vector3::from),
+            build_time,
+        }
+    }
+
     /// Create a new [`Universe`] based on this template's specifications.
+    ///
+    /// Building may be stopped early, returning an error, by cancelling a
+    /// [`CancellationToken`](all_is_cubes::util::CancellationToken) attached to `p`
+    /// using [`YieldProgress::with_cancellation()`].
     pub async fn build(
         self,
         p: YieldProgress,
@@ -144,9 +253,12 @@ impl UniverseTemplate {
         let [demo_blocks_progress, p] = p.split(0.1);
         install_demo_blocks(&mut universe, demo_blocks_progress).await?;
         p.progress(0.).await;
+        p.check_cancelled()?;
 
         let default_space_name: Name = "space".into();
 
+        // Kept because `p` is given away to the specific template.
+        let cancellation = p.cancellation().clone();
         let mut p = Some(p);
         use UniverseTemplate::*;
         let maybe_space: Option<Result<Space, InGenError>> = match self {
@@ -156,6 +268,7 @@ impl UniverseTemplate {
                 "the Fail template always fails to generate".into(),
             ))),
             DemoCity => Some(demo_city(&mut universe, p.take().unwrap(), params).await),
+            City => Some(procedural_city(&mut universe, p.take().unwrap(), params).await),
             Dungeon => Some(demo_dungeon(&mut universe, p.take().unwrap(), params).await),
             Islands => Some(islands(&mut universe, p.take().unwrap(), params).await),
             Atrium => Some(atrium(&mut universe, p.take().unwrap()).await),
@@ -165,6 +278,7 @@ impl UniverseTemplate {
                 &mut universe,
                 params.size.unwrap_or(Vector3::new(54, 16, 54)),
             )),
+            PhysicsLab => Some(physics_lab(&mut universe)),
             #[cfg(feature = "arbitrary")]
             Random => Some(
                 arbitrary_space(&mut universe, p.take().unwrap(), params.seed.unwrap_or(0)).await,
@@ -174,6 +288,7 @@ impl UniverseTemplate {
         if let Some(p) = p {
             p.progress(1.0).await;
         }
+        cancellation.check()?;
 
         // Insert the space and generate the initial character.
         if let Some(space_result) = maybe_space {
@@ -182,7 +297,9 @@ impl UniverseTemplate {
 
             // TODO: "character" is a special default name used for finding the character the
             // player actually uses, and we should replace that or handle it more formally.
-            universe.insert("character".into(), Character::spawn_default(space_ref))?;
+            let character_ref =
+                universe.insert("character".into(), Character::spawn_default(space_ref))?;
+            universe.insert("character_stats".into(), PlayerStats::new(character_ref))?;
         }
 
         Ok(universe)
@@ -232,6 +349,43 @@ pub struct TemplateParameters {
     pub size: Option<Vector3<GridCoordinate>>,
 }
 
+/// Information about a [`UniverseTemplate`], as returned by [`UniverseTemplate::info()`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[non_exhaustive]
+pub struct TemplateInfo {
+    /// One-sentence description of what the template produces.
+    pub description: &'static str,
+
+    /// Whether [`TemplateParameters::seed`] affects the result.
+    pub uses_seed: bool,
+
+    /// Whether [`TemplateParameters::size`] affects the result.
+    pub uses_size: bool,
+
+    /// Approximate dimensions of the primary space produced when no size is specified,
+    /// if the template has a definite size.
+    pub default_size: Option<Vector3<GridCoordinate>>,
+
+    /// Roughly how long [`UniverseTemplate::build()`] takes with the default parameters.
+    pub build_time: TemplateBuildTime,
+}
+
+/// Rough estimate of how long building a [`UniverseTemplate`] takes, as found in
+/// [`TemplateInfo`].
+///
+/// The estimates are for an optimized build on a typical desktop computer; unoptimized
+/// builds may be many times slower.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+#[non_exhaustive]
+pub enum TemplateBuildTime {
+    /// Under a second.
+    Fast,
+    /// A few seconds.
+    Moderate,
+    /// Long enough that a progress indicator is essential.
+    Slow,
+}
+
 // -- Specific templates below this point ---
 
 async fn islands(
@@ -284,6 +438,7 @@ async fn islands(
             wavy_landscape(occupied_bounds, &mut space, &landscape_blocks, 0.5)?;
         }
         p.progress(i as f32 / island_grid.volume() as f32).await;
+        p.check_cancelled()?;
     }
 
     Ok(space)
@@ -373,7 +528,8 @@ async fn arbitrary_space(
                 // Patch physics to be reasonable
                 let mut p = space.physics().clone();
                 p.gravity = Vector3::zero(); // won't be a floor
-                p.sky_color = p.sky_color * (0.5 / p.sky_color.luminance());
+                let sky_color = p.sky.mean();
+                p.sky = Sky::Uniform(sky_color * (0.5 / sky_color.luminance()));
                 space.set_physics(p);
 
                 // TODO: These patches are still not enough to get a good result.
@@ -396,6 +552,7 @@ mod tests {
     use super::*;
     use all_is_cubes::time::{practically_infinite_deadline, Tick};
     use futures_core::future::BoxFuture;
+    use strum::IntoEnumIterator as _;
 
     #[allow(clippy::let_underscore_future)]
     fn _test_build_future_is_send() {
@@ -404,6 +561,47 @@ mod tests {
         );
     }
 
+    #[test]
+    fn info_is_plausible() {
+        for template in UniverseTemplate::iter() {
+            let info = template.info();
+            assert!(info.description.ends_with('.'), "{template:?}: {info:?}");
+            if let Some(size) = info.default_size {
+                assert!(
+                    size.x > 0 && size.y > 0 && size.z > 0,
+                    "{template:?}: {info:?}"
+                );
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn cancelled_build_fails() {
+        let token = all_is_cubes::util::CancellationToken::new();
+        token.cancel();
+        UniverseTemplate::LightingBench
+            .build(
+                YieldProgress::noop().with_cancellation(token),
+                TemplateParameters::default(),
+            )
+            .await
+            .unwrap_err();
+    }
+
+    #[tokio::test]
+    async fn city_with_zero_width_fails() {
+        UniverseTemplate::City
+            .build(
+                YieldProgress::noop(),
+                TemplateParameters {
+                    seed: None,
+                    size: Some(Vector3::new(0, 48, 192)),
+                },
+            )
+            .await
+            .unwrap_err();
+    }
+
     pub(super) async fn check_universe_template(template: UniverseTemplate) {
         let params = if let UniverseTemplate::Islands = template {
             // Kludge: the islands template is known to be very slow.
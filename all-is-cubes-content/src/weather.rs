@@ -0,0 +1,180 @@
+//! Effects of the [`Weather`] on the blocks of a [`Space`].
+
+use std::sync::Arc;
+
+use rand::{Rng as _, SeedableRng as _};
+use rand_xoshiro::Xoshiro256Plus;
+
+use all_is_cubes::behavior::{Behavior, BehaviorContext};
+use all_is_cubes::block::{Block, AIR};
+use all_is_cubes::math::GridPoint;
+use all_is_cubes::space::{Precipitation, Space, SpaceTransaction};
+use all_is_cubes::time::Tick;
+use all_is_cubes::transaction::Merge;
+use all_is_cubes::universe::{RefVisitor, UniverseTransaction, VisitRefs};
+
+#[cfg(doc)]
+use all_is_cubes::space::Weather;
+
+/// Fraction of the surface which gets wet per second in rain of intensity 1.
+const WETTING_PER_SECOND: f64 = 0.05;
+
+/// Fraction of the surface which dries per second when it is not raining.
+const DRYING_PER_SECOND: f64 = 0.01;
+
+/// A [`Behavior`] which, while it is raining in its [`Space`], replaces surface blocks
+/// with wet versions of themselves, and replaces them with the dry versions again when
+/// the rain has stopped.
+///
+/// A surface block is the topmost block, within the bounds the behavior is attached to,
+/// of a column which has nothing but [`AIR`] above it in the whole space.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Wetting {
+    /// Pairs of dry and wet versions of the same block.
+    pairs: Arc<[(Block, Block)]>,
+    rng: Xoshiro256Plus,
+}
+
+impl Wetting {
+    /// Constructs a [`Wetting`] behavior which replaces the first block of each pair
+    /// with the second, and vice versa.
+    pub fn new(pairs: impl IntoIterator<Item = (Block, Block)>) -> Self {
+        Self {
+            pairs: pairs.into_iter().collect(),
+            rng: Xoshiro256Plus::seed_from_u64(0x5745_5454_494e_4721),
+        }
+    }
+
+    /// Returns the replacement for `block` if it is one of the blocks to be changed.
+    fn replacement(&self, block: &Block, raining: bool) -> Option<&Block> {
+        self.pairs.iter().find_map(|(dry, wet)| {
+            if raining && block == dry {
+                Some(wet)
+            } else if !raining && block == wet {
+                Some(dry)
+            } else {
+                None
+            }
+        })
+    }
+}
+
+impl Behavior<Space> for Wetting {
+    fn step(&self, context: &BehaviorContext<'_, Space>, tick: Tick) -> UniverseTransaction {
+        if tick.paused() || self.pairs.is_empty() {
+            return UniverseTransaction::default();
+        }
+        let space = context.host;
+        let weather = space.weather();
+        let raining = weather.precipitation() == Some(Precipitation::Rain);
+        let rate = if raining {
+            WETTING_PER_SECOND * f64::from(weather.intensity())
+        } else {
+            DRYING_PER_SECOND
+        };
+        // Probability that any given column is affected during this step.
+        let probability = 1.0 - (-rate * tick.delta_t().as_secs_f64()).exp();
+        if probability <= 0.0 {
+            return UniverseTransaction::default();
+        }
+
+        let mut mut_self = self.clone();
+        let mut txn = SpaceTransaction::default();
+        let bounds = context.attachment.bounds();
+        let space_top = space.bounds().upper_bounds().y;
+        for z in bounds.z_range() {
+            for x in bounds.x_range() {
+                if !mut_self.rng.gen_bool(probability) {
+                    continue;
+                }
+                // Find the topmost non-air block, if it is not covered by anything
+                // outside the bounds.
+                let Some(cube) = (bounds.lower_bounds().y..space_top)
+                    .rev()
+                    .map(|y| GridPoint::new(x, y, z))
+                    .find(|&cube| space[cube] != AIR)
+                else {
+                    continue;
+                };
+                if !bounds.contains_cube(cube) {
+                    continue;
+                }
+                let old = &space[cube];
+                if let Some(new) = self.replacement(old, raining) {
+                    txn.set(cube, Some(old.clone()), Some(new.clone())).unwrap();
+                }
+            }
+        }
+
+        context
+            .replace_self(mut_self)
+            .merge(context.bind_host(txn))
+            .unwrap()
+    }
+
+    fn alive(&self, _context: &BehaviorContext<'_, Space>) -> bool {
+        true
+    }
+
+    fn ephemeral(&self) -> bool {
+        false
+    }
+}
+
+impl VisitRefs for Wetting {
+    fn visit_refs(&self, visitor: &mut dyn RefVisitor) {
+        for (dry, wet) in self.pairs.iter() {
+            dry.visit_refs(visitor);
+            wet.visit_refs(visitor);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use all_is_cubes::math::{GridAab, Rgba};
+    use all_is_cubes::space::Weather;
+    use all_is_cubes::time::practically_infinite_deadline;
+    use all_is_cubes::transaction::{self, Transaction as _};
+    use all_is_cubes::universe::Universe;
+
+    #[test]
+    fn wetting_and_drying() {
+        let dry = Block::from(Rgba::new(0.5, 0.4, 0.3, 1.0));
+        let wet = Block::from(Rgba::new(0.3, 0.2, 0.1, 1.0));
+        let roof = Block::from(Rgba::WHITE);
+
+        let bounds = GridAab::from_lower_size([0, 0, 0], [2, 3, 1]);
+        let mut space = Space::builder(bounds).build();
+        space
+            .fill_uniform(GridAab::from_lower_size([0, 0, 0], [2, 1, 1]), &dry)
+            .unwrap();
+        // Cover one column; it should stay dry.
+        space.set([1, 2, 0], &roof).unwrap();
+        space.set_weather_immediately(Weather::new(Some(Precipitation::Rain), 1.0, 0.0));
+        SpaceTransaction::add_behavior(
+            GridAab::from_lower_size([0, 0, 0], [2, 1, 1]),
+            Wetting::new([(dry.clone(), wet.clone())]),
+        )
+        .execute(&mut space, &mut transaction::no_outputs)
+        .unwrap();
+
+        let mut universe = Universe::new();
+        let space = universe.insert_anonymous(space);
+
+        // A very long step so that every column is affected.
+        universe.step(Tick::from_seconds(10000.0), practically_infinite_deadline());
+        {
+            let space = space.read().unwrap();
+            assert_eq!(space[[0, 0, 0]], wet);
+            assert_eq!(space[[1, 0, 0]], dry);
+        }
+
+        space
+            .try_modify(|space| space.set_weather_immediately(Weather::CLEAR))
+            .unwrap();
+        universe.step(Tick::from_seconds(10000.0), practically_infinite_deadline());
+        assert_eq!(space.read().unwrap()[[0, 0, 0]], dry);
+    }
+}
@@ -4,6 +4,7 @@ use std::time::Instant;
 
 use anyhow::anyhow;
 use image::imageops::{self, FilterType};
+use winit::dpi::{LogicalSize, PhysicalPosition};
 use winit::event::{DeviceEvent, ElementState, Event, KeyboardInput, WindowEvent};
 use winit::event_loop::{ControlFlow, EventLoop};
 use winit::window::{Window, WindowBuilder};
@@ -17,9 +18,10 @@ use all_is_cubes_gpu::wgpu;
 use all_is_cubes_ui::apps::Session;
 
 use crate::choose_graphical_window_size;
+use crate::config_files::{self, WindowState};
 use crate::glue::winit::{
-    cursor_icon_to_winit, logical_size_from_vec, map_key, map_mouse_button,
-    monitor_size_for_window, physical_size_to_viewport, sync_cursor_grab,
+    cursor_icon_to_winit, logical_size_from_vec, map_key, map_mouse_button, map_mouse_wheel,
+    monitor_size_for_window, physical_size_to_viewport, sync_cursor_grab, FullscreenSync,
 };
 use crate::session::DesktopSession;
 
@@ -29,6 +31,8 @@ use crate::session::DesktopSession;
 pub(crate) fn winit_main_loop<Ren: RendererToWinit + 'static>(
     event_loop: EventLoop<()>,
     mut dsession: DesktopSession<Ren, Window>,
+    fullscreen: FullscreenSync,
+    mut window_state: Option<WindowState>,
 ) -> Result<(), anyhow::Error> {
     let loop_start_time = Instant::now();
     let mut first_frame = true;
@@ -43,6 +47,7 @@ pub(crate) fn winit_main_loop<Ren: RendererToWinit + 'static>(
 
         // Sync UI state back to window
         sync_cursor_grab(&dsession.window, &mut dsession.session.input_processor);
+        fullscreen.sync(&dsession.window);
 
         // Compute when we want to resume.
         // Note that handle_winit_event() might override this.
@@ -50,35 +55,79 @@ pub(crate) fn winit_main_loop<Ren: RendererToWinit + 'static>(
             *control_flow = ControlFlow::WaitUntil(t);
         }
 
+        if let Event::WindowEvent {
+            event: WindowEvent::CloseRequested,
+            ..
+        } = event
+        {
+            if let Some(state) = &mut window_state {
+                save_window_state(&dsession.window, state);
+            }
+        }
+
         handle_winit_event(event, &mut dsession, control_flow)
     })
 }
 
+/// Records how `window` is sized and placed in `state`, and saves it.
+fn save_window_state(window: &Window, state: &mut WindowState) {
+    state.maximized = window.is_maximized();
+    // When maximized or fullscreen, the window's current size and position are not the
+    // ones it should have when it is restored from that, so keep the previous ones.
+    if !state.maximized && window.fullscreen().is_none() {
+        let size: LogicalSize<u32> = window.inner_size().to_logical(window.scale_factor());
+        state.size = Some([size.width, size.height]);
+        state.position = window.outer_position().ok().map(|p| [p.x, p.y]);
+    }
+    if let Err(e) = config_files::save_window_state(state) {
+        log::error!("Failed to save window state: {e}");
+    }
+}
+
 pub(crate) fn create_window(
     event_loop: &EventLoop<()>,
     window_title: &str,
     requested_size: Option<Vector2<u32>>,
     fullscreen: bool,
+    saved_state: Option<&WindowState>,
 ) -> Result<Window, winit::error::OsError> {
+    // An explicitly requested size overrides everything saved about the window.
+    let saved_state = saved_state.filter(|_| requested_size.is_none());
+
     // Pick a window size.
-    let inner_size = if let Some(size) = requested_size {
-        logical_size_from_vec(size)
-    } else {
-        // TODO: Does this strategy actually best reflect what monitor the window is
-        // going to appear on?
-        let maybe_monitor = event_loop
-            .primary_monitor()
-            .or_else(|| event_loop.available_monitors().next());
-        logical_size_from_vec(choose_graphical_window_size(
-            maybe_monitor.map(monitor_size_for_window),
-        ))
-    };
-
-    WindowBuilder::new()
+    let inner_size =
+        if let Some(size) = requested_size.or_else(|| saved_state?.size.map(Vector2::from)) {
+            logical_size_from_vec(size)
+        } else {
+            // TODO: Does this strategy actually best reflect what monitor the window is
+            // going to appear on?
+            let maybe_monitor = event_loop
+                .primary_monitor()
+                .or_else(|| event_loop.available_monitors().next());
+            logical_size_from_vec(choose_graphical_window_size(
+                maybe_monitor.map(monitor_size_for_window),
+            ))
+        };
+
+    let mut builder = WindowBuilder::new()
         .with_inner_size(inner_size)
         .with_title(window_title)
-        .with_fullscreen(fullscreen.then_some(winit::window::Fullscreen::Borderless(None)))
-        .build(event_loop)
+        .with_fullscreen(fullscreen.then_some(winit::window::Fullscreen::Borderless(None)));
+    if let Some(state) = saved_state {
+        builder = builder.with_maximized(state.maximized);
+        // Don't put the window where it can't be seen, if the monitors have changed.
+        if let Some(position) = state.position.map(|[x, y]| PhysicalPosition::new(x, y)) {
+            if event_loop.available_monitors().any(|monitor| {
+                let origin = monitor.position();
+                let size = monitor.size();
+                (origin.x..origin.x + size.width as i32).contains(&position.x)
+                    && (origin.y..origin.y + size.height as i32).contains(&position.y)
+            }) {
+                builder = builder.with_position(position);
+            }
+        }
+    }
+    builder.build(event_loop)
 }
 
 pub(crate) async fn create_winit_wgpu_desktop_session(
@@ -262,9 +311,8 @@ fn handle_winit_event<Ren: RendererToWinit>(
                     }
                     ElementState::Released => {}
                 },
-                WindowEvent::MouseWheel { .. } => {
-                    // TODO: Hook up to input processor once we have customizable bindings
-                    // or otherwise something to do with it
+                WindowEvent::MouseWheel { delta, .. } => {
+                    input_processor.mouse_wheel(map_mouse_wheel(delta));
                 }
 
                 // Window state
@@ -352,7 +400,7 @@ fn handle_winit_event<Ren: RendererToWinit>(
 
             dsession
                 .renderer
-                .redraw(&dsession.session, &dsession.window);
+                .redraw(&mut dsession.session, &dsession.window);
 
             dsession.session.frame_clock.did_draw();
         }
@@ -372,7 +420,7 @@ fn handle_winit_event<Ren: RendererToWinit>(
 pub(crate) trait RendererToWinit: 'static {
     fn update_world_camera(&mut self);
     fn cameras(&self) -> &StandardCameras;
-    fn redraw(&mut self, session: &Session, window: &Window);
+    fn redraw(&mut self, session: &mut Session, window: &Window);
 }
 
 impl RendererToWinit for SurfaceRenderer {
@@ -384,12 +432,13 @@ impl RendererToWinit for SurfaceRenderer {
         self.cameras()
     }
 
-    fn redraw(&mut self, session: &Session, _window: &Window) {
-        let _info = self
+    fn redraw(&mut self, session: &mut Session, _window: &Window) {
+        let info = self
             .render_frame(session.cursor_result(), |render_info| {
                 format!("{}", session.info_text(render_info))
             })
             .unwrap();
+        session.record_render_time(info.frame_time());
     }
 }
 
@@ -408,7 +457,7 @@ impl RendererToWinit for RtToSoftbuffer {
         self.renderer.cameras()
     }
 
-    fn redraw(&mut self, session: &Session, window: &Window) {
+    fn redraw(&mut self, session: &mut Session, window: &Window) {
         self.renderer.update(session.cursor_result()).unwrap(/* TODO: fix */);
 
         let (image, _render_info, _flaws) = self
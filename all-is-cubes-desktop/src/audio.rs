@@ -1,8 +1,11 @@
 use std::fmt;
 use std::sync::{atomic, mpsc, Arc};
 
+use all_is_cubes::character::Character;
 use all_is_cubes::fluff::Fluff;
-use all_is_cubes::listen::Listener;
+use all_is_cubes::listen::{ListenableSource, Listener};
+use all_is_cubes::sound::SoundEvent;
+use all_is_cubes::universe::URef;
 use all_is_cubes_ui::apps::Session;
 use kira::manager::error::PlaySoundError;
 use kira::manager::AudioManager;
@@ -21,7 +24,8 @@ impl fmt::Debug for AudioOut {
 
 #[derive(Debug)]
 enum AudioCommand {
-    Fluff(Fluff),
+    /// Play the sound for the given [`Fluff`] at the given volume.
+    Fluff(Fluff, f32),
 }
 
 pub(crate) fn init_sound(session: &Session) -> Result<AudioOut, anyhow::Error> {
@@ -41,6 +45,11 @@ pub(crate) fn init_sound(session: &Session) -> Result<AudioOut, anyhow::Error> {
 
     // Hook up fluff channel
     session.listen_fluff(FluffListener::new(sender.clone()));
+    // Hook up sounds from the universe, which are heard from the character's position
+    session.listen_sounds(SoundListener {
+        fluff_listener: FluffListener::new(sender.clone()),
+        character: session.character(),
+    });
 
     Ok(AudioOut { sender })
 }
@@ -81,15 +90,22 @@ fn audio_command_thread(receiver: mpsc::Receiver<AudioCommand>, mut manager: Aud
 
     while let Ok(message) = receiver.recv() {
         match message {
-            AudioCommand::Fluff(Fluff::Beep) => play_fluff(&mut manager, &beep),
-            AudioCommand::Fluff(Fluff::Happened) => play_fluff(&mut manager, &happened),
-            AudioCommand::Fluff(f) => log::debug!("No known sound for Fluff value: {f:?}"),
+            AudioCommand::Fluff(Fluff::Beep, volume) => play_fluff(&mut manager, &beep, volume),
+            AudioCommand::Fluff(
+                Fluff::Happened | Fluff::PlaceBlockGeneric | Fluff::RemoveBlockGeneric,
+                volume,
+            ) => play_fluff(&mut manager, &happened, volume),
+            AudioCommand::Fluff(f, _) => log::debug!("No known sound for Fluff value: {f:?}"),
         }
     }
 }
 
-fn play_fluff(manager: &mut AudioManager, sound: &StaticSoundData) {
-    match manager.play(sound.clone()) {
+fn play_fluff(manager: &mut AudioManager, sound: &StaticSoundData, volume: f32) {
+    let mut sound = sound.clone();
+    sound.settings = sound
+        .settings
+        .volume(kira::Volume::Amplitude(f64::from(volume)));
+    match manager.play(sound) {
         Ok(_handle) => {}
         Err(PlaySoundError::SoundLimitReached) => {
             // Ignore this, since fluff is inconsequential
@@ -115,9 +131,9 @@ impl FluffListener {
     }
 }
 
-impl Listener<Fluff> for FluffListener {
-    fn receive(&self, fluff: Fluff) {
-        match self.sender.try_send(AudioCommand::Fluff(fluff)) {
+impl FluffListener {
+    fn send(&self, fluff: Fluff, volume: f32) {
+        match self.sender.try_send(AudioCommand::Fluff(fluff, volume)) {
             Ok(()) => {}
             Err(mpsc::TrySendError::Full(_)) => {}
             Err(mpsc::TrySendError::Disconnected(_)) => {
@@ -125,8 +141,40 @@ impl Listener<Fluff> for FluffListener {
             }
         }
     }
+}
+
+impl Listener<Fluff> for FluffListener {
+    fn receive(&self, fluff: Fluff) {
+        self.send(fluff, 1.0);
+    }
 
     fn alive(&self) -> bool {
         self.alive.load(atomic::Ordering::Relaxed)
     }
 }
+
+/// Adapter from [`SoundEvent`]s to the audio thread channel, which attenuates them
+/// according to their distance from the character.
+struct SoundListener {
+    fluff_listener: FluffListener,
+    character: ListenableSource<Option<URef<Character>>>,
+}
+
+impl Listener<SoundEvent> for SoundListener {
+    fn receive(&self, event: SoundEvent) {
+        let listener_position = self.character.snapshot().and_then(|character| {
+            let position = character.read().ok()?.body.position;
+            Some(position)
+        });
+        let volume = match listener_position {
+            Some(position) => event.volume_at(position),
+            // No character to hear from; play as if nearby.
+            None => event.volume,
+        };
+        self.fluff_listener.send(event.sound, volume);
+    }
+
+    fn alive(&self) -> bool {
+        self.fluff_listener.alive()
+    }
+}
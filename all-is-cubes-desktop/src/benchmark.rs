@@ -0,0 +1,149 @@
+//! Headless benchmarking: run the simulation for a fixed number of steps and report
+//! performance data as JSON, for tracking performance across versions.
+
+use std::io::Write as _;
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+
+use all_is_cubes::camera::GraphicsOptions;
+use all_is_cubes::space::Space;
+use all_is_cubes::universe::UniverseStepInfo;
+use all_is_cubes_mesh::{
+    block_meshes_for_space, BlockVertex, MeshOptions, NoTexture, NoTextures, SpaceMesh,
+};
+
+use crate::session::{ClockSource, DesktopSession};
+
+/// Time between steps; equal to the usual step length so that each
+/// [`DesktopSession::advance_time_and_maybe_step()`] performs exactly one step.
+const STEP_LENGTH: Duration = Duration::from_micros(1_000_000 / 60);
+
+/// Everything reported by [`benchmark_main`], serialized as JSON to stdout.
+#[derive(Clone, Debug, Serialize)]
+struct BenchmarkReport {
+    steps: usize,
+    step_time: TimeSummary,
+    light: LightReport,
+    /// Absent if there is no character, and hence no space to measure.
+    mesh: Option<MeshReport>,
+}
+
+/// Statistics about a set of time measurements, in seconds.
+#[derive(Clone, Debug, Serialize)]
+struct TimeSummary {
+    total: f64,
+    mean: f64,
+    min: f64,
+    max: f64,
+}
+
+impl TimeSummary {
+    fn new(times: &[Duration]) -> Self {
+        let seconds = times.iter().map(Duration::as_secs_f64);
+        let total: f64 = seconds.clone().sum();
+        Self {
+            total,
+            mean: if times.is_empty() {
+                0.0
+            } else {
+                total / times.len() as f64
+            },
+            min: seconds.clone().fold(f64::INFINITY, f64::min),
+            max: seconds.fold(0.0, f64::max),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+struct LightReport {
+    /// Seconds taken by `--precompute-light`, if it was requested.
+    precompute_time: Option<f64>,
+    /// Number of light updates performed during steps.
+    step_updates: usize,
+    /// Number of cubes in the light update queue after the last step.
+    final_queue_count: usize,
+}
+
+/// Size of a mesh of the character's space, computed after the last step.
+#[derive(Clone, Debug, Serialize)]
+struct MeshReport {
+    /// Seconds taken to compute the block meshes and the space mesh.
+    time: f64,
+    block_meshes: usize,
+    vertices: usize,
+    indices: usize,
+}
+
+/// Main loop for `--benchmark`: run `steps` steps as fast as possible, then print a
+/// [`BenchmarkReport`].
+///
+/// If `precompute_light` is true, light is evaluated before stepping, and the time taken
+/// is included in the report.
+pub(crate) fn benchmark_main(
+    mut dsession: DesktopSession<(), ()>,
+    steps: usize,
+    precompute_light: bool,
+) -> Result<(), anyhow::Error> {
+    let space_ref = dsession
+        .session
+        .universe_mut()
+        .get_default_character()
+        .map(|c| c.read().unwrap().space.clone());
+
+    let precompute_time = match (&space_ref, precompute_light) {
+        (Some(space_ref), true) => {
+            let start = Instant::now();
+            space_ref.try_modify(crate::evaluate_light_with_progress)?;
+            Some(start.elapsed().as_secs_f64())
+        }
+        _ => None,
+    };
+
+    log::info!("Running {steps} steps...");
+    dsession.clock_source = ClockSource::Fixed(STEP_LENGTH);
+    let mut step_times = Vec::with_capacity(steps);
+    let mut step_updates = 0;
+    let mut last_info = UniverseStepInfo::default();
+    while step_times.len() < steps {
+        if let Some(info) = dsession.advance_time_and_maybe_step() {
+            step_times.push(info.computation_time);
+            step_updates += info.space_step.light.update_count;
+            last_info = info;
+        }
+    }
+
+    let report = BenchmarkReport {
+        steps,
+        step_time: TimeSummary::new(&step_times),
+        light: LightReport {
+            precompute_time,
+            step_updates,
+            final_queue_count: last_info.space_step.light.queue_count,
+        },
+        mesh: match space_ref {
+            Some(space_ref) => Some(measure_mesh(&space_ref.read()?)),
+            None => None,
+        },
+    };
+
+    let mut stdout = std::io::stdout().lock();
+    serde_json::to_writer_pretty(&mut stdout, &report)?;
+    writeln!(stdout)?;
+    Ok(())
+}
+
+fn measure_mesh(space: &Space) -> MeshReport {
+    let start = Instant::now();
+    let options = MeshOptions::new(&GraphicsOptions::default());
+    let block_meshes =
+        block_meshes_for_space::<BlockVertex<NoTexture>, _>(space, &NoTextures, &options);
+    let mesh: SpaceMesh<BlockVertex<NoTexture>, NoTexture> =
+        SpaceMesh::new(space, space.bounds(), &options, &*block_meshes);
+    MeshReport {
+        time: start.elapsed().as_secs_f64(),
+        block_meshes: block_meshes.len(),
+        vertices: mesh.vertices().len(),
+        indices: mesh.indices().len(),
+    }
+}
@@ -8,12 +8,14 @@ use std::time::Duration;
 use clap::builder::{PathBufValueParser, PossibleValue, PossibleValuesParser};
 use clap::{builder::TypedValueParser, Parser, ValueEnum};
 use once_cell::sync::Lazy;
+use rand::Rng as _;
 use strum::IntoEnumIterator;
 
 use all_is_cubes::cgmath::{Vector2, Vector3};
 use all_is_cubes::math::GridCoordinate;
 use all_is_cubes_content::{TemplateParameters, UniverseTemplate};
 use all_is_cubes_port::ExportFormat;
+use all_is_cubes_ui::i18n::Language;
 
 use crate::record::{RecordAnimationOptions, RecordFormat, RecordOptions};
 use crate::TITLE;
@@ -32,16 +34,16 @@ use crate::TITLE;
 {all-args}{after-help}",
 )]
 pub(crate) struct AicDesktopArgs {
+    /// If [`None`], the window mode last used, or else [`GraphicsType::Window`].
     #[arg(
         long = "graphics",
         short = 'g',
-        default_value = "window",
         value_name = "mode",
         hide_possible_values = true,
-        help = "Graphics/UI mode",
+        help = "Graphics/UI mode [default: last window mode used, or window]",
         long_help = &**GRAPHICS_HELP_LONG,
     )]
-    pub(crate) graphics: GraphicsType,
+    pub(crate) graphics: Option<GraphicsType>,
 
     /// Window size or image size, if applicable to the selected --graphics mode.
     #[arg(long = "display-size", value_name = "W×H", default_value = "auto")]
@@ -53,6 +55,13 @@ pub(crate) struct AicDesktopArgs {
     #[arg(long)]
     pub(crate) fullscreen: bool,
 
+    /// Language of the user interface, such as 'en' or 'de'.
+    ///
+    /// If not specified, the language of the system locale, if it is supported,
+    /// or else English.
+    #[arg(long = "language", value_name = "CODE")]
+    pub(crate) language: Option<Language>,
+
     /// Which world template to use.
     ///
     /// Mutually exclusive with specifying an input file.
@@ -60,11 +69,7 @@ pub(crate) struct AicDesktopArgs {
         long = "template",
         short = 't',
         default_value = "demo-city",
-        value_parser = PossibleValuesParser::new(
-            UniverseTemplate::iter().map(|t| {
-                PossibleValue::new(<&str>::from(t.clone())).hide(!t.include_in_lists())
-            }),
-        ).map(|string| UniverseTemplate::from_str(&string).unwrap()),
+        value_parser = template_value_parser(),
     )]
     pub(crate) template: UniverseTemplate,
 
@@ -93,11 +98,35 @@ pub(crate) struct AicDesktopArgs {
     #[arg(long = "precompute-light")]
     pub(crate) precompute_light: bool,
 
+    /// Render 'record' mode images with the GPU instead of raytracing.
+    #[arg(long = "record-gpu")]
+    pub(crate) record_gpu: bool,
+
+    /// Record input to this file for --replay-input.
+    ///
+    /// The universe template and seed, or the input file, are recorded too.
+    #[arg(long = "record-input", value_name = "FILE")]
+    pub(crate) record_input: Option<PathBuf>,
+
+    /// Play back input recorded with --record-input.
+    ///
+    /// The universe is created the same way as when the input was recorded.
+    #[arg(
+        long = "replay-input",
+        value_name = "FILE",
+        conflicts_with = "template",
+        conflicts_with = "template_size",
+        conflicts_with = "seed",
+        conflicts_with = "input_file"
+    )]
+    pub(crate) replay_input: Option<PathBuf>,
+
     /// Output file name for 'record' mode.
     ///
     /// The file name must have an extension specifying the format to use:
     ///
     /// * “.alliscubesjson” — All is Cubes native save file format.
+    /// * “.alliscubes” — All is Cubes native save file format, compressed binary.
     /// * “.png” or “.apng” — export rendered scene.
     /// * “.gltf” — export scene as meshes in glTF format
     ///   (has accompanying “.glbin” data files).
@@ -124,6 +153,12 @@ pub(crate) struct AicDesktopArgs {
     #[arg(long = "duration", value_name = "SECONDS", verbatim_doc_comment)]
     pub(crate) duration: Option<f64>,
 
+    /// Benchmark: run this many steps and print performance data as JSON.
+    ///
+    /// Requires '--graphics headless'. Also measures the time taken by --precompute-light.
+    #[arg(long = "benchmark", value_name = "STEPS")]
+    pub(crate) benchmark: Option<usize>,
+
     /// Additional logging to stderr.
     #[arg(long = "verbose", short = 'v')]
     pub(crate) verbose: bool,
@@ -137,6 +172,8 @@ pub(crate) struct AicDesktopArgs {
     ///
     /// Currently supported formats:
     ///
+    /// * All is Cubes saved universes (.alliscubesjson and .alliscubes)
+    ///
     /// * MagicaVoxel .vox (partial support)
     #[arg(
         conflicts_with = "template",
@@ -145,6 +182,63 @@ pub(crate) struct AicDesktopArgs {
         value_name = "FILE"
     )]
     pub(crate) input_file: Option<PathBuf>,
+
+    /// Existing save/document file to load; the same as the FILE argument.
+    #[arg(
+        long = "input",
+        short = 'i',
+        conflicts_with = "input_file",
+        conflicts_with = "template",
+        conflicts_with = "template_size",
+        conflicts_with = "seed",
+        conflicts_with = "replay_input",
+        value_name = "FILE"
+    )]
+    pub(crate) input_file_option: Option<PathBuf>,
+
+    /// Content pack to load into the universe, in addition to the template or input
+    /// file. May be given more than once.
+    ///
+    /// A content pack is a directory or zip file of All is Cubes saved universes or
+    /// MagicaVoxel .vox files. Their contents are added under names prefixed by the
+    /// name of the directory or zip file (without extension) and a slash.
+    #[arg(long = "content-pack", value_name = "PATH")]
+    pub(crate) content_packs: Vec<PathBuf>,
+
+    #[command(subcommand)]
+    pub(crate) command: Option<Command>,
+}
+
+/// Things to do other than running the game.
+#[derive(Clone, Debug, clap::Subcommand)]
+pub(crate) enum Command {
+    /// Show information about the world templates which may be used with --template.
+    Templates {
+        #[command(subcommand)]
+        command: TemplatesCommand,
+    },
+}
+
+/// Subcommands of [`Command::Templates`].
+#[derive(Clone, Debug, clap::Subcommand)]
+pub(crate) enum TemplatesCommand {
+    /// List the available templates, with a short description of each.
+    List,
+
+    /// Describe one template, including which options it responds to.
+    Describe {
+        /// Name of the template, as would be given to --template.
+        #[arg(value_parser = template_value_parser())]
+        name: UniverseTemplate,
+    },
+}
+
+fn template_value_parser() -> impl TypedValueParser<Value = UniverseTemplate> {
+    PossibleValuesParser::new(
+        UniverseTemplate::iter()
+            .map(|t| PossibleValue::new(<&str>::from(t.clone())).hide(!t.include_in_lists())),
+    )
+    .map(|string| UniverseTemplate::from_str(&string).unwrap())
 }
 
 impl AicDesktopArgs {
@@ -163,6 +257,7 @@ impl AicDesktopArgs {
         Ok(RecordOptions {
             output_path: self.output_file.clone().unwrap(),
             output_format,
+            gpu: self.record_gpu,
             image_size: self
                 .display_size
                 .0
@@ -184,7 +279,8 @@ impl AicDesktopArgs {
     ///
     /// Concretely, this is expected to imply not contacting audio or display devices.
     pub(crate) fn is_headless(&self) -> bool {
-        match self.graphics {
+        // The default is always one of the window modes.
+        match self.graphics.unwrap_or(GraphicsType::Window) {
             GraphicsType::Window => false,
             GraphicsType::WindowRt => false,
             GraphicsType::Terminal => false,
@@ -221,12 +317,23 @@ static GRAPHICS_HELP_LONG: Lazy<String> = Lazy::new(|| {
         )
         .unwrap();
     }
+    text.push_str("\n\nIf not specified, the window mode last used, or else 'window'.");
     text
 });
 
 #[derive(
-    Clone, Copy, Debug, Eq, PartialEq, clap::ValueEnum, strum::EnumString, strum::IntoStaticStr,
+    Clone,
+    Copy,
+    Debug,
+    Eq,
+    PartialEq,
+    clap::ValueEnum,
+    serde::Deserialize,
+    serde::Serialize,
+    strum::EnumString,
+    strum::IntoStaticStr,
 )]
+#[serde(rename_all = "kebab-case")]
 #[strum(serialize_all = "kebab-case")]
 #[non_exhaustive]
 pub enum GraphicsType {
@@ -239,7 +346,7 @@ pub enum GraphicsType {
     Terminal,
     #[value(help = "Non-interactive; don't draw anything but only simulates")]
     Headless,
-    #[value(help = "Non-interactive; save an image or video (uses raytracing)")]
+    #[value(help = "Non-interactive; save an image or video (uses raytracing by default)")]
     Record,
     #[value(help = "Non-interactive; print one frame like 'terminal' mode then exit")]
     Print,
@@ -307,6 +414,9 @@ pub fn determine_record_format(output_path: &Path) -> Result<RecordFormat, &'sta
             Some("alliscubesjson" | "ALLISCUBESJSON") => {
                 return Ok(RecordFormat::Export(ExportFormat::AicJson))
             }
+            Some("alliscubes" | "ALLISCUBES") => {
+                return Ok(RecordFormat::Export(ExportFormat::AicBinary))
+            }
             Some("png" | "PNG") => return Ok(RecordFormat::PngOrApng),
             Some("apng" | "APNG") => return Ok(RecordFormat::PngOrApng),
             Some("gltf" | "GLTF") => return Ok(RecordFormat::Gltf),
@@ -318,7 +428,7 @@ pub fn determine_record_format(output_path: &Path) -> Result<RecordFormat, &'sta
     // TODO: Have a separate option for choosing file type as a fallback
     Err(
         "file name must have an extension specifying the type; one of \
-        'alliscubesjson', 'png', 'apng', 'gltf', 'stl', or 'vox'",
+        'alliscubesjson', 'alliscubes', 'png', 'apng', 'gltf', 'stl', or 'vox'",
     )
 }
 
@@ -346,6 +456,27 @@ pub(crate) fn parse_universe_source(
     }
 }
 
+impl UniverseSource {
+    /// If this is a template with no seed specified, chooses one randomly, so that the
+    /// same universe can be created again.
+    pub(crate) fn with_seed_chosen(self) -> Self {
+        match self {
+            UniverseSource::Template(template, TemplateParameters { seed: None, size }) => {
+                let seed = rand::thread_rng().gen();
+                log::info!("Randomly chosen universe seed: {seed}");
+                UniverseSource::Template(
+                    template,
+                    TemplateParameters {
+                        seed: Some(seed),
+                        size,
+                    },
+                )
+            }
+            source => source,
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use clap::error::{ContextValue, ErrorKind};
@@ -375,6 +506,7 @@ mod tests {
             RecordOptions {
                 output_path: PathBuf::from("output.png"),
                 output_format: RecordFormat::PngOrApng,
+                gpu: false,
                 image_size: Vector2::new(640, 480),
                 animation: None,
             },
@@ -390,6 +522,7 @@ mod tests {
             RecordOptions {
                 output_path: PathBuf::from("fancy.png"),
                 output_format: RecordFormat::PngOrApng,
+                gpu: false,
                 image_size: Vector2::new(640, 480),
                 animation: Some(RecordAnimationOptions {
                     frame_count: 180,
@@ -438,16 +571,44 @@ mod tests {
         );
     }
 
+    #[test]
+    fn language_option() {
+        assert_eq!(parse(&[]).unwrap().language, None);
+        assert_eq!(
+            parse(&["--language", "de"]).unwrap().language,
+            Some(Language::German)
+        );
+        let e = parse(&["--language", "xx"]).unwrap_err();
+        assert_eq!(e.kind(), ErrorKind::ValueValidation);
+    }
+
     fn parse_universe_test(args: &[&str]) -> clap::error::Result<UniverseSource> {
         let AicDesktopArgs {
             template,
             input_file,
+            input_file_option,
             seed,
             template_size: size,
             ..
         } = parse(args)?;
         // TODO: make this a method on AicDesktopArgs
-        Ok(parse_universe_source(input_file, template, size, seed))
+        Ok(parse_universe_source(
+            input_file.or(input_file_option),
+            template,
+            size,
+            seed,
+        ))
+    }
+
+    #[test]
+    fn content_pack_option() {
+        assert_eq!(parse(&[]).unwrap().content_packs, Vec::<PathBuf>::new());
+        assert_eq!(
+            parse(&["--content-pack", "a.zip", "--content-pack", "b"])
+                .unwrap()
+                .content_packs,
+            vec![PathBuf::from("a.zip"), PathBuf::from("b")]
+        );
     }
 
     #[test]
@@ -474,6 +635,38 @@ mod tests {
         );
     }
 
+    #[test]
+    fn universe_from_file_option() {
+        assert_eq!(
+            parse_universe_test(&["--input", "foo/bar"]).unwrap(),
+            UniverseSource::File(PathBuf::from("foo/bar")),
+        );
+        assert_eq!(
+            parse_universe_test(&["--input", "foo/bar", "foo/baz"])
+                .unwrap_err()
+                .kind(),
+            ErrorKind::ArgumentConflict
+        );
+    }
+
+    #[test]
+    fn templates_subcommand() {
+        assert!(matches!(
+            parse(&["templates", "describe", "cornell-box"])
+                .unwrap()
+                .command,
+            Some(Command::Templates {
+                command: TemplatesCommand::Describe {
+                    name: UniverseTemplate::CornellBox
+                }
+            })
+        ));
+        assert_eq!(
+            parse(&["templates", "describe", "foo"]).unwrap_err().kind(),
+            ErrorKind::InvalidValue
+        );
+    }
+
     #[test]
     fn universe_option_conflict() {
         assert_eq!(
@@ -484,6 +677,16 @@ mod tests {
         );
     }
 
+    #[test]
+    fn replay_input_conflict() {
+        assert_eq!(
+            parse(&["--replay-input", "input.jsonl", "--seed", "1"])
+                .unwrap_err()
+                .kind(),
+            ErrorKind::ArgumentConflict
+        );
+    }
+
     #[test]
     fn universe_option_invalid_template() {
         assert_eq!(
@@ -2,28 +2,63 @@ use std::error::Error;
 use std::fs::create_dir_all;
 use std::fs::File;
 use std::io::BufReader;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 use directories_next::ProjectDirs;
-use serde::{de::DeserializeOwned, Serialize};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
 
 use all_is_cubes::camera::GraphicsOptions;
 
-pub fn load_config() -> Result<GraphicsOptions, Box<dyn Error>> {
-    // TODO: make testable
-    let project_dirs = ProjectDirs::from("org.switchb", "", "all-is-cubes")
-        .ok_or_else(|| <Box<dyn Error>>::from("could not find configuration directory"))?;
-    create_dir_all(project_dirs.config_dir())?;
+use crate::command_options::GraphicsType;
 
+pub fn load_config() -> Result<GraphicsOptions, Box<dyn Error>> {
     let graphics_options = read_or_create_default_json_file(
         "graphics options",
-        &project_dirs.config_dir().join("graphics.json"),
+        &config_dir()?.join("graphics.json"),
         GraphicsOptions::default,
     );
 
     Ok(graphics_options)
 }
 
+/// How the window was when the application last exited, so that it can be reopened
+/// the same way.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WindowState {
+    /// Size of the window's contents, in logical pixels.
+    pub size: Option<[u32; 2]>,
+    /// Position of the window on the desktop, in physical pixels, if the window system
+    /// reports it.
+    pub position: Option<[i32; 2]>,
+    pub maximized: bool,
+    /// Which of the window graphics modes was used.
+    pub graphics: Option<GraphicsType>,
+}
+
+pub fn load_window_state() -> Result<WindowState, Box<dyn Error>> {
+    Ok(read_or_create_default_json_file(
+        "window state",
+        &config_dir()?.join("window.json"),
+        WindowState::default,
+    ))
+}
+
+pub fn save_window_state(state: &WindowState) -> Result<(), Box<dyn Error>> {
+    let json_text = serde_json::to_string_pretty(state)?;
+    std::fs::write(config_dir()?.join("window.json"), json_text.as_bytes())?;
+    Ok(())
+}
+
+/// Returns the directory containing configuration files, creating it if needed.
+fn config_dir() -> Result<PathBuf, Box<dyn Error>> {
+    // TODO: make testable
+    let project_dirs = ProjectDirs::from("org.switchb", "", "all-is-cubes")
+        .ok_or_else(|| <Box<dyn Error>>::from("could not find configuration directory"))?;
+    create_dir_all(project_dirs.config_dir())?;
+    Ok(project_dirs.config_dir().to_owned())
+}
+
 fn read_or_create_default_json_file<V: DeserializeOwned + Serialize>(
     description: &str,
     path: &Path,
@@ -14,6 +14,8 @@ pub fn event_to_key(event: &Event) -> Option<Key> {
             (_, KeyCode::Down) => Some(Key::Down),
             (_, KeyCode::Left) => Some(Key::Left),
             (_, KeyCode::Right) => Some(Key::Right),
+            (_, KeyCode::Backspace) => Some(Key::Backspace),
+            (_, KeyCode::Enter) => Some(Key::Character('\r')),
             _ => None,
         },
         _ => None,
@@ -1,10 +1,13 @@
+use std::sync::{Arc, Mutex};
+
 use winit::dpi::{LogicalSize, PhysicalSize};
 use winit::monitor::MonitorHandle;
 
 use all_is_cubes::camera::Viewport;
 use all_is_cubes::cgmath::Vector2;
-use all_is_cubes_ui::apps::InputProcessor;
-use winit::window::CursorGrabMode;
+use all_is_cubes::listen::ListenableCell;
+use all_is_cubes_ui::apps::{InputProcessor, SessionBuilder};
+use winit::window::{CursorGrabMode, Fullscreen};
 
 pub fn physical_size_to_viewport(scale_factor: f64, size: PhysicalSize<u32>) -> Viewport {
     let size: Vector2<u32> = Vector2::<u32>::from(<[u32; 2]>::from(size));
@@ -35,6 +38,16 @@ pub fn map_mouse_button(button: winit::event::MouseButton) -> usize {
     }
 }
 
+/// Converts a scroll amount to lines, as [`InputProcessor::mouse_wheel()`] expects:
+/// positive for scrolling down.
+pub fn map_mouse_wheel(delta: winit::event::MouseScrollDelta) -> f64 {
+    match delta {
+        winit::event::MouseScrollDelta::LineDelta(_, y) => -f64::from(y),
+        // There is no true line height to use, so pick one typical of text.
+        winit::event::MouseScrollDelta::PixelDelta(position) => -position.y / 20.0,
+    }
+}
+
 pub fn map_key(key: winit::event::VirtualKeyCode) -> Option<all_is_cubes_ui::apps::Key> {
     use all_is_cubes_ui::apps::Key as A;
     use winit::event::VirtualKeyCode as V;
@@ -113,7 +126,7 @@ pub fn map_key(key: winit::event::VirtualKeyCode) -> Option<all_is_cubes_ui::app
         V::Up => A::Up,
         V::Right => A::Right,
         V::Down => A::Down,
-        V::Back => return None,
+        V::Back => A::Backspace,
         V::Return => A::Character('\r'),
         V::Space => A::Character(' '),
         V::Compose => return None,
@@ -224,6 +237,57 @@ pub fn sync_cursor_grab(window: &winit::window::Window, input_processor: &mut In
     }
 }
 
+/// Connects the fullscreen controls of a [`Session`](all_is_cubes_ui::apps::Session)
+/// to a window which the session's setter function cannot itself have access to.
+///
+/// Requests are carried out, and the state the session sees is updated, by
+/// [`FullscreenSync::sync()`].
+#[derive(Debug)]
+pub struct FullscreenSync {
+    state: ListenableCell<Option<bool>>,
+    request: Arc<Mutex<Option<bool>>>,
+}
+
+impl FullscreenSync {
+    /// `fullscreen` should be whether the window will be created fullscreen.
+    pub fn new(fullscreen: bool) -> Self {
+        Self {
+            state: ListenableCell::new(Some(fullscreen)),
+            request: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    /// Gives the session which `builder` builds the ability to see and change the
+    /// fullscreen state.
+    pub fn configure(&self, builder: SessionBuilder) -> SessionBuilder {
+        let request = self.request.clone();
+        builder.fullscreen(
+            self.state.as_source(),
+            Some(Arc::new(move |fullscreen: bool| {
+                if let Ok(mut request) = request.lock() {
+                    *request = Some(fullscreen);
+                }
+            })),
+        )
+    }
+
+    /// Applies the most recent request to `window`, and reports the window's state.
+    pub fn sync(&self, window: &winit::window::Window) {
+        let requested = self
+            .request
+            .lock()
+            .ok()
+            .and_then(|mut request| request.take());
+        if let Some(fullscreen) = requested {
+            window.set_fullscreen(fullscreen.then_some(Fullscreen::Borderless(None)));
+        }
+        let actual = Some(window.fullscreen().is_some());
+        if *self.state.get() != actual {
+            self.state.set(actual);
+        }
+    }
+}
+
 pub fn cursor_icon_to_winit(icon: &all_is_cubes_ui::apps::CursorIcon) -> winit::window::CursorIcon {
     use all_is_cubes_ui::apps::CursorIcon as A;
     use winit::window::CursorIcon as W;
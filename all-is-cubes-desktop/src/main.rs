@@ -34,23 +34,29 @@
 // Crate-specific lint settings.
 // * This crate does not forbid(unsafe_code) because wgpu initialization requires it.
 
+use std::path::PathBuf;
 use std::time::{Duration, Instant};
 
+use anyhow::Context as _;
+
 use all_is_cubes::universe::Universe;
-use all_is_cubes_content::TemplateParameters;
 use clap::{CommandFactory as _, Parser as _};
 use indicatif::{ProgressBar, ProgressStyle};
-use rand::Rng;
 
 use all_is_cubes::camera::{GraphicsOptions, Viewport};
 use all_is_cubes::cgmath::{Vector2, Zero as _};
 use all_is_cubes::listen::ListenableCell;
 use all_is_cubes::space::{LightUpdatesInfo, Space};
 use all_is_cubes::util::YieldProgress;
+use all_is_cubes_content::{TemplateBuildTime, UniverseTemplate};
 use all_is_cubes_ui::apps::Session;
+use all_is_cubes_ui::i18n::Language;
+use strum::IntoEnumIterator as _;
 
 mod aic_winit;
 use aic_winit::winit_main_loop;
+mod benchmark;
+use benchmark::benchmark_main;
 mod command_options;
 use command_options::GraphicsType;
 mod config_files;
@@ -58,14 +64,18 @@ mod glue;
 mod record;
 use record::record_main;
 mod audio;
+mod replay;
 mod session;
 mod terminal;
 
 use crate::aic_winit::{create_winit_rt_desktop_session, create_winit_wgpu_desktop_session};
 use crate::command_options::{
-    determine_record_format, parse_universe_source, AicDesktopArgs, DisplaySizeArg, UniverseSource,
+    determine_record_format, parse_universe_source, AicDesktopArgs, Command, DisplaySizeArg,
+    TemplatesCommand, UniverseSource,
 };
+use crate::glue::winit::FullscreenSync;
 use crate::record::create_recording_session;
+use crate::replay::{read_input_recording, InputRecorder};
 use crate::session::DesktopSession;
 use crate::terminal::{
     create_terminal_session, terminal_main_loop, terminal_print_once, TerminalOptions,
@@ -79,6 +89,17 @@ fn title_and_version() -> String {
     format!("{TITLE} v{v}", v = clap::crate_version!())
 }
 
+/// Returns the language named by the locale environment variables, if it is supported,
+/// or else English.
+fn system_language() -> Language {
+    ["LC_ALL", "LC_MESSAGES", "LANG"]
+        .into_iter()
+        .filter_map(|var| std::env::var(var).ok())
+        .find(|value| !value.is_empty())
+        .and_then(|value| value.parse().ok())
+        .unwrap_or_default()
+}
+
 fn main() -> Result<(), anyhow::Error> {
     let runtime = tokio::runtime::Builder::new_multi_thread().build().unwrap();
 
@@ -86,23 +107,56 @@ fn main() -> Result<(), anyhow::Error> {
     let options = AicDesktopArgs::parse();
     // Destructure as a check that we're using/skipping all the args
     let AicDesktopArgs {
-        graphics: graphics_type,
+        graphics: graphics_arg,
         display_size: DisplaySizeArg(display_size),
         fullscreen,
+        language,
         template,
         template_size,
         seed,
         precompute_light,
+        record_gpu: _,
+        record_input,
+        replay_input,
         input_file,
+        input_file_option,
+        content_packs,
         output_file,
         duration,
+        benchmark,
         verbose,
         no_config_files,
+        command,
     } = options.clone();
-    let input_source = parse_universe_source(input_file, template, template_size, seed);
+
+    if let Some(Command::Templates { command }) = command {
+        templates_command(command);
+        return Ok(());
+    }
+
+    let (input_source, input_to_play) = match replay_input {
+        Some(path) => read_input_recording(&path)?,
+        None => (
+            parse_universe_source(
+                input_file.or(input_file_option),
+                template,
+                template_size,
+                seed,
+            ),
+            Vec::new(),
+        ),
+    };
+    if benchmark.is_some() && graphics_arg != Some(GraphicsType::Headless) {
+        AicDesktopArgs::command()
+            .error(
+                clap::error::ErrorKind::ArgumentConflict,
+                "--benchmark requires --graphics headless",
+            )
+            .exit();
+    }
 
     // Initialize logging -- but only if it won't interfere.
-    if graphics_type != GraphicsType::Terminal || verbose {
+    if graphics_arg != Some(GraphicsType::Terminal) || verbose {
         // Note: Something like this log configuration also appears in other binaries.
         // Unclear how to deduplicate since we don't want to have a library-level dep on
         // simplelog. For now, just remember to consider updating other instances.
@@ -131,6 +185,33 @@ fn main() -> Result<(), anyhow::Error> {
         config_files::load_config().expect("Error loading configuration files")
     };
 
+    // Window state is remembered only if configuration files are in use at all.
+    let window_state = if no_config_files {
+        None
+    } else {
+        match config_files::load_window_state() {
+            Ok(state) => Some(state),
+            Err(e) => {
+                log::error!("Failed to load window state: {e}");
+                None
+            }
+        }
+    };
+    let graphics_type = graphics_arg
+        .or_else(|| {
+            // Only window modes are remembered, since others may not be appropriate for
+            // all uses, and the terminal mode affects the logging configuration above.
+            window_state
+                .as_ref()
+                .and_then(|state| state.graphics)
+                .filter(|g| matches!(g, GraphicsType::Window | GraphicsType::WindowRt))
+        })
+        .unwrap_or(GraphicsType::Window);
+    let window_state = window_state.map(|state| config_files::WindowState {
+        graphics: Some(graphics_type),
+        ..state
+    });
+
     // This cell will be moved into the session after (possibly) being reset to the actual
     // window size. This is a kludge because the `Session`'s `Vui` wants to be able to track
     // the viewport aspect ratio. It would be nice to have a better strategy, but at least
@@ -141,7 +222,15 @@ fn main() -> Result<(), anyhow::Error> {
     ));
 
     let start_session_time = Instant::now();
-    let mut session = runtime.block_on(Session::builder().ui(viewport_cell.as_source()).build());
+    // Only windows, not terminals, can be made fullscreen by the session.
+    let fullscreen_sync = FullscreenSync::new(fullscreen);
+    let mut session_builder = Session::builder()
+        .ui(viewport_cell.as_source())
+        .language(language.unwrap_or_else(system_language));
+    if matches!(graphics_type, GraphicsType::Window | GraphicsType::WindowRt) {
+        session_builder = fullscreen_sync.configure(session_builder);
+    }
+    let mut session = runtime.block_on(session_builder.build());
     session.graphics_options_mut().set(graphics_options);
     let session_done_time = Instant::now();
     log::debug!(
@@ -157,14 +246,26 @@ fn main() -> Result<(), anyhow::Error> {
             && output_file.as_ref().map_or(false, |file| {
                 determine_record_format(file).map_or(false, |fmt| fmt.includes_light())
             }));
-    let universe = runtime.block_on(create_universe(input_source, precompute_light))?;
+    let input_source = input_source.with_seed_chosen();
+    let input_recorder = record_input
+        .map(|path| InputRecorder::create(path, &input_source))
+        .transpose()?;
+    // Benchmark mode does its own light precomputation so that it can be timed.
+    let universe = runtime.block_on(create_universe(
+        input_source,
+        &content_packs,
+        precompute_light && benchmark.is_none(),
+    ))?;
     session.set_universe(universe);
+    session.play_recorded_input(input_to_play);
+    session.set_recording_input(input_recorder.is_some());
 
     // Bundle of inputs to `inner_main()`, which — unlike this function — is generic over
     // the kind of window system we're using.
     let inner_params = InnerMainParams {
         before_loop_time: Instant::now(),
         headless: options.is_headless(),
+        input_recorder,
     };
 
     // The graphics type selects not only the kind of 'window' we create, but also the
@@ -187,12 +288,15 @@ fn main() -> Result<(), anyhow::Error> {
                     &title_and_version(),
                     display_size,
                     fullscreen,
+                    window_state.as_ref(),
                 )?,
                 viewport_cell,
             ))?;
             inner_main(
                 inner_params,
-                move |dsession| winit_main_loop(event_loop, dsession),
+                move |dsession| {
+                    winit_main_loop(event_loop, dsession, fullscreen_sync, window_state)
+                },
                 dsession,
             )
         }
@@ -205,12 +309,15 @@ fn main() -> Result<(), anyhow::Error> {
                     &title_and_version(),
                     display_size,
                     fullscreen,
+                    window_state.as_ref(),
                 )?,
                 viewport_cell,
             )?;
             inner_main(
                 inner_params,
-                move |dsession| winit_main_loop(event_loop, dsession),
+                move |dsession| {
+                    winit_main_loop(event_loop, dsession, fullscreen_sync, window_state)
+                },
                 dsession,
             )
         }
@@ -257,12 +364,67 @@ fn main() -> Result<(), anyhow::Error> {
         }
         GraphicsType::Headless => inner_main(
             inner_params,
-            |dsession| headless_main_loop(dsession, duration),
+            |dsession| match benchmark {
+                Some(steps) => benchmark_main(dsession, steps, precompute_light),
+                None => headless_main_loop(dsession, duration),
+            },
             DesktopSession::new((), (), session, viewport_cell),
         ),
     }
 }
 
+/// Prints information about [`UniverseTemplate`]s, for the `templates` subcommand.
+fn templates_command(command: TemplatesCommand) {
+    match command {
+        TemplatesCommand::List => {
+            let templates: Vec<UniverseTemplate> = UniverseTemplate::iter()
+                .filter(UniverseTemplate::include_in_lists)
+                .collect();
+            let width = templates
+                .iter()
+                .map(|template| template.to_string().len())
+                .max()
+                .unwrap_or(0);
+            for template in templates {
+                println!(
+                    "{name:width$}  {description}",
+                    name = template.to_string(),
+                    description = template.info().description
+                );
+            }
+        }
+        TemplatesCommand::Describe { name: template } => {
+            let info = template.info();
+            println!("{template}: {}", info.description);
+            if !template.include_in_lists() {
+                println!("(This template is not listed, being mostly useful for testing.)");
+            }
+            println!();
+            let uses = |used: bool| if used { "used" } else { "ignored" };
+            println!("--seed: {}", uses(info.uses_seed));
+            match info.default_size {
+                Some(size) => println!(
+                    "--template-size: {}; the default is {}×{}×{}",
+                    uses(info.uses_size),
+                    size.x,
+                    size.y,
+                    size.z
+                ),
+                None => println!("--template-size: {}", uses(info.uses_size)),
+            }
+            println!(
+                "Expected build time: {}",
+                match info.build_time {
+                    TemplateBuildTime::Fast => "under a second",
+                    TemplateBuildTime::Moderate => "a few seconds",
+                    TemplateBuildTime::Slow => "more than a few seconds",
+                    _ => "unknown",
+                }
+            );
+        }
+    }
+}
+
 /// Given a [`DesktopSession`] and an event loop type already decided, run the remainder
 /// of main operations.
 ///
@@ -272,6 +434,8 @@ fn inner_main<Ren, Win>(
     looper: impl FnOnce(DesktopSession<Ren, Win>) -> Result<(), anyhow::Error>,
     mut dsession: DesktopSession<Ren, Win>,
 ) -> Result<(), anyhow::Error> {
+    dsession.input_recorder = params.input_recorder;
+
     if !params.headless {
         match audio::init_sound(&dsession.session) {
             Ok(audio_out) => dsession.audio = Some(audio_out),
@@ -298,11 +462,13 @@ fn inner_main<Ren, Win>(
 struct InnerMainParams {
     before_loop_time: Instant,
     headless: bool,
+    input_recorder: Option<InputRecorder>,
 }
 
 /// Perform and log the creation of the universe.
 async fn create_universe(
     input_source: UniverseSource,
+    content_packs: &[PathBuf],
     precompute_light: bool,
 ) -> Result<Universe, anyhow::Error> {
     let start_time = Instant::now();
@@ -316,38 +482,38 @@ async fn create_universe(
     universe_progress_bar.set_position(0);
     let yield_progress = {
         let universe_progress_bar = universe_progress_bar.clone();
-        YieldProgress::new(
+        YieldProgress::new_staged(
             || std::future::ready(()),
-            move |fraction, label| {
-                universe_progress_bar.set_position((fraction * 100.0) as u64);
-                universe_progress_bar.set_message(String::from(label));
+            move |info| {
+                universe_progress_bar.set_position((info.fraction() * 100.0) as u64);
+                universe_progress_bar.set_message(info.to_string());
             },
         )
     };
-    let universe = match input_source.clone() {
-        UniverseSource::Template(template, TemplateParameters { seed, size }) => {
-            let seed: u64 = seed.unwrap_or_else(|| {
-                let seed = rand::thread_rng().gen();
-                log::info!("Randomly chosen universe seed: {seed}");
-                seed
-            });
-            template
-                .build(
-                    yield_progress,
-                    TemplateParameters {
-                        seed: Some(seed),
-                        size,
-                    },
-                )
-                .await
-                .map_err(anyhow::Error::from)
-        }
+    let [yield_progress, packs_progress] =
+        yield_progress.split(if content_packs.is_empty() { 1.0 } else { 0.8 });
+    let mut universe = match input_source.clone() {
+        UniverseSource::Template(template, parameters) => template
+            .build(yield_progress, parameters)
+            .await
+            .map_err(anyhow::Error::from),
         UniverseSource::File(path) => {
             all_is_cubes_port::load_universe_from_file(yield_progress, &*path)
                 .await
                 .map_err(anyhow::Error::from)
         }
     }?;
+    for (path, progress) in content_packs
+        .iter()
+        .zip(packs_progress.split_evenly(content_packs.len()))
+    {
+        // The namespace is the file name without any extension such as “.zip”.
+        let namespace = path
+            .file_stem()
+            .with_context(|| format!("content pack path {} has no name", path.display()))?
+            .to_string_lossy();
+        all_is_cubes_port::load_content_pack(progress, path, &namespace, &mut universe).await?;
+    }
     universe_progress_bar.finish();
     let universe_done_time = Instant::now();
     log::debug!(
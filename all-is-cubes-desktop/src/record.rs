@@ -1,12 +1,16 @@
 //! Headless image (and someday video) generation.
 
 use std::fs::File;
-use std::sync::mpsc;
+use std::sync::{mpsc, Arc};
 
-use all_is_cubes::camera::{Flaws, StandardCameras};
+use image::RgbaImage;
+
+use all_is_cubes::camera::{Flaws, HeadlessRenderer, StandardCameras};
 use all_is_cubes::listen::ListenableSource;
 use all_is_cubes::raytracer::RtRenderer;
 use all_is_cubes::util::YieldProgress;
+use all_is_cubes_gpu::in_wgpu;
+use all_is_cubes_gpu::wgpu;
 use all_is_cubes_port::gltf::{GltfDataDestination, GltfWriter};
 
 mod options;
@@ -33,6 +37,7 @@ pub(crate) struct Recorder {
 enum RecorderInner {
     Shutdown,
     Raytrace(RtRecorder),
+    Gpu(GpuRecorder),
     Mesh(write_gltf::MeshRecorder),
 }
 
@@ -58,38 +63,14 @@ impl Recorder {
 
         let inner = match options.output_format {
             RecordFormat::PngOrApng => {
-                // Set up threads. Raytracing is internally parallel using Rayon, but we want to
-                // thread everything else too so we're not alternating single-threaded and parallel
-                // operations.
-                let (scene_sender, scene_receiver) =
-                    mpsc::sync_channel::<(FrameNumber, RtRenderer)>(1);
                 let (image_data_sender, image_data_receiver) = mpsc::sync_channel(1);
 
-                // Raytracing thread.
-                std::thread::Builder::new()
-                    .name("renderer".to_string())
-                    .spawn({
-                        move || {
-                            while let Ok((frame_number, renderer)) = scene_receiver.recv() {
-                                let (image, _info, flaws) = renderer.draw_rgba(|_| String::new());
-                                image_data_sender
-                                    .send((
-                                        Status {
-                                            frame_number,
-                                            flaws,
-                                        },
-                                        image,
-                                    ))
-                                    .unwrap();
-                            }
-                        }
-                    })?;
-
                 // Image encoding and writing thread.
                 std::thread::Builder::new()
                     .name("image encoder".to_string())
                     .spawn({
                         let file = File::create(&options.output_path)?;
+                        let options = options.clone();
                         move || {
                             write_png::threaded_write_frames(
                                 file,
@@ -101,10 +82,53 @@ impl Recorder {
                         }
                     })?;
 
-                RecorderInner::Raytrace(RtRecorder {
-                    cameras,
-                    scene_sender,
-                })
+                if options.gpu {
+                    // The GPU renderer does its work asynchronously from the CPU's perspective
+                    // already, so it does not need a thread of its own.
+                    let renderer = runtime_handle.block_on(async {
+                        let adapter = request_headless_adapter().await?;
+                        let builder = in_wgpu::headless::Builder::from_adapter(adapter).await?;
+                        Ok::<_, anyhow::Error>(builder.build(cameras))
+                    })?;
+
+                    RecorderInner::Gpu(GpuRecorder {
+                        renderer,
+                        runtime_handle: runtime_handle.clone(),
+                        image_data_sender,
+                    })
+                } else {
+                    // Set up threads. Raytracing is internally parallel using Rayon, but we
+                    // want to thread everything else too so we're not alternating
+                    // single-threaded and parallel operations.
+                    let (scene_sender, scene_receiver) =
+                        mpsc::sync_channel::<(FrameNumber, RtRenderer)>(1);
+
+                    // Raytracing thread.
+                    std::thread::Builder::new()
+                        .name("renderer".to_string())
+                        .spawn({
+                            move || {
+                                while let Ok((frame_number, renderer)) = scene_receiver.recv() {
+                                    let (image, _info, flaws) =
+                                        renderer.draw_rgba(|_| String::new());
+                                    image_data_sender
+                                        .send((
+                                            Status {
+                                                frame_number,
+                                                flaws,
+                                            },
+                                            image,
+                                        ))
+                                        .unwrap();
+                                }
+                            }
+                        })?;
+
+                    RecorderInner::Raytrace(RtRecorder {
+                        cameras,
+                        scene_sender,
+                    })
+                }
             }
             RecordFormat::Gltf => {
                 let (scene_sender, scene_receiver) =
@@ -167,6 +191,7 @@ impl Recorder {
                     .send((this_frame_number, renderer))
                     .expect("channel closed; recorder render thread died?");
             }
+            RecorderInner::Gpu(rec) => rec.capture_frame(this_frame_number),
             RecorderInner::Mesh(rec) => rec.capture_frame(this_frame_number),
             RecorderInner::Shutdown => unreachable!(),
         }
@@ -184,3 +209,58 @@ pub(crate) struct RtRecorder {
     cameras: StandardCameras,
     scene_sender: mpsc::SyncSender<(FrameNumber, RtRenderer)>,
 }
+
+/// GPU rendering machinery of [`Recorder`].
+#[derive(Debug)]
+pub(crate) struct GpuRecorder {
+    renderer: in_wgpu::headless::Renderer,
+    runtime_handle: tokio::runtime::Handle,
+    image_data_sender: mpsc::SyncSender<(Status, RgbaImage)>,
+}
+
+impl GpuRecorder {
+    fn capture_frame(&mut self, frame_number: FrameNumber) {
+        let (image, flaws) = self
+            .runtime_handle
+            .block_on(async {
+                self.renderer.update(None).await?;
+                self.renderer.draw("").await
+            })
+            .expect("GPU rendering failed");
+
+        // TODO: instead of panic on send failure, log the problem
+        self.image_data_sender
+            .send((
+                Status {
+                    frame_number,
+                    flaws,
+                },
+                image,
+            ))
+            .expect("channel closed; recorder image encoder thread died?");
+    }
+}
+
+/// Obtain a [`wgpu::Adapter`] suitable for rendering without any window or surface.
+async fn request_headless_adapter() -> Result<Arc<wgpu::Adapter>, anyhow::Error> {
+    let instance = wgpu::Instance::new(wgpu::InstanceDescriptor {
+        backends: wgpu::util::backend_bits_from_env().unwrap_or_else(wgpu::Backends::all),
+        ..Default::default()
+    });
+    let mut adapter: Option<wgpu::Adapter> =
+        wgpu::util::initialize_adapter_from_env(&instance, wgpu::Backends::all());
+    if adapter.is_none() {
+        adapter = instance
+            .request_adapter(&wgpu::RequestAdapterOptions {
+                power_preference: wgpu::util::power_preference_from_env()
+                    .unwrap_or(wgpu::PowerPreference::HighPerformance),
+                compatible_surface: None,
+                force_fallback_adapter: false,
+            })
+            .await;
+    }
+    let adapter = adapter
+        .ok_or_else(|| anyhow::format_err!("Could not request suitable graphics adapter"))?;
+    log::debug!("Adapter: {:?}", adapter.get_info());
+    Ok(Arc::new(adapter))
+}
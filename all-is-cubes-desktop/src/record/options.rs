@@ -14,6 +14,9 @@ use all_is_cubes::cgmath::Vector2;
 pub struct RecordOptions {
     pub output_path: PathBuf,
     pub output_format: RecordFormat,
+    /// Whether to render images using the GPU rather than the raytracer.
+    /// Only affects [`RecordFormat::PngOrApng`].
+    pub gpu: bool,
     pub image_size: Vector2<u32>,
     pub animation: Option<RecordAnimationOptions>,
 }
@@ -0,0 +1,185 @@
+//! Files of input recorded by [`Session`] for later playback, for debugging and for
+//! making demonstrations.
+//!
+//! The file is in JSON Lines format: the first line says how the universe was created,
+//! and each following line is one [`RecordedInput`]. Lines are written as the input is
+//! received, so that the recording is complete even if the process exits abruptly.
+//!
+//! [`Session`]: all_is_cubes_ui::apps::Session
+
+use std::fs::File;
+use std::io::{BufRead as _, BufReader, BufWriter, Write as _};
+use std::path::{Path, PathBuf};
+use std::str::FromStr as _;
+
+use anyhow::Context as _;
+use serde::{Deserialize, Serialize};
+
+use all_is_cubes::math::GridCoordinate;
+use all_is_cubes_content::{TemplateParameters, UniverseTemplate};
+use all_is_cubes_ui::apps::RecordedInput;
+
+use crate::command_options::UniverseSource;
+
+/// Serialization of [`UniverseSource`], written as the first line of the file.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type")]
+enum ReplayHeader {
+    Template {
+        template: String,
+        seed: Option<u64>,
+        size: Option<[GridCoordinate; 3]>,
+    },
+    File {
+        path: PathBuf,
+    },
+}
+
+impl From<&UniverseSource> for ReplayHeader {
+    fn from(source: &UniverseSource) -> Self {
+        match source {
+            UniverseSource::Template(template, TemplateParameters { seed, size }) => {
+                ReplayHeader::Template {
+                    template: <&str>::from(template.clone()).to_owned(),
+                    seed: *seed,
+                    size: size.map(Into::into),
+                }
+            }
+            UniverseSource::File(path) => ReplayHeader::File { path: path.clone() },
+        }
+    }
+}
+
+impl TryFrom<ReplayHeader> for UniverseSource {
+    type Error = anyhow::Error;
+
+    fn try_from(header: ReplayHeader) -> Result<Self, Self::Error> {
+        Ok(match header {
+            ReplayHeader::Template {
+                template,
+                seed,
+                size,
+            } => UniverseSource::Template(
+                UniverseTemplate::from_str(&template)
+                    .with_context(|| format!("unknown template {template:?}"))?,
+                TemplateParameters {
+                    seed,
+                    size: size.map(Into::into),
+                },
+            ),
+            ReplayHeader::File { path } => UniverseSource::File(path),
+        })
+    }
+}
+
+/// Writes input recorded by a [`Session`](all_is_cubes_ui::apps::Session) to a file.
+#[derive(Debug)]
+pub(crate) struct InputRecorder {
+    path: PathBuf,
+    file: BufWriter<File>,
+}
+
+impl InputRecorder {
+    /// Creates the file and writes the description of how the universe was created.
+    pub(crate) fn create(path: PathBuf, source: &UniverseSource) -> Result<Self, anyhow::Error> {
+        let file = File::create(&path)
+            .with_context(|| format!("could not create input recording {}", path.display()))?;
+        let mut recorder = Self {
+            path,
+            file: BufWriter::new(file),
+        };
+        recorder.write_line(&ReplayHeader::from(source))?;
+        recorder.file.flush()?;
+        Ok(recorder)
+    }
+
+    /// Appends the given input to the file.
+    pub(crate) fn write(&mut self, inputs: Vec<RecordedInput>) -> Result<(), anyhow::Error> {
+        if inputs.is_empty() {
+            return Ok(());
+        }
+        for input in &inputs {
+            self.write_line(input)?;
+        }
+        self.file
+            .flush()
+            .with_context(|| format!("could not write to {}", self.path.display()))
+    }
+
+    fn write_line<T: Serialize>(&mut self, value: &T) -> Result<(), anyhow::Error> {
+        serde_json::to_writer(&mut self.file, value)?;
+        writeln!(self.file)?;
+        Ok(())
+    }
+}
+
+/// Reads a file written by [`InputRecorder`], returning how the universe should be
+/// created and the input to play back.
+pub(crate) fn read_input_recording(
+    path: &Path,
+) -> Result<(UniverseSource, Vec<RecordedInput>), anyhow::Error> {
+    let file = File::open(path)
+        .with_context(|| format!("could not open input recording {}", path.display()))?;
+    let mut lines = BufReader::new(file).lines();
+    let header: ReplayHeader = serde_json::from_str(
+        &lines
+            .next()
+            .context("input recording is empty")?
+            .context("could not read input recording")?,
+    )
+    .context("could not parse input recording header")?;
+    let inputs = lines
+        .map(|line| Ok(serde_json::from_str(&line?)?))
+        .collect::<Result<Vec<RecordedInput>, anyhow::Error>>()
+        .context("could not parse input recording")?;
+    Ok((UniverseSource::try_from(header)?, inputs))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use all_is_cubes::cgmath::Vector3;
+    use all_is_cubes_ui::apps::{InputEvent, Key};
+
+    #[test]
+    fn header_round_trip() {
+        let source = UniverseSource::Template(
+            UniverseTemplate::CornellBox,
+            TemplateParameters {
+                seed: Some(123),
+                size: Some(Vector3::new(10, 20, 30)),
+            },
+        );
+        let header = ReplayHeader::from(&source);
+        assert_eq!(
+            header,
+            ReplayHeader::Template {
+                template: String::from("cornell-box"),
+                seed: Some(123),
+                size: Some([10, 20, 30]),
+            }
+        );
+        assert_eq!(UniverseSource::try_from(header).unwrap(), source);
+    }
+
+    #[test]
+    fn write_and_read() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("input.jsonl");
+        let source = UniverseSource::File(PathBuf::from("foo.alliscubesjson"));
+
+        let inputs = vec![
+            RecordedInput::new(0, InputEvent::KeyDown(Key::Character('w'))),
+            RecordedInput::new(5, InputEvent::MouseNdcPosition(Some([0.5, -0.25]))),
+            RecordedInput::new(5, InputEvent::Click(1)),
+        ];
+
+        let mut recorder = InputRecorder::create(path.clone(), &source).unwrap();
+        recorder.write(inputs[..1].to_vec()).unwrap();
+        recorder.write(Vec::new()).unwrap();
+        recorder.write(inputs[1..].to_vec()).unwrap();
+        drop(recorder);
+
+        assert_eq!(read_input_recording(&path).unwrap(), (source, inputs));
+    }
+}
@@ -5,7 +5,7 @@ use all_is_cubes::camera::Viewport;
 use all_is_cubes::listen::ListenableCell;
 use all_is_cubes::universe::UniverseStepInfo;
 use all_is_cubes::util::YieldProgress;
-use all_is_cubes_ui::apps::Session;
+use all_is_cubes_ui::apps::{Session, UniverseLoadError};
 
 /// Wraps a basic [`Session`] to add functionality that is common within
 /// all-is-cubes-desktop's scope of supported usage (such as loading a universe
@@ -31,6 +31,9 @@ pub(crate) struct DesktopSession<Ren, Win> {
     /// If present, writes frames to disk.
     pub(crate) recorder: Option<crate::record::Recorder>,
 
+    /// If present, writes the input given to `session` to disk.
+    pub(crate) input_recorder: Option<crate::replay::InputRecorder>,
+
     /// If present, connection to system audio output.
     /// If absent, sound is not produced
     pub(crate) audio: Option<crate::audio::AudioOut>,
@@ -57,6 +60,7 @@ impl<Ren, Win> DesktopSession<Ren, Win> {
             viewport_cell,
             clock_source: ClockSource::Instant,
             recorder: None,
+            input_recorder: None,
             audio: None,
             occluded: false,
         }
@@ -74,6 +78,14 @@ impl<Ren, Win> DesktopSession<Ren, Win> {
         }
         let step_info = self.session.maybe_step_universe();
 
+        if let Some(input_recorder) = self.input_recorder.as_mut() {
+            if let Err(e) = input_recorder.write(self.session.take_recorded_input()) {
+                log::error!("Failed to record input; will stop recording.\n{e:#}");
+                self.input_recorder = None;
+                self.session.set_recording_input(false);
+            }
+        }
+
         // If we are recording, then do it now.
         // (TODO: We want to record 1 frame *before the first step* too)
         // (TODO: This code is awkward because of partial refactoring towards recording being a
@@ -88,6 +100,8 @@ impl<Ren, Win> DesktopSession<Ren, Win> {
     /// Replace the session's universe with one whose contents are the given file.
     ///
     /// See [`crate::data_files::load_universe_from_file`] for supported formats.
+    /// If loading fails, the session reports the error and the user may retry, which
+    /// reads the file again.
     ///
     /// TODO: Instead of specifying exactly “replace universe”, we should have a
     /// general application concept of “open a provided file” which matches the
@@ -96,13 +110,16 @@ impl<Ren, Win> DesktopSession<Ren, Win> {
     pub fn replace_universe_with_file(&mut self, path: PathBuf) {
         // TODO: Offer confirmation before replacing the current universe.
         // Also a progress bar and other UI.
-        self.session.set_universe_async(async move {
-            all_is_cubes_port::load_universe_from_file(YieldProgress::noop(), &*path)
-                .await
-                .map_err(|e| {
-                    // TODO: show error in user interface
-                    log::error!("Failed to load file '{}':\n{}", path.display(), e);
-                })
+        self.session.set_universe_async_retryable(move || {
+            let path = path.clone();
+            async move {
+                all_is_cubes_port::load_universe_from_file(YieldProgress::noop(), &*path)
+                    .await
+                    .map_err(|e| UniverseLoadError::File {
+                        name: path.display().to_string(),
+                        source: Box::new(e),
+                    })
+            }
         })
     }
 }
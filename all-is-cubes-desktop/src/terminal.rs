@@ -8,9 +8,9 @@ use crossterm::event::{Event, KeyCode, KeyEvent, KeyModifiers, MouseEvent, Mouse
 use tui::layout::Rect;
 
 use all_is_cubes::camera::{Camera, StandardCameras, Viewport};
-use all_is_cubes::cgmath::{Point2, Vector2};
+use all_is_cubes::cgmath::Vector2;
 use all_is_cubes::listen::{ListenableCell, ListenableSource};
-use all_is_cubes::math::Rgba;
+use all_is_cubes::math::{Rgb, Rgba};
 use all_is_cubes::raytracer::{CharacterBuf, CharacterRtData, ColorBuf, PixelBuf, RtRenderer};
 use all_is_cubes_ui::apps::Session;
 
@@ -22,8 +22,9 @@ mod options;
 pub(crate) use options::TerminalOptions;
 mod ray_image;
 use ray_image::TextRayImage;
+mod sixel;
 mod ui;
-use ui::{InventoryDisplay, OutMsg, TerminalWindow, UiFrame};
+use ui::{InventoryDisplay, MouseTarget, OutMsg, TerminalWindow, UiFrame};
 
 /// Print the scene to stdout and return, instead of starting any interaction.
 ///
@@ -203,28 +204,42 @@ fn run(dsession: &mut DesktopSession<TerminalRenderer, TerminalWindow>) -> cross
                     column,
                     row,
                     modifiers: _,
-                }) => {
-                    // TODO: there's a scaling of nominal_size in viewport_from_terminal_size that we
-                    // need to undo here, but it would be better to directly input the right coordinate
-                    // system. Define a version of mouse_pixel_position which takes sizes directly?
-                    let position =
-                        Point2::new((f64::from(column) - 0.5) * 0.5, f64::from(row) - 0.5);
-                    dsession.session.input_processor.mouse_pixel_position(
-                        *dsession.viewport_cell.get(),
-                        Some(position),
-                        true,
-                    );
-                    match kind {
-                        MouseEventKind::Down(button) => {
+                }) => match dsession.window.mouse_target(column, row) {
+                    MouseTarget::Scene(position) => {
+                        dsession.session.input_processor.mouse_pixel_position(
+                            *dsession.viewport_cell.get(),
+                            Some(position),
+                            true,
+                        );
+                        if let MouseEventKind::Down(button) = kind {
+                            // Make sure the cursor reflects this position, not the last frame's.
+                            dsession.session.update_cursor(&dsession.renderer.cameras);
                             dsession.session.click(map_mouse_button(button));
                         }
-                        MouseEventKind::Up(_)
-                        | MouseEventKind::Drag(_)
-                        | MouseEventKind::Moved
-                        | MouseEventKind::ScrollDown
-                        | MouseEventKind::ScrollUp => {}
                     }
-                }
+                    MouseTarget::ToolbarSlot(slot) => {
+                        dsession.session.input_processor.mouse_pixel_position(
+                            *dsession.viewport_cell.get(),
+                            None,
+                            false,
+                        );
+                        if let MouseEventKind::Down(button) = kind {
+                            if let Some(character_ref) = dsession.session.character().snapshot() {
+                                // Ignore failure; the selection will just not change.
+                                let _ = character_ref.try_modify(|c| {
+                                    c.set_selected_slot(map_mouse_button(button), slot)
+                                });
+                            }
+                        }
+                    }
+                    MouseTarget::Other => {
+                        dsession.session.input_processor.mouse_pixel_position(
+                            *dsession.viewport_cell.get(),
+                            None,
+                            false,
+                        );
+                    }
+                },
                 Event::FocusGained | Event::FocusLost => {}
                 Event::Paste(_) => {}
             }
@@ -342,6 +357,15 @@ impl PixelBuf for ColorCharacterBuf {
         self.text.add(surface_color, text);
     }
 
+    #[inline]
+    fn absorb(&mut self, transmittance: Rgb, _: &Self::BlockData) {
+        if self.override_color {
+            return;
+        }
+
+        self.color.absorb(transmittance, &());
+    }
+
     fn hit_nothing(&mut self) {
         self.text
             .add(Rgba::TRANSPARENT, &CharacterRtData(Cow::Borrowed(" ")));
@@ -34,7 +34,10 @@ pub(super) fn image_patch_to_character(
             };
 
             let text = match &options.characters {
-                CharacterMode::Names | CharacterMode::Shades | CharacterMode::Braille => {
+                CharacterMode::Names
+                | CharacterMode::Shades
+                | CharacterMode::Braille
+                | CharacterMode::Sixel => {
                     unreachable!()
                 }
                 CharacterMode::Split => match (lum1 > threshold1, lum2 > threshold2) {
@@ -111,6 +114,11 @@ pub(super) fn image_patch_to_character(
                 Colors::new(Color::Reset, Color::Reset),
             )
         }
+        (CharacterMode::Sixel, _) => {
+            // Sixel images are written by `super::sixel` instead of as characters;
+            // if we get here anyway, draw blank space.
+            (" ", Colors::new(Color::Reset, Color::Reset))
+        }
         (CharacterMode::Braille, _) => {
             let braille_dot_bit = |(_, color): &TextAndColor, number_z: usize| -> usize {
                 if let Some(color) = color {
@@ -40,7 +40,7 @@ impl TerminalOptions {
 impl Default for TerminalOptions {
     fn default() -> Self {
         Self {
-            colors: ColorMode::TwoFiftySix, // TODO: default to 16-color mode once we have it implemented
+            colors: ColorMode::detect(),
             characters: CharacterMode::Split,
         }
     }
@@ -56,6 +56,16 @@ pub(crate) enum ColorMode {
 }
 
 impl ColorMode {
+    /// Guess the best mode the terminal supports, from the `COLORTERM` environment
+    /// variable which many terminals set to advertise 24-bit color.
+    pub(crate) fn detect() -> Self {
+        match std::env::var("COLORTERM").as_deref() {
+            Ok("truecolor" | "24bit") => ColorMode::Rgb,
+            // TODO: default to 16-color mode once we have it implemented
+            _ => ColorMode::TwoFiftySix,
+        }
+    }
+
     /// Returns the “next” option for purposes of UI stepping.
     pub(crate) fn cycle(self) -> Self {
         use ColorMode::*;
@@ -158,6 +168,13 @@ pub(crate) enum CharacterMode {
 
     /// Use Braille symbols as 2×4 dot patterns.
     Braille,
+
+    /// Don't use characters at all, but draw the image using Sixel graphics.
+    ///
+    /// This only works in terminals which support Sixel, and assumes that character
+    /// cells are 8×16 pixels. It ignores [`ColorMode`], since Sixel images always
+    /// specify their own colors.
+    Sixel,
 }
 
 impl CharacterMode {
@@ -169,7 +186,8 @@ impl CharacterMode {
             Shades => Split,
             Split => Shapes,
             Shapes => Braille,
-            Braille => Names,
+            Braille => Sixel,
+            Sixel => Names,
         }
     }
 
@@ -179,6 +197,8 @@ impl CharacterMode {
             Names | Shades => Vector2::new(1, 1),
             Split | Shapes => Vector2::new(1, 2),
             Braille => Vector2::new(2, 4),
+            // Each ray is drawn as 2×2 pixels; see `super::sixel`.
+            Sixel => Vector2::new(4, 8),
         }
     }
 }
@@ -0,0 +1,159 @@
+//! Writing images as Sixel graphics, for [`CharacterMode::Sixel`].
+//!
+//! Sixel is a bitmap graphics format that some terminals can display inline with text.
+//! The image is written in horizontal bands of 6 pixels height; each band is drawn once
+//! per color appearing in it, with one byte per column giving which of the 6 pixels in
+//! that column have that color.
+//!
+//! [`CharacterMode::Sixel`]: super::options::CharacterMode::Sixel
+
+use std::io;
+
+use all_is_cubes::cgmath::Vector2;
+use all_is_cubes::math::Rgba;
+
+use super::TextRayImage;
+
+/// Number of Sixel pixels, horizontally and vertically, used to display each ray.
+const PIXELS_PER_RAY: usize = 2;
+
+/// Number of colors in the palette: a 6×6×6 RGB cube.
+const PALETTE_SIZE: usize = 216;
+
+/// Write the portion of `image` which covers `size_in_characters` (starting from the
+/// upper left) as a Sixel image.
+///
+/// The cursor should already be positioned where the image should be drawn.
+pub(super) fn write_sixel(
+    out: &mut impl io::Write,
+    image: &TextRayImage,
+    size_in_characters: Vector2<usize>,
+) -> io::Result<()> {
+    let rays_per_character = image.patch_size().map(usize::from);
+    let image_row_length = image.viewport.framebuffer_size.x as usize;
+    let width = size_in_characters.x * rays_per_character.x * PIXELS_PER_RAY;
+    let height = size_in_characters.y * rays_per_character.y * PIXELS_PER_RAY;
+
+    let palette_index_at = |x: usize, y: usize| -> usize {
+        let ray = (y / PIXELS_PER_RAY) * image_row_length + x / PIXELS_PER_RAY;
+        palette_index(image.image[ray].1.unwrap_or(Rgba::BLACK))
+    };
+
+    // Begin the Sixel sequence, and specify the size and square pixel aspect ratio.
+    write!(out, "\x1bPq\"1;1;{width};{height}")?;
+
+    // Define only the colors that are used, to keep the output smaller.
+    let mut used = [false; PALETTE_SIZE];
+    for y in (0..height).step_by(PIXELS_PER_RAY) {
+        for x in (0..width).step_by(PIXELS_PER_RAY) {
+            used[palette_index_at(x, y)] = true;
+        }
+    }
+    for (index, _) in used.iter().enumerate().filter(|&(_, &used)| used) {
+        let [r, g, b] = palette_color(index);
+        write!(out, "#{index};2;{r};{g};{b}")?;
+    }
+
+    let mut band: Vec<usize> = vec![0; width * 6];
+    for band_top in (0..height).step_by(6) {
+        let band_height = (height - band_top).min(6);
+        let mut used_in_band = [false; PALETTE_SIZE];
+        for row in 0..band_height {
+            for x in 0..width {
+                let index = palette_index_at(x, band_top + row);
+                band[row * width + x] = index;
+                used_in_band[index] = true;
+            }
+        }
+
+        for (color, _) in used_in_band.iter().enumerate().filter(|&(_, &used)| used) {
+            write!(out, "#{color}")?;
+            let mut run: Option<(u8, usize)> = None;
+            for x in 0..width {
+                let mut bits = 0u8;
+                for row in 0..band_height {
+                    if band[row * width + x] == color {
+                        bits |= 1 << row;
+                    }
+                }
+                let sixel = b'?' + bits;
+                run = match run {
+                    Some((previous, count)) if previous == sixel => Some((sixel, count + 1)),
+                    Some((previous, count)) => {
+                        write_run(out, previous, count)?;
+                        Some((sixel, 1))
+                    }
+                    None => Some((sixel, 1)),
+                };
+            }
+            if let Some((sixel, count)) = run {
+                write_run(out, sixel, count)?;
+            }
+            // Return to the start of the band to draw the next color.
+            out.write_all(b"$")?;
+        }
+        // Advance to the next band.
+        out.write_all(b"-")?;
+    }
+
+    out.write_all(b"\x1b\\")?;
+    Ok(())
+}
+
+/// Write a run of identical sixels, using the repeat introducer if it is shorter.
+fn write_run(out: &mut impl io::Write, sixel: u8, count: usize) -> io::Result<()> {
+    if count > 3 {
+        write!(out, "!{count}")?;
+        out.write_all(&[sixel])
+    } else {
+        out.write_all(&[sixel].repeat(count))
+    }
+}
+
+/// Quantize a color to the nearest entry in the palette.
+fn palette_index(color: Rgba) -> usize {
+    let [r, g, b, _] = color.to_srgb8().map(|c| (usize::from(c) * 5 + 127) / 255);
+    (r * 6 + g) * 6 + b
+}
+
+/// Returns the color of a palette entry, as the percentages Sixel uses.
+fn palette_color(index: usize) -> [usize; 3] {
+    [index / 36, (index / 6) % 6, index % 6].map(|level| level * 20)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::terminal::options::{CharacterMode, ColorMode};
+    use crate::terminal::TerminalOptions;
+    use all_is_cubes::camera::Viewport;
+    use all_is_cubes::raytracer::RaytraceInfo;
+
+    #[test]
+    fn palette_round_trip() {
+        for index in 0..PALETTE_SIZE {
+            let [r, g, b] = palette_color(index).map(|p| (p * 255 / 100) as u8);
+            assert_eq!(palette_index(Rgba::from_srgb8([r, g, b, 255])), index);
+        }
+    }
+
+    #[test]
+    fn uniform_image() {
+        let options = TerminalOptions {
+            colors: ColorMode::None,
+            characters: CharacterMode::Sixel,
+        };
+        let image = TextRayImage {
+            viewport: Viewport::with_scale(1.0, Vector2::new(4, 8)),
+            options,
+            image: vec![(String::new(), Some(Rgba::new(1.0, 0.0, 0.0, 1.0))); 4 * 8],
+            info: RaytraceInfo::default(),
+        };
+        let mut out = Vec::new();
+        write_sixel(&mut out, &image, Vector2::new(1, 1)).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "\x1bPq\"1;1;8;16#180;2;100;0;0#180!8~$-#180!8~$-#180!8N$-\x1b\\"
+        );
+    }
+}
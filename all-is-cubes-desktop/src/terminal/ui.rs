@@ -14,13 +14,15 @@ use tui::text::{Span, Spans};
 use tui::widgets::{Borders, Paragraph};
 use tui::Terminal;
 
-use all_is_cubes::cgmath::{ElementWise as _, Vector2};
+use all_is_cubes::cgmath::{ElementWise as _, Point2, Vector2};
 use all_is_cubes::character::{Character, Cursor};
 use all_is_cubes::inv::Slot;
 use all_is_cubes::universe::URef;
 use all_is_cubes::util::{CustomFormat, StatusText};
 
 use crate::terminal::chars::{image_patch_to_character, write_colored_and_measure};
+use crate::terminal::options::CharacterMode;
+use crate::terminal::sixel::write_sixel;
 use crate::terminal::TextRayImage;
 
 /// Fills the window slot of [`DesktopSession`].
@@ -33,6 +35,8 @@ pub(crate) struct TerminalWindow {
 
     /// Last retrieved size of the terminal.
     viewport_position: Rect,
+    /// Last retrieved positions of the toolbar slots.
+    toolbar_slots: Vec<Rect>,
 }
 
 #[derive(Debug)]
@@ -47,6 +51,20 @@ pub(super) enum OutMsg {
 #[derive(Debug)]
 pub(crate) enum InMsg {
     Viewport(Rect),
+    ToolbarSlots(Vec<Rect>),
+}
+
+/// What part of the terminal UI a mouse event occurred over; returned by
+/// [`TerminalWindow::mouse_target()`].
+#[derive(Clone, Debug, PartialEq)]
+pub(super) enum MouseTarget {
+    /// The scene, at the given position in the coordinates of the scene's
+    /// [`Viewport::nominal_size`](all_is_cubes::camera::Viewport::nominal_size).
+    Scene(Point2<f64>),
+    /// A toolbar slot, identified by its inventory index.
+    ToolbarSlot(usize),
+    /// Anything else.
+    Other,
 }
 
 /// All the information to draw one frame of world and UI.
@@ -75,6 +93,9 @@ struct TerminalState {
     /// (e.g. emoji might be 2 wide), empirically determined by querying the cursor
     /// position.
     widths: HashMap<String, u16>,
+
+    /// Regions of the terminal occupied by toolbar slots; updated when `tui` layout runs.
+    toolbar_slots: Vec<Rect>,
 }
 
 impl TerminalWindow {
@@ -87,6 +108,7 @@ impl TerminalWindow {
             viewport_position: Rect::default(),
             terminal_state_dirty: true,
             widths: HashMap::new(),
+            toolbar_slots: Vec::new(),
         };
         let thread = std::thread::Builder::new()
             .name("all-is-cubes terminal IO".into())
@@ -97,6 +119,7 @@ impl TerminalWindow {
             in_receiver,
             thread: Some(thread),
             viewport_position: Rect::default(),
+            toolbar_slots: Vec::new(),
         })
     }
 
@@ -139,11 +162,19 @@ impl TerminalWindow {
         self.viewport_position
     }
 
+    /// Determine what part of the UI is at the given terminal cell coordinates,
+    /// as reported by mouse events.
+    pub fn mouse_target(&mut self, column: u16, row: u16) -> MouseTarget {
+        self.update();
+        mouse_target(self.viewport_position, &self.toolbar_slots, column, row)
+    }
+
     fn update(&mut self) {
         #[allow(clippy::while_let_loop)] // this is clearer about its behavior
         loop {
             match self.in_receiver.try_recv() {
                 Ok(InMsg::Viewport(v)) => self.viewport_position = v,
+                Ok(InMsg::ToolbarSlots(slots)) => self.toolbar_slots = slots,
                 Err(mpsc::TryRecvError::Disconnected | mpsc::TryRecvError::Empty) => break,
             }
         }
@@ -248,6 +279,21 @@ impl TerminalState {
         rect.width = rect.width.min(image_size_in_characters.x as u16);
         rect.height = rect.height.min(image_size_in_characters.y as u16);
 
+        if image.options.characters == CharacterMode::Sixel {
+            if draw_into_rect {
+                backend.queue(MoveTo(rect.x, rect.y))?;
+            }
+            write_sixel(
+                backend,
+                image,
+                Vector2::new(usize::from(rect.width), usize::from(rect.height)),
+            )?;
+            if !draw_into_rect {
+                backend.write_all(b"\r\n")?;
+            }
+            return Ok(());
+        }
+
         for y in 0..rect.height {
             if draw_into_rect {
                 backend.queue(MoveTo(rect.x, rect.y + y))?;
@@ -291,6 +337,7 @@ impl TerminalState {
     fn write_ui(&mut self, ui_frame: &UiFrame) -> crossterm::Result<()> {
         let TextRayImage { info, .. } = ui_frame.frame;
         let mut viewport_rect = None;
+        let mut toolbar_slot_rects = None;
         self.tui.draw(|f| {
             const HELP_TEXT: &str = "\
                 Move: WS AD EC  Turn: ←→ ↑↓\n\
@@ -344,6 +391,7 @@ impl TerminalState {
                     .direction(Direction::Horizontal)
                     .constraints([Constraint::Ratio(1, SLOTS as u32); SLOTS])
                     .split(toolbar_rect);
+                toolbar_slot_rects = Some(slot_rects.clone());
 
                 let selected_slots = ui_frame.inventory.selected_slots;
                 for ((i, rect), slot) in slot_rects
@@ -449,11 +497,36 @@ impl TerminalState {
             self.viewport_position = viewport_rect;
             let _ = self.in_sender.send(InMsg::Viewport(viewport_rect));
         }
+        let toolbar_slot_rects = toolbar_slot_rects.expect("layout failed to update");
+        if self.toolbar_slots != toolbar_slot_rects {
+            self.toolbar_slots = toolbar_slot_rects.clone();
+            let _ = self.in_sender.send(InMsg::ToolbarSlots(toolbar_slot_rects));
+        }
 
         Ok(())
     }
 }
 
+/// Implementation of [`TerminalWindow::mouse_target()`].
+fn mouse_target(viewport: Rect, toolbar_slots: &[Rect], column: u16, row: u16) -> MouseTarget {
+    let contains =
+        |r: Rect| (r.left()..r.right()).contains(&column) && (r.top()..r.bottom()).contains(&row);
+
+    if contains(viewport) {
+        // Use the center of the character cell, and account for characters being
+        // twice as tall as they are wide, as `TerminalOptions::viewport_from_terminal_size()`
+        // does.
+        MouseTarget::Scene(Point2::new(
+            (f64::from(column - viewport.x) + 0.5) * 0.5,
+            f64::from(row - viewport.y) + 0.5,
+        ))
+    } else if let Some(slot) = toolbar_slots.iter().position(|&r| contains(r)) {
+        MouseTarget::ToolbarSlot(slot)
+    } else {
+        MouseTarget::Other
+    }
+}
+
 /// Snapshot of a [`Character`] inventory for the UI.
 #[derive(Clone, Debug)]
 pub(super) struct InventoryDisplay {
@@ -491,3 +564,27 @@ const STYLE_NONE: Style = Style {
     add_modifier: Modifier::empty(),
     sub_modifier: Modifier::empty(),
 };
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mouse_target_regions() {
+        let viewport = Rect::new(0, 0, 20, 10);
+        let slots = [Rect::new(0, 10, 10, 3), Rect::new(10, 10, 10, 3)];
+        assert_eq!(
+            mouse_target(viewport, &slots, 0, 0),
+            MouseTarget::Scene(Point2::new(0.25, 0.5))
+        );
+        assert_eq!(
+            mouse_target(viewport, &slots, 19, 9),
+            MouseTarget::Scene(Point2::new(9.75, 9.5))
+        );
+        assert_eq!(
+            mouse_target(viewport, &slots, 12, 11),
+            MouseTarget::ToolbarSlot(1)
+        );
+        assert_eq!(mouse_target(viewport, &slots, 5, 20), MouseTarget::Other);
+    }
+}
@@ -77,6 +77,7 @@ impl FrameBudget {
     pub const SIXTY_FPS: FrameBudget = FrameBudget {
         update_meshes: Layers {
             world: Duration::from_millis(5),
+            hand: Duration::from_millis(1),
             ui: Duration::from_millis(3),
         },
     };
@@ -86,6 +87,7 @@ impl FrameBudget {
     pub const PRACTICALLY_INFINITE: FrameBudget = FrameBudget {
         update_meshes: Layers {
             world: VERY_LONG,
+            hand: VERY_LONG,
             ui: VERY_LONG,
         },
     };
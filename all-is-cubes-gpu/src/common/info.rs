@@ -18,6 +18,18 @@ pub struct RenderInfo {
     pub flaws: Flaws,
 }
 
+impl RenderInfo {
+    /// Returns the total CPU time taken to render the frame, including time spent
+    /// waiting for the GPU.
+    pub fn frame_time(&self) -> Duration {
+        self.waiting_for_gpu
+            .saturating_add(self.update.total_time)
+            .saturating_add(self.draw.times.world)
+            .saturating_add(self.draw.times.hand)
+            .saturating_add(self.draw.times.ui)
+    }
+}
+
 /// Info about the “update” operation, where fresh scene information is gathered,
 /// processed (e.g. mesh generation), and copied to GPU memory.
 ///
@@ -84,20 +96,18 @@ impl CustomFormat<StatusText> for RenderInfo {
             flaws,
         } = self;
 
-        let total_time = waiting_for_gpu
-            .saturating_add(update_time)
-            .saturating_add(draw_time.world)
-            .saturating_add(draw_time.ui);
+        let total_time = self.frame_time();
 
         // Overall summary line
         write!(
             fmt,
             // TODO: adjust this format to account for more pieces
-            "Frame time: {} (GPU wait {}, update {}, draw world {}, ui {}",
+            "Frame time: {} (GPU wait {}, update {}, draw world {}, hand {}, ui {}",
             total_time.custom_format(StatusText),
             waiting_for_gpu.custom_format(StatusText),
             update_time.custom_format(StatusText),
             draw_time.world.custom_format(StatusText),
+            draw_time.hand.custom_format(StatusText),
             draw_time.ui.custom_format(StatusText),
         )?;
         if let Some(t) = submit_time {
@@ -251,6 +261,8 @@ impl CustomFormat<StatusText> for SpaceDrawInfo {
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub struct BlockTextureInfo {
     pub(crate) flushed: usize,
+    /// Whether the texture was enlarged (and therefore entirely rewritten) by this flush.
+    pub(crate) resized: bool,
     pub(crate) flush_time: Duration,
     pub(crate) in_use_tiles: usize,
     pub(crate) in_use_texels: usize,
@@ -261,6 +273,7 @@ impl Default for BlockTextureInfo {
     fn default() -> Self {
         BlockTextureInfo {
             flushed: 0,
+            resized: false,
             flush_time: Duration::ZERO,
             in_use_tiles: 0,
             in_use_texels: 0,
@@ -273,12 +286,13 @@ impl CustomFormat<StatusText> for BlockTextureInfo {
     fn fmt(&self, fmt: &mut fmt::Formatter<'_>, format_type: StatusText) -> fmt::Result {
         write!(
             fmt,
-            "Textures: {} tiles, {} texels ({}%) used, {:2} flushed in {}",
+            "Textures: {} tiles, {} texels ({}%) used, {:2} flushed in {}{}",
             self.in_use_tiles,
             self.in_use_texels,
             (self.in_use_texels as f32 / self.capacity_texels as f32 * 100.0).ceil() as usize,
             self.flushed,
-            self.flush_time.custom_format(format_type)
+            self.flush_time.custom_format(format_type),
+            if self.resized { " (resized)" } else { "" },
         )
     }
 }
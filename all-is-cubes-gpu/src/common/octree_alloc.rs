@@ -51,6 +51,40 @@ impl Alloctree {
         Some(handle)
     }
 
+    /// Enlarges the allocatable region to edge length `2.pow(new_size_exponent)`.
+    ///
+    /// Existing allocations keep their positions, since the old region becomes the
+    /// low corner of the new one.
+    ///
+    /// Panics if `new_size_exponent` is smaller than the current size or larger than
+    /// [`Alloctree::MAX_SIZE_EXPONENT`].
+    pub fn grow_to(&mut self, new_size_exponent: u8) {
+        assert!(
+            new_size_exponent >= self.size_exponent,
+            "Alloctree::grow_to: cannot shrink"
+        );
+        assert!(
+            new_size_exponent <= Self::MAX_SIZE_EXPONENT,
+            "Alloctree size_exponent too large",
+        );
+        while self.size_exponent < new_size_exponent {
+            let old_root = std::mem::replace(&mut self.root, AlloctreeNode::Empty);
+            if !matches!(old_root, AlloctreeNode::Empty) {
+                self.root = AlloctreeNode::Oct(Box::new([
+                    old_root,
+                    AlloctreeNode::Empty,
+                    AlloctreeNode::Empty,
+                    AlloctreeNode::Empty,
+                    AlloctreeNode::Empty,
+                    AlloctreeNode::Empty,
+                    AlloctreeNode::Empty,
+                    AlloctreeNode::Empty,
+                ]));
+            }
+            self.size_exponent += 1;
+        }
+    }
+
     /// Deallocates the given previously allocated region.
     ///
     /// If the handle does not exactly match a previous allocation from this allocator,
@@ -61,6 +95,11 @@ impl Alloctree {
         self.occupied_volume -= handle.allocation.volume();
     }
 
+    /// Returns log2 of the edge length of [`Self::bounds()`].
+    pub fn size_exponent(&self) -> u8 {
+        self.size_exponent
+    }
+
     /// Returns the region that could be allocated within.
     pub fn bounds(&self) -> GridAab {
         let size = expsize(self.size_exponent);
@@ -193,6 +232,15 @@ impl AlloctreeNode {
                     size_exponent - 1,
                     relative_low_corner - which_child.to_vec() * child_size,
                 );
+
+                // Merge free space, so that it is available to requests larger than
+                // a child node.
+                if children
+                    .iter()
+                    .all(|child| matches!(child, AlloctreeNode::Empty))
+                {
+                    *self = AlloctreeNode::Empty;
+                }
             }
         }
     }
@@ -291,6 +339,48 @@ mod tests {
         }
     }
 
+    /// Freeing every allocation within a subdivided node makes the whole node available.
+    #[test]
+    fn free_merges_empty_nodes() {
+        let mut t = Alloctree::new(5);
+        let handles = check_no_overlaps(&mut t, [GridAab::for_block(R4), GridAab::for_block(R8)]);
+        assert_eq!(None, t.allocate(GridAab::for_block(R32)));
+        for handle in handles {
+            t.free(handle);
+        }
+        assert!(t.allocate(GridAab::for_block(R32)).is_some());
+    }
+
+    #[test]
+    fn grow_keeps_allocations() {
+        let mut t = Alloctree::new(4);
+        let old_handles = check_no_overlaps(&mut t, [GridAab::for_block(R16)]);
+        assert_eq!(None, t.allocate(GridAab::for_block(R16)));
+
+        t.grow_to(5);
+        assert_eq!(t.bounds(), GridAab::for_block(R32));
+        assert_eq!(t.occupied_volume(), 16 * 16 * 16);
+        let new_handles = check_no_overlaps(&mut t, [GridAab::for_block(R16); 7]);
+        for new in &new_handles {
+            for old in &old_handles {
+                assert_eq!(
+                    new.allocation
+                        .intersection(old.allocation)
+                        .map(|i| i.volume())
+                        .unwrap_or(0),
+                    0
+                );
+            }
+        }
+        assert_eq!(None, t.allocate(GridAab::for_block(R16)));
+
+        // The original allocation can still be freed correctly.
+        for handle in old_handles {
+            t.free(handle);
+        }
+        assert!(t.allocate(GridAab::for_block(R16)).is_some());
+    }
+
     #[test]
     fn no_overlap() {
         let mut t = Alloctree::new(5);
@@ -8,7 +8,9 @@ use std::sync::Arc;
 use all_is_cubes::notnan;
 use instant::Instant;
 
-use all_is_cubes::camera::{info_text_drawable, Layers, StandardCameras};
+use all_is_cubes::camera::{
+    info_text_drawable, info_text_scale, InfoTextScale, Layers, StandardCameras,
+};
 use all_is_cubes::cgmath::Vector2;
 use all_is_cubes::character::Cursor;
 use all_is_cubes::content::palette;
@@ -33,6 +35,7 @@ use crate::{
 
 mod block_texture;
 mod bloom;
+mod body;
 mod camera;
 mod frame_texture;
 use frame_texture::{DrawableTexture, FramebufferTextures};
@@ -41,9 +44,11 @@ pub mod headless;
 #[doc(hidden)]
 pub mod init;
 mod pipelines;
+mod portal;
 mod postprocess;
 #[doc(hidden)] // public for tests/shader_tests.rs
 pub mod shader_testing;
+mod sky;
 mod space;
 use space::SpaceRenderer;
 mod vertex;
@@ -150,13 +155,16 @@ impl SurfaceRenderer {
             world: SpaceDrawInfo {
                 flaws: world_flaws, ..
             },
+            hand: SpaceDrawInfo {
+                flaws: hand_flaws, ..
+            },
             ui: SpaceDrawInfo {
                 flaws: ui_flaws, ..
             },
         } = draw_info.space_info;
         let info = RenderInfo {
             waiting_for_gpu: after_get.duration_since(before_get),
-            flaws: update_info.flaws | world_flaws | ui_flaws,
+            flaws: update_info.flaws | world_flaws | hand_flaws | ui_flaws,
             update: update_info,
             draw: draw_info,
         };
@@ -388,6 +396,7 @@ impl EverythingRenderer {
         let ws = self.cameras.world_space().snapshot(); // TODO: ugly
         let spaces_to_render = Layers {
             world: ws.as_ref(),
+            hand: self.cameras.hand_space(),
             ui: self.cameras.ui_space(),
         };
 
@@ -401,6 +410,14 @@ impl EverythingRenderer {
             &self.pipelines,
             &self.block_texture,
         )?;
+        Self::update_space_renderer(
+            "hand",
+            &mut self.space_renderers.hand,
+            spaces_to_render.hand,
+            &self.device,
+            &self.pipelines,
+            &self.block_texture,
+        )?;
         Self::update_space_renderer(
             "ui",
             &mut self.space_renderers.ui,
@@ -425,7 +442,17 @@ impl EverythingRenderer {
         let update_prep_to_space_update_time = Instant::now();
 
         let world_deadline = update_prep_to_space_update_time + frame_budget.update_meshes.world;
-        let ui_deadline = world_deadline + frame_budget.update_meshes.ui;
+        let hand_deadline = world_deadline + frame_budget.update_meshes.hand;
+        let ui_deadline = hand_deadline + frame_budget.update_meshes.ui;
+
+        // Must precede the world space update, which flushes the avatars' textures.
+        if let Some(sr) = &mut self.space_renderers.world {
+            sr.update_body(
+                self.cameras.world_avatars(),
+                &self.cameras.cameras().world,
+                bwp.reborrow(),
+            );
+        }
 
         let space_infos: Layers<SpaceUpdateInfo> = Layers {
             world: self
@@ -436,12 +463,28 @@ impl EverythingRenderer {
                     sr.update(
                         world_deadline,
                         queue,
+                        &self.pipelines,
                         &self.cameras.cameras().world,
                         bwp.reborrow(),
                     )
                 })
                 .transpose()?
                 .unwrap_or_default(),
+            hand: self
+                .space_renderers
+                .hand
+                .as_mut()
+                .map(|sr| {
+                    sr.update(
+                        hand_deadline,
+                        queue,
+                        &self.pipelines,
+                        &self.cameras.cameras().hand,
+                        bwp.reborrow(),
+                    )
+                })
+                .transpose()?
+                .unwrap_or_default(),
             ui: self
                 .space_renderers
                 .ui
@@ -450,6 +493,7 @@ impl EverythingRenderer {
                     sr.update(
                         ui_deadline,
                         queue,
+                        &self.pipelines,
                         &self.cameras.cameras().ui,
                         bwp.reborrow(),
                     )
@@ -477,6 +521,26 @@ impl EverythingRenderer {
                 }
             }
 
+            if let Some(selection) = self.cameras.selection() {
+                if Some(selection.space()) == spaces_to_render.world {
+                    wireframe_vertices::<WgpuLinesVertex, _, _>(
+                        &mut v,
+                        palette::SELECTION_OUTLINE,
+                        selection,
+                    );
+                }
+            }
+
+            // Precipitation particles are drawn as lines too. (Their vertices have their
+            // own colors, so the color given here is not used.)
+            if let Some(space) = spaces_to_render.world {
+                let particles = space
+                    .read()
+                    .unwrap()
+                    .weather_particles(self.cameras.cameras().world.view_position());
+                wireframe_vertices::<WgpuLinesVertex, _, _>(&mut v, palette::RAIN, &particles);
+            }
+
             gather_debug_lines(
                 self.cameras
                     .character()
@@ -512,7 +576,10 @@ impl EverythingRenderer {
 
         let finish_update_time = Instant::now();
         Ok(UpdateInfo {
-            flaws: self.fb.flaws() | space_infos.world.flaws() | space_infos.ui.flaws(),
+            flaws: self.fb.flaws()
+                | space_infos.world.flaws()
+                | space_infos.hand.flaws()
+                | space_infos.ui.flaws(),
             total_time: finish_update_time.duration_since(start_frame_time),
             prep_time: update_prep_to_space_update_time.duration_since(start_frame_time),
             lines_time: lines_to_submit_time.duration_since(space_update_to_lines_time),
@@ -559,6 +626,18 @@ impl EverythingRenderer {
         &mut self,
         queue: &wgpu::Queue,
     ) -> Result<DrawInfo, GraphicsResourceError> {
+        // Portal views are drawn and submitted separately, before anything else, because
+        // they reuse the world space renderer's buffers.
+        if let Some(sr) = &mut self.space_renderers.world {
+            sr.draw_portal_views(
+                &self.device,
+                queue,
+                &self.fb,
+                &self.pipelines,
+                &self.cameras.cameras().world,
+            )?;
+        }
+
         let depth_texture_view = &self.fb.depth_texture_view;
         let mut encoder = self
             .device
@@ -587,9 +666,11 @@ impl EverythingRenderer {
                 } else {
                     wgpu::LoadOp::Load
                 },
+                true,
                 // We need to store the depth buffer if and only if we are going to do
                 // the lines pass.
                 self.lines_vertex_count > 0,
+                true,
             )?
         } else {
             SpaceDrawInfo::default()
@@ -622,7 +703,28 @@ impl EverythingRenderer {
             render_pass.draw(0..self.lines_vertex_count, 0..1);
         }
 
-        let lines_to_ui_time = Instant::now();
+        let lines_to_hand_time = Instant::now();
+        let hand_draw_info = if let Some(sr) = &self.space_renderers.hand {
+            sr.draw(
+                &self.fb,
+                queue,
+                &mut encoder,
+                &self.pipelines,
+                &self.cameras.cameras().hand,
+                if mem::take(&mut output_needs_clearing) {
+                    wgpu::LoadOp::Clear(to_wgpu_color(palette::NO_WORLD_TO_SHOW))
+                } else {
+                    wgpu::LoadOp::Load
+                },
+                false,
+                false, // nothing uses the hand depth buffer
+                false,
+            )?
+        } else {
+            SpaceDrawInfo::default()
+        };
+
+        let hand_to_ui_time = Instant::now();
         let ui_draw_info = if let Some(sr) = &self.space_renderers.ui {
             sr.draw(
                 &self.fb,
@@ -635,7 +737,9 @@ impl EverythingRenderer {
                 } else {
                     wgpu::LoadOp::Load
                 },
+                false,
                 false, // nothing uses the ui depth buffer
+                false,
             )?
         } else {
             SpaceDrawInfo::default()
@@ -648,6 +752,7 @@ impl EverythingRenderer {
             &self.postprocess_camera_buffer,
             0,
             bytemuck::bytes_of(&PostprocessUniforms::new(
+                &self.cameras.cameras().world.color_pipeline(),
                 self.cameras.graphics_options(),
                 !output_needs_clearing,
             )),
@@ -668,10 +773,12 @@ impl EverythingRenderer {
         Ok(DrawInfo {
             times: Layers {
                 world: world_to_lines_time.duration_since(start_draw_time),
-                ui: ui_to_postprocess_time.duration_since(lines_to_ui_time),
+                hand: hand_to_ui_time.duration_since(lines_to_hand_time),
+                ui: ui_to_postprocess_time.duration_since(hand_to_ui_time),
             },
             space_info: Layers {
                 world: world_draw_info,
+                hand: hand_draw_info,
                 ui: ui_draw_info,
             },
             // TODO: count bloom (call it postprocess) time separately from submit
@@ -685,17 +792,29 @@ impl EverythingRenderer {
         output: &wgpu::Texture,
         mut text: &str,
     ) {
+        let world_camera = &self.cameras.cameras().world;
+
         // Apply info text option
-        if !self.cameras.cameras().world.options().debug_info_text {
+        if !world_camera.options().debug_info_text {
             text = "";
         }
 
         let info_text_texture = &mut self.info_text_texture;
         // Update info text texture if there is text to draw or if there *was* text that we need to clear.
         if !text.is_empty() || info_text_texture.is_nonzero() {
+            // The texture is of the viewport's nominal size, not its framebuffer size.
+            let viewport = world_camera.viewport();
+            let scale = info_text_scale(
+                world_camera.options(),
+                viewport,
+                viewport.nominal_size.x as u32,
+            );
             info_text_texture.draw_target().clear_transparent();
             info_text_drawable(text, Rgb888::new(0, 0, 0))
-                .draw(info_text_texture.draw_target())
+                .draw(&mut InfoTextScale::new(
+                    info_text_texture.draw_target(),
+                    scale,
+                ))
                 .unwrap(); // TODO: use .into_ok() when stable
             info_text_texture.upload(queue);
         }
@@ -5,11 +5,12 @@ use std::sync::{Arc, Mutex, Weak};
 
 use instant::Instant;
 
-use all_is_cubes::cgmath::{Point3, Vector3};
-use all_is_cubes::math::GridAab;
+use all_is_cubes::camera::TextureFilteringOption;
+use all_is_cubes::cgmath::{Point3, Vector3, Vector4, Zero as _};
+use all_is_cubes::math::{GridAab, Rgb, Rgba};
 use all_is_cubes_mesh::{Texel, TextureAllocator, TextureCoordinate, TextureTile};
 
-use crate::in_wgpu::glue::{size_vector_to_extent, write_texture_by_aab};
+use crate::in_wgpu::glue::{size_vector_to_extent, write_texture_by_aab_at_level};
 use crate::in_wgpu::vertex::TexPoint;
 use crate::octree_alloc::{Alloctree, AlloctreeHandle};
 use crate::{BlockTextureInfo, GraphicsResourceError};
@@ -17,22 +18,72 @@ use crate::{BlockTextureInfo, GraphicsResourceError};
 /// Alias for the concrete type of the block texture.
 type BlockTexture = wgpu::Texture;
 
+/// log2 of the edge length of the atlas texture when first created.
+const INITIAL_SIZE_EXPONENT: u8 = 6;
+
+/// log2 of the largest edge length the atlas texture may grow to.
+///
+/// Texture coordinates are always expressed relative to this size, so that they stay
+/// valid when the atlas grows; the shader rescales them to the actual texture size.
+/// The shader function `block_texcoord()` must be kept consistent with this.
+const MAX_SIZE_EXPONENT: u8 = 8;
+
+/// Number of mipmap levels in the atlas textures, including the full-resolution one.
+const MIP_LEVEL_COUNT: u32 = 4;
+
+/// Minimum size of the atlas region reserved for each tile, so that every tile's
+/// region is aligned to the texels of every mip level, and downsampling a tile never
+/// mixes it with its neighbors.
+const MIP_ALIGNMENT: i32 = 1 << (MIP_LEVEL_COUNT - 1);
+
 /// Implementation of [`TextureAllocator`] for [`wgpu`].
 ///
 /// After any allocations, you must call [`AtlasAllocator::flush`] to write the
 /// updates to the actual GPU texture for drawing.
+///
+/// When allocation does not fit, the atlas is enlarged (up to a device-dependent limit),
+/// and the next [`AtlasAllocator::flush`] replaces the GPU textures with larger ones.
+/// Users of [`AtlasAllocator::views`] must then rebuild their bind groups.
 #[derive(Debug)]
 pub struct AtlasAllocator {
+    label_prefix: String,
+
     // GPU resources
-    texture: BlockTexture,
-    pub texture_view: wgpu::TextureView,
-    pub sampler: wgpu::Sampler,
+    /// Note on lock ordering: Acquire this lock only while holding the `backing` lock,
+    /// or no other lock.
+    gpu: Mutex<AtlasGpu>,
+    /// Samplers for each [`TextureFilteringOption`].
+    nearest_sampler: wgpu::Sampler,
+    mipmapped_sampler: wgpu::Sampler,
+    anisotropic_sampler: wgpu::Sampler,
 
     // CPU allocation tracking
     /// Note on lock ordering: Do not attempt to acquire this lock while a tile's lock is held.
     backing: Arc<Mutex<AllocatorBacking>>,
 }
 
+/// GPU textures of an [`AtlasAllocator`], which are replaced when the atlas grows.
+#[derive(Debug)]
+struct AtlasGpu {
+    texture: BlockTexture,
+    /// Texture with the same layout as `texture`, containing light emission
+    /// instead of reflectance.
+    emission_texture: BlockTexture,
+    /// Size of `texture` and `emission_texture`.
+    bounds: GridAab,
+    views: Arc<BlockTextureViews>,
+}
+
+/// The resources of an [`AtlasAllocator`] that are needed to bind it for drawing.
+///
+/// A new value is created whenever the atlas textures are replaced, so comparing
+/// with [`Arc::ptr_eq`] detects when bind groups need to be recreated.
+#[derive(Debug)]
+pub struct BlockTextureViews {
+    pub texture_view: wgpu::TextureView,
+    pub emission_texture_view: wgpu::TextureView,
+}
+
 /// Texture tile handle used by [`AtlasAllocator`].
 ///
 /// This is public out of necessity but should not generally need to be used.
@@ -41,13 +92,7 @@ pub struct AtlasTile {
     /// Translation of the requested grid to the actual region within the texture.
     /// (This is always integer but will always be used in a float computation.)
     offset: Vector3<TextureCoordinate>,
-    /// Scale factor to convert from texel grid coordinates to GPU texture coordinates
-    /// where 0.0 and 1.0 are the final size.
-    /// In other words, the reciprocal of the overall texture size. This does not
-    /// vary per-tile but is stored here for convenience of implementing [`TextureTile`].
-    scale: TextureCoordinate,
-    /// Actual storage and metadata about the tile; may be updated as needed by the
-    /// allocator to grow the texture.
+    /// Actual storage and metadata about the tile.
     ///
     /// Note on lock ordering: Do not attempt to acquire the allocator's lock while this
     /// lock is held.
@@ -56,9 +101,12 @@ pub struct AtlasTile {
 #[derive(Debug)]
 struct TileBacking {
     /// Allocator information, and the region of the atlas texture which this tile owns.
-    ///
-    /// Property: `self.handle.unwrap().allocation.volume() == self.data.len()`.
+    /// This may be larger than `region` to satisfy [`MIP_ALIGNMENT`].
     handle: Option<AlloctreeHandle>,
+    /// The region of the atlas texture which this tile's texels occupy.
+    ///
+    /// Property: `self.region.volume() == self.data.len()`.
+    region: GridAab,
     /// Texture data (that might not be sent to the GPU yet).
     data: Option<Box<[Texel]>>,
     /// Whether the data has changed so that we need to send it to the GPU on next
@@ -74,6 +122,9 @@ struct AllocatorBacking {
     /// Tracks which regions of the texture are free or allocated.
     alloctree: Alloctree,
 
+    /// Largest size exponent `alloctree` may be grown to, given the device's limits.
+    max_size_exponent: u8,
+
     /// Whether flush needs to do anything.
     dirty: bool,
 
@@ -85,79 +136,119 @@ struct AllocatorBacking {
 
 impl AtlasAllocator {
     pub fn new(label_prefix: &str, device: &wgpu::Device) -> Result<Self, GraphicsResourceError> {
-        // TODO: When we have reallocation implemented, be willing to use
-        // a smaller texture to start, to save GPU memory.
-        let alloctree = Alloctree::new(8);
+        let alloctree = Alloctree::new(INITIAL_SIZE_EXPONENT);
 
-        // TODO: How do we check for insufficient memory?
-        let texture = device.create_texture(&wgpu::TextureDescriptor {
-            size: size_vector_to_extent(alloctree.bounds().size()),
-            mip_level_count: 1,
-            sample_count: 1,
-            dimension: wgpu::TextureDimension::D3,
-            format: wgpu::TextureFormat::Rgba8UnormSrgb,
-            view_formats: &[],
-            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
-            label: Some(&format!("{label_prefix} block texture")),
-        });
+        let device_max_size = device.limits().max_texture_dimension_3d;
+        let max_size_exponent = (INITIAL_SIZE_EXPONENT..=MAX_SIZE_EXPONENT)
+            .rev()
+            .find(|&exponent| 1u32 << exponent <= device_max_size)
+            .unwrap_or(INITIAL_SIZE_EXPONENT);
 
-        // TODO: schedule this write lazily
-        // // Fill texture with a marker color, so it isn't transparent.
-        // // (If we didn't, wgpu would leave it as [0, 0, 0, 0].)
-        // // This is mostly useful for debugging since the texture allocation
-        // // procedure should never actually let unwritten texels appear.
-        // write_texture_by_aab(
-        //     queue,
-        //     &texture,
-        //     alloctree.bounds(),
-        //     &vec![palette::UNPAINTED_TEXTURE_FALLBACK.to_srgb8(); alloctree.bounds().volume()],
-        // );
-
-        let texture_view = texture.create_view(&wgpu::TextureViewDescriptor::default());
-
-        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
-            label: Some(&format!("{label_prefix} block sampler")),
-            address_mode_u: wgpu::AddressMode::ClampToEdge,
-            address_mode_v: wgpu::AddressMode::ClampToEdge,
-            address_mode_w: wgpu::AddressMode::ClampToEdge,
-            mag_filter: wgpu::FilterMode::Nearest,
-            min_filter: wgpu::FilterMode::Nearest,
-            mipmap_filter: wgpu::FilterMode::Nearest,
-            ..Default::default()
-        });
+        let create_sampler = |name: &str, descriptor: wgpu::SamplerDescriptor<'_>| {
+            device.create_sampler(&wgpu::SamplerDescriptor {
+                label: Some(&format!("{label_prefix} block sampler {name}")),
+                address_mode_u: wgpu::AddressMode::ClampToEdge,
+                address_mode_v: wgpu::AddressMode::ClampToEdge,
+                address_mode_w: wgpu::AddressMode::ClampToEdge,
+                ..descriptor
+            })
+        };
+        let nearest_sampler = create_sampler(
+            "nearest",
+            wgpu::SamplerDescriptor {
+                mag_filter: wgpu::FilterMode::Nearest,
+                min_filter: wgpu::FilterMode::Nearest,
+                mipmap_filter: wgpu::FilterMode::Nearest,
+                lod_max_clamp: 0.0,
+                ..Default::default()
+            },
+        );
+        let mipmapped_sampler = create_sampler(
+            "mipmapped",
+            wgpu::SamplerDescriptor {
+                // Within a mip level, use nearest sampling, so that voxels stay sharp and
+                // the shader's clamping to the tile keeps us from sampling other tiles.
+                mag_filter: wgpu::FilterMode::Nearest,
+                min_filter: wgpu::FilterMode::Nearest,
+                mipmap_filter: wgpu::FilterMode::Linear,
+                ..Default::default()
+            },
+        );
+        let anisotropic_sampler = create_sampler(
+            "anisotropic",
+            wgpu::SamplerDescriptor {
+                // Anisotropic filtering requires linear filtering.
+                // TODO: At reduced mip levels, linear filtering can pick up texels from
+                // beyond the tile's edge, since the clamp rectangle is only inset by half
+                // a full-resolution texel.
+                mag_filter: wgpu::FilterMode::Linear,
+                min_filter: wgpu::FilterMode::Linear,
+                mipmap_filter: wgpu::FilterMode::Linear,
+                anisotropy_clamp: 16,
+                ..Default::default()
+            },
+        );
 
         Ok(Self {
-            texture,
-            texture_view,
-            sampler,
+            gpu: Mutex::new(AtlasGpu::new(label_prefix, device, alloctree.bounds())),
+            nearest_sampler,
+            mipmapped_sampler,
+            anisotropic_sampler,
+            label_prefix: label_prefix.to_owned(),
             backing: Arc::new(Mutex::new(AllocatorBacking {
                 alloctree,
+                max_size_exponent,
                 dirty: false,
                 in_use: Vec::new(),
             })),
         })
     }
 
+    /// Returns the texture views to bind for drawing with the textures of this atlas.
+    ///
+    /// The result is replaced by [`AtlasAllocator::flush`] when the atlas grows.
+    pub fn views(&self) -> Arc<BlockTextureViews> {
+        self.gpu.lock().unwrap().views.clone()
+    }
+
+    /// Returns the sampler to bind for drawing with the given filtering option.
+    pub fn sampler(&self, filtering: &TextureFilteringOption) -> &wgpu::Sampler {
+        match filtering {
+            TextureFilteringOption::Nearest => &self.nearest_sampler,
+            TextureFilteringOption::Mipmapped => &self.mipmapped_sampler,
+            TextureFilteringOption::Anisotropic => &self.anisotropic_sampler,
+            _ => &self.nearest_sampler,
+        }
+    }
+
     /// Copy the texels of all modified and still-referenced tiles to the GPU's texture.
-    pub fn flush(&self, queue: &wgpu::Queue) -> BlockTextureInfo {
+    ///
+    /// If the atlas has grown since the last flush, this replaces the GPU textures
+    /// with larger ones and writes every tile into them.
+    pub fn flush(&self, device: &wgpu::Device, queue: &wgpu::Queue) -> BlockTextureInfo {
         let start_time = Instant::now();
         let mut allocator_backing = self.backing.lock().unwrap();
+        let mut gpu = self.gpu.lock().unwrap();
+
+        let resized = gpu.bounds != allocator_backing.alloctree.bounds();
+        if resized {
+            *gpu = AtlasGpu::new(
+                &self.label_prefix,
+                device,
+                allocator_backing.alloctree.bounds(),
+            );
+        }
 
         let mut count_written = 0;
-        if allocator_backing.dirty {
+        if allocator_backing.dirty || resized {
             allocator_backing.in_use.retain(|weak_backing| {
                 // Process the non-dropped weak references
                 weak_backing.upgrade().map_or(false, |strong_backing| {
                     let backing: &mut TileBacking = &mut strong_backing.lock().unwrap();
-                    if backing.dirty {
+                    // If the textures were replaced, every tile must be written again.
+                    if backing.dirty || resized {
                         if let Some(data) = backing.data.as_ref() {
-                            let region: GridAab = backing
-                                .handle
-                                .as_ref()
-                                .expect("can't happen: dead TileBacking")
-                                .allocation;
-
-                            write_texture_by_aab(queue, &self.texture, region, data);
+                            gpu.write_tile(queue, backing.region, data);
                             backing.dirty = false;
                             count_written += 1;
                         }
@@ -170,6 +261,7 @@ impl AtlasAllocator {
         allocator_backing.dirty = false;
         BlockTextureInfo {
             flushed: count_written,
+            resized,
             flush_time: Instant::now().duration_since(start_time),
             in_use_tiles: allocator_backing.in_use.len(),
             in_use_texels: allocator_backing.alloctree.occupied_volume(),
@@ -184,11 +276,30 @@ impl TextureAllocator for AtlasAllocator {
 
     fn allocate(&self, requested_bounds: GridAab) -> Option<AtlasTile> {
         let mut allocator_backing = self.backing.lock().unwrap();
-        let handle = allocator_backing.alloctree.allocate(requested_bounds)?;
+        // Reserve at least MIP_ALIGNMENT texels on each axis, so that the tile is
+        // aligned to the texels of every mip level.
+        let reserved_bounds = GridAab::from_lower_size(
+            requested_bounds.lower_bounds(),
+            requested_bounds.size().map(|s| s.max(MIP_ALIGNMENT)),
+        );
+        let handle = loop {
+            if let Some(handle) = allocator_backing.alloctree.allocate(reserved_bounds) {
+                break handle;
+            }
+            // Out of space; enlarge the atlas if we can.
+            // (Growing does not help requests larger than the largest size, but
+            // such requests should not be made; the mesher only allocates per-block tiles.)
+            let size_exponent = allocator_backing.alloctree.size_exponent();
+            if size_exponent >= allocator_backing.max_size_exponent {
+                return None;
+            }
+            allocator_backing.alloctree.grow_to(size_exponent + 1);
+            allocator_backing.dirty = true;
+        };
         let result = AtlasTile {
             offset: handle.offset.map(|c| c as TextureCoordinate),
-            scale: (allocator_backing.alloctree.bounds().size().x as TextureCoordinate).recip(),
             backing: Arc::new(Mutex::new(TileBacking {
+                region: requested_bounds.translate(handle.offset),
                 handle: Some(handle),
                 data: None,
                 dirty: false,
@@ -210,7 +321,9 @@ impl TextureTile for AtlasTile {
     }
 
     fn grid_to_texcoord(&self, in_tile_grid: Point3<TextureCoordinate>) -> TexPoint {
-        (in_tile_grid + self.offset) * self.scale
+        // Scale relative to the maximum size, not the current size, so that these
+        // coordinates remain valid if the atlas grows.
+        (in_tile_grid + self.offset) / TextureCoordinate::from(1u16 << MAX_SIZE_EXPONENT)
     }
 
     fn write(&mut self, data: &[Texel]) {
@@ -239,6 +352,120 @@ impl TextureTile for AtlasTile {
     }
 }
 
+impl AtlasGpu {
+    fn new(label_prefix: &str, device: &wgpu::Device, bounds: GridAab) -> Self {
+        // TODO: How do we check for insufficient memory?
+        let create_texture = |label: &str| {
+            device.create_texture(&wgpu::TextureDescriptor {
+                size: size_vector_to_extent(bounds.size()),
+                mip_level_count: MIP_LEVEL_COUNT,
+                sample_count: 1,
+                dimension: wgpu::TextureDimension::D3,
+                format: wgpu::TextureFormat::Rgba8UnormSrgb,
+                view_formats: &[],
+                usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+                label: Some(label),
+            })
+        };
+        let texture = create_texture(&format!("{label_prefix} block texture"));
+        let emission_texture = create_texture(&format!("{label_prefix} block emission texture"));
+
+        let views = Arc::new(BlockTextureViews {
+            texture_view: texture.create_view(&wgpu::TextureViewDescriptor::default()),
+            emission_texture_view: emission_texture
+                .create_view(&wgpu::TextureViewDescriptor::default()),
+        });
+
+        Self {
+            texture,
+            emission_texture,
+            bounds,
+            views,
+        }
+    }
+
+    /// Write the texels of a tile, and its downsampled versions, to the textures.
+    fn write_tile(&self, queue: &wgpu::Queue, region: GridAab, data: &[Texel]) {
+        let mut region = region;
+        let mut level_data: Box<[Texel]> = data.into();
+        for mip_level in 0..MIP_LEVEL_COUNT {
+            if mip_level > 0 {
+                (region, level_data) = downsample(region, &level_data);
+            }
+
+            let (colors, emissions): (Vec<[u8; 4]>, Vec<[u8; 4]>) = level_data
+                .iter()
+                .map(|texel: &Texel| {
+                    let [r, g, b] = texel.emission;
+                    (texel.color, [r, g, b, 255])
+                })
+                .unzip();
+            write_texture_by_aab_at_level(queue, &self.texture, mip_level, region, &colors);
+            write_texture_by_aab_at_level(
+                queue,
+                &self.emission_texture,
+                mip_level,
+                region,
+                &emissions,
+            );
+        }
+    }
+}
+
+/// Computes the next smaller mip level of a tile: each texel of the result is the
+/// average of the (up to) 2×2×2 texels of `data` it covers.
+///
+/// `region`'s lower bounds must be even (which [`MIP_ALIGNMENT`] ensures); its upper
+/// bounds are rounded up, so the result never extends past the tile's reserved region.
+fn downsample(region: GridAab, data: &[Texel]) -> (GridAab, Box<[Texel]>) {
+    let lower = region.lower_bounds();
+    let upper = region.upper_bounds();
+    debug_assert!(
+        lower.x.rem_euclid(2) == 0 && lower.y.rem_euclid(2) == 0 && lower.z.rem_euclid(2) == 0,
+        "unaligned tile region {region:?}"
+    );
+    let new_region =
+        GridAab::from_lower_upper(lower.map(|c| c / 2), upper.map(|c| (c + 1).div_euclid(2)));
+
+    let new_data = new_region
+        .interior_iter()
+        .map(|new_cube| {
+            let source_box = GridAab::from_lower_size(new_cube * 2, [2, 2, 2])
+                .intersection(region)
+                .expect("can't happen: downsampled region outside source");
+
+            // Average colors weighted by alpha, so transparent texels' colors don't
+            // darken the result.
+            let mut premultiplied_sum = Vector4::<f32>::zero();
+            let mut emission_sum = Vector3::<f32>::zero();
+            for cube in source_box.interior_iter() {
+                let texel = data[region.index(cube).unwrap()];
+                let color = Vector4::from(Rgba::from_srgb8(texel.color));
+                premultiplied_sum += color.truncate().extend(1.0) * color.w;
+                emission_sum += Vector3::from(Rgb::from_srgb8(texel.emission));
+            }
+            let count = source_box.volume() as f32;
+            let alpha = premultiplied_sum.w / count;
+            let color = if premultiplied_sum.w > 0.0 {
+                premultiplied_sum.truncate() / premultiplied_sum.w
+            } else {
+                Vector3::zero()
+            };
+            let emission = emission_sum / count;
+            let [er, eg, eb, _] = Rgb::new(emission.x, emission.y, emission.z)
+                .with_alpha_one()
+                .to_srgb8();
+
+            Texel {
+                color: Rgba::new(color.x, color.y, color.z, alpha).to_srgb8(),
+                emission: [er, eg, eb],
+            }
+        })
+        .collect();
+
+    (new_region, new_data)
+}
+
 /// Compared by reference. This definition of equality is cheaper and non-panicking
 /// vs. the derived behavior of [`RefCell::eq`] which is to borrow and compare the contents.
 impl PartialEq for AtlasTile {
@@ -257,3 +484,36 @@ impl Drop for TileBacking {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn texel(color: [u8; 4]) -> Texel {
+        Texel {
+            color,
+            emission: [0, 0, 0],
+        }
+    }
+
+    #[test]
+    fn downsample_weights_by_alpha() {
+        let (region, data) = downsample(
+            GridAab::from_lower_size([0, 0, 0], [2, 1, 1]),
+            &[texel([255, 0, 0, 255]), texel([0, 0, 0, 0])],
+        );
+        assert_eq!(region, GridAab::from_lower_size([0, 0, 0], [1, 1, 1]));
+        assert_eq!(&*data, &[texel([255, 0, 0, 128])]);
+    }
+
+    /// Odd sizes round up, and the partial texels average only what they cover.
+    #[test]
+    fn downsample_odd_size() {
+        let (region, data) = downsample(
+            GridAab::from_lower_size([8, 0, 0], [3, 1, 1]),
+            &[texel([255; 4]), texel([255; 4]), texel([0, 0, 255, 255])],
+        );
+        assert_eq!(region, GridAab::from_lower_size([4, 0, 0], [2, 1, 1]));
+        assert_eq!(&*data, &[texel([255; 4]), texel([0, 0, 255, 255])]);
+    }
+}
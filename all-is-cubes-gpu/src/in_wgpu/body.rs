@@ -0,0 +1,175 @@
+//! Drawing characters' avatars: the character's own when it is visible in
+//! [`CameraMode::ThirdPerson`], and those of remote characters.
+
+use std::collections::HashMap;
+
+use all_is_cubes::block::Block;
+use all_is_cubes::camera::Camera;
+use all_is_cubes::cgmath::{EuclideanSpace as _, InnerSpace as _, Point3, Transform as _, Vector3};
+use all_is_cubes::character::PosedPart;
+use all_is_cubes::math::{Face6, FreeCoordinate, GridCoordinate, GridVector};
+use all_is_cubes_mesh::{BlockMesh, BlockVertex, GfxVertex as _, MeshOptions};
+
+use crate::in_wgpu::block_texture::{AtlasAllocator, AtlasTile};
+use crate::in_wgpu::glue::{BeltWritingParts, ResizingBuffer};
+use crate::in_wgpu::vertex::{TexPoint, WgpuBlockVertex, WgpuInstanceData};
+
+#[cfg(doc)]
+use all_is_cubes::camera::CameraMode;
+
+/// Mesh of the parts of characters' [`Avatar`]s, drawn using the same pipeline and
+/// lighting as the blocks of the space it is in.
+///
+/// Each part's block is triangulated once and cached; every frame, the vertices are
+/// transformed into the part's pose.
+///
+/// [`Avatar`]: all_is_cubes::character::Avatar
+#[derive(Debug, Default)]
+pub(crate) struct BodyMesh {
+    /// Meshes of the blocks of the parts drawn most recently.
+    ///
+    /// TODO: These are not updated if a block's definition changes.
+    block_meshes: HashMap<Block, BlockMesh<BlockVertex<TexPoint>, AtlasTile>>,
+    /// Options `block_meshes` were computed with.
+    mesh_options: Option<MeshOptions>,
+
+    vertex_buffer: ResizingBuffer,
+    index_buffer: ResizingBuffer,
+    index_count: u32,
+    /// Translation to apply to the vertices, or [`None`] if there is nothing to draw.
+    translation: Option<GridVector>,
+}
+
+impl BodyMesh {
+    /// Replace the mesh with one of the given posed parts, or nothing if `parts` is empty.
+    ///
+    /// Textures are allocated from `block_texture` but not flushed.
+    pub(crate) fn update(
+        &mut self,
+        parts: &[PosedPart],
+        camera: &Camera,
+        block_texture: &AtlasAllocator,
+        mut bwp: BeltWritingParts<'_, '_>,
+    ) {
+        if parts.is_empty() {
+            self.translation = None;
+            return;
+        }
+
+        let mesh_options = MeshOptions::new(camera.options());
+        if self.mesh_options.as_ref() != Some(&mesh_options) {
+            self.block_meshes.clear();
+            self.mesh_options = Some(mesh_options.clone());
+        }
+        self.block_meshes
+            .retain(|block, _| parts.iter().any(|part| part.block == *block));
+
+        // Transform the vertices of every part into the space, keeping their normals
+        // axis-aligned as the vertex format requires.
+        let mut posed_vertices: Vec<BlockVertex<TexPoint>> = Vec::new();
+        let mut indices: Vec<u32> = Vec::new();
+        for part in parts {
+            let block_mesh = self
+                .block_meshes
+                .entry(part.block.clone())
+                .or_insert_with(|| match part.block.evaluate() {
+                    Ok(evaluated) => BlockMesh::new(&evaluated, block_texture, &mesh_options),
+                    Err(e) => {
+                        log::error!("Failed to evaluate avatar block: {e}");
+                        BlockMesh::default()
+                    }
+                });
+            block_mesh.extend_opaque(&mut posed_vertices, &mut indices, |vertex| BlockVertex {
+                position: part.transform.transform_point(vertex.position),
+                face: nearest_face(part.transform.transform_vector(vertex.face.normal_vector())),
+                coloring: vertex.coloring.clone(),
+            });
+        }
+        if indices.is_empty() {
+            self.translation = None;
+            return;
+        }
+
+        // The vertex format can only express nonnegative positions relative to the
+        // instance translation, so translate by the lowest corner of all the vertices.
+        let lowest = posed_vertices.iter().fold(
+            Point3::new(
+                FreeCoordinate::INFINITY,
+                FreeCoordinate::INFINITY,
+                FreeCoordinate::INFINITY,
+            ),
+            |lowest, vertex| lowest.zip(vertex.position, FreeCoordinate::min),
+        );
+        let translation: GridVector = lowest.to_vec().map(|c| c.floor() as GridCoordinate);
+        let vertices: Vec<WgpuBlockVertex> = posed_vertices
+            .into_iter()
+            .map(|mut vertex| {
+                vertex.position -= translation.map(FreeCoordinate::from);
+                // Assign each vertex to the cube just behind its surface, since the
+                // shader looks up flat lighting in the cube next to that one in the
+                // direction of the face normal.
+                let cube = (vertex.position - vertex.face.normal_vector() * (1. / 512.))
+                    .map(|c| c.floor().max(0.) as GridCoordinate);
+                vertex.position -= cube.to_vec().map(FreeCoordinate::from);
+                let mut vertex = WgpuBlockVertex::from(vertex);
+                vertex.instantiate_vertex(WgpuBlockVertex::instantiate_block(cube));
+                vertex
+            })
+            .collect();
+
+        self.vertex_buffer.write_with_resizing(
+            bwp.reborrow(),
+            &wgpu::util::BufferInitDescriptor {
+                label: Some("BodyMesh::vertex_buffer"),
+                contents: bytemuck::cast_slice::<WgpuBlockVertex, u8>(&vertices),
+                usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+            },
+        );
+        self.index_buffer.write_with_resizing(
+            bwp,
+            &wgpu::util::BufferInitDescriptor {
+                label: Some("BodyMesh::index_buffer"),
+                contents: bytemuck::cast_slice::<u32, u8>(&indices),
+                usage: wgpu::BufferUsages::INDEX | wgpu::BufferUsages::COPY_DST,
+            },
+        );
+        self.index_count = u32::try_from(indices.len()).expect("too many indices");
+        self.translation = Some(translation);
+    }
+
+    /// Draws the avatars, if there are any, and returns the number of squares drawn.
+    ///
+    /// The render pass must already have the opaque block pipeline, the space's bind
+    /// groups, and the instance buffer that `instance_data` will be written to set.
+    pub(crate) fn draw<'pass>(
+        &'pass self,
+        render_pass: &mut wgpu::RenderPass<'pass>,
+        instance_data: &mut Vec<WgpuInstanceData>,
+    ) -> usize {
+        let (Some(translation), Some(vertex_buffer), Some(index_buffer)) = (
+            self.translation,
+            self.vertex_buffer.get(),
+            self.index_buffer.get(),
+        ) else {
+            return 0;
+        };
+        let id = u32::try_from(instance_data.len()).unwrap();
+        instance_data.push(WgpuInstanceData::new(translation));
+        render_pass.set_vertex_buffer(0, vertex_buffer.slice(..));
+        render_pass.set_index_buffer(index_buffer.slice(..), wgpu::IndexFormat::Uint32);
+        render_pass.draw_indexed(0..self.index_count, 0, id..(id + 1));
+        self.index_count as usize / 6
+    }
+}
+
+/// Returns the face whose normal is closest in direction to `vector`.
+fn nearest_face(vector: Vector3<FreeCoordinate>) -> Face6 {
+    Face6::ALL
+        .into_iter()
+        .max_by(|a, b| {
+            a.normal_vector()
+                .dot(vector)
+                .total_cmp(&b.normal_vector().dot(vector))
+        })
+        .unwrap()
+}
@@ -1,6 +1,7 @@
 use all_is_cubes::camera::{Camera, FogOption, LightingOption};
 use all_is_cubes::cgmath::{EuclideanSpace, Matrix4, Vector3};
-use all_is_cubes::math::Rgb;
+use all_is_cubes::math::{FreeCoordinate, Rgb};
+use all_is_cubes::space::Weather;
 
 use crate::in_wgpu::glue::PaddedVec3;
 
@@ -41,9 +42,23 @@ pub(crate) struct ShaderSpaceCamera {
 }
 
 impl ShaderSpaceCamera {
-    pub fn new(camera: &Camera, sky_color: Rgb, light_lookup_offset: Vector3<i32>) -> Self {
+    /// `ready_distance` is the distance within which the chunks to be drawn are ready
+    /// (see [`ChunkedSpaceMesh::ready_distance()`]); fog is drawn no farther than that,
+    /// so that chunks not yet ready are hidden.
+    ///
+    /// `weather` brings the fog closer if it is foggy, unless fog is disabled.
+    ///
+    /// [`ChunkedSpaceMesh::ready_distance()`]: all_is_cubes_mesh::chunked_mesh::ChunkedSpaceMesh::ready_distance
+    pub fn new(
+        camera: &Camera,
+        sky_color: Rgb,
+        weather: &Weather,
+        light_lookup_offset: Vector3<i32>,
+        ready_distance: FreeCoordinate,
+    ) -> Self {
         let options = camera.options();
-        let view_distance = camera.view_distance() as f32;
+        let view_distance =
+            camera.view_distance().min(ready_distance) as f32 * weather.fog_distance_scale();
         let (fog_mode_blend, fog_distance) = match options.fog {
             FogOption::Abrupt => (1.0, view_distance),
             FogOption::Compromise => (0.5, view_distance),
@@ -340,6 +340,12 @@ impl FramebufferTextures {
         self.config.flaws
     }
 
+    /// Returns the adapter features this was created with, for creating other
+    /// [`FramebufferTextures`] compatible with the same pipelines.
+    pub(crate) fn features(&self) -> FbtFeatures {
+        self.config.features
+    }
+
     pub(crate) fn bloom_data_texture(&self) -> &wgpu::TextureView {
         &self
             .bloom
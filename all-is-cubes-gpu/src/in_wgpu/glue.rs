@@ -50,8 +50,10 @@ impl From<Vector3<f32>> for PaddedVec3 {
     }
 }
 
+/// Converts a linear color to a [`wgpu::Color`], which is also linear: it is encoded
+/// according to the format of the texture it is written to, so this is correct for both
+/// linear and `*Srgb` formats.
 pub fn to_wgpu_color(color: Rgba) -> wgpu::Color {
-    // TODO: Check whether this is gamma-correct
     wgpu::Color {
         // convert from NotNan<f32> to f64
         r: color.red().into_inner().into(),
@@ -94,6 +96,18 @@ pub fn write_texture_by_aab<T: Pod>(
     texture: &wgpu::Texture,
     region: GridAab,
     data: &[T],
+) {
+    write_texture_by_aab_at_level(queue, texture, 0, region, data)
+}
+
+/// As [`write_texture_by_aab`], but writes to the given mip level, in whose texel
+/// coordinates `region` is expressed.
+pub fn write_texture_by_aab_at_level<T: Pod>(
+    queue: &wgpu::Queue,
+    texture: &wgpu::Texture,
+    mip_level: u32,
+    region: GridAab,
+    data: &[T],
 ) {
     let volume = region.volume();
     assert_eq!(
@@ -105,7 +119,7 @@ pub fn write_texture_by_aab<T: Pod>(
     queue.write_texture(
         wgpu::ImageCopyTexture {
             texture,
-            mip_level: 0,
+            mip_level,
             origin: point_to_origin(region.lower_bounds()),
             aspect: wgpu::TextureAspect::All,
         },
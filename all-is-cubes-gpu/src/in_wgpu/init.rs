@@ -6,6 +6,9 @@
 /// Create a [`wgpu::Instance`] and [`wgpu::Adapter`] controlled by environment variables,
 /// and print information about the decision made.
 ///
+/// If no adapter is specified and none is found automatically, this falls back to a
+/// software adapter if one is available, so that tests can run without a GPU.
+///
 /// `log` receives whole lines with no trailing newlines, as suitable for logging or
 /// printing using [`println!()`].
 #[doc(hidden)]
@@ -50,6 +53,21 @@ pub async fn create_instance_and_adapter_for_test(
             })
             .await;
     }
+    if adapter.is_none() {
+        // There may be no hardware GPU (e.g. in a container or virtual machine), but
+        // there may still be a software implementation such as llvmpipe or lavapipe
+        // (which can also be chosen explicitly with WGPU_BACKEND and WGPU_ADAPTER_NAME).
+        log(format_args!(
+            "No adapter found automatically; trying fallback (software) adapter."
+        ));
+        adapter = instance
+            .request_adapter(&wgpu::RequestAdapterOptions {
+                power_preference: wgpu::PowerPreference::LowPower,
+                compatible_surface: None,
+                force_fallback_adapter: true,
+            })
+            .await;
+    }
 
     if let Some(adapter) = &adapter {
         log(format_args!(
@@ -11,6 +11,7 @@ use crate::in_wgpu::glue::create_wgsl_module_from_reloadable;
 use crate::in_wgpu::vertex::WgpuBlockVertex;
 use crate::in_wgpu::vertex::WgpuInstanceData;
 use crate::in_wgpu::vertex::WgpuLinesVertex;
+use crate::in_wgpu::vertex::WgpuPortalVertex;
 use crate::reloadable::{reloadable_str, Reloadable};
 
 /// Resources needed for rendering that aren't actually specific to any content and so
@@ -49,6 +50,18 @@ pub(crate) struct Pipelines {
 
     /// Pipeline for drawing transparent (alpha ≠ 1) blocks.
     pub(crate) transparent_render_pipeline: wgpu::RenderPipeline,
+
+    /// Bind group layout for the texture and sampler of a portal's view.
+    pub(crate) portal_bind_group_layout: wgpu::BindGroupLayout,
+
+    /// Pipeline for drawing the faces of portal blocks.
+    pub(crate) portal_render_pipeline: wgpu::RenderPipeline,
+
+    /// Bind group layout for the cube map texture and sampler of a space's sky.
+    pub(crate) sky_bind_group_layout: wgpu::BindGroupLayout,
+
+    /// Pipeline for drawing the sky behind all blocks.
+    pub(crate) sky_render_pipeline: wgpu::RenderPipeline,
 }
 
 /// Shader code for rendering `Space` content, and debug lines.
@@ -57,6 +70,10 @@ pub(crate) struct Pipelines {
 pub(crate) static BLOCKS_AND_LINES_SHADER: Lazy<Reloadable> =
     Lazy::new(|| reloadable_str!("src/in_wgpu/shaders/blocks-and-lines.wgsl"));
 
+/// Shader code for rendering the sky of a `Space`.
+pub(crate) static SKY_SHADER: Lazy<Reloadable> =
+    Lazy::new(|| reloadable_str!("src/in_wgpu/shaders/sky.wgsl"));
+
 impl Pipelines {
     /// * `device` is used to create pipelines.
     /// * `fb` is used to determine what texture formats these pipelines must be compatible
@@ -108,6 +125,16 @@ impl Pipelines {
                         },
                         count: None,
                     },
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 3,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Texture {
+                            multisampled: false,
+                            view_dimension: wgpu::TextureViewDimension::D3,
+                            sample_type: wgpu::TextureSampleType::Float { filterable: false },
+                        },
+                        count: None,
+                    },
                 ],
                 label: Some("Pipelines::space_texture_bind_group_layout"),
             });
@@ -190,7 +217,10 @@ impl Pipelines {
                 fragment: Some(wgpu::FragmentState {
                     module: &shader,
                     entry_point: match current_graphics_options.transparency {
-                        TransparencyOption::Volumetric => "block_fragment_transparent_volumetric",
+                        // TODO: Tinted should use a shader which absorbs per color channel
+                        TransparencyOption::Volumetric | TransparencyOption::Tinted => {
+                            "block_fragment_transparent_volumetric"
+                        }
                         TransparencyOption::Surface | TransparencyOption::Threshold(_) => {
                             "block_fragment_transparent_surface"
                         }
@@ -268,8 +298,144 @@ impl Pipelines {
                 multiview: None,
             });
 
+        let portal_bind_group_layout =
+            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                entries: &[
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 0,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Texture {
+                            multisampled: false,
+                            view_dimension: wgpu::TextureViewDimension::D2,
+                            sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        },
+                        count: None,
+                    },
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 1,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                        count: None,
+                    },
+                ],
+                label: Some("Pipelines::portal_bind_group_layout"),
+            });
+
+        // Portals are drawn in the same render pass as blocks, so the layout includes
+        // the space texture group even though the portal shader does not use it.
+        let portal_render_pipeline_layout =
+            device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                label: Some("Pipelines::portal_render_pipeline_layout"),
+                bind_group_layouts: &[
+                    &camera_bind_group_layout,
+                    &space_texture_bind_group_layout,
+                    &portal_bind_group_layout,
+                ],
+                push_constant_ranges: &[],
+            });
+
+        let portal_render_pipeline =
+            device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+                label: Some("Pipelines::portal_render_pipeline"),
+                layout: Some(&portal_render_pipeline_layout),
+                vertex: wgpu::VertexState {
+                    module: &shader,
+                    entry_point: "portal_vertex",
+                    buffers: &[WgpuPortalVertex::desc()],
+                },
+                fragment: Some(wgpu::FragmentState {
+                    module: &shader,
+                    entry_point: "portal_fragment",
+                    targets: &[Some(wgpu::ColorTargetState {
+                        format: fb.linear_scene_texture_format(),
+                        blend: None,
+                        write_mask: wgpu::ColorWrites::ALL,
+                    })],
+                }),
+                primitive: block_primitive_state,
+                depth_stencil: Some(wgpu::DepthStencilState {
+                    format: FramebufferTextures::DEPTH_FORMAT,
+                    depth_write_enabled: true,
+                    depth_compare: wgpu::CompareFunction::Less,
+                    stencil: wgpu::StencilState::default(),
+                    bias: wgpu::DepthBiasState::default(),
+                }),
+                multisample,
+                multiview: None,
+            });
+
+        let sky_shader = create_wgsl_module_from_reloadable(device, "sky", &SKY_SHADER);
+
+        let sky_bind_group_layout =
+            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                entries: &[
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 0,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Texture {
+                            multisampled: false,
+                            view_dimension: wgpu::TextureViewDimension::Cube,
+                            sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        },
+                        count: None,
+                    },
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 1,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                        count: None,
+                    },
+                ],
+                label: Some("Pipelines::sky_bind_group_layout"),
+            });
+
+        // Like portals, the sky is drawn in the same render pass as blocks, so the layout
+        // includes the space texture group even though the sky shader does not use it.
+        let sky_render_pipeline_layout =
+            device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                label: Some("Pipelines::sky_render_pipeline_layout"),
+                bind_group_layouts: &[
+                    &camera_bind_group_layout,
+                    &space_texture_bind_group_layout,
+                    &sky_bind_group_layout,
+                ],
+                push_constant_ranges: &[],
+            });
+
+        let sky_render_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Pipelines::sky_render_pipeline"),
+            layout: Some(&sky_render_pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &sky_shader,
+                entry_point: "sky_vertex",
+                buffers: &[],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &sky_shader,
+                entry_point: "sky_fragment",
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: fb.linear_scene_texture_format(),
+                    blend: None,
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: Some(wgpu::DepthStencilState {
+                format: FramebufferTextures::DEPTH_FORMAT,
+                // The sky is drawn at the far plane, after opaque geometry, so that it
+                // covers exactly the pixels that nothing else has been drawn to.
+                depth_write_enabled: false,
+                depth_compare: wgpu::CompareFunction::LessEqual,
+                stencil: wgpu::StencilState::default(),
+                bias: wgpu::DepthBiasState::default(),
+            }),
+            multisample,
+            multiview: None,
+        });
+
         let dirty = DirtyFlag::new(false);
         BLOCKS_AND_LINES_SHADER.as_source().listen(dirty.listener());
+        SKY_SHADER.as_source().listen(dirty.listener());
         graphics_options.listen(dirty.listener());
 
         Self {
@@ -282,6 +448,12 @@ impl Pipelines {
             transparent_render_pipeline,
 
             lines_render_pipeline,
+
+            portal_bind_group_layout,
+            portal_render_pipeline,
+
+            sky_bind_group_layout,
+            sky_render_pipeline,
         }
     }
 
@@ -0,0 +1,419 @@
+//! Drawing blocks with [`BlockAttributes::portal`], whose faces show the view from
+//! another location in the same space.
+//!
+//! Each distinct [`Portal`] near the camera gets a small offscreen framebuffer, which
+//! [`SpaceRenderer`] redraws every few frames from the portal's viewpoint; the faces of
+//! the portal blocks are then drawn as quads textured with that view, in front of the
+//! blocks' own surfaces.
+
+use std::collections::{HashMap, HashSet};
+use std::ops::Range;
+
+use all_is_cubes::block::Portal;
+use all_is_cubes::camera::{Camera, Viewport};
+use all_is_cubes::cgmath::{InnerSpace as _, Point2, Vector2, Vector3};
+use all_is_cubes::math::{Face6, FreeCoordinate, GridPoint};
+use all_is_cubes::space::{BlockIndex, Space};
+
+use crate::in_wgpu::frame_texture::{FbtFeatures, FramebufferTextures};
+use crate::in_wgpu::glue::{BeltWritingParts, ResizingBuffer};
+use crate::in_wgpu::pipelines::Pipelines;
+use crate::in_wgpu::vertex::WgpuPortalVertex;
+
+#[cfg(doc)]
+use {crate::in_wgpu::space::SpaceRenderer, all_is_cubes::block::BlockAttributes};
+
+/// Width and height, in pixels, of each portal's view.
+const VIEW_SIZE: u32 = 128;
+
+/// Maximum number of portal views to maintain. Portal blocks whose portals are not
+/// among the nearest this many are drawn as ordinary blocks.
+const MAX_VIEWS: usize = 4;
+
+/// Number of frames between redraws of each portal's view.
+///
+/// Only one view is drawn per frame, so with more than this many views, each is
+/// redrawn less often.
+const UPDATE_PERIOD: u32 = 4;
+
+/// Distance outside the portal block's faces at which the view is drawn, so that it is
+/// not hidden by the block's own surface.
+const SURFACE_OFFSET: FreeCoordinate = 1. / 256.;
+
+/// Changes to a [`Space`] which may affect what portals it contains.
+#[derive(Debug, Default)]
+pub(crate) struct PortalTodo {
+    /// Cubes whose blocks were replaced.
+    /// None means the entire space should be rescanned.
+    pub(crate) cubes: Option<HashSet<GridPoint>>,
+    /// Indices of blocks whose definitions changed.
+    pub(crate) block_values: HashSet<BlockIndex>,
+}
+
+/// Tracks the portal blocks in a [`Space`], and the GPU resources for displaying
+/// their views.
+#[derive(Debug)]
+pub(crate) struct SpacePortals {
+    /// Every cube in the space containing a portal block.
+    cubes: HashMap<GridPoint, Portal>,
+    /// Indices of the space's blocks which are (or were) portals, to tell whether a
+    /// change to a block definition requires rescanning the space.
+    block_indices: HashSet<BlockIndex>,
+    /// Whether `cubes` has changed since `vertex_buffer` was last written.
+    cubes_changed: bool,
+
+    /// Portals whose views are currently displayed, nearest first, and the range of
+    /// `vertex_buffer` containing the faces that display each one.
+    shown: Vec<(Portal, Range<u32>)>,
+    views: HashMap<Portal, PortalView>,
+    vertex_buffer: ResizingBuffer,
+    sampler: wgpu::Sampler,
+}
+
+/// Framebuffer and camera for drawing one [`Portal`]'s view.
+#[derive(Debug)]
+pub(crate) struct PortalView {
+    pub(crate) camera: Camera,
+    pub(crate) fb: FramebufferTextures,
+    /// Bind group for sampling `fb`; must be recreated whenever `fb` is.
+    bind_group: wgpu::BindGroup,
+    /// Number of frames since the view was drawn, or [`None`] if `fb` contains nothing
+    /// yet.
+    frames_since_drawn: Option<u32>,
+}
+
+impl SpacePortals {
+    pub(crate) fn new(device: &wgpu::Device) -> Self {
+        Self {
+            cubes: HashMap::new(),
+            block_indices: HashSet::new(),
+            cubes_changed: false,
+            shown: Vec::new(),
+            views: HashMap::new(),
+            vertex_buffer: ResizingBuffer::default(),
+            sampler: device.create_sampler(&wgpu::SamplerDescriptor {
+                label: Some("SpacePortals::sampler"),
+                address_mode_u: wgpu::AddressMode::ClampToEdge,
+                address_mode_v: wgpu::AddressMode::ClampToEdge,
+                address_mode_w: wgpu::AddressMode::ClampToEdge,
+                mag_filter: wgpu::FilterMode::Linear,
+                min_filter: wgpu::FilterMode::Linear,
+                ..Default::default()
+            }),
+        }
+    }
+
+    /// Update the set of portal blocks from `space` according to `todo`, and choose
+    /// which portals' views should be displayed to `camera`.
+    pub(crate) fn update(
+        &mut self,
+        space: &Space,
+        todo: &mut PortalTodo,
+        camera: &Camera,
+        bwp: BeltWritingParts<'_, '_>,
+    ) {
+        // A block definition change could add or remove any number of portal cubes,
+        // so it requires a full rescan, but only if it involves a portal at all.
+        let definitions_changed = todo.block_values.drain().any(|index| {
+            self.block_indices.contains(&index)
+                || space
+                    .block_data()
+                    .get(usize::from(index))
+                    .map_or(false, |data| data.evaluated().attributes.portal.is_some())
+        });
+        match (todo.cubes.replace(HashSet::new()), definitions_changed) {
+            (Some(cubes), false) => {
+                for cube in cubes {
+                    self.update_cube(space, cube);
+                }
+            }
+            _ => self.rescan(space),
+        }
+
+        self.choose_shown(camera, bwp);
+    }
+
+    fn rescan(&mut self, space: &Space) {
+        self.block_indices = space
+            .block_data()
+            .iter()
+            .enumerate()
+            .filter(|(_, data)| data.evaluated().attributes.portal.is_some())
+            .map(|(index, _)| BlockIndex::try_from(index).unwrap())
+            .collect();
+        self.cubes.clear();
+        // Skip examining every cube if there cannot be any portals.
+        if !self.block_indices.is_empty() {
+            for cube in space.bounds().interior_iter() {
+                if let Some(portal) = space.get_evaluated(cube).attributes.portal {
+                    self.cubes.insert(cube, portal);
+                }
+            }
+        }
+        self.cubes_changed = true;
+    }
+
+    fn update_cube(&mut self, space: &Space, cube: GridPoint) {
+        let changed = match space.get_evaluated(cube).attributes.portal {
+            Some(portal) => {
+                if let Some(index) = space.get_block_index(cube) {
+                    self.block_indices.insert(index);
+                }
+                self.cubes.insert(cube, portal) != Some(portal)
+            }
+            None => self.cubes.remove(&cube).is_some(),
+        };
+        self.cubes_changed |= changed;
+    }
+
+    /// Choose the portals nearest to `camera` to be shown, and write the vertices for
+    /// their faces if they differ from the previous choice.
+    fn choose_shown(&mut self, camera: &Camera, bwp: BeltWritingParts<'_, '_>) {
+        let eye = camera.view_position();
+        let mut distances: HashMap<Portal, FreeCoordinate> = HashMap::new();
+        for (&cube, &portal) in &self.cubes {
+            let distance_squared =
+                (cube.map(FreeCoordinate::from) + Vector3::new(0.5, 0.5, 0.5) - eye).magnitude2();
+            distances
+                .entry(portal)
+                .and_modify(|d| *d = d.min(distance_squared))
+                .or_insert(distance_squared);
+        }
+        let max_distance_squared = camera.view_distance().powi(2);
+        let mut candidates: Vec<(Portal, FreeCoordinate)> = distances
+            .into_iter()
+            .filter(|&(_, d)| d <= max_distance_squared)
+            .collect();
+        candidates.sort_by(|a, b| a.1.total_cmp(&b.1));
+        candidates.truncate(MAX_VIEWS);
+
+        // Changes in order alone don't matter.
+        let same_portals = candidates.len() == self.shown.len()
+            && candidates
+                .iter()
+                .all(|(portal, _)| self.shown.iter().any(|(p, _)| p == portal));
+        if same_portals && !self.cubes_changed {
+            return;
+        }
+        self.cubes_changed = false;
+
+        let mut vertices: Vec<WgpuPortalVertex> = Vec::new();
+        self.shown = candidates
+            .into_iter()
+            .map(|(portal, _)| {
+                let start = vertices.len();
+                for (&cube, _) in self.cubes.iter().filter(|&(_, &p)| p == portal) {
+                    push_cube_faces(&mut vertices, cube);
+                }
+                let range = start..vertices.len();
+                (
+                    portal,
+                    u32::try_from(range.start).unwrap()..u32::try_from(range.end).unwrap(),
+                )
+            })
+            .collect();
+        if !vertices.is_empty() {
+            self.vertex_buffer.write_with_resizing(
+                bwp,
+                &wgpu::util::BufferInitDescriptor {
+                    label: Some("SpacePortals::vertex_buffer"),
+                    contents: bytemuck::cast_slice::<WgpuPortalVertex, u8>(&vertices),
+                    usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+                },
+            );
+        }
+
+        self.views
+            .retain(|portal, _| self.shown.iter().any(|(p, _)| p == portal));
+    }
+
+    /// Ensures that every shown portal has an up-to-date [`PortalView`], and returns
+    /// which one, if any, should be drawn this frame.
+    ///
+    /// Views copy their graphics options and exposure from `main_camera`.
+    pub(crate) fn next_view_to_draw(
+        &mut self,
+        device: &wgpu::Device,
+        features: FbtFeatures,
+        pipelines: &Pipelines,
+        main_camera: &Camera,
+    ) -> Option<Portal> {
+        for (portal, _) in &self.shown {
+            let view = self.views.entry(*portal).or_insert_with(|| {
+                PortalView::new(
+                    device,
+                    features,
+                    pipelines,
+                    &self.sampler,
+                    *portal,
+                    main_camera,
+                )
+            });
+            view.update(device, pipelines, &self.sampler, main_camera);
+        }
+
+        // Choose the view that has never been drawn or has waited longest.
+        let (&portal, view) = self
+            .views
+            .iter_mut()
+            .filter(|(_, view)| view.frames_since_drawn.map_or(true, |f| f >= UPDATE_PERIOD))
+            .max_by_key(|(_, view)| view.frames_since_drawn.unwrap_or(u32::MAX))?;
+        view.frames_since_drawn = Some(0);
+        Some(portal)
+    }
+
+    /// Returns the view for a portal returned by [`Self::next_view_to_draw()`].
+    pub(crate) fn view(&self, portal: &Portal) -> &PortalView {
+        &self.views[portal]
+    }
+
+    /// Draws the faces of the portal blocks whose views are available.
+    ///
+    /// The render pass must already have the camera bind group set.
+    pub(crate) fn draw_faces<'pass>(
+        &'pass self,
+        render_pass: &mut wgpu::RenderPass<'pass>,
+        pipelines: &'pass Pipelines,
+    ) {
+        let Some(buffer) = self.vertex_buffer.get() else {
+            return;
+        };
+        render_pass.set_pipeline(&pipelines.portal_render_pipeline);
+        render_pass.set_vertex_buffer(0, buffer.slice(..));
+        for (portal, range) in &self.shown {
+            if let Some(view) = self
+                .views
+                .get(portal)
+                .filter(|view| view.frames_since_drawn.is_some())
+            {
+                render_pass.set_bind_group(2, &view.bind_group, &[]);
+                render_pass.draw(range.clone(), 0..1);
+            }
+        }
+    }
+}
+
+impl PortalView {
+    fn new(
+        device: &wgpu::Device,
+        features: FbtFeatures,
+        pipelines: &Pipelines,
+        sampler: &wgpu::Sampler,
+        portal: Portal,
+        main_camera: &Camera,
+    ) -> Self {
+        let mut camera = Camera::new(
+            main_camera.options().clone(),
+            Viewport::with_scale(1.0, Vector2::new(VIEW_SIZE, VIEW_SIZE)),
+        );
+        camera.set_view_transform(portal.view_transform());
+        let fb = FramebufferTextures::new(
+            features,
+            device,
+            &Self::surface_config(),
+            camera.options(),
+            false,
+        );
+        Self {
+            bind_group: create_view_bind_group(device, pipelines, sampler, &fb),
+            camera,
+            fb,
+            frames_since_drawn: None,
+        }
+    }
+
+    /// Copies the options and exposure of `main_camera`, and counts a frame passing.
+    fn update(
+        &mut self,
+        device: &wgpu::Device,
+        pipelines: &Pipelines,
+        sampler: &wgpu::Sampler,
+        main_camera: &Camera,
+    ) {
+        if self.camera.options() != main_camera.options() {
+            self.camera.set_options(main_camera.options().clone());
+        }
+        self.camera
+            .set_measured_exposure(main_camera.exposure().into_inner());
+
+        if self
+            .fb
+            .rebuild_if_changed(device, &Self::surface_config(), self.camera.options())
+        {
+            self.bind_group = create_view_bind_group(device, pipelines, sampler, &self.fb);
+            // The new textures have no contents.
+            self.frames_since_drawn = None;
+        }
+
+        if let Some(frames) = &mut self.frames_since_drawn {
+            *frames = frames.saturating_add(1);
+        }
+    }
+
+    /// [`FramebufferTextures`] are configured from a surface configuration, of which
+    /// only the size matters here.
+    fn surface_config() -> wgpu::SurfaceConfiguration {
+        wgpu::SurfaceConfiguration {
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+            format: wgpu::TextureFormat::Rgba8UnormSrgb,
+            width: VIEW_SIZE,
+            height: VIEW_SIZE,
+            present_mode: wgpu::PresentMode::Fifo,
+            alpha_mode: wgpu::CompositeAlphaMode::Auto,
+            view_formats: vec![],
+        }
+    }
+}
+
+fn create_view_bind_group(
+    device: &wgpu::Device,
+    pipelines: &Pipelines,
+    sampler: &wgpu::Sampler,
+    fb: &FramebufferTextures,
+) -> wgpu::BindGroup {
+    device.create_bind_group(&wgpu::BindGroupDescriptor {
+        layout: &pipelines.portal_bind_group_layout,
+        entries: &[
+            wgpu::BindGroupEntry {
+                binding: 0,
+                resource: wgpu::BindingResource::TextureView(fb.scene_for_postprocessing_input()),
+            },
+            wgpu::BindGroupEntry {
+                binding: 1,
+                resource: wgpu::BindingResource::Sampler(sampler),
+            },
+        ],
+        label: Some("PortalView::bind_group"),
+    })
+}
+
+/// Appends two triangles for each face of `cube`, textured so that the view appears
+/// upright and unmirrored to a viewer outside the cube, as if looking through a window.
+fn push_cube_faces(vertices: &mut Vec<WgpuPortalVertex>, cube: GridPoint) {
+    let center = cube.map(FreeCoordinate::from) + Vector3::new(0.5, 0.5, 0.5);
+    for face in Face6::ALL {
+        let normal: Vector3<FreeCoordinate> = face.normal_vector();
+        // Directions which appear as up and right to a viewer looking at this face.
+        let up = if face.axis_number() == 1 {
+            Vector3::unit_z()
+        } else {
+            Vector3::unit_y()
+        };
+        let right = (-normal).cross(up);
+        let face_center = center + normal * (0.5 + SURFACE_OFFSET);
+        // Counterclockwise as seen by that viewer.
+        for [s, t] in [
+            [0.0, 0.0],
+            [1.0, 0.0],
+            [0.0, 1.0],
+            [0.0, 1.0],
+            [1.0, 0.0],
+            [1.0, 1.0],
+        ] {
+            vertices.push(WgpuPortalVertex::new(
+                face_center + right * (s - 0.5) + up * (t - 0.5),
+                Point2::new(s as f32, 1.0 - t as f32),
+            ));
+        }
+    }
+}
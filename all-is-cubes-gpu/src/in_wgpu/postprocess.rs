@@ -7,7 +7,7 @@ use std::sync::Arc;
 
 use once_cell::sync::Lazy;
 
-use all_is_cubes::camera::{GraphicsOptions, ToneMappingOperator};
+use all_is_cubes::camera::{ColorPipeline, GraphicsOptions, ToneMappingOperator, UiPaletteOption};
 
 use crate::in_wgpu::glue::create_wgsl_module_from_reloadable;
 use crate::reloadable::{reloadable_str, Reloadable};
@@ -142,14 +142,25 @@ pub(crate) struct PostprocessUniforms {
 
     bloom_intensity: f32,
 
-    /// pad out to multiple of vec4<something32>
-    _padding: f32,
+    /// 0 or 1 boolean indicating whether the info text should have a solid outline
+    /// ([`UiPaletteOption::HighContrast`]) instead of a soft shadow.
+    text_high_contrast: i32,
+
+    /// Reciprocal of [`ColorPipeline::gamma()`], the exponent applied after tone mapping.
+    gamma_exponent: f32,
+
+    _padding: [i32; 3],
 }
 
 impl PostprocessUniforms {
-    pub(crate) fn new(options: &GraphicsOptions, texture_is_valid: bool) -> Self {
+    pub(crate) fn new(
+        color_pipeline: &ColorPipeline,
+        options: &GraphicsOptions,
+        texture_is_valid: bool,
+    ) -> Self {
         Self {
-            tone_mapping_id: match options.tone_mapping {
+            // Exposure is not included because it is applied when rendering the scene.
+            tone_mapping_id: match *color_pipeline.tone_mapping() {
                 ToneMappingOperator::Clamp => 0,
                 ToneMappingOperator::Reinhard => 1,
                 ref tmo => panic!("Missing implementation for tone mapping operator {tmo:?}"),
@@ -159,7 +170,14 @@ impl PostprocessUniforms {
 
             bloom_intensity: options.bloom_intensity.into_inner(),
 
-            _padding: Default::default(),
+            text_high_contrast: i32::from(matches!(
+                options.ui_palette,
+                UiPaletteOption::HighContrast
+            )),
+
+            gamma_exponent: color_pipeline.gamma().into_inner().recip(),
+
+            _padding: [0; 3],
         }
     }
 }
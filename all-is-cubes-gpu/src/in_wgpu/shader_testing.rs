@@ -16,6 +16,7 @@ use all_is_cubes::cgmath::{self, One as _, Point3, Vector3, Zero as _};
 use all_is_cubes::listen::ListenableSource;
 use all_is_cubes::math::{Face6, GridAab, Rgb, Rgba};
 use all_is_cubes::notnan;
+use all_is_cubes::space::Weather;
 use all_is_cubes_mesh::{BlockVertex, Coloring};
 
 use crate::in_wgpu::{
@@ -115,11 +116,14 @@ where
     });
 
     // Placeholder space data for the bind group
+    let block_texture =
+        in_wgpu::block_texture::AtlasAllocator::new("shader test space", &device).unwrap();
     let space_bind_group = in_wgpu::space::create_space_bind_group(
         "shader test space",
         &device,
         &pipelines,
-        &in_wgpu::block_texture::AtlasAllocator::new("shader test space", &device).unwrap(),
+        &block_texture.views(),
+        block_texture.sampler(&Default::default()),
         &in_wgpu::space::SpaceLightTexture::new(
             "shader_test_space",
             &device,
@@ -169,7 +173,13 @@ where
     queue.write_buffer(
         &camera_buffer.buffer,
         0,
-        bytemuck::bytes_of(&ShaderSpaceCamera::new(&camera, Rgb::ZERO, Vector3::zero())),
+        bytemuck::bytes_of(&ShaderSpaceCamera::new(
+            &camera,
+            Rgb::ZERO,
+            &Weather::CLEAR,
+            Vector3::zero(),
+            camera.view_distance(),
+        )),
     );
 
     let mut encoder =
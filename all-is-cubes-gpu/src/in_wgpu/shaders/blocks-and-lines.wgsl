@@ -19,8 +19,8 @@ struct WgpuBlockVertex {
     @location(0) cube_packed: u32,
     @location(1) position_in_cube_and_normal_packed: vec2<u32>,
     @location(2) color_or_texture: vec4<f32>,
-    @location(3) clamp_min: vec3<f32>,
-    @location(4) clamp_max: vec3<f32>,
+    @location(3) clamp_min_and_max_x: vec4<f32>,
+    @location(4) clamp_max_yz: vec2<f32>,
 };
 
 // Mirrors `struct WgpuInstanceData` on the Rust side.
@@ -34,6 +34,12 @@ struct WgpuLinesVertex {
     @location(1) color: vec4<f32>,
 };
 
+// Mirrors `struct WgpuPortalVertex` on the Rust side.
+struct WgpuPortalVertex {
+    @location(0) position: vec3<f32>,
+    @location(1) texcoord: vec2<f32>,
+};
+
 // This group is named camera_bind_group_layout in the code.
 @group(0) @binding(0) var<uniform> camera: ShaderSpaceCamera;
 
@@ -41,6 +47,11 @@ struct WgpuLinesVertex {
 @group(1) @binding(0) var block_texture: texture_3d<f32>;
 @group(1) @binding(1) var block_sampler: sampler;
 @group(1) @binding(2) var light_texture: texture_3d<u32>;
+@group(1) @binding(3) var block_emission_texture: texture_3d<f32>;
+
+// This group is named portal_bind_group_layout in the code.
+@group(2) @binding(0) var portal_texture: texture_2d<f32>;
+@group(2) @binding(1) var portal_sampler: sampler;
 
 // --- Fog computation --------------------------------------------------------
 
@@ -144,7 +155,7 @@ fn block_vertex_main(
 
     // Unpack normal.
     var normal = vec3<f32>(1.0);
-    switch ((input.position_in_cube_and_normal_packed[1] >> 16u) & 0xFFFFu) {
+    switch ((input.position_in_cube_and_normal_packed[1] >> 16u) & 0xFFu) {
         case 1u { normal = vec3<f32>(-1.0, 0.0, 0.0); }
         case 2u { normal = vec3<f32>(0.0, -1.0, 0.0); }
         case 3u { normal = vec3<f32>(0.0, 0.0, -1.0); }
@@ -164,6 +175,13 @@ fn block_vertex_main(
         bitangent = abs(cross(tangent, normal));
     }
 
+    // Unpack color or texture coordinates into the form the fragment shader uses,
+    // where a fourth component of -1 marks texture coordinates.
+    var color_or_texture = input.color_or_texture;
+    if ((input.position_in_cube_and_normal_packed[1] >> 24u) & 1u) != 0u {
+        color_or_texture = vec4<f32>(color_or_texture.xyz, -1.0);
+    }
+
     let combined_matrix = camera.projection * camera.view_matrix;
     // TODO: eventually this should become a camera-relative position, not a world position.
     // That will require further work in light-lookup cooordinates.
@@ -177,9 +195,9 @@ fn block_vertex_main(
         tangent,
         bitangent,
         normal,
-        input.color_or_texture,
-        input.clamp_min,
-        input.clamp_max,
+        color_or_texture,
+        input.clamp_min_and_max_x.xyz, // clamp_min
+        vec3<f32>(input.clamp_min_and_max_x.w, input.clamp_max_yz), // clamp_max
         compute_fog(world_position),
         // Note that we do not normalize this vector: by keeping things linear, we
         // allow linear interpolation between vertices to get the right answer.
@@ -253,7 +271,7 @@ fn light_texture_fetch(fragment_position: vec3<f32>) -> vec4<f32> {
     // Decode logarithmic representation.
     // Exception: A texel value of exactly 0 is taken as 0, not the lowest power of 2.
     let not_zero: vec3<bool> = packed_light > vec3<i32>(0);
-    let unpacked_light: vec3<f32> = pow(vec3<f32>(2.0), vec3<f32>(packed_light - 128) / 16.0) * vec3<f32>(not_zero);
+    let unpacked_light: vec3<f32> = pow(vec3<f32>(2.0), vec3<f32>(packed_light - 32768) / 2048.0) * vec3<f32>(not_zero);
 
     // See all_is_cubes::space::LightStatus for the value this is interpreting.
     // The enum values are grouped into approximately {0, 128, 255}, so multiplying by 2 and
@@ -380,12 +398,42 @@ fn lighting(in: BlockFragmentInput) -> vec3<f32> {
     }
 }
 
+// Texture coordinates are relative to the largest size the atlas may grow to,
+// so that they stay valid when it does; this rescales them to the actual texture size.
+// 256.0 must match `MAX_SIZE_EXPONENT` in `block_texture.rs`.
+fn block_texcoord_scale() -> vec3<f32> {
+    return 256.0 / vec3<f32>(textureDimensions(block_texture, 0));
+}
+
+// Compute the block texture coordinates to sample for textured geometry.
+fn block_texcoord(in: BlockFragmentInput) -> vec3<f32> {
+    let texcoord: vec3<f32> = clamp(in.color_or_texture.xyz, in.clamp_min, in.clamp_max);
+    return texcoord * block_texcoord_scale();
+}
+
+// Sample a block texture, choosing the mip level from the screen-space derivatives
+// of the texture coordinates. Must be called in uniform control flow, since the
+// derivatives are only meaningful there; for untextured geometry, returns zero.
+//
+// The derivatives are taken of the unclamped coordinates, since clamping would
+// make them zero at the edges of the clamp rectangle.
+fn sample_block_texture(in: BlockFragmentInput, texture: texture_3d<f32>) -> vec4<f32> {
+    let unclamped = in.color_or_texture.xyz * block_texcoord_scale();
+    let ddx = dpdx(unclamped);
+    let ddy = dpdy(unclamped);
+    if in.color_or_texture[3] < -0.5 {
+        return textureSampleGrad(texture, block_sampler, block_texcoord(in), ddx, ddy);
+    } else {
+        return vec4<f32>(0.0);
+    }
+}
+
 // Get the vertex color or texel value to display
 fn get_diffuse_color(in: BlockFragmentInput) -> vec4<f32> {
+    let texel = sample_block_texture(in, block_texture);
     if in.color_or_texture[3] < -0.5 {
         // Texture coordinates.
-        let texcoord: vec3<f32> = clamp(in.color_or_texture.xyz, in.clamp_min, in.clamp_max);
-        return textureSampleLevel(block_texture, block_sampler, texcoord, 0.0);
+        return texel;
 
         // TODO: implement DEBUG_TEXTURE_EDGE
     } else {
@@ -394,6 +442,11 @@ fn get_diffuse_color(in: BlockFragmentInput) -> vec4<f32> {
     }
 }
 
+// Get the light emitted by the texel, which is only available for textured geometry.
+fn get_emission(in: BlockFragmentInput) -> vec3<f32> {
+    return sample_block_texture(in, block_emission_texture).rgb;
+}
+
 // Apply the effects of distance fog and camera exposure.
 // These effects are independent of alpha and therefore the input and output is RGB.
 fn apply_fog_and_exposure(lit_color: vec3<f32>, fog_mix: f32) -> vec3<f32> {
@@ -439,14 +492,15 @@ fn volumetric_transparency(in: BlockFragmentInput, starting_alpha: f32) -> f32 {
 // Entry point for opaque geometry.
 @fragment
 fn block_fragment_opaque(in: BlockFragmentInput) -> @location(0) vec4<f32> {
-    let lit_color: vec3<f32> = get_diffuse_color(in).rgb * lighting(in);
+    let lit_color: vec3<f32> = get_diffuse_color(in).rgb * lighting(in) + get_emission(in);
     return vec4<f32>(apply_fog_and_exposure(lit_color, in.fog_mix), 1.0);
 }
 
 // Entry point for transparency under TransparencyOption::Surface.
 @fragment
 fn block_fragment_transparent_surface(in: BlockFragmentInput) -> @location(0) vec4<f32> {
-    let lit_color = get_diffuse_color(in) * vec4<f32>(lighting(in), 1.0);
+    let lit_color = get_diffuse_color(in) * vec4<f32>(lighting(in), 1.0)
+        + vec4<f32>(get_emission(in), 0.0);
     let exposed_color = vec4<f32>(apply_fog_and_exposure(lit_color.rgb, in.fog_mix), lit_color.a);
     return vec4<f32>(exposed_color.rgb * exposed_color.a, exposed_color.a);
 }
@@ -456,7 +510,8 @@ fn block_fragment_transparent_surface(in: BlockFragmentInput) -> @location(0) ve
 fn block_fragment_transparent_volumetric(in: BlockFragmentInput) -> @location(0) vec4<f32> {
     var diffuse_color = get_diffuse_color(in);
     diffuse_color.a = volumetric_transparency(in, diffuse_color.a);
-    let lit_color = diffuse_color * vec4<f32>(lighting(in), 1.0);
+    let lit_color = diffuse_color * vec4<f32>(lighting(in), 1.0)
+        + vec4<f32>(get_emission(in), 0.0);
     let exposed_color = vec4<f32>(apply_fog_and_exposure(lit_color.rgb, in.fog_mix), lit_color.a);
     return vec4<f32>(exposed_color.rgb * exposed_color.a, exposed_color.a);
 }
@@ -493,3 +548,35 @@ fn lines_fragment(input: LinesFragmentInput) -> @location(0) vec4<f32> {
 
     return fogged_color;
 }
+
+// --- Portal shader -----------------------------------------------------------
+//
+// Draws the faces of portal blocks, showing the view rendered from the portal's target.
+
+// Vertex-to-fragment data for portals
+struct PortalFragmentInput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) texcoord: vec2<f32>,
+    @location(1) fog_mix: f32,
+};
+
+@vertex
+fn portal_vertex(
+    input: WgpuPortalVertex,
+) -> PortalFragmentInput {
+    return PortalFragmentInput(
+        camera.projection * camera.view_matrix * vec4<f32>(input.position, 1.0),
+        input.texcoord,
+        compute_fog(input.position),
+    );
+}
+
+@fragment
+fn portal_fragment(input: PortalFragmentInput) -> @location(0) vec4<f32> {
+    // The view was rendered with the same exposure as this camera's, so it is already
+    // exposed, and only the fog remains to be applied.
+    let view_color = textureSample(portal_texture, portal_sampler, input.texcoord).rgb;
+    let exposed_fog_color = camera.fog_color_and_fog_mode_blend.rgb * camera.fog_distance_and_exposure[1];
+
+    return vec4<f32>(mix(view_color, exposed_fog_color, input.fog_mix), 1.0);
+}
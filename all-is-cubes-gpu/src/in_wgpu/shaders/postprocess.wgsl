@@ -5,7 +5,9 @@ struct PostprocessUniforms {
     tone_mapping_id: i32,
     scene_texture_valid: i32,
     bloom_intensity: f32,
-    padding: f32,
+    text_high_contrast: i32,
+    gamma_exponent: f32,
+    // followed by padding
 };
 
 
@@ -113,6 +115,10 @@ fn text_shadow_alpha(texcoord: vec2<f32>) -> f32 {
         let weight: f32 = 0.2 / max(1.0, length(offset));
         accumulator = accumulator + offset_alpha * weight;
     }
+    if camera.text_high_contrast != 0 {
+        // Solid outline wherever any part of the text is nearby.
+        return ceil(clamp(accumulator, 0.0, 1.0));
+    }
     return pow(clamp(accumulator, 0.0, 0.5), 0.48);
 }
 
@@ -123,8 +129,16 @@ fn postprocess_fragment(in: VertexOutput) -> @location(0) vec4<f32> {
 
     let scene_color = scene_pixel(texcoord);
 
-    // apply tone mapping, respecting premultiplied alpha
-    let tone_mapped_scene = vec4<f32>(tone_map(scene_color.rgb * scene_color.a) / scene_color.a, scene_color.a);
+    // Apply tone mapping to the premultiplied color, then un-premultiply before applying
+    // gamma, so that the result matches `ColorPipeline::apply()` on the CPU side.
+    // (The alpha is kept away from zero to avoid dividing by it; the color of a fully
+    // transparent pixel does not matter.)
+    let alpha = max(scene_color.a, 1e-6);
+    let tone_mapped_rgb = pow(
+        max(tone_map(scene_color.rgb * alpha) / alpha, vec3<f32>(0.0)),
+        vec3<f32>(camera.gamma_exponent)
+    );
+    let tone_mapped_scene = vec4<f32>(tone_mapped_rgb, scene_color.a);
 
     let shadowing = text_shadow_alpha(texcoord);
     let foreground_texel = textureSampleLevel(text_texture, text_sampler, texcoord, 0.0);
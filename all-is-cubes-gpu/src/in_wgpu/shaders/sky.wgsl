@@ -0,0 +1,68 @@
+// --- Interface declarations --------------------------------------------------
+
+// Mirrors `struct ShaderSpaceCamera` on the Rust side.
+// Must be kept identical to the declaration in `blocks-and-lines.wgsl`.
+struct ShaderSpaceCamera {
+    projection: mat4x4<f32>,
+    view_matrix: mat4x4<f32>,
+    view_position: vec3<f32>,
+    light_lookup_offset_and_option: vec4<i32>, // vec3 + 1
+    fog_color_and_fog_mode_blend: vec4<f32>, // vec3 + 1
+    fog_distance_and_exposure: vec4<f32>, // last two components unused
+};
+
+// This group is named camera_bind_group_layout in the code.
+@group(0) @binding(0) var<uniform> camera: ShaderSpaceCamera;
+
+// Group 1 is space_texture_bind_group_layout, which is not used by this shader.
+
+// This group is named sky_bind_group_layout in the code.
+@group(2) @binding(0) var sky_texture: texture_cube<f32>;
+@group(2) @binding(1) var sky_sampler: sampler;
+
+// --- Vertex shader -----------------------------------------------------------
+
+struct SkyFragmentInput {
+    @builtin(position) clip_position: vec4<f32>,
+
+    // World-space direction from the eye (not normalized).
+    @location(0) direction: vec3<f32>,
+};
+
+@vertex
+fn sky_vertex(
+    @builtin(vertex_index) in_vertex_index: u32,
+) -> SkyFragmentInput {
+    // Full-screen triangle, at the far plane so that all other geometry is in front.
+    let position = vec2<f32>(
+        mix(-1.0, 3.0, f32(in_vertex_index == 1u)),
+        mix(-1.0, 3.0, f32(in_vertex_index == 2u)),
+    );
+
+    // Undo the projection to find the eye-space direction, then undo the view rotation
+    // to find the world-space direction. The direction is affine in screen position,
+    // so it may be interpolated.
+    let eye_direction = vec3<f32>(
+        position.x / camera.projection[0][0],
+        position.y / camera.projection[1][1],
+        -1.0,
+    );
+    let view_rotation = mat3x3<f32>(
+        camera.view_matrix[0].xyz,
+        camera.view_matrix[1].xyz,
+        camera.view_matrix[2].xyz,
+    );
+
+    return SkyFragmentInput(
+        vec4<f32>(position, 1.0, 1.0),
+        transpose(view_rotation) * eye_direction,
+    );
+}
+
+// --- Fragment shader ---------------------------------------------------------
+
+@fragment
+fn sky_fragment(in: SkyFragmentInput) -> @location(0) vec4<f32> {
+    let sky_color = textureSample(sky_texture, sky_sampler, in.direction).rgb;
+    return vec4<f32>(sky_color * camera.fog_distance_and_exposure[1], 1.0);
+}
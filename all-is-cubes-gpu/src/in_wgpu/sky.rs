@@ -0,0 +1,179 @@
+//! Drawing the [`Sky`] of a [`Space`] behind its blocks.
+//!
+//! Skies which are not a uniform color are uploaded as a cube map texture and drawn
+//! as a full-screen triangle after the opaque geometry. Uniform skies need no drawing,
+//! since the framebuffer is cleared to the sky color anyway.
+
+use std::sync::Arc;
+
+use all_is_cubes::math::{Face6, Rgb};
+use all_is_cubes::space::{CubeMap, Sky};
+
+use crate::in_wgpu::pipelines::Pipelines;
+
+#[cfg(doc)]
+use all_is_cubes::space::Space;
+
+/// Resolution of the cube map used to draw skies which are not already [`Sky::CubeMap`].
+const RASTERIZED_RESOLUTION: u16 = 32;
+
+/// GPU resources for drawing the sky of a single space.
+#[derive(Debug, Default)]
+pub(crate) struct SpaceSky {
+    /// The sky `bind_group` was created from, or [`None`] if nothing has been uploaded yet.
+    sky: Option<Sky>,
+
+    /// Bind group containing the sky's cube map texture, or [`None`] if the sky is
+    /// uniform and does not need to be drawn.
+    bind_group: Option<wgpu::BindGroup>,
+}
+
+impl SpaceSky {
+    /// Replace the GPU resources if `sky` differs from the last one given.
+    pub(crate) fn update(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        pipelines: &Pipelines,
+        space_label: &str,
+        sky: &Sky,
+    ) {
+        let unchanged = match (&self.sky, sky) {
+            // Cube maps are large, so compare them by identity rather than by contents.
+            (Some(Sky::CubeMap(old)), Sky::CubeMap(new)) => Arc::ptr_eq(old, new),
+            (old, new) => old.as_ref() == Some(new),
+        };
+        if unchanged {
+            return;
+        }
+
+        self.bind_group = match sky {
+            Sky::Uniform(_) => None,
+            Sky::CubeMap(cube_map) => Some(create_sky_bind_group(
+                device,
+                queue,
+                pipelines,
+                space_label,
+                cube_map,
+            )),
+            _ => Some(create_sky_bind_group(
+                device,
+                queue,
+                pipelines,
+                space_label,
+                &CubeMap::from_fn(RASTERIZED_RESOLUTION, |direction| sky.sample(direction)),
+            )),
+        };
+        self.sky = Some(sky.clone());
+    }
+
+    /// Draws the sky, if it is not uniform, wherever the depth buffer is still clear.
+    ///
+    /// The render pass must already have the camera and space bind groups set.
+    pub(crate) fn draw<'pass>(
+        &'pass self,
+        render_pass: &mut wgpu::RenderPass<'pass>,
+        pipelines: &'pass Pipelines,
+    ) {
+        let Some(bind_group) = &self.bind_group else {
+            return;
+        };
+        render_pass.set_pipeline(&pipelines.sky_render_pipeline);
+        render_pass.set_bind_group(2, bind_group, &[]);
+        render_pass.draw(0..3, 0..1);
+    }
+}
+
+fn create_sky_bind_group(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    pipelines: &Pipelines,
+    space_label: &str,
+    cube_map: &CubeMap,
+) -> wgpu::BindGroup {
+    let resolution = u32::from(cube_map.resolution());
+    let size = wgpu::Extent3d {
+        width: resolution,
+        height: resolution,
+        depth_or_array_layers: 6,
+    };
+    let texture = device.create_texture(&wgpu::TextureDescriptor {
+        label: Some(&format!("{space_label} sky")),
+        size,
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        // Floating-point because sky colors are HDR and may exceed 1.
+        format: wgpu::TextureFormat::Rgba16Float,
+        usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+        view_formats: &[],
+    });
+
+    // Layers must be in the order +X, -X, +Y, -Y, +Z, -Z, which matches the texel
+    // layout of `CubeMap`.
+    let texels: Vec<[half::f16; 4]> = [
+        Face6::PX,
+        Face6::NX,
+        Face6::PY,
+        Face6::NY,
+        Face6::PZ,
+        Face6::NZ,
+    ]
+    .into_iter()
+    .flat_map(|face| cube_map.face(face).iter().copied().map(rgb_to_f16))
+    .collect();
+    queue.write_texture(
+        wgpu::ImageCopyTexture {
+            texture: &texture,
+            mip_level: 0,
+            origin: wgpu::Origin3d::ZERO,
+            aspect: wgpu::TextureAspect::All,
+        },
+        bytemuck::cast_slice::<[half::f16; 4], u8>(&texels),
+        wgpu::ImageDataLayout {
+            offset: 0,
+            bytes_per_row: Some(std::mem::size_of::<[half::f16; 4]>() as u32 * resolution),
+            rows_per_image: Some(resolution),
+        },
+        size,
+    );
+
+    let view = texture.create_view(&wgpu::TextureViewDescriptor {
+        label: Some(&format!("{space_label} sky view")),
+        dimension: Some(wgpu::TextureViewDimension::Cube),
+        ..Default::default()
+    });
+    let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+        label: Some(&format!("{space_label} sky sampler")),
+        address_mode_u: wgpu::AddressMode::ClampToEdge,
+        address_mode_v: wgpu::AddressMode::ClampToEdge,
+        address_mode_w: wgpu::AddressMode::ClampToEdge,
+        mag_filter: wgpu::FilterMode::Linear,
+        min_filter: wgpu::FilterMode::Linear,
+        ..Default::default()
+    });
+
+    device.create_bind_group(&wgpu::BindGroupDescriptor {
+        layout: &pipelines.sky_bind_group_layout,
+        entries: &[
+            wgpu::BindGroupEntry {
+                binding: 0,
+                resource: wgpu::BindingResource::TextureView(&view),
+            },
+            wgpu::BindGroupEntry {
+                binding: 1,
+                resource: wgpu::BindingResource::Sampler(&sampler),
+            },
+        ],
+        label: Some(&format!("{space_label} sky bind group")),
+    })
+}
+
+fn rgb_to_f16(color: Rgb) -> [half::f16; 4] {
+    [
+        half::f16::from_f32(color.red().into_inner()),
+        half::f16::from_f32(color.green().into_inner()),
+        half::f16::from_f32(color.blue().into_inner()),
+        half::f16::ONE,
+    ]
+}
@@ -5,23 +5,29 @@ use std::sync::{Arc, Mutex, Weak};
 
 use instant::Instant;
 
-use all_is_cubes::camera::{Camera, Flaws};
+use all_is_cubes::camera::{Camera, Flaws, TextureFilteringOption};
 use all_is_cubes::cgmath::{EuclideanSpace, Point3, Transform, Vector3};
+use all_is_cubes::character::PosedPart;
 use all_is_cubes::chunking::ChunkPos;
 use all_is_cubes::content::palette;
 use all_is_cubes::listen::{Listen as _, Listener};
 use all_is_cubes::math::{Face6, FaceMap, FreeCoordinate, GridAab, GridCoordinate, GridPoint, Rgb};
-use all_is_cubes::space::{Space, SpaceChange};
+use all_is_cubes::space::{Space, SpaceChange, Weather};
 use all_is_cubes::universe::URef;
 use all_is_cubes_mesh::chunked_mesh::{ChunkMeshUpdate, ChunkedSpaceMesh};
 use all_is_cubes_mesh::{DepthOrdering, IndexSlice};
 
+use crate::in_wgpu::body::BodyMesh;
 use crate::in_wgpu::frame_texture::FramebufferTextures;
-use crate::in_wgpu::glue::{size_vector_to_extent, to_wgpu_index_format, write_texture_by_aab};
+use crate::in_wgpu::glue::{
+    size_vector_to_extent, to_wgpu_color, to_wgpu_index_format, write_texture_by_aab,
+};
 use crate::in_wgpu::pipelines::Pipelines;
+use crate::in_wgpu::portal::{PortalTodo, SpacePortals};
+use crate::in_wgpu::sky::SpaceSky;
 use crate::in_wgpu::vertex::{WgpuInstanceData, WgpuLinesVertex};
 use crate::in_wgpu::{
-    block_texture::{AtlasAllocator, AtlasTile},
+    block_texture::{AtlasAllocator, AtlasTile, BlockTextureViews},
     camera::ShaderSpaceCamera,
     glue::{to_wgpu_index_range, BeltWritingParts, ResizingBuffer},
     vertex::WgpuBlockVertex,
@@ -44,10 +50,19 @@ pub(crate) struct SpaceRenderer {
     /// Note that `self.csm` has its own todo listener too.
     todo: Arc<Mutex<SpaceRendererTodo>>,
 
-    /// Cached copy of `space.physics.sky_color`.
+    /// Cached copy of `space.physics.sky.horizon()`, used for clearing and fog.
     pub(crate) sky_color: Rgb,
+    /// Texture for drawing `space.physics.sky` if it is not uniform.
+    sky: SpaceSky,
+    /// Cached copy of `space.weather()`, used for fog.
+    weather: Weather,
 
     block_texture: Arc<AtlasAllocator>,
+    /// The views of `block_texture` that `space_bind_group` was created with.
+    /// These are replaced when the atlas grows.
+    block_texture_views: Arc<BlockTextureViews>,
+    /// The texture filtering option `space_bind_group`'s sampler was chosen for.
+    texture_filtering: TextureFilteringOption,
     light_texture: SpaceLightTexture,
 
     /// Buffer containing the [`ShaderSpaceCamera`] configured for this Space.
@@ -61,6 +76,12 @@ pub(crate) struct SpaceRenderer {
     space_bind_group: wgpu::BindGroup,
 
     csm: ChunkedSpaceMesh<Option<ChunkBuffers>, WgpuBlockVertex, AtlasAllocator, CHUNK_SIZE>,
+
+    /// Portal blocks in the space and their views.
+    portals: SpacePortals,
+
+    /// Avatars of characters to be drawn in this space.
+    body: BodyMesh,
 }
 
 #[derive(Debug, Default)]
@@ -85,11 +106,15 @@ impl SpaceRenderer {
 
         let light_texture = SpaceLightTexture::new(&space_label, device, space_borrowed.bounds());
 
+        let block_texture_views = block_texture.views();
+        // Updated from the camera's options in update().
+        let texture_filtering = TextureFilteringOption::default();
         let space_bind_group = create_space_bind_group(
             &space_label,
             device,
             pipelines,
-            &block_texture,
+            &block_texture_views,
+            block_texture.sampler(&texture_filtering),
             &light_texture,
         );
 
@@ -103,13 +128,20 @@ impl SpaceRenderer {
             render_pass_label: format!("{space_label} render_pass"),
             instance_buffer_label: format!("{space_label} instances"),
             space_label,
-            sky_color: space_borrowed.physics().sky_color,
+            sky_color: space_borrowed.physics().sky.horizon(),
+            // Uploaded in update().
+            sky: SpaceSky::default(),
+            weather: *space_borrowed.weather(),
             block_texture,
+            block_texture_views,
+            texture_filtering,
             light_texture,
             space_bind_group,
             camera_buffer,
             instance_buffer: ResizingBuffer::default(),
             csm: ChunkedSpaceMesh::new(space),
+            portals: SpacePortals::new(device),
+            body: BodyMesh::default(),
         })
     }
 
@@ -135,12 +167,18 @@ impl SpaceRenderer {
             instance_buffer_label: _,
             todo,
             sky_color,
+            sky,
+            weather,
             block_texture,
+            block_texture_views,
+            texture_filtering,
             light_texture,
             camera_buffer: _,
             instance_buffer: _,
             space_bind_group,
             csm,
+            portals,
+            body: _,
         } = self;
 
         let space_borrowed = space.read().unwrap();
@@ -152,12 +190,21 @@ impl SpaceRenderer {
         };
         // TODO: rescue ChunkChart and maybe block meshes from the old `csm`.
         *csm = ChunkedSpaceMesh::new(space.clone());
-        *sky_color = space_borrowed.physics().sky_color;
+        *portals = SpacePortals::new(device);
+        *sky_color = space_borrowed.physics().sky.horizon();
+        *sky = SpaceSky::default();
+        *weather = *space_borrowed.weather();
         // TODO: don't replace light texture if the size is the same
         *light_texture = SpaceLightTexture::new(space_label, device, space_borrowed.bounds());
         // bind group must be recreated for new light texture
-        *space_bind_group =
-            create_space_bind_group(space_label, device, pipelines, block_texture, light_texture);
+        *space_bind_group = create_space_bind_group(
+            space_label,
+            device,
+            pipelines,
+            block_texture_views,
+            block_texture.sampler(texture_filtering),
+            light_texture,
+        );
     }
 
     /// Update renderer internal state from the given [`Camera`] and referenced [`Space`],
@@ -167,6 +214,7 @@ impl SpaceRenderer {
         &mut self,
         deadline: Instant,
         queue: &wgpu::Queue,
+        pipelines: &Pipelines,
         camera: &Camera,
         mut bwp: BeltWritingParts<'_, '_>,
     ) -> Result<SpaceUpdateInfo, GraphicsResourceError> {
@@ -180,12 +228,21 @@ impl SpaceRenderer {
             .read()
             .map_err(GraphicsResourceError::read_err)?;
 
-        // Update sky color (cheap so we don't bother todo-tracking it)
-        self.sky_color = space.physics().sky_color;
+        // Update sky and weather (cheap to compare so we don't bother todo-tracking them)
+        self.sky_color = space.physics().sky.horizon();
+        self.sky.update(
+            bwp.device,
+            queue,
+            pipelines,
+            &self.space_label,
+            &space.physics().sky,
+        );
+        self.weather = *space.weather();
 
         // Update light texture
         let start_light_update = Instant::now();
         let mut light_update_count = 0;
+        let mut light_texture_replaced = false;
         if let Some(set) = &mut todo.light {
             // TODO: work in larger, ahem, chunks
             for cube in set.drain() {
@@ -196,11 +253,20 @@ impl SpaceRenderer {
                 );
             }
         } else {
+            // The space's bounds may have changed, in which case the texture must too.
+            if !self.light_texture.fits(space.bounds()) {
+                self.light_texture =
+                    SpaceLightTexture::new(&self.space_label, bwp.device, space.bounds());
+                light_texture_replaced = true;
+            }
             light_update_count += self.light_texture.update_all(queue, space);
             todo.light = Some(HashSet::new());
         }
         let end_light_update = Instant::now();
 
+        self.portals
+            .update(space, &mut todo.portals, camera, bwp.reborrow());
+
         // Update chunks
         let csm_info = self.csm.update_blocks_and_some_chunks(
             camera,
@@ -235,7 +301,9 @@ impl SpaceRenderer {
             &wgpu::BufferDescriptor {
                 label: Some(&self.instance_buffer_label),
                 size: u64::try_from(
-                    self.csm.chunk_chart().count_all() * std::mem::size_of::<WgpuInstanceData>(),
+                    // One instance per chunk, plus the avatars.
+                    (self.csm.chunk_chart().count_all() + 1)
+                        * std::mem::size_of::<WgpuInstanceData>(),
                 )
                 .expect("instance buffer size overflow"),
                 usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::VERTEX,
@@ -246,7 +314,28 @@ impl SpaceRenderer {
         // Flush all texture updates to GPU.
         // This must happen after `csm.update_blocks_and_some_chunks` so that the newly
         // generated meshes have the texels they expect.
-        let texture_info = self.block_texture.flush(queue);
+        let texture_info = self.block_texture.flush(bwp.device, queue);
+
+        // If the atlas grew (during this flush or another renderer's), its textures were
+        // replaced, so the bind group must be too. Likewise if the sampler should change,
+        // or the light texture was replaced.
+        let current_views = self.block_texture.views();
+        let texture_filtering = &camera.options().texture_filtering;
+        if !Arc::ptr_eq(&current_views, &self.block_texture_views)
+            || *texture_filtering != self.texture_filtering
+            || light_texture_replaced
+        {
+            self.block_texture_views = current_views;
+            self.texture_filtering = texture_filtering.clone();
+            self.space_bind_group = create_space_bind_group(
+                &self.space_label,
+                bwp.device,
+                pipelines,
+                &self.block_texture_views,
+                self.block_texture.sampler(&self.texture_filtering),
+                &self.light_texture,
+            );
+        }
 
         let end_time = Instant::now();
 
@@ -259,9 +348,26 @@ impl SpaceRenderer {
         })
     }
 
+    /// Set the parts of characters' avatars to draw, which may be empty.
+    ///
+    /// The avatars are drawn by [`Self::draw()`] along with the blocks of the space.
+    /// This should be called before [`Self::update()`], which writes the textures of
+    /// the avatars' blocks.
+    pub(crate) fn update_body(
+        &mut self,
+        parts: &[PosedPart],
+        camera: &Camera,
+        bwp: BeltWritingParts<'_, '_>,
+    ) {
+        self.body.update(parts, camera, &self.block_texture, bwp);
+    }
+
     /// Draw the space as of the last [`Self::update`].
     ///
     /// Does not access the [`Space`] contents at all.
+    ///
+    /// If `draw_sky` is true, the sky is drawn behind the blocks if it is not uniform;
+    /// it should be false if the space is being drawn over other content.
     // TODO: needs error return or not?
     #[allow(clippy::too_many_arguments)]
     pub fn draw(
@@ -272,7 +378,9 @@ impl SpaceRenderer {
         pipelines: &Pipelines,
         camera: &Camera,
         color_load_op: wgpu::LoadOp<wgpu::Color>,
+        draw_sky: bool,
         store_depth: bool,
+        draw_portals: bool,
     ) -> Result<SpaceDrawInfo, GraphicsResourceError> {
         let start_time = Instant::now();
 
@@ -296,7 +404,9 @@ impl SpaceRenderer {
             bytemuck::bytes_of(&ShaderSpaceCamera::new(
                 camera,
                 self.sky_color,
+                &self.weather,
                 self.light_texture.light_lookup_offset(),
+                csm.ready_distance(),
             )),
         );
 
@@ -360,6 +470,17 @@ impl SpaceRenderer {
             }
             flaws |= chunk.mesh().flaws();
         }
+        squares_drawn += self.body.draw(&mut render_pass, &mut instance_data);
+
+        if draw_portals {
+            self.portals.draw_faces(&mut render_pass, pipelines);
+        }
+
+        // The sky goes behind everything opaque, but transparent geometry must be
+        // blended over it.
+        if draw_sky {
+            self.sky.draw(&mut render_pass, pipelines);
+        }
 
         // Transparent geometry after opaque geometry, in back-to-front order
         let start_draw_transparent_time = Instant::now();
@@ -404,6 +525,51 @@ impl SpaceRenderer {
         })
     }
 
+    /// Draw the view of at most one of the portals in the space (whichever is most out of
+    /// date), for use by the next [`Self::draw()`] with `draw_portals` true.
+    ///
+    /// This performs its own submission, and must be called before any other drawing
+    /// using this [`SpaceRenderer`] is submitted, because it reuses the same camera and
+    /// instance buffers, and [`wgpu::Queue::write_buffer()`] takes effect on submission.
+    ///
+    /// TODO: The view draws only the chunks which are loaded for the main camera, and
+    /// does not itself show portals.
+    pub(crate) fn draw_portal_views(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        main_fb: &FramebufferTextures,
+        pipelines: &Pipelines,
+        main_camera: &Camera,
+    ) -> Result<(), GraphicsResourceError> {
+        let Some(portal) =
+            self.portals
+                .next_view_to_draw(device, main_fb.features(), pipelines, main_camera)
+        else {
+            return Ok(());
+        };
+        let view = self.portals.view(&portal);
+
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("SpaceRenderer::draw_portal_views()"),
+        });
+        self.draw(
+            &view.fb,
+            queue,
+            &mut encoder,
+            pipelines,
+            &view.camera,
+            wgpu::LoadOp::Clear(to_wgpu_color(
+                (self.sky_color * view.camera.exposure()).with_alpha_one(),
+            )),
+            true,
+            false,
+            false,
+        )?;
+        queue.submit(std::iter::once(encoder.finish()));
+        Ok(())
+    }
+
     /// Returns the camera, to allow additional drawing in the same coordinate system.
     pub(crate) fn camera_bind_group(&self) -> &wgpu::BindGroup {
         &self.camera_buffer.bind_group
@@ -486,7 +652,8 @@ pub(in crate::in_wgpu) fn create_space_bind_group(
     space_label: &str,
     device: &wgpu::Device,
     pipelines: &Pipelines,
-    block_texture: &AtlasAllocator,
+    block_texture_views: &BlockTextureViews,
+    block_sampler: &wgpu::Sampler,
     light_texture: &SpaceLightTexture,
 ) -> wgpu::BindGroup {
     device.create_bind_group(&wgpu::BindGroupDescriptor {
@@ -494,16 +661,22 @@ pub(in crate::in_wgpu) fn create_space_bind_group(
         entries: &[
             wgpu::BindGroupEntry {
                 binding: 0,
-                resource: wgpu::BindingResource::TextureView(&block_texture.texture_view),
+                resource: wgpu::BindingResource::TextureView(&block_texture_views.texture_view),
             },
             wgpu::BindGroupEntry {
                 binding: 1,
-                resource: wgpu::BindingResource::Sampler(&block_texture.sampler),
+                resource: wgpu::BindingResource::Sampler(block_sampler),
             },
             wgpu::BindGroupEntry {
                 binding: 2,
                 resource: wgpu::BindingResource::TextureView(&light_texture.texture_view),
             },
+            wgpu::BindGroupEntry {
+                binding: 3,
+                resource: wgpu::BindingResource::TextureView(
+                    &block_texture_views.emission_texture_view,
+                ),
+            },
         ],
         label: Some(&format!("{space_label} space_bind_group")),
     })
@@ -574,6 +747,8 @@ struct SpaceRendererTodo {
     ///
     /// TODO: experiment with different granularities of light invalidation (chunks, dirty rects, etc.)
     light: Option<HashSet<GridPoint>>,
+
+    portals: PortalTodo,
 }
 
 /// [`Listener`] adapter for [`SpaceRendererTodo`].
@@ -587,6 +762,7 @@ impl Listener<SpaceChange> for TodoListener {
                 match message {
                     SpaceChange::EveryBlock => {
                         todo.light = None;
+                        todo.portals.cubes = None;
                     }
                     SpaceChange::Lighting(p) => {
                         // None means we're already at "update everything"
@@ -594,9 +770,17 @@ impl Listener<SpaceChange> for TodoListener {
                             set.insert(p);
                         }
                     }
-                    SpaceChange::Block(..) => {}
+                    SpaceChange::Block(p) => {
+                        if let Some(set) = &mut todo.portals.cubes {
+                            set.insert(p);
+                        }
+                    }
                     SpaceChange::Number(..) => {}
-                    SpaceChange::BlockValue(..) => {}
+                    SpaceChange::BlockValue(index) => {
+                        todo.portals.block_values.insert(index);
+                    }
+                    // Weather is read from the space on every update.
+                    SpaceChange::Weather => {}
                 }
             }
         }
@@ -622,21 +806,13 @@ impl SpaceLightTexture {
     /// Construct a new `SpaceLightTexture` for the specified size of [`Space`],
     /// with no data.
     pub fn new(label_prefix: &str, device: &wgpu::Device, bounds: GridAab) -> Self {
-        // Boundary of 1 extra cube automatically captures sky light.
-        let texture_bounds = bounds.expand(FaceMap {
-            px: 1,
-            py: 1,
-            pz: 1,
-            nx: 0,
-            ny: 0,
-            nz: 0,
-        });
+        let texture_bounds = Self::texture_bounds_for(bounds);
         let texture = device.create_texture(&wgpu::TextureDescriptor {
             size: size_vector_to_extent(texture_bounds.size()),
             mip_level_count: 1,
             sample_count: 1,
             dimension: wgpu::TextureDimension::D3,
-            format: wgpu::TextureFormat::Rgba8Uint,
+            format: wgpu::TextureFormat::Rgba16Uint,
             view_formats: &[],
             usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
             label: Some(&format!("{label_prefix} space light")),
@@ -648,9 +824,28 @@ impl SpaceLightTexture {
         }
     }
 
+    /// Returns the region of cube coordinates the texture should cover for a space with
+    /// the given bounds.
+    fn texture_bounds_for(bounds: GridAab) -> GridAab {
+        // Boundary of 1 extra cube automatically captures sky light.
+        bounds.expand(FaceMap {
+            px: 1,
+            py: 1,
+            pz: 1,
+            nx: 0,
+            ny: 0,
+            nz: 0,
+        })
+    }
+
+    /// Returns whether this texture was created for a space with the given bounds.
+    pub fn fits(&self, bounds: GridAab) -> bool {
+        self.texture_bounds == Self::texture_bounds_for(bounds)
+    }
+
     /// Copy the specified region of light data.
     pub fn update(&mut self, queue: &wgpu::Queue, space: &Space, region: GridAab) -> usize {
-        let mut data: Vec<[u8; 4]> = Vec::with_capacity(region.volume());
+        let mut data: Vec<[u16; 4]> = Vec::with_capacity(region.volume());
         // TODO: Enable circular operation and eliminate the need for the offset of the
         // coordinates (texture_bounds.lower_bounds() and light_offset in the shader)
         // by doing a coordinate wrap-around -- the shader and the Space will agree
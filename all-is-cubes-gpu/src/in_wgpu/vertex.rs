@@ -1,5 +1,5 @@
-use all_is_cubes::cgmath::{Point3, Vector3};
-use all_is_cubes::math::{GridPoint, GridVector};
+use all_is_cubes::cgmath::{Point2, Point3, Vector3};
+use all_is_cubes::math::{FreeCoordinate, GridPoint, GridVector};
 use all_is_cubes_mesh::{BlockVertex, Coloring, GfxVertex};
 
 use crate::DebugLineVertex;
@@ -25,8 +25,9 @@ pub(crate) struct WgpuBlockVertex {
     ///   The scale factor 256 is chosen as being greater than the smallest [`Resolution`]
     ///   available. (Equal would also work.)
     /// * The second u32 is
-    ///   `position.z * 256 | (face << 16)`
-    ///   where `face` is a `Face6` converted to integer.
+    ///   `position.z * 256 | (face << 16) | (textured << 24)`
+    ///   where `face` is a `Face6` converted to integer, and `textured` is 1 if
+    ///   `color_or_texture` contains texture coordinates and 0 if it is a color.
     ///
     /// Vertex position is added to `cube` to make the true vertex position.
     ///
@@ -34,23 +35,27 @@ pub(crate) struct WgpuBlockVertex {
     /// convenience and making efficient use of `u32` bits. (`u32` is the minimum size
     /// of integer that WGSL allows.)
     position_in_cube_and_normal_packed: [u32; 2],
-    /// Packed format:
-    /// * If `[3]` is in the range 0.0 to 1.0, then the attribute is a linear RGBA color.
-    /// * If `[3]` is -1.0, then the first three components are 3D texture coordinates.
-    color_or_texture: [f32; 4],
+    /// Either a linear RGBA color, or 3D texture coordinates and an unused fourth
+    /// component, as indicated by the `textured` bit above. All components are
+    /// normalized fixed point (0 is 0.0 and [`u16::MAX`] is 1.0).
+    color_or_texture: [u16; 4],
     /// Interpolated texture coordinates are clamped to be ≥ this value, to avoid bleeding.
-    clamp_min: [f32; 3],
+    /// Normalized fixed point like `color_or_texture`.
+    clamp_min: [u16; 3],
     /// Interpolated texture coordinates are clamped to be ≤ this value, to avoid bleeding.
-    clamp_max: [f32; 3],
+    /// Normalized fixed point like `color_or_texture`.
+    clamp_max: [u16; 3],
 }
 
 impl WgpuBlockVertex {
     const ATTRIBUTE_LAYOUT: &'static [wgpu::VertexAttribute] = &wgpu::vertex_attr_array![
         0 => Uint32, // cube_packed
         1 => Uint32x2, // position_in_cube_and_normal_packed
-        2 => Float32x4, // color_or_texture
-        3 => Float32x3, // clamp_min
-        4 => Float32x3, // clamp_max
+        2 => Unorm16x4, // color_or_texture
+        // The vertex formats have no 3-component 16-bit types, so the clamps are
+        // regrouped as 4 + 2 components.
+        3 => Unorm16x4, // clamp_min, clamp_max[0]
+        4 => Unorm16x2, // clamp_max[1..3]
         // location numbers must not clash with WgpuInstanceData
     ];
 
@@ -71,22 +76,22 @@ impl From<BlockVertex<TexPoint>> for WgpuBlockVertex {
         let cube_packed = 0; // will be overwritten later by instantiate_vertex()
         let normal = vertex.face as u32;
 
-        let position_in_cube_and_normal_packed = [
-            position_in_cube_fixed.x | (position_in_cube_fixed.y << 16),
-            position_in_cube_fixed.z | (normal << 16),
-        ];
+        let pack_position_and_normal = |textured: u32| {
+            [
+                position_in_cube_fixed.x | (position_in_cube_fixed.y << 16),
+                position_in_cube_fixed.z | (normal << 16) | (textured << 24),
+            ]
+        };
         match vertex.coloring {
             Coloring::Solid(color) => {
-                let mut color_attribute: [f32; 4] = color.into();
-                // Clamp out-of-range alpha values so they fit into the
-                // VertexColorOrTexture protocol (not less than zero).
-                color_attribute[3] = color_attribute[3].clamp(0., 1.);
+                // Out-of-range components are clamped by the conversion.
+                let color: [f32; 4] = color.into();
                 Self {
                     cube_packed,
-                    position_in_cube_and_normal_packed,
-                    color_or_texture: color_attribute,
-                    clamp_min: [0., 0., 0.],
-                    clamp_max: [0., 0., 0.],
+                    position_in_cube_and_normal_packed: pack_position_and_normal(0),
+                    color_or_texture: color.map(to_unorm16),
+                    clamp_min: [0, 0, 0],
+                    clamp_max: [0, 0, 0],
                 }
             }
             Coloring::Texture {
@@ -95,15 +100,22 @@ impl From<BlockVertex<TexPoint>> for WgpuBlockVertex {
                 clamp_max,
             } => Self {
                 cube_packed,
-                position_in_cube_and_normal_packed,
-                color_or_texture: [tc[0], tc[1], tc[2], -1.0],
-                clamp_min: clamp_min.into(),
-                clamp_max: clamp_max.into(),
+                position_in_cube_and_normal_packed: pack_position_and_normal(1),
+                color_or_texture: [tc[0], tc[1], tc[2], 0.0].map(to_unorm16),
+                clamp_min: <[f32; 3]>::from(clamp_min).map(to_unorm16),
+                clamp_max: <[f32; 3]>::from(clamp_max).map(to_unorm16),
             },
         }
     }
 }
 
+/// Convert a value in the range 0.0 to 1.0 to the fixed-point form read by the
+/// `Unorm16` vertex formats, clamping if it is out of range.
+#[inline]
+fn to_unorm16(value: f32) -> u16 {
+    (value.clamp(0.0, 1.0) * f32::from(u16::MAX)).round() as u16
+}
+
 impl GfxVertex for WgpuBlockVertex {
     const WANTS_DEPTH_SORTING: bool = true;
     /// TODO: no reason this should be f32 other than scaling to fractional integers.
@@ -209,6 +221,38 @@ impl DebugLineVertex for WgpuLinesVertex {
     }
 }
 
+/// Vertex of the faces of portal blocks, which display the view from the portal's target
+/// as a texture.
+#[derive(Clone, Copy, Debug, PartialEq, bytemuck::Pod, bytemuck::Zeroable)]
+#[repr(C)]
+pub(crate) struct WgpuPortalVertex {
+    position: [f32; 3],
+    /// Coordinates in the portal view texture, with (0, 0) being the top left corner.
+    texcoord: [f32; 2],
+}
+
+impl WgpuPortalVertex {
+    const ATTRIBUTE_LAYOUT: &'static [wgpu::VertexAttribute] = &wgpu::vertex_attr_array![
+        0 => Float32x3,
+        1 => Float32x2,
+    ];
+
+    pub fn desc() -> wgpu::VertexBufferLayout<'static> {
+        wgpu::VertexBufferLayout {
+            array_stride: std::mem::size_of::<Self>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Vertex,
+            attributes: Self::ATTRIBUTE_LAYOUT,
+        }
+    }
+
+    pub fn new(position: Point3<FreeCoordinate>, texcoord: Point2<f32>) -> Self {
+        Self {
+            position: position.map(|c| c as f32).into(),
+            texcoord: texcoord.into(),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use all_is_cubes::math::{Face6, Rgba};
@@ -221,8 +265,9 @@ mod tests {
     /// the struct is designed to have a fixed layout communicating to the shader anyway.
     #[test]
     fn vertex_size() {
-        assert_eq!(mem::size_of::<WgpuBlockVertex>(), 52);
+        assert_eq!(mem::size_of::<WgpuBlockVertex>(), 32);
         assert_eq!(mem::size_of::<WgpuLinesVertex>(), 28);
+        assert_eq!(mem::size_of::<WgpuPortalVertex>(), 20);
     }
 
     /// Test implementation of [`GfxVertex::position()`],
@@ -238,4 +283,21 @@ mod tests {
         vertex.instantiate_vertex(WgpuBlockVertex::instantiate_block(Point3::new(100, 50, 7)));
         assert_eq!(GfxVertex::position(&vertex), Point3::new(100.25, 50.0, 8.0));
     }
+
+    #[test]
+    fn block_vertex_texture_packing() {
+        let vertex = WgpuBlockVertex::from(BlockVertex {
+            position: Point3::new(0.0, 0.0, 0.0),
+            face: Face6::PX,
+            coloring: Coloring::Texture {
+                pos: Point3::new(0.5, 0.0, 1.0),
+                clamp_min: Point3::new(0.25, 0.0, 2.0),
+                clamp_max: Point3::new(0.75, 1.0, 1.0),
+            },
+        });
+        assert_eq!(vertex.position_in_cube_and_normal_packed[1] >> 24, 1);
+        assert_eq!(vertex.color_or_texture, [32768, 0, 65535, 0]);
+        assert_eq!(vertex.clamp_min, [16384, 0, 65535]);
+        assert_eq!(vertex.clamp_max, [49151, 65535, 65535]);
+    }
 }
@@ -9,14 +9,14 @@ use all_is_cubes::camera::Flaws;
 use all_is_cubes::cgmath::{Point2, Point3, Transform as _};
 use all_is_cubes::math::{
     Face6, Face7, FaceMap, FreeCoordinate, GridAab, GridArray, GridCoordinate, OpacityCategory,
-    Rgba,
+    Rgb, Rgba,
 };
 use all_is_cubes::space::Space;
 
 use crate::{
     copy_voxels_into_existing_texture, copy_voxels_to_texture, push_quad, BlockVertex,
-    GreedyMesher, IndexVec, MeshOptions, QuadColoring, QuadTransform, TextureAllocator,
-    TextureTile,
+    GreedyMesher, IndexVec, MeshOptions, PoolableMesh, QuadColoring, QuadTransform,
+    TextureAllocator, TextureTile,
 };
 
 /// Part of the triangle mesh calculated for a [`Block`], stored in a [`BlockMesh`] keyed
@@ -88,7 +88,8 @@ impl<V> Default for BlockFaceMesh<V> {
 /// * `V` is the type of vertices.
 /// * `T` is the type of textures, which come from a [`TextureAllocator`].
 ///
-/// TODO: Add methods so this can be read out directly if you really want to.
+/// A [`BlockMesh`] may be read out directly with [`BlockMesh::extend_opaque()`], for
+/// drawing a block by itself rather than as part of a space.
 ///
 /// [`Block`]: all_is_cubes::block::Block
 #[derive(Clone, Debug, PartialEq, Eq)]
@@ -141,6 +142,23 @@ impl<V, T> BlockMesh<V, T> {
         flaws: Flaws::empty(),
     };
 
+    fn clear(&mut self) {
+        let Self {
+            face_vertices,
+            interior_vertices,
+            textures_used,
+            voxel_opacity_mask,
+            flaws,
+        } = self;
+        for (_, fv) in face_vertices.iter_mut() {
+            fv.clear();
+        }
+        interior_vertices.clear();
+        textures_used.clear();
+        *voxel_opacity_mask = None;
+        *flaws = Flaws::empty();
+    }
+
     /// Iterate over all seven [`BlockFaceMesh`]es, including the interior vertices.
     ///
     /// This function is not public because it is mostly a helper for higher-level
@@ -161,6 +179,33 @@ impl<V, T> BlockMesh<V, T> {
         &self.textures_used
     }
 
+    /// Appends all of this mesh's vertices, and the indices of its opaque triangles, to
+    /// `vertices` and `indices`, as if it were drawn alone with nothing adjacent to hide
+    /// any of its faces. The appended indices are offset by the existing length of
+    /// `vertices`.
+    ///
+    /// Each vertex is passed through `transform` along with the face mesh it belongs to.
+    /// Transparent triangles are omitted, since they would need to be depth-sorted
+    /// together with everything else drawn.
+    pub fn extend_opaque<W>(
+        &self,
+        vertices: &mut Vec<W>,
+        indices: &mut Vec<u32>,
+        mut transform: impl FnMut(&V) -> W,
+    ) {
+        for (_, face_mesh) in self.all_face_meshes() {
+            let base = u32::try_from(vertices.len()).expect("too many vertices");
+            vertices.extend(face_mesh.vertices.iter().map(&mut transform));
+            indices.extend(
+                face_mesh
+                    .indices_opaque
+                    .as_slice(..)
+                    .iter_u32()
+                    .map(|i| base + i),
+            );
+        }
+    }
+
     /// Reports any flaws in this mesh: reasons why using it to create a rendering would
     /// fail to accurately represent the scene.
     pub fn flaws(&self) -> Flaws {
@@ -209,6 +254,18 @@ impl<V: 'static, T: 'static> BlockMesh<V, T> {
     pub const EMPTY_REF: &'static Self = &Self::EMPTY;
 }
 
+impl<V, T> PoolableMesh for BlockMesh<V, T> {
+    fn clear_for_reuse(&mut self) {
+        self.clear();
+    }
+
+    fn vertex_capacity(&self) -> usize {
+        self.all_face_meshes()
+            .map(|(_, face_mesh)| face_mesh.vertices.capacity())
+            .sum()
+    }
+}
+
 impl<V, T> BlockMesh<V, T>
 where
     V: From<BlockVertex<<T as TextureTile>::Point>>,
@@ -226,23 +283,6 @@ where
         new_self
     }
 
-    fn clear(&mut self) {
-        let Self {
-            face_vertices,
-            interior_vertices,
-            textures_used,
-            voxel_opacity_mask,
-            flaws,
-        } = self;
-        for (_, fv) in face_vertices.iter_mut() {
-            fv.clear();
-        }
-        interior_vertices.clear();
-        textures_used.clear();
-        *voxel_opacity_mask = None;
-        *flaws = Flaws::empty();
-    }
-
     /// Generate the [`BlockMesh`] for a block's current appearance, writing it into
     /// `self`. This is equivalent to [`BlockMesh::new()`] except that it reuses existing
     /// memory allocations.
@@ -260,7 +300,11 @@ where
         self.clear();
 
         // If this is true, avoid using vertex coloring even on solid rectangles.
-        let prefer_textures = block.attributes.animation_hint.redefinition != AnimationChange::None;
+        // A block which is about to be replaced by one of the same shape (such as the next
+        // frame of a `Modifier::Animate`) may then have its texture updated in place.
+        let hint = block.attributes.animation_hint;
+        let prefer_textures = hint.redefinition != AnimationChange::None
+            || hint.replacement == AnimationChange::ColorSameCategory;
 
         let flaws = &mut self.flaws;
 
@@ -272,11 +316,10 @@ where
             &block.voxels
         };
         match *voxels {
-            Evoxels::One(Evoxel {
-                color: block_color, ..
-            }) => {
-                let block_color = options.transparency.limit_alpha(block_color);
+            Evoxels::One(_) | Evoxels::Faces(..) => {
+                let face_colors = voxels.single_voxel_face_colors().unwrap();
                 for (face, face_mesh) in self.face_vertices.iter_mut() {
+                    let block_color = options.transparency.limit_alpha(face_colors[face]);
                     if !block_color.fully_transparent() {
                         face_mesh.vertices.reserve_exact(4);
                         push_quad(
@@ -302,6 +345,13 @@ where
             Evoxels::Many(resolution, ref voxels_array) => {
                 let mut used_any_vertex_colors = false;
 
+                // Emission can only be displayed using textures.
+                let prefer_textures = prefer_textures
+                    || voxels_array
+                        .bounds()
+                        .interior_iter()
+                        .any(|cube| voxels_array[cube].emission != Rgb::ZERO);
+
                 // Exit when the voxel data is not at all in the right volume.
                 // This dodges some integer overflow cases on bad input.
                 // TODO: Add a test for this case
@@ -10,12 +10,12 @@ use all_is_cubes::camera::{Camera, Flaws};
 use all_is_cubes::cgmath::Point3;
 use all_is_cubes::chunking::{cube_to_chunk, point_to_chunk, ChunkChart, ChunkPos, OctantMask};
 use all_is_cubes::listen::{Listen as _, Listener};
-use all_is_cubes::math::{FreeCoordinate, GridCoordinate, GridPoint, LineVertex};
+use all_is_cubes::math::{FreeCoordinate, GridCoordinate, GridPoint, LineVertex, NotNan};
 use all_is_cubes::space::{BlockIndex, Space, SpaceChange};
 use all_is_cubes::universe::URef;
 use all_is_cubes::util::{CustomFormat, StatusText, TimeStats};
 
-use crate::{GfxVertex, MeshOptions, TextureAllocator, TextureTile};
+use crate::{GfxVertex, MeshOptions, MeshPool, SpaceMesh, TextureAllocator, TextureTile};
 
 mod blocks;
 use blocks::VersionedBlockMeshes;
@@ -36,8 +36,6 @@ const LOG_CHUNK_UPDATES: bool = false;
 /// Each chunk, a [`ChunkMesh`], owns a data value of type `D`, which is
 /// initialized using `D::default()`. This value may be a reference to a corresponding
 /// GPU buffer, for example. It will usually need to be an [`Option`] of something.
-///
-/// [`SpaceMesh`]: crate::SpaceMesh
 #[derive(Debug)]
 pub struct ChunkedSpaceMesh<D, Vert, Tex, const CHUNK_SIZE: GridCoordinate>
 where
@@ -54,12 +52,20 @@ where
     /// in `todo.read().unwrap().chunks`.
     chunks: FnvHashMap<ChunkPos<CHUNK_SIZE>, ChunkMesh<D, Vert, Tex, CHUNK_SIZE>>,
 
+    /// Meshes of chunks which have been dropped for being out of range, kept so that
+    /// their allocations may be reused by newly created chunks.
+    spare_chunk_meshes: MeshPool<SpaceMesh<Vert, Tex::Tile>>,
+
     /// Resized as needed upon each [`Self::update_blocks_and_some_chunks()`].
     chunk_chart: ChunkChart<CHUNK_SIZE>,
 
     /// The chunk in which the last [`Camera`] provided is located.
     view_chunk: ChunkPos<CHUNK_SIZE>,
 
+    /// Distance from `view_chunk` within which every chunk has a mesh, as of the last
+    /// update. See [`Self::ready_distance()`].
+    ready_distance: FreeCoordinate,
+
     /// Whether, on the previous frame, we did not finish updating all visible chunks.
     ///
     /// If so, then we prioritize adding new chunks over updating existing ones,
@@ -93,8 +99,10 @@ where
             todo: todo_rc,
             block_meshes: VersionedBlockMeshes::new(),
             chunks: FnvHashMap::default(),
+            spare_chunk_meshes: MeshPool::new(),
             chunk_chart: ChunkChart::new(0.0),
             view_chunk: ChunkPos(Point3::new(0, 0, 0)),
+            ready_distance: 0.0,
             did_not_finish_chunks: true,
             last_mesh_options: None,
             zero_time: Instant::now(),
@@ -107,7 +115,7 @@ where
         &self.space
     }
 
-    /// Returns a [`ChunkChart`] for the view distance used by the most recent
+    /// Returns a [`ChunkChart`] for the chunk loading distance used by the most recent
     /// [`Self::update_blocks_and_some_chunks`].
     pub fn chunk_chart(&self) -> &ChunkChart<CHUNK_SIZE> {
         &self.chunk_chart
@@ -216,7 +224,8 @@ where
             self.complete_time = None;
         }
 
-        self.chunk_chart.resize_if_needed(camera.view_distance());
+        let loading_distance = camera.chunk_loading_distance();
+        self.chunk_chart.resize_if_needed(loading_distance);
 
         let prep_to_update_meshes_time = Instant::now();
 
@@ -242,9 +251,15 @@ where
             // Not urgently needed, though.
             let cache_distance = FreeCoordinate::from(CHUNK_SIZE);
             let retention_distance_squared =
-                (camera.view_distance().ceil() + cache_distance).powi(2) as i32;
-            self.chunks.retain(|pos, _| {
-                pos.min_distance_squared_from(view_chunk) <= retention_distance_squared
+                (loading_distance.ceil() + cache_distance).powi(2) as i32;
+            let spare_chunk_meshes = &mut self.spare_chunk_meshes;
+            self.chunks.retain(|pos, chunk| {
+                let retain =
+                    pos.min_distance_squared_from(view_chunk) <= retention_distance_squared;
+                if !retain {
+                    spare_chunk_meshes.put(chunk.take_mesh());
+                }
+                retain
             });
             todo.chunks.retain(|pos, _| {
                 pos.min_distance_squared_from(view_chunk) <= retention_distance_squared
@@ -256,6 +271,7 @@ where
         let mut chunk_mesh_generation_times = TimeStats::default();
         let mut chunk_mesh_callback_times = TimeStats::default();
         let mut did_not_finish = false;
+        let mut first_missing_chunk = None;
         for p in self.chunk_chart.chunks(view_chunk, OctantMask::ALL) {
             if !chunk_bounds.contains_cube(p.0) {
                 // Chunk not in the Space
@@ -265,6 +281,12 @@ where
             let this_chunk_start_time = Instant::now();
             if this_chunk_start_time > deadline {
                 did_not_finish = true;
+                // Out of time, but keep looking (cheaply) for the nearest chunk with no
+                // mesh at all, to determine the ready distance.
+                if self.chunks.contains_key(&p) {
+                    continue;
+                }
+                first_missing_chunk = Some(p);
                 break;
             }
 
@@ -285,8 +307,8 @@ where
                 let chunk = chunk_entry.or_insert_with(|| {
                     // Remember that we want to track dirty flags for this chunk.
                     todo.chunks.insert(p, ChunkTodo::CLEAN);
-                    // Generate new chunk.
-                    ChunkMesh::new(p)
+                    // Generate new chunk, reusing a dropped chunk's allocations if available.
+                    ChunkMesh::new(p, self.spare_chunk_meshes.take(0))
                 });
                 chunk.recompute_mesh(
                     todo.chunks.get_mut(&p).unwrap(), // TODO: can we eliminate the double lookup with a todo entry?
@@ -304,6 +326,12 @@ where
             }
         }
         self.did_not_finish_chunks = did_not_finish;
+        // Since the chart is ordered by distance, every chunk nearer than the first
+        // missing one has a mesh.
+        self.ready_distance = match first_missing_chunk {
+            Some(p) => FreeCoordinate::from(p.min_distance_squared_from(view_chunk)).sqrt(),
+            None => loading_distance,
+        };
         let chunk_scan_end_time = Instant::now();
 
         // Update the drawing order of transparent parts of the chunk the camera is in.
@@ -349,6 +377,7 @@ where
             chunk_mesh_callback_times,
             depth_sort_time: depth_sort_end_time.map(|t| t.duration_since(chunk_scan_end_time)),
             block_updates,
+            ready_distance: NotNan::new(self.ready_distance).unwrap_or_default(),
 
             // TODO: remember this rather than computing it
             chunk_count: self.chunks.len(),
@@ -367,6 +396,17 @@ where
         self.view_chunk
     }
 
+    /// Returns the distance from the [view chunk](Self::view_chunk) within which every
+    /// chunk of the space has a mesh (possibly a stale one), as of the most recent
+    /// [`Self::update_blocks_and_some_chunks`].
+    ///
+    /// If all chunks are ready, this is the camera's
+    /// [chunk loading distance](Camera::chunk_loading_distance). Renderers may limit the
+    /// fog distance to this value, to hide chunks which are not yet ready.
+    pub fn ready_distance(&self) -> FreeCoordinate {
+        self.ready_distance
+    }
+
     /// Produces lines that visualize the boundaries of visible nonempty chunks.
     #[doc(hidden)] // TODO: good public API?
     pub fn chunk_debug_lines(&self, camera: &Camera, output: &mut impl Extend<LineVertex>) {
@@ -397,6 +437,9 @@ pub struct CsmUpdateInfo {
     depth_sort_time: Option<Duration>,
     /// Time spent on building block meshes this frame.
     pub block_updates: TimeStats,
+    /// Distance within which all chunks have meshes; see
+    /// [`ChunkedSpaceMesh::ready_distance()`].
+    pub ready_distance: NotNan<FreeCoordinate>,
 
     /// Number of chunks that currently exist.
     pub chunk_count: usize,
@@ -415,6 +458,7 @@ impl CustomFormat<StatusText> for CsmUpdateInfo {
             chunk_mesh_callback_times,
             depth_sort_time,
             block_updates,
+            ready_distance,
             chunk_count,
             chunk_total_cpu_byte_size,
         } = self;
@@ -427,7 +471,7 @@ impl CustomFormat<StatusText> for CsmUpdateInfo {
                       mesh gen {chunk_mesh_generation_times}
                       upload   {chunk_mesh_callback_times}
                       depthsort {depth_sort_time}
-                Mem: {chunk_mib} MiB for {chunk_count} chunks\
+                Mem: {chunk_mib} MiB for {chunk_count} chunks, ready within {ready_distance:.0}\
             "},
             flaws = flaws,
             prep_time = prep_time.custom_format(StatusText),
@@ -440,6 +484,7 @@ impl CustomFormat<StatusText> for CsmUpdateInfo {
                 .custom_format(StatusText),
             chunk_mib = chunk_total_cpu_byte_size / (1024 * 1024),
             chunk_count = chunk_count,
+            ready_distance = ready_distance.into_inner(),
         )
     }
 }
@@ -516,6 +561,9 @@ impl<const CHUNK_SIZE: GridCoordinate> Listener<SpaceChange> for TodoListener<CH
                             todo.blocks.insert(index);
                         }
                     }
+                    SpaceChange::Weather => {
+                        // Meshes are not affected by weather
+                    }
                 }
             }
         }
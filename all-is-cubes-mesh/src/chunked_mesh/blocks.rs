@@ -3,17 +3,24 @@ use std::num::NonZeroU32;
 use fnv::FnvHashSet;
 use instant::{Duration, Instant};
 
-use all_is_cubes::block::{EvaluatedBlock, Resolution};
+use all_is_cubes::block::{Block, EvaluatedBlock, Resolution};
 use all_is_cubes::space::{BlockIndex, Space};
 use all_is_cubes::util::{CustomFormat as _, StatusText, TimeStats};
 
-use crate::{BlockMesh, GetBlockMesh, GfxVertex, MeshOptions, TextureAllocator, TextureTile};
+use crate::{
+    BlockMesh, GetBlockMesh, GfxVertex, MeshOptions, MeshPool, PoolableMesh as _, TextureAllocator,
+    TextureTile,
+};
 
 #[derive(Debug)]
 pub(crate) struct VersionedBlockMeshes<Vert, Tile> {
     /// Indices of this vector are block IDs in the Space.
     pub(crate) meshes: Vec<VersionedBlockMesh<Vert, Tile>>,
 
+    /// Meshes which have been replaced, kept so that their allocations may be reused
+    /// by the next recomputation.
+    spare_meshes: MeshPool<BlockMesh<Vert, Tile>>,
+
     last_version_counter: NonZeroU32,
 }
 
@@ -25,6 +32,7 @@ where
     pub fn new() -> Self {
         Self {
             meshes: Vec::new(),
+            spare_meshes: MeshPool::new(),
             last_version_counter: NonZeroU32::new(u32::MAX).unwrap(),
         }
     }
@@ -34,6 +42,7 @@ where
     /// extra data.
     pub fn clear(&mut self) {
         self.meshes.clear();
+        self.spare_meshes.clear();
     }
 
     /// Update block meshes based on the given [`Space`].
@@ -73,7 +82,9 @@ where
             let old_len = self.meshes.len();
             let new_len = block_data.len();
             if old_len > new_len {
-                self.meshes.truncate(new_len);
+                for removed in self.meshes.drain(new_len..) {
+                    self.spare_meshes.put(removed.mesh);
+                }
             } else {
                 // Increase length, and initialize the new elements.
                 // This must be done quickly, so that we do not have a hiccup when initializing
@@ -89,12 +100,14 @@ where
                         .push(if evaluated.resolution() > Resolution::R1 {
                             // If the block has voxels, generate a placeholder mesh,
                             // marked as not-ready so it will be replaced eventually.
+                            // Connections are not worth computing for the placeholder.
                             VersionedBlockMesh {
                                 mesh: BlockMesh::new(
                                     evaluated,
                                     block_texture_allocator,
                                     &fast_options,
                                 ),
+                                connected: Box::default(),
                                 version: BlockMeshVersion::NotReady,
                             }
                         } else {
@@ -106,6 +119,12 @@ where
                                     block_texture_allocator,
                                     mesh_options,
                                 ),
+                                connected: connected_meshes(
+                                    bd.block(),
+                                    evaluated,
+                                    block_texture_allocator,
+                                    mesh_options,
+                                ),
                                 version: current_version_number,
                             }
                         });
@@ -132,18 +151,28 @@ where
             //     None => 1,
             // };
 
-            if current_mesh_entry
-                .mesh
-                .try_update_texture_only(new_evaluated_block)
+            let new_connected_meshes = connected_meshes(
+                bd.block(),
+                new_evaluated_block,
+                block_texture_allocator,
+                mesh_options,
+            );
+
+            if new_connected_meshes.is_empty()
+                && current_mesh_entry.connected.is_empty()
+                && current_mesh_entry
+                    .mesh
+                    .try_update_texture_only(new_evaluated_block)
             {
                 // Updated the texture in-place. No need for mesh updates.
             } else {
-                // Compute a new mesh.
-                // TODO: Try using BlockMesh::compute() to reuse allocations.
-                // The catch is that then we will no longer be able to compare the new mesh
-                // to the existing mesh below, so this won't be a pure win.
-                let new_block_mesh =
-                    BlockMesh::new(new_evaluated_block, block_texture_allocator, mesh_options);
+                // Compute a new mesh, reusing the allocations of a previously discarded one.
+                // (We can't compute into the existing mesh, because we need to compare
+                // against it below.)
+                let mut new_block_mesh = self
+                    .spare_meshes
+                    .take(current_mesh_entry.mesh.vertex_capacity());
+                new_block_mesh.compute(new_evaluated_block, block_texture_allocator, mesh_options);
 
                 // Only invalidate the chunks if we actually have different data.
                 // Note: This comparison depends on such things as the definition of PartialEq
@@ -154,16 +183,23 @@ where
                 // updated texture tiles, which might have geometry gaps or otherwise be obviously
                 // inconsistent.)
                 if new_block_mesh != current_mesh_entry.mesh
+                    || new_connected_meshes != current_mesh_entry.connected
                     || current_mesh_entry.version == BlockMeshVersion::NotReady
                 {
-                    *current_mesh_entry = VersionedBlockMesh {
-                        mesh: new_block_mesh,
-                        version: current_version_number,
-                    };
+                    let old_entry = std::mem::replace(
+                        current_mesh_entry,
+                        VersionedBlockMesh {
+                            mesh: new_block_mesh,
+                            connected: new_connected_meshes,
+                            version: current_version_number,
+                        },
+                    );
+                    self.spare_meshes.put(old_entry.mesh);
                 } else {
                     // The new mesh is identical to the old one (which might happen because
                     // interior voxels or non-rendered attributes were changed), so don't invalidate
                     // the chunks.
+                    self.spare_meshes.put(new_block_mesh);
                 }
             }
             let duration = stats.record_consecutive_interval(&mut last_start_time, Instant::now());
@@ -190,12 +226,58 @@ impl<'a, Vert: 'static, Tile: 'static> GetBlockMesh<'a, Vert, Tile>
             .map(|vbm| &vbm.mesh)
             .unwrap_or(BlockMesh::<Vert, Tile>::EMPTY_REF)
     }
+
+    fn get_connected_block_mesh(
+        &mut self,
+        index: BlockIndex,
+        variant_index: usize,
+    ) -> &'a BlockMesh<Vert, Tile> {
+        match self.meshes.get(usize::from(index)) {
+            Some(vbm) => vbm.connected.get(variant_index).unwrap_or(&vbm.mesh),
+            None => BlockMesh::<Vert, Tile>::EMPTY_REF,
+        }
+    }
+}
+
+/// Compute a mesh for each of the [`Modifier::Connect`] variants of `block`, indexed by
+/// variant index, or an empty list if it has none.
+///
+/// Variants which are missing or fail to evaluate are given the mesh of `evaluated`,
+/// the evaluation of `block` itself.
+///
+/// [`Modifier::Connect`]: all_is_cubes::block::Modifier::Connect
+fn connected_meshes<Vert, A>(
+    block: &Block,
+    evaluated: &EvaluatedBlock,
+    block_texture_allocator: &A,
+    mesh_options: &MeshOptions,
+) -> Box<[BlockMesh<Vert, A::Tile>]>
+where
+    Vert: GfxVertex<TexPoint = <A::Tile as TextureTile>::Point>,
+    A: TextureAllocator,
+{
+    let Some((_, variants)) = block.connection_variants() else {
+        return Box::default();
+    };
+    variants
+        .into_iter()
+        .map(|variant| match variant.as_ref().map(Block::evaluate) {
+            Some(Ok(variant_evaluated)) => {
+                BlockMesh::new(&variant_evaluated, block_texture_allocator, mesh_options)
+            }
+            None | Some(Err(_)) => BlockMesh::new(evaluated, block_texture_allocator, mesh_options),
+        })
+        .collect()
 }
 
 /// Entry in [`VersionedBlockMeshes`].
 #[derive(Debug)]
 pub(crate) struct VersionedBlockMesh<Vert, Tile> {
     pub(crate) mesh: BlockMesh<Vert, Tile>,
+    /// Meshes for each of the block's [`Modifier::Connect`] variants, if it has any.
+    ///
+    /// [`Modifier::Connect`]: all_is_cubes::block::Modifier::Connect
+    pub(crate) connected: Box<[BlockMesh<Vert, Tile>]>,
     /// Version ID used to track whether chunks have stale block meshes (ones that don't
     /// match the current definition of that block-index in the space).
     pub(crate) version: BlockMeshVersion,
@@ -35,10 +35,13 @@ where
     Tex: TextureAllocator,
     Tex::Tile: 'static,
 {
-    pub(crate) fn new(position: ChunkPos<CHUNK_SIZE>) -> Self {
+    /// Creates a chunk with no mesh data yet. `mesh` must be empty, but may have
+    /// allocated capacity to reuse.
+    pub(crate) fn new(position: ChunkPos<CHUNK_SIZE>, mesh: SpaceMesh<Vert, Tex::Tile>) -> Self {
+        debug_assert!(mesh.is_empty());
         Self {
             position,
-            mesh: SpaceMesh::default(),
+            mesh,
             render_data: D::default(),
             block_dependencies: Vec::new(),
             update_debug: false,
@@ -50,6 +53,12 @@ where
         &self.mesh
     }
 
+    /// Removes the mesh from this chunk (leaving an empty one), so that its allocations
+    /// may be reused when this chunk is being discarded.
+    pub(crate) fn take_mesh(&mut self) -> SpaceMesh<Vert, Tex::Tile> {
+        std::mem::take(&mut self.mesh)
+    }
+
     #[inline]
     pub fn position(&self) -> ChunkPos<CHUNK_SIZE> {
         self.position
@@ -196,6 +196,31 @@ fn basic_chunk_presence() {
     // TODO: Check that chunks end at the view distance.
 }
 
+#[test]
+fn ready_distance() {
+    let mut tester = CsmTester::new(Space::empty_positive(48, 16, 16), LARGE_VIEW_DISTANCE);
+    let mut options = tester.camera.options().clone();
+    options.chunk_loading_distance = Some(notnan!(40.0));
+    tester.camera.set_options(options);
+    assert_eq!(tester.csm.ready_distance(), 0.0);
+
+    // With no time to work, no chunks are ready.
+    let info = tester.csm.update_blocks_and_some_chunks(
+        &tester.camera,
+        &NoTextures,
+        Instant::now() - Duration::from_secs(1),
+        |_| {},
+    );
+    assert_eq!(tester.csm.ready_distance(), 0.0);
+    assert_eq!(info.ready_distance, notnan!(0.0));
+
+    // With enough time, all chunks within the loading distance are ready.
+    let info = tester.update(|_| {});
+    assert_eq!(tester.csm.ready_distance(), 40.0);
+    assert_eq!(info.ready_distance, notnan!(40.0));
+    assert_ne!(None, tester.csm.chunk(ChunkPos::new(2, 0, 0)));
+}
+
 #[test]
 fn sort_view_every_frame_only_if_transparent() {
     let mut tester = CsmTester::new(Space::empty_positive(1, 1, 1), LARGE_VIEW_DISTANCE);
@@ -68,6 +68,8 @@ mod space_mesh;
 pub use space_mesh::*;
 mod planar;
 use planar::*;
+mod pool;
+use pool::*;
 mod texalloc;
 pub use texalloc::*;
 
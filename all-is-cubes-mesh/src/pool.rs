@@ -0,0 +1,166 @@
+//! Reuse of mesh memory allocations.
+//!
+//! This module is internal.
+
+use std::fmt;
+
+/// Maximum number of meshes kept in each size class of a [`MeshPool`].
+/// Meshes offered beyond this limit are dropped.
+const MAX_PER_CLASS: usize = 16;
+
+/// Number of size classes; meshes whose vertex capacity is larger than
+/// `2.pow(SIZE_CLASSES - 1)` all share the last class.
+const SIZE_CLASSES: usize = 24;
+
+/// Mesh types whose allocations can be held by a [`MeshPool`].
+pub(crate) trait PoolableMesh: Default {
+    /// Delete all data, leaving the mesh empty but retaining its allocations.
+    ///
+    /// This must drop any texture tiles the mesh holds, so that they can be deallocated.
+    fn clear_for_reuse(&mut self);
+
+    /// Number of vertices that the mesh can hold without reallocating.
+    fn vertex_capacity(&self) -> usize;
+}
+
+/// Holds cleared meshes so that their buffers may be reused instead of allocating
+/// new ones, when meshes are frequently discarded and recomputed (e.g. animation).
+///
+/// Meshes are grouped by the approximate size (power of 2) of their vertex capacity,
+/// so that a request for a mesh of a given size is likely to get one which fits
+/// without reallocating, but does not pin down much more memory than needed.
+pub(crate) struct MeshPool<M> {
+    classes: Vec<Vec<M>>,
+}
+
+impl<M: PoolableMesh> MeshPool<M> {
+    pub fn new() -> Self {
+        Self {
+            classes: (0..SIZE_CLASSES).map(|_| Vec::new()).collect(),
+        }
+    }
+
+    /// Returns an empty mesh, preferably one with capacity for at least `vertex_count`
+    /// vertices.
+    pub fn take(&mut self, vertex_count: usize) -> M {
+        let wanted = size_class(vertex_count);
+        // Prefer the exact class, then larger ones (which will fit), then smaller ones
+        // (which will at least save some reallocation).
+        let search_order = (wanted..SIZE_CLASSES).chain((0..wanted).rev());
+        for class in search_order {
+            if let Some(mesh) = self.classes[class].pop() {
+                return mesh;
+            }
+        }
+        M::default()
+    }
+
+    /// Clears `mesh` and stores it for reuse, unless the pool is full or the mesh has
+    /// no allocations worth keeping.
+    pub fn put(&mut self, mut mesh: M) {
+        let capacity = mesh.vertex_capacity();
+        if capacity == 0 {
+            return;
+        }
+        let class = &mut self.classes[size_class(capacity)];
+        if class.len() < MAX_PER_CLASS {
+            mesh.clear_for_reuse();
+            class.push(mesh);
+        }
+    }
+
+    /// Drop all stored meshes, releasing their memory.
+    pub fn clear(&mut self) {
+        for class in self.classes.iter_mut() {
+            *class = Vec::new();
+        }
+    }
+
+    /// Number of meshes currently stored.
+    #[cfg(test)]
+    pub fn len(&self) -> usize {
+        self.classes.iter().map(Vec::len).sum()
+    }
+}
+
+impl<M> fmt::Debug for MeshPool<M> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MeshPool")
+            .field(
+                "sizes",
+                &self.classes.iter().map(Vec::len).collect::<Vec<_>>(),
+            )
+            .finish()
+    }
+}
+
+/// Returns the size class for a mesh holding `vertex_count` vertices: the
+/// number of bits needed to represent the count, clamped to the available classes.
+fn size_class(vertex_count: usize) -> usize {
+    let bits = (usize::BITS - vertex_count.leading_zeros()) as usize;
+    bits.min(SIZE_CLASSES - 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        BlockMesh, BlockVertex, MeshOptions, TestTextureAllocator, TestTextureTile, TtPoint,
+    };
+    use all_is_cubes::block::Block;
+    use all_is_cubes::math::Rgba;
+
+    type TestMesh = BlockMesh<BlockVertex<TtPoint>, TestTextureTile>;
+
+    fn nonempty_mesh() -> TestMesh {
+        BlockMesh::new(
+            &Block::from(Rgba::WHITE).evaluate().unwrap(),
+            &TestTextureAllocator::new(),
+            &MeshOptions::dont_care_for_test(),
+        )
+    }
+
+    #[test]
+    fn size_class_boundaries() {
+        assert_eq!(size_class(0), 0);
+        assert_eq!(size_class(1), 1);
+        assert_eq!(size_class(2), 2);
+        assert_eq!(size_class(3), 2);
+        assert_eq!(size_class(4), 3);
+        assert_eq!(size_class(usize::MAX), SIZE_CLASSES - 1);
+    }
+
+    #[test]
+    fn reuses_cleared_allocation() {
+        let mesh = nonempty_mesh();
+        assert!(!mesh.is_empty());
+        let capacity = mesh.vertex_capacity();
+
+        let mut pool = MeshPool::new();
+        pool.put(mesh);
+        assert_eq!(pool.len(), 1);
+
+        let reused = pool.take(capacity);
+        assert_eq!(pool.len(), 0);
+        assert!(reused.is_empty());
+        assert_eq!(reused.vertex_capacity(), capacity);
+    }
+
+    #[test]
+    fn empty_mesh_not_retained() {
+        let mut pool = MeshPool::<TestMesh>::new();
+        pool.put(TestMesh::default());
+        assert_eq!(pool.len(), 0);
+    }
+
+    #[test]
+    fn class_limit() {
+        let mut pool = MeshPool::new();
+        for _ in 0..(MAX_PER_CLASS + 3) {
+            pool.put(nonempty_mesh());
+        }
+        assert_eq!(pool.len(), MAX_PER_CLASS);
+        pool.clear();
+        assert_eq!(pool.len(), 0);
+    }
+}
@@ -4,12 +4,13 @@ use std::ops::Range;
 use bitvec::vec::BitVec;
 use ordered_float::OrderedFloat;
 
+use all_is_cubes::block::Modifier;
 use all_is_cubes::camera::Flaws;
 use all_is_cubes::cgmath::{EuclideanSpace as _, MetricSpace as _, Point3, Vector3, Zero as _};
-use all_is_cubes::math::{Face6, GridAab, GridCoordinate, GridPoint, GridRotation};
+use all_is_cubes::math::{Face6, FaceMap, GridAab, GridCoordinate, GridPoint, GridRotation};
 use all_is_cubes::space::{BlockIndex, Space};
 
-use crate::{BlockMesh, GfxVertex, IndexSlice, IndexVec, MeshOptions, TextureTile};
+use crate::{BlockMesh, GfxVertex, IndexSlice, IndexVec, MeshOptions, PoolableMesh, TextureTile};
 
 /// A triangle mesh representation of a [`Space`] (or part of it) which may
 /// then be rasterized.
@@ -170,6 +171,36 @@ impl<V, T> SpaceMesh<V, T> {
             + block_indices_used.capacity() / 8
             + textures_used.capacity() * size_of::<T>()
     }
+
+    /// Delete all data, leaving the mesh empty but retaining the allocations.
+    fn clear(&mut self) {
+        let Self {
+            vertices,
+            indices,
+            opaque_range,
+            transparent_ranges,
+            block_indices_used,
+            textures_used,
+            flaws,
+        } = self;
+        vertices.clear();
+        indices.clear();
+        *opaque_range = ZERO_RANGE;
+        *transparent_ranges = [ZERO_RANGE; DepthOrdering::COUNT];
+        block_indices_used.clear();
+        textures_used.clear();
+        *flaws = Flaws::empty();
+    }
+}
+
+impl<V, T> PoolableMesh for SpaceMesh<V, T> {
+    fn clear_for_reuse(&mut self) {
+        self.clear();
+    }
+
+    fn vertex_capacity(&self) -> usize {
+        self.vertices.capacity()
+    }
 }
 
 impl<V: GfxVertex, T: TextureTile> SpaceMesh<V, T> {
@@ -186,7 +217,8 @@ impl<V: GfxVertex, T: TextureTile> SpaceMesh<V, T> {
     /// and may contain severe lighting errors.
     ///
     /// Note about edge case behavior: This algorithm does not use the [`Space`]'s block data
-    /// at all. Thus, it always has a consistent interpretation based on
+    /// except to find [`Modifier::Connect`]s, which select which of `block_meshes`' meshes
+    /// to use. Thus, it always has a consistent interpretation based on
     /// `block_meshes` (as opposed to, for example, using face opacity data not the
     /// same as the meshes and thus producing a rendering with gaps in it).
     ///
@@ -203,11 +235,7 @@ impl<V: GfxVertex, T: TextureTile> SpaceMesh<V, T> {
         T: 'p,
     {
         // use the buffer but not the existing data
-        self.vertices.clear();
-        self.indices.clear();
-        self.block_indices_used.clear();
-        self.textures_used.clear();
-        self.flaws = Flaws::empty();
+        self.clear();
 
         // Use temporary buffer for positioning the transparent indices
         // TODO: Consider reuse
@@ -216,12 +244,11 @@ impl<V: GfxVertex, T: TextureTile> SpaceMesh<V, T> {
         bounds.interior_iter().for_each(|cube| {
             // TODO: On out-of-range, draw an obviously invalid block instead of an invisible one?
             // Do we want to make it the caller's responsibility to specify in-bounds?
-            let index: BlockIndex = match space.get_block_index(cube) {
-                Some(index) => index,
+            let (index, block_mesh) = match mesh_for_cube(space, &mut block_meshes, cube) {
+                Some(found) => found,
                 None => return, // continue in for_each() loop
             };
             let already_seen_index = bitset_set_and_get(&mut self.block_indices_used, index.into());
-            let block_mesh = block_meshes.get_block_mesh(index);
 
             if !already_seen_index {
                 // Capture texture handles to ensure that our texture coordinates stay valid.
@@ -240,11 +267,10 @@ impl<V: GfxVertex, T: TextureTile> SpaceMesh<V, T> {
                 &mut transparent_indices,
                 |face| {
                     let adjacent_cube = cube + face.normal_vector();
-                    if let Some(adj_block_index) = space.get_block_index(adjacent_cube) {
-                        if block_meshes.get_block_mesh(adj_block_index).face_vertices
-                            [face.opposite()]
-                        .fully_opaque
-                        {
+                    if let Some((adj_block_index, adj_block_mesh)) =
+                        mesh_for_cube(space, &mut block_meshes, adjacent_cube)
+                    {
+                        if adj_block_mesh.face_vertices[face.opposite()].fully_opaque {
                             // Don't draw obscured faces, but do record that we depended on them.
                             bitset_set_and_get(
                                 &mut self.block_indices_used,
@@ -569,6 +595,43 @@ impl<V: GfxVertex, T: TextureTile> From<&BlockMesh<V, T>> for SpaceMesh<V, T> {
     }
 }
 
+/// Returns the block index of `cube` in `space`, and the mesh from `block_meshes` which
+/// should be drawn for it, taking into account any [`Modifier::Connect`] on the block.
+///
+/// Returns [`None`] if `cube` is out of bounds.
+fn mesh_for_cube<'p, V, T, P>(
+    space: &Space,
+    block_meshes: &mut P,
+    cube: GridPoint,
+) -> Option<(BlockIndex, &'p BlockMesh<V, T>)>
+where
+    P: GetBlockMesh<'p, V, T>,
+{
+    let index = space.get_block_index(cube)?;
+    let connect = space
+        .block_data()
+        .get(usize::from(index))?
+        .block()
+        .modifiers()
+        .iter()
+        .find_map(|modifier| match modifier {
+            Modifier::Connect(connect) => Some(connect),
+            _ => None,
+        });
+    let mesh = match connect {
+        None => block_meshes.get_block_mesh(index),
+        Some(connect) => {
+            // Connect to each neighbor which is the same block.
+            let connections = FaceMap::from_fn(|face| {
+                connect.faces()[face]
+                    && space.get_block_index(cube + face.normal_vector()) == Some(index)
+            });
+            block_meshes.get_connected_block_mesh(index, connect.variant_index(connections))
+        }
+    };
+    Some((index, mesh))
+}
+
 /// Set the given element in the [`BitVec`] to `true`, and return the old
 /// value.
 fn bitset_set_and_get(v: &mut BitVec, index: usize) -> bool {
@@ -618,6 +681,27 @@ pub trait GetBlockMesh<'a, V, T> {
     /// Note that the returned [`BlockMesh`] may have [`Flaws`] which will be incorporated
     /// into the [`SpaceMesh`]'s flaws.
     fn get_block_mesh(&mut self, index: BlockIndex) -> &'a BlockMesh<V, T>;
+
+    /// Returns a mesh which depicts the block which is the `index`-th element of
+    /// [`Space::block_data()`] in the relevant [`Space`], when it is connected to its
+    /// neighbors as specified by the block's [`Modifier::Connect`]; that is, a mesh of
+    /// the `variant_index`-th block returned by
+    /// [`Block::connection_variants()`](all_is_cubes::block::Block::connection_variants).
+    ///
+    /// This is only called for blocks which have a [`Modifier::Connect`].
+    /// If the variant does not exist, this should return the same mesh as
+    /// [`Self::get_block_mesh()`].
+    ///
+    /// The default implementation ignores the connections and always returns
+    /// [`Self::get_block_mesh()`], so blocks will be displayed unconnected.
+    fn get_connected_block_mesh(
+        &mut self,
+        index: BlockIndex,
+        variant_index: usize,
+    ) -> &'a BlockMesh<V, T> {
+        let _ = variant_index;
+        self.get_block_mesh(index)
+    }
 }
 
 /// Basic implementation of [`GetBlockMesh`] for any slice of meshes.
@@ -1,23 +1,27 @@
 //! Tests for [`crate::mesh`].
 
+use std::num::NonZeroU16;
+
 use pretty_assertions::assert_eq;
 
-use all_is_cubes::block::{Block, BlockAttributes, Primitive, Resolution::*, AIR};
+use all_is_cubes::block::{
+    Animate, Block, BlockAttributes, Connect, Primitive, Resolution::*, AIR,
+};
 use all_is_cubes::camera::{Flaws, GraphicsOptions, TransparencyOption};
 use all_is_cubes::cgmath::{MetricSpace as _, Point3, Transform as _, Vector3};
 use all_is_cubes::content::{make_some_blocks, make_some_voxel_blocks};
 use all_is_cubes::math::{
     Face6::{self, *},
-    FaceMap, FreeCoordinate, GridAab, GridPoint, GridRotation, Rgba,
+    FaceMap, FreeCoordinate, GridAab, GridPoint, GridRotation, Rgb, Rgba,
 };
-use all_is_cubes::space::{Space, SpacePhysics};
+use all_is_cubes::space::{BlockIndex, Space, SpacePhysics};
 use all_is_cubes::universe::Universe;
 use all_is_cubes::{notnan, rgba_const};
 
 use crate::{
     block_meshes_for_space, BlockMesh, BlockMeshes, BlockVertex, Coloring, DepthOrdering,
-    IndexSlice, MeshOptions, SpaceMesh, TestTextureAllocator, TestTextureTile, TextureCoordinate,
-    TtPoint,
+    GetBlockMesh, IndexSlice, MeshOptions, SpaceMesh, TestTextureAllocator, TestTextureTile,
+    TextureCoordinate, TtPoint,
 };
 
 /// Shorthand for writing out an entire [`BlockVertex`] with solid color.
@@ -156,6 +160,72 @@ fn no_panic_on_missing_blocks() {
     assert_eq!(space_mesh.flaws(), Flaws::empty());
 }
 
+/// Blocks with [`Connect`] use the mesh of the variant matching their neighbors.
+#[test]
+fn connected_blocks_use_variant_meshes() {
+    type TestMesh = BlockMesh<BlockVertex<TtPoint>, TestTextureTile>;
+
+    /// [`GetBlockMesh`] which supplies connected variants from a separate table.
+    struct WithConnected<'a> {
+        base: &'a [TestMesh],
+        connected: &'a [TestMesh],
+    }
+    impl<'a> GetBlockMesh<'a, BlockVertex<TtPoint>, TestTextureTile> for WithConnected<'a> {
+        fn get_block_mesh(&mut self, index: BlockIndex) -> &'a TestMesh {
+            &self.base[usize::from(index)]
+        }
+        fn get_connected_block_mesh(
+            &mut self,
+            _index: BlockIndex,
+            variant_index: usize,
+        ) -> &'a TestMesh {
+            &self.connected[variant_index]
+        }
+    }
+
+    // Connects along the X axis; variant index bit 0 is NX and bit 1 is PX.
+    let [base, v0, v1, v2, v3] = make_some_blocks();
+    let connecting = base.with_modifier(Connect::new(
+        FaceMap::repeat(false).with(NX, true).with(PX, true),
+        vec![v0, v1.clone(), v2.clone(), v3],
+    ));
+    let mut space = Space::empty_positive(3, 1, 1);
+    space.set([0, 0, 0], &connecting).unwrap();
+    space.set([1, 0, 0], &connecting).unwrap();
+
+    let options = MeshOptions::dont_care_for_test();
+    let tex = TestTextureAllocator::new();
+    let base_meshes: BlockMeshes<BlockVertex<TtPoint>, _> =
+        block_meshes_for_space(&space, &tex, &options);
+    let connected_meshes: Vec<TestMesh> = connecting
+        .connection_variants()
+        .unwrap()
+        .1
+        .into_iter()
+        .map(|variant| BlockMesh::new(&variant.unwrap().evaluate().unwrap(), &tex, &options))
+        .collect();
+    let space_mesh: SpaceMesh<BlockVertex<TtPoint>, TestTextureTile> = SpaceMesh::new(
+        &space,
+        space.bounds(),
+        &options,
+        WithConnected {
+            base: &base_meshes,
+            connected: &connected_meshes,
+        },
+    );
+
+    // The cube at x=0 is connected on its PX side, and the cube at x=1 on its NX side.
+    let color_of_face = |face: Face6, x: FreeCoordinate| {
+        space_mesh
+            .vertices()
+            .iter()
+            .find(|v| v.face == face && v.position.x == x)
+            .map(|v| v.coloring)
+    };
+    assert_eq!(color_of_face(NX, 0.0), Some(Coloring::Solid(v2.color())));
+    assert_eq!(color_of_face(PX, 2.0), Some(Coloring::Solid(v1.color())));
+}
+
 /// Construct a 1x1 recursive block and test that this is equivalent in geometry
 /// to an atom block.
 #[test]
@@ -226,6 +296,29 @@ fn space_mesh_equals_block_mesh() {
     assert_eq!(space_rendered, SpaceMesh::from(&block_meshes[0]));
 }
 
+/// [`BlockMesh::extend_opaque()`] produces the same triangles as a [`SpaceMesh`] of the
+/// block, and offsets its indices to follow existing vertices.
+#[test]
+fn extend_opaque_equals_space_mesh() {
+    let [block] = make_some_blocks();
+    let block_mesh = test_block_mesh(block);
+    let space_mesh = SpaceMesh::from(&block_mesh);
+
+    let mut vertices = Vec::new();
+    let mut indices = Vec::new();
+    block_mesh.extend_opaque(&mut vertices, &mut indices, Clone::clone);
+    assert_eq!(vertices, space_mesh.vertices().to_vec());
+    assert_eq!(indices, space_mesh.indices().iter_u32().collect::<Vec<_>>());
+
+    let count = vertices.len() as u32;
+    block_mesh.extend_opaque(&mut vertices, &mut indices, Clone::clone);
+    assert_eq!(vertices.len(), 2 * space_mesh.vertices().len());
+    assert_eq!(
+        indices[indices.len() / 2],
+        space_mesh.indices().iter_u32().next().unwrap() + count
+    );
+}
+
 /// TODO: This test stops being meaningful when we finish migrating the texture allocator to use arbitrary-sized tiles
 #[test]
 fn block_resolution_greater_than_tile() {
@@ -364,6 +457,76 @@ fn shrunken_box_uniform_color() {
     );
 }
 
+/// Emission is only displayable using textures, so it prevents the uniform-color case
+/// from using vertex colors.
+#[test]
+fn emissive_uniform_color_uses_texture() {
+    let mut u = Universe::new();
+    let filler_block = Block::builder()
+        .color(Rgba::new(0.0, 1.0, 0.5, 1.0))
+        .light_emission(Rgb::new(1.0, 1.0, 1.0))
+        .build();
+    let less_than_full_block = Block::builder()
+        .voxels_fn(&mut u, R8, |cube| {
+            if GridAab::from_lower_size([2, 2, 2], [4, 4, 4]).contains_cube(cube) {
+                &filler_block
+            } else {
+                &AIR
+            }
+        })
+        .unwrap()
+        .build();
+
+    let tex = TestTextureAllocator::new();
+    let mesh: BlockMesh<BlockVertex<TtPoint>, TestTextureTile> = BlockMesh::new(
+        &less_than_full_block.evaluate().unwrap(),
+        &tex,
+        &MeshOptions::dont_care_for_test(),
+    );
+
+    assert_eq!(tex.count_allocated(), 1);
+    assert!(mesh.all_face_meshes().all(|(_, face_mesh)| face_mesh
+        .vertices
+        .iter()
+        .all(|v| matches!(v.coloring, Coloring::Texture { .. }))));
+}
+
+/// An animated block whose frames have the same shape uses a texture, and the
+/// texture can be updated in place for the next frame.
+#[test]
+fn animated_same_shape_reuses_texture() {
+    let resolution = R8;
+    let mut u = Universe::new();
+    let frames = [Rgba::new(0.0, 1.0, 0.5, 1.0), Rgba::new(1.0, 0.0, 0.5, 1.0)].map(|color| {
+        let filler_block = Block::from(color);
+        Block::builder()
+            .voxels_fn(&mut u, resolution, |cube| {
+                if GridAab::from_lower_size([2, 2, 2], [4, 4, 4]).contains_cube(cube) {
+                    &filler_block
+                } else {
+                    &AIR
+                }
+            })
+            .unwrap()
+            .build()
+    });
+    let animate = Animate::new(frames.to_vec(), NonZeroU16::new(1).unwrap());
+    let block = AIR.with_modifier(animate.clone());
+    let next_block = AIR.with_modifier(animate.with_phase(1));
+
+    let tex = TestTextureAllocator::new();
+    let mut mesh: BlockMesh<BlockVertex<TtPoint>, TestTextureTile> = BlockMesh::new(
+        &block.evaluate().unwrap(),
+        &tex,
+        &MeshOptions::dont_care_for_test(),
+    );
+    // Without the animation, this block would use only vertex colors.
+    assert_eq!(tex.count_allocated(), 1);
+
+    assert!(mesh.try_update_texture_only(&next_block.evaluate().unwrap()));
+    assert_eq!(tex.count_allocated(), 1);
+}
+
 fn opacities<V, T>(mesh: &BlockMesh<V, T>) -> FaceMap<bool> {
     assert!(
         !mesh.interior_vertices.fully_opaque,
@@ -389,6 +552,29 @@ fn atom_transparency() {
     );
 }
 
+/// [`Primitive::Faces`] is drawn with one solid color per face, without textures.
+#[test]
+fn faces_use_vertex_colors() {
+    let faces = FaceMap::repeat(Rgba::WHITE)
+        .with(PY, Rgba::new(0.0, 1.0, 0.0, 1.0))
+        .with(NY, Rgba::TRANSPARENT);
+    let mesh = test_block_mesh(Block::builder().face_colors(faces).build());
+
+    assert!(mesh.textures().is_empty());
+    for face in Face6::ALL {
+        let vertices = &mesh.face_vertices[face].vertices;
+        if face == NY {
+            assert!(vertices.is_empty());
+        } else {
+            assert_eq!(vertices.len(), 4, "{face:?}");
+            for vertex in vertices {
+                assert_eq!(vertex.coloring, Coloring::Solid(faces[face]), "{face:?}");
+            }
+        }
+    }
+    assert_eq!(opacities(&mesh), FaceMap::repeat(true).with(NY, false));
+}
+
 #[test]
 fn atom_transparency_thresholded() {
     // Threshold means that partial transparency should produce exactly the same mesh as 0 or 1
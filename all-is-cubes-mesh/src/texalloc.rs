@@ -15,9 +15,26 @@ use all_is_cubes::util::{ConciseDebug, CustomFormat};
 
 use crate::TextureCoordinate;
 
-/// Color data accepted by [`TextureAllocator`].
-/// The components are sRGB `[R, G, B, A]`.
-pub type Texel = [u8; 4];
+/// Data for a single voxel, accepted by [`TextureTile::write()`].
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+#[allow(clippy::exhaustive_structs)]
+pub struct Texel {
+    /// Reflectance color; the components are sRGB `[R, G, B, A]`.
+    pub color: [u8; 4],
+    /// Light emission ([`Evoxel::emission`]); the components are sRGB `[R, G, B]`.
+    /// Emission greater than 1 is clamped.
+    pub emission: [u8; 3],
+}
+
+impl Texel {
+    fn from_evoxel(voxel: Evoxel) -> Self {
+        let [r, g, b, _] = voxel.emission.with_alpha_one().to_srgb8();
+        Self {
+            color: voxel.color.to_srgb8(),
+            emission: [r, g, b],
+        }
+    }
+}
 
 /// Allocator of 3D regions ("tiles") in a texture atlas to paint block voxels into.
 /// Implement this trait using the target graphics API's 3D texture type.
@@ -54,7 +71,7 @@ pub trait TextureTile: Clone {
     /// target [`GfxVertex`](super::GfxVertex) type.
     fn grid_to_texcoord(&self, in_tile_grid: Point3<TextureCoordinate>) -> Self::Point;
 
-    /// Write texture data as RGBA color.
+    /// Write texture data as reflectance and emission colors.
     ///
     /// `data` must be of length `self.bounds().volume()`.
     // TODO: Replace it with a GridArray (requires changing the ordering).
@@ -106,13 +123,11 @@ pub(super) fn copy_voxels_into_existing_texture<T: TextureTile>(voxels: &Evoxels
     for z in bounds.z_range() {
         for y in bounds.y_range() {
             for x in bounds.x_range() {
-                texels.push(
+                texels.push(Texel::from_evoxel(
                     voxels
                         .get(GridPoint { x, y, z })
-                        .unwrap_or(Evoxel::from_color(palette::MISSING_VOXEL_FALLBACK))
-                        .color
-                        .to_srgb8(),
-                );
+                        .unwrap_or(Evoxel::from_color(palette::MISSING_VOXEL_FALLBACK)),
+                ));
             }
         }
     }
@@ -258,6 +273,7 @@ pub type TtPoint = Point3<TextureCoordinate>;
 mod tests {
     use super::*;
     use all_is_cubes::block::Resolution::*;
+    use all_is_cubes::math::{Rgb, Rgba};
 
     /// Test the [`TestTextureAllocator`].
     #[test]
@@ -272,4 +288,17 @@ mod tests {
         assert!(allocator.allocate(bounds).is_some());
         assert!(allocator.allocate(bounds).is_none());
     }
+
+    #[test]
+    fn texel_from_evoxel() {
+        let mut voxel = Evoxel::from_color(Rgba::new(1.0, 0.0, 0.0, 1.0));
+        voxel.emission = Rgb::new(0.0, 1.0, 5.0);
+        assert_eq!(
+            Texel::from_evoxel(voxel),
+            Texel {
+                color: [255, 0, 0, 255],
+                emission: [0, 255, 255],
+            }
+        );
+    }
 }
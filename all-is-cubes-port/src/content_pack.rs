@@ -0,0 +1,240 @@
+//! Loading content packs: directories or zip files of block definitions and structures.
+
+use std::fs;
+use std::io::{self, Read as _};
+use std::path::{Path, PathBuf};
+
+use all_is_cubes::block::BlockDef;
+use all_is_cubes::universe::{Name, RegisterBlockError, URef, Universe};
+use all_is_cubes::util::YieldProgress;
+
+use crate::file::NonDiskFile;
+use crate::{load_universe_from_file, ImportError, ImportErrorKind};
+
+/// File name extensions of the files in a content pack which are loaded.
+/// All other files are ignored.
+const PACK_FILE_EXTENSIONS: &[&str] = &["alliscubesjson", "alliscubes", "vox"];
+
+/// Load a content pack into `universe`.
+///
+/// A content pack is a directory, or a zip file, containing files in any format that
+/// [`load_universe_from_file()`] can load, with the extensions `.alliscubesjson`,
+/// `.alliscubes`, or `.vox`; other files are ignored. Typically, these files contain
+/// block definitions and [`Space`](all_is_cubes::space::Space)s of structures built
+/// from them.
+///
+/// The members of all of the files are added to `universe` with their names prefixed
+/// by `namespace` and a slash (e.g. `stone` becomes `mypack/stone`). Each block
+/// definition is also registered in the [`BlockRegistry`] with the identifier
+/// `namespace:name` (e.g. `mypack:stone`), if that is a valid identifier.
+///
+/// Returns an error if a file could not be read or loaded, or if a name or identifier
+/// is already in use. In that case, the contents of some of the files may already have
+/// been added to `universe`.
+///
+/// [`BlockRegistry`]: all_is_cubes::universe::BlockRegistry
+pub async fn load_content_pack(
+    progress: YieldProgress,
+    path: &Path,
+    namespace: &str,
+    universe: &mut Universe,
+) -> Result<(), ImportError> {
+    let error = |detail| ImportError {
+        source_path: path.display().to_string(),
+        detail,
+    };
+
+    let files = if path.is_dir() {
+        read_pack_directory(path)
+    } else {
+        read_pack_zip(path)
+    }
+    .map_err(error)?;
+
+    let count = files.len();
+    for ((name, bytes), progress) in files.into_iter().zip(progress.split_evenly(count)) {
+        let file = NonDiskFile::from_name_and_data_source(
+            format!("{}/{name}", path.display()),
+            move || Ok(bytes.clone()),
+        );
+        let pack_universe = load_universe_from_file(progress, &file).await?;
+
+        let block_defs: Vec<(Name, URef<BlockDef>)> =
+            pack_universe.iter_by_type::<BlockDef>().collect();
+        universe
+            .merge(pack_universe, &format!("{namespace}/"))
+            .map_err(|e| error(ImportErrorKind::Insert(Box::new(e))))?;
+        for (name, block_def) in block_defs {
+            let Name::Specific(name) = name else {
+                continue;
+            };
+            match universe.register_block(&format!("{namespace}:{name}"), block_def) {
+                Ok(()) | Err(RegisterBlockError::InvalidId(_)) => {}
+                Err(e) => return Err(error(ImportErrorKind::Insert(Box::new(e)))),
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Returns whether a file with the given name should be loaded from a content pack.
+fn is_pack_file(name: &Path) -> bool {
+    name.extension()
+        .and_then(|extension| extension.to_str())
+        .map_or(false, |extension| PACK_FILE_EXTENSIONS.contains(&extension))
+}
+
+/// Reads the files to be loaded from a content pack directory, including its
+/// subdirectories, in sorted order of their paths.
+fn read_pack_directory(root: &Path) -> Result<Vec<(String, Vec<u8>)>, ImportErrorKind> {
+    fn walk(directory: &Path, paths: &mut Vec<PathBuf>) -> io::Result<()> {
+        for entry in fs::read_dir(directory)? {
+            let path = entry?.path();
+            if path.is_dir() {
+                walk(&path, paths)?;
+            } else if is_pack_file(&path) {
+                paths.push(path);
+            }
+        }
+        Ok(())
+    }
+
+    let mut paths = Vec::new();
+    walk(root, &mut paths).map_err(|error| ImportErrorKind::Read { path: None, error })?;
+    paths.sort();
+
+    paths
+        .into_iter()
+        .map(|path| {
+            let bytes = fs::read(&path).map_err(|error| ImportErrorKind::Read {
+                path: Some(path.display().to_string()),
+                error,
+            })?;
+            let name = path
+                .strip_prefix(root)
+                .unwrap_or(&path)
+                .components()
+                .map(|component| component.as_os_str().to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("/");
+            Ok((name, bytes))
+        })
+        .collect()
+}
+
+/// Reads the files to be loaded from a content pack zip file, in the order they appear
+/// in the zip file.
+fn read_pack_zip(path: &Path) -> Result<Vec<(String, Vec<u8>)>, ImportErrorKind> {
+    let zip_error = |error: zip::result::ZipError| match error {
+        zip::result::ZipError::Io(error) => ImportErrorKind::Read { path: None, error },
+        error => ImportErrorKind::Parse(Box::new(error)),
+    };
+
+    let file = fs::File::open(path).map_err(|error| ImportErrorKind::Read { path: None, error })?;
+    let mut archive = zip::ZipArchive::new(io::BufReader::new(file)).map_err(zip_error)?;
+    let mut files = Vec::new();
+    for index in 0..archive.len() {
+        let mut entry = archive.by_index(index).map_err(zip_error)?;
+        let name = entry.name().to_owned();
+        if entry.is_dir() || !is_pack_file(Path::new(&name)) {
+            continue;
+        }
+        let mut bytes = Vec::new();
+        entry
+            .read_to_end(&mut bytes)
+            .map_err(|error| ImportErrorKind::Read {
+                path: Some(format!("{}/{name}", path.display())),
+                error,
+            })?;
+        files.push((name, bytes));
+    }
+    Ok(files)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{export_to_bytes, ExportFormat, ExportSet};
+    use all_is_cubes::content::make_some_blocks;
+    use std::io::Write as _;
+
+    /// Returns the serialized form of a universe containing a block named `stone`.
+    async fn pack_file_bytes() -> Vec<u8> {
+        let [block] = make_some_blocks();
+        let mut universe = Universe::new();
+        universe
+            .insert("stone".into(), BlockDef::new(block))
+            .unwrap();
+        export_to_bytes(
+            YieldProgress::noop(),
+            ExportFormat::AicJson,
+            ExportSet::all_of_universe(&universe),
+        )
+        .await
+        .unwrap()
+    }
+
+    fn assert_loaded(universe: &Universe) {
+        let block_def: URef<BlockDef> = universe.get(&Name::from("test/stone")).unwrap();
+        assert_eq!(
+            universe.block_registry().get("test:stone"),
+            Some(&block_def)
+        );
+    }
+
+    #[tokio::test]
+    async fn load_directory() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir(dir.path().join("blocks")).unwrap();
+        fs::write(
+            dir.path().join("blocks/stone.alliscubesjson"),
+            pack_file_bytes().await,
+        )
+        .unwrap();
+        fs::write(dir.path().join("README.txt"), "not loaded").unwrap();
+
+        let mut universe = Universe::new();
+        load_content_pack(YieldProgress::noop(), dir.path(), "test", &mut universe)
+            .await
+            .unwrap();
+        assert_loaded(&universe);
+    }
+
+    #[tokio::test]
+    async fn load_zip() {
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        {
+            let mut zip = zip::ZipWriter::new(file.as_file_mut());
+            let options = zip::write::FileOptions::default();
+            zip.start_file("stone.alliscubesjson", options).unwrap();
+            zip.write_all(&pack_file_bytes().await).unwrap();
+            zip.start_file("README.txt", options).unwrap();
+            zip.write_all(b"not loaded").unwrap();
+            zip.finish().unwrap();
+        }
+
+        let mut universe = Universe::new();
+        load_content_pack(YieldProgress::noop(), file.path(), "test", &mut universe)
+            .await
+            .unwrap();
+        assert_loaded(&universe);
+    }
+
+    #[tokio::test]
+    async fn load_conflict() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("a.alliscubesjson"), pack_file_bytes().await).unwrap();
+
+        let mut universe = Universe::new();
+        load_content_pack(YieldProgress::noop(), dir.path(), "test", &mut universe)
+            .await
+            .unwrap();
+        let error = load_content_pack(YieldProgress::noop(), dir.path(), "test", &mut universe)
+            .await
+            .unwrap_err();
+        assert!(
+            matches!(error.detail, ImportErrorKind::Insert(_)),
+            "{error:?}"
+        );
+    }
+}
@@ -333,6 +333,10 @@ pub(crate) async fn export_gltf(
                 blocks: block_defs,
                 spaces,
                 characters,
+                entities,
+                player_stats,
+                scripts,
+                waypoints,
             },
     } = source;
 
@@ -349,6 +353,30 @@ pub(crate) async fn export_gltf(
             reason: "Exporting characters to glTF is not yet supported".into(),
         });
     }
+    if let Some(first) = entities.get(0) {
+        return Err(ExportError::NotRepresentable {
+            name: Some(first.name()),
+            reason: "Exporting entities to glTF is not yet supported".into(),
+        });
+    }
+    if let Some(first) = player_stats.get(0) {
+        return Err(ExportError::NotRepresentable {
+            name: Some(first.name()),
+            reason: "Exporting player statistics to glTF is not supported".into(),
+        });
+    }
+    if let Some(first) = scripts.get(0) {
+        return Err(ExportError::NotRepresentable {
+            name: Some(first.name()),
+            reason: "Exporting scripts to glTF is not supported".into(),
+        });
+    }
+    if let Some(first) = waypoints.get(0) {
+        return Err(ExportError::NotRepresentable {
+            name: Some(first.name()),
+            reason: "Exporting waypoints to glTF is not supported".into(),
+        });
+    }
 
     let mut writer = GltfWriter::new(GltfDataDestination::new(Some(destination.clone()), 2000));
     let mesh_options = MeshOptions::new(&GraphicsOptions::default());
@@ -2,11 +2,14 @@
 //!
 //! Currently supported formats:
 //!
-//! * All is Cubes native format (work in progress; currently import only)
+//! * All is Cubes native format (work in progress), as JSON or as compressed binary
 //! * MagicaVoxel `.vox` voxel scene files (import only)
 //! * [glTF 2.0] (export only)
 //! * [STL] (export only)
 //!
+//! Content packs of block definitions and structures, made of files in the above formats,
+//! can be loaded from directories or zip files with [`load_content_pack()`].
+//!
 //! [glTF 2.0]: https://registry.khronos.org/glTF/specs/2.0/glTF-2.0.html
 //! [STL]: <https://en.wikipedia.org/wiki/STL_(file_format)>
 
@@ -45,15 +48,21 @@
 #![forbid(unsafe_code)]
 #![warn(missing_docs)]
 
+use std::collections::BTreeSet;
 use std::ffi::OsString;
 use std::fs;
 use std::path::{Path, PathBuf};
 
 use all_is_cubes::block::{self, BlockDef};
-use all_is_cubes::space::Space;
-use all_is_cubes::universe::{self, PartialUniverse, URef, Universe};
+use all_is_cubes::character::{Character, PlayerStats};
+use all_is_cubes::entity::Entity;
+use all_is_cubes::script::Script;
+use all_is_cubes::space::{Space, Waypoint};
+use all_is_cubes::universe::{self, PartialUniverse, URef, URefErased, Universe, VisitRefs};
 use all_is_cubes::util::YieldProgress;
 
+mod content_pack;
+pub use content_pack::load_content_pack;
 pub mod file;
 pub mod gltf;
 mod mv;
@@ -79,6 +88,9 @@ pub async fn load_universe_from_file(
     if bytes.starts_with(b"{") {
         // Assume it's JSON. Furthermore, assume it's ours.
         native::import_native_json(&bytes, &file)
+    } else if bytes.starts_with(&[0x1f, 0x8b]) {
+        // gzip magic number; assume it's our binary format.
+        native::import_native_binary(&bytes, &file)
     } else if bytes.starts_with(b"VOX ") {
         load_dot_vox(progress, &bytes)
             .await
@@ -107,7 +119,12 @@ pub async fn export_to_path(
     destination: PathBuf,
 ) -> Result<(), crate::ExportError> {
     match format {
-        ExportFormat::AicJson => native::export_native_json(progress, source, destination).await,
+        ExportFormat::AicJson => {
+            native::export_native_json(progress, source, fs::File::create(destination)?).await
+        }
+        ExportFormat::AicBinary => {
+            native::export_native_binary(progress, source, fs::File::create(destination)?).await
+        }
         ExportFormat::DotVox => {
             // TODO: async file IO?
             mv::export_dot_vox(progress, source, fs::File::create(destination)?).await
@@ -117,6 +134,33 @@ pub async fn export_to_path(
     }
 }
 
+/// Export data specified by an [`ExportSet`] to bytes in memory, for destinations which
+/// are not files on disk, such as browser storage.
+///
+/// Formats which require multiple files are not supported, and produce
+/// [`ExportError::NotRepresentable`].
+pub async fn export_to_bytes(
+    progress: YieldProgress,
+    format: ExportFormat,
+    source: ExportSet,
+) -> Result<Vec<u8>, crate::ExportError> {
+    let mut bytes = Vec::new();
+    match format {
+        ExportFormat::AicJson => native::export_native_json(progress, source, &mut bytes).await?,
+        ExportFormat::AicBinary => {
+            native::export_native_binary(progress, source, &mut bytes).await?
+        }
+        ExportFormat::DotVox => mv::export_dot_vox(progress, source, &mut bytes).await?,
+        ExportFormat::Gltf | ExportFormat::Stl => {
+            return Err(ExportError::NotRepresentable {
+                name: None,
+                reason: format!("{format:?} cannot be exported to a single file"),
+            })
+        }
+    }
+    Ok(bytes)
+}
+
 /// Selection of the data to be exported.
 #[derive(Clone, Debug)]
 pub struct ExportSet {
@@ -152,6 +196,147 @@ impl ExportSet {
         }
     }
 
+    /// Construct an [`ExportSet`] specifying exporting all members of the universe whose
+    /// names match the given glob pattern.
+    ///
+    /// In the pattern, `*` matches any sequence of characters and `?` matches any single
+    /// character; all other characters match only themselves. Anonymous members are
+    /// never matched.
+    ///
+    /// Members which the selected members refer to are not included unless they also
+    /// match; use [`ExportSet::with_reachable()`] to include them.
+    pub fn from_name_glob(universe: &Universe, pattern: &str) -> Self {
+        let matches = |name: &universe::Name| match name {
+            universe::Name::Specific(s) => glob_match(pattern, s),
+            _ => false,
+        };
+        Self {
+            contents: PartialUniverse {
+                blocks: universe
+                    .iter_by_type()
+                    .filter(|(name, _)| matches(name))
+                    .map(|(_, r)| r)
+                    .collect(),
+                characters: universe
+                    .iter_by_type()
+                    .filter(|(name, _)| matches(name))
+                    .map(|(_, r)| r)
+                    .collect(),
+                entities: universe
+                    .iter_by_type()
+                    .filter(|(name, _)| matches(name))
+                    .map(|(_, r)| r)
+                    .collect(),
+                player_stats: universe
+                    .iter_by_type()
+                    .filter(|(name, _)| matches(name))
+                    .map(|(_, r)| r)
+                    .collect(),
+                scripts: universe
+                    .iter_by_type()
+                    .filter(|(name, _)| matches(name))
+                    .map(|(_, r)| r)
+                    .collect(),
+                spaces: universe
+                    .iter_by_type()
+                    .filter(|(name, _)| matches(name))
+                    .map(|(_, r)| r)
+                    .collect(),
+                waypoints: universe
+                    .iter_by_type()
+                    .filter(|(name, _)| matches(name))
+                    .map(|(_, r)| r)
+                    .collect(),
+            },
+        }
+    }
+
+    /// Remove the given characters from this set, such as characters which are only
+    /// temporary and should not be saved.
+    ///
+    /// Any other members which refer to them will fail to export.
+    #[must_use]
+    pub fn without_characters(mut self, characters: &[URef<Character>]) -> Self {
+        self.contents
+            .characters
+            .retain(|character| !characters.contains(character));
+        self
+    }
+
+    /// Add to this set every member of `universe` which the members already in the set
+    /// refer to, directly or indirectly, so that the exported data is self-contained.
+    ///
+    /// For example, `ExportSet::from_spaces(vec![space]).with_reachable(&universe)`
+    /// selects a single [`Space`] along with all the block definitions (and any other
+    /// spaces) needed to reconstruct it.
+    ///
+    /// Returns an error if a member could not be read, or if a reference leads outside
+    /// of `universe`.
+    pub fn with_reachable(self, universe: &Universe) -> Result<Self, ExportError> {
+        let PartialUniverse {
+            blocks,
+            characters,
+            entities,
+            player_stats,
+            scripts,
+            spaces,
+            waypoints,
+        } = self.contents;
+
+        let mut found = PartialUniverse::default();
+        let mut seen: BTreeSet<universe::Name> = BTreeSet::new();
+        let mut queue: Vec<universe::Name> = Vec::new();
+        for r in blocks {
+            add_reachable(&mut found.blocks, &mut seen, &mut queue, r)?;
+        }
+        for r in characters {
+            add_reachable(&mut found.characters, &mut seen, &mut queue, r)?;
+        }
+        for r in entities {
+            add_reachable(&mut found.entities, &mut seen, &mut queue, r)?;
+        }
+        for r in player_stats {
+            add_reachable(&mut found.player_stats, &mut seen, &mut queue, r)?;
+        }
+        for r in scripts {
+            add_reachable(&mut found.scripts, &mut seen, &mut queue, r)?;
+        }
+        for r in spaces {
+            add_reachable(&mut found.spaces, &mut seen, &mut queue, r)?;
+        }
+        for r in waypoints {
+            add_reachable(&mut found.waypoints, &mut seen, &mut queue, r)?;
+        }
+
+        while let Some(name) = queue.pop() {
+            if seen.contains(&name) {
+                continue;
+            }
+            if let Some(r) = universe.get::<BlockDef>(&name) {
+                add_reachable(&mut found.blocks, &mut seen, &mut queue, r)?;
+            } else if let Some(r) = universe.get::<Character>(&name) {
+                add_reachable(&mut found.characters, &mut seen, &mut queue, r)?;
+            } else if let Some(r) = universe.get::<Entity>(&name) {
+                add_reachable(&mut found.entities, &mut seen, &mut queue, r)?;
+            } else if let Some(r) = universe.get::<PlayerStats>(&name) {
+                add_reachable(&mut found.player_stats, &mut seen, &mut queue, r)?;
+            } else if let Some(r) = universe.get::<Script>(&name) {
+                add_reachable(&mut found.scripts, &mut seen, &mut queue, r)?;
+            } else if let Some(r) = universe.get::<Space>(&name) {
+                add_reachable(&mut found.spaces, &mut seen, &mut queue, r)?;
+            } else if let Some(r) = universe.get::<Waypoint>(&name) {
+                add_reachable(&mut found.waypoints, &mut seen, &mut queue, r)?;
+            } else {
+                return Err(ExportError::NotRepresentable {
+                    reason: format!("reference to {name} which is not in the universe"),
+                    name: Some(name),
+                });
+            }
+        }
+
+        Ok(Self { contents: found })
+    }
+
     /// Calculate the file path to use supposing that we want to export one member to one file
     /// (as opposed to all members into one file).
     ///
@@ -183,6 +368,55 @@ impl ExportSet {
     }
 }
 
+/// Helper for [`ExportSet::with_reachable()`]: add `r` to `table` and the names of
+/// the members it refers to to `queue`, unless it has already been seen.
+fn add_reachable<T: VisitRefs + 'static>(
+    table: &mut Vec<URef<T>>,
+    seen: &mut BTreeSet<universe::Name>,
+    queue: &mut Vec<universe::Name>,
+    r: URef<T>,
+) -> Result<(), ExportError> {
+    if !seen.insert(r.name()) {
+        return Ok(());
+    }
+    r.read()?
+        .visit_refs(&mut |referent: &dyn URefErased| queue.push(referent.name()));
+    table.push(r);
+    Ok(())
+}
+
+/// Match `text` against a pattern in which `*` matches any sequence of characters,
+/// and `?` matches any single character.
+fn glob_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+
+    // Iterative matching with backtracking to the most recent `*`.
+    let (mut p, mut t) = (0, 0);
+    let mut star: Option<(usize, usize)> = None;
+    while t < text.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                star = Some((p, t));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == text[t] => {
+                p += 1;
+                t += 1;
+            }
+            _ => match star {
+                Some((star_p, star_t)) => {
+                    p = star_p + 1;
+                    t = star_t + 1;
+                    star = Some((star_p, star_t + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
 /// File formats that All is Cubes data can be exported to.
 #[derive(Copy, Clone, Debug, Eq, Hash, PartialEq)]
 #[non_exhaustive]
@@ -190,6 +424,12 @@ pub enum ExportFormat {
     /// Native format: JSON-encoded All is Cubes universe serialization.
     AicJson,
 
+    /// Native format: gzip-compressed [CBOR] encoding of the same data as
+    /// [`ExportFormat::AicJson`], with [`Space`] contents run-length encoded.
+    ///
+    /// [CBOR]: https://cbor.io/
+    AicBinary,
+
     /// [MagicaVoxel `.vox`][vox] file.
     ///
     /// TODO: document version details and export limitations
@@ -221,6 +461,7 @@ impl ExportFormat {
     pub fn includes_light(self) -> bool {
         match self {
             ExportFormat::AicJson => true,
+            ExportFormat::AicBinary => true,
             ExportFormat::DotVox => false,
             ExportFormat::Gltf => false, // TODO: implement light
             ExportFormat::Stl => false,
@@ -276,6 +517,28 @@ pub enum ImportErrorKind {
     #[non_exhaustive]
     #[error("the data is not in a recognized format")]
     UnknownFormat {},
+
+    /// The data is in All is Cubes' own format, but was saved by a newer version of
+    /// All is Cubes using a format version that this version does not understand.
+    #[non_exhaustive]
+    #[error(
+        "the data is in a newer format version ({version}) than this version of \
+        All is Cubes can read"
+    )]
+    UnsupportedVersion {
+        /// The format version found, as it appears in the data (e.g. `"UniverseV2"`).
+        version: String,
+    },
+
+    /// The data could not be added to the destination universe, such as because a name
+    /// or block identifier it uses is already in use there.
+    #[non_exhaustive]
+    #[error("could not add the imported data to the universe")]
+    Insert(
+        /// The error from the universe.
+        #[source]
+        Box<dyn std::error::Error + Send + Sync>,
+    ),
 }
 
 /// Fatal errors that may be encountered during an export operation.
@@ -1,6 +1,6 @@
 //! Import and export of MagicaVoxel `.vox` files.
 
-use all_is_cubes::block::{self, Block};
+use all_is_cubes::block::{self, Block, BlockDef, Primitive};
 use all_is_cubes::cgmath::{EuclideanSpace as _, Point3, Transform as _, Vector3};
 use all_is_cubes::character::{Character, Spawn};
 use all_is_cubes::content::free_editing_starter_inventory;
@@ -56,11 +56,25 @@ pub(crate) async fn dot_vox_data_to_universe(
     );
     p.progress(0.15).await;
 
-    let palette = dot_vox_palette_to_blocks(palette);
-    let p = p.finish_and_cut(0.3).await;
-
     let mut universe = Universe::new();
 
+    // Each palette entry is a block definition, so that when the file is part of a content
+    // pack, its blocks are registered with identifiers like any other pack's blocks.
+    let palette = dot_vox_palette_to_blocks(palette)
+        .into_iter()
+        .enumerate()
+        .map(|(index, block)| {
+            let block_def = universe
+                .insert(
+                    Name::from(&*format!("palette_{index}")),
+                    BlockDef::new(block),
+                )
+                .map_err(|e| DotVoxConversionError::Unexpected(InGenError::from(e)))?;
+            Ok(Block::from_primitive(Primitive::Indirect(block_def)))
+        })
+        .collect::<Result<Vec<Block>, DotVoxConversionError>>()?;
+    let p = p.finish_and_cut(0.3).await;
+
     let models_progress = p.split_evenly(models.len());
     for ((i, model), model_progress) in models.iter().enumerate().zip(models_progress) {
         let mut space = dot_vox_model_to_space(&palette, model)?;
@@ -99,6 +113,10 @@ pub(crate) async fn export_to_dot_vox_data(
                 blocks: block_defs,
                 spaces: to_export,
                 characters: _,
+                entities: _,
+                player_stats: _,
+                scripts: _,
+                waypoints: _,
             },
     } = source;
 
@@ -321,7 +339,6 @@ fn aic_to_mv_coordinate_transform(aic_bounds: GridAab) -> GridMatrix {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use all_is_cubes::block::BlockDef;
     use all_is_cubes::math::GridPoint;
     use all_is_cubes::raytracer::print_space;
     use all_is_cubes::universe::URef;
@@ -422,16 +439,27 @@ mod tests {
             s.bounds(),
             GridAab::from_lower_size([0, 0, 0], bounds.size())
         );
-        // Block metadata is not preserved. The display name is filled with the palette index.
+        // Block metadata is not preserved. The display name is filled with the palette index,
+        // and each palette entry is a block definition.
+        let palette_0: URef<BlockDef> = import_universe.get(&"palette_0".into()).unwrap();
+        let palette_1: URef<BlockDef> = import_universe.get(&"palette_1".into()).unwrap();
         assert_eq!(
             s[[0, 1, 2]],
+            Block::from_primitive(Primitive::Indirect(palette_0.clone()))
+        );
+        assert_eq!(
+            s[[0, 1, 0]],
+            Block::from_primitive(Primitive::Indirect(palette_1.clone()))
+        );
+        assert_eq!(
+            **palette_0.read().unwrap(),
             Block::builder()
                 .color(block1.color())
                 .display_name("0")
                 .build()
         );
         assert_eq!(
-            s[[0, 1, 0]],
+            **palette_1.read().unwrap(),
             Block::builder()
                 .color(block1.color())
                 .display_name("1")
@@ -1,8 +1,9 @@
-use std::path::PathBuf;
-use std::{fs, io};
+use std::io;
 
 use all_is_cubes::universe::Universe;
 use all_is_cubes::util::YieldProgress;
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
 
 use crate::file::Fileish;
 use crate::{ExportError, ExportSet, ImportError, ImportErrorKind};
@@ -10,6 +11,10 @@ use crate::{ExportError, ExportSet, ImportError, ImportErrorKind};
 #[cfg(test)]
 mod tests;
 
+/// Values of the `type` field of a serialized [`Universe`] which this version can read.
+/// Must be kept in sync with `all_is_cubes::save::schema::UniverseSchema`.
+const SUPPORTED_UNIVERSE_TYPES: &[&str] = &["UniverseV1"];
+
 pub(crate) fn import_native_json(
     bytes: &[u8],
     file: &impl Fileish,
@@ -22,22 +27,83 @@ pub(crate) fn import_native_json(
                 error: io::Error::new(io::ErrorKind::Other, error),
             }
         } else {
-            ImportErrorKind::Parse(Box::new(error))
+            // Parse the data again, loosely, to find out whether the failure was because
+            // it is from a newer version.
+            let type_tag = serde_json::from_slice::<serde_json::Value>(bytes)
+                .ok()
+                .and_then(|value| Some(value.get("type")?.as_str()?.to_owned()));
+            unsupported_version(type_tag).unwrap_or_else(|| ImportErrorKind::Parse(Box::new(error)))
+        },
+    })
+}
+
+pub(crate) fn import_native_binary(
+    bytes: &[u8],
+    file: &impl Fileish,
+) -> Result<Universe, ImportError> {
+    ciborium::de::from_reader::<Universe, _>(GzDecoder::new(bytes)).map_err(|error| ImportError {
+        source_path: file.display_full_path().to_string(),
+        detail: match error {
+            ciborium::de::Error::Io(error) => ImportErrorKind::Read { path: None, error },
+            error => {
+                let type_tag =
+                    ciborium::de::from_reader::<ciborium::value::Value, _>(GzDecoder::new(bytes))
+                        .ok()
+                        .and_then(|value| {
+                            value.into_map().ok()?.into_iter().find_map(|(key, value)| {
+                                if key.as_text() == Some("type") {
+                                    value.into_text().ok()
+                                } else {
+                                    None
+                                }
+                            })
+                        });
+                unsupported_version(type_tag)
+                    .unwrap_or_else(|| ImportErrorKind::Parse(Box::new(error)))
+            }
         },
     })
 }
 
+/// Given the `type` field of data which failed to deserialize as a [`Universe`], returns
+/// [`ImportErrorKind::UnsupportedVersion`] if it is a universe of a version we don't know.
+fn unsupported_version(type_tag: Option<String>) -> Option<ImportErrorKind> {
+    let type_tag = type_tag?;
+    (type_tag.starts_with("Universe") && !SUPPORTED_UNIVERSE_TYPES.contains(&&*type_tag))
+        .then_some(ImportErrorKind::UnsupportedVersion { version: type_tag })
+}
+
 pub(crate) async fn export_native_json(
     progress: YieldProgress,
     source: ExportSet,
-    destination: PathBuf,
+    destination: impl io::Write,
 ) -> Result<(), ExportError> {
     // TODO: Spin off a blocking thread to perform this export
     let ExportSet { contents } = source;
-    serde_json::to_writer(fs::File::create(destination)?, &contents).map_err(|error| {
+    serde_json::to_writer(destination, &contents).map_err(|error| {
         // TODO: report non-IO errors distinctly
         ExportError::Write(io::Error::new(io::ErrorKind::Other, error))
     })?;
     progress.finish().await;
     Ok(())
 }
+
+pub(crate) async fn export_native_binary(
+    progress: YieldProgress,
+    source: ExportSet,
+    destination: impl io::Write,
+) -> Result<(), ExportError> {
+    // TODO: Spin off a blocking thread to perform this export
+    let ExportSet { contents } = source;
+    let mut encoder = GzEncoder::new(destination, flate2::Compression::default());
+    ciborium::ser::into_writer(&contents, &mut encoder).map_err(|error| match error {
+        ciborium::ser::Error::Io(error) => ExportError::Write(error),
+        // TODO: report non-IO errors distinctly
+        ciborium::ser::Error::Value(message) => {
+            ExportError::Write(io::Error::new(io::ErrorKind::Other, message))
+        }
+    })?;
+    encoder.finish()?;
+    progress.finish().await;
+    Ok(())
+}
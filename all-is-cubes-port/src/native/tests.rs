@@ -3,7 +3,11 @@ use std::fs;
 use all_is_cubes::block;
 use all_is_cubes::universe::{Name, URef};
 
-use crate::{export_to_path, load_universe_from_file, BlockDef, ExportSet, Path, YieldProgress};
+use crate::file::NonDiskFile;
+use crate::{
+    export_to_bytes, export_to_path, load_universe_from_file, BlockDef, ExportSet, ImportErrorKind,
+    Path, YieldProgress,
+};
 
 #[tokio::test]
 async fn import_export_native_format() {
@@ -38,3 +42,77 @@ async fn import_export_native_format() {
         serde_json::from_reader(fs::File::open(&destination).unwrap()).unwrap();
     pretty_assertions::assert_eq!(expected_value, actual_value);
 }
+
+#[tokio::test]
+async fn export_import_native_binary_format() {
+    let import_path = Path::new(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/src/native/tests/native-test.alliscubesjson"
+    ));
+    let universe = load_universe_from_file(YieldProgress::noop(), import_path)
+        .await
+        .unwrap();
+
+    let destination = assert_fs::NamedTempFile::new("foo.alliscubes").unwrap();
+    export_to_path(
+        YieldProgress::noop(),
+        crate::ExportFormat::AicBinary,
+        ExportSet::all_of_universe(&universe),
+        destination.to_path_buf(),
+    )
+    .await
+    .unwrap();
+
+    let reimported = load_universe_from_file(YieldProgress::noop(), destination.path())
+        .await
+        .unwrap();
+    let uref: URef<BlockDef> = reimported.get(&Name::from("foo")).unwrap();
+    assert_eq!(**uref.read().unwrap(), block::AIR);
+}
+
+#[tokio::test]
+async fn export_import_native_binary_bytes() {
+    let import_path = Path::new(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/src/native/tests/native-test.alliscubesjson"
+    ));
+    let universe = load_universe_from_file(YieldProgress::noop(), import_path)
+        .await
+        .unwrap();
+
+    let bytes = export_to_bytes(
+        YieldProgress::noop(),
+        crate::ExportFormat::AicBinary,
+        ExportSet::all_of_universe(&universe),
+    )
+    .await
+    .unwrap();
+
+    let reimported = load_universe_from_file(
+        YieldProgress::noop(),
+        &NonDiskFile::from_name_and_data_source(String::from("saved"), || Ok(bytes.clone())),
+    )
+    .await
+    .unwrap();
+    let uref: URef<BlockDef> = reimported.get(&Name::from("foo")).unwrap();
+    assert_eq!(**uref.read().unwrap(), block::AIR);
+}
+
+#[tokio::test]
+async fn import_native_json_newer_version() {
+    let error = load_universe_from_file(
+        YieldProgress::noop(),
+        &NonDiskFile::from_name_and_data_source(String::from("newer"), || {
+            Ok(br#"{"type": "UniverseV9999", "members": []}"#.to_vec())
+        }),
+    )
+    .await
+    .unwrap_err();
+    assert!(
+        matches!(
+            &error.detail,
+            ImportErrorKind::UnsupportedVersion { version } if version == "UniverseV9999"
+        ),
+        "{error:?}"
+    );
+}
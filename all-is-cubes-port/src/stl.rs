@@ -27,6 +27,10 @@ pub(crate) async fn export_stl(
                 blocks: block_defs,
                 spaces,
                 characters: _,
+                entities: _,
+                player_stats: _,
+                scripts: _,
+                waypoints: _,
             },
     } = &source;
 
@@ -1,12 +1,16 @@
 use std::error::Error as _;
 
-use all_is_cubes::block;
+use all_is_cubes::block::{self, Block};
+use all_is_cubes::character::Character;
+use all_is_cubes::math::{GridAab, Rgba};
+use all_is_cubes::space::Space;
+use all_is_cubes::universe::Name;
 use all_is_cubes::util::assert_send_sync;
 
 use crate::file::NonDiskFile;
 use crate::{
-    load_universe_from_file, BlockDef, ExportError, ExportSet, ImportError, Path, PathBuf,
-    Universe, YieldProgress,
+    glob_match, load_universe_from_file, BlockDef, ExportError, ExportSet, ImportError, Path,
+    PathBuf, Universe, YieldProgress,
 };
 
 #[test]
@@ -52,3 +56,107 @@ fn member_export_path() {
         PathBuf::from("/export/data.ext"),
     );
 }
+
+#[test]
+fn glob_match_cases() {
+    assert!(glob_match("foo", "foo"));
+    assert!(!glob_match("foo", "foobar"));
+    assert!(glob_match("foo*", "foobar"));
+    assert!(glob_match("*bar", "foobar"));
+    assert!(glob_match("f*o*r", "foobar"));
+    assert!(glob_match("f?o", "foo"));
+    assert!(!glob_match("f?o", "fo"));
+    assert!(glob_match("*", ""));
+    assert!(!glob_match("a*b", "aXbY"));
+}
+
+fn names(set: &ExportSet) -> Vec<Name> {
+    let c = &set.contents;
+    let mut names: Vec<Name> = c
+        .blocks
+        .iter()
+        .map(|r| r.name())
+        .chain(c.characters.iter().map(|r| r.name()))
+        .chain(c.entities.iter().map(|r| r.name()))
+        .chain(c.player_stats.iter().map(|r| r.name()))
+        .chain(c.scripts.iter().map(|r| r.name()))
+        .chain(c.spaces.iter().map(|r| r.name()))
+        .chain(c.waypoints.iter().map(|r| r.name()))
+        .collect();
+    names.sort();
+    names
+}
+
+#[test]
+fn from_name_glob() {
+    let mut universe = Universe::new();
+    universe
+        .insert("house-door".into(), BlockDef::new(block::AIR))
+        .unwrap();
+    universe
+        .insert("house-wall".into(), BlockDef::new(block::AIR))
+        .unwrap();
+    universe
+        .insert("tree".into(), BlockDef::new(block::AIR))
+        .unwrap();
+    universe.insert_anonymous(BlockDef::new(block::AIR));
+
+    assert_eq!(
+        names(&ExportSet::from_name_glob(&universe, "house-*")),
+        vec![Name::from("house-door"), Name::from("house-wall")]
+    );
+}
+
+#[test]
+fn without_characters() {
+    let mut universe = Universe::new();
+    let space = universe
+        .insert("space".into(), Space::builder(GridAab::ORIGIN_CUBE).build())
+        .unwrap();
+    let kept = universe
+        .insert("kept".into(), Character::spawn_default(space.clone()))
+        .unwrap();
+    let removed = universe
+        .insert("removed".into(), Character::spawn_default(space))
+        .unwrap();
+
+    let set = ExportSet::all_of_universe(&universe).without_characters(&[removed]);
+    assert_eq!(names(&set), vec![kept.name().clone(), Name::from("space")]);
+}
+
+#[test]
+fn with_reachable() {
+    let mut universe = Universe::new();
+    let color = universe
+        .insert(
+            "color".into(),
+            BlockDef::new(Block::from(Rgba::new(1.0, 0.0, 0.0, 1.0))),
+        )
+        .unwrap();
+    let indirect = universe
+        .insert(
+            "indirect".into(),
+            BlockDef::new(Block::from(block::Primitive::Indirect(color))),
+        )
+        .unwrap();
+    let _unrelated = universe
+        .insert("unrelated".into(), BlockDef::new(block::AIR))
+        .unwrap();
+    let mut space = Space::builder(GridAab::ORIGIN_CUBE).build();
+    space
+        .set([0, 0, 0], Block::from(block::Primitive::Indirect(indirect)))
+        .unwrap();
+    let space = universe.insert("space".into(), space).unwrap();
+
+    let set = ExportSet::from_spaces(vec![space])
+        .with_reachable(&universe)
+        .unwrap();
+    assert_eq!(
+        names(&set),
+        vec![
+            Name::from("color"),
+            Name::from("indirect"),
+            Name::from("space")
+        ]
+    );
+}
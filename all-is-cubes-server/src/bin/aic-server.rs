@@ -35,21 +35,57 @@
 // Crate-specific lint settings.
 #![forbid(unsafe_code)]
 
+use std::net::{IpAddr, SocketAddr};
+use std::path::PathBuf;
+use std::time::SystemTime;
+
 use clap::builder::{PossibleValuesParser, TypedValueParser};
 
-use all_is_cubes_server::{start_server, AicClientSource};
+use all_is_cubes_server::{
+    export_static, start_server, AicClientSource, LogFormat, ServeConfig, ServerConfig, TlsConfig,
+};
 
 #[derive(Debug, clap::Parser)]
 struct Args {
     #[arg(long, short = 'v')]
     verbose: bool,
 
+    /// IP address to listen on.
+    ///
+    /// Use 0.0.0.0 or :: to accept connections from other hosts.
+    #[arg(long, value_name = "ADDRESS", default_value = "127.0.0.1")]
+    bind: IpAddr,
+
     /// TCP port to bind.
     ///
     /// If not specified, an arbitrary port will be chosen.
     #[arg(long)]
     port: Option<u16>,
 
+    /// Path prefix under which to serve everything, such as `/aic`, for use behind a
+    /// reverse proxy which does not remove the prefix.
+    #[arg(long, value_name = "PATH", default_value = "")]
+    base_path: String,
+
+    /// PEM file containing the TLS certificate chain. If specified, HTTPS is served
+    /// instead of HTTP.
+    #[arg(long, value_name = "FILE", requires = "tls_key")]
+    tls_cert: Option<PathBuf>,
+
+    /// PEM file containing the private key for --tls-cert.
+    #[arg(long, value_name = "FILE", requires = "tls_cert")]
+    tls_key: Option<PathBuf>,
+
+    /// Address of a reverse proxy whose X-Forwarded-For header should be trusted to
+    /// give the address of the client. May be given more than once.
+    #[arg(long, value_name = "ADDRESS")]
+    trusted_proxy: Vec<IpAddr>,
+
+    /// Content pack (directory or zip file) to load at startup. May be given more
+    /// than once.
+    #[arg(long, value_name = "PATH")]
+    content_pack: Vec<PathBuf>,
+
     #[arg(
         long,
         value_parser = PossibleValuesParser::new(
@@ -66,41 +102,174 @@ struct Args {
         })
     )]
     client_source: AicClientSource,
+
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Debug, clap::Subcommand)]
+enum Command {
+    /// Instead of running the server, write the client and a universe as files which
+    /// can be hosted by any static web server, for single-player use.
+    ExportStatic {
+        /// Directory to write into. It is created if it does not exist.
+        #[arg(long, value_name = "DIR")]
+        out: PathBuf,
+
+        /// Universe file to play, in any format the client can load.
+        #[arg(long, value_name = "FILE")]
+        universe: PathBuf,
+    },
+
+    /// Run a dedicated game server, hosting a universe as well as serving the client,
+    /// as specified by a configuration file. The other options are ignored, except
+    /// for --verbose.
+    Serve {
+        /// TOML configuration file; see the README for its contents.
+        #[arg(long, value_name = "FILE")]
+        config: PathBuf,
+    },
 }
 
 #[tokio::main]
 async fn main() -> Result<(), anyhow::Error> {
     let Args {
+        bind,
         port,
+        base_path,
+        tls_cert,
+        tls_key,
+        trusted_proxy,
+        content_pack,
         verbose,
         client_source,
+        command,
     } = <Args as clap::Parser>::parse();
 
-    // Note: Something like this log configuration also appears in other binaries.
-    // Unclear how to deduplicate since we don't want to have a library-level dep on
-    // simplelog. For now, just remember to consider updating other instances.
-    use simplelog::LevelFilter::{Debug, Off, Trace};
-    simplelog::TermLogger::init(
-        match verbose {
-            false => Debug,
-            true => Trace,
-        },
-        simplelog::ConfigBuilder::new()
-            .set_target_level(Trace)
-            .set_location_level(Off)
-            .add_filter_ignore_str("tracing") // noisy
-            .add_filter_ignore_str("hyper") // noisy
-            .add_filter_ignore_str("mio") // uninteresting
-            .build(),
-        simplelog::TerminalMode::Stderr,
-        simplelog::ColorChoice::Auto,
-    )?;
-
-    let addr = std::net::SocketAddr::from(([127, 0, 0, 1], port.unwrap_or(0)));
-
-    let (url, finished) = start_server(addr, &client_source)?;
+    let config = match command {
+        Some(Command::ExportStatic { out, universe }) => {
+            init_logging(verbose, LogFormat::Text)?;
+            export_static(&client_source, &universe, &out)?;
+            println!("Wrote {}", out.display());
+            return Ok(());
+        }
+        Some(Command::Serve { config }) => {
+            let ServeConfig { server, log } = ServeConfig::read(&config)?;
+            init_logging(verbose || log.verbose, log.format)?;
+            server
+        }
+        None => {
+            init_logging(verbose, LogFormat::Text)?;
+            let mut config =
+                ServerConfig::new(SocketAddr::new(bind, port.unwrap_or(0)), client_source);
+            config.base_path = base_path;
+            config.tls = tls_cert
+                .zip(tls_key)
+                .map(|(cert_path, key_path)| TlsConfig::new(cert_path, key_path));
+            config.trusted_proxies = trusted_proxy;
+            config.content_packs = content_pack;
+            config
+        }
+    };
+
+    let (url, finished) = start_server(config, shutdown_signal()).await?;
     println!("{url}"); // note: printed *to stdout* for the use of tests
 
     finished.await?;
     Ok(())
 }
+
+/// Log targets which are not worth including in the log.
+const IGNORED_LOG_TARGETS: [&str; 3] = [
+    "tracing", // noisy
+    "hyper",   // noisy
+    "mio",     // uninteresting
+];
+
+fn init_logging(verbose: bool, format: LogFormat) -> Result<(), anyhow::Error> {
+    use simplelog::LevelFilter::{Debug, Off, Trace};
+    let level = match verbose {
+        false => Debug,
+        true => Trace,
+    };
+    match format {
+        LogFormat::Json => {
+            log::set_boxed_logger(Box::new(JsonLogger { level }))?;
+            log::set_max_level(level);
+        }
+        // Note: Something like this log configuration also appears in other binaries.
+        // Unclear how to deduplicate since we don't want to have a library-level dep on
+        // simplelog. For now, just remember to consider updating other instances.
+        _ => {
+            let mut config = simplelog::ConfigBuilder::new();
+            config.set_target_level(Trace).set_location_level(Off);
+            for target in IGNORED_LOG_TARGETS {
+                config.add_filter_ignore_str(target);
+            }
+            simplelog::TermLogger::init(
+                level,
+                config.build(),
+                simplelog::TerminalMode::Stderr,
+                simplelog::ColorChoice::Auto,
+            )?;
+        }
+    }
+    Ok(())
+}
+
+/// Logger which writes each message to stderr as a line of JSON, for
+/// [`LogFormat::Json`].
+struct JsonLogger {
+    level: log::LevelFilter,
+}
+
+impl log::Log for JsonLogger {
+    fn enabled(&self, metadata: &log::Metadata<'_>) -> bool {
+        metadata.level() <= self.level
+            && !IGNORED_LOG_TARGETS
+                .iter()
+                .any(|ignored| metadata.target().starts_with(ignored))
+    }
+
+    fn log(&self, record: &log::Record<'_>) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        let time = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .map_or(0.0, |since_epoch| since_epoch.as_secs_f64());
+        let line = serde_json::json!({
+            "time": time,
+            "level": record.level().as_str(),
+            "target": record.target(),
+            "message": record.args().to_string(),
+        });
+        eprintln!("{line}");
+    }
+
+    fn flush(&self) {}
+}
+
+/// Completes when the process is asked to terminate, by Ctrl-C or (on Unix) `SIGTERM`.
+async fn shutdown_signal() {
+    let ctrl_c = async {
+        tokio::signal::ctrl_c()
+            .await
+            .expect("failed to listen for Ctrl-C");
+    };
+    #[cfg(unix)]
+    let terminate = async {
+        tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())
+            .expect("failed to listen for SIGTERM")
+            .recv()
+            .await;
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        () = ctrl_c => {}
+        () = terminate => {}
+    }
+    eprintln!("Shutting down...");
+}
@@ -1,5 +1,12 @@
+use std::path::Path;
+use std::{fs, io};
+
 use axum::routing::Router;
 
+/// Location of the client files built in the development workspace.
+const WORKSPACE_CLIENT_DIR: &str =
+    concat!(env!("CARGO_MANIFEST_DIR"), "/../all-is-cubes-wasm/dist/");
+
 /// Where to obtain the WebAssembly+JS code for the All is Cubes in-browser game engine.
 #[derive(Clone, Debug, Eq, Hash, PartialEq)]
 #[non_exhaustive]
@@ -22,18 +29,40 @@ impl AicClientSource {
         let static_service = match self {
             #[cfg(feature = "embed")]
             AicClientSource::Embedded => axum::routing::get(embedded::client),
-            AicClientSource::Workspace => {
-                axum::routing::get_service(tower_http::services::ServeDir::new(concat!(
-                    env!("CARGO_MANIFEST_DIR"),
-                    "/../all-is-cubes-wasm/dist/"
-                )))
-            }
+            AicClientSource::Workspace => axum::routing::get_service(
+                tower_http::services::ServeDir::new(WORKSPACE_CLIENT_DIR),
+            ),
         };
 
         axum::Router::new()
             .route("/", static_service.clone())
             .route("/*path", static_service)
     }
+
+    /// Writes all client files into the existing directory `out`.
+    pub(crate) fn write_to(&self, out: &Path) -> io::Result<()> {
+        match self {
+            #[cfg(feature = "embed")]
+            AicClientSource::Embedded => embedded::CLIENT_STATIC.extract(out),
+            AicClientSource::Workspace => copy_dir(Path::new(WORKSPACE_CLIENT_DIR), out),
+        }
+    }
+}
+
+/// Recursively copies the contents of the directory `from` into the existing
+/// directory `to`.
+fn copy_dir(from: &Path, to: &Path) -> io::Result<()> {
+    for entry in fs::read_dir(from)? {
+        let entry = entry?;
+        let destination = to.join(entry.file_name());
+        if entry.file_type()?.is_dir() {
+            fs::create_dir_all(&destination)?;
+            copy_dir(&entry.path(), &destination)?;
+        } else {
+            fs::copy(entry.path(), destination)?;
+        }
+    }
+    Ok(())
 }
 
 #[cfg(feature = "embed")]
@@ -43,7 +72,7 @@ mod embedded {
     use axum::http::StatusCode;
     use axum::response::{IntoResponse, Response};
 
-    static CLIENT_STATIC: include_dir::Dir<'static> =
+    pub(super) static CLIENT_STATIC: include_dir::Dir<'static> =
         include_dir::include_dir!("$CARGO_MANIFEST_DIR/../all-is-cubes-wasm/dist");
 
     /// Handler for client static files
@@ -0,0 +1,323 @@
+//! Reading the configuration file for `aic-server serve`.
+
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::path::{Path, PathBuf};
+use std::str::FromStr as _;
+use std::time::Duration;
+
+use anyhow::Context as _;
+
+use all_is_cubes_content::UniverseTemplate;
+
+use crate::game::{save_format, AutosaveConfig, GameConfig, WorldSource};
+use crate::{AicClientSource, ServerConfig, TlsConfig};
+
+/// Configuration read from a TOML file, for running a dedicated game server.
+///
+/// See the crate's README for the format of the file.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[non_exhaustive]
+pub struct ServeConfig {
+    /// Configuration to pass to [`start_server()`](crate::start_server).
+    pub server: ServerConfig,
+    /// How the server should log.
+    pub log: LogConfig,
+}
+
+/// How a server should log, as specified by the `[log]` section of a configuration file.
+#[derive(Clone, Debug, Default, Eq, PartialEq, serde::Deserialize)]
+#[serde(default, deny_unknown_fields)]
+#[non_exhaustive]
+pub struct LogConfig {
+    /// Format of log messages.
+    pub format: LogFormat,
+    /// Whether to include messages at the trace level.
+    pub verbose: bool,
+}
+
+/// Format of log messages, for [`LogConfig`].
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+#[non_exhaustive]
+pub enum LogFormat {
+    /// Human-readable lines of text.
+    #[default]
+    Text,
+    /// One JSON object per line, with the fields `time`, `level`, `target`, and
+    /// `message`, for consumption by log collectors.
+    Json,
+}
+
+impl ServeConfig {
+    /// Reads the configuration file at `path`. Relative paths in the file are
+    /// interpreted relative to the directory containing it.
+    pub fn read(path: &Path) -> Result<Self, anyhow::Error> {
+        let text = std::fs::read_to_string(path)
+            .with_context(|| format!("could not read config file {}", path.display()))?;
+        Self::from_toml(&text, path.parent().unwrap_or(Path::new("")))
+            .with_context(|| format!("invalid config file {}", path.display()))
+    }
+
+    /// Parses the text of a configuration file. Relative paths in the file are
+    /// interpreted relative to `base_dir`.
+    pub fn from_toml(text: &str, base_dir: &Path) -> Result<Self, anyhow::Error> {
+        let ConfigFile {
+            bind,
+            port,
+            base_path,
+            client_source,
+            tls,
+            trusted_proxies,
+            content_packs,
+            world,
+            tick_rate,
+            autosave,
+            admin_tokens,
+            session_grace_period,
+            log,
+        } = toml::from_str(text)?;
+        let resolve = |path: PathBuf| base_dir.join(path);
+
+        let client_source = match client_source.as_deref() {
+            #[cfg(feature = "embed")]
+            Some("embedded") | None => AicClientSource::Embedded,
+            #[cfg(not(feature = "embed"))]
+            None => AicClientSource::Workspace,
+            Some("workspace") => AicClientSource::Workspace,
+            Some(other) => anyhow::bail!("unknown client_source {other:?}"),
+        };
+
+        let world = match world {
+            WorldTable {
+                template: Some(name),
+                seed,
+                save: None,
+            } => WorldSource::Template {
+                template: UniverseTemplate::from_str(&name)
+                    .ok()
+                    .with_context(|| format!("unknown template {name:?}"))?,
+                seed,
+            },
+            WorldTable {
+                template: None,
+                seed: None,
+                save: Some(path),
+            } => WorldSource::Save(resolve(path)),
+            WorldTable {
+                template: None,
+                seed: Some(_),
+                save: Some(_),
+            } => anyhow::bail!("world.seed may only be given with world.template"),
+            _ => anyhow::bail!("exactly one of world.template and world.save must be given"),
+        };
+
+        if !(1.0..=1000.0).contains(&tick_rate) {
+            anyhow::bail!("tick_rate must be between 1 and 1000 steps per second");
+        }
+
+        let autosave = autosave
+            .map(|AutosaveTable { path, interval }| {
+                let path = resolve(path);
+                save_format(&path)?;
+                if interval == 0 {
+                    anyhow::bail!("autosave.interval must be at least 1 second");
+                }
+                Ok(AutosaveConfig::new(path, Duration::from_secs(interval)))
+            })
+            .transpose()?;
+
+        if session_grace_period == 0 {
+            anyhow::bail!("session_grace_period must be at least 1 second");
+        }
+
+        if admin_tokens.iter().any(String::is_empty) {
+            anyhow::bail!("admin_tokens must not contain an empty token");
+        }
+
+        let mut server = ServerConfig::new(SocketAddr::new(bind, port), client_source);
+        server.base_path = base_path;
+        server.tls = tls.map(|TlsTable { cert, key }| TlsConfig::new(resolve(cert), resolve(key)));
+        server.trusted_proxies = trusted_proxies;
+        server.content_packs = content_packs.into_iter().map(resolve).collect();
+        server.game = Some(GameConfig {
+            world,
+            tick_interval: Duration::from_secs_f64(tick_rate.recip()),
+            autosave,
+            admin_tokens,
+            session_grace_period: Duration::from_secs(session_grace_period),
+        });
+
+        Ok(Self { server, log })
+    }
+}
+
+/// Format of the configuration file, before validation.
+#[derive(Debug, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ConfigFile {
+    #[serde(default = "default_bind")]
+    bind: IpAddr,
+    #[serde(default)]
+    port: u16,
+    #[serde(default)]
+    base_path: String,
+    client_source: Option<String>,
+    tls: Option<TlsTable>,
+    #[serde(default)]
+    trusted_proxies: Vec<IpAddr>,
+    #[serde(default)]
+    content_packs: Vec<PathBuf>,
+    world: WorldTable,
+    #[serde(default = "default_tick_rate")]
+    tick_rate: f64,
+    autosave: Option<AutosaveTable>,
+    #[serde(default)]
+    admin_tokens: Vec<String>,
+    /// Seconds to keep the character of a disconnected session.
+    #[serde(default = "default_session_grace_period")]
+    session_grace_period: u64,
+    #[serde(default)]
+    log: LogConfig,
+}
+
+#[derive(Debug, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+struct WorldTable {
+    template: Option<String>,
+    seed: Option<u64>,
+    save: Option<PathBuf>,
+}
+
+#[derive(Debug, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+struct AutosaveTable {
+    path: PathBuf,
+    /// Seconds between saves.
+    #[serde(default = "default_autosave_interval")]
+    interval: u64,
+}
+
+#[derive(Debug, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+struct TlsTable {
+    cert: PathBuf,
+    key: PathBuf,
+}
+
+fn default_bind() -> IpAddr {
+    IpAddr::V4(Ipv4Addr::LOCALHOST)
+}
+
+fn default_tick_rate() -> f64 {
+    60.0
+}
+
+fn default_autosave_interval() -> u64 {
+    300
+}
+
+fn default_session_grace_period() -> u64 {
+    60
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(text: &str) -> Result<ServeConfig, anyhow::Error> {
+        ServeConfig::from_toml(text, Path::new("/srv/aic"))
+    }
+
+    #[test]
+    fn full_config() {
+        let config = parse(
+            r#"
+            bind = "0.0.0.0"
+            port = 8080
+            client_source = "workspace"
+            tick_rate = 20
+            admin_tokens = ["secret"]
+            session_grace_period = 120
+            content_packs = ["packs/extra"]
+
+            [world]
+            template = "demo-city"
+            seed = 4
+
+            [autosave]
+            path = "world.alliscubes"
+            interval = 60
+
+            [log]
+            format = "json"
+            "#,
+        )
+        .unwrap();
+
+        let mut expected =
+            ServerConfig::new("0.0.0.0:8080".parse().unwrap(), AicClientSource::Workspace);
+        expected.content_packs = vec![PathBuf::from("/srv/aic/packs/extra")];
+        expected.game = Some(GameConfig {
+            world: WorldSource::Template {
+                template: UniverseTemplate::DemoCity,
+                seed: Some(4),
+            },
+            tick_interval: Duration::from_millis(50),
+            autosave: Some(AutosaveConfig::new(
+                PathBuf::from("/srv/aic/world.alliscubes"),
+                Duration::from_secs(60),
+            )),
+            admin_tokens: vec![String::from("secret")],
+            session_grace_period: Duration::from_secs(120),
+        });
+        assert_eq!(
+            config,
+            ServeConfig {
+                server: expected,
+                log: LogConfig {
+                    format: LogFormat::Json,
+                    verbose: false,
+                },
+            }
+        );
+    }
+
+    #[test]
+    fn minimal_config() {
+        let config = parse("[world]\nsave = \"/elsewhere/world.alliscubesjson\"").unwrap();
+        let game = config.server.game.unwrap();
+        assert_eq!(
+            game.world,
+            WorldSource::Save(PathBuf::from("/elsewhere/world.alliscubesjson"))
+        );
+        assert_eq!(game.autosave, None);
+        assert_eq!(game.admin_tokens, Vec::<String>::new());
+        assert_eq!(game.session_grace_period, Duration::from_secs(60));
+        assert_eq!(config.server.bind_addr, "127.0.0.1:0".parse().unwrap());
+        assert_eq!(config.log, LogConfig::default());
+    }
+
+    #[test]
+    fn world_errors() {
+        parse("").unwrap_err();
+        parse("[world]").unwrap_err();
+        parse("[world]\ntemplate = \"blank\"\nsave = \"a.alliscubes\"").unwrap_err();
+        parse("[world]\nseed = 1\nsave = \"a.alliscubes\"").unwrap_err();
+        parse("[world]\ntemplate = \"no-such-template\"").unwrap_err();
+    }
+
+    #[test]
+    fn value_errors() {
+        let world = "[world]\ntemplate = \"blank\"\n";
+        parse(&format!("tick_rate = 0\n{world}")).unwrap_err();
+        parse(&format!("session_grace_period = 0\n{world}")).unwrap_err();
+        parse(&format!("admin_tokens = [\"\"]\n{world}")).unwrap_err();
+        parse(&format!("unknown_key = 1\n{world}")).unwrap_err();
+        parse(&format!("{world}[autosave]\npath = \"world.txt\"")).unwrap_err();
+        parse(&format!(
+            "{world}[autosave]\npath = \"a.alliscubes\"\ninterval = 0"
+        ))
+        .unwrap_err();
+        parse(&format!("{world}[log]\nformat = \"xml\"")).unwrap_err();
+    }
+}
@@ -0,0 +1,81 @@
+//! Writing the web client and a universe as files which can be served by any static web
+//! server, without this server's participation.
+
+use std::fs;
+use std::path::Path;
+
+use anyhow::Context as _;
+
+use crate::client_static::AicClientSource;
+
+/// Writes the client from `client_source`, and a copy of `universe_file`, into the
+/// directory `out`, such that opening the written `index.html` plays in that universe.
+///
+/// `universe_file` may be in any format the client can load; it is not checked.
+/// The directory `out` is created if it does not exist; existing files are overwritten.
+pub fn export_static(
+    client_source: &AicClientSource,
+    universe_file: &Path,
+    out: &Path,
+) -> Result<(), anyhow::Error> {
+    fs::create_dir_all(out).with_context(|| format!("could not create {}", out.display()))?;
+    client_source
+        .write_to(out)
+        .with_context(|| format!("could not write client files to {}", out.display()))?;
+
+    // Use a fixed name so that it needs no escaping in HTML or URLs, but keep the
+    // extension for the benefit of anyone looking at the files.
+    let universe_name = match universe_file.extension().and_then(|ext| ext.to_str()) {
+        Some(ext) if ext.chars().all(|c| c.is_ascii_alphanumeric()) => format!("universe.{ext}"),
+        _ => String::from("universe"),
+    };
+    fs::copy(universe_file, out.join(&universe_name))
+        .with_context(|| format!("could not copy {}", universe_file.display()))?;
+
+    let index_path = out.join("index.html");
+    let index = fs::read_to_string(&index_path)
+        .with_context(|| format!("could not read {}", index_path.display()))?;
+    fs::write(&index_path, specify_universe(&index, &universe_name)?)
+        .with_context(|| format!("could not write {}", index_path.display()))?;
+
+    Ok(())
+}
+
+/// Adds to the client's `index.html` the element which tells the client to load the
+/// universe at `universe_url` rather than generating one.
+///
+/// `universe_url` must not need escaping.
+fn specify_universe(index_html: &str, universe_url: &str) -> Result<String, anyhow::Error> {
+    const HEAD: &str = "<head>";
+    let insert_at = index_html
+        .find(HEAD)
+        .context("client index.html has no <head>")?
+        + HEAD.len();
+    let (before, after) = index_html.split_at(insert_at);
+    Ok(format!(
+        "{before}\n  <meta name=\"all-is-cubes-universe\" content=\"{universe_url}\">{after}"
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn specify_universe_inserts_meta() {
+        assert_eq!(
+            specify_universe(
+                "<html><head>\n  <title>All is Cubes</title>\n</head></html>",
+                "universe.alliscubesjson"
+            )
+            .unwrap(),
+            "<html><head>\n  <meta name=\"all-is-cubes-universe\" \
+                content=\"universe.alliscubesjson\">\n  <title>All is Cubes</title>\n</head></html>"
+        );
+    }
+
+    #[test]
+    fn specify_universe_without_head() {
+        specify_universe("<html></html>", "universe").unwrap_err();
+    }
+}
@@ -0,0 +1,767 @@
+//! Hosting a game universe: stepping it in real time, saving it periodically,
+//! letting players join it, and letting administrators inspect, save, and moderate it
+//! over HTTP and monitor it with Prometheus.
+//!
+//! The game runs in a headless [`Session`] on a blocking thread, so that stepping and
+//! saving do not hold up the handling of HTTP requests; requests reach it by channel.
+
+use std::collections::{BTreeSet, VecDeque};
+use std::fmt::Write as _;
+use std::net::IpAddr;
+use std::path::{Path, PathBuf};
+use std::sync::{mpsc, Arc, Mutex};
+use std::time::Duration;
+
+use anyhow::Context as _;
+use axum::extract::{self, State};
+use axum::http::{header, HeaderMap, StatusCode};
+use axum::response::IntoResponse as _;
+use axum::{Extension, Json};
+use tokio::sync::oneshot;
+
+use all_is_cubes::character::Character;
+use all_is_cubes::time::Instant;
+use all_is_cubes::universe::{PartialUniverse, URef, Universe};
+use all_is_cubes::util::YieldProgress;
+use all_is_cubes_content::{TemplateParameters, UniverseTemplate};
+use all_is_cubes_port::{ExportFormat, ExportSet};
+use all_is_cubes_ui::apps::{FrameClock, Session};
+
+use crate::session::{PlayerInfo, SessionInfo, Sessions};
+use crate::webserver::ClientAddr;
+
+/// Configuration of the game a server hosts, for [`ServerConfig::game`].
+///
+/// [`ServerConfig::game`]: crate::ServerConfig::game
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[non_exhaustive]
+pub struct GameConfig {
+    /// Where the universe comes from.
+    pub world: WorldSource,
+
+    /// Real time between steps of the universe.
+    pub tick_interval: Duration,
+
+    /// If not [`None`], where and how often to save the universe. It is also saved when
+    /// the server shuts down.
+    pub autosave: Option<AutosaveConfig>,
+
+    /// Bearer tokens which grant access to the `admin/` and `metrics` HTTP endpoints.
+    /// If empty, those endpoints are disabled. Empty tokens are ignored.
+    pub admin_tokens: Vec<String>,
+
+    /// How long a player session is kept after its client was last heard from, so that
+    /// the client may reconnect and resume control of its character. After this time,
+    /// the character is removed from the universe.
+    pub session_grace_period: Duration,
+}
+
+impl GameConfig {
+    /// Constructs a [`GameConfig`] stepping 60 times per second, without autosave or
+    /// administrators, and keeping disconnected sessions for one minute.
+    pub fn new(world: WorldSource) -> Self {
+        Self {
+            world,
+            tick_interval: Duration::from_secs(1) / 60,
+            autosave: None,
+            admin_tokens: Vec::new(),
+            session_grace_period: Duration::from_secs(60),
+        }
+    }
+}
+
+/// Where the universe of a [`GameConfig`] comes from.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum WorldSource {
+    /// Generate a new universe from a template.
+    Template {
+        /// The template to use.
+        template: UniverseTemplate,
+        /// Seed for the template's randomization, if it has any.
+        seed: Option<u64>,
+    },
+    /// Load a saved universe, in any format [`all_is_cubes_port`] can import.
+    Save(PathBuf),
+}
+
+/// Where and how often to save the universe of a [`GameConfig`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[non_exhaustive]
+pub struct AutosaveConfig {
+    /// File to write. Its extension should be `.alliscubes` or `.alliscubesjson`, which
+    /// determines the format.
+    pub path: PathBuf,
+    /// Time between saves.
+    pub interval: Duration,
+}
+
+impl AutosaveConfig {
+    /// Constructs an [`AutosaveConfig`].
+    pub fn new(path: PathBuf, interval: Duration) -> Self {
+        Self { path, interval }
+    }
+}
+
+/// Creates the universe described by `source`.
+pub(crate) async fn load_world(source: &WorldSource) -> Result<Universe, anyhow::Error> {
+    match source {
+        WorldSource::Template { template, seed } => template
+            .clone()
+            .build(
+                YieldProgress::noop(),
+                TemplateParameters {
+                    seed: *seed,
+                    size: None,
+                },
+            )
+            .await
+            .with_context(|| format!("could not generate world from template {template}")),
+        WorldSource::Save(path) => {
+            all_is_cubes_port::load_universe_from_file(YieldProgress::noop(), path.as_path())
+                .await
+                .with_context(|| format!("could not load world {}", path.display()))
+        }
+    }
+}
+
+/// A running game, which can be joined, asked about its status or to save, and stopped.
+#[derive(Debug)]
+pub(crate) struct GameHandle {
+    commands: mpsc::SyncSender<GameCommand>,
+    admin_tokens: Vec<String>,
+    /// Client addresses which may not start sessions. Bans last until the server stops.
+    bans: Mutex<BTreeSet<IpAddr>>,
+}
+
+#[derive(Debug)]
+enum GameCommand {
+    Status(oneshot::Sender<GameStatus>),
+    Save(oneshot::Sender<Result<(), String>>),
+    Players(oneshot::Sender<Vec<PlayerInfo>>),
+    Kick(String, oneshot::Sender<Option<IpAddr>>),
+    Join(IpAddr, oneshot::Sender<Result<SessionInfo, String>>),
+    Resume(String, oneshot::Sender<Option<SessionInfo>>),
+    Leave(String, oneshot::Sender<bool>),
+    Stop,
+}
+
+/// Response to `GET admin/status`, and the source of `GET metrics`.
+#[derive(Clone, Debug, PartialEq, serde::Serialize)]
+struct GameStatus {
+    steps: u64,
+    /// Number of steps taken in the last second.
+    ticks_per_second: usize,
+    /// Number of transactions committed by stepping the universe, as counted by
+    /// [`UniverseStepInfo::transactions_committed`].
+    ///
+    /// [`UniverseStepInfo::transactions_committed`]: all_is_cubes::universe::UniverseStepInfo::transactions_committed
+    transactions_committed: u64,
+    members: usize,
+    sessions: usize,
+    autosave_path: Option<PathBuf>,
+}
+
+impl GameHandle {
+    /// Starts stepping `universe` according to `config`, in a blocking task which runs
+    /// until [`GameHandle::stop()`] is called.
+    ///
+    /// Must be called from within a Tokio runtime.
+    pub(crate) fn start(
+        universe: Universe,
+        config: GameConfig,
+    ) -> (Arc<Self>, tokio::task::JoinHandle<()>) {
+        let GameConfig {
+            world: _,
+            tick_interval,
+            autosave,
+            admin_tokens,
+            session_grace_period,
+        } = config;
+        let (commands, receiver) = mpsc::sync_channel(64);
+        let task = tokio::task::spawn_blocking(move || {
+            run_game(
+                universe,
+                tick_interval,
+                autosave,
+                Sessions::new(session_grace_period),
+                receiver,
+            )
+        });
+        (
+            Arc::new(Self {
+                commands,
+                admin_tokens,
+                bans: Mutex::new(BTreeSet::new()),
+            }),
+            task,
+        )
+    }
+
+    /// Asks the game to save, if configured to, and stop stepping. Wait for the task
+    /// returned by [`GameHandle::start()`] to know when it has done so.
+    pub(crate) async fn stop(&self) {
+        loop {
+            match self.commands.try_send(GameCommand::Stop) {
+                // If the task has already ended, there's nothing to stop.
+                Ok(()) | Err(mpsc::TrySendError::Disconnected(_)) => return,
+                Err(mpsc::TrySendError::Full(_)) => {
+                    tokio::time::sleep(Duration::from_millis(10)).await;
+                }
+            }
+        }
+    }
+
+    /// Returns a router for the `session`, `admin/`, and `metrics` endpoints.
+    pub(crate) fn router(self: &Arc<Self>) -> axum::Router {
+        axum::Router::new()
+            .route(
+                "/session",
+                axum::routing::post(session_join).delete(session_leave),
+            )
+            .route("/session/resume", axum::routing::post(session_resume))
+            .route("/admin/status", axum::routing::get(admin_status))
+            .route("/admin/save", axum::routing::post(admin_save))
+            .route("/admin/players", axum::routing::get(admin_players))
+            .route(
+                "/admin/players/:character/kick",
+                axum::routing::post(admin_kick),
+            )
+            .route(
+                "/admin/players/:character/ban",
+                axum::routing::post(admin_ban),
+            )
+            .route("/admin/bans", axum::routing::get(admin_bans))
+            .route("/admin/bans/:address", axum::routing::delete(admin_unban))
+            .route("/metrics", axum::routing::get(metrics))
+            .with_state(self.clone())
+    }
+
+    fn is_banned(&self, address: IpAddr) -> bool {
+        self.bans.lock().unwrap().contains(&address)
+    }
+
+    /// Checks the `Authorization` header of an admin request.
+    fn authorize(&self, headers: &HeaderMap) -> Result<(), StatusCode> {
+        if self.admin_tokens.is_empty() {
+            return Err(StatusCode::NOT_FOUND);
+        }
+        let token = bearer_token(headers).ok_or(StatusCode::UNAUTHORIZED)?;
+        if self
+            .admin_tokens
+            .iter()
+            .any(|allowed| !allowed.is_empty() && tokens_equal(allowed, token))
+        {
+            Ok(())
+        } else {
+            Err(StatusCode::FORBIDDEN)
+        }
+    }
+
+    async fn request<T>(
+        &self,
+        command: impl FnOnce(oneshot::Sender<T>) -> GameCommand,
+    ) -> Result<T, StatusCode> {
+        let (reply, response) = oneshot::channel();
+        // Sending does not wait, so that a busy game cannot block the HTTP runtime.
+        self.commands
+            .try_send(command(reply))
+            .map_err(|_| StatusCode::SERVICE_UNAVAILABLE)?;
+        response.await.map_err(|_| StatusCode::SERVICE_UNAVAILABLE)
+    }
+}
+
+/// Returns the token from an `Authorization: Bearer` header, if there is one.
+fn bearer_token(headers: &HeaderMap) -> Option<&str> {
+    headers
+        .get(header::AUTHORIZATION)?
+        .to_str()
+        .ok()?
+        .strip_prefix("Bearer ")
+}
+
+/// `POST session`: spawns a new character and returns a session controlling it,
+/// unless the client's address is banned.
+async fn session_join(
+    State(game): State<Arc<GameHandle>>,
+    Extension(ClientAddr(address)): Extension<ClientAddr>,
+) -> Result<Json<SessionInfo>, (StatusCode, String)> {
+    if game.is_banned(address) {
+        return Err((StatusCode::FORBIDDEN, String::new()));
+    }
+    game.request(|reply| GameCommand::Join(address, reply))
+        .await
+        .map_err(|status| (status, String::new()))?
+        .map(Json)
+        .map_err(|message| (StatusCode::SERVICE_UNAVAILABLE, message))
+}
+
+/// `POST session/resume`, with the session token as a bearer token: resumes an
+/// existing session, keeping it from expiring. If the session has already expired,
+/// responds with 404 Not Found, and the client should start a new one.
+async fn session_resume(
+    State(game): State<Arc<GameHandle>>,
+    headers: HeaderMap,
+) -> Result<Json<SessionInfo>, StatusCode> {
+    let token = bearer_token(&headers).ok_or(StatusCode::UNAUTHORIZED)?;
+    game.request(|reply| GameCommand::Resume(token.to_owned(), reply))
+        .await?
+        .map(Json)
+        .ok_or(StatusCode::NOT_FOUND)
+}
+
+/// `DELETE session`, with the session token as a bearer token: ends the session
+/// without waiting for the grace period.
+async fn session_leave(
+    State(game): State<Arc<GameHandle>>,
+    headers: HeaderMap,
+) -> Result<StatusCode, StatusCode> {
+    let token = bearer_token(&headers).ok_or(StatusCode::UNAUTHORIZED)?;
+    if game
+        .request(|reply| GameCommand::Leave(token.to_owned(), reply))
+        .await?
+    {
+        Ok(StatusCode::NO_CONTENT)
+    } else {
+        Err(StatusCode::NOT_FOUND)
+    }
+}
+
+async fn admin_status(
+    State(game): State<Arc<GameHandle>>,
+    headers: HeaderMap,
+) -> Result<Json<GameStatus>, StatusCode> {
+    game.authorize(&headers)?;
+    Ok(Json(game.request(GameCommand::Status).await?))
+}
+
+async fn admin_save(
+    State(game): State<Arc<GameHandle>>,
+    headers: HeaderMap,
+) -> Result<(), (StatusCode, String)> {
+    game.authorize(&headers)
+        .map_err(|status| (status, String::new()))?;
+    game.request(GameCommand::Save)
+        .await
+        .map_err(|status| (status, String::new()))?
+        .map_err(|message| (StatusCode::INTERNAL_SERVER_ERROR, message))
+}
+
+/// `GET admin/players`: lists the current sessions.
+async fn admin_players(
+    State(game): State<Arc<GameHandle>>,
+    headers: HeaderMap,
+) -> Result<Json<Vec<PlayerInfo>>, StatusCode> {
+    game.authorize(&headers)?;
+    Ok(Json(game.request(GameCommand::Players).await?))
+}
+
+/// `POST admin/players/{character}/kick`: ends the session controlling the character.
+/// The client may join again.
+async fn admin_kick(
+    State(game): State<Arc<GameHandle>>,
+    extract::Path(character): extract::Path<String>,
+    headers: HeaderMap,
+) -> Result<StatusCode, StatusCode> {
+    game.authorize(&headers)?;
+    game.request(|reply| GameCommand::Kick(character, reply))
+        .await?
+        .map(|_| StatusCode::NO_CONTENT)
+        .ok_or(StatusCode::NOT_FOUND)
+}
+
+/// `POST admin/players/{character}/ban`: ends the session controlling the character,
+/// and refuses new sessions from the address of its client.
+async fn admin_ban(
+    State(game): State<Arc<GameHandle>>,
+    extract::Path(character): extract::Path<String>,
+    headers: HeaderMap,
+) -> Result<StatusCode, StatusCode> {
+    game.authorize(&headers)?;
+    let address = game
+        .request(|reply| GameCommand::Kick(character.clone(), reply))
+        .await?
+        .ok_or(StatusCode::NOT_FOUND)?;
+    log::info!("Banned {address}, the client of {character}");
+    game.bans.lock().unwrap().insert(address);
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// `GET admin/bans`: lists the banned addresses.
+async fn admin_bans(
+    State(game): State<Arc<GameHandle>>,
+    headers: HeaderMap,
+) -> Result<Json<Vec<IpAddr>>, StatusCode> {
+    game.authorize(&headers)?;
+    Ok(Json(game.bans.lock().unwrap().iter().copied().collect()))
+}
+
+/// `DELETE admin/bans/{address}`: lifts a ban.
+async fn admin_unban(
+    State(game): State<Arc<GameHandle>>,
+    extract::Path(address): extract::Path<IpAddr>,
+    headers: HeaderMap,
+) -> Result<StatusCode, StatusCode> {
+    game.authorize(&headers)?;
+    if game.bans.lock().unwrap().remove(&address) {
+        log::info!("Unbanned {address}");
+        Ok(StatusCode::NO_CONTENT)
+    } else {
+        Err(StatusCode::NOT_FOUND)
+    }
+}
+
+/// `GET metrics`: reports the game's status in the Prometheus text format.
+async fn metrics(
+    State(game): State<Arc<GameHandle>>,
+    headers: HeaderMap,
+) -> Result<axum::response::Response, StatusCode> {
+    game.authorize(&headers)?;
+    let status = game.request(GameCommand::Status).await?;
+    Ok((
+        [(header::CONTENT_TYPE, "text/plain; version=0.0.4")],
+        format_metrics(&status),
+    )
+        .into_response())
+}
+
+/// Formats `status` in the Prometheus text exposition format.
+fn format_metrics(status: &GameStatus) -> String {
+    let GameStatus {
+        steps,
+        ticks_per_second,
+        transactions_committed,
+        members,
+        sessions,
+        autosave_path: _,
+    } = status;
+    let mut text = String::new();
+    let mut metric = |name: &str, kind: &str, help: &str, value: &dyn std::fmt::Display| {
+        let _ = write!(
+            text,
+            "# HELP {name} {help}\n# TYPE {name} {kind}\n{name} {value}\n"
+        );
+    };
+    metric(
+        "aic_steps_total",
+        "counter",
+        "Steps of the game universe since the server started.",
+        steps,
+    );
+    metric(
+        "aic_ticks_per_second",
+        "gauge",
+        "Steps of the game universe in the last second.",
+        ticks_per_second,
+    );
+    metric(
+        "aic_players_online",
+        "gauge",
+        "Current player sessions.",
+        sessions,
+    );
+    metric(
+        "aic_transactions_applied_total",
+        "counter",
+        "Transactions committed by stepping the game universe.",
+        transactions_committed,
+    );
+    metric(
+        "aic_universe_members",
+        "gauge",
+        "Members of the game universe.",
+        members,
+    );
+    text
+}
+
+/// Compares tokens in time depending only on their lengths, so that the time taken
+/// does not reveal how much of a guessed token is correct.
+fn tokens_equal(a: &str, b: &str) -> bool {
+    a.len() == b.len()
+        && a.bytes()
+            .zip(b.bytes())
+            .fold(0, |difference, (a, b)| difference | (a ^ b))
+            == 0
+}
+
+/// Steps the game and handles commands until [`GameCommand::Stop`] is received or the
+/// [`GameHandle`] is dropped. This blocks, and must run on a thread of its own, such as
+/// by [`tokio::task::spawn_blocking()`].
+fn run_game(
+    universe: Universe,
+    tick_interval: Duration,
+    autosave: Option<AutosaveConfig>,
+    mut sessions: Sessions,
+    commands: mpsc::Receiver<GameCommand>,
+) {
+    let runtime = tokio::runtime::Handle::current();
+
+    // The session has no user interface; it only steps the universe on schedule.
+    let mut session = runtime.block_on(Session::builder().build());
+    session.frame_clock = FrameClock::with_step_length(tick_interval);
+    session.set_universe(universe);
+
+    let mut next_autosave = autosave
+        .as_ref()
+        .map(|autosave| Instant::now() + autosave.interval);
+    let mut steps: u64 = 0;
+    let mut transactions_committed: u64 = 0;
+    // Times of the steps taken in the last second.
+    let mut recent_steps: VecDeque<Instant> = VecDeque::new();
+
+    log::info!(
+        "Game started with {} members, stepping every {tick_interval:?}",
+        PartialUniverse::all_of(session.universe_mut()).count(),
+    );
+
+    'game: loop {
+        let now = Instant::now();
+        sessions.expire(session.universe_mut(), now);
+        session.frame_clock.advance_to(now);
+        if let Some(info) = session.maybe_step_universe() {
+            steps += 1;
+            transactions_committed += u64::try_from(info.transactions_committed).unwrap();
+            recent_steps.push_back(now);
+        }
+        while let Some(&time) = recent_steps.front() {
+            if now.saturating_duration_since(time) < Duration::from_secs(1) {
+                break;
+            }
+            recent_steps.pop_front();
+        }
+
+        if let (Some(autosave), Some(due)) = (&autosave, &mut next_autosave) {
+            if now >= *due {
+                // Errors are logged by autosave_now().
+                let _ = runtime.block_on(autosave_now(
+                    session.universe_mut(),
+                    &sessions.characters(),
+                    Some(autosave),
+                ));
+                *due = Instant::now() + autosave.interval;
+            }
+        }
+
+        // Handle commands until it is time for the next step.
+        let next_step = session.frame_clock.next_step_or_draw_time().unwrap_or(now);
+        loop {
+            let timeout = next_step.saturating_duration_since(Instant::now());
+            let command = match commands.recv_timeout(timeout) {
+                Ok(command) => command,
+                Err(mpsc::RecvTimeoutError::Timeout) => break,
+                Err(mpsc::RecvTimeoutError::Disconnected) => break 'game,
+            };
+            let universe = session.universe_mut();
+            match command {
+                GameCommand::Status(reply) => {
+                    let _ = reply.send(GameStatus {
+                        steps,
+                        ticks_per_second: recent_steps.len(),
+                        transactions_committed,
+                        members: PartialUniverse::all_of(universe).count(),
+                        sessions: sessions.len(),
+                        autosave_path: autosave.as_ref().map(|a| a.path.clone()),
+                    });
+                }
+                GameCommand::Save(reply) => {
+                    let result = runtime.block_on(autosave_now(
+                        universe,
+                        &sessions.characters(),
+                        autosave.as_ref(),
+                    ));
+                    let _ = reply.send(result.map_err(|e| format!("{e:#}")));
+                }
+                GameCommand::Players(reply) => {
+                    let _ = reply.send(sessions.players(Instant::now()));
+                }
+                GameCommand::Kick(character, reply) => {
+                    let _ = reply.send(sessions.kick(universe, &character));
+                }
+                GameCommand::Join(address, reply) => {
+                    let result = sessions.join(universe, Instant::now(), address);
+                    let _ = reply.send(result.map_err(|e| format!("{e:#}")));
+                }
+                GameCommand::Resume(token, reply) => {
+                    let _ = reply.send(sessions.resume(&token, Instant::now()));
+                }
+                GameCommand::Leave(token, reply) => {
+                    let _ = reply.send(sessions.leave(universe, &token));
+                }
+                GameCommand::Stop => break 'game,
+            }
+        }
+    }
+
+    if autosave.is_some() {
+        let _ = runtime.block_on(autosave_now(
+            session.universe_mut(),
+            &sessions.characters(),
+            autosave.as_ref(),
+        ));
+    }
+    log::info!("Game stopped after {steps} steps");
+}
+
+/// Saves `universe` as configured by `autosave`, logging the outcome.
+///
+/// The `excluded` characters, which belong to sessions, are not saved, since the
+/// sessions themselves do not survive a restart.
+async fn autosave_now(
+    universe: &Universe,
+    excluded: &[URef<Character>],
+    autosave: Option<&AutosaveConfig>,
+) -> Result<(), anyhow::Error> {
+    let Some(autosave) = autosave else {
+        anyhow::bail!("no autosave path is configured");
+    };
+    match save(universe, excluded, &autosave.path).await {
+        Ok(()) => {
+            log::info!("Saved world to {}", autosave.path.display());
+            Ok(())
+        }
+        Err(e) => {
+            log::error!("Failed to save world: {e:#}");
+            Err(e)
+        }
+    }
+}
+
+/// Writes `universe`, except for the `excluded` characters, to `path`, in the format
+/// given by its extension.
+///
+/// The data is first written to a temporary file beside it, so that a failed save
+/// does not destroy the previous one.
+async fn save(
+    universe: &Universe,
+    excluded: &[URef<Character>],
+    path: &Path,
+) -> Result<(), anyhow::Error> {
+    let format = save_format(path)?;
+    let mut temp_name = path
+        .file_name()
+        .with_context(|| format!("save path {} has no file name", path.display()))?
+        .to_owned();
+    temp_name.push(".tmp");
+    let temp_path = path.with_file_name(temp_name);
+
+    all_is_cubes_port::export_to_path(
+        YieldProgress::noop(),
+        format,
+        ExportSet::all_of_universe(universe).without_characters(excluded),
+        temp_path.clone(),
+    )
+    .await
+    .with_context(|| format!("could not write {}", temp_path.display()))?;
+    std::fs::rename(&temp_path, path)
+        .with_context(|| format!("could not replace {}", path.display()))?;
+    Ok(())
+}
+
+/// Chooses the format in which to save to `path`.
+pub(crate) fn save_format(path: &Path) -> Result<ExportFormat, anyhow::Error> {
+    match path.extension().and_then(|ext| ext.to_str()) {
+        Some("alliscubes") => Ok(ExportFormat::AicBinary),
+        Some("alliscubesjson") => Ok(ExportFormat::AicJson),
+        _ => anyhow::bail!(
+            "save path {} must have the extension .alliscubes or .alliscubesjson",
+            path.display()
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::HeaderValue;
+
+    fn handle(admin_tokens: &[&str]) -> GameHandle {
+        GameHandle {
+            commands: mpsc::sync_channel(1).0,
+            admin_tokens: admin_tokens.iter().map(|&t| String::from(t)).collect(),
+            bans: Mutex::new(BTreeSet::new()),
+        }
+    }
+
+    fn headers(authorization: Option<&'static str>) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        if let Some(value) = authorization {
+            headers.insert(header::AUTHORIZATION, HeaderValue::from_static(value));
+        }
+        headers
+    }
+
+    #[test]
+    fn authorize() {
+        let game = handle(&["alpha", "beta"]);
+        assert_eq!(game.authorize(&headers(Some("Bearer alpha"))), Ok(()));
+        assert_eq!(game.authorize(&headers(Some("Bearer beta"))), Ok(()));
+        assert_eq!(
+            game.authorize(&headers(Some("Bearer alph"))),
+            Err(StatusCode::FORBIDDEN)
+        );
+        assert_eq!(
+            game.authorize(&headers(Some("alpha"))),
+            Err(StatusCode::UNAUTHORIZED)
+        );
+        assert_eq!(
+            game.authorize(&headers(None)),
+            Err(StatusCode::UNAUTHORIZED)
+        );
+    }
+
+    #[test]
+    fn authorize_ignores_empty_token() {
+        assert_eq!(
+            handle(&["", "alpha"]).authorize(&headers(Some("Bearer "))),
+            Err(StatusCode::FORBIDDEN)
+        );
+    }
+
+    #[test]
+    fn authorize_without_tokens() {
+        assert_eq!(
+            handle(&[]).authorize(&headers(Some("Bearer "))),
+            Err(StatusCode::NOT_FOUND)
+        );
+    }
+
+    #[test]
+    fn metrics_format() {
+        let text = format_metrics(&GameStatus {
+            steps: 600,
+            ticks_per_second: 60,
+            transactions_committed: 25,
+            members: 40,
+            sessions: 2,
+            autosave_path: None,
+        });
+        assert_eq!(
+            text.lines()
+                .filter(|line| !line.starts_with('#'))
+                .collect::<Vec<_>>(),
+            vec![
+                "aic_steps_total 600",
+                "aic_ticks_per_second 60",
+                "aic_players_online 2",
+                "aic_transactions_applied_total 25",
+                "aic_universe_members 40",
+            ]
+        );
+        assert!(text.contains("# TYPE aic_players_online gauge\n"));
+    }
+
+    #[test]
+    fn save_format_from_extension() {
+        assert_eq!(
+            save_format(Path::new("a/b.alliscubes")).unwrap(),
+            ExportFormat::AicBinary
+        );
+        assert_eq!(
+            save_format(Path::new("b.alliscubesjson")).unwrap(),
+            ExportFormat::AicJson
+        );
+        save_format(Path::new("b.json")).unwrap_err();
+        save_format(Path::new("b")).unwrap_err();
+    }
+}
@@ -36,7 +36,18 @@
 #![warn(missing_docs)]
 
 mod webserver;
-pub use webserver::start_server;
+pub use webserver::{start_server, ServerConfig, TlsConfig};
 
 mod client_static;
 pub use client_static::AicClientSource;
+
+mod config;
+pub use config::{LogConfig, LogFormat, ServeConfig};
+
+mod export;
+pub use export::export_static;
+
+mod game;
+pub use game::{AutosaveConfig, GameConfig, WorldSource};
+
+mod session;
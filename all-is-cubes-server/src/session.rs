@@ -0,0 +1,395 @@
+//! Player sessions: which client controls which [`Character`], so that a client which
+//! loses its connection can resume control of the same character.
+//!
+//! For now, a session only reserves its character; the server does not yet accept
+//! input from clients, so they cannot actually move or act as it.
+//!
+//! Sessions exist only in memory, so session characters are left out of saves;
+//! otherwise, after a restart they would remain in the world with no session to
+//! control or expire them.
+
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::time::Duration;
+
+use all_is_cubes::character::Character;
+use all_is_cubes::space::Space;
+use all_is_cubes::time::Instant;
+use all_is_cubes::transaction::Transaction as _;
+use all_is_cubes::universe::{
+    InsertError, InsertErrorKind, Name, URef, Universe, UniverseTransaction,
+};
+
+/// The sessions of a hosted game.
+///
+/// Each session has a secret token, which the client presents to resume it, and owns
+/// one [`Character`] in the universe. A session which has not been heard from for the
+/// grace period is ended and its character removed from the universe.
+#[derive(Debug)]
+pub(crate) struct Sessions {
+    grace_period: Duration,
+    sessions: HashMap<String, Session>,
+    /// Used to choose names for new characters.
+    next_character_number: u64,
+}
+
+#[derive(Debug)]
+struct Session {
+    character: URef<Character>,
+    /// Address of the client which started the session.
+    address: IpAddr,
+    last_seen: Instant,
+}
+
+/// Response to `POST session` and `POST session/resume`.
+#[derive(Clone, Debug, Eq, PartialEq, serde::Serialize)]
+pub(crate) struct SessionInfo {
+    /// Token to present in order to resume the session.
+    pub token: String,
+    /// Name of the character the session controls.
+    pub character: String,
+    /// Whether this is an existing session rather than a new one.
+    pub resumed: bool,
+}
+
+/// One entry in the response to `GET admin/players`.
+#[derive(Clone, Debug, PartialEq, serde::Serialize)]
+pub(crate) struct PlayerInfo {
+    /// Name of the character the session controls.
+    pub character: String,
+    /// Address of the client which started the session.
+    pub address: IpAddr,
+    /// Seconds since the client was last heard from.
+    pub idle_seconds: f64,
+}
+
+impl Sessions {
+    pub(crate) fn new(grace_period: Duration) -> Self {
+        Self {
+            grace_period,
+            sessions: HashMap::new(),
+            next_character_number: 1,
+        }
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.sessions.len()
+    }
+
+    /// Returns the characters of all current sessions.
+    pub(crate) fn characters(&self) -> Vec<URef<Character>> {
+        self.sessions
+            .values()
+            .map(|session| session.character.clone())
+            .collect()
+    }
+
+    /// Returns information about every session, ordered by character name.
+    pub(crate) fn players(&self, now: Instant) -> Vec<PlayerInfo> {
+        let mut players: Vec<PlayerInfo> = self
+            .sessions
+            .values()
+            .map(|session| PlayerInfo {
+                character: session.character.name().to_string(),
+                address: session.address,
+                idle_seconds: now
+                    .saturating_duration_since(session.last_seen)
+                    .as_secs_f64(),
+            })
+            .collect();
+        players.sort_by(|a, b| a.character.cmp(&b.character));
+        players
+    }
+
+    /// Starts a new session for the client at `address`, spawning a character for it in
+    /// the same space as the universe's default character, or else in any space.
+    pub(crate) fn join(
+        &mut self,
+        universe: &mut Universe,
+        now: Instant,
+        address: IpAddr,
+    ) -> Result<SessionInfo, anyhow::Error> {
+        let space = spawn_space(universe)
+            .ok_or_else(|| anyhow::anyhow!("the world has no space for characters to spawn in"))?;
+
+        // Skip over names already in use, such as by characters from a loaded save.
+        let character = loop {
+            let name = Name::from(&*format!("player-{}", self.next_character_number));
+            self.next_character_number += 1;
+            match universe.insert(name, Character::spawn_default(space.clone())) {
+                Ok(character) => break character,
+                Err(InsertError {
+                    kind: InsertErrorKind::AlreadyExists,
+                    ..
+                }) => continue,
+                Err(e) => return Err(e.into()),
+            }
+        };
+
+        let token = new_token();
+        let info = SessionInfo {
+            token: token.clone(),
+            character: character.name().to_string(),
+            resumed: false,
+        };
+        log::info!("Session started for {}", info.character);
+        self.sessions.insert(
+            token,
+            Session {
+                character,
+                address,
+                last_seen: now,
+            },
+        );
+        Ok(info)
+    }
+
+    /// Resumes the session with the given token, if it has not ended, and restarts
+    /// its grace period.
+    ///
+    /// Clients should call this periodically, not only after reconnecting, so that
+    /// their session is not ended.
+    pub(crate) fn resume(&mut self, token: &str, now: Instant) -> Option<SessionInfo> {
+        let session = self.sessions.get_mut(token)?;
+        if session.character.read().is_err() {
+            // The character was deleted by some other means, so there is nothing to
+            // resume control of.
+            self.sessions.remove(token);
+            return None;
+        }
+        session.last_seen = now;
+        Some(SessionInfo {
+            token: token.to_owned(),
+            character: session.character.name().to_string(),
+            resumed: true,
+        })
+    }
+
+    /// Ends the session with the given token immediately, removing its character.
+    ///
+    /// Returns whether there was such a session.
+    pub(crate) fn leave(&mut self, universe: &mut Universe, token: &str) -> bool {
+        match self.sessions.remove(token) {
+            Some(session) => {
+                log::info!("Session for {} ended by client", session.character.name());
+                remove_character(universe, session.character);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Ends the session controlling the character named `character`, removing the
+    /// character, at an administrator's request.
+    ///
+    /// Returns the address of the session's client, or [`None`] if there was no such
+    /// session.
+    pub(crate) fn kick(&mut self, universe: &mut Universe, character: &str) -> Option<IpAddr> {
+        let character = Name::from(character);
+        let token = self
+            .sessions
+            .iter()
+            .find(|(_, session)| session.character.name() == character)
+            .map(|(token, _)| token.clone())?;
+        let session = self.sessions.remove(&token).unwrap();
+        log::info!("Session for {character} ended by an administrator");
+        remove_character(universe, session.character);
+        Some(session.address)
+    }
+
+    /// Ends all sessions which have not been resumed within the grace period, removing
+    /// their characters.
+    pub(crate) fn expire(&mut self, universe: &mut Universe, now: Instant) {
+        let grace_period = self.grace_period;
+        let expired: Vec<String> = self
+            .sessions
+            .iter()
+            .filter(|(_, session)| now.saturating_duration_since(session.last_seen) > grace_period)
+            .map(|(token, _)| token.clone())
+            .collect();
+        for token in expired {
+            let session = self.sessions.remove(&token).unwrap();
+            log::info!(
+                "Session for {} expired after {grace_period:?} without contact",
+                session.character.name()
+            );
+            remove_character(universe, session.character);
+        }
+    }
+}
+
+fn spawn_space(universe: &Universe) -> Option<URef<Space>> {
+    universe
+        .get_default_character()
+        .and_then(|character| Some(character.read().ok()?.space.clone()))
+        .or_else(|| {
+            universe
+                .iter_by_type::<Space>()
+                .map(|(_, space)| space)
+                .next()
+        })
+}
+
+fn remove_character(universe: &mut Universe, character: URef<Character>) {
+    let name = character.name();
+    if let Err(e) = UniverseTransaction::delete(character).execute(universe, &mut drop) {
+        log::error!("Failed to remove character {name}: {e}");
+    }
+}
+
+/// Generates a session token which cannot feasibly be guessed.
+fn new_token() -> String {
+    let bytes: [u8; 16] = rand::Rng::gen(&mut rand::thread_rng());
+    bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use all_is_cubes::math::GridAab;
+
+    const GRACE: Duration = Duration::from_secs(60);
+    const ADDRESS: IpAddr = IpAddr::V4(std::net::Ipv4Addr::LOCALHOST);
+
+    fn universe_with_space() -> Universe {
+        let mut universe = Universe::new();
+        universe
+            .insert(
+                "space".into(),
+                Space::empty(GridAab::from_lower_size([0, 0, 0], [1, 1, 1])),
+            )
+            .unwrap();
+        universe
+    }
+
+    #[test]
+    fn join_and_resume() {
+        let mut universe = universe_with_space();
+        let mut sessions = Sessions::new(GRACE);
+        let start = Instant::now();
+
+        let first = sessions.join(&mut universe, start, ADDRESS).unwrap();
+        let second = sessions.join(&mut universe, start, ADDRESS).unwrap();
+        assert_eq!(first.character, "player-1");
+        assert_eq!(second.character, "player-2");
+        assert!(!first.resumed);
+        assert_ne!(first.token, second.token);
+
+        assert_eq!(
+            sessions.resume(&first.token, start + GRACE / 2),
+            Some(SessionInfo {
+                resumed: true,
+                ..first.clone()
+            })
+        );
+        assert_eq!(sessions.resume("not a token", start), None);
+        assert_eq!(sessions.len(), 2);
+    }
+
+    #[test]
+    fn characters() {
+        let mut universe = universe_with_space();
+        let mut sessions = Sessions::new(GRACE);
+        let info = sessions
+            .join(&mut universe, Instant::now(), ADDRESS)
+            .unwrap();
+
+        let characters = sessions.characters();
+        assert_eq!(characters.len(), 1);
+        assert_eq!(characters[0].name().to_string(), info.character);
+    }
+
+    #[test]
+    fn join_skips_existing_names() {
+        let mut universe = universe_with_space();
+        let space = universe.get::<Space>(&"space".into()).unwrap();
+        universe
+            .insert("player-1".into(), Character::spawn_default(space))
+            .unwrap();
+
+        let info = Sessions::new(GRACE)
+            .join(&mut universe, Instant::now(), ADDRESS)
+            .unwrap();
+        assert_eq!(info.character, "player-2");
+    }
+
+    #[test]
+    fn join_without_space() {
+        Sessions::new(GRACE)
+            .join(&mut Universe::new(), Instant::now(), ADDRESS)
+            .unwrap_err();
+    }
+
+    #[test]
+    fn expire_after_grace_period() {
+        let mut universe = universe_with_space();
+        let mut sessions = Sessions::new(GRACE);
+        let start = Instant::now();
+        let kept = sessions.join(&mut universe, start, ADDRESS).unwrap();
+        let dropped = sessions.join(&mut universe, start, ADDRESS).unwrap();
+
+        // Resuming restarts the grace period.
+        sessions.resume(&kept.token, start + GRACE / 2).unwrap();
+        sessions.expire(&mut universe, start + GRACE + Duration::from_secs(1));
+
+        assert!(sessions.resume(&kept.token, start + GRACE).is_some());
+        assert_eq!(sessions.resume(&dropped.token, start + GRACE), None);
+        assert!(universe
+            .get::<Character>(&kept.character.as_str().into())
+            .is_some());
+        assert!(universe
+            .get::<Character>(&dropped.character.as_str().into())
+            .is_none());
+    }
+
+    #[test]
+    fn leave_removes_character() {
+        let mut universe = universe_with_space();
+        let mut sessions = Sessions::new(GRACE);
+        let info = sessions
+            .join(&mut universe, Instant::now(), ADDRESS)
+            .unwrap();
+
+        assert!(sessions.leave(&mut universe, &info.token));
+        assert!(!sessions.leave(&mut universe, &info.token));
+        assert_eq!(sessions.len(), 0);
+        assert!(universe
+            .get::<Character>(&info.character.as_str().into())
+            .is_none());
+    }
+
+    #[test]
+    fn players_and_kick() {
+        let mut universe = universe_with_space();
+        let mut sessions = Sessions::new(GRACE);
+        let start = Instant::now();
+        let other_address: IpAddr = "192.0.2.1".parse().unwrap();
+        let first = sessions.join(&mut universe, start, ADDRESS).unwrap();
+        sessions.join(&mut universe, start, other_address).unwrap();
+
+        assert_eq!(
+            sessions.players(start + Duration::from_secs(2)),
+            vec![
+                PlayerInfo {
+                    character: "player-1".into(),
+                    address: ADDRESS,
+                    idle_seconds: 2.0,
+                },
+                PlayerInfo {
+                    character: "player-2".into(),
+                    address: other_address,
+                    idle_seconds: 2.0,
+                },
+            ]
+        );
+
+        assert_eq!(
+            sessions.kick(&mut universe, "player-2"),
+            Some(other_address)
+        );
+        assert_eq!(sessions.kick(&mut universe, "player-2"), None);
+        assert!(universe.get::<Character>(&"player-2".into()).is_none());
+        assert!(sessions.resume(&first.token, start).is_some());
+        assert_eq!(sessions.len(), 1);
+    }
+}
@@ -2,24 +2,375 @@
 //! server in the multiplayer sense (eventually).
 
 use std::future::Future;
-use std::net::SocketAddr;
+use std::net::{IpAddr, SocketAddr};
+use std::path::PathBuf;
+use std::sync::Arc;
 
-/// Run the All is Cubes web server on an arbitrary local port.
+use anyhow::Context as _;
+use axum::extract::{ConnectInfo, State};
+use axum::http::{HeaderMap, Request};
+use axum::middleware::Next;
+use axum::response::{Redirect, Response};
+
+use all_is_cubes::universe::Universe;
+use all_is_cubes::util::YieldProgress;
+
+use crate::client_static::AicClientSource;
+use crate::game::{load_world, GameConfig, GameHandle};
+
+/// Configuration for [`start_server()`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[non_exhaustive]
+pub struct ServerConfig {
+    /// Address and port to listen on. If the port is 0, an arbitrary port is chosen.
+    pub bind_addr: SocketAddr,
+
+    /// Where to obtain the client to serve.
+    pub client_source: AicClientSource,
+
+    /// Path under which everything is served, such as `/aic` if a reverse proxy
+    /// forwards `https://example.net/aic/` to this server without removing the prefix.
+    /// Empty to serve at the root.
+    pub base_path: String,
+
+    /// If not [`None`], serve HTTPS using this certificate, rather than plain HTTP.
+    pub tls: Option<TlsConfig>,
+
+    /// Addresses of reverse proxies whose `X-Forwarded-For` headers are believed as to
+    /// the address of the client. Requests from other addresses are taken to be from
+    /// the client itself.
+    pub trusted_proxies: Vec<IpAddr>,
+
+    /// Content packs to load at startup, as described by
+    /// [`all_is_cubes_port::load_content_pack()`]. Each is loaded under a namespace taken
+    /// from its file name without extension.
+    ///
+    /// If [`ServerConfig::game`] is [`None`], the packs are only checked for errors.
+    pub content_packs: Vec<PathBuf>,
+
+    /// If not [`None`], host a game universe as well as serving the client.
+    /// The content packs are loaded into its universe.
+    pub game: Option<GameConfig>,
+}
+
+impl ServerConfig {
+    /// Constructs a [`ServerConfig`] serving plain HTTP at the root path, trusting
+    /// no proxies, loading no content packs, and hosting no game.
+    pub fn new(bind_addr: SocketAddr, client_source: AicClientSource) -> Self {
+        Self {
+            bind_addr,
+            client_source,
+            base_path: String::new(),
+            tls: None,
+            trusted_proxies: Vec::new(),
+            content_packs: Vec::new(),
+            game: None,
+        }
+    }
+}
+
+/// Files from which to read the TLS certificate and private key, for [`ServerConfig`].
+///
+/// On Unix, the files are read again when the process receives `SIGHUP`, so that a
+/// renewed certificate can be used without restarting the server and dropping game
+/// sessions.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[non_exhaustive]
+pub struct TlsConfig {
+    /// Path to the PEM-encoded certificate chain.
+    pub cert_path: PathBuf,
+    /// Path to the PEM-encoded private key.
+    pub key_path: PathBuf,
+}
+
+impl TlsConfig {
+    /// Constructs a [`TlsConfig`].
+    pub fn new(cert_path: PathBuf, key_path: PathBuf) -> Self {
+        Self {
+            cert_path,
+            key_path,
+        }
+    }
+}
+
+/// Run the All is Cubes web server as configured by `config`.
 ///
-/// Returns the base URL to access it, and [TODO: explain the future, and have a shutdown plan]
-pub fn start_server(
-    bind_addr: SocketAddr,
-    client_source: &crate::client_static::AicClientSource,
+/// Returns the base URL to access it, and a future which runs the server. When
+/// `shutdown_signal` completes, the server stops accepting connections and finishes
+/// responding to requests already in progress, and then the future completes.
+pub async fn start_server(
+    config: ServerConfig,
+    shutdown_signal: impl Future<Output = ()>,
 ) -> Result<(String, impl Future<Output = Result<(), anyhow::Error>>), anyhow::Error> {
-    let static_router = client_source.client_router();
+    let ServerConfig {
+        bind_addr,
+        client_source,
+        base_path,
+        tls,
+        trusted_proxies,
+        content_packs,
+        game,
+    } = config;
+
+    let mut universe = match &game {
+        Some(game) => load_world(&game.world).await?,
+        None => Universe::new(),
+    };
+    for path in &content_packs {
+        let namespace = path
+            .file_stem()
+            .with_context(|| format!("content pack path {} has no name", path.display()))?
+            .to_string_lossy();
+        all_is_cubes_port::load_content_pack(
+            YieldProgress::noop(),
+            path,
+            &namespace,
+            &mut universe,
+        )
+        .await
+        .with_context(|| format!("could not load content pack {}", path.display()))?;
+        log::info!("Loaded content pack {}", path.display());
+    }
+
+    let base_path = base_path.trim_end_matches('/');
+    if !(base_path.is_empty() || base_path.starts_with('/')) {
+        anyhow::bail!("base path {base_path:?} must start with '/'");
+    }
+
+    let game = game.map(|game_config| {
+        log::info!("Loaded world from {:?}", game_config.world);
+        GameHandle::start(universe, game_config)
+    });
+
+    let static_router = match &game {
+        Some((handle, _)) => handle
+            .router()
+            .fallback_service(client_source.client_router()),
+        None => client_source.client_router(),
+    };
 
     // TODO: serve static at well defined subdir separate from root, so that we have
     // more division of responsibility in which urls mean what
-    let app = static_router;
+    let app = if base_path.is_empty() {
+        static_router
+    } else {
+        // The client's resources are found relative to the page, so the page must be
+        // requested with a trailing slash.
+        let with_slash = format!("{base_path}/");
+        let redirect = Redirect::permanent(&with_slash);
+        axum::Router::new()
+            .nest_service(&with_slash, static_router)
+            .route(
+                base_path,
+                axum::routing::get(move || async move { redirect }),
+            )
+    };
+    let app = app.layer(axum::middleware::from_fn_with_state(
+        Arc::<[IpAddr]>::from(trusted_proxies),
+        log_request,
+    ));
+
+    let listener = std::net::TcpListener::bind(bind_addr)
+        .with_context(|| format!("could not listen on {bind_addr}"))?;
+    listener.set_nonblocking(true)?;
+    let local_addr = listener.local_addr()?;
+
+    let handle = axum_server::Handle::new();
+    let make_service = app.into_make_service_with_connect_info::<SocketAddr>();
+    let mut tls_reload_task = None;
+    let (scheme, server) = match tls {
+        Some(TlsConfig {
+            cert_path,
+            key_path,
+        }) => {
+            let rustls_config =
+                axum_server::tls_rustls::RustlsConfig::from_pem_file(&cert_path, &key_path)
+                    .await
+                    .with_context(|| {
+                        format!(
+                            "could not load TLS certificate {} and key {}",
+                            cert_path.display(),
+                            key_path.display()
+                        )
+                    })?;
+            tls_reload_task = Some(tokio::spawn(reload_tls_on_hangup(
+                rustls_config.clone(),
+                cert_path,
+                key_path,
+            )));
+            let server = axum_server::from_tcp_rustls(listener, rustls_config)
+                .handle(handle.clone())
+                .serve(make_service);
+            ("https", futures_util::future::Either::Left(server))
+        }
+        None => {
+            let server = axum_server::from_tcp(listener)
+                .handle(handle.clone())
+                .serve(make_service);
+            ("http", futures_util::future::Either::Right(server))
+        }
+    };
 
-    let server = axum::Server::bind(&bind_addr).serve(app.into_make_service());
     // TODO: refactor so stdout writing isn't hardcoded into this function
-    let url = format!("http://{}/", server.local_addr());
+    let url = format!("{scheme}://{local_addr}{base_path}/");
+
+    Ok((url, async move {
+        tokio::pin!(server);
+        let result = tokio::select! {
+            result = &mut server => result,
+            () = shutdown_signal => {
+                handle.graceful_shutdown(None);
+                server.await
+            }
+        };
+        if let Some(task) = tls_reload_task {
+            task.abort();
+        }
+        result?;
+        if let Some((game_handle, game_task)) = game {
+            game_handle.stop().await;
+            game_task.await.context("game task failed")?;
+        }
+        Ok(())
+    }))
+}
+
+/// Reads the TLS certificate and key into `rustls_config` again whenever the process
+/// receives `SIGHUP`. Connections already established keep using the old certificate,
+/// and if reading fails, the old certificate remains in use.
+///
+/// On other platforms, this does nothing.
+async fn reload_tls_on_hangup(
+    rustls_config: axum_server::tls_rustls::RustlsConfig,
+    cert_path: PathBuf,
+    key_path: PathBuf,
+) {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+
+        let mut hangup = match signal(SignalKind::hangup()) {
+            Ok(signal) => signal,
+            Err(e) => {
+                log::error!("Cannot reload TLS certificate: failed to listen for SIGHUP: {e}");
+                return;
+            }
+        };
+        while hangup.recv().await.is_some() {
+            match rustls_config
+                .reload_from_pem_file(&cert_path, &key_path)
+                .await
+            {
+                Ok(()) => log::info!("Reloaded TLS certificate {}", cert_path.display()),
+                Err(e) => log::error!(
+                    "Failed to reload TLS certificate {} and key {}: {e}",
+                    cert_path.display(),
+                    key_path.display()
+                ),
+            }
+        }
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = (rustls_config, cert_path, key_path);
+    }
+}
+
+/// Address of the client which made a request, as determined by [`client_addr()`].
+///
+/// [`log_request()`] adds this to every request's extensions, for handlers to extract.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) struct ClientAddr(pub IpAddr);
+
+/// Middleware which logs each request along with the address of the client, and
+/// records that address as a [`ClientAddr`].
+async fn log_request<B>(
+    State(trusted_proxies): State<Arc<[IpAddr]>>,
+    ConnectInfo(peer): ConnectInfo<SocketAddr>,
+    mut request: Request<B>,
+    next: Next<B>,
+) -> Response {
+    let client = client_addr(peer.ip(), request.headers(), &trusted_proxies);
+    log::debug!("{client} {} {}", request.method(), request.uri());
+    request.extensions_mut().insert(ClientAddr(client));
+    next.run(request).await
+}
+
+/// Determines the address of the client which made a request received from `peer`.
+///
+/// If `peer` is one of the `trusted_proxies`, then the `X-Forwarded-For` header is
+/// consulted, skipping any further trusted proxies listed in it. Otherwise, the header
+/// might have been written by the client and is ignored.
+fn client_addr(peer: IpAddr, headers: &HeaderMap, trusted_proxies: &[IpAddr]) -> IpAddr {
+    let mut client = peer;
+    if !trusted_proxies.contains(&client) {
+        return client;
+    }
+    // Each proxy appends the address it received the request from, so read from the end.
+    let forwarded = headers
+        .get_all("x-forwarded-for")
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .collect::<Vec<&str>>();
+    for hop in forwarded.into_iter().rev() {
+        match hop.trim().parse::<IpAddr>() {
+            Ok(addr) => {
+                client = addr;
+                if !trusted_proxies.contains(&client) {
+                    break;
+                }
+            }
+            // Can't tell who's beyond a garbled entry, so stop at the last proxy.
+            Err(_) => break,
+        }
+    }
+    client
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::HeaderValue;
+
+    fn headers(forwarded_for: &[&'static str]) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        for &value in forwarded_for {
+            headers.append("x-forwarded-for", HeaderValue::from_static(value));
+        }
+        headers
+    }
+
+    #[test]
+    fn client_addr_untrusted_peer() {
+        let peer: IpAddr = "192.0.2.1".parse().unwrap();
+        assert_eq!(client_addr(peer, &headers(&["198.51.100.1"]), &[]), peer);
+    }
+
+    #[test]
+    fn client_addr_trusted_proxies() {
+        let proxy_1: IpAddr = "10.0.0.1".parse().unwrap();
+        let proxy_2: IpAddr = "10.0.0.2".parse().unwrap();
+        let client: IpAddr = "198.51.100.1".parse().unwrap();
+        let trusted = [proxy_1, proxy_2];
 
-    Ok((url, async move { Ok(server.await?) }))
+        assert_eq!(client_addr(proxy_1, &headers(&[]), &trusted), proxy_1);
+        assert_eq!(
+            client_addr(proxy_1, &headers(&["198.51.100.1"]), &trusted),
+            client
+        );
+        // The client may have sent its own spoofed header, which is not believed.
+        assert_eq!(
+            client_addr(
+                proxy_1,
+                &headers(&["203.0.113.7, 198.51.100.1", "10.0.0.2"]),
+                &trusted
+            ),
+            client
+        );
+        assert_eq!(
+            client_addr(proxy_1, &headers(&["nonsense, 10.0.0.2"]), &trusted),
+            proxy_2
+        );
+    }
 }
@@ -7,15 +7,25 @@ use reqwest::header::HeaderValue;
 use reqwest::Url;
 use tokio::io::AsyncBufReadExt;
 
-async fn with_server<F: AsyncFnOnce1<Url, Output = ()>>(client_source: &'static str, f: F) {
-    let mut server = tokio::process::Command::new(env!("CARGO_BIN_EXE_aic-server"))
-        .arg("--client-source")
-        .arg(client_source)
+async fn with_server<F: AsyncFnOnce1<Url, Output = ()>>(args: &[&str], f: F) {
+    let mut server = start_server(args);
+    let url = read_server_url(&mut server).await;
+
+    f(url).await;
+
+    server.kill().await.unwrap()
+}
+
+fn start_server(args: &[&str]) -> tokio::process::Child {
+    tokio::process::Command::new(env!("CARGO_BIN_EXE_aic-server"))
+        .args(args)
         .kill_on_drop(true)
         .stdout(Stdio::piped())
         .spawn()
-        .unwrap();
+        .unwrap()
+}
 
+async fn read_server_url(server: &mut tokio::process::Child) -> Url {
     // Read listening address from the server
     let mut stdout = tokio::io::BufReader::new(server.stdout.take().unwrap());
     let mut server_url_line = String::new();
@@ -25,11 +35,7 @@ async fn with_server<F: AsyncFnOnce1<Url, Output = ()>>(client_source: &'static
         server_url_line.starts_with("http"),
         "Expected URL: {server_url_line:?}"
     );
-    let url = Url::parse(server_url_line.trim()).expect("parsing server URL failed");
-
-    f(url).await;
-
-    server.kill().await.unwrap()
+    Url::parse(server_url_line.trim()).expect("parsing server URL failed")
 }
 
 /// This test will fail if the static file serving is not working.
@@ -52,9 +58,129 @@ async fn client_smoke_test(root_url: Url) {
 #[cfg(feature = "embed")]
 #[tokio::test]
 async fn client_smoke_test_embedded() {
-    with_server("embedded", client_smoke_test).await
+    with_server(&["--client-source", "embedded"], client_smoke_test).await
 }
 #[tokio::test]
 async fn client_smoke_test_workspace() {
-    with_server("workspace", client_smoke_test).await
+    with_server(&["--client-source", "workspace"], client_smoke_test).await
+}
+
+#[tokio::test]
+async fn client_smoke_test_base_path() {
+    with_server(
+        &["--client-source", "workspace", "--base-path", "/aic"],
+        |root_url: Url| async move {
+            assert_eq!(root_url.path(), "/aic/");
+            client_smoke_test(root_url.clone()).await;
+
+            // Without the trailing slash, relative URLs would not work, so redirect.
+            let resp = reqwest::get(root_url.join("/aic").unwrap()).await.unwrap();
+            assert_eq!(resp.url().path(), "/aic/");
+        },
+    )
+    .await
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn graceful_shutdown() {
+    let mut server = start_server(&["--client-source", "workspace"]);
+    read_server_url(&mut server).await;
+
+    let status = std::process::Command::new("kill")
+        .arg("-TERM")
+        .arg(server.id().unwrap().to_string())
+        .status()
+        .unwrap();
+    assert!(status.success());
+
+    let exit_status = server.wait().await.unwrap();
+    assert!(exit_status.success(), "{exit_status:?}");
+}
+
+#[tokio::test]
+async fn serve_with_config_file() {
+    let dir = std::path::Path::new(env!("CARGO_TARGET_TMPDIR")).join("serve_with_config_file");
+    std::fs::create_dir_all(&dir).unwrap();
+    let config_path = dir.join("server.toml");
+    std::fs::write(
+        &config_path,
+        r#"
+            client_source = "workspace"
+            admin_tokens = ["secret"]
+
+            [world]
+            template = "blank"
+
+            [autosave]
+            path = "world.alliscubesjson"
+        "#,
+    )
+    .unwrap();
+    let _ = std::fs::remove_file(dir.join("world.alliscubesjson"));
+
+    with_server(
+        &["serve", "--config", config_path.to_str().unwrap()],
+        |root_url: Url| async move {
+            client_smoke_test(root_url.clone()).await;
+
+            let client = reqwest::Client::new();
+            let status_url = root_url.join("admin/status").unwrap();
+            let save_url = root_url.join("admin/save").unwrap();
+
+            let resp = client.get(status_url.clone()).send().await.unwrap();
+            assert_eq!(resp.status(), reqwest::StatusCode::UNAUTHORIZED);
+            let resp = client
+                .get(status_url.clone())
+                .bearer_auth("wrong")
+                .send()
+                .await
+                .unwrap();
+            assert_eq!(resp.status(), reqwest::StatusCode::FORBIDDEN);
+
+            let status = client
+                .get(status_url)
+                .bearer_auth("secret")
+                .send()
+                .await
+                .unwrap()
+                .error_for_status()
+                .unwrap()
+                .text()
+                .await
+                .unwrap();
+            assert!(status.contains("\"steps\":"), "{status}");
+
+            client
+                .post(save_url)
+                .bearer_auth("secret")
+                .send()
+                .await
+                .unwrap()
+                .error_for_status()
+                .unwrap();
+
+            // The blank world has no space to spawn characters in.
+            let resp = client
+                .post(root_url.join("session").unwrap())
+                .send()
+                .await
+                .unwrap();
+            assert_eq!(resp.status(), reqwest::StatusCode::SERVICE_UNAVAILABLE);
+
+            let resume_url = root_url.join("session/resume").unwrap();
+            let resp = client.post(resume_url.clone()).send().await.unwrap();
+            assert_eq!(resp.status(), reqwest::StatusCode::UNAUTHORIZED);
+            let resp = client
+                .post(resume_url)
+                .bearer_auth("no such session")
+                .send()
+                .await
+                .unwrap();
+            assert_eq!(resp.status(), reqwest::StatusCode::NOT_FOUND);
+        },
+    )
+    .await;
+
+    assert!(dir.join("world.alliscubesjson").exists());
 }
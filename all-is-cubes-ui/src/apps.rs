@@ -1,8 +1,17 @@
 //! Components for "apps", or game clients: user interface and top-level state.
 
+mod console;
+pub use console::*;
+
 mod input;
 pub use input::*;
 
+mod quality;
+pub use quality::*;
+
+mod replay;
+pub use replay::*;
+
 mod session;
 pub use session::*;
 
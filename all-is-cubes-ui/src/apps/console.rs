@@ -0,0 +1,579 @@
+//! [`CommandRegistry`]: the commands which may be typed into a session's console.
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::sync::Arc;
+
+use all_is_cubes::block::{Block, BlockDef, Primitive, AIR};
+use all_is_cubes::character::{Character, CharacterTransaction};
+use all_is_cubes::event::GameEvent;
+use all_is_cubes::inv::{InventoryTransaction, Tool};
+use all_is_cubes::math::{FreeCoordinate, GridCoordinate, GridVector};
+use all_is_cubes::physics::BodyTransaction;
+use all_is_cubes::space::{Selection, Waypoint};
+use all_is_cubes::transaction::Transaction as _;
+use all_is_cubes::universe::{Name, URef, Universe, UniverseTransaction};
+
+/// Function implementing a command registered with [`CommandRegistry::register()`].
+///
+/// It is given the arguments following the command name, split on whitespace, and
+/// returns the transaction that carries out the command.
+pub type CommandHandler =
+    dyn Fn(&CommandContext<'_>, &[&str]) -> Result<UniverseTransaction, CommandError> + Send + Sync;
+
+/// The commands which may be typed into the console of a [`Session`](super::Session),
+/// such as `/tp 0 10 0`.
+///
+/// Commands do not modify the universe directly; each produces a [`UniverseTransaction`]
+/// which the session then executes.
+#[derive(Clone)]
+pub struct CommandRegistry {
+    commands: BTreeMap<String, Command>,
+}
+
+#[derive(Clone)]
+struct Command {
+    usage: Arc<str>,
+    handler: Arc<CommandHandler>,
+}
+
+/// The state a [`CommandHandler`] may consult in order to construct its transaction.
+#[derive(Clone, Copy, Debug)]
+#[non_exhaustive]
+pub struct CommandContext<'a> {
+    /// The universe the transaction will be executed on.
+    pub universe: &'a Universe,
+    /// The character controlled by the user who typed the command, if any.
+    pub character: Option<&'a URef<Character>>,
+    /// The cubes the user has selected, if any.
+    pub selection: Option<&'a Selection>,
+}
+
+/// Error from running a command in a [`CommandRegistry`].
+#[derive(Clone, Debug, Eq, PartialEq, thiserror::Error)]
+#[non_exhaustive]
+pub enum CommandError {
+    /// There is no command with the given name.
+    #[error("unknown command '/{0}'")]
+    Unknown(String),
+    /// The command's arguments were not valid.
+    ///
+    /// A [`CommandHandler`] may return this with an empty string; [`CommandRegistry::run()`]
+    /// replaces it with the command's usage.
+    #[error("usage: {0}")]
+    Usage(String),
+    /// The command could not be carried out.
+    #[error("{0}")]
+    Failed(String),
+}
+
+impl CommandRegistry {
+    /// Constructs a [`CommandRegistry`] with no commands.
+    pub fn new() -> Self {
+        Self {
+            commands: BTreeMap::new(),
+        }
+    }
+
+    /// Constructs a [`CommandRegistry`] with the commands every session has:
+    ///
+    /// * `/tp <x> <y> <z>` moves the user's character to the given position.
+    /// * `/give <block name>` puts the block with the given name, defined in the
+    ///   universe, in the user's character's inventory.
+    /// * `/say <message>` sends a chat message, as a [`GameEvent::Chat`].
+    /// * `/waypoint add <name>` creates a [`Waypoint`] at the user's character's position;
+    ///   `/waypoint list` lists the universe's waypoints, as a [`GameEvent::Chat`]; and
+    ///   `/waypoint tp <name>` moves the user's character to the named waypoint.
+    /// * `/fill <block name>` replaces every selected cube's block with the named block.
+    /// * `/replace <block name> with <block name>` replaces the first block with the
+    ///   second in the selected cubes.
+    /// * `/copy <dx> <dy> <dz>` copies the blocks in the selected cubes to the cubes
+    ///   displaced from them by the given offset.
+    ///
+    /// Blocks are named by their identifier in the universe's
+    /// [`BlockRegistry`](all_is_cubes::universe::BlockRegistry), the name of their
+    /// [`BlockDef`] in the universe, or `air`.
+    pub fn with_standard_commands() -> Self {
+        let mut this = Self::new();
+        this.register("tp", "<x> <y> <z>", teleport_command);
+        this.register("give", "<block name>", give_command);
+        this.register("say", "<message>", say_command);
+        this.register(
+            "waypoint",
+            "add <name> | list | tp <name>",
+            waypoint_command,
+        );
+        this.register("fill", "<block name>", fill_command);
+        this.register("replace", "<block name> with <block name>", replace_command);
+        this.register("copy", "<dx> <dy> <dz>", copy_command);
+        this
+    }
+
+    /// Adds a command, replacing any existing command with the same name.
+    ///
+    /// `name` is what is typed to invoke the command, without the leading `/`, and
+    /// `usage` describes its arguments, for showing to the user when they are invalid.
+    pub fn register(
+        &mut self,
+        name: impl Into<String>,
+        usage: impl Into<Arc<str>>,
+        handler: impl Fn(&CommandContext<'_>, &[&str]) -> Result<UniverseTransaction, CommandError>
+            + Send
+            + Sync
+            + 'static,
+    ) {
+        self.commands.insert(
+            name.into(),
+            Command {
+                usage: usage.into(),
+                handler: Arc::new(handler),
+            },
+        );
+    }
+
+    /// Returns the names of all registered commands, in sorted order.
+    pub fn names(&self) -> impl Iterator<Item = &str> + '_ {
+        self.commands.keys().map(String::as_str)
+    }
+
+    /// Parses a line of input, such as `/tp 0 10 0`, and returns the transaction which
+    /// carries out the command. The leading `/` is optional.
+    pub fn run(
+        &self,
+        line: &str,
+        context: &CommandContext<'_>,
+    ) -> Result<UniverseTransaction, CommandError> {
+        let line = line.trim();
+        let mut words = line.strip_prefix('/').unwrap_or(line).split_whitespace();
+        let name = words.next().unwrap_or_default();
+        let args: Vec<&str> = words.collect();
+        let command = self
+            .commands
+            .get(name)
+            .ok_or_else(|| CommandError::Unknown(name.to_owned()))?;
+        (command.handler)(context, &args).map_err(|error| match error {
+            CommandError::Usage(usage) if usage.is_empty() => {
+                CommandError::Usage(format!("/{name} {}", command.usage))
+            }
+            error => error,
+        })
+    }
+}
+
+impl Default for CommandRegistry {
+    /// Constructs a [`CommandRegistry`] with no commands, as [`Self::new()`] does.
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Debug for CommandRegistry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // Handlers are functions, so only show the names.
+        f.debug_set().entries(self.names()).finish()
+    }
+}
+
+fn character_of(context: &CommandContext<'_>) -> Result<URef<Character>, CommandError> {
+    context
+        .character
+        .cloned()
+        .ok_or_else(|| CommandError::Failed("there is no character".into()))
+}
+
+fn selection_of<'a>(context: &CommandContext<'a>) -> Result<&'a Selection, CommandError> {
+    context
+        .selection
+        .ok_or_else(|| CommandError::Failed("nothing is selected".into()))
+}
+
+/// Finds the block named by `words`, which may contain spaces and so have been split
+/// as if they were separate arguments.
+fn named_block(context: &CommandContext<'_>, words: &[&str]) -> Result<Block, CommandError> {
+    if words.is_empty() {
+        return Err(CommandError::Usage(String::new()));
+    }
+    let joined = words.join(" ");
+    if let Some(block_def) = context.universe.block_registry().get(&joined) {
+        return Ok(Block::from_primitive(Primitive::Indirect(
+            block_def.clone(),
+        )));
+    }
+    let name = Name::from(joined.as_str());
+    match context.universe.get::<BlockDef>(&name) {
+        Some(block_def) => Ok(Block::from_primitive(Primitive::Indirect(block_def))),
+        None if name == Name::from("air") => Ok(AIR),
+        None => Err(CommandError::Failed(format!(
+            "there is no block named {name}"
+        ))),
+    }
+}
+
+fn teleport_command(
+    context: &CommandContext<'_>,
+    args: &[&str],
+) -> Result<UniverseTransaction, CommandError> {
+    let &[x, y, z] = args else {
+        return Err(CommandError::Usage(String::new()));
+    };
+    let parse = |s: &str| {
+        s.parse::<FreeCoordinate>()
+            .ok()
+            .filter(|c| c.is_finite())
+            .ok_or_else(|| CommandError::Usage(String::new()))
+    };
+    let position = [parse(x)?, parse(y)?, parse(z)?];
+    Ok(
+        CharacterTransaction::body(BodyTransaction::teleport(position))
+            .bind(character_of(context)?),
+    )
+}
+
+fn give_command(
+    context: &CommandContext<'_>,
+    args: &[&str],
+) -> Result<UniverseTransaction, CommandError> {
+    let tool = Tool::Block(named_block(context, args)?);
+    Ok(
+        CharacterTransaction::inventory(InventoryTransaction::insert([tool]))
+            .bind(character_of(context)?),
+    )
+}
+
+fn say_command(
+    context: &CommandContext<'_>,
+    args: &[&str],
+) -> Result<UniverseTransaction, CommandError> {
+    if args.is_empty() {
+        return Err(CommandError::Usage(String::new()));
+    }
+    Ok(UniverseTransaction::event(GameEvent::Chat {
+        sender: context.character.cloned(),
+        message: args.join(" ").into(),
+    }))
+}
+
+fn waypoint_command(
+    context: &CommandContext<'_>,
+    args: &[&str],
+) -> Result<UniverseTransaction, CommandError> {
+    let Some((&subcommand, name_words)) = args.split_first() else {
+        return Err(CommandError::Usage(String::new()));
+    };
+    let name = Name::from(name_words.join(" ").as_str());
+    match (subcommand, name_words.is_empty()) {
+        ("add", false) => {
+            if context.universe.get_any(&name).is_some() {
+                return Err(CommandError::Failed(format!(
+                    "there is already something named {name}"
+                )));
+            }
+            let character_ref = character_of(context)?;
+            let character = character_ref
+                .read()
+                .map_err(|e| CommandError::Failed(e.to_string()))?;
+            let waypoint = Waypoint::new(character.space.clone(), character.body.position);
+            Ok(UniverseTransaction::insert(URef::new_pending(
+                name, waypoint,
+            )))
+        }
+        ("list", true) => {
+            let names: Vec<String> = context
+                .universe
+                .iter_by_type::<Waypoint>()
+                .map(|(name, _)| name.to_string())
+                .collect();
+            let message = if names.is_empty() {
+                "There are no waypoints.".to_owned()
+            } else {
+                format!("Waypoints: {}", names.join(", "))
+            };
+            Ok(UniverseTransaction::event(GameEvent::Chat {
+                sender: None,
+                message: message.into(),
+            }))
+        }
+        ("tp", false) => {
+            let waypoint: URef<Waypoint> = context.universe.get(&name).ok_or_else(|| {
+                CommandError::Failed(format!("there is no waypoint named {name}"))
+            })?;
+            let waypoint = waypoint
+                .read()
+                .map_err(|e| CommandError::Failed(e.to_string()))?;
+            waypoint
+                .teleport(&character_of(context)?)
+                .map_err(|e| CommandError::Failed(e.to_string()))
+        }
+        _ => Err(CommandError::Usage(String::new())),
+    }
+}
+
+fn fill_command(
+    context: &CommandContext<'_>,
+    args: &[&str],
+) -> Result<UniverseTransaction, CommandError> {
+    let block = named_block(context, args)?;
+    selection_of(context)?
+        .fill(&block)
+        .map_err(|e| CommandError::Failed(e.to_string()))
+}
+
+fn replace_command(
+    context: &CommandContext<'_>,
+    args: &[&str],
+) -> Result<UniverseTransaction, CommandError> {
+    let Some(separator) = args.iter().position(|&word| word == "with") else {
+        return Err(CommandError::Usage(String::new()));
+    };
+    let old = named_block(context, &args[..separator])?;
+    let new = named_block(context, &args[separator + 1..])?;
+    selection_of(context)?
+        .replace(&old, &new)
+        .map_err(|e| CommandError::Failed(e.to_string()))
+}
+
+fn copy_command(
+    context: &CommandContext<'_>,
+    args: &[&str],
+) -> Result<UniverseTransaction, CommandError> {
+    let &[x, y, z] = args else {
+        return Err(CommandError::Usage(String::new()));
+    };
+    let parse = |s: &str| {
+        s.parse::<GridCoordinate>()
+            .map_err(|_| CommandError::Usage(String::new()))
+    };
+    let offset = GridVector::new(parse(x)?, parse(y)?, parse(z)?);
+    selection_of(context)?
+        .copy_to(offset)
+        .map_err(|e| CommandError::Failed(e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use all_is_cubes::cgmath::Point3;
+    use all_is_cubes::content::make_some_blocks;
+    use all_is_cubes::inv::Slot;
+    use all_is_cubes::listen::{Listen as _, Sink};
+    use all_is_cubes::math::GridAab;
+    use all_is_cubes::space::Space;
+    use all_is_cubes::transaction;
+
+    fn universe_and_character() -> (Universe, URef<Character>) {
+        let mut universe = Universe::new();
+        let space = universe.insert_anonymous(Space::empty_positive(10, 10, 10));
+        let character = universe.insert_anonymous(Character::spawn_default(space));
+        (universe, character)
+    }
+
+    fn run(
+        registry: &CommandRegistry,
+        universe: &mut Universe,
+        character: &URef<Character>,
+        line: &str,
+    ) -> Result<(), CommandError> {
+        run_with_selection(registry, universe, character, None, line)
+    }
+
+    fn run_with_selection(
+        registry: &CommandRegistry,
+        universe: &mut Universe,
+        character: &URef<Character>,
+        selection: Option<&Selection>,
+        line: &str,
+    ) -> Result<(), CommandError> {
+        let transaction = registry.run(
+            line,
+            &CommandContext {
+                universe,
+                character: Some(character),
+                selection,
+            },
+        )?;
+        transaction
+            .execute(universe, &mut transaction::no_outputs)
+            .map_err(|e| CommandError::Failed(e.to_string()))
+    }
+
+    #[test]
+    fn tp() {
+        let registry = CommandRegistry::with_standard_commands();
+        let (mut universe, character) = universe_and_character();
+        run(&registry, &mut universe, &character, "/tp 1 2.5 -3").unwrap();
+        assert_eq!(
+            character.read().unwrap().body.position,
+            Point3::new(1.0, 2.5, -3.0)
+        );
+    }
+
+    #[test]
+    fn give() {
+        let registry = CommandRegistry::with_standard_commands();
+        let (mut universe, character) = universe_and_character();
+        let [block, registered_block] = make_some_blocks();
+        let block_def = universe
+            .insert("my block".into(), BlockDef::new(block))
+            .unwrap();
+        let registered_def = universe
+            .insert("other".into(), BlockDef::new(registered_block))
+            .unwrap();
+        universe
+            .register_block("test:other", registered_def.clone())
+            .unwrap();
+        run(&registry, &mut universe, &character, "give my block").unwrap();
+        run(&registry, &mut universe, &character, "give test:other").unwrap();
+        for def in [block_def, registered_def] {
+            assert!(character
+                .read()
+                .unwrap()
+                .inventory()
+                .slots
+                .iter()
+                .any(|slot| *slot
+                    == Slot::from(Tool::Block(Block::from_primitive(Primitive::Indirect(
+                        def.clone()
+                    ))))));
+        }
+
+        assert_eq!(
+            run(&registry, &mut universe, &character, "give nothing"),
+            Err(CommandError::Failed(
+                "there is no block named 'nothing'".into()
+            ))
+        );
+    }
+
+    #[test]
+    fn say() {
+        let registry = CommandRegistry::with_standard_commands();
+        let (mut universe, character) = universe_and_character();
+        let sink = Sink::new();
+        universe.listen(sink.listener());
+        run(&registry, &mut universe, &character, "/say hello  world").unwrap();
+        assert_eq!(
+            sink.drain(),
+            vec![GameEvent::Chat {
+                sender: Some(character),
+                message: "hello world".into(),
+            }]
+        );
+    }
+
+    #[test]
+    fn waypoint() {
+        let registry = CommandRegistry::with_standard_commands();
+        let (mut universe, character) = universe_and_character();
+        let sink = Sink::new();
+        universe.listen(sink.listener());
+
+        run(&registry, &mut universe, &character, "/waypoint list").unwrap();
+        run(&registry, &mut universe, &character, "/tp 1 2 3").unwrap();
+        run(&registry, &mut universe, &character, "/waypoint add home").unwrap();
+        run(&registry, &mut universe, &character, "/tp 5 5 5").unwrap();
+        run(&registry, &mut universe, &character, "/waypoint tp home").unwrap();
+        assert_eq!(
+            character.read().unwrap().body.position,
+            Point3::new(1.0, 2.0, 3.0)
+        );
+        run(&registry, &mut universe, &character, "/waypoint list").unwrap();
+        assert_eq!(
+            sink.drain(),
+            vec![
+                GameEvent::Chat {
+                    sender: None,
+                    message: "There are no waypoints.".into(),
+                },
+                GameEvent::Chat {
+                    sender: None,
+                    message: "Waypoints: 'home'".into(),
+                },
+            ]
+        );
+
+        assert_eq!(
+            run(&registry, &mut universe, &character, "/waypoint add home"),
+            Err(CommandError::Failed(
+                "there is already something named 'home'".into()
+            ))
+        );
+        assert_eq!(
+            run(&registry, &mut universe, &character, "/waypoint tp away"),
+            Err(CommandError::Failed(
+                "there is no waypoint named 'away'".into()
+            ))
+        );
+        assert_eq!(
+            run(&registry, &mut universe, &character, "/waypoint add"),
+            Err(CommandError::Usage(
+                "/waypoint add <name> | list | tp <name>".into()
+            ))
+        );
+    }
+
+    #[test]
+    fn selection_commands() {
+        let registry = CommandRegistry::with_standard_commands();
+        let (mut universe, character) = universe_and_character();
+        let [block] = make_some_blocks();
+        let block_def = universe
+            .insert("stone".into(), BlockDef::new(block))
+            .unwrap();
+        let stone = Block::from_primitive(Primitive::Indirect(block_def));
+        let space = character.read().unwrap().space.clone();
+        let selection = Selection::from_box(
+            space.clone(),
+            GridAab::from_lower_size([0, 0, 0], [2, 1, 1]),
+        );
+        let mut run = |line: &str| {
+            run_with_selection(&registry, &mut universe, &character, Some(&selection), line)
+        };
+
+        run("/fill stone").unwrap();
+        assert_eq!(space.read().unwrap()[[1, 0, 0]], stone);
+        run("/copy 0 2 0").unwrap();
+        assert_eq!(space.read().unwrap()[[1, 2, 0]], stone);
+        run("/replace stone with air").unwrap();
+        assert_eq!(space.read().unwrap()[[1, 0, 0]], AIR);
+        assert_eq!(space.read().unwrap()[[1, 2, 0]], stone);
+
+        assert_eq!(
+            run("/replace stone"),
+            Err(CommandError::Usage(
+                "/replace <block name> with <block name>".into()
+            ))
+        );
+        assert_eq!(
+            run_with_selection(&registry, &mut universe, &character, None, "/fill air"),
+            Err(CommandError::Failed("nothing is selected".into()))
+        );
+    }
+
+    #[test]
+    fn errors() {
+        let registry = CommandRegistry::with_standard_commands();
+        let (mut universe, character) = universe_and_character();
+        assert_eq!(
+            run(&registry, &mut universe, &character, "/nonexistent"),
+            Err(CommandError::Unknown("nonexistent".into()))
+        );
+        assert_eq!(
+            run(&registry, &mut universe, &character, "/tp 1 2"),
+            Err(CommandError::Usage("/tp <x> <y> <z>".into()))
+        );
+        assert_eq!(
+            run(&registry, &mut universe, &character, "/tp 1 2 NaN"),
+            Err(CommandError::Usage("/tp <x> <y> <z>".into()))
+        );
+    }
+
+    #[test]
+    fn register() {
+        let mut registry = CommandRegistry::new();
+        assert_eq!(registry.names().count(), 0);
+        registry.register("nothing", "", |_, _| Ok(UniverseTransaction::default()));
+        assert_eq!(registry.names().collect::<Vec<_>>(), vec!["nothing"]);
+        let (mut universe, character) = universe_and_character();
+        run(&registry, &mut universe, &character, "nothing").unwrap();
+    }
+}
@@ -2,18 +2,21 @@ use std::collections::{HashMap, HashSet};
 use std::sync::mpsc;
 use std::time::Duration;
 
+use serde::{Deserialize, Serialize};
+
 use all_is_cubes::camera::{
-    FogOption, GraphicsOptions, LightingOption, TransparencyOption, Viewport,
+    CameraMode, FogOption, GraphicsOptions, LightingOption, TransparencyOption, Viewport,
 };
 use all_is_cubes::cgmath::{EuclideanSpace as _, Point2, Vector2, Vector3, Zero as _};
-use all_is_cubes::character::Character;
+use all_is_cubes::character::{Character, CursorPolicy};
+use all_is_cubes::inv::Hotbar;
 use all_is_cubes::listen::{ListenableCell, ListenableSource};
 use all_is_cubes::math::FreeCoordinate;
 use all_is_cubes::notnan;
 use all_is_cubes::time::Tick;
 use all_is_cubes::universe::{URef, Universe};
 
-use crate::apps::ControlMessage;
+use crate::apps::{ControlMessage, InputEvent};
 
 /// Parse input events, particularly key-down/up pairs, into character control and such.
 ///
@@ -45,6 +48,13 @@ pub struct InputProcessor {
     /// once per press rather than while held.
     command_buffer: Vec<Key>,
 
+    /// Text being typed into the command console, or [`None`] if it is closed.
+    /// While it is open, keys are used for typing instead of their usual functions.
+    console_line: Option<String>,
+    /// Lines entered in the console and not yet taken by
+    /// [`Self::take_console_commands()`].
+    console_commands: Vec<String>,
+
     /// Do we *want* pointer lock for mouselook?
     ///
     /// This is listenable so that the UI can react to this state.
@@ -58,9 +68,38 @@ pub struct InputProcessor {
     /// Mouse position in NDC. None if out of bounds/lost focus.
     mouse_ndc_position: Option<Point2<FreeCoordinate>>,
 
+    /// Net scroll wheel movement, in lines, not yet applied to the hotbar.
+    ///
+    /// Only whole lines are applied, so that fine-grained scrolling devices such as
+    /// touchpads take several events to move the selection by one position.
+    wheel_buffer: FreeCoordinate,
+
     /// Mouse position used for generating mouselook deltas.
     /// [`None`] if games.
     mouse_previous_pixel_position: Option<Point2<f64>>,
+
+    /// Whether to view the world from the character's eyes or from behind it.
+    ///
+    /// This is listenable so that it can be given to
+    /// [`StandardCameras`](all_is_cubes::camera::StandardCameras).
+    camera_mode: ListenableCell<CameraMode>,
+
+    /// Which blocks the cursor selects in the world; toggled between
+    /// [`CursorPolicy::Selectable`] and [`CursorPolicy::SelectableOpaque`] so that
+    /// blocks behind glass or water may be targeted.
+    ///
+    /// This is listenable so that it can be given to
+    /// [`StandardCameras`](all_is_cubes::camera::StandardCameras).
+    cursor_policy: ListenableCell<CursorPolicy>,
+
+    /// Which inventory slots are offered for quick selection by the digit keys and the
+    /// scroll wheel, and which of them is selected.
+    ///
+    /// This is listenable so that the UI can display it.
+    hotbar: ListenableCell<Hotbar>,
+
+    /// Inputs received since the last [`Self::take_recorded_input()`], if recording.
+    recorded_input: Option<Vec<InputEvent>>,
 }
 
 impl InputProcessor {
@@ -73,11 +112,18 @@ impl InputProcessor {
             keys_held: HashSet::new(),
             momentary_timeout: HashMap::new(),
             command_buffer: Vec::new(),
+            console_line: None,
+            console_commands: Vec::new(),
             mouselook_mode: ListenableCell::new(false), // TODO: might want a parameter
             has_pointer_lock: false,
             mouselook_buffer: Vector2::zero(),
             mouse_ndc_position: Some(Point2::origin()),
             mouse_previous_pixel_position: None,
+            wheel_buffer: 0.0,
+            camera_mode: ListenableCell::new(CameraMode::default()),
+            cursor_policy: ListenableCell::new(CursorPolicy::Selectable),
+            hotbar: ListenableCell::new(Hotbar::default()),
+            recorded_input: None,
         }
     }
 
@@ -99,11 +145,20 @@ impl InputProcessor {
             Key::Down => true,
             Key::Character(' ') => true,
             Key::Character(d) if d.is_ascii_digit() => true,
+            Key::Character(',') => true,
+            Key::Character('.') => true,
+            Key::Character('f') => true,
+            Key::Character('g') => true,
             Key::Character('i') => true,
             Key::Character('l') => true,
             Key::Character('o') => true,
             Key::Character('p') => true,
             Key::Character('u') => true,
+            Key::Character('v') => true,
+            Key::Character('x') => true,
+            Key::Character('z') => true,
+            // Used in `InputProcessor::key_down()`.
+            Key::Character('/') => true,
             _ => false,
         }
     }
@@ -114,11 +169,18 @@ impl InputProcessor {
         match key {
             Key::Escape => true,
             Key::Character(d) if d.is_ascii_digit() => true,
+            Key::Character(',') => true,
+            Key::Character('.') => true,
+            Key::Character('f') => true,
+            Key::Character('g') => true,
             Key::Character('i') => true,
             Key::Character('l') => true,
             Key::Character('o') => true,
             Key::Character('p') => true,
             Key::Character('u') => true,
+            Key::Character('v') => true,
+            Key::Character('x') => true,
+            Key::Character('z') => true,
             // TODO: move slot selection commands here
             _ => false,
         }
@@ -126,6 +188,37 @@ impl InputProcessor {
 
     /// Handles incoming key-down events. Returns whether the key was unbound.
     pub fn key_down(&mut self, key: Key) -> bool {
+        self.record(InputEvent::KeyDown(key));
+        self.key_down_impl(key)
+    }
+
+    fn key_down_impl(&mut self, key: Key) -> bool {
+        if let Some(line) = &mut self.console_line {
+            match key {
+                Key::Escape => self.console_line = None,
+                Key::Character('\r') => {
+                    let line = self.console_line.take().unwrap_or_default();
+                    if !line.trim_start_matches('/').trim().is_empty() {
+                        self.console_commands.push(line);
+                    }
+                }
+                Key::Backspace => {
+                    line.pop();
+                }
+                Key::Character(c) if !c.is_control() => line.push(c),
+                _ => {}
+            }
+            return true;
+        }
+        if key == Key::Character('/') {
+            // Open the console, as if typing the start of a command. Held keys are
+            // released so that the character does not keep moving while typing.
+            self.console_line = Some(String::from("/"));
+            self.keys_held.clear();
+            self.momentary_timeout.clear();
+            return true;
+        }
+
         let bound = Self::is_bound(key);
         if bound {
             self.keys_held.insert(key);
@@ -138,16 +231,18 @@ impl InputProcessor {
 
     /// Handles incoming key-up events.
     pub fn key_up(&mut self, key: Key) {
+        self.record(InputEvent::KeyUp(key));
         self.keys_held.remove(&key);
     }
 
     /// Handles incoming key events in the case where key-up events are not available,
     /// such that an assumption about equivalent press duration must be made.
     pub fn key_momentary(&mut self, key: Key) -> bool {
+        self.record(InputEvent::KeyMomentary(key));
         self.momentary_timeout
             .insert(key, Duration::from_millis(200));
-        self.key_up(key);
-        self.key_down(key)
+        self.keys_held.remove(&key);
+        self.key_down_impl(key)
     }
 
     /// Handles the keyboard focus being gained or lost. If the platform does not have
@@ -156,6 +251,7 @@ impl InputProcessor {
     /// `InputProcessor` will assume that if focus is lost, key-up events may be lost and
     /// so currently held keys should stop taking effect.
     pub fn key_focus(&mut self, has_focus: bool) {
+        self.record(InputEvent::KeyFocus(has_focus));
         if has_focus {
             // Nothing to do.
         } else {
@@ -178,6 +274,7 @@ impl InputProcessor {
     /// known to be successfully enabled, after [`InputProcessor::wants_pointer_lock`]
     /// requests it or it is disabled for any reason.
     pub fn has_pointer_lock(&mut self, value: bool) {
+        self.record(InputEvent::HasPointerLock(value));
         self.has_pointer_lock = value;
     }
 
@@ -188,6 +285,7 @@ impl InputProcessor {
     ///
     /// Note that absolute cursor positions must be provided separately.
     pub fn mouselook_delta(&mut self, delta: Vector2<FreeCoordinate>) {
+        self.record(InputEvent::MouselookDelta(delta.into()));
         // TODO: sensitivity option
         if self.has_pointer_lock {
             self.mouselook_buffer += delta * 0.2;
@@ -205,6 +303,7 @@ impl InputProcessor {
     /// If this is never called, the default value is (0, 0) which corresponds to the
     /// center of the screen.
     pub fn mouse_ndc_position(&mut self, position: Option<Point2<FreeCoordinate>>) {
+        self.record(InputEvent::MouseNdcPosition(position.map(Into::into)));
         self.mouse_ndc_position = position.filter(|p| p.x.abs() <= 1. && p.y.abs() <= 1.);
     }
 
@@ -240,6 +339,44 @@ impl InputProcessor {
         }
     }
 
+    /// Provide scroll wheel movement, in lines. Positive values are scrolling down
+    /// (toward the user), which selects later positions of the [hotbar](Self::hotbar).
+    ///
+    /// Fractional values are accumulated until they add up to a whole line.
+    pub fn mouse_wheel(&mut self, delta: FreeCoordinate) {
+        self.record(InputEvent::MouseWheel(delta));
+        if delta.is_finite() {
+            self.wheel_buffer += delta;
+        }
+    }
+
+    /// Starts or stops keeping a copy of every input received, to be taken with
+    /// [`Self::take_recorded_input()`].
+    pub(crate) fn set_recording_input(&mut self, recording: bool) {
+        if recording != self.recorded_input.is_some() {
+            self.recorded_input = recording.then(Vec::new);
+        }
+    }
+
+    /// Returns the inputs received since the last call, if recording.
+    pub(crate) fn take_recorded_input(&mut self) -> Vec<InputEvent> {
+        self.recorded_input
+            .as_mut()
+            .map(std::mem::take)
+            .unwrap_or_default()
+    }
+
+    /// Adds an input to the recording, if recording.
+    ///
+    /// This is used by [`Session`](crate::apps::Session) for inputs which do not pass
+    /// through the [`InputProcessor`], so that their order relative to other inputs is
+    /// kept.
+    pub(crate) fn record(&mut self, event: InputEvent) {
+        if let Some(recorded_input) = &mut self.recorded_input {
+            recorded_input.push(event);
+        }
+    }
+
     /// Returns the character movement velocity that input is currently requesting.
     pub fn movement(&self) -> Vector3<FreeCoordinate> {
         Vector3::new(
@@ -249,6 +386,25 @@ impl InputProcessor {
         )
     }
 
+    /// Returns the text being typed into the command console, or [`None`] if the console
+    /// is not open.
+    ///
+    /// The console is opened by pressing the `/` key, and closed by pressing Escape or by
+    /// pressing Enter to run the command.
+    pub fn console_line(&self) -> Option<&str> {
+        self.console_line.as_deref()
+    }
+
+    /// Returns the commands entered in the console since the last call.
+    pub(crate) fn take_console_commands(&mut self) -> Vec<String> {
+        std::mem::take(&mut self.console_commands)
+    }
+
+    /// Returns whether input is currently requesting that the character jump.
+    pub fn jump(&self) -> bool {
+        self.keys_held.contains(&Key::Character(' '))
+    }
+
     /// Advance time insofar as input interpretation is affected by time.
     ///
     /// This method should be called *after* [`apply_input`](Self::apply_input), when
@@ -303,13 +459,33 @@ impl InputProcessor {
                     character.body.yaw = (character.body.yaw + turning.x).rem_euclid(360.0);
                     character.body.pitch = (character.body.pitch + turning.y).clamp(-90.0, 90.0);
 
-                    if self.keys_held.contains(&Key::Character(' ')) {
+                    if self.jump() {
                         character.jump_if_able();
                     }
                 })
                 .expect("character was borrowed during apply_input()");
         }
 
+        // Hotbar positions selected by digit keys or scrolling; applied after commands.
+        let mut hotbar_selection = false;
+        let wheel_steps = self.wheel_buffer.trunc();
+        if wheel_steps != 0.0 {
+            self.wheel_buffer -= wheel_steps;
+            if let Some(character_ref) = character_opt {
+                // Scroll from the character's selection, which may have been changed by
+                // something other than the hotbar.
+                let current_slot = character_ref
+                    .read()
+                    .expect("character was borrowed during apply_input()")
+                    .selected_slots()[1];
+                self.hotbar.update_mut(|hotbar| {
+                    hotbar.select_slot(current_slot);
+                    hotbar.cycle(wheel_steps as isize);
+                });
+                hotbar_selection = true;
+            }
+        }
+
         for key in self.command_buffer.drain(..) {
             match key {
                 Key::Escape => {
@@ -317,6 +493,24 @@ impl InputProcessor {
                         let _ = ch.try_send(ControlMessage::Back);
                     }
                 }
+                Key::Character(key @ (',' | '.')) => {
+                    if let Some(ch) = control_channel {
+                        let _ = ch.try_send(ControlMessage::ChangeTimeScale { faster: key == '.' });
+                    }
+                }
+                Key::Character('f') => {
+                    if let Some(ch) = control_channel {
+                        let _ = ch.try_send(ControlMessage::ToggleFullscreen);
+                    }
+                }
+                Key::Character('g') => {
+                    self.cursor_policy.update_mut(|policy| {
+                        *policy = match policy {
+                            CursorPolicy::SelectableOpaque => CursorPolicy::Selectable,
+                            _ => CursorPolicy::SelectableOpaque,
+                        };
+                    });
+                }
                 Key::Character('i') => {
                     if let Some(cell) = graphics_options {
                         cell.update_mut(|options| {
@@ -343,7 +537,8 @@ impl InputProcessor {
                         cell.update_mut(|options| {
                             options.transparency = match options.transparency {
                                 TransparencyOption::Surface => TransparencyOption::Volumetric,
-                                TransparencyOption::Volumetric => {
+                                TransparencyOption::Volumetric => TransparencyOption::Tinted,
+                                TransparencyOption::Tinted => {
                                     TransparencyOption::Threshold(notnan!(0.5))
                                 }
                                 TransparencyOption::Threshold(_) => TransparencyOption::Surface,
@@ -371,24 +566,78 @@ impl InputProcessor {
                         });
                     }
                 }
+                Key::Character('v') => {
+                    self.camera_mode.update_mut(|mode| {
+                        *mode = match mode {
+                            CameraMode::FirstPerson => CameraMode::ThirdPerson,
+                            CameraMode::ThirdPerson => CameraMode::FirstPerson,
+                            _ => CameraMode::FirstPerson, // TODO: either stop doing cycle-commands or put it on the enum so it can be exhaustive
+                        };
+                    });
+                }
+                Key::Character('x') => {
+                    if let Some(ch) = control_channel {
+                        let _ = ch.try_send(ControlMessage::ExtendSelection);
+                    }
+                }
+                Key::Character('z') => {
+                    if let Some(ch) = control_channel {
+                        let _ = ch.try_send(ControlMessage::ClearSelection);
+                    }
+                }
                 Key::Character(numeral) if numeral.is_ascii_digit() => {
                     let digit = numeral.to_digit(10).unwrap() as usize;
-                    let slot = (digit + 9).rem_euclid(10); // wrap 0 to 9
-                    if let Some(character_ref) = character_opt {
-                        character_ref
-                            .try_modify(|c| c.set_selected_slot(1, slot))
-                            .expect("character was borrowed during apply_input()");
+                    let position = (digit + 9).rem_euclid(10); // wrap 0 to 9
+                    if self.hotbar.get().slot(position).is_some() {
+                        self.hotbar.update_mut(|hotbar| {
+                            hotbar.select(position);
+                        });
+                        hotbar_selection = true;
                     }
                 }
                 _ => {}
             }
         }
+
+        if hotbar_selection {
+            if let (Some(character_ref), Some(slot)) =
+                (character_opt, self.hotbar.get().selected_slot())
+            {
+                character_ref
+                    .try_modify(|c| c.set_selected_slot(1, slot))
+                    .expect("character was borrowed during apply_input()");
+            }
+        }
     }
 
     pub fn mouselook_mode(&self) -> ListenableSource<bool> {
         self.mouselook_mode.as_source()
     }
 
+    /// Returns the [`Hotbar`] whose positions the digit keys and scroll wheel select,
+    /// for display by the UI.
+    pub fn hotbar(&self) -> ListenableSource<Hotbar> {
+        self.hotbar.as_source()
+    }
+
+    /// Replaces the [`Hotbar`], such as to change which or how many inventory slots it
+    /// offers.
+    pub fn set_hotbar(&mut self, hotbar: Hotbar) {
+        self.hotbar.set(hotbar);
+    }
+
+    /// Returns the [`CameraMode`] chosen by input, for use with
+    /// [`StandardCameras`](all_is_cubes::camera::StandardCameras).
+    pub fn camera_mode(&self) -> ListenableSource<CameraMode> {
+        self.camera_mode.as_source()
+    }
+
+    /// Returns the [`CursorPolicy`] chosen by input, for use with
+    /// [`StandardCameras::with_cursor_policy()`](all_is_cubes::camera::StandardCameras::with_cursor_policy).
+    pub fn cursor_policy(&self) -> ListenableSource<CursorPolicy> {
+        self.cursor_policy.as_source()
+    }
+
     // TODO: duplicated with the keybinding impl because of borrow conflicts
     pub(crate) fn toggle_mouselook_mode(&mut self) {
         let new_state = !*self.mouselook_mode.get();
@@ -442,7 +691,7 @@ pub(crate) struct InputTargets<'a> {
 }
 
 /// A platform-neutral representation of keyboard keys for [`InputProcessor`].
-#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq, Serialize, Deserialize)]
 #[non_exhaustive]
 pub enum Key {
     /// Letters should be lowercase.
@@ -457,6 +706,8 @@ pub enum Key {
     Up,
     /// Down arrow key.
     Down,
+    /// Backspace key, used only when typing text.
+    Backspace,
 }
 
 #[cfg(test)]
@@ -529,6 +780,127 @@ mod tests {
         input.key_up(Key::Character('0'));
         apply_input_helper(&mut input, u, &character);
         assert_eq!(character.read().unwrap().selected_slots()[1], 9);
+        assert_eq!(input.hotbar().get().selected(), 9);
+    }
+
+    #[test]
+    fn slot_selection_custom_hotbar() {
+        let u = &mut Universe::new();
+        let space = u.insert_anonymous(Space::empty_positive(1, 1, 1));
+        let character = u.insert_anonymous(Character::spawn_default(space.clone()));
+        let mut input = InputProcessor::new();
+        input.set_hotbar(Hotbar::new(vec![7, 2, 5]));
+
+        input.key_down(Key::Character('2'));
+        input.key_up(Key::Character('2'));
+        apply_input_helper(&mut input, u, &character);
+        assert_eq!(character.read().unwrap().selected_slots()[1], 2);
+
+        // Beyond the end of the hotbar; no effect.
+        input.key_down(Key::Character('4'));
+        input.key_up(Key::Character('4'));
+        apply_input_helper(&mut input, u, &character);
+        assert_eq!(character.read().unwrap().selected_slots()[1], 2);
+    }
+
+    #[test]
+    fn wheel_cycles_hotbar() {
+        let u = &mut Universe::new();
+        let space = u.insert_anonymous(Space::empty_positive(1, 1, 1));
+        let character = u.insert_anonymous(Character::spawn_default(space.clone()));
+        let mut input = InputProcessor::new();
+        input.set_hotbar(Hotbar::new(vec![7, 2, 5]));
+        character.try_modify(|c| c.set_selected_slot(1, 2)).unwrap();
+
+        input.mouse_wheel(1.0);
+        apply_input_helper(&mut input, u, &character);
+        assert_eq!(character.read().unwrap().selected_slots()[1], 5);
+
+        // Wraps around.
+        input.mouse_wheel(1.0);
+        apply_input_helper(&mut input, u, &character);
+        assert_eq!(character.read().unwrap().selected_slots()[1], 7);
+
+        // Partial lines accumulate.
+        input.mouse_wheel(-0.5);
+        apply_input_helper(&mut input, u, &character);
+        assert_eq!(character.read().unwrap().selected_slots()[1], 7);
+        input.mouse_wheel(-0.5);
+        apply_input_helper(&mut input, u, &character);
+        assert_eq!(character.read().unwrap().selected_slots()[1], 5);
+        assert_eq!(input.hotbar().get().selected(), 2);
+    }
+
+    #[test]
+    fn camera_mode_toggle() {
+        let u = &mut Universe::new();
+        let space = u.insert_anonymous(Space::empty_positive(1, 1, 1));
+        let character = u.insert_anonymous(Character::spawn_default(space.clone()));
+        let mut input = InputProcessor::new();
+        let mode = input.camera_mode();
+        assert_eq!(*mode.get(), CameraMode::FirstPerson);
+
+        input.key_down(Key::Character('v'));
+        input.key_up(Key::Character('v'));
+        apply_input_helper(&mut input, u, &character);
+        assert_eq!(*mode.get(), CameraMode::ThirdPerson);
+
+        input.key_down(Key::Character('v'));
+        input.key_up(Key::Character('v'));
+        apply_input_helper(&mut input, u, &character);
+        assert_eq!(*mode.get(), CameraMode::FirstPerson);
+    }
+
+    #[test]
+    fn cursor_policy_toggle() {
+        let u = &mut Universe::new();
+        let space = u.insert_anonymous(Space::empty_positive(1, 1, 1));
+        let character = u.insert_anonymous(Character::spawn_default(space.clone()));
+        let mut input = InputProcessor::new();
+        let policy = input.cursor_policy();
+        assert_eq!(*policy.get(), CursorPolicy::Selectable);
+
+        input.key_down(Key::Character('g'));
+        input.key_up(Key::Character('g'));
+        apply_input_helper(&mut input, u, &character);
+        assert_eq!(*policy.get(), CursorPolicy::SelectableOpaque);
+
+        input.key_down(Key::Character('g'));
+        input.key_up(Key::Character('g'));
+        apply_input_helper(&mut input, u, &character);
+        assert_eq!(*policy.get(), CursorPolicy::Selectable);
+    }
+
+    #[test]
+    fn console_typing() {
+        let mut input = InputProcessor::new();
+        input.key_down(Key::Character('w'));
+        assert_eq!(input.console_line(), None);
+
+        input.key_down(Key::Character('/'));
+        assert_eq!(input.console_line(), Some("/"));
+        assert_eq!(input.movement(), Vector3::zero()); // held keys released
+        for c in "tpx 1".chars() {
+            input.key_down(Key::Character(c));
+        }
+        input.key_down(Key::Left); // ignored
+        input.key_down(Key::Backspace);
+        input.key_down(Key::Backspace);
+        input.key_down(Key::Backspace);
+        assert_eq!(input.console_line(), Some("/tp"));
+        assert_eq!(input.movement(), Vector3::zero()); // typed keys do not move
+        input.key_down(Key::Character('\r'));
+        assert_eq!(input.console_line(), None);
+        assert_eq!(input.take_console_commands(), vec!["/tp".to_owned()]);
+        assert_eq!(input.take_console_commands(), Vec::<String>::new());
+
+        // Escape cancels without running a command, or going back in the UI.
+        input.key_down(Key::Character('/'));
+        input.key_down(Key::Character('x'));
+        input.key_down(Key::Escape);
+        assert_eq!(input.console_line(), None);
+        assert_eq!(input.take_console_commands(), Vec::<String>::new());
+        assert_eq!(input.command_buffer, vec![]);
     }
 
     // TODO: test jump and flying logic
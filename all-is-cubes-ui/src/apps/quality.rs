@@ -0,0 +1,192 @@
+use instant::Duration;
+
+use all_is_cubes::camera::{AntialiasingOption, GraphicsOptions, TransparencyOption};
+use all_is_cubes::math::NotNan;
+
+/// Algorithm for automatically reducing graphics quality when rendering is too slow,
+/// as enabled by [`GraphicsOptions::auto_quality`].
+///
+/// It is informed of the time taken to render each frame, and maintains a “reduction
+/// level” which [`QualityController::apply()`] uses to modify the graphics options
+/// requested by the user. Like [`FrameClock`](super::FrameClock), it does not consult
+/// any clocks itself.
+#[derive(Clone, Debug, PartialEq)]
+pub struct QualityController {
+    /// How much to reduce quality; 0 means the options are used unaltered.
+    level: u8,
+    /// Exponentially smoothed frame time, in seconds.
+    average_frame_time: Option<f64>,
+    /// Number of frames to wait before changing the level again, so that the effect of
+    /// the previous change is measured before making another.
+    cooldown: u32,
+}
+
+impl QualityController {
+    const TARGET_FRAME_TIME: Duration = Duration::from_micros(1_000_000 / 60);
+    /// Reduce quality if the average frame time exceeds the target by this factor.
+    const REDUCE_THRESHOLD: f64 = 1.25;
+    /// Increase quality only if the average frame time is less than the target by this
+    /// factor. This is well below 1 so that a level is not immediately abandoned after
+    /// it has been found to be fast enough.
+    const INCREASE_THRESHOLD: f64 = 0.6;
+    const COOLDOWN_FRAMES: u32 = 30;
+    const MAX_LEVEL: u8 = 6;
+
+    /// Constructs a new [`QualityController`], which initially makes no reductions.
+    pub fn new() -> Self {
+        Self {
+            level: 0,
+            average_frame_time: None,
+            cooldown: 0,
+        }
+    }
+
+    /// Returns the current reduction level, where 0 means no reduction.
+    pub fn level(&self) -> u8 {
+        self.level
+    }
+
+    /// Informs the controller of the time taken to render a frame, and adjusts the
+    /// reduction level if appropriate.
+    ///
+    /// Returns whether the level changed, in which case the options should be
+    /// [applied](Self::apply) again.
+    pub fn record_frame_time(&mut self, time: Duration) -> bool {
+        let this_frame = time.as_secs_f64();
+        let average = match self.average_frame_time {
+            Some(previous) => {
+                let mix = 2.0f64.powi(-3);
+                this_frame * mix + previous * (1. - mix)
+            }
+            None => this_frame,
+        };
+        self.average_frame_time = Some(average);
+
+        if self.cooldown > 0 {
+            self.cooldown -= 1;
+            return false;
+        }
+
+        let target = Self::TARGET_FRAME_TIME.as_secs_f64();
+        let new_level = if average > target * Self::REDUCE_THRESHOLD {
+            self.level.saturating_add(1).min(Self::MAX_LEVEL)
+        } else if average < target * Self::INCREASE_THRESHOLD {
+            self.level.saturating_sub(1)
+        } else {
+            self.level
+        };
+        if new_level == self.level {
+            false
+        } else {
+            self.level = new_level;
+            self.cooldown = Self::COOLDOWN_FRAMES;
+            true
+        }
+    }
+
+    /// Forgets all measurements and returns to making no reductions.
+    pub fn reset(&mut self) {
+        *self = Self::new();
+    }
+
+    /// Returns `options` modified according to the current reduction level.
+    ///
+    /// Nothing is changed if [`GraphicsOptions::auto_quality`] is false.
+    /// Otherwise, in order of increasing level: antialiasing is disabled, transparency is
+    /// drawn as [`TransparencyOption::Surface`], and then the view distance is reduced
+    /// further at each level.
+    pub fn apply(&self, mut options: GraphicsOptions) -> GraphicsOptions {
+        if !options.auto_quality {
+            return options;
+        }
+        if self.level >= 1 {
+            options.antialiasing = AntialiasingOption::None;
+        }
+        if self.level >= 2
+            && matches!(
+                options.transparency,
+                TransparencyOption::Volumetric | TransparencyOption::Tinted
+            )
+        {
+            options.transparency = TransparencyOption::Surface;
+        }
+        if self.level >= 3 {
+            let factor = 0.75f64.powi(i32::from(self.level - 2));
+            options.view_distance = (options.view_distance * factor).max(NotNan::from(1));
+        }
+        options
+    }
+}
+
+impl Default for QualityController {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn auto_options() -> GraphicsOptions {
+        let mut options = GraphicsOptions::default();
+        options.auto_quality = true;
+        options.antialiasing = AntialiasingOption::Always;
+        options
+    }
+
+    #[test]
+    fn reduces_then_recovers_with_hysteresis() {
+        let mut qc = QualityController::new();
+        let slow = Duration::from_millis(50);
+        let ok = Duration::from_millis(15);
+        let fast = Duration::from_millis(2);
+
+        assert!(qc.record_frame_time(slow));
+        assert_eq!(qc.level(), 1);
+        // No further change until the cooldown has passed.
+        for _ in 0..QualityController::COOLDOWN_FRAMES {
+            assert!(!qc.record_frame_time(slow));
+        }
+        assert!(qc.record_frame_time(slow));
+        assert_eq!(qc.level(), 2);
+
+        // Frame times just under the target do not raise the level.
+        for _ in 0..200 {
+            qc.record_frame_time(ok);
+        }
+        assert_eq!(qc.level(), 2);
+
+        for _ in 0..200 {
+            qc.record_frame_time(fast);
+        }
+        assert_eq!(qc.level(), 0);
+    }
+
+    #[test]
+    fn level_is_capped() {
+        let mut qc = QualityController::new();
+        for _ in 0..1000 {
+            qc.record_frame_time(Duration::from_secs(1));
+        }
+        assert_eq!(qc.level(), QualityController::MAX_LEVEL);
+    }
+
+    #[test]
+    fn apply() {
+        let mut qc = QualityController::new();
+        let options = auto_options();
+        assert_eq!(qc.apply(options.clone()), options);
+
+        qc.level = 3;
+        let reduced = qc.apply(options.clone());
+        assert_eq!(reduced.antialiasing, AntialiasingOption::None);
+        assert_eq!(reduced.transparency, TransparencyOption::Surface);
+        assert_eq!(reduced.view_distance, options.view_distance * 0.75);
+
+        // Disabled
+        let mut manual = options;
+        manual.auto_quality = false;
+        assert_eq!(qc.apply(manual.clone()), manual);
+    }
+}
@@ -0,0 +1,55 @@
+//! Recording of user input so that it can be played back.
+
+use serde::{Deserialize, Serialize};
+
+use all_is_cubes::math::FreeCoordinate;
+
+use crate::apps::Key;
+
+/// One input given to a [`Session`](crate::apps::Session), either through its
+/// [`InputProcessor`](crate::apps::InputProcessor) or directly, in a form which can be
+/// stored and later given again.
+///
+/// Obtain these from [`Session::take_recorded_input()`](crate::apps::Session::take_recorded_input)
+/// and play them back with
+/// [`Session::play_recorded_input()`](crate::apps::Session::play_recorded_input).
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub enum InputEvent {
+    /// [`InputProcessor::key_down()`](crate::apps::InputProcessor::key_down)
+    KeyDown(Key),
+    /// [`InputProcessor::key_up()`](crate::apps::InputProcessor::key_up)
+    KeyUp(Key),
+    /// [`InputProcessor::key_momentary()`](crate::apps::InputProcessor::key_momentary)
+    KeyMomentary(Key),
+    /// [`InputProcessor::key_focus()`](crate::apps::InputProcessor::key_focus)
+    KeyFocus(bool),
+    /// [`InputProcessor::has_pointer_lock()`](crate::apps::InputProcessor::has_pointer_lock)
+    HasPointerLock(bool),
+    /// [`InputProcessor::mouselook_delta()`](crate::apps::InputProcessor::mouselook_delta)
+    MouselookDelta([FreeCoordinate; 2]),
+    /// [`InputProcessor::mouse_ndc_position()`](crate::apps::InputProcessor::mouse_ndc_position)
+    MouseNdcPosition(Option<[FreeCoordinate; 2]>),
+    /// [`InputProcessor::mouse_wheel()`](crate::apps::InputProcessor::mouse_wheel)
+    MouseWheel(FreeCoordinate),
+    /// [`Session::click()`](crate::apps::Session::click)
+    Click(usize),
+}
+
+/// An [`InputEvent`] and when it was given.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct RecordedInput {
+    /// Number of steps the [`Session`](crate::apps::Session) had taken since its universe
+    /// was last set when the input was given; the input takes effect in the next step.
+    pub step: u64,
+    /// The input.
+    pub event: InputEvent,
+}
+
+impl RecordedInput {
+    /// Constructs a [`RecordedInput`].
+    pub fn new(step: u64, event: InputEvent) -> Self {
+        Self { step, event }
+    }
+}
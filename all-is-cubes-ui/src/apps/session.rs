@@ -1,25 +1,41 @@
+use std::collections::VecDeque;
+use std::error::Error;
 use std::fmt;
 use std::future::Future;
 use std::sync::mpsc::{self, TryRecvError};
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 use std::task::{Context, Poll};
 
 use futures_core::future::BoxFuture;
 use futures_task::noop_waker_ref;
 
 use all_is_cubes::camera::{GraphicsOptions, StandardCameras, UiViewState, Viewport};
-use all_is_cubes::character::{Character, Cursor};
+use all_is_cubes::cgmath::{Point2, Vector2};
+use all_is_cubes::character::{
+    AuthoritativeState, Character, CharacterInput, Cursor, PlayerStats, PosedPart, Prediction,
+    RemoteCharacter,
+};
+use all_is_cubes::entity::Entity;
+use all_is_cubes::event::GameEvent;
 use all_is_cubes::fluff::Fluff;
 use all_is_cubes::inv::ToolError;
 use all_is_cubes::listen::{
-    Listen as _, ListenableCell, ListenableCellWithLocal, ListenableSource, Listener, Notifier,
+    DirtyFlag, FnListener, Listen as _, ListenableCell, ListenableCellWithLocal, ListenableSource,
+    Listener, Notifier,
 };
+use all_is_cubes::math::GridAab;
+use all_is_cubes::sound::SoundEvent;
+use all_is_cubes::space::{Selection, Waypoint};
 use all_is_cubes::time::{Duration, Instant};
 use all_is_cubes::transaction::{self, Transaction as _};
 use all_is_cubes::universe::{URef, Universe, UniverseStepInfo};
-use all_is_cubes::util::{CustomFormat, StatusText};
+use all_is_cubes::util::{CustomFormat, ErrorChain, StatusText, YieldProgress};
 
-use crate::apps::{FpsCounter, FrameClock, InputProcessor, InputTargets};
+use crate::apps::{
+    CommandContext, CommandError, CommandRegistry, FpsCounter, FrameClock, InputEvent,
+    InputProcessor, InputTargets, QualityController, RecordedInput,
+};
+use crate::i18n::Language;
 use crate::vui::Vui;
 
 const LOG_FIRST_FRAMES: bool = false;
@@ -39,20 +55,77 @@ pub struct Session {
     pub input_processor: InputProcessor,
 
     graphics_options: ListenableCell<GraphicsOptions>,
+    graphics_options_dirty: DirtyFlag,
+    /// Graphics options actually used for rendering: `graphics_options` as modified by
+    /// `quality`.
+    effective_graphics_options: ListenableCell<GraphicsOptions>,
+    quality: QualityController,
+
+    /// Language of the user interface text.
+    language: ListenableCell<Language>,
 
     game_universe: Universe,
     game_character: ListenableCellWithLocal<Option<URef<Character>>>,
 
     /// If present, a future that should be polled to produce a new [`Universe`]
     /// to replace `self.game_universe`. See [`Self::set_universe_async`].
-    game_universe_in_progress: Option<BoxFuture<'static, Result<Universe, ()>>>,
+    game_universe_in_progress: Option<UniverseFuture>,
+    /// If present, a function which starts `game_universe_in_progress` over again.
+    /// See [`Self::retry_set_universe`].
+    game_universe_retry: Option<Box<dyn FnMut() -> UniverseFuture + Send>>,
+    /// Notifier for failures of `game_universe_in_progress`.
+    universe_load_error_notifier: Notifier<Arc<UniverseLoadError>>,
+
+    /// Events emitted by `game_universe`, to be logged and shown to the user.
+    game_events: Arc<Mutex<Vec<GameEvent>>>,
 
     fluff_notifier: Notifier<Fluff>, // TODO: should include spatial information
 
+    /// Notifier for sounds emitted by the game universe.
+    sound_notifier: Notifier<SoundEvent>,
+
     paused: ListenableCell<bool>,
 
+    /// Copy of [`FrameClock::step_interpolation()`] as of the last
+    /// [`Session::maybe_step_universe()`], for [`StandardCameras`] to use.
+    step_interpolation: ListenableCell<f64>,
+
+    /// Characters controlled by other players; see [`Self::remote_characters_mut()`].
+    remote_characters: Vec<RemoteCharacter>,
+    /// Posed avatars of `remote_characters`, and parts of `game_universe`'s entities,
+    /// in the game character's space, as of the last
+    /// [`Session::maybe_step_universe()`], for [`StandardCameras`] to draw.
+    remote_avatars: ListenableCell<Vec<PosedPart>>,
+
+    /// Waypoints in `game_universe` as of the last [`Session::maybe_step_universe()`],
+    /// for the UI to point out.
+    waypoints: ListenableCell<Vec<URef<Waypoint>>>,
+
+    /// Present if in networked mode; see [`Self::set_networked()`].
+    prediction: Option<Prediction>,
+    /// Inputs recorded by `prediction` and not yet taken by
+    /// [`Self::take_character_inputs()`].
+    outgoing_inputs: Vec<CharacterInput>,
+
+    /// Commands which may be typed into the console; see [`Self::commands_mut()`].
+    commands: CommandRegistry,
+
+    /// Number of steps taken since the universe was last set, by which recorded input
+    /// is timed.
+    universe_step_count: u64,
+    /// Input recorded from `input_processor` and not yet taken by
+    /// [`Self::take_recorded_input()`].
+    recorded_input: Vec<RecordedInput>,
+    /// Input given to [`Self::play_recorded_input()`] which is not yet due.
+    input_to_play: VecDeque<RecordedInput>,
+
     ui: Option<Vui>,
 
+    /// Whether the session is being displayed full-screen, and how to change that;
+    /// see [`SessionBuilder::fullscreen()`].
+    fullscreen_state: ListenableSource<FullscreenState>,
+    set_fullscreen: FullscreenSetter,
+
     /// Messages for controlling the state that aren't via [`InputProcessor`].
     ///
     /// TODO: This is originally a quick kludge to make onscreen UI buttons work.
@@ -65,6 +138,10 @@ pub struct Session {
     /// one of two different spaces.
     cursor_result: Option<Cursor>,
 
+    /// Cubes chosen by the user for the selection commands to operate on; see
+    /// [`Self::extend_selection()`].
+    selection: ListenableCell<Option<Selection>>,
+
     last_step_info: UniverseStepInfo,
 
     tick_counter_for_logging: u8,
@@ -76,15 +153,36 @@ impl fmt::Debug for Session {
             frame_clock,
             input_processor,
             graphics_options,
+            graphics_options_dirty: _,
+            effective_graphics_options,
+            quality,
+            language,
             game_universe,
             game_character,
             game_universe_in_progress,
+            game_universe_retry,
+            universe_load_error_notifier,
+            game_events: _,
             fluff_notifier,
+            sound_notifier,
             paused,
+            step_interpolation,
+            remote_characters,
+            remote_avatars: _,
+            waypoints,
+            prediction,
+            outgoing_inputs,
+            commands,
+            universe_step_count,
+            recorded_input,
+            input_to_play,
             ui,
+            fullscreen_state,
+            set_fullscreen: _,
             control_channel: _,
             control_channel_sender: _,
             cursor_result,
+            selection,
             last_step_info,
             tick_counter_for_logging,
         } = self;
@@ -93,16 +191,36 @@ impl fmt::Debug for Session {
             .field("frame_clock", frame_clock)
             .field("input_processor", input_processor)
             .field("graphics_options", graphics_options)
+            .field("effective_graphics_options", effective_graphics_options)
+            .field("quality", quality)
+            .field("language", language)
             .field("game_universe", game_universe)
             .field("game_character", game_character)
             .field(
                 "game_universe_in_progress",
                 &game_universe_in_progress.as_ref().map(|_| "..."),
             )
+            .field(
+                "game_universe_retry",
+                &game_universe_retry.as_ref().map(|_| "..."),
+            )
+            .field("universe_load_error_notifier", universe_load_error_notifier)
             .field("fluff_notifier", fluff_notifier)
+            .field("sound_notifier", sound_notifier)
             .field("paused", &paused)
+            .field("step_interpolation", &step_interpolation)
+            .field("remote_characters", &remote_characters)
+            .field("waypoints", &waypoints)
+            .field("prediction", &prediction)
+            .field("outgoing_inputs", &outgoing_inputs)
+            .field("commands", &commands)
+            .field("universe_step_count", &universe_step_count)
+            .field("recorded_input", &recorded_input)
+            .field("input_to_play", &input_to_play)
             .field("ui", &ui)
+            .field("fullscreen_state", &fullscreen_state)
             .field("cursor_result", &cursor_result)
+            .field("selection", &selection)
             .field("last_step_info", &last_step_info)
             .field("tick_counter_for_logging", &tick_counter_for_logging)
             .finish_non_exhaustive()
@@ -125,10 +243,22 @@ impl Session {
     pub fn set_universe(&mut self, u: Universe) {
         // Clear any previous set_universe_async.
         self.game_universe_in_progress = None;
+        self.game_universe_retry = None;
 
         self.game_universe = u;
+        self.game_events = listen_for_events(&self.game_universe);
         self.game_character
             .set(self.game_universe.get_default_character());
+        // Remote characters refer to spaces in the old universe.
+        self.remote_characters.clear();
+        // Inputs applied to the old character cannot be replayed on the new one.
+        if self.prediction.is_some() {
+            self.prediction = Some(Prediction::new());
+        }
+        self.outgoing_inputs.clear();
+        self.universe_step_count = 0;
+        // The selection refers to a space in the old universe.
+        self.selection.set(None);
     }
 
     /// Perform [`Self::set_universe`] on the result of the provided future when it
@@ -141,13 +271,54 @@ impl Session {
     /// [`Self::set_universe`] is called before it completes.
     /// Currently, the future is polled once per frame unconditionally.
     ///
-    /// If the future returns `Err`, then the current universe is not replaced. There is
-    /// not any mechanism to display an error message; that must be done separately.
+    /// If the future returns `Err`, then the current universe is not replaced; instead,
+    /// the error is logged, shown in the user interface, and delivered to listeners
+    /// registered with [`Self::listen_universe_load_errors`].
     pub fn set_universe_async<F>(&mut self, future: F)
     where
-        F: Future<Output = Result<Universe, ()>> + Send + 'static,
+        F: Future<Output = Result<Universe, UniverseLoadError>> + Send + 'static,
     {
         self.game_universe_in_progress = Some(Box::pin(future));
+        self.game_universe_retry = None;
+    }
+
+    /// Like [`Self::set_universe_async`], but takes a function producing the future,
+    /// which will be called again if the user asks to retry after a failure, or if
+    /// [`Self::retry_set_universe`] is called.
+    pub fn set_universe_async_retryable<G, F>(&mut self, mut make_future: G)
+    where
+        G: FnMut() -> F + Send + 'static,
+        F: Future<Output = Result<Universe, UniverseLoadError>> + Send + 'static,
+    {
+        let mut make_future = move || -> UniverseFuture { Box::pin(make_future()) };
+        self.game_universe_in_progress = Some(make_future());
+        self.game_universe_retry = Some(Box::new(make_future));
+    }
+
+    /// Starts over the most recent [`Self::set_universe_async_retryable`] operation,
+    /// as after it has failed.
+    ///
+    /// Returns false, and does nothing, if there is no such operation to retry; that is,
+    /// if [`Self::set_universe_async_retryable`] has not been called, or the universe
+    /// has since been replaced.
+    pub fn retry_set_universe(&mut self) -> bool {
+        match &mut self.game_universe_retry {
+            Some(make_future) => {
+                self.game_universe_in_progress = Some(make_future());
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Listen for failures of [`Self::set_universe_async`] and
+    /// [`Self::set_universe_async_retryable`], so that they may be reported to the user
+    /// in ways other than the session's own user interface.
+    pub fn listen_universe_load_errors(
+        &self,
+        listener: impl Listener<Arc<UniverseLoadError>> + Send + Sync + 'static,
+    ) {
+        self.universe_load_error_notifier.listen(listener)
     }
 
     /// Returns a mutable reference to the [`Universe`].
@@ -166,6 +337,14 @@ impl Session {
         }
     }
 
+    /// What the renderer should be displaying on screen as the character's held tool.
+    fn hand_view(&self) -> ListenableSource<UiViewState> {
+        match &self.ui {
+            Some(ui) => ui.hand_view(),
+            None => ListenableSource::constant(UiViewState::default()),
+        }
+    }
+
     pub fn graphics_options(&self) -> ListenableSource<GraphicsOptions> {
         self.graphics_options.as_source()
     }
@@ -174,14 +353,35 @@ impl Session {
         &self.graphics_options
     }
 
+    /// Returns the language in which the user interface is displayed.
+    pub fn language(&self) -> ListenableSource<Language> {
+        self.language.as_source()
+    }
+
+    /// Changes the language in which the user interface is displayed.
+    ///
+    /// The UI is rebuilt in the new language when the session is next stepped.
+    pub fn set_language(&self, language: Language) {
+        self.language.set(language);
+    }
+
     /// Create [`StandardCameras`] which may be used in rendering a view of this session.
+    ///
+    /// Their graphics options are those of [`Session::graphics_options()`], except when
+    /// [`GraphicsOptions::auto_quality`] causes them to be reduced.
     pub fn create_cameras(&self, viewport_source: ListenableSource<Viewport>) -> StandardCameras {
         StandardCameras::new(
-            self.graphics_options(),
+            self.effective_graphics_options.as_source(),
             viewport_source,
             self.character(),
+            self.input_processor.camera_mode(),
             self.ui_view(),
+            self.hand_view(),
+            self.step_interpolation.as_source(),
         )
+        .with_remote_avatars(self.remote_avatars.as_source())
+        .with_cursor_policy(self.input_processor.cursor_policy())
+        .with_selection(self.selection.as_source())
     }
 
     /// Listen for [`Fluff`] events from this session. Fluff constitutes short-duration
@@ -190,11 +390,46 @@ impl Session {
         self.fluff_notifier.listen(listener)
     }
 
+    /// Listen for [`SoundEvent`]s emitted by the game universe as it is stepped.
+    pub fn listen_sounds(&self, listener: impl Listener<SoundEvent> + Send + Sync + 'static) {
+        self.sound_notifier.listen(listener)
+    }
+
     /// Steps the universe if the `FrameClock` says it's time to do so.
     /// Always returns info for the last step even if multiple steps were taken.
     ///
     /// Also applies input from the control channel. TODO: Should that be separate?
     pub fn maybe_step_universe(&mut self) -> Option<UniverseStepInfo> {
+        // Without a `YieldProgress`, the future never yields, so one poll completes it.
+        match Box::pin(self.maybe_step_universe_impl(None))
+            .as_mut()
+            .poll(&mut Context::from_waker(noop_waker_ref()))
+        {
+            Poll::Ready(result) => result,
+            Poll::Pending => unreachable!("synchronous step yielded"),
+        }
+    }
+
+    /// As [`Self::maybe_step_universe()`], but steps the game universe using
+    /// [`Universe::step_async()`], yielding via `yield_progress` whenever stepping takes
+    /// longer than its time budget. This is for hosts with a single-threaded event loop,
+    /// which would otherwise be blocked until a slow step finishes.
+    pub async fn maybe_step_universe_async(
+        &mut self,
+        yield_progress: YieldProgress,
+    ) -> Option<UniverseStepInfo> {
+        self.maybe_step_universe_impl(Some(yield_progress)).await
+    }
+
+    async fn maybe_step_universe_impl(
+        &mut self,
+        mut yield_progress: Option<YieldProgress>,
+    ) -> Option<UniverseStepInfo> {
+        // Input received since the last call takes effect in the next step, and so is
+        // timed by it; input being played back is given at the same point.
+        self.stamp_recorded_input();
+        self.play_due_input();
+
         loop {
             match self.control_channel.try_recv() {
                 Ok(msg) => match msg {
@@ -210,9 +445,44 @@ impl Session {
                     ControlMessage::ToggleMouselook => {
                         self.input_processor.toggle_mouselook_mode();
                     }
+                    ControlMessage::ToggleFullscreen => {
+                        if let Some(set_fullscreen) = &self.set_fullscreen {
+                            // An unknown state is most likely to be the usual windowed one.
+                            set_fullscreen(!(*self.fullscreen_state.get()).unwrap_or(false));
+                        }
+                    }
+                    ControlMessage::ChangeTimeScale { faster } => {
+                        let old = self.frame_clock.time_scale();
+                        let new = if faster { old.faster() } else { old.slower() };
+                        self.frame_clock.set_time_scale(new);
+                        if let Some(ui) = &self.ui {
+                            let catalog = self.language.get().catalog();
+                            ui.show_message(
+                                catalog
+                                    .format("message-time-scale", &[("scale", &new)])
+                                    .into(),
+                            );
+                        }
+                    }
                     ControlMessage::ModifyGraphicsOptions(f) => {
                         self.graphics_options.set(f(self.graphics_options.get()));
                     }
+                    ControlMessage::ExtendSelection => self.extend_selection(),
+                    ControlMessage::ClearSelection => self.clear_selection(),
+                    ControlMessage::RetryUniverseLoad => {
+                        self.retry_set_universe();
+                    }
+                    ControlMessage::ShowStats => {
+                        if let Some(ui) = &mut self.ui {
+                            let character = self.game_character.borrow().clone();
+                            let stats = self
+                                .game_universe
+                                .iter_by_type::<PlayerStats>()
+                                .filter_map(|(_, stats_ref)| stats_ref.read().ok())
+                                .find(|stats| Some(stats.character()) == character.as_ref());
+                            ui.show_stats(stats.as_deref());
+                        }
+                    }
                 },
                 Err(TryRecvError::Empty) => break,
                 Err(TryRecvError::Disconnected) => {
@@ -233,29 +503,51 @@ impl Session {
                     match result {
                         Ok(universe) => {
                             self.set_universe(universe);
+                            if let Some(ui) = &mut self.ui {
+                                ui.hide_load_error();
+                            }
                         }
-                        Err(()) => {
-                            // No error reporting, for now; let it be the caller's resposibility
-                            // (which we indicate by making the error type be ()).
-                            // There should be something, but it's not clear what; perhaps
-                            // it will become clearer as the UI gets fleshed out.
+                        Err(error) => {
+                            log::error!("{}", ErrorChain(&error));
+                            if let Some(ui) = &mut self.ui {
+                                ui.show_load_error(&error, self.game_universe_retry.is_some());
+                            }
+                            self.universe_load_error_notifier.notify(Arc::new(error));
                         }
                     }
                 }
             }
         }
 
+        // Show the console before running commands, so that closing it does not hide the
+        // commands' results.
+        if let Some(ui) = &self.ui {
+            ui.show_console_line(self.input_processor.console_line());
+        }
+        for line in self.input_processor.take_console_commands() {
+            // Errors are reported by run_command().
+            let _ = self.run_command(&line);
+        }
+
         let mut result = None;
         // TODO: Catch-up implementation should probably live in FrameClock.
         for _ in 0..FrameClock::CATCH_UP_STEPS {
+            if self.input_due() {
+                // Let the next call play the input before the step it belongs to.
+                break;
+            }
             if self.frame_clock.should_step() {
                 let base_tick = self.frame_clock.tick();
-                let game_tick = if *self.paused.get() {
+                self.frame_clock.did_step();
+                let game_steps = self.frame_clock.take_game_steps();
+                let game_steps = if *self.paused.get() { 0 } else { game_steps };
+                // If no game time is to pass, we still step with a paused tick, so that
+                // things which don't depend on time passing continue to be updated.
+                let game_tick = if game_steps == 0 {
                     base_tick.pause()
                 } else {
                     base_tick
                 };
-                self.frame_clock.did_step();
 
                 if let Some(character_ref) = self.game_character.borrow() {
                     self.input_processor.apply_input(
@@ -268,6 +560,16 @@ impl Session {
                         },
                         game_tick,
                     );
+                    if let (Some(prediction), false) = (&mut self.prediction, game_tick.paused()) {
+                        match character_ref.read() {
+                            Ok(character) => self.outgoing_inputs.push(prediction.record_input(
+                                &character,
+                                self.input_processor.jump(),
+                                game_tick,
+                            )),
+                            Err(e) => log::error!("Failed to record input for prediction: {e}"),
+                        }
+                    }
                 }
                 self.input_processor.step(game_tick);
 
@@ -277,9 +579,29 @@ impl Session {
                 let deadline = Instant::now() + base_tick.delta_t() / 4;
 
                 // TODO(time-budget): give UI a minimum fraction of budget
-                let mut info = self.game_universe.step(game_tick, deadline);
+                // The time scale changes how many ticks there are, never their length.
+                let mut info = UniverseStepInfo::default();
+                for _ in 0..game_steps.max(1) {
+                    info += match yield_progress.take() {
+                        Some(progress) => {
+                            let [this_step, rest] = progress.split(0.5);
+                            yield_progress = Some(rest);
+                            self.game_universe
+                                .step_async(game_tick, deadline, this_step)
+                                .await
+                        }
+                        None => self.game_universe.step(game_tick, deadline),
+                    };
+                    for remote in &mut self.remote_characters {
+                        remote.step(game_tick);
+                    }
+                }
+                for sound in self.game_universe.take_sounds() {
+                    self.sound_notifier.notify(sound);
+                }
                 if let Some(ui) = &mut self.ui {
-                    info += ui.step(base_tick, deadline);
+                    ui.record_step_info(&info);
+                    info += ui.step(base_tick, deadline).await;
                 }
 
                 if LOG_FIRST_FRAMES && self.tick_counter_for_logging <= 10 {
@@ -291,12 +613,230 @@ impl Session {
                     );
                 }
                 self.last_step_info = info.clone();
+                self.universe_step_count += 1;
                 result = Some(info);
             }
         }
+        self.show_game_events();
+        self.step_interpolation
+            .set(self.frame_clock.step_interpolation());
+        self.update_remote_avatars();
+        self.update_waypoints();
+        if self.graphics_options_dirty.get_and_clear() {
+            self.update_effective_graphics_options();
+        }
+        result
+    }
+
+    /// Returns the characters controlled by other players, which are drawn in the world
+    /// (when they are in the same space as the game character) and animated as the
+    /// universe is stepped.
+    ///
+    /// A multiplayer client should add and remove these as players join and leave, and
+    /// pass them the updates it receives; they are discarded when the universe is
+    /// replaced.
+    pub fn remote_characters_mut(&mut self) -> &mut Vec<RemoteCharacter> {
+        &mut self.remote_characters
+    }
+
+    /// Enables or disables networked mode, in which the movement of the game character
+    /// is predicted from the user's input while a server decides where it actually is.
+    ///
+    /// In networked mode, the input applied to the game character each step is recorded,
+    /// and should be taken with [`Self::take_character_inputs()`] and sent to the server;
+    /// each state the server sends back should be passed to
+    /// [`Self::receive_character_state()`].
+    pub fn set_networked(&mut self, networked: bool) {
+        if networked != self.prediction.is_some() {
+            self.prediction = networked.then(Prediction::new);
+            self.outgoing_inputs.clear();
+        }
+    }
+
+    /// Returns the inputs applied to the game character since the last call, to be sent
+    /// to the server. This is always empty if not in networked mode.
+    pub fn take_character_inputs(&mut self) -> Vec<CharacterInput> {
+        std::mem::take(&mut self.outgoing_inputs)
+    }
+
+    /// Corrects the game character's movement according to the state computed by the
+    /// server, replaying the inputs the server has not yet applied.
+    ///
+    /// Does nothing if not in networked mode.
+    pub fn receive_character_state(&mut self, state: &AuthoritativeState) {
+        let (Some(prediction), Some(character_ref)) =
+            (&mut self.prediction, self.game_character.borrow())
+        else {
+            return;
+        };
+        let result = character_ref.try_modify(|character| prediction.reconcile(character, state));
+        if !matches!(result, Ok(Ok(()))) {
+            log::error!("Failed to reconcile character state: {result:?}");
+        }
+    }
+
+    /// Starts or stops recording the input given to this session, to be taken with
+    /// [`Self::take_recorded_input()`].
+    ///
+    /// Recorded input, given to [`Self::play_recorded_input()`] in a session whose
+    /// universe was constructed the same way, reproduces what the user did, as long as
+    /// recording was started when the universe was set. Messages from the user
+    /// interface, such as those changing the time scale, are not recorded separately,
+    /// since they are caused by the recorded input.
+    pub fn set_recording_input(&mut self, recording: bool) {
+        self.stamp_recorded_input();
+        self.input_processor.set_recording_input(recording);
+    }
+
+    /// Returns the input given to this session since the last call, if recording.
+    pub fn take_recorded_input(&mut self) -> Vec<RecordedInput> {
+        self.stamp_recorded_input();
+        std::mem::take(&mut self.recorded_input)
+    }
+
+    /// Gives the session previously recorded input, in addition to any input from the
+    /// user, at the same steps since the universe was set as when it was recorded.
+    ///
+    /// Clicks are made at the current cursor position, which was set by the recorded
+    /// mouse movement only if the viewport is the same size as when it was recorded.
+    pub fn play_recorded_input(&mut self, input: impl IntoIterator<Item = RecordedInput>) {
+        self.input_to_play.extend(input);
+    }
+
+    /// Returns whether any input given to [`Self::play_recorded_input()`] has not yet
+    /// been played.
+    pub fn is_playing_recorded_input(&self) -> bool {
+        !self.input_to_play.is_empty()
+    }
+
+    /// Moves input from `input_processor`'s recording to `recorded_input`.
+    fn stamp_recorded_input(&mut self) {
+        let step = self.universe_step_count;
+        self.recorded_input.extend(
+            self.input_processor
+                .take_recorded_input()
+                .into_iter()
+                .map(|event| RecordedInput::new(step, event)),
+        );
+    }
+
+    /// Returns whether there is input to play before the next step.
+    fn input_due(&self) -> bool {
+        self.input_to_play
+            .front()
+            .map_or(false, |input| input.step <= self.universe_step_count)
+    }
+
+    fn play_due_input(&mut self) {
+        while self.input_due() {
+            let input = self.input_to_play.pop_front().unwrap();
+            match input.event {
+                InputEvent::KeyDown(key) => {
+                    self.input_processor.key_down(key);
+                }
+                InputEvent::KeyUp(key) => self.input_processor.key_up(key),
+                InputEvent::KeyMomentary(key) => {
+                    self.input_processor.key_momentary(key);
+                }
+                InputEvent::KeyFocus(has_focus) => self.input_processor.key_focus(has_focus),
+                InputEvent::HasPointerLock(value) => self.input_processor.has_pointer_lock(value),
+                InputEvent::MouselookDelta(delta) => {
+                    self.input_processor.mouselook_delta(Vector2::from(delta))
+                }
+                InputEvent::MouseNdcPosition(position) => self
+                    .input_processor
+                    .mouse_ndc_position(position.map(Point2::from)),
+                InputEvent::MouseWheel(delta) => self.input_processor.mouse_wheel(delta),
+                InputEvent::Click(button) => self.click(button),
+            }
+        }
+    }
+
+    /// Returns the commands which may be typed into the console, so that more may be
+    /// registered. Initially, these are [`CommandRegistry::with_standard_commands()`].
+    pub fn commands_mut(&mut self) -> &mut CommandRegistry {
+        &mut self.commands
+    }
+
+    /// Runs a command, such as `/tp 0 10 0`, as if it were typed into the console, and
+    /// shows the outcome to the user.
+    pub fn run_command(&mut self, line: &str) -> Result<(), CommandError> {
+        let character = self.game_character.borrow().clone();
+        let txn = self.commands.run(
+            line,
+            &CommandContext {
+                universe: &self.game_universe,
+                character: character.as_ref(),
+                selection: Option::as_ref(&self.selection.get()),
+            },
+        );
+        let result = txn.and_then(|txn| {
+            txn.execute(&mut self.game_universe, &mut transaction::no_outputs)
+                .map_err(|e| CommandError::Failed(e.to_string()))
+        });
+
+        match &result {
+            Ok(()) => log::info!("Ran command {line}"),
+            Err(error) => log::info!("Command {line} failed: {error}"),
+        }
+        if let Some(ui) = &self.ui {
+            ui.show_message(match &result {
+                Ok(()) => line.into(),
+                Err(error) => error.to_string().into(),
+            });
+        }
         result
     }
 
+    /// Logs the events the game universe has emitted, and shows those of interest to the
+    /// user in the UI.
+    fn show_game_events(&mut self) {
+        let events = std::mem::take(&mut *self.game_events.lock().unwrap());
+        for event in events {
+            log::info!("{event}");
+            if let Some(ui) = &self.ui {
+                ui.show_event(&event);
+            }
+        }
+    }
+
+    fn update_remote_avatars(&mut self) {
+        let space = self
+            .game_character
+            .borrow()
+            .as_ref()
+            .and_then(|c| c.read().ok().map(|c| c.space.clone()));
+        let mut avatars: Vec<PosedPart> = self
+            .remote_characters
+            .iter()
+            .filter(|remote| Some(remote.space()) == space.as_ref())
+            .flat_map(RemoteCharacter::posed_avatar)
+            .collect();
+        // Entities are drawn the same way. If one is busy, it is skipped this time.
+        for (_, entity_ref) in self.game_universe.iter_by_type::<Entity>() {
+            if let Ok(entity) = entity_ref.read() {
+                if Some(&entity.space) == space.as_ref() {
+                    avatars.push(entity.posed_part());
+                }
+            }
+        }
+        // Avoid notifying when there continue to be none.
+        if !(avatars.is_empty() && self.remote_avatars.get().is_empty()) {
+            self.remote_avatars.set(avatars);
+        }
+    }
+
+    fn update_waypoints(&mut self) {
+        let waypoints: Vec<URef<Waypoint>> = self
+            .game_universe
+            .iter_by_type::<Waypoint>()
+            .map(|(_, waypoint)| waypoint)
+            .collect();
+        if *self.waypoints.get() != waypoints {
+            self.waypoints.set(waypoints);
+        }
+    }
+
     /// Call this once per frame to update the cursor raycast.
     ///
     /// TODO: bad API; revisit general cursor handling logic.
@@ -314,6 +854,38 @@ impl Session {
         self.cursor_result.as_ref()
     }
 
+    /// Returns the cubes chosen by the user for the selection commands to operate on,
+    /// such as `/fill`.
+    pub fn selection(&self) -> ListenableSource<Option<Selection>> {
+        self.selection.as_source()
+    }
+
+    /// Adds the cube the [`Cursor`] is pointing at in the game world to the selection,
+    /// enlarging the selected box to include it.
+    ///
+    /// If the cursor is in a different space than the current selection, the selection
+    /// is replaced with just that cube. If the cursor is not pointing at anything in the
+    /// game world, does nothing.
+    pub fn extend_selection(&mut self) {
+        let Some(cursor) = &self.cursor_result else {
+            return;
+        };
+        if Some(cursor.space()) == Option::as_ref(&self.ui_view().get().space) {
+            return;
+        }
+        let mut selection = match Option::clone(&self.selection.get()) {
+            Some(selection) if selection.space() == cursor.space() => selection,
+            _ => Selection::from_box(cursor.space().clone(), GridAab::single_cube(cursor.cube())),
+        };
+        selection.extend(cursor.cube());
+        self.selection.set(Some(selection));
+    }
+
+    /// Deselects all cubes.
+    pub fn clear_selection(&mut self) {
+        self.selection.set(None);
+    }
+
     /// Returns the suggested mouse-pointer/cursor appearance for the current [`Cursor`]
     /// as computed by the last call to [`Session::update_cursor()`].
     ///
@@ -336,6 +908,7 @@ impl Session {
     pub fn click(&mut self, button: usize) {
         // TODO: This function has no tests.
 
+        self.input_processor.record(InputEvent::Click(button));
         let result = self.click_impl(button);
 
         // Now, do all the _reporting_ of the tool's success or failure.
@@ -386,6 +959,10 @@ impl Session {
                     .execute(self.universe_mut(), &mut transaction::no_outputs)
                     .map_err(|e| ToolError::Internal(e.to_string()))?;
 
+                if let Some(ui) = &mut self.ui {
+                    ui.swing_hand();
+                }
+
                 // Spend a little time doing light updates, to ensure that changes right in front of
                 // the player are clean (and not flashes of blackness).
                 if let Some(space_ref) = self.cursor_result.as_ref().map(Cursor::space) {
@@ -419,6 +996,33 @@ impl Session {
         }
     }
 
+    /// Records how long it took to render the most recent frame, so that it can be
+    /// graphed in the HUD along with the stepping information.
+    ///
+    /// It is also used to adjust the graphics options if
+    /// [`GraphicsOptions::auto_quality`] is enabled.
+    ///
+    /// The renderer should call this once per frame drawn, if it has timing information.
+    pub fn record_render_time(&mut self, time: Duration) {
+        if let Some(ui) = &self.ui {
+            ui.record_render_time(time);
+        }
+
+        if self.graphics_options.get().auto_quality {
+            if self.quality.record_frame_time(time) {
+                log::debug!("Graphics quality reduction level {}", self.quality.level());
+                self.update_effective_graphics_options();
+            }
+        } else {
+            self.quality.reset();
+        }
+    }
+
+    fn update_effective_graphics_options(&mut self) {
+        self.effective_graphics_options
+            .set(self.quality.apply(self.graphics_options.snapshot()));
+    }
+
     #[doc(hidden)] // TODO: Decide whether we want FpsCounter in our public API
     pub fn draw_fps_counter(&self) -> &FpsCounter {
         self.frame_clock.draw_fps_counter()
@@ -431,6 +1035,7 @@ impl Session {
 #[allow(missing_debug_implementations)]
 pub struct SessionBuilder {
     viewport_for_ui: Option<ListenableSource<Viewport>>,
+    language: Language,
 
     fullscreen_state: ListenableSource<FullscreenState>,
     set_fullscreen: FullscreenSetter,
@@ -440,6 +1045,7 @@ impl Default for SessionBuilder {
     fn default() -> Self {
         Self {
             viewport_for_ui: None,
+            language: Language::default(),
             fullscreen_state: ListenableSource::constant(None),
             set_fullscreen: None,
         }
@@ -455,14 +1061,20 @@ impl SessionBuilder {
     pub async fn build(self) -> Session {
         let Self {
             viewport_for_ui,
+            language,
             fullscreen_state,
             set_fullscreen,
         } = self;
         let game_universe = Universe::new();
+        let game_events = listen_for_events(&game_universe);
         let game_character = ListenableCellWithLocal::new(None);
         let input_processor = InputProcessor::new();
         let graphics_options = ListenableCell::new(GraphicsOptions::default());
+        let graphics_options_dirty = DirtyFlag::listening(false, graphics_options.as_source());
+        let effective_graphics_options = ListenableCell::new(graphics_options.snapshot());
+        let language = ListenableCell::new(language);
         let paused = ListenableCell::new(false);
+        let waypoints = ListenableCell::new(Vec::new());
         let (control_send, control_recv) = mpsc::sync_channel(100);
 
         Session {
@@ -471,12 +1083,14 @@ impl SessionBuilder {
                     Vui::new(
                         &input_processor,
                         game_character.as_source(),
+                        waypoints.as_source(),
                         paused.as_source(),
                         graphics_options.as_source(),
+                        language.as_source(),
                         control_send.clone(),
                         viewport,
-                        fullscreen_state,
-                        set_fullscreen,
+                        fullscreen_state.clone(),
+                        set_fullscreen.clone(),
                     )
                     .await,
                 ),
@@ -485,14 +1099,35 @@ impl SessionBuilder {
             frame_clock: FrameClock::new(),
             input_processor,
             graphics_options,
+            graphics_options_dirty,
+            effective_graphics_options,
+            quality: QualityController::new(),
+            language,
             game_character,
             game_universe,
             game_universe_in_progress: None,
+            game_universe_retry: None,
+            universe_load_error_notifier: Notifier::new(),
+            game_events,
             fluff_notifier: Notifier::new(),
+            sound_notifier: Notifier::new(),
             paused,
+            step_interpolation: ListenableCell::new(1.0),
+            remote_characters: Vec::new(),
+            remote_avatars: ListenableCell::new(Vec::new()),
+            waypoints,
+            prediction: None,
+            outgoing_inputs: Vec::new(),
+            commands: CommandRegistry::with_standard_commands(),
+            universe_step_count: 0,
+            recorded_input: Vec::new(),
+            input_to_play: VecDeque::new(),
+            fullscreen_state,
+            set_fullscreen,
             control_channel: control_recv,
             control_channel_sender: control_send,
             cursor_result: None,
+            selection: ListenableCell::new(None),
             last_step_info: UniverseStepInfo::default(),
             tick_counter_for_logging: 0,
         }
@@ -510,6 +1145,13 @@ impl SessionBuilder {
         self
     }
 
+    /// Set the language in which the user interface is initially displayed.
+    /// It may be changed later with [`Session::set_language()`].
+    pub fn language(mut self, language: Language) -> Self {
+        self.language = language;
+        self
+    }
+
     /// Enable awareness of whether the session is being displayed full-screen.
     ///
     /// * `state` should report the current state (`true` = is full screen).
@@ -526,6 +1168,46 @@ impl SessionBuilder {
     }
 }
 
+/// Returns a queue which collects the [`GameEvent`]s emitted by `universe`.
+fn listen_for_events(universe: &Universe) -> Arc<Mutex<Vec<GameEvent>>> {
+    let events = Arc::new(Mutex::new(Vec::new()));
+    universe.listen(FnListener::new(
+        &events,
+        |events: &Mutex<Vec<GameEvent>>, event| {
+            if let Ok(mut events) = events.lock() {
+                events.push(event);
+            }
+        },
+    ));
+    events
+}
+
+type UniverseFuture = BoxFuture<'static, Result<Universe, UniverseLoadError>>;
+
+/// Error from the future given to [`Session::set_universe_async`], explaining why a new
+/// universe could not be produced.
+#[derive(Debug, thiserror::Error)]
+#[non_exhaustive]
+pub enum UniverseLoadError {
+    /// Reading or importing a file failed.
+    #[error("failed to load world from file {name}")]
+    File {
+        /// The name or path of the file, for display.
+        name: String,
+        /// The reason the file could not be loaded.
+        #[source]
+        source: Box<dyn Error + Send + Sync>,
+    },
+
+    /// Generating a universe (for example, from a template) failed.
+    #[error("failed to generate world")]
+    Generation(#[source] Box<dyn Error + Send + Sync>),
+
+    /// Any other failure, described only by a message.
+    #[error("failed to load world: {0}")]
+    Other(String),
+}
+
 // TODO: these should be in one struct or something.
 pub(crate) type FullscreenState = Option<bool>;
 pub(crate) type FullscreenSetter = Option<Arc<dyn Fn(bool) + Send + Sync>>;
@@ -540,8 +1222,24 @@ pub(crate) enum ControlMessage {
     Back,
     TogglePause,
     ToggleMouselook,
+    /// Ask to enter or leave fullscreen, if [`SessionBuilder::fullscreen()`] was given
+    /// a way to do that.
+    ToggleFullscreen,
+    /// Change [`FrameClock::time_scale()`] to the next faster or slower
+    /// [`TimeScale`](crate::apps::TimeScale).
+    ChangeTimeScale {
+        faster: bool,
+    },
     /// TODO: this should be "modify user preferences", from which graphics options are derived.
     ModifyGraphicsOptions(Box<dyn FnOnce(Arc<GraphicsOptions>) -> Arc<GraphicsOptions> + Send>),
+    /// Add the cube under the cursor to the selection; see [`Session::extend_selection()`].
+    ExtendSelection,
+    /// Deselect all cubes; see [`Session::clear_selection()`].
+    ClearSelection,
+    /// Show the [`PlayerStats`] of the game character.
+    ShowStats,
+    /// Perform [`Session::retry_set_universe()`].
+    RetryUniverseLoad,
 }
 
 impl fmt::Debug for ControlMessage {
@@ -551,9 +1249,18 @@ impl fmt::Debug for ControlMessage {
             Self::Back => write!(f, "Back"),
             Self::TogglePause => write!(f, "TogglePause"),
             Self::ToggleMouselook => write!(f, "ToggleMouselook"),
+            Self::ToggleFullscreen => write!(f, "ToggleFullscreen"),
+            Self::ChangeTimeScale { faster } => f
+                .debug_struct("ChangeTimeScale")
+                .field("faster", faster)
+                .finish(),
             Self::ModifyGraphicsOptions(_f) => f
                 .debug_struct("ModifyGraphicsOptions")
                 .finish_non_exhaustive(),
+            Self::ExtendSelection => write!(f, "ExtendSelection"),
+            Self::ClearSelection => write!(f, "ClearSelection"),
+            Self::ShowStats => write!(f, "ShowStats"),
+            Self::RetryUniverseLoad => write!(f, "RetryUniverseLoad"),
         }
     }
 }
@@ -609,9 +1316,32 @@ pub enum CursorIcon {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::apps::Key;
+    use all_is_cubes::character::{cursor_raycast, CursorPolicy};
+    use all_is_cubes::content::make_some_blocks;
+    use all_is_cubes::listen::Sink;
+    use all_is_cubes::math::FreeCoordinate;
+    use all_is_cubes::raycast::Ray;
     use all_is_cubes::space::Space;
     use all_is_cubes::universe::{Name, Universe};
     use futures_channel::oneshot;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[tokio::test]
+    async fn maybe_step_universe_async() {
+        let mut session = Session::builder().build().await;
+        assert_eq!(
+            session
+                .maybe_step_universe_async(YieldProgress::noop())
+                .await,
+            None
+        );
+        session.frame_clock.advance_by(Duration::from_millis(20));
+        assert!(session
+            .maybe_step_universe_async(YieldProgress::noop())
+            .await
+            .is_some());
+    }
 
     #[tokio::test]
     async fn set_universe_async() {
@@ -646,4 +1376,177 @@ mod tests {
         // Verify cleanup (that the next step can succeed).
         session.maybe_step_universe();
     }
+
+    #[tokio::test]
+    async fn set_universe_async_failure_and_retry() {
+        let new_marker = Name::from("new");
+        let mut session = Session::builder().build().await;
+        let errors = Sink::new();
+        session.listen_universe_load_errors(errors.listener());
+
+        let attempts = Arc::new(AtomicUsize::new(0));
+        session.set_universe_async_retryable({
+            let attempts = attempts.clone();
+            let new_marker = new_marker.clone();
+            move || {
+                let first = attempts.fetch_add(1, Ordering::Relaxed) == 0;
+                let new_marker = new_marker.clone();
+                async move {
+                    if first {
+                        return Err(UniverseLoadError::Other("nope".into()));
+                    }
+                    let mut new_universe = Universe::new();
+                    new_universe
+                        .insert(new_marker, Space::empty_positive(1, 1, 1))
+                        .unwrap();
+                    Ok(new_universe)
+                }
+            }
+        });
+
+        // First attempt fails and is reported.
+        session.maybe_step_universe();
+        let reported = errors.drain();
+        assert_eq!(reported.len(), 1);
+        assert_eq!(reported[0].to_string(), "failed to load world: nope");
+        assert!(session.universe_mut().get::<Space>(&new_marker).is_none());
+
+        // Retrying succeeds.
+        assert!(session.retry_set_universe());
+        session.maybe_step_universe();
+        assert!(session.universe_mut().get::<Space>(&new_marker).is_some());
+        assert!(errors.drain().is_empty());
+        assert_eq!(attempts.load(Ordering::Relaxed), 2);
+
+        // Once successful, there is nothing to retry.
+        assert!(!session.retry_set_universe());
+    }
+
+    #[tokio::test]
+    async fn record_and_play_input() {
+        fn universe() -> Universe {
+            let mut universe = Universe::new();
+            let space = universe.insert_anonymous(Space::empty_positive(10, 10, 10));
+            let mut character = Character::spawn_default(space);
+            character.body.flying = true;
+            universe.insert("character".into(), character).unwrap();
+            universe
+        }
+        fn step(session: &mut Session) {
+            session
+                .frame_clock
+                .advance_by(Duration::from_micros(16_667));
+            session.maybe_step_universe();
+        }
+        fn position(session: &Session) -> all_is_cubes::cgmath::Point3<f64> {
+            session
+                .character()
+                .snapshot()
+                .unwrap()
+                .read()
+                .unwrap()
+                .body
+                .position
+        }
+
+        // Record some movement.
+        let mut session = Session::builder().build().await;
+        session.set_universe(universe());
+        session.set_recording_input(true);
+        let start = position(&session);
+        step(&mut session);
+        session.input_processor.key_down(Key::Character('d'));
+        for _ in 0..5 {
+            step(&mut session);
+        }
+        session.input_processor.key_up(Key::Character('d'));
+        step(&mut session);
+        let recorded_position = position(&session);
+        let recording = session.take_recorded_input();
+        assert_eq!(
+            recording,
+            vec![
+                RecordedInput::new(1, InputEvent::KeyDown(Key::Character('d'))),
+                RecordedInput::new(6, InputEvent::KeyUp(Key::Character('d'))),
+            ]
+        );
+        assert!(recorded_position.x > start.x);
+
+        // Play it back in a new session.
+        let mut session = Session::builder().build().await;
+        session.set_universe(universe());
+        session.play_recorded_input(recording);
+        for _ in 0..7 {
+            step(&mut session);
+        }
+        assert!(!session.is_playing_recorded_input());
+        assert_eq!(position(&session), recorded_position);
+    }
+
+    #[tokio::test]
+    async fn extend_and_clear_selection() {
+        let [block] = make_some_blocks();
+        let mut session = Session::builder().build().await;
+        let mut space = Space::empty_positive(4, 1, 1);
+        space.fill_uniform(space.bounds(), block).unwrap();
+        let space = session.universe_mut().insert_anonymous(space);
+        let point_at = |session: &mut Session, x: f64| {
+            session.cursor_result = cursor_raycast(
+                Ray::new([x, 0.5, -1.0], [0., 0., 1.]),
+                &space,
+                FreeCoordinate::INFINITY,
+                CursorPolicy::default(),
+            );
+            assert!(session.cursor_result.is_some());
+        };
+
+        point_at(&mut session, 0.5);
+        session.extend_selection();
+        point_at(&mut session, 2.5);
+        session.extend_selection();
+        assert_eq!(
+            *session.selection().get(),
+            Some(Selection::from_box(
+                space.clone(),
+                GridAab::from_lower_size([0, 0, 0], [3, 1, 1])
+            ))
+        );
+
+        session.clear_selection();
+        assert_eq!(*session.selection().get(), None);
+    }
+
+    #[tokio::test]
+    async fn toggle_fullscreen_key() {
+        let fullscreen_cell = Arc::new(ListenableCell::new(Some(false)));
+        let mut session = Session::builder()
+            .fullscreen(
+                fullscreen_cell.as_source(),
+                Some(Arc::new({
+                    let fullscreen_cell = fullscreen_cell.clone();
+                    move |value| fullscreen_cell.set(Some(value))
+                })),
+            )
+            .build()
+            .await;
+        let mut universe = Universe::new();
+        let space = universe.insert_anonymous(Space::empty_positive(1, 1, 1));
+        universe
+            .insert("character".into(), Character::spawn_default(space))
+            .unwrap();
+        session.set_universe(universe);
+
+        for expected in [true, false] {
+            session.input_processor.key_down(Key::Character('f'));
+            session.input_processor.key_up(Key::Character('f'));
+            // The first step applies the input, and the second handles the message.
+            for _ in 0..2 {
+                session
+                    .frame_clock
+                    .advance_by(Duration::from_micros(16_667));
+                session.maybe_step_universe();
+            }
+            assert_eq!(*fullscreen_cell.get(), Some(expected));
+        }
+    }
 }
@@ -1,3 +1,5 @@
+use std::fmt;
+
 use instant::{Duration, Instant};
 
 use all_is_cubes::math::NotNan;
@@ -11,32 +13,49 @@ use all_is_cubes::universe::Universe;
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub struct FrameClock {
     last_absolute_time: Option<Instant>,
+    /// Real time between steps.
+    step_length: Duration,
     /// Whether there was a step and we should therefore draw a frame.
     /// TODO: This might go away in favor of actual dirty-notifications.
     render_dirty: bool,
     accumulated_step_time: Duration,
 
+    time_scale: TimeScale,
+    /// Game time, in quarter steps, which has passed but not yet been taken by
+    /// [`FrameClock::take_game_steps()`].
+    accumulated_game_quarter_steps: u32,
+
     draw_fps_counter: FpsCounter,
 }
 
 impl FrameClock {
-    const STEP_LENGTH_MICROS: u64 = 1_000_000 / 60;
-    const STEP_LENGTH: Duration = Duration::from_micros(Self::STEP_LENGTH_MICROS);
+    /// Step length used by [`FrameClock::new()`].
+    const STEP_LENGTH: Duration = Duration::from_micros(1_000_000 / 60);
     /// Number of steps per frame to permit.
-    /// This sets how low the frame rate can go below [`STEP_LENGTH`] before game time
+    /// This sets how low the frame rate can go below the step length before game time
     /// slows down.
     pub(crate) const CATCH_UP_STEPS: u8 = 2;
-    const ACCUMULATOR_CAP: Duration =
-        Duration::from_micros(Self::STEP_LENGTH_MICROS * Self::CATCH_UP_STEPS as u64);
 
-    /// Constructs a new [`FrameClock`].
+    /// Constructs a new [`FrameClock`] which steps 60 times per second.
     ///
     /// This operation is independent of the system clock.
     pub fn new() -> Self {
+        Self::with_step_length(Self::STEP_LENGTH)
+    }
+
+    /// Constructs a new [`FrameClock`] which steps every `step_length` of real time,
+    /// such as for a server configured with its own tick rate.
+    ///
+    /// Panics if `step_length` is zero.
+    pub fn with_step_length(step_length: Duration) -> Self {
+        assert!(!step_length.is_zero(), "step length must not be zero");
         Self {
             last_absolute_time: None,
+            step_length,
             render_dirty: true,
             accumulated_step_time: Duration::ZERO,
+            time_scale: TimeScale::Normal,
+            accumulated_game_quarter_steps: 0,
             draw_fps_counter: FpsCounter::default(),
         }
     }
@@ -84,7 +103,7 @@ impl FrameClock {
     /// [`FrameClock::advance_to()`] must have previously been called to give an absolute
     /// time reference.
     pub fn next_step_or_draw_time(&self) -> Option<Instant> {
-        Some(self.last_absolute_time? + Self::STEP_LENGTH)
+        Some(self.last_absolute_time? + self.step_length)
     }
 
     /// Indicates whether a new frame should be drawn, given the amount of time that this
@@ -108,20 +127,56 @@ impl FrameClock {
     /// When a step *is* performd, [`FrameClock::did_step`] must be called; otherwise, this
     /// will always return true.
     pub fn should_step(&self) -> bool {
-        self.accumulated_step_time >= Self::STEP_LENGTH
+        self.accumulated_step_time >= self.step_length
     }
 
     /// Informs the [`FrameClock`] that a step was just performed.
     pub fn did_step(&mut self) {
-        self.accumulated_step_time -= Self::STEP_LENGTH;
+        self.accumulated_step_time -= self.step_length;
         self.render_dirty = true;
+        self.accumulated_game_quarter_steps += self.time_scale.quarter_steps();
+    }
+
+    /// Returns how many times the game universe should be stepped, with ticks of
+    /// [`FrameClock::tick()`], for the steps reported by [`FrameClock::did_step()`]
+    /// since the last call.
+    ///
+    /// This is one per step when the [`TimeScale`] is [`TimeScale::Normal`], and more
+    /// or fewer otherwise; the length of each tick is always the same, so that the
+    /// outcome of the simulation does not depend on the time scale.
+    pub fn take_game_steps(&mut self) -> u32 {
+        let steps = self.accumulated_game_quarter_steps / 4;
+        self.accumulated_game_quarter_steps %= 4;
+        steps
+    }
+
+    /// Returns how fast game time passes relative to real time.
+    pub fn time_scale(&self) -> TimeScale {
+        self.time_scale
+    }
+
+    /// Sets how fast game time passes relative to real time.
+    pub fn set_time_scale(&mut self, time_scale: TimeScale) {
+        self.time_scale = time_scale;
     }
 
     /// The timestep value that should be passed to [`Universe::step()`]
     /// when stepping in response to [`FrameClock::should_step`] returning true.
     #[must_use] // avoid confusion with side-effecting methods
     pub fn tick(&self) -> Tick {
-        Tick::from_duration(Self::STEP_LENGTH)
+        Tick::from_duration(self.step_length)
+    }
+
+    /// Returns how far the clock has advanced past the most recent step, as a fraction of
+    /// the step length between 0 and 1.
+    ///
+    /// This should be used when drawing a frame to interpolate between the states before
+    /// and after the most recent step (as [`Character::view_interpolated()`] does), so
+    /// that motion appears smooth even when frames are not drawn in sync with steps.
+    ///
+    /// [`Character::view_interpolated()`]: all_is_cubes::character::Character::view_interpolated
+    pub fn step_interpolation(&self) -> f64 {
+        (self.accumulated_step_time.as_secs_f64() / self.step_length.as_secs_f64()).min(1.0)
     }
 
     #[doc(hidden)] // TODO: Decide whether we want FpsCounter in our public API
@@ -130,8 +185,9 @@ impl FrameClock {
     }
 
     fn cap_step_time(&mut self) {
-        if self.accumulated_step_time > Self::ACCUMULATOR_CAP {
-            self.accumulated_step_time = Self::ACCUMULATOR_CAP;
+        let cap = self.step_length * u32::from(Self::CATCH_UP_STEPS);
+        if self.accumulated_step_time > cap {
+            self.accumulated_step_time = cap;
         }
     }
 }
@@ -142,6 +198,67 @@ impl Default for FrameClock {
     }
 }
 
+/// How fast game time passes relative to real time; see [`FrameClock::time_scale()`].
+///
+/// [`TimeScale::Stopped`] differs from pausing the [`Session`](super::Session) in that
+/// the game controls remain available, so the user may look around the frozen world.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+#[non_exhaustive]
+pub enum TimeScale {
+    /// No game time passes.
+    Stopped,
+    /// Game time passes at one quarter of real time.
+    Slow,
+    /// Game time passes at the same rate as real time.
+    Normal,
+    /// Game time passes at four times real time.
+    Fast,
+}
+
+impl TimeScale {
+    /// Returns the next slower time scale, or `self` if it is the slowest.
+    #[must_use]
+    pub fn slower(self) -> Self {
+        match self {
+            Self::Stopped | Self::Slow => Self::Stopped,
+            Self::Normal => Self::Slow,
+            Self::Fast => Self::Normal,
+        }
+    }
+
+    /// Returns the next faster time scale, or `self` if it is the fastest.
+    #[must_use]
+    pub fn faster(self) -> Self {
+        match self {
+            Self::Stopped => Self::Slow,
+            Self::Slow => Self::Normal,
+            Self::Normal | Self::Fast => Self::Fast,
+        }
+    }
+
+    /// Returns the ratio of game time to real time.
+    pub fn multiplier(self) -> f64 {
+        f64::from(self.quarter_steps()) / 4.0
+    }
+
+    /// Game time per real step, in quarters of a step, so that it can be accumulated
+    /// exactly.
+    fn quarter_steps(self) -> u32 {
+        match self {
+            Self::Stopped => 0,
+            Self::Slow => 1,
+            Self::Normal => 4,
+            Self::Fast => 16,
+        }
+    }
+}
+
+impl fmt::Display for TimeScale {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}×", self.multiplier())
+    }
+}
+
 /// Counts frame time / frames-per-second against real time as defined by [`Instant::now`].
 #[derive(Clone, Debug, Default, Eq, PartialEq)]
 #[doc(hidden)] // TODO: Decide whether we want FpsCounter in our public API
@@ -190,3 +307,70 @@ impl FpsCounter {
         self.period_seconds().recip()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn game_steps_per_real_step(time_scale: TimeScale, real_steps: u32) -> Vec<u32> {
+        let mut clock = FrameClock::new();
+        clock.set_time_scale(time_scale);
+        (0..real_steps)
+            .map(|_| {
+                clock.advance_by(FrameClock::STEP_LENGTH);
+                assert!(clock.should_step());
+                clock.did_step();
+                assert!(!clock.should_step());
+                clock.take_game_steps()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn time_scale_changes_number_of_game_steps() {
+        assert_eq!(
+            game_steps_per_real_step(TimeScale::Stopped, 4),
+            [0, 0, 0, 0]
+        );
+        assert_eq!(
+            game_steps_per_real_step(TimeScale::Slow, 8),
+            [0, 0, 0, 1, 0, 0, 0, 1]
+        );
+        assert_eq!(game_steps_per_real_step(TimeScale::Normal, 4), [1, 1, 1, 1]);
+        assert_eq!(game_steps_per_real_step(TimeScale::Fast, 2), [4, 4]);
+    }
+
+    #[test]
+    fn custom_step_length() {
+        let step_length = Duration::from_millis(100);
+        let mut clock = FrameClock::with_step_length(step_length);
+        assert_eq!(clock.tick(), Tick::from_duration(step_length));
+        clock.advance_by(step_length / 2);
+        assert!(!clock.should_step());
+        assert_eq!(clock.step_interpolation(), 0.5);
+        clock.advance_by(step_length / 2);
+        assert!(clock.should_step());
+
+        // Catching up is limited to the same number of steps as usual.
+        clock.advance_by(step_length * 10);
+        clock.did_step();
+        clock.did_step();
+        assert!(!clock.should_step());
+    }
+
+    #[test]
+    fn time_scale_display() {
+        assert_eq!(
+            [
+                TimeScale::Stopped,
+                TimeScale::Slow,
+                TimeScale::Normal,
+                TimeScale::Fast
+            ]
+            .map(|t| t.to_string()),
+            ["0×", "0.25×", "1×", "4×"]
+        );
+        assert_eq!(TimeScale::Stopped.slower(), TimeScale::Stopped);
+        assert_eq!(TimeScale::Normal.faster(), TimeScale::Fast);
+    }
+}
@@ -0,0 +1,389 @@
+//! Translation of user interface text into the user's choice of [`Language`].
+//!
+//! Text is looked up by key in a [`Catalog`] of messages for the language. The
+//! catalogs are stored in the `i18n/` directory of this crate's source, one file per
+//! language, using a subset of the syntax of [Project Fluent](https://projectfluent.org/):
+//!
+//! ```ftl
+//! # Comment
+//! message-key = Text of the message, which may include { $variable } placeables
+//!     and continue on indented lines.
+//! ```
+//!
+//! Messages missing from a catalog are taken from the English catalog.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+
+use once_cell::sync::Lazy;
+
+/// A language which the user interface can be displayed in.
+///
+/// The [`Display`](fmt::Display) form of a language is its name in that language.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+#[non_exhaustive]
+pub enum Language {
+    /// English (`en`).
+    #[default]
+    English,
+    /// German (`de`).
+    German,
+}
+
+impl Language {
+    /// All languages, in the order they should be offered to the user.
+    pub const ALL: [Language; 2] = [Language::English, Language::German];
+
+    /// Returns the ISO 639-1 code of this language, such as `en`.
+    pub fn code(self) -> &'static str {
+        match self {
+            Language::English => "en",
+            Language::German => "de",
+        }
+    }
+
+    /// Returns the catalog of messages in this language.
+    pub fn catalog(self) -> &'static Catalog {
+        static ENGLISH: Lazy<Catalog> =
+            Lazy::new(|| Catalog::load(Language::English, include_str!("i18n/en.ftl")));
+        static GERMAN: Lazy<Catalog> =
+            Lazy::new(|| Catalog::load(Language::German, include_str!("i18n/de.ftl")));
+
+        match self {
+            Language::English => &ENGLISH,
+            Language::German => &GERMAN,
+        }
+    }
+}
+
+impl fmt::Display for Language {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Language::English => "English",
+            Language::German => "Deutsch",
+        }
+        .fmt(f)
+    }
+}
+
+/// Parses a language tag such as `de` or `de-AT`; only the language subtag is used.
+impl FromStr for Language {
+    type Err = UnknownLanguage;
+
+    fn from_str(tag: &str) -> Result<Self, Self::Err> {
+        let code = tag.split(['-', '_', '.']).next().unwrap_or_default();
+        Language::ALL
+            .into_iter()
+            .find(|language| language.code().eq_ignore_ascii_case(code))
+            .ok_or_else(|| UnknownLanguage(tag.to_owned()))
+    }
+}
+
+/// Error from parsing a [`Language`] that is not one of [`Language::ALL`].
+#[derive(Clone, Debug, Eq, PartialEq, thiserror::Error)]
+#[error("unknown or unsupported language {0:?}")]
+pub struct UnknownLanguage(String);
+
+/// The messages making up the user interface text in one [`Language`].
+///
+/// Obtain catalogs using [`Language::catalog()`].
+#[derive(Debug)]
+pub struct Catalog {
+    language: Language,
+    messages: HashMap<String, String>,
+}
+
+impl Catalog {
+    /// Parses the catalog file for `language`, panicking on syntax errors since the
+    /// files are part of the program.
+    fn load(language: Language, source: &str) -> Self {
+        match parse(source) {
+            Ok(messages) => Self { language, messages },
+            Err(error) => panic!("syntax error in {}.ftl: {error}", language.code()),
+        }
+    }
+
+    /// Returns the language of this catalog.
+    pub fn language(&self) -> Language {
+        self.language
+    }
+
+    /// Returns the text of the message with the given key.
+    ///
+    /// If the message is not in this catalog, the English text is returned instead,
+    /// and if it is not in that either, the key itself.
+    ///
+    /// Placeables in the text are left as they are; use [`Catalog::format()`] for
+    /// messages which have them.
+    pub fn get<'a>(&'a self, key: &'a str) -> &'a str {
+        if let Some(text) = self.messages.get(key) {
+            return text;
+        }
+        match Language::English.catalog().messages.get(key) {
+            Some(text) => text,
+            None => {
+                log::warn!("missing message {key:?}");
+                key
+            }
+        }
+    }
+
+    /// Returns the translation of `name`, the English display name of a block or tool from
+    /// the game world (its [`BlockAttributes::display_name`]), or `name` itself if there is
+    /// none.
+    ///
+    /// The translation is the message whose key is `name-` followed by `name` in
+    /// lowercase, with each run of characters other than letters and digits replaced by
+    /// `-`; for example, the translation of `Push/Pull` is `name-push-pull`.
+    ///
+    /// [`BlockAttributes::display_name`]: all_is_cubes::block::BlockAttributes::display_name
+    pub fn display_name<'a>(&'a self, name: &'a str) -> &'a str {
+        self.messages
+            .get(&display_name_key(name))
+            .map_or(name, String::as_str)
+    }
+
+    /// Returns the text of the message with the given key, with each `{ $name }`
+    /// placeable replaced by the value of that name in `args`.
+    ///
+    /// ```
+    /// use all_is_cubes_ui::i18n::Language;
+    ///
+    /// assert_eq!(
+    ///     Language::German.catalog().format("message-time-scale", &[("scale", &2)]),
+    ///     "Zeitfaktor: 2",
+    /// );
+    /// ```
+    pub fn format(&self, key: &str, args: &[(&str, &dyn fmt::Display)]) -> String {
+        let mut output = String::new();
+        let mut rest = self.get(key);
+        while let Some((before, after)) = rest.split_once('{') {
+            output += before;
+            // Placeables were checked when the catalog was parsed.
+            let (placeable, after) = after.split_once('}').unwrap_or((after, ""));
+            let name = placeable.trim().trim_start_matches('$');
+            match args.iter().find(|&&(arg_name, _)| arg_name == name) {
+                Some((_, value)) => output += &value.to_string(),
+                // Same as Fluent's presentation of a missing variable.
+                None => output += &format!("{{${name}}}"),
+            }
+            rest = after;
+        }
+        output += rest;
+        output
+    }
+}
+
+/// Returns the message key of the translation of a display name, as described in
+/// [`Catalog::display_name()`].
+fn display_name_key(name: &str) -> String {
+    let mut key = String::from("name");
+    for word in name
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+    {
+        key.push('-');
+        key.extend(word.chars().flat_map(char::to_lowercase));
+    }
+    key
+}
+
+/// Parses the contents of a catalog file into a map from message key to text.
+fn parse(source: &str) -> Result<HashMap<String, String>, String> {
+    let mut messages: HashMap<String, String> = HashMap::new();
+    // Key of the message which indented lines continue.
+    let mut current: Option<&str> = None;
+    for (index, line) in source.lines().enumerate() {
+        let line_number = index + 1;
+        let trimmed = line.trim();
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            current = None;
+            continue;
+        }
+        check_placeables(trimmed).map_err(|e| format!("line {line_number}: {e}"))?;
+
+        if line.starts_with(char::is_whitespace) {
+            // Continuation of the previous message.
+            let Some(text) = current.and_then(|key| messages.get_mut(key)) else {
+                return Err(format!(
+                    "line {line_number}: indented line is not part of a message"
+                ));
+            };
+            if !text.is_empty() {
+                text.push('\n');
+            }
+            text.push_str(trimmed);
+        } else {
+            let Some((key, text)) = line.split_once('=') else {
+                return Err(format!("line {line_number}: expected `key = text`"));
+            };
+            let key = key.trim();
+            if !is_valid_key(key) {
+                return Err(format!("line {line_number}: invalid message key {key:?}"));
+            }
+            if messages.contains_key(key) {
+                return Err(format!("line {line_number}: duplicate message key {key:?}"));
+            }
+            messages.insert(key.to_owned(), text.trim().to_owned());
+            current = Some(key);
+        }
+    }
+    Ok(messages)
+}
+
+/// Message keys are ASCII identifiers which may contain `-` and `_`.
+fn is_valid_key(key: &str) -> bool {
+    key.starts_with(|c: char| c.is_ascii_alphabetic())
+        && key
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+/// Checks that every `{` in `text` begins a placeable of the form `{ $name }`.
+fn check_placeables(text: &str) -> Result<(), &'static str> {
+    let mut rest = text;
+    while let Some((_, after)) = rest.split_once('{') {
+        let Some((placeable, after)) = after.split_once('}') else {
+            return Err("unclosed placeable");
+        };
+        match placeable.trim().strip_prefix('$') {
+            Some(name) if is_valid_key(name) => {}
+            _ => return Err("placeables must be of the form `{ $name }`"),
+        }
+        rest = after;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeSet;
+
+    /// Returns the names of the variables used by a message.
+    fn variables(text: &str) -> BTreeSet<&str> {
+        text.split('{')
+            .skip(1)
+            .map(|s| s.split('}').next().unwrap().trim().trim_start_matches('$'))
+            .collect()
+    }
+
+    /// Every catalog has exactly the messages the English catalog has, with the
+    /// same variables.
+    #[test]
+    fn catalogs_are_complete() {
+        let english = &Language::English.catalog().messages;
+        for language in Language::ALL {
+            let catalog = language.catalog();
+            assert_eq!(catalog.language(), language);
+            let mut missing: Vec<&str> = english
+                .keys()
+                .filter(|key| !catalog.messages.contains_key(*key))
+                .map(String::as_str)
+                .collect();
+            missing.sort_unstable();
+            assert_eq!(missing, Vec::<&str>::new(), "missing from {language:?}");
+            for (key, text) in &catalog.messages {
+                let english_text = english
+                    .get(key)
+                    .unwrap_or_else(|| panic!("{language:?} has extra message {key:?}"));
+                assert_eq!(
+                    variables(text),
+                    variables(english_text),
+                    "variables of {key:?} in {language:?}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn parse_syntax() {
+        let messages = parse(
+            "# comment\n\
+            a = Hello, { $name }!\n\
+            \n\
+            b =\n    \
+                first\n    \
+                second\n\
+            c-d_2 = x\n",
+        )
+        .unwrap();
+        assert_eq!(messages["a"], "Hello, { $name }!");
+        assert_eq!(messages["b"], "first\nsecond");
+        assert_eq!(messages["c-d_2"], "x");
+        assert_eq!(messages.len(), 3);
+    }
+
+    #[test]
+    fn parse_errors() {
+        assert_eq!(
+            parse("a = b\nc\n"),
+            Err("line 2: expected `key = text`".into())
+        );
+        assert_eq!(
+            parse("  continued\n"),
+            Err("line 1: indented line is not part of a message".into())
+        );
+        assert_eq!(
+            parse("a = b\na = c\n"),
+            Err("line 2: duplicate message key \"a\"".into())
+        );
+        assert_eq!(
+            parse("1a = b\n"),
+            Err("line 1: invalid message key \"1a\"".into())
+        );
+        assert_eq!(
+            parse("a = { b }\n"),
+            Err("line 1: placeables must be of the form `{ $name }`".into())
+        );
+        assert_eq!(
+            parse("a = { $b\n"),
+            Err("line 1: unclosed placeable".into())
+        );
+    }
+
+    #[test]
+    fn format_args() {
+        let catalog = Catalog {
+            language: Language::English,
+            messages: parse("a = { $x } and {$y}, { $z }.").unwrap(),
+        };
+        assert_eq!(
+            catalog.format("a", &[("y", &"why"), ("x", &1)]),
+            "1 and why, {$z}."
+        );
+    }
+
+    #[test]
+    fn display_names() {
+        assert_eq!(display_name_key("Push/Pull"), "name-push-pull");
+        assert_eq!(display_name_key("Jetpack (off)"), "name-jetpack-off");
+        let german = Language::German.catalog();
+        assert_eq!(german.display_name("Push/Pull"), "Schieben/Ziehen");
+        assert_eq!(german.display_name("Not Translated"), "Not Translated");
+        assert_eq!(german.display_name(""), "");
+    }
+
+    #[test]
+    fn get_falls_back() {
+        let catalog = Catalog {
+            language: Language::German,
+            messages: HashMap::new(),
+        };
+        assert_eq!(catalog.get("page-paused"), "Paused");
+        assert_eq!(catalog.get("nonexistent"), "nonexistent");
+    }
+
+    #[test]
+    fn language_from_str() {
+        assert_eq!("en".parse(), Ok(Language::English));
+        assert_eq!("DE".parse(), Ok(Language::German));
+        assert_eq!("de-AT".parse(), Ok(Language::German));
+        assert_eq!("de_DE.UTF-8".parse(), Ok(Language::German));
+        assert_eq!(
+            "xx".parse::<Language>(),
+            Err(UnknownLanguage("xx".to_owned()))
+        );
+    }
+}
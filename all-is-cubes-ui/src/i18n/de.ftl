@@ -0,0 +1,70 @@
+# German (de) user interface text. See `en.ftl` for the format.
+
+## Page headings
+
+page-paused = Pausiert
+page-options = Optionen
+page-controls = Steuerung
+page-about = Über
+page-statistics = Statistik
+page-load-failed = Welt konnte nicht geladen werden
+
+## Button labels
+
+button-back = Zurück
+button-about = Über
+button-pause = Pause
+button-options = Optionen
+button-statistics = Statistik
+button-retry = Erneut versuchen
+button-mouselook = Mausblick
+button-fullscreen = Vollbild
+button-antialiasing = Kantenglättung
+button-debug-info-text = Debug: Infotext
+button-debug-chunk-boxes = Debug: Chunk-Rahmen
+button-debug-behaviors = Debug: Verhalten
+button-debug-collision-boxes = Debug: Kollisionsboxen
+button-debug-light-rays = Debug: Lichtstrahlen am Cursor
+button-high-contrast = Hoher Kontrast
+
+## Names of other user interface blocks
+
+block-crosshair = Fadenkreuz
+block-toolbar-slot-frame = Werkzeugleisten-Feldrahmen
+block-toolbar-selected = Ausgewählt
+block-dialog-background = Dialoghintergrund
+
+## Statistics page
+
+stats-none = Für diese Figur werden keine Statistiken erfasst.
+stats-time-played = Spielzeit
+stats-distance-traveled = Zurückgelegte Strecke
+stats-distance-value = { $meters } m
+stats-blocks-placed = Platzierte Blöcke
+stats-blocks-removed = Entfernte Blöcke
+stats-most-placed = Am häufigsten platziert:
+stats-unnamed-block = (unbenannt)
+
+## Messages
+
+message-time-scale = Zeitfaktor: { $scale }
+
+## Errors from using tools
+
+tool-error-no-tool = kein Werkzeug
+tool-error-not-usable = nicht anwendbar
+tool-error-obstacle = etwas ist im Weg
+tool-error-protected = dieser Bereich ist geschützt
+tool-error-nothing-selected = nichts ist ausgewählt
+tool-error-space-ref = Fehler beim Zugriff auf den Raum: { $detail }
+tool-error-internal = unerwarteter Fehler: { $detail }
+
+## Display names of blocks and tools from the game world
+
+name-activate = Aktivieren
+name-delete-block = Block löschen
+name-copy-block-from-cursor = Block am Cursor kopieren
+name-edit-block = Block bearbeiten
+name-push-pull = Schieben/Ziehen
+name-jetpack-on = Jetpack (an)
+name-jetpack-off = Jetpack (aus)
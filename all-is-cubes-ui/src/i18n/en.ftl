@@ -0,0 +1,77 @@
+# English (en) user interface text.
+#
+# This file uses the syntax of Project Fluent <https://projectfluent.org/>, limited to
+# what `all_is_cubes_ui::i18n` understands: simple messages, optionally continued on
+# indented lines, with `{ $variable }` placeables.
+
+## Page headings
+
+page-paused = Paused
+page-options = Options
+page-controls = Controls
+page-about = About
+page-statistics = Statistics
+page-load-failed = Could Not Load World
+
+## Button labels
+
+button-back = Back
+button-about = About
+button-pause = Pause
+button-options = Options
+button-statistics = Statistics
+button-retry = Retry
+button-mouselook = Mouselook
+button-fullscreen = Fullscreen
+button-antialiasing = Antialiasing
+button-debug-info-text = Debug: Info Text
+button-debug-chunk-boxes = Debug: Chunk Boxes
+button-debug-behaviors = Debug: Behaviors
+button-debug-collision-boxes = Debug: Collision Boxes
+button-debug-light-rays = Debug: Light Rays at Cursor
+button-high-contrast = High Contrast
+
+## Names of other user interface blocks
+
+block-crosshair = Crosshair
+block-toolbar-slot-frame = Toolbar Slot Frame
+block-toolbar-selected = Selected
+block-dialog-background = Dialog Background
+
+## Statistics page
+
+stats-none = No statistics are being recorded for this character.
+stats-time-played = Time played
+stats-distance-traveled = Distance traveled
+stats-distance-value = { $meters } m
+stats-blocks-placed = Blocks placed
+stats-blocks-removed = Blocks removed
+stats-most-placed = Most placed:
+stats-unnamed-block = (unnamed)
+
+## Messages
+
+message-time-scale = Time scale: { $scale }
+
+## Errors from using tools
+
+tool-error-no-tool = no tool
+tool-error-not-usable = does not apply
+tool-error-obstacle = there's something in the way
+tool-error-protected = this area is protected
+tool-error-nothing-selected = nothing is selected
+tool-error-space-ref = error accessing space: { $detail }
+tool-error-internal = unexpected error: { $detail }
+
+## Display names of blocks and tools from the game world
+#
+# The key of each is `name-` followed by the English name in lowercase, with
+# punctuation and spaces replaced by `-`.
+
+name-activate = Activate
+name-delete-block = Delete Block
+name-copy-block-from-cursor = Copy Block from Cursor
+name-edit-block = Edit Block
+name-push-pull = Push/Pull
+name-jetpack-on = Jetpack (on)
+name-jetpack-off = Jetpack (off)
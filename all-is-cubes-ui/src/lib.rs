@@ -50,5 +50,6 @@
 // TODO: #![warn(missing_docs)]
 
 pub mod apps;
+pub mod i18n;
 pub mod logo;
 pub mod vui;
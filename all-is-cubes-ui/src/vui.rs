@@ -6,26 +6,34 @@
 use std::sync::mpsc::TryRecvError;
 use std::sync::{mpsc, Arc, Mutex};
 
-use all_is_cubes::camera::{FogOption, GraphicsOptions, UiViewState, ViewTransform, Viewport};
+use all_is_cubes::camera::{
+    FogOption, GraphicsOptions, UiPaletteOption, UiViewState, ViewTransform, Viewport,
+};
 use all_is_cubes::cgmath::{Angle as _, Decomposed, Deg, One, Transform, Vector3};
-use all_is_cubes::character::{Character, Cursor};
+use all_is_cubes::character::{Character, Cursor, PlayerStats};
+use all_is_cubes::event::GameEvent;
 use all_is_cubes::inv::{EphemeralOpaque, Tool, ToolError, ToolInput};
 use all_is_cubes::listen::{DirtyFlag, ListenableCell, ListenableSource, Notifier};
 use all_is_cubes::math::FreeCoordinate;
 use all_is_cubes::math::NotNan;
-use all_is_cubes::space::Space;
-use all_is_cubes::time::{Instant, Tick};
+use all_is_cubes::space::{Space, Waypoint};
+use all_is_cubes::time::{Duration, Instant, Tick};
 use all_is_cubes::transaction::{self, Transaction};
 use all_is_cubes::universe::{URef, Universe, UniverseStepInfo};
-use all_is_cubes::util::YieldProgress;
+use all_is_cubes::util::{ErrorChain, YieldProgress};
 
-use crate::apps::{ControlMessage, FullscreenSetter, FullscreenState, InputProcessor};
+use crate::apps::{
+    ControlMessage, FullscreenSetter, FullscreenState, InputProcessor, UniverseLoadError,
+};
+use crate::i18n::Language;
 use crate::vui::pages::{PageInst, UiSize};
 use crate::vui::widgets::TooltipState;
 
 #[doc(hidden)] // public for use by test-renderers only
 pub mod blocks;
 pub(crate) use blocks::UiBlocks;
+mod hand;
+use hand::Hand;
 mod hud;
 use hud::*;
 mod layout;
@@ -52,7 +60,16 @@ pub(crate) struct Vui {
 
     changed_viewport: DirtyFlag,
     viewport_source: ListenableSource<Viewport>,
-    /// Size computed from `viewport_source` and compared with `PageInst`.
+    changed_language: DirtyFlag,
+    language_source: ListenableSource<Language>,
+    /// Notices changes to `hud_inputs.graphics_options`, which affect the UI's size and
+    /// colors.
+    changed_graphics_options: DirtyFlag,
+    /// Notices changes to `hud_inputs.hotbar`, whose length affects the toolbar's layout.
+    changed_hotbar: DirtyFlag,
+    /// Length of the hotbar the pages were built with.
+    hotbar_len: usize,
+    /// Size computed from `viewport_source` and `ui_scale`, and compared with `PageInst`.
     last_ui_size: UiSize,
     #[allow(dead_code)] // TODO: probably going to need this for more dynamic UIs
     hud_inputs: HudInputs,
@@ -63,20 +80,28 @@ pub(crate) struct Vui {
     options_page: PageInst,
     /// Whatever [`VuiPageState::Dump`] contained.
     dump_page: PageInst,
+    /// The content of the page most recently opened by [`Self::show_load_error()`],
+    /// so that it can be replaced or closed.
+    load_error_page: Option<EphemeralOpaque<WidgetTree>>,
 
     /// Receiving internal messages from widgets for controlling the UI itself
     /// (changing `state`, etc).
     control_channel: mpsc::Receiver<VuiMessage>,
     character_source: ListenableSource<Option<URef<Character>>>,
     changed_character: DirtyFlag,
+    waypoint_source: ListenableSource<Vec<URef<Waypoint>>>,
     tooltip_state: Arc<Mutex<TooltipState>>,
+    performance_graphs: PerformanceGraphs,
     /// Messages from session to UI that don't fit as [`ListenableSource`] changes.
     cue_channel: CueNotifier,
+    /// The character's selected tool, drawn between the world and the UI.
+    hand: Hand,
 }
 
 impl Vui {
     /// `input_processor` is the `InputProcessor` whose state may be reflected on the HUD.
     /// `character_source` reports the `Character` whose inventory should be displayed.
+    /// `waypoint_source` reports the [`Waypoint`]s which the HUD should point out.
     ///
     /// TODO: Reduce coupling, perhaps by passing in a separate struct with just the listenable
     /// elements.
@@ -87,8 +112,10 @@ impl Vui {
     pub(crate) async fn new(
         input_processor: &InputProcessor,
         character_source: ListenableSource<Option<URef<Character>>>,
+        waypoint_source: ListenableSource<Vec<URef<Waypoint>>>,
         paused: ListenableSource<bool>,
         graphics_options: ListenableSource<GraphicsOptions>,
+        language_source: ListenableSource<Language>,
         app_control_channel: mpsc::SyncSender<ControlMessage>,
         viewport_source: ListenableSource<Viewport>,
         fullscreen_source: ListenableSource<FullscreenState>,
@@ -96,19 +123,40 @@ impl Vui {
     ) -> Self {
         let mut universe = Universe::new();
         // TODO: take YieldProgress as a parameter
-        let hud_blocks = Arc::new(HudBlocks::new(&mut universe, YieldProgress::noop()).await);
+        let catalog = language_source.snapshot().catalog();
+        let ui_palette = graphics_options.get().ui_palette.clone();
+        let hud_blocks = Arc::new(
+            HudBlocks::new(&mut universe, catalog, &ui_palette, YieldProgress::noop()).await,
+        );
 
         let (control_send, control_recv) = mpsc::sync_channel(100);
         let state = ListenableCell::new(VuiPageState::Hud);
 
         let tooltip_state = Arc::<Mutex<TooltipState>>::default();
+        let performance_graphs = PerformanceGraphs::new();
         let cue_channel: CueNotifier = Arc::new(Notifier::new());
+        let hand = Hand::new(
+            &mut universe,
+            character_source.clone(),
+            graphics_options.clone(),
+            viewport_source.clone(),
+        );
 
         // TODO: terrible mess of tightly coupled parameters
         let changed_viewport = DirtyFlag::listening(false, &viewport_source);
-        let ui_size = UiSize::new(viewport_source.snapshot());
+        let changed_language = DirtyFlag::listening(false, &language_source);
+        let changed_graphics_options = DirtyFlag::listening(false, &graphics_options);
+        let hotbar = input_processor.hotbar();
+        let changed_hotbar = DirtyFlag::listening(false, &hotbar);
+        let hotbar_len = hotbar.get().len();
+        let ui_size = UiSize::new(
+            viewport_source.snapshot(),
+            graphics_options.get().ui_scale.into_inner(),
+        );
         let hud_inputs = HudInputs {
             hud_blocks,
+            catalog,
+            ui_palette,
             cue_channel: cue_channel.clone(),
             vui_control_channel: control_send,
             app_control_channel,
@@ -116,20 +164,10 @@ impl Vui {
             paused,
             page_state: state.as_source(),
             mouselook_mode: input_processor.mouselook_mode(),
+            hotbar,
             fullscreen_mode: fullscreen_source,
             set_fullscreen,
         };
-        let hud_widget_tree = new_hud_widget_tree(
-            character_source.clone(),
-            &hud_inputs,
-            &mut universe,
-            tooltip_state.clone(),
-        );
-
-        let paused_widget_tree = pages::new_paused_widget_tree(&mut universe, &hud_inputs).unwrap();
-        let options_widget_tree =
-            pages::new_options_widget_tree(&mut universe, &hud_inputs).unwrap();
-        let about_widget_tree = pages::new_about_widget_tree(&mut universe, &hud_inputs).unwrap();
 
         let mut new_self = Self {
             universe,
@@ -138,31 +176,95 @@ impl Vui {
 
             changed_viewport,
             viewport_source,
+            changed_language,
+            language_source,
+            changed_graphics_options,
+            changed_hotbar,
+            hotbar_len,
             last_ui_size: ui_size,
             hud_inputs,
 
-            hud_page: PageInst::new(hud_widget_tree),
-            paused_page: PageInst::new(paused_widget_tree),
-            options_page: PageInst::new(options_widget_tree),
-            about_page: PageInst::new(about_widget_tree),
+            // Filled in by create_pages() below.
+            hud_page: PageInst::new(LayoutTree::empty()),
+            paused_page: PageInst::new(LayoutTree::empty()),
+            options_page: PageInst::new(LayoutTree::empty()),
+            about_page: PageInst::new(LayoutTree::empty()),
             dump_page: PageInst::new(LayoutTree::empty()),
+            load_error_page: None,
 
             control_channel: control_recv,
             changed_character: DirtyFlag::listening(false, &character_source),
             character_source,
+            waypoint_source,
             tooltip_state,
+            performance_graphs,
             cue_channel,
+            hand,
         };
+        new_self.create_pages();
         new_self.set_space_from_state();
         new_self
     }
 
+    /// Creates the widget trees of the HUD and of the menu pages, replacing any
+    /// existing ones.
+    fn create_pages(&mut self) {
+        let universe = &mut self.universe;
+        let hud_inputs = &self.hud_inputs;
+        self.hud_page = PageInst::new(new_hud_widget_tree(
+            self.character_source.clone(),
+            self.waypoint_source.clone(),
+            hud_inputs,
+            universe,
+            self.tooltip_state.clone(),
+            &self.performance_graphs,
+        ));
+        self.paused_page =
+            PageInst::new(pages::new_paused_widget_tree(universe, hud_inputs).unwrap());
+        self.options_page =
+            PageInst::new(pages::new_options_widget_tree(universe, hud_inputs).unwrap());
+        self.about_page =
+            PageInst::new(pages::new_about_widget_tree(universe, hud_inputs).unwrap());
+    }
+
+    /// Rebuilds the UI's blocks and pages so that their text is in `language` and their
+    /// colors are from `ui_palette`, if either differs from what they were built with.
+    async fn set_language_and_palette(&mut self, language: Language, ui_palette: UiPaletteOption) {
+        let catalog = language.catalog();
+        if std::ptr::eq(catalog, self.hud_inputs.catalog)
+            && ui_palette == self.hud_inputs.ui_palette
+        {
+            return;
+        }
+
+        let hud_blocks = HudBlocks::new(
+            &mut self.universe,
+            catalog,
+            &ui_palette,
+            YieldProgress::noop(),
+        )
+        .await;
+        self.hud_inputs.hud_blocks = Arc::new(hud_blocks);
+        self.hud_inputs.catalog = catalog;
+        self.hud_inputs.ui_palette = ui_palette;
+
+        self.create_pages();
+        self.current_view.set(UiViewState::default()); // force reconstruction
+        self.set_space_from_state();
+    }
+
     /// The space that should be displayed to the user, drawn on top of the world.
     // TODO: It'd be more encapsulating if we could provide a _read-only_ URef...
     pub fn view(&self) -> ListenableSource<UiViewState> {
         self.current_view.as_source()
     }
 
+    /// The view of the character's selected tool, drawn in front of the world and
+    /// behind [`Self::view()`].
+    pub fn hand_view(&self) -> ListenableSource<UiViewState> {
+        self.hand.view()
+    }
+
     pub(crate) fn set_state(&mut self, state: VuiPageState) {
         self.state.set(state);
 
@@ -262,6 +364,7 @@ impl Vui {
         // Fixed view distance for our layout.
         // TODO: Derive this from HudLayout and also FOV (since FOV determines eye-to-space distance).
         options.view_distance = NotNan::from(100);
+        options.chunk_loading_distance = None;
 
         // clutter
         options.debug_chunk_boxes = false;
@@ -269,7 +372,11 @@ impl Vui {
         options
     }
 
-    pub fn step(&mut self, tick: Tick, deadline: Instant) -> UniverseStepInfo {
+    /// Steps the UI's universe and applies changes to its state and inputs.
+    ///
+    /// This is an async function because changing the language or palette rebuilds the
+    /// UI's blocks. It never waits, so it may be blocked on from a synchronous context.
+    pub(crate) async fn step(&mut self, tick: Tick, deadline: Instant) -> UniverseStepInfo {
         // TODO: This should possibly be the responsibility of the TooltipState itself?
         if self.changed_character.get_and_clear() {
             if let Some(character_ref) = &*self.character_source.get() {
@@ -296,9 +403,29 @@ impl Vui {
             }
         }
 
-        if self.changed_viewport.get_and_clear() {
+        let changed_graphics_options = self.changed_graphics_options.get_and_clear();
+
+        if self.changed_language.get_and_clear() || changed_graphics_options {
+            let ui_palette = self.hud_inputs.graphics_options.get().ui_palette.clone();
+            self.set_language_and_palette(*self.language_source.get(), ui_palette)
+                .await;
+        }
+
+        if self.changed_hotbar.get_and_clear() {
+            let hotbar_len = self.hud_inputs.hotbar.get().len();
+            if hotbar_len != self.hotbar_len {
+                // The toolbar's size is fixed when it is built, so rebuild it.
+                self.hotbar_len = hotbar_len;
+                self.create_pages();
+                self.current_view.set(UiViewState::default()); // force reconstruction
+                self.set_space_from_state();
+            }
+        }
+
+        if self.changed_viewport.get_and_clear() || changed_graphics_options {
             let new_viewport = self.viewport_source.snapshot();
-            let new_size = UiSize::new(new_viewport);
+            let ui_scale = self.hud_inputs.graphics_options.get().ui_scale;
+            let new_size = UiSize::new(new_viewport, ui_scale.into_inner());
             if new_size != self.last_ui_size {
                 self.last_ui_size = new_size;
                 self.current_view.set(UiViewState::default()); // force reconstruction
@@ -318,9 +445,21 @@ impl Vui {
             }
         }
 
+        self.hand.step(tick, &self.hud_inputs.hud_blocks.icons);
+
         self.universe.step(tick, deadline)
     }
 
+    /// Record performance information from stepping the game universe, for display.
+    pub(crate) fn record_step_info(&self, info: &UniverseStepInfo) {
+        self.performance_graphs.record_step_info(info);
+    }
+
+    /// Record the time taken to render a frame, for display.
+    pub(crate) fn record_render_time(&self, time: Duration) {
+        self.performance_graphs.record_render_time(time);
+    }
+
     /// Present the UI visual response to a click (that has already been handled),
     /// either a small indication that a button was pressed or an error message.
     pub fn show_click_result(&self, button: usize, result: Result<(), ToolError>) {
@@ -331,10 +470,116 @@ impl Vui {
         }
     }
 
+    /// Animate the hand to show that a tool was used in the world.
+    pub fn swing_hand(&mut self) {
+        self.hand.swing();
+    }
+
+    /// Briefly show a message to the user.
+    pub fn show_message(&self, text: Arc<str>) {
+        if let Ok(mut state) = self.tooltip_state.lock() {
+            state.set_message(text);
+        }
+    }
+
+    /// Show the text being typed into the command console, or stop showing it if
+    /// [`None`].
+    pub fn show_console_line(&self, line: Option<&str>) {
+        if let Ok(mut state) = self.tooltip_state.lock() {
+            state.set_console_line(line);
+        }
+    }
+
+    /// Show a notification of the event, if it is one the user should see.
+    pub fn show_event(&self, event: &GameEvent) {
+        match event {
+            GameEvent::Chat { .. } | GameEvent::Died { .. } | GameEvent::Error { .. } => {
+                self.show_message(event.to_string().into());
+            }
+            _ => {}
+        }
+    }
+
+    /// Open a page displaying the given player statistics, or saying that there are
+    /// none. The page's back button returns to the current page.
+    pub fn show_stats(&mut self, stats: Option<&PlayerStats>) {
+        let tree = match pages::new_stats_widget_tree(&mut self.universe, &self.hud_inputs, stats) {
+            Ok(tree) => tree,
+            Err(e) => {
+                log::error!("failed to create statistics page: {e}");
+                return;
+            }
+        };
+        let previous = VuiPageState::clone(&self.state.get());
+        self.set_state(VuiPageState::Dump {
+            previous: Box::new(previous),
+            content: EphemeralOpaque::from(Arc::new(tree)),
+        });
+    }
+
+    /// Open a page reporting that a new universe could not be loaded, with a button to
+    /// retry if `retryable`. The page's back button returns to the current page.
+    ///
+    /// If a previous load error page is being shown, it is replaced rather than
+    /// returned to.
+    pub fn show_load_error(&mut self, error: &UniverseLoadError, retryable: bool) {
+        let tree = match pages::new_load_error_widget_tree(
+            &mut self.universe,
+            &self.hud_inputs,
+            ErrorChain(error).to_string(),
+            retryable,
+        ) {
+            Ok(tree) => tree,
+            Err(e) => {
+                log::error!("failed to create load error page: {e}");
+                return;
+            }
+        };
+        let previous = match &*self.state.get() {
+            VuiPageState::Dump { previous, content }
+                if Some(content) == self.load_error_page.as_ref() =>
+            {
+                VuiPageState::clone(previous)
+            }
+            state => state.clone(),
+        };
+        let content = EphemeralOpaque::from(Arc::new(tree));
+        self.load_error_page = Some(content.clone());
+        self.set_state(VuiPageState::Dump {
+            previous: Box::new(previous),
+            content,
+        });
+    }
+
+    /// Close the page opened by [`Self::show_load_error()`], if it is being shown,
+    /// because a universe has since been loaded successfully.
+    pub fn hide_load_error(&mut self) {
+        let current_state = self.state.get();
+        if let VuiPageState::Dump { previous, content } = &*current_state {
+            if Some(content) == self.load_error_page.as_ref() {
+                self.set_state(VuiPageState::clone(previous));
+            }
+        }
+        self.load_error_page = None;
+    }
+
     fn show_tool_error(&self, error: ToolError) {
         // TODO: review text formatting
+        let catalog = self.hud_inputs.catalog;
+        let text = match &error {
+            ToolError::NoTool => catalog.get("tool-error-no-tool").to_owned(),
+            ToolError::NotUsable => catalog.get("tool-error-not-usable").to_owned(),
+            ToolError::Obstacle => catalog.get("tool-error-obstacle").to_owned(),
+            ToolError::Protected => catalog.get("tool-error-protected").to_owned(),
+            ToolError::NothingSelected => catalog.get("tool-error-nothing-selected").to_owned(),
+            ToolError::SpaceRef(e) => catalog.format("tool-error-space-ref", &[("detail", e)]),
+            ToolError::Internal(detail) => {
+                catalog.format("tool-error-internal", &[("detail", detail)])
+            }
+            _ => error.to_string(),
+        };
         if let Ok(mut state) = self.tooltip_state.lock() {
-            state.set_message(error.to_string().into());
+            state.set_message(text.into());
         }
     }
 
@@ -410,7 +655,6 @@ pub(crate) enum VuiPageState {
     /// Arbitrary widgets that have already been computed, and which don't demand
     /// any navigation behavior more complex than “cancellable”. This is to be used for
     /// viewing various reports/dialogs until we have a better idea.
-    #[allow(dead_code)] // TODO: Use this
     Dump {
         previous: Box<VuiPageState>,
         content: EphemeralOpaque<WidgetTree>,
@@ -451,13 +695,19 @@ pub(crate) enum CueMessage {
 mod tests {
     use super::*;
 
-    async fn new_vui_for_test(paused: bool) -> (Vui, mpsc::Receiver<ControlMessage>) {
+    async fn new_vui_for_test(
+        paused: bool,
+        graphics_options: ListenableSource<GraphicsOptions>,
+        language: ListenableSource<Language>,
+    ) -> (Vui, mpsc::Receiver<ControlMessage>) {
         let (cctx, ccrx) = mpsc::sync_channel(1);
         let vui = Vui::new(
             &InputProcessor::new(),
             ListenableSource::constant(None),
+            ListenableSource::constant(Vec::new()),
             ListenableSource::constant(paused),
-            ListenableSource::constant(GraphicsOptions::default()),
+            graphics_options,
+            language,
             cctx,
             ListenableSource::constant(Viewport::ARBITRARY),
             ListenableSource::constant(None),
@@ -469,7 +719,12 @@ mod tests {
 
     #[tokio::test]
     async fn back_pause() {
-        let (mut vui, control_channel) = new_vui_for_test(false).await;
+        let (mut vui, control_channel) = new_vui_for_test(
+            false,
+            ListenableSource::constant(GraphicsOptions::default()),
+            ListenableSource::constant(Language::English),
+        )
+        .await;
         vui.back();
         let msg = control_channel.try_recv().unwrap();
         assert!(matches!(msg, ControlMessage::TogglePause), "{msg:?}");
@@ -478,11 +733,88 @@ mod tests {
 
     #[tokio::test]
     async fn back_unpause() {
-        let (mut vui, control_channel) = new_vui_for_test(true).await;
+        let (mut vui, control_channel) = new_vui_for_test(
+            true,
+            ListenableSource::constant(GraphicsOptions::default()),
+            ListenableSource::constant(Language::English),
+        )
+        .await;
         vui.set_state(VuiPageState::Paused);
         vui.back();
         let msg = control_channel.try_recv().unwrap();
         assert!(matches!(msg, ControlMessage::TogglePause), "{msg:?}");
         assert!(control_channel.try_recv().is_err());
     }
+
+    #[tokio::test]
+    async fn load_error_page_is_replaced_and_hidden() {
+        let (mut vui, _) = new_vui_for_test(
+            false,
+            ListenableSource::constant(GraphicsOptions::default()),
+            ListenableSource::constant(Language::English),
+        )
+        .await;
+        let error = UniverseLoadError::Other("nope".into());
+
+        vui.show_load_error(&error, true);
+        let first_page = VuiPageState::clone(&vui.state.get());
+        vui.show_load_error(&error, true);
+        assert_ne!(*vui.state.get(), first_page);
+        // Going back returns to the HUD, not to the first error page.
+        vui.back();
+        assert_eq!(*vui.state.get(), VuiPageState::Hud);
+
+        vui.show_load_error(&error, true);
+        vui.hide_load_error();
+        assert_eq!(*vui.state.get(), VuiPageState::Hud);
+
+        // Hiding does not close other pages.
+        vui.set_state(VuiPageState::Options);
+        vui.hide_load_error();
+        assert_eq!(*vui.state.get(), VuiPageState::Options);
+    }
+
+    #[tokio::test]
+    async fn language_change_rebuilds_pages() {
+        let language = ListenableCell::new(Language::English);
+        let (mut vui, _) = new_vui_for_test(
+            false,
+            ListenableSource::constant(GraphicsOptions::default()),
+            language.as_source(),
+        )
+        .await;
+        let english_space = vui.view().snapshot().space;
+        assert_eq!(vui.hud_inputs.catalog.language(), Language::English);
+
+        language.set(Language::German);
+        vui.step(Tick::arbitrary(), Instant::now()).await;
+        assert_eq!(vui.hud_inputs.catalog.language(), Language::German);
+        let german_space = vui.view().snapshot().space;
+        assert!(german_space.is_some());
+        assert_ne!(german_space, english_space);
+    }
+
+    #[tokio::test]
+    async fn graphics_options_change_rebuilds_pages() {
+        let graphics_options = ListenableCell::new(GraphicsOptions::default());
+        let (mut vui, _) = new_vui_for_test(
+            false,
+            graphics_options.as_source(),
+            ListenableSource::constant(Language::English),
+        )
+        .await;
+        let standard_space = vui.view().snapshot().space;
+        let standard_size = vui.last_ui_size;
+
+        let mut options = GraphicsOptions::default();
+        options.ui_palette = UiPaletteOption::HighContrast;
+        options.ui_scale = NotNan::new(0.5).unwrap();
+        graphics_options.set(options);
+        vui.step(Tick::arbitrary(), Instant::now()).await;
+        assert_eq!(vui.hud_inputs.ui_palette, UiPaletteOption::HighContrast);
+        assert_ne!(vui.last_ui_size, standard_size);
+        let high_contrast_space = vui.view().snapshot().space;
+        assert!(high_contrast_space.is_some());
+        assert_ne!(high_contrast_space, standard_space);
+    }
 }
@@ -3,6 +3,7 @@ use std::fmt;
 use exhaust::Exhaust;
 
 use all_is_cubes::block::{Block, Resolution::*, AIR};
+use all_is_cubes::camera::UiPaletteOption;
 use all_is_cubes::content::load_image::{default_srgb, space_from_image};
 use all_is_cubes::content::palette;
 use all_is_cubes::drawing::embedded_graphics::mono_font::iso_8859_1 as font;
@@ -11,11 +12,12 @@ use all_is_cubes::drawing::VoxelBrush;
 use all_is_cubes::inv::Tool;
 use all_is_cubes::inv::TOOL_SELECTIONS;
 use all_is_cubes::linking::{BlockModule, BlockProvider};
-use all_is_cubes::math::GridRotation;
+use all_is_cubes::math::{GridRotation, Rgba};
 use all_is_cubes::universe::Universe;
 use all_is_cubes::util::YieldProgress;
 use all_is_cubes::{include_image, rgba_const};
 
+use crate::i18n::Catalog;
 #[cfg(doc)]
 use crate::vui::widgets;
 use crate::vui::widgets::{
@@ -54,6 +56,8 @@ pub enum UiBlocks {
     AboutButtonLabel,
     PauseButtonLabel,
     OptionsButtonLabel,
+    StatsButtonLabel,
+    RetryButtonLabel,
     MouselookButtonLabel,
     FullscreenButtonLabel,
     AntialiasButtonLabel,
@@ -62,6 +66,7 @@ pub enum UiBlocks {
     DebugChunkBoxesButtonLabel,
     DebugCollisionBoxesButtonLabel,
     DebugLightRaysButtonLabel,
+    HighContrastButtonLabel,
 }
 
 impl BlockModule for UiBlocks {
@@ -85,6 +90,8 @@ impl fmt::Display for UiBlocks {
             UiBlocks::AboutButtonLabel => write!(f, "about-button"),
             UiBlocks::PauseButtonLabel => write!(f, "pause-button"),
             UiBlocks::OptionsButtonLabel => write!(f, "options-button"),
+            UiBlocks::StatsButtonLabel => write!(f, "stats-button"),
+            UiBlocks::RetryButtonLabel => write!(f, "retry-button"),
             UiBlocks::MouselookButtonLabel => write!(f, "mouselook-button"),
             UiBlocks::FullscreenButtonLabel => write!(f, "fullscreen-button"),
             UiBlocks::AntialiasButtonLabel => write!(f, "antialias-button"),
@@ -97,16 +104,25 @@ impl fmt::Display for UiBlocks {
                 write!(f, "debug-collision-boxes-button")
             }
             UiBlocks::DebugLightRaysButtonLabel => write!(f, "debug-light-rays-button"),
+            UiBlocks::HighContrastButtonLabel => write!(f, "high-contrast-button"),
         }
     }
 }
 
 impl UiBlocks {
-    pub async fn new(universe: &mut Universe, p: YieldProgress) -> BlockProvider<UiBlocks> {
+    /// Constructs the blocks, with names and labels in the language of `catalog` and
+    /// colors from `ui_palette`.
+    pub async fn new(
+        universe: &mut Universe,
+        catalog: &Catalog,
+        ui_palette: &UiPaletteOption,
+        p: YieldProgress,
+    ) -> BlockProvider<UiBlocks> {
+        let colors = &UiColors::new(ui_palette);
         BlockProvider::new(p, |key| {
             Ok(match key {
                 UiBlocks::Crosshair => Block::builder()
-                    .display_name("Crosshair")
+                    .display_name(catalog.get("block-crosshair"))
                     .voxels_ref(
                         R64, // TODO: get resolution from image file
                         universe.insert_anonymous(space_from_image(
@@ -119,7 +135,7 @@ impl UiBlocks {
 
                 UiBlocks::ToolbarSlotFrame => {
                     Block::builder()
-                        .display_name("Toolbar Slot Frame")
+                        .display_name(catalog.get("block-toolbar-slot-frame"))
                         .voxels_ref(
                             R64,
                             universe.insert_anonymous(space_from_image(
@@ -139,7 +155,7 @@ impl UiBlocks {
                     ToolbarButtonState::Unmapped,
                 ]) => AIR,
                 UiBlocks::ToolbarPointer(buttons) => Block::builder()
-                    .display_name("Selected")
+                    .display_name(catalog.get("block-toolbar-selected"))
                     .voxels_ref(
                         R32, // TODO: get resolution from image file
                         universe.insert_anonymous(space_from_image(
@@ -158,7 +174,7 @@ impl UiBlocks {
 
                 UiBlocks::DialogBackground => {
                     Block::builder()
-                        .display_name("Dialog Background")
+                        .display_name(catalog.get("block-dialog-background"))
                         .voxels_ref(
                             R64, // 16 res × 4 tiles
                             universe.insert_anonymous(space_from_image(
@@ -170,90 +186,126 @@ impl UiBlocks {
                         .build()
                 }
 
-                UiBlocks::ActionButton(state) => state.button_block(universe)?,
-                UiBlocks::ToggleButton(state) => state.button_block(universe)?,
+                UiBlocks::ActionButton(state) => state.button_block(universe, colors)?,
+                UiBlocks::ToggleButton(state) => state.button_block(universe, colors)?,
 
                 UiBlocks::BackButtonLabel => make_button_label_block(
                     universe,
-                    "Back",
+                    catalog.get("button-back"),
                     ButtonIcon::Icon(include_image!("icons/button-back.png")),
+                    colors,
                 )?
                 .build(),
 
                 UiBlocks::AboutButtonLabel => make_button_label_block(
                     universe,
-                    "About",
+                    catalog.get("button-about"),
                     ButtonIcon::Text(&font::FONT_10X20, "?"),
+                    colors,
                 )?
                 .build(),
 
                 UiBlocks::PauseButtonLabel => make_button_label_block(
                     universe,
-                    "Back",
+                    catalog.get("button-pause"),
                     ButtonIcon::Icon(include_image!("icons/button-pause.png")),
+                    colors,
                 )?
                 .build(),
 
                 UiBlocks::OptionsButtonLabel => make_button_label_block(
                     universe,
-                    "Back",
+                    catalog.get("button-options"),
                     ButtonIcon::Icon(include_image!("icons/button-options.png")),
+                    colors,
+                )?
+                .build(),
+
+                UiBlocks::StatsButtonLabel => make_button_label_block(
+                    universe,
+                    catalog.get("button-statistics"),
+                    ButtonIcon::Text(&font::FONT_10X20, "#"),
+                    colors,
+                )?
+                .build(),
+
+                UiBlocks::RetryButtonLabel => make_button_label_block(
+                    universe,
+                    catalog.get("button-retry"),
+                    ButtonIcon::Text(&font::FONT_10X20, "R"),
+                    colors,
                 )?
                 .build(),
 
                 UiBlocks::MouselookButtonLabel => make_button_label_block(
                     universe,
-                    "Mouselook",
+                    catalog.get("button-mouselook"),
                     ButtonIcon::Icon(include_image!("icons/button-mouselook.png")),
+                    colors,
                 )?
                 .build(),
 
                 UiBlocks::FullscreenButtonLabel => make_button_label_block(
                     universe,
-                    "Fullscreen",
+                    catalog.get("button-fullscreen"),
                     ButtonIcon::Icon(include_image!("icons/button-fullscreen.png")),
+                    colors,
                 )?
                 .build(),
 
                 UiBlocks::AntialiasButtonLabel => make_button_label_block(
                     universe,
-                    "Antialiasing",
+                    catalog.get("button-antialiasing"),
                     ButtonIcon::Icon(include_image!("icons/button-antialias.png")),
+                    colors,
                 )?
                 .build(),
 
                 UiBlocks::DebugInfoTextButtonLabel => make_button_label_block(
                     universe,
-                    "Debug: Info Text",
+                    catalog.get("button-debug-info-text"),
                     ButtonIcon::Icon(include_image!("icons/button-debug-info-text.png")),
+                    colors,
                 )?
                 .build(),
 
                 UiBlocks::DebugChunkBoxesButtonLabel => make_button_label_block(
                     universe,
-                    "Debug: Chunk Boxes",
+                    catalog.get("button-debug-chunk-boxes"),
                     ButtonIcon::Icon(include_image!("icons/button-debug-chunk-boxes.png")),
+                    colors,
                 )?
                 .build(),
 
                 UiBlocks::DebugBehaviorsButtonLabel => make_button_label_block(
                     universe,
-                    "Debug: Behaviors",
+                    catalog.get("button-debug-behaviors"),
                     ButtonIcon::Icon(include_image!("icons/button-debug-behaviors.png")),
+                    colors,
                 )?
                 .build(),
 
                 UiBlocks::DebugCollisionBoxesButtonLabel => make_button_label_block(
                     universe,
-                    "Debug: Collision Boxes",
+                    catalog.get("button-debug-collision-boxes"),
                     ButtonIcon::Icon(include_image!("icons/button-debug-collision-boxes.png")),
+                    colors,
                 )?
                 .build(),
 
                 UiBlocks::DebugLightRaysButtonLabel => make_button_label_block(
                     universe,
-                    "Debug: Light Rays at Cursor",
+                    catalog.get("button-debug-light-rays"),
                     ButtonIcon::Icon(include_image!("icons/button-debug-light-rays.png")),
+                    colors,
+                )?
+                .build(),
+
+                UiBlocks::HighContrastButtonLabel => make_button_label_block(
+                    universe,
+                    catalog.get("button-high-contrast"),
+                    ButtonIcon::Text(&font::FONT_10X20, "Aa"),
+                    colors,
                 )?
                 .build(),
             })
@@ -263,6 +315,34 @@ impl UiBlocks {
     }
 }
 
+/// Colors of the VUI's blocks, chosen according to [`UiPaletteOption`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct UiColors {
+    pub button_frame: Rgba,
+    pub button_back: Rgba,
+    pub button_label: Rgba,
+    pub button_activated_back: Rgba,
+}
+
+impl UiColors {
+    pub fn new(ui_palette: &UiPaletteOption) -> Self {
+        match ui_palette {
+            UiPaletteOption::HighContrast => Self {
+                button_frame: palette::HIGH_CONTRAST_BUTTON_FRAME,
+                button_back: palette::HIGH_CONTRAST_BUTTON_BACK,
+                button_label: palette::HIGH_CONTRAST_BUTTON_LABEL,
+                button_activated_back: palette::HIGH_CONTRAST_BUTTON_ACTIVATED_BACK,
+            },
+            _ => Self {
+                button_frame: palette::BUTTON_FRAME,
+                button_back: palette::BUTTON_BACK,
+                button_label: palette::BUTTON_LABEL,
+                button_activated_back: palette::BUTTON_ACTIVATED_BACK,
+            },
+        }
+    }
+}
+
 /// The state of a mouse button's relationship to a toolbar slot.
 #[derive(Copy, Clone, Debug, Eq, Hash, PartialEq, Exhaust)]
 #[doc(hidden)] // public only because UiBlocks is
@@ -303,9 +383,16 @@ impl fmt::Display for ToolbarButtonState {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::i18n::Language;
 
     #[tokio::test]
     async fn blocks_smoke_test() {
-        UiBlocks::new(&mut Universe::new(), YieldProgress::noop()).await;
+        UiBlocks::new(
+            &mut Universe::new(),
+            Language::English.catalog(),
+            &UiPaletteOption::Standard,
+            YieldProgress::noop(),
+        )
+        .await;
     }
 }
@@ -0,0 +1,201 @@
+//! Display of the character's selected tool in front of the world, as if held in hand.
+
+use std::f64::consts::PI;
+
+use all_is_cubes::block::{Block, AIR};
+#[cfg(doc)]
+use all_is_cubes::camera::Layers;
+use all_is_cubes::camera::{GraphicsOptions, UiViewState, ViewTransform, Viewport};
+use all_is_cubes::cgmath::{
+    Angle as _, Basis3, Decomposed, Deg, Rotation as _, Rotation3 as _, Transform as _, Vector3,
+};
+use all_is_cubes::character::Character;
+use all_is_cubes::content::palette;
+use all_is_cubes::inv::{Icons, Slot};
+use all_is_cubes::linking::BlockProvider;
+use all_is_cubes::listen::{DirtyFlag, Listen as _, ListenableCell, ListenableSource};
+use all_is_cubes::math::{FreeCoordinate, GridAab};
+use all_is_cubes::space::{Sky, Space, SpacePhysics};
+use all_is_cubes::time::Tick;
+use all_is_cubes::universe::{URef, Universe};
+
+use crate::vui::Vui;
+
+/// Which of the character's [selections](Character::selected_slots) is shown: that of
+/// the secondary button, which ordinarily is the block being placed.
+const HAND_SELECTION: usize = 1;
+
+/// Duration of the swing animation, in seconds.
+const SWING_SECONDS: f64 = 0.25;
+
+/// Distance from the eye to the center of the shown block.
+const DISTANCE: FreeCoordinate = 8.0;
+
+/// Maintains the [`Layers::hand`] view: a space containing the icon of the character's
+/// selected tool, and a viewpoint which places it in the lower right corner of the
+/// screen and animates it when the tool is used.
+#[derive(Debug)]
+pub(crate) struct Hand {
+    character_source: ListenableSource<Option<URef<Character>>>,
+    graphics_options: ListenableSource<GraphicsOptions>,
+    viewport_source: ListenableSource<Viewport>,
+    /// Set when `graphics_options` or `viewport_source` change.
+    inputs_dirty: DirtyFlag,
+
+    /// Space containing the icon at the origin cube.
+    space: URef<Space>,
+    /// The icon currently in `space`, or [`None`] if there is no tool to show.
+    shown_icon: Option<Block>,
+    /// Fraction of the swing animation remaining; 0 when not swinging.
+    swing: f64,
+
+    current_view: ListenableCell<UiViewState>,
+}
+
+impl Hand {
+    pub(crate) fn new(
+        universe: &mut Universe,
+        character_source: ListenableSource<Option<URef<Character>>>,
+        graphics_options: ListenableSource<GraphicsOptions>,
+        viewport_source: ListenableSource<Viewport>,
+    ) -> Self {
+        let inputs_dirty = DirtyFlag::listening(false, &graphics_options);
+        viewport_source.listen(inputs_dirty.listener());
+
+        // The space has a margin around the icon so that it has light.
+        let mut space = Space::builder(GridAab::from_lower_upper([-1, -1, -1], [2, 2, 2]))
+            .physics({
+                let mut physics = SpacePhysics::default();
+                physics.sky = Sky::Uniform(palette::HUD_SKY);
+                physics
+            })
+            .build();
+        space.fast_evaluate_light();
+
+        Self {
+            character_source,
+            graphics_options,
+            viewport_source,
+            inputs_dirty,
+            space: universe.insert_anonymous(space),
+            shown_icon: None,
+            swing: 0.0,
+            current_view: ListenableCell::new(UiViewState::default()),
+        }
+    }
+
+    /// The view that should be displayed to the user in front of the world.
+    pub(crate) fn view(&self) -> ListenableSource<UiViewState> {
+        self.current_view.as_source()
+    }
+
+    /// Starts the animation of using the tool.
+    pub(crate) fn swing(&mut self) {
+        self.swing = 1.0;
+    }
+
+    /// Updates the shown tool from the character, and advances the animation.
+    ///
+    /// This should be called before the universe containing the hand space is stepped,
+    /// so that the lighting of a newly shown icon is updated.
+    pub(crate) fn step(&mut self, tick: Tick, icons: &BlockProvider<Icons>) {
+        let mut changed = self.inputs_dirty.get_and_clear();
+
+        let icon = self.selected_icon(icons);
+        if icon != self.shown_icon {
+            let block = icon.clone().unwrap_or(AIR);
+            let result = self.space.try_modify(|space| space.set([0, 0, 0], block));
+            if !matches!(result, Ok(Ok(_))) {
+                log::error!("Failed to update hand space: {result:?}");
+            }
+            self.shown_icon = icon;
+            changed = true;
+        }
+
+        if self.swing > 0.0 {
+            self.swing = (self.swing - tick.delta_t().as_secs_f64() / SWING_SECONDS).max(0.0);
+            changed = true;
+        }
+
+        if changed {
+            self.current_view.set(self.view_state());
+        }
+    }
+
+    fn selected_icon(&self, icons: &BlockProvider<Icons>) -> Option<Block> {
+        let character_ref = self.character_source.snapshot()?;
+        let character = character_ref.read().ok()?;
+        let slot = character
+            .inventory()
+            .slots
+            .get(character.selected_slots()[HAND_SELECTION])?;
+        match slot {
+            Slot::Empty => None,
+            Slot::Stack(..) => Some(slot.icon(icons).into_owned()),
+        }
+    }
+
+    fn view_state(&self) -> UiViewState {
+        let graphics_options = Vui::graphics_options(self.graphics_options.snapshot());
+        UiViewState {
+            view_transform: Self::view_transform(
+                Deg(graphics_options.fov_y.into_inner()),
+                self.viewport_source.get().nominal_aspect_ratio(),
+                self.swing,
+            ),
+            space: self.shown_icon.as_ref().map(|_| self.space.clone()),
+            graphics_options,
+        }
+    }
+
+    /// Computes a [`ViewTransform`] which places the icon cube in the lower right of the
+    /// view, given the fraction of the swing animation remaining.
+    fn view_transform(
+        fov_y: Deg<FreeCoordinate>,
+        aspect_ratio: FreeCoordinate,
+        swing: f64,
+    ) -> ViewTransform {
+        let half_height = DISTANCE * (fov_y / 2.).tan();
+        // Rises from 0 to 1 and falls back over the course of the animation.
+        let swing_amount = (swing * PI).sin();
+
+        let rotation: Basis3<FreeCoordinate> =
+            Basis3::from_angle_x(Deg(20. - 60. * swing_amount)) * Basis3::from_angle_y(Deg(-30.));
+        let center = Vector3::new(
+            0.6 * half_height * aspect_ratio,
+            -0.5 * half_height,
+            -DISTANCE + 1.5 * swing_amount,
+        );
+        let icon_to_eye: ViewTransform = Decomposed {
+            scale: 1.0,
+            rot: rotation,
+            disp: center - rotation.rotate_vector(Vector3::new(0.5, 0.5, 0.5)),
+        };
+        // Our view transform standard is camera-to-world, not world-to-camera.
+        icon_to_eye.inverse_transform().unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use all_is_cubes::cgmath::{EuclideanSpace as _, Point3, Transform as _};
+
+    #[test]
+    fn view_transform_shows_icon_in_lower_right() {
+        let fov_y = Deg(30.);
+        let eye_to_world = Hand::view_transform(fov_y, 1.5, 0.0);
+        let icon_center_in_eye = eye_to_world
+            .inverse_transform()
+            .unwrap()
+            .transform_point(Point3::new(0.5, 0.5, 0.5))
+            .to_vec();
+        assert!(icon_center_in_eye.x > 0.0, "{icon_center_in_eye:?}");
+        assert!(icon_center_in_eye.y < 0.0, "{icon_center_in_eye:?}");
+        assert!(icon_center_in_eye.z < 0.0, "{icon_center_in_eye:?}");
+        // Within the field of view
+        let half_height = -icon_center_in_eye.z * (fov_y / 2.).tan();
+        assert!(icon_center_in_eye.x < half_height * 1.5);
+        assert!(-icon_center_in_eye.y < half_height);
+    }
+}
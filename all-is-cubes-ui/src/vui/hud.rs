@@ -1,34 +1,42 @@
 use std::fmt;
 use std::sync::{mpsc, Arc, Mutex};
 
-use all_is_cubes::block::{self, Block};
-use all_is_cubes::camera::GraphicsOptions;
+use all_is_cubes::block::{self, Block, BlockDefTransaction, Primitive};
+use all_is_cubes::camera::{GraphicsOptions, UiPaletteOption};
 use all_is_cubes::character::Character;
 use all_is_cubes::content::palette;
 use all_is_cubes::drawing::VoxelBrush;
-use all_is_cubes::inv::Icons;
-use all_is_cubes::linking::BlockProvider;
+use all_is_cubes::inv::{Hotbar, Icons};
+use all_is_cubes::linking::{BlockModule, BlockProvider};
 use all_is_cubes::listen::ListenableSource;
-use all_is_cubes::math::{Face6, GridPoint};
-use all_is_cubes::universe::{URef, Universe};
+use all_is_cubes::math::{Face6, GridCoordinate, GridPoint, Rgba};
+use all_is_cubes::space::Waypoint;
+use all_is_cubes::time::Duration;
+use all_is_cubes::transaction;
+use all_is_cubes::universe::{URef, Universe, UniverseStepInfo};
 use all_is_cubes::util::YieldProgress;
 
 use crate::apps::{ControlMessage, FullscreenSetter, FullscreenState};
+use crate::i18n::Catalog;
 use crate::vui::options::{graphics_options_widgets, pause_toggle_button, OptionsStyle};
 use crate::vui::pages::open_page_button;
-use crate::vui::widgets::{self, BoxStyle, Crosshair, TooltipState};
+use crate::vui::widgets::{
+    self, BoxStyle, Compass, Crosshair, Graph, GraphData, HealthBar, Minimap, TooltipState,
+};
 use crate::vui::{CueNotifier, LayoutTree, UiBlocks, VuiMessage, VuiPageState, Widget, WidgetTree};
 
 pub(crate) use all_is_cubes::drawing::embedded_graphics::mono_font::iso_8859_1::FONT_8X13_BOLD as HudFont;
 
-pub(crate) const TOOLBAR_POSITIONS: usize = 10;
-
 /// Ad-hoc bundle of elements needed to construct HUD UI widgets.
 ///
 /// TODO: Disentangle general UI from the concept of "HUD" — this is used for lots of things
 /// that aren't HUD
 pub(crate) struct HudInputs {
     pub hud_blocks: Arc<HudBlocks>,
+    /// Text of the user's chosen language, which `hud_blocks` were also made with.
+    pub catalog: &'static Catalog,
+    /// Colors which `hud_blocks` were made with.
+    pub ui_palette: UiPaletteOption,
     pub cue_channel: CueNotifier,
     pub vui_control_channel: mpsc::SyncSender<VuiMessage>,
     pub app_control_channel: mpsc::SyncSender<ControlMessage>,
@@ -36,6 +44,8 @@ pub(crate) struct HudInputs {
     pub paused: ListenableSource<bool>,
     pub page_state: ListenableSource<VuiPageState>,
     pub mouselook_mode: ListenableSource<bool>,
+    /// Which inventory slots the toolbar shows.
+    pub hotbar: ListenableSource<Hotbar>,
     pub fullscreen_mode: ListenableSource<FullscreenState>,
     pub set_fullscreen: FullscreenSetter,
 }
@@ -50,21 +60,28 @@ impl fmt::Debug for HudInputs {
 pub(super) fn new_hud_widget_tree(
     // TODO: mess of tightly coupled parameters
     character_source: ListenableSource<Option<URef<Character>>>,
+    waypoint_source: ListenableSource<Vec<URef<Waypoint>>>,
     hud_inputs: &HudInputs,
     // TODO: stop mutating the universe in widget construction
     universe: &mut Universe,
     tooltip_state: Arc<Mutex<TooltipState>>,
+    performance_graphs: &PerformanceGraphs,
 ) -> WidgetTree {
+    let minimap: Arc<dyn Widget> = Minimap::new(2, character_source.clone(), universe);
+    let compass: Arc<dyn Widget> =
+        Compass::new(9, character_source.clone(), waypoint_source, universe);
+    let health_bar: Arc<dyn Widget> = HealthBar::new(9, character_source.clone(), universe);
     let toolbar: Arc<dyn Widget> = widgets::Toolbar::new(
         character_source,
         Arc::clone(&hud_inputs.hud_blocks),
-        TOOLBAR_POSITIONS,
+        hud_inputs.hotbar.clone(),
         universe,
         hud_inputs.cue_channel.clone(),
     );
     let tooltip: Arc<dyn Widget> = widgets::Tooltip::new(
         Arc::clone(&tooltip_state),
         hud_inputs.hud_blocks.clone(),
+        hud_inputs.catalog,
         universe,
     );
     let hud_widget_tree: WidgetTree = Arc::new(LayoutTree::Hud {
@@ -74,9 +91,21 @@ pub(super) fn new_hud_widget_tree(
         )),
         toolbar: Arc::new(LayoutTree::Stack {
             direction: Face6::PY,
-            children: vec![LayoutTree::leaf(toolbar), LayoutTree::leaf(tooltip)],
+            children: vec![
+                LayoutTree::leaf(toolbar),
+                LayoutTree::leaf(health_bar),
+                LayoutTree::leaf(compass),
+                LayoutTree::leaf(tooltip),
+            ],
+        }),
+        control_bar: Arc::new(LayoutTree::Stack {
+            direction: Face6::NY,
+            children: vec![
+                control_bar(hud_inputs),
+                LayoutTree::leaf(minimap),
+                performance_graphs.widget_tree(hud_inputs, universe),
+            ],
         }),
-        control_bar: control_bar(hud_inputs),
     });
     hud_widget_tree
 }
@@ -125,6 +154,72 @@ pub(crate) fn control_bar(hud_inputs: &HudInputs) -> WidgetTree {
     }
 }
 
+/// Recent performance measurements, displayed as graphs in the HUD when
+/// [`GraphicsOptions::debug_info_text`] is enabled.
+#[derive(Clone, Debug)]
+pub(crate) struct PerformanceGraphs {
+    /// Time taken to render each frame, in milliseconds.
+    frame_time: Arc<Mutex<GraphData>>,
+    /// Time taken to step the game universe, in milliseconds.
+    step_time: Arc<Mutex<GraphData>>,
+    /// Number of cubes in all spaces' light update queues.
+    light_queue: Arc<Mutex<GraphData>>,
+}
+
+impl PerformanceGraphs {
+    const WIDTH_IN_HUD: GridCoordinate = 6;
+
+    pub(crate) fn new() -> Self {
+        let data = || {
+            Arc::new(Mutex::new(GraphData::new(Graph::sample_capacity(
+                Self::WIDTH_IN_HUD,
+            ))))
+        };
+        Self {
+            frame_time: data(),
+            step_time: data(),
+            light_queue: data(),
+        }
+    }
+
+    pub(crate) fn record_step_info(&self, info: &UniverseStepInfo) {
+        if let Ok(mut data) = self.step_time.lock() {
+            data.push(info.computation_time.as_secs_f32() * 1000.0);
+        }
+        if let Ok(mut data) = self.light_queue.lock() {
+            data.push(info.space_step.light.queue_count as f32);
+        }
+    }
+
+    pub(crate) fn record_render_time(&self, time: Duration) {
+        if let Ok(mut data) = self.frame_time.lock() {
+            data.push(time.as_secs_f32() * 1000.0);
+        }
+    }
+
+    fn widget_tree(&self, hud_inputs: &HudInputs, universe: &mut Universe) -> WidgetTree {
+        let mut graph = |data: &Arc<Mutex<GraphData>>, full_scale: f32, color: Rgba| {
+            LayoutTree::leaf(Graph::new(
+                Self::WIDTH_IN_HUD,
+                Arc::clone(data),
+                full_scale,
+                Block::from(color),
+                Block::from(palette::HUD_GRAPH_BACK),
+                hud_inputs.graphics_options.clone(),
+                universe,
+            ) as Arc<dyn Widget>)
+        };
+        Arc::new(LayoutTree::Stack {
+            direction: Face6::NY,
+            children: vec![
+                graph(&self.frame_time, 50.0, palette::HUD_GRAPH_FRAME_TIME),
+                graph(&self.step_time, 20.0, palette::HUD_GRAPH_STEP_TIME),
+                graph(&self.light_queue, 1000.0, palette::HUD_GRAPH_LIGHT_QUEUE),
+            ],
+        })
+    }
+}
+
 // TODO: Unclear if HudBlocks should exist; maybe it should be reworked into a BlockProvider for widget graphics instead.
 #[derive(Debug, Clone)]
 pub(crate) struct HudBlocks {
@@ -135,10 +230,21 @@ pub(crate) struct HudBlocks {
 }
 
 impl HudBlocks {
-    pub(crate) async fn new(universe: &mut Universe, p: YieldProgress) -> Self {
+    /// Constructs the blocks and adds their definitions to `universe`.
+    ///
+    /// If `universe` already contains definitions from a previous call, as when the
+    /// language or palette changes, their blocks are replaced instead.
+    pub(crate) async fn new(
+        universe: &mut Universe,
+        catalog: &Catalog,
+        ui_palette: &UiPaletteOption,
+        p: YieldProgress,
+    ) -> Self {
         let [p1, p2] = p.split(0.5);
-        let ui_blocks = UiBlocks::new(universe, p1).await.install(universe).unwrap();
-        let icons = Icons::new(universe, p2).await.install(universe).unwrap();
+        let ui_blocks = UiBlocks::new(universe, catalog, ui_palette, p1).await;
+        let ui_blocks = install_or_replace(universe, &ui_blocks);
+        let icons = Icons::new(universe, p2).await;
+        let icons = install_or_replace(universe, &icons);
 
         let text_brush = VoxelBrush::new::<_, Block>([
             ([0, 0, 1], palette::HUD_TEXT_FILL.into()),
@@ -171,3 +277,25 @@ impl HudBlocks {
         widgets::Frame::new(self.dialog_box_style.clone())
     }
 }
+
+/// Adds the definitions of `blocks` to `universe`, or, if it already has definitions with
+/// the same names, replaces their blocks so that everything using them is updated.
+fn install_or_replace<E: BlockModule>(
+    universe: &mut Universe,
+    blocks: &BlockProvider<E>,
+) -> BlockProvider<E> {
+    let Ok(installed) = BlockProvider::<E>::using(universe) else {
+        return blocks.install(universe).unwrap();
+    };
+    for key in E::exhaust() {
+        if let Primitive::Indirect(block_def) = installed[key.clone()].primitive() {
+            block_def
+                .execute(
+                    &BlockDefTransaction::overwrite(blocks[key].clone()),
+                    &mut transaction::no_outputs,
+                )
+                .unwrap();
+        }
+    }
+    installed
+}
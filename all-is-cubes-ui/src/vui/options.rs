@@ -1,7 +1,7 @@
 use std::sync::Arc;
 
 use all_is_cubes::block;
-use all_is_cubes::camera::{AntialiasingOption, GraphicsOptions};
+use all_is_cubes::camera::{AntialiasingOption, GraphicsOptions, UiPaletteOption};
 use all_is_cubes::content::palette;
 use all_is_cubes::drawing::embedded_graphics::{mono_font::iso_8859_1 as font, text::TextStyle};
 use all_is_cubes::drawing::VoxelBrush;
@@ -93,6 +93,22 @@ pub(crate) fn graphics_options_widgets(
             |g, v| g.debug_light_rays_at_cursor = v,
         ),
     ]);
+    if let OptionsStyle::LabeledColumn = style {
+        // Not in the compact row, since it is not something to switch back and forth.
+        w.push(graphics_toggle_button(
+            hud_inputs,
+            style,
+            UiBlocks::HighContrastButtonLabel,
+            |g| g.ui_palette == UiPaletteOption::HighContrast,
+            |g, v| {
+                g.ui_palette = if v {
+                    UiPaletteOption::HighContrast
+                } else {
+                    UiPaletteOption::Standard
+                }
+            },
+        ));
+    }
     w
 }
 
@@ -1,4 +1,5 @@
 use std::borrow::Cow;
+use std::fmt::Write as _;
 use std::sync::Arc;
 
 use all_is_cubes::block::AIR;
@@ -8,14 +9,17 @@ use all_is_cubes::block::{
 };
 use all_is_cubes::camera;
 use all_is_cubes::cgmath::Vector2;
+use all_is_cubes::character::PlayerStats;
 use all_is_cubes::content::palette;
 use all_is_cubes::drawing::embedded_graphics::{mono_font::iso_8859_1 as font, text::TextStyle};
 use all_is_cubes::drawing::VoxelBrush;
 use all_is_cubes::math::{Face6, FreeCoordinate, GridAab, GridCoordinate, GridVector, Rgba};
-use all_is_cubes::space::{Space, SpaceBuilder, SpacePhysics};
+use all_is_cubes::space::{Sky, Space, SpaceBuilder, SpacePhysics};
 use all_is_cubes::transaction;
 use all_is_cubes::universe::{URef, Universe};
 
+use crate::apps::ControlMessage;
+use crate::i18n::Catalog;
 use crate::logo::logo_text;
 use crate::vui::hud::HudInputs;
 use crate::vui::options::{graphics_options_widgets, pause_toggle_button, OptionsStyle};
@@ -25,8 +29,11 @@ use crate::vui::{
 };
 use crate::vui::{widgets, VuiMessage, VuiPageState};
 
+#[cfg(doc)]
+use all_is_cubes::camera::GraphicsOptions;
+
 /// Bounds for UI display; a choice of scale and aspect ratio based on the viewport size
-/// and aspect ratio (and maybe in the future, preferences).
+/// and aspect ratio and the user's chosen [`GraphicsOptions::ui_scale`].
 ///
 ///
 #[derive(Copy, Clone, Debug, Eq, PartialEq)]
@@ -38,12 +45,22 @@ pub(crate) struct UiSize {
 impl UiSize {
     pub(crate) const DEPTH_BEHIND_VIEW_PLANE: GridCoordinate = 5;
 
+    /// Width at a `ui_scale` of 1.
+    const DEFAULT_WIDTH: GridCoordinate = 25;
+
+    /// Narrowest width which still fits the toolbar.
+    const MIN_WIDTH: GridCoordinate = 21;
+
     /// Construct [`UiSize`] that suits the given viewport
-    /// (based on pixel resolution and aspect ratio).
-    pub fn new(viewport: camera::Viewport) -> Self {
+    /// (based on pixel resolution and aspect ratio) and [`GraphicsOptions::ui_scale`].
+    pub fn new(viewport: camera::Viewport, ui_scale: f32) -> Self {
         // Note: Dimensions are enforced to be odd so that the crosshair can work.
         // The toolbar is also designed to be odd width when it has an even number of positions.
-        let width = 25;
+        // A larger scale means fewer cubes fill the viewport, so each one appears larger.
+        let width = (FreeCoordinate::from(Self::DEFAULT_WIDTH) / FreeCoordinate::from(ui_scale))
+            .round() as GridCoordinate;
+        let width = (width / 2 * 2 + 1).max(Self::MIN_WIDTH); // ensure odd
+
         // we want to ceil() the height because the camera setup makes the height match
         // the viewport and ignores width, so we want to prefer too-narrow over too-wide
         let height = ((FreeCoordinate::from(width) / viewport.nominal_aspect_ratio()).ceil()
@@ -71,7 +88,7 @@ impl UiSize {
         let mut space = Space::builder(bounds)
             .physics({
                 let mut physics = SpacePhysics::default();
-                physics.sky_color = palette::HUD_SKY;
+                physics.sky = Sky::Uniform(palette::HUD_SKY);
                 physics
             })
             .build();
@@ -171,6 +188,36 @@ pub(crate) fn open_page_button(
     )
 }
 
+/// Make a button that asks the session to show the player's statistics, which it does
+/// by calling [`Vui::show_stats()`](crate::vui::Vui::show_stats).
+fn stats_button(hud_inputs: &HudInputs) -> Arc<dyn Widget> {
+    widgets::ActionButton::new(
+        hud_inputs.hud_blocks.blocks[UiBlocks::StatsButtonLabel].clone(),
+        &hud_inputs.hud_blocks.blocks,
+        {
+            let cc = hud_inputs.app_control_channel.clone();
+            move || {
+                let _ignore_errors = cc.send(ControlMessage::ShowStats);
+            }
+        },
+    )
+}
+
+/// Make a button that asks the session to retry loading a universe, which it does by
+/// calling [`Session::retry_set_universe()`](crate::apps::Session::retry_set_universe).
+fn retry_button(hud_inputs: &HudInputs) -> Arc<dyn Widget> {
+    widgets::ActionButton::new(
+        hud_inputs.hud_blocks.blocks[UiBlocks::RetryButtonLabel].clone(),
+        &hud_inputs.hud_blocks.blocks,
+        {
+            let cc = hud_inputs.app_control_channel.clone();
+            move || {
+                let _ignore_errors = cc.send(ControlMessage::RetryUniverseLoad);
+            }
+        },
+    )
+}
+
 /// Wrap the given widget tree in a transparent screen-filling background.
 fn page_modal_backdrop(foreground: WidgetTree) -> WidgetTree {
     Arc::new(LayoutTree::Stack {
@@ -198,13 +245,14 @@ pub(super) fn new_paused_widget_tree(
     hud_inputs: &HudInputs,
 ) -> Result<WidgetTree, InstallVuiError> {
     use parts::{heading, shrink};
+    let catalog = hud_inputs.catalog;
 
     let contents = Arc::new(LayoutTree::Stack {
         direction: Face6::NY,
         children: vec![
             // TODO: establish standard resolutions for logo etc
             LayoutTree::leaf(shrink(u, R16, LayoutTree::leaf(logo_text()))?),
-            LayoutTree::leaf(shrink(u, R32, heading("Paused"))?),
+            LayoutTree::leaf(shrink(u, R32, heading(catalog.get("page-paused")))?),
             LayoutTree::leaf(open_page_button(
                 hud_inputs,
                 VuiPageState::AboutText,
@@ -215,6 +263,7 @@ pub(super) fn new_paused_widget_tree(
                 VuiPageState::Options,
                 hud_inputs.hud_blocks.blocks[UiBlocks::OptionsButtonLabel].clone(),
             )),
+            LayoutTree::leaf(stats_button(hud_inputs)),
             LayoutTree::leaf(pause_toggle_button(hud_inputs)),
         ],
     });
@@ -231,12 +280,13 @@ pub(super) fn new_options_widget_tree(
     hud_inputs: &HudInputs,
 ) -> Result<WidgetTree, InstallVuiError> {
     use parts::{heading, shrink};
+    let catalog = hud_inputs.catalog;
 
     let contents = Arc::new(LayoutTree::Stack {
         direction: Face6::NY,
         children: vec![
             LayoutTree::leaf(shrink(u, R32, LayoutTree::leaf(logo_text()))?),
-            LayoutTree::leaf(shrink(u, R32, heading("Options"))?),
+            LayoutTree::leaf(shrink(u, R32, heading(catalog.get("page-options")))?),
             widgets::back_button(hud_inputs),
             Arc::new(LayoutTree::Stack {
                 direction: Face6::NY,
@@ -259,16 +309,24 @@ pub(super) fn new_about_widget_tree(
     hud_inputs: &HudInputs,
 ) -> Result<WidgetTree, InstallVuiError> {
     use parts::{heading, paragraph, shrink};
+    let catalog = hud_inputs.catalog;
 
+    // TODO: Translate these texts too. The controls table would need its columns
+    // aligned per language, and the about text should be customizable anyway.
     let controls_text = indoc::indoc! {"
         W A S D    movement
           E C      fly up/down (requires jetpack item)
         Arrows     turn
            L       toggle mouselook
+           F       toggle fullscreen
+           G       toggle targeting through transparent blocks
           0-9      select items on toolbar
+           X       add cube to selection
+           Z       clear selection
       Left mouse   use first toolbar item
       Right mouse  use selected toolbar item
            P       toggle pause
+          , .      slower/faster time
         Escape     toggle pause; exit menu
     "};
 
@@ -287,9 +345,9 @@ pub(super) fn new_about_widget_tree(
         children: vec![
             LayoutTree::leaf(shrink(u, R8, LayoutTree::leaf(logo_text()))?),
             widgets::back_button(hud_inputs),
-            LayoutTree::leaf(shrink(u, R32, heading("Controls"))?),
+            LayoutTree::leaf(shrink(u, R32, heading(catalog.get("page-controls")))?),
             LayoutTree::leaf(shrink(u, R32, paragraph(controls_text))?),
-            LayoutTree::leaf(shrink(u, R32, heading("About"))?),
+            LayoutTree::leaf(shrink(u, R32, heading(catalog.get("page-about")))?),
             LayoutTree::leaf(shrink(u, R32, paragraph(about_text))?),
             // LayoutTree::leaf(shrink(u, R32, heading("License"))?),
             // LayoutTree::leaf(shrink(u, R32, paragraph("TODO"))?),
@@ -303,6 +361,132 @@ pub(super) fn new_about_widget_tree(
     ))))
 }
 
+/// Page displaying the given statistics, or saying that there are none.
+pub(super) fn new_stats_widget_tree(
+    u: &mut Universe,
+    hud_inputs: &HudInputs,
+    stats: Option<&PlayerStats>,
+) -> Result<WidgetTree, InstallVuiError> {
+    use parts::{heading, paragraph, shrink};
+    let catalog = hud_inputs.catalog;
+
+    let contents = Arc::new(LayoutTree::Stack {
+        direction: Face6::NY,
+        children: vec![
+            LayoutTree::leaf(shrink(u, R32, heading(catalog.get("page-statistics")))?),
+            widgets::back_button(hud_inputs),
+            LayoutTree::leaf(shrink(u, R32, paragraph(stats_text(catalog, stats)))?),
+        ],
+    });
+    Ok(page_modal_backdrop(Arc::new(LayoutTree::Shrink(
+        hud_inputs
+            .hud_blocks
+            .dialog_background()
+            .as_background_of(contents),
+    ))))
+}
+
+/// Page reporting that a universe could not be loaded, with `message` describing why,
+/// and offering to retry if `retryable`.
+pub(super) fn new_load_error_widget_tree(
+    u: &mut Universe,
+    hud_inputs: &HudInputs,
+    message: String,
+    retryable: bool,
+) -> Result<WidgetTree, InstallVuiError> {
+    use parts::{heading, paragraph, shrink};
+    let catalog = hud_inputs.catalog;
+
+    let mut buttons = vec![widgets::back_button(hud_inputs)];
+    if retryable {
+        buttons.push(LayoutTree::leaf(retry_button(hud_inputs)));
+    }
+    let contents = Arc::new(LayoutTree::Stack {
+        direction: Face6::NY,
+        children: vec![
+            LayoutTree::leaf(shrink(u, R32, heading(catalog.get("page-load-failed")))?),
+            Arc::new(LayoutTree::Stack {
+                direction: Face6::PX,
+                children: buttons,
+            }),
+            LayoutTree::leaf(shrink(u, R32, paragraph(message))?),
+        ],
+    });
+    Ok(page_modal_backdrop(Arc::new(LayoutTree::Shrink(
+        hud_inputs
+            .hud_blocks
+            .dialog_background()
+            .as_background_of(contents),
+    ))))
+}
+
+/// Maximum number of kinds of block listed in [`stats_text()`].
+const STATS_BLOCK_LIST_LENGTH: usize = 5;
+
+/// Text of the statistics page.
+fn stats_text(catalog: &Catalog, stats: Option<&PlayerStats>) -> String {
+    let Some(stats) = stats else {
+        return String::from(catalog.get("stats-none"));
+    };
+
+    let time = stats.time_played().as_secs();
+    let distance = format!("{:.0}", stats.distance_traveled());
+    let rows = [
+        (
+            catalog.get("stats-time-played"),
+            format!("{}:{:02}:{:02}", time / 3600, time / 60 % 60, time % 60),
+        ),
+        (
+            catalog.get("stats-distance-traveled"),
+            catalog.format("stats-distance-value", &[("meters", &distance)]),
+        ),
+        (
+            catalog.get("stats-blocks-placed"),
+            stats.total_blocks_placed().to_string(),
+        ),
+        (
+            catalog.get("stats-blocks-removed"),
+            stats.total_blocks_removed().to_string(),
+        ),
+    ];
+    // Align the values in a column after the longest label.
+    let width = rows
+        .iter()
+        .map(|(label, _)| label.chars().count() + 2)
+        .max()
+        .unwrap_or(0);
+    let mut text = String::new();
+    for (label, value) in rows {
+        let _ = writeln!(text, "{label:<width$}{value}");
+    }
+
+    let mut placed: Vec<(String, u64)> = stats
+        .iter_blocks_placed()
+        .map(|(block, count)| {
+            let name = block
+                .evaluate()
+                .map(|ev| catalog.display_name(&ev.attributes.display_name).to_owned())
+                .unwrap_or_default();
+            (name, count)
+        })
+        .collect();
+    placed.sort_by(|(name_a, count_a), (name_b, count_b)| {
+        count_b.cmp(count_a).then_with(|| name_a.cmp(name_b))
+    });
+    if !placed.is_empty() {
+        let _ = write!(text, "\n{}\n", catalog.get("stats-most-placed"));
+    }
+    for (name, count) in placed.into_iter().take(STATS_BLOCK_LIST_LENGTH) {
+        let name = if name.is_empty() {
+            catalog.get("stats-unnamed-block")
+        } else {
+            &name
+        };
+        let _ = writeln!(text, "  {count:>6}  {name}");
+    }
+    text
+}
+
 /// Helpers for assembling widget trees into dialog stuff.
 mod parts {
     use super::*;
@@ -347,18 +531,32 @@ mod parts {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::i18n::Language;
+    use all_is_cubes::character::Character;
+    use all_is_cubes::event::GameEvent;
+    use all_is_cubes::math::GridPoint;
+    use all_is_cubes::transaction::Transaction as _;
+    use all_is_cubes::universe::UniverseTransaction;
 
     #[test]
     fn ui_size() {
-        let cases: Vec<([u32; 2], [i32; 2])> =
-            vec![([800, 600], [25, 19]), ([1000, 600], [25, 15])];
+        let cases: Vec<([u32; 2], f32, [i32; 2])> = vec![
+            ([800, 600], 1.0, [25, 19]),
+            ([1000, 600], 1.0, [25, 15]),
+            ([800, 600], 0.5, [51, 39]),
+            // Enlargement is limited by the width of the toolbar.
+            ([800, 600], 2.0, [21, 17]),
+        ];
         let mut failed = 0;
-        for (nominal_viewport, expected_size) in cases {
-            let actual_size =
-                UiSize::new(camera::Viewport::with_scale(1.0, nominal_viewport.into())).size;
+        for (nominal_viewport, ui_scale, expected_size) in cases {
+            let actual_size = UiSize::new(
+                camera::Viewport::with_scale(1.0, nominal_viewport.into()),
+                ui_scale,
+            )
+            .size;
             let actual_size: [i32; 2] = actual_size.into();
             if actual_size != expected_size {
-                println!("{nominal_viewport:?} expected to produce {expected_size:?}; got {actual_size:?}");
+                println!("{nominal_viewport:?} at {ui_scale} expected to produce {expected_size:?}; got {actual_size:?}");
                 failed += 1;
             }
         }
@@ -366,4 +564,51 @@ mod tests {
             panic!("{failed} cases failed");
         }
     }
+
+    #[test]
+    fn stats_text_lists_blocks() {
+        let mut universe = Universe::new();
+        let space = universe.insert_anonymous(Space::empty_positive(1, 1, 1));
+        let character = universe.insert_anonymous(Character::spawn_default(space.clone()));
+        let stats = universe.insert_anonymous(PlayerStats::new(character.clone()));
+        let block = Block::builder()
+            .color(Rgba::WHITE)
+            .display_name("foo")
+            .build();
+        for _ in 0..3 {
+            UniverseTransaction::event(GameEvent::BlockPlaced {
+                space: space.clone(),
+                cube: GridPoint::new(0, 0, 0),
+                block: block.clone(),
+                character: Some(character.clone()),
+            })
+            .execute(&mut universe, &mut transaction::no_outputs)
+            .unwrap();
+        }
+
+        assert_eq!(
+            stats_text(Language::English.catalog(), Some(&stats.read().unwrap())),
+            indoc::indoc! {"
+                Time played        0:00:00
+                Distance traveled  0 m
+                Blocks placed      3
+                Blocks removed     0
+
+                Most placed:
+                       3  foo
+            "}
+        );
+        assert_eq!(
+            stats_text(Language::German.catalog(), Some(&stats.read().unwrap())),
+            indoc::indoc! {"
+                Spielzeit              0:00:00
+                Zurückgelegte Strecke  0 m
+                Platzierte Blöcke      3
+                Entfernte Blöcke       0
+
+                Am häufigsten platziert:
+                       3  foo
+            "}
+        );
+    }
 }
@@ -8,10 +8,18 @@ use all_is_cubes::space::SpaceTransaction;
 
 use crate::vui;
 
+mod compass;
+pub(crate) use compass::*;
 mod crosshair;
 pub(crate) use crosshair::*;
 mod frame;
 pub use frame::*;
+mod graph;
+pub(crate) use graph::*;
+mod health_bar;
+pub(crate) use health_bar::*;
+mod minimap;
+pub(crate) use minimap::*;
 mod text;
 pub use text::*;
 mod button;
@@ -36,6 +36,7 @@ use all_is_cubes::time::Tick;
 use all_is_cubes::transaction::Merge;
 use all_is_cubes::universe::{URef, Universe};
 
+use crate::vui::blocks::UiColors;
 use crate::vui::{self, Layoutable as _, UiBlocks};
 
 type Action = EphemeralOpaque<dyn Fn() + Send + Sync>;
@@ -351,6 +352,7 @@ pub(crate) fn make_button_label_block(
     universe: &mut Universe,
     name: &str,
     icon: ButtonIcon<'_>,
+    colors: &UiColors,
 ) -> Result<BlockBuilder<BlockBuilderVoxels>, InGenError> {
     let mut space = Space::builder(GridAab::from_lower_size(
         [0, 0, 0],
@@ -370,10 +372,7 @@ pub(crate) fn make_button_label_block(
             Text::with_text_style(
                 text,
                 Point::new(-1, -1),
-                MonoTextStyle::new(
-                    font,
-                    &VoxelBrush::single(Block::from(palette::BUTTON_LABEL)),
-                ),
+                MonoTextStyle::new(font, &VoxelBrush::single(Block::from(colors.button_label))),
                 TextStyleBuilder::new()
                     .baseline(Baseline::Middle)
                     .alignment(Alignment::Center)
@@ -433,7 +432,8 @@ pub(crate) trait ButtonBase {
     /// Constructs the block for this kind of button in this state, without any label.
     ///
     /// TODO: switch from `&mut Universe` to transactions
-    fn button_block(&self, universe: &mut Universe) -> Result<Block, InGenError>;
+    fn button_block(&self, universe: &mut Universe, colors: &UiColors)
+        -> Result<Block, InGenError>;
 
     /// Where within the [`Self::button_block()`] the label should be positioned.
     ///
@@ -447,14 +447,18 @@ impl ButtonBase for ButtonVisualState {
         theme::UNPRESSED_Z + if self.pressed { -2 } else { 0 }
     }
 
-    fn button_block(&self, universe: &mut Universe) -> Result<Block, InGenError> {
+    fn button_block(
+        &self,
+        universe: &mut Universe,
+        colors: &UiColors,
+    ) -> Result<Block, InGenError> {
         let label_z = self.button_label_z();
-        let back_block = palette::BUTTON_BACK; // TODO: different color theme for action than toggle?
-        let frame_brush = VoxelBrush::single(Block::from(palette::BUTTON_FRAME));
+        let back_block = colors.button_back; // TODO: different color theme for action than toggle?
+        let frame_brush = VoxelBrush::single(Block::from(colors.button_frame));
         let back_brush = VoxelBrush::with_thickness(back_block, 0..label_z);
         let cap_rim_brush = VoxelBrush::new([(
             [0, 0, label_z - 1],
-            Block::from(theme::rim_lightening(palette::BUTTON_BACK)),
+            Block::from(theme::rim_lightening(colors.button_back)),
         )]);
 
         let outer_inset = 2; // TODO duplicate number
@@ -505,19 +509,23 @@ impl ButtonBase for ToggleButtonVisualState {
         self.common.button_label_z()
     }
 
-    fn button_block(&self, universe: &mut Universe) -> Result<Block, InGenError> {
+    fn button_block(
+        &self,
+        universe: &mut Universe,
+        colors: &UiColors,
+    ) -> Result<Block, InGenError> {
         let label_z = self.button_label_z();
         let active = self.value;
         let back_block = Block::from(if active {
-            palette::BUTTON_ACTIVATED_BACK
+            colors.button_activated_back
         } else {
-            palette::BUTTON_BACK
+            colors.button_back
         });
-        let frame_brush = VoxelBrush::single(Block::from(palette::BUTTON_FRAME));
+        let frame_brush = VoxelBrush::single(Block::from(colors.button_frame));
         let back_brush = VoxelBrush::with_thickness(back_block, 0..label_z);
         let cap_rim_brush = VoxelBrush::new([(
             [0, 0, label_z - 1],
-            Block::from(theme::rim_lightening(palette::BUTTON_BACK)),
+            Block::from(theme::rim_lightening(colors.button_back)),
         )]);
 
         let outer_inset = 2;
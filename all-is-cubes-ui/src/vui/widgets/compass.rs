@@ -0,0 +1,289 @@
+use std::error::Error;
+use std::sync::Arc;
+
+use all_is_cubes::block::{space_to_blocks, AnimationHint, Block, BlockAttributes, Resolution};
+use all_is_cubes::cgmath::EuclideanSpace as _;
+use all_is_cubes::character::Character;
+use all_is_cubes::content::palette;
+use all_is_cubes::listen::ListenableSource;
+use all_is_cubes::math::{FreeCoordinate, GridAab, GridCoordinate, GridPoint, GridVector, Rgba};
+use all_is_cubes::space::{Space, SpacePhysics, SpaceTransaction, Waypoint};
+use all_is_cubes::time::Tick;
+use all_is_cubes::universe::{URef, Universe};
+
+use crate::vui::{
+    InstallVuiError, LayoutGrant, LayoutRequest, Layoutable, Widget, WidgetController,
+    WidgetTransaction,
+};
+
+/// Widget which displays the direction a character is facing, as a horizontal strip
+/// with a tick mark every 45° (north, which is −Z, in red) and a marker in the
+/// direction of each [`Waypoint`] in the character's space.
+///
+/// Only directions within 90° of the direction the character is facing are shown,
+/// so waypoints appear as the character turns towards them.
+#[derive(Debug)]
+pub(crate) struct Compass {
+    width_in_hud: GridCoordinate,
+    character_source: ListenableSource<Option<URef<Character>>>,
+    waypoint_source: ListenableSource<Vec<URef<Waypoint>>>,
+    /// Space we draw the compass into.
+    compass_space: URef<Space>,
+}
+
+impl Compass {
+    const RESOLUTION: Resolution = Resolution::R16;
+
+    /// Angle in degrees between the directions shown at the left and right edges.
+    const SPAN: FreeCoordinate = 180.0;
+
+    /// Creates a compass `width_in_hud` cubes wide and one cube high.
+    pub(crate) fn new(
+        width_in_hud: GridCoordinate,
+        character_source: ListenableSource<Option<URef<Character>>>,
+        waypoint_source: ListenableSource<Vec<URef<Waypoint>>>,
+        universe: &mut Universe,
+    ) -> Arc<Self> {
+        let resolution = GridCoordinate::from(Self::RESOLUTION);
+        let compass_space = Space::builder(GridAab::from_lower_size(
+            GridPoint::origin(),
+            GridVector::new(width_in_hud * resolution, resolution, 1),
+        ))
+        .physics(SpacePhysics::DEFAULT_FOR_BLOCK)
+        .build();
+        Arc::new(Self {
+            width_in_hud,
+            character_source,
+            waypoint_source,
+            compass_space: universe.insert_anonymous(compass_space),
+        })
+    }
+
+    /// Returns the voxel column at which a direction `relative` degrees to the right of
+    /// the facing direction is drawn, or [`None`] if it is not shown.
+    fn column(&self, relative: FreeCoordinate) -> Option<GridCoordinate> {
+        let width = self.width_in_hud * GridCoordinate::from(Self::RESOLUTION);
+        let half_span = Self::SPAN / 2.0;
+        (-half_span..half_span)
+            .contains(&relative)
+            .then(|| ((relative / Self::SPAN + 0.5) * FreeCoordinate::from(width)).floor())
+            .map(|x| x as GridCoordinate)
+    }
+
+    /// Computes what should be drawn, or [`None`] if the character cannot currently be
+    /// read.
+    fn marks(&self) -> Option<Vec<Mark>> {
+        let Some(character_ref) = self.character_source.snapshot() else {
+            return Some(Vec::new());
+        };
+        let character = character_ref.read().ok()?;
+        let yaw = character.body.yaw;
+        let mut marks = Vec::new();
+
+        for i in 0..8 {
+            let kind = match i {
+                0 => MarkKind::North,
+                _ if i % 2 == 0 => MarkKind::Cardinal,
+                _ => MarkKind::Minor,
+            };
+            if let Some(x) = self.column(relative_bearing(FreeCoordinate::from(i) * 45.0, yaw)) {
+                marks.push(Mark { x, kind });
+            }
+        }
+
+        for waypoint_ref in self.waypoint_source.snapshot() {
+            let Ok(waypoint) = waypoint_ref.read() else {
+                continue;
+            };
+            if *waypoint.space() != character.space {
+                continue;
+            }
+            let direction = waypoint.position() - character.body.position;
+            if direction.x == 0.0 && direction.z == 0.0 {
+                continue;
+            }
+            // Same convention as the yaw of a character: 0 is −Z and 90 is +X.
+            let bearing = direction.x.atan2(-direction.z).to_degrees();
+            let Some(x) = self.column(relative_bearing(bearing, yaw)) else {
+                continue;
+            };
+            let color = waypoint
+                .icon()
+                .and_then(|icon| icon.evaluate().ok())
+                .map_or(palette::HUD_WAYPOINT_MARKER, |ev| ev.color);
+            marks.push(Mark {
+                x,
+                kind: MarkKind::Waypoint(color),
+            });
+        }
+
+        Some(marks)
+    }
+}
+
+/// Returns the angle in degrees, from −180 to 180, by which `bearing` is to the right
+/// of `yaw`.
+fn relative_bearing(bearing: FreeCoordinate, yaw: FreeCoordinate) -> FreeCoordinate {
+    (bearing - yaw + 180.0).rem_euclid(360.0) - 180.0
+}
+
+/// Something drawn on the [`Compass`] at a particular voxel column.
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Mark {
+    x: GridCoordinate,
+    kind: MarkKind,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum MarkKind {
+    North,
+    /// East, south, or west.
+    Cardinal,
+    /// A direction halfway between two cardinal directions.
+    Minor,
+    /// A waypoint, drawn in the given color.
+    Waypoint(Rgba),
+}
+
+impl Layoutable for Compass {
+    fn requirements(&self) -> LayoutRequest {
+        LayoutRequest {
+            minimum: GridVector::new(self.width_in_hud, 1, 1),
+        }
+    }
+}
+
+impl Widget for Compass {
+    fn controller(self: Arc<Self>, grant: &LayoutGrant) -> Box<dyn WidgetController> {
+        Box::new(CompassController {
+            position: grant
+                .shrink_to(GridVector::new(self.width_in_hud, 1, 1), false)
+                .bounds,
+            definition: self,
+            drawn: None,
+        })
+    }
+}
+
+#[derive(Debug)]
+struct CompassController {
+    definition: Arc<Compass>,
+    position: GridAab,
+    /// What was last drawn, or [`None`] if nothing has been drawn yet.
+    drawn: Option<Vec<Mark>>,
+}
+
+impl CompassController {
+    fn draw(&self, marks: &[Mark]) -> Result<(), Box<dyn Error + Send + Sync>> {
+        self.definition.compass_space.try_modify(|compass_space| {
+            let bounds = compass_space.bounds();
+            compass_space.fill_uniform(bounds, &Block::from(palette::HUD_COMPASS_BACK))?;
+            let height = bounds.size().y;
+            for &Mark { x, kind } in marks {
+                let tick =
+                    |length| GridAab::from_lower_upper([x, height - length, 0], [x + 1, height, 1]);
+                let (color, region) = match kind {
+                    MarkKind::North => (palette::HUD_COMPASS_NORTH, tick(6)),
+                    MarkKind::Cardinal => (palette::HUD_COMPASS_TICK, tick(6)),
+                    MarkKind::Minor => (palette::HUD_COMPASS_TICK, tick(3)),
+                    MarkKind::Waypoint(color) => (
+                        color,
+                        GridAab::from_lower_upper([x - 1, 2, 0], [x + 2, 8, 1]),
+                    ),
+                };
+                if let Some(region) = region.intersection(bounds) {
+                    compass_space.fill_uniform(region, &Block::from(color))?;
+                }
+            }
+            Ok::<(), Box<dyn Error + Send + Sync>>(())
+        })??;
+        Ok(())
+    }
+}
+
+impl WidgetController for CompassController {
+    fn initialize(&mut self) -> Result<WidgetTransaction, InstallVuiError> {
+        let mut txn = SpaceTransaction::default();
+        let compass_blocks = space_to_blocks(
+            Compass::RESOLUTION,
+            BlockAttributes {
+                animation_hint: AnimationHint::CONTINUOUS,
+                ..BlockAttributes::default()
+            },
+            self.definition.compass_space.clone(),
+        )
+        .unwrap(); // TODO: should be InstallVuiError but we don't have a good way of constructing it
+        let origin: GridPoint = self.position.lower_bounds();
+        for cube in compass_blocks.bounds().interior_iter() {
+            txn.set_overwrite(origin + cube.to_vec(), compass_blocks[cube].clone());
+        }
+        Ok(txn)
+    }
+
+    fn step(&mut self, _tick: Tick) -> Result<WidgetTransaction, Box<dyn Error + Send + Sync>> {
+        // If the character is busy, try again next step.
+        if let Some(marks) = self.definition.marks() {
+            if self.drawn.as_ref() != Some(&marks) {
+                self.draw(&marks)?;
+                self.drawn = Some(marks);
+            }
+        }
+        Ok(WidgetTransaction::default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use all_is_cubes::cgmath::Point3;
+    use all_is_cubes::listen::ListenableCell;
+
+    #[test]
+    fn draws_ticks_and_waypoints() {
+        let mut universe = Universe::new();
+        let space = universe.insert_anonymous(Space::empty_positive(10, 10, 10));
+        let mut character = Character::spawn_default(space.clone());
+        character.body.position = Point3::new(5.0, 0.5, 5.0);
+        character.body.yaw = 0.0;
+        let character = universe.insert_anonymous(character);
+        let red = Rgba::new(1.0, 0.0, 0.0, 1.0);
+        let waypoints = vec![
+            // Straight ahead (north).
+            universe.insert_anonymous(Waypoint::new(space.clone(), [5.0, 0.0, 0.0])),
+            // To the northeast, with an icon.
+            universe.insert_anonymous(
+                Waypoint::new(space.clone(), [10.0, 0.0, 0.0]).with_icon(Block::from(red)),
+            ),
+            // Behind, so not shown.
+            universe.insert_anonymous(Waypoint::new(space, [5.0, 0.0, 10.0])),
+        ];
+        let character_cell = ListenableCell::new(Some(character));
+        let waypoint_cell = ListenableCell::new(waypoints);
+
+        let compass = Compass::new(
+            1,
+            character_cell.as_source(),
+            waypoint_cell.as_source(),
+            &mut universe,
+        );
+        let grant = LayoutGrant::new(GridAab::from_lower_size([0, 0, 0], [1, 1, 1]));
+        let mut controller = compass.clone().controller(&grant);
+        controller.initialize().unwrap();
+        controller.step(Tick::arbitrary()).unwrap();
+
+        let compass_space = compass.compass_space.read().unwrap();
+        let back = Block::from(palette::HUD_COMPASS_BACK);
+        let north = Block::from(palette::HUD_COMPASS_NORTH);
+        let tick = Block::from(palette::HUD_COMPASS_TICK);
+        let marker = Block::from(palette::HUD_WAYPOINT_MARKER);
+        // North is in the center, and northeast halfway to the right edge.
+        assert_eq!(compass_space[[8, 15, 0]], north);
+        assert_eq!(compass_space[[12, 15, 0]], tick);
+        assert_eq!(compass_space[[12, 11, 0]], back);
+        assert_eq!(compass_space[[8, 4, 0]], marker);
+        assert_eq!(compass_space[[12, 4, 0]], Block::from(red));
+        // Behind the character, there is nothing, not even the south tick or waypoint.
+        assert_eq!(compass_space[[0, 4, 0]], back);
+        assert_eq!(compass_space[[0, 15, 0]], back);
+    }
+}
@@ -0,0 +1,265 @@
+use std::collections::VecDeque;
+use std::error::Error;
+use std::sync::{Arc, Mutex};
+
+use all_is_cubes::block::{
+    space_to_blocks, AnimationHint, Block, BlockAttributes, Resolution, AIR,
+};
+use all_is_cubes::camera::GraphicsOptions;
+use all_is_cubes::cgmath::EuclideanSpace as _;
+use all_is_cubes::listen::{DirtyFlag, ListenableSource};
+use all_is_cubes::math::{GridAab, GridCoordinate, GridPoint, GridVector};
+use all_is_cubes::space::{Space, SpacePhysics, SpaceTransaction};
+use all_is_cubes::time::Tick;
+use all_is_cubes::universe::{URef, Universe};
+
+use crate::vui::{
+    InstallVuiError, LayoutGrant, LayoutRequest, Layoutable, Widget, WidgetController,
+    WidgetTransaction,
+};
+
+/// Rolling record of the most recent values of some quantity, for display by a [`Graph`].
+#[derive(Debug)]
+pub(crate) struct GraphData {
+    samples: VecDeque<f32>,
+    capacity: usize,
+    /// Whether `samples` has changed since the graph was last drawn.
+    dirty: bool,
+}
+
+impl GraphData {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            samples: VecDeque::with_capacity(capacity),
+            capacity,
+            dirty: false,
+        }
+    }
+
+    /// Appends a sample, discarding the oldest one if the record is full.
+    pub fn push(&mut self, value: f32) {
+        if self.samples.len() >= self.capacity {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(value);
+        self.dirty = true;
+    }
+
+    /// Returns the samples, oldest first.
+    pub fn samples(&self) -> impl Iterator<Item = f32> + '_ {
+        self.samples.iter().copied()
+    }
+}
+
+/// Widget which displays a [`GraphData`] as a bar chart, one voxel column per sample,
+/// with the newest sample at the right.
+///
+/// The graph is only visible while [`GraphicsOptions::debug_info_text`] is enabled,
+/// since it is diagnostic information of the same sort.
+#[derive(Debug)]
+pub(crate) struct Graph {
+    width_in_hud: GridCoordinate,
+    data: Arc<Mutex<GraphData>>,
+    /// Value which corresponds to a full-height bar. Larger values are clamped.
+    full_scale: f32,
+    bar: Block,
+    background: Block,
+    graphics_options: ListenableSource<GraphicsOptions>,
+    /// Space we draw the bars into.
+    graph_space: URef<Space>,
+}
+
+impl Graph {
+    const RESOLUTION: Resolution = Resolution::R16;
+
+    /// Creates a graph `width_in_hud` cubes wide. `data` should have a capacity of at least
+    /// `width_in_hud * 16` samples to fill the width.
+    pub(crate) fn new(
+        width_in_hud: GridCoordinate,
+        data: Arc<Mutex<GraphData>>,
+        full_scale: f32,
+        bar: Block,
+        background: Block,
+        graphics_options: ListenableSource<GraphicsOptions>,
+        universe: &mut Universe,
+    ) -> Arc<Self> {
+        let graph_space = Space::builder(GridAab::from_lower_size(
+            GridPoint::origin(),
+            GridVector::new(
+                width_in_hud * GridCoordinate::from(Self::RESOLUTION),
+                GridCoordinate::from(Self::RESOLUTION),
+                1,
+            ),
+        ))
+        .physics(SpacePhysics::DEFAULT_FOR_BLOCK)
+        .build();
+        Arc::new(Self {
+            width_in_hud,
+            data,
+            full_scale,
+            bar,
+            background,
+            graphics_options,
+            graph_space: universe.insert_anonymous(graph_space),
+        })
+    }
+
+    /// Number of samples that fit in the width of the graph.
+    pub(crate) fn sample_capacity(width_in_hud: GridCoordinate) -> usize {
+        (width_in_hud * GridCoordinate::from(Self::RESOLUTION)) as usize
+    }
+
+    fn visible(&self) -> bool {
+        self.graphics_options.get().debug_info_text
+    }
+
+    /// Redraws the graph space from `self.data`, if the data has changed.
+    fn draw(&self) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let samples: Vec<f32> = match self.data.try_lock() {
+            Ok(mut data) if data.dirty => {
+                data.dirty = false;
+                data.samples().collect()
+            }
+            _ => return Ok(()),
+        };
+
+        self.graph_space.try_modify(|graph_space| {
+            let bounds = graph_space.bounds();
+            let height = bounds.size().y;
+            graph_space.fill_uniform(bounds, &self.background)?;
+
+            // Align the newest sample with the right edge.
+            let first_column = bounds.upper_bounds().x - samples.len() as GridCoordinate;
+            for (column, value) in (first_column..).zip(samples) {
+                let bar_height = bar_height(value, self.full_scale, height);
+                if column >= bounds.lower_bounds().x && bar_height > 0 {
+                    graph_space.fill_uniform(
+                        GridAab::from_lower_size([column, 0, 0], [1, bar_height, 1]),
+                        &self.bar,
+                    )?;
+                }
+            }
+            Ok::<(), Box<dyn Error + Send + Sync>>(())
+        })??;
+        Ok(())
+    }
+}
+
+/// Height in voxels of the bar for `value`, rounded and clamped to the `height` of the graph.
+fn bar_height(value: f32, full_scale: f32, height: GridCoordinate) -> GridCoordinate {
+    let fraction = value / full_scale;
+    if fraction.is_nan() {
+        0
+    } else {
+        ((fraction * height as f32).round() as GridCoordinate).clamp(0, height)
+    }
+}
+
+impl Layoutable for Graph {
+    fn requirements(&self) -> LayoutRequest {
+        LayoutRequest {
+            minimum: GridVector::new(self.width_in_hud, 1, 1),
+        }
+    }
+}
+
+impl Widget for Graph {
+    fn controller(self: Arc<Self>, grant: &LayoutGrant) -> Box<dyn WidgetController> {
+        Box::new(GraphController {
+            todo: DirtyFlag::listening(false, &self.graphics_options),
+            position: grant
+                .shrink_to(GridVector::new(self.width_in_hud, 1, 1), false)
+                .bounds,
+            definition: self,
+            shown: false,
+        })
+    }
+}
+
+#[derive(Debug)]
+struct GraphController {
+    definition: Arc<Graph>,
+    position: GridAab,
+    /// Dirty flag for visibility changes.
+    todo: DirtyFlag,
+    /// Whether the graph blocks are currently placed.
+    shown: bool,
+}
+
+impl GraphController {
+    fn show_or_hide(&mut self) -> Result<WidgetTransaction, InstallVuiError> {
+        let mut txn = SpaceTransaction::default();
+        let visible = self.definition.visible();
+        if visible == self.shown {
+            return Ok(txn);
+        }
+        self.shown = visible;
+
+        let origin: GridPoint = self.position.lower_bounds();
+        if visible {
+            let graph_blocks = space_to_blocks(
+                Graph::RESOLUTION,
+                BlockAttributes {
+                    animation_hint: AnimationHint::CONTINUOUS,
+                    ..BlockAttributes::default()
+                },
+                self.definition.graph_space.clone(),
+            )
+            .unwrap(); // TODO: should be InstallVuiError but we don't have a good way of constructing it
+            for i in 0..self.position.size().x {
+                txn.set_overwrite(
+                    origin + i * GridVector::unit_x(),
+                    graph_blocks[GridPoint::from_vec(i * GridVector::unit_x())].clone(),
+                );
+            }
+        } else {
+            for i in 0..self.position.size().x {
+                txn.set_overwrite(origin + i * GridVector::unit_x(), AIR);
+            }
+        }
+        Ok(txn)
+    }
+}
+
+impl WidgetController for GraphController {
+    fn initialize(&mut self) -> Result<WidgetTransaction, InstallVuiError> {
+        self.show_or_hide()
+    }
+
+    fn step(&mut self, _tick: Tick) -> Result<WidgetTransaction, Box<dyn Error + Send + Sync>> {
+        let txn = if self.todo.get_and_clear() {
+            self.show_or_hide()?
+        } else {
+            SpaceTransaction::default()
+        };
+        if self.shown {
+            self.definition.draw()?;
+        }
+        Ok(txn)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn graph_data_rolls_over() {
+        let mut data = GraphData::new(3);
+        assert!(!data.dirty);
+        for i in 0..5 {
+            data.push(i as f32);
+        }
+        assert!(data.dirty);
+        assert_eq!(data.samples().collect::<Vec<f32>>(), vec![2.0, 3.0, 4.0]);
+    }
+
+    #[test]
+    fn bar_height_clamps() {
+        assert_eq!(bar_height(0.0, 10.0, 16), 0);
+        assert_eq!(bar_height(5.0, 10.0, 16), 8);
+        assert_eq!(bar_height(100.0, 10.0, 16), 16);
+        assert_eq!(bar_height(-1.0, 10.0, 16), 0);
+        assert_eq!(bar_height(f32::NAN, 10.0, 16), 0);
+    }
+}
@@ -0,0 +1,184 @@
+use std::error::Error;
+use std::sync::Arc;
+
+use all_is_cubes::block::{
+    space_to_blocks, AnimationHint, Block, BlockAttributes, Resolution, AIR,
+};
+use all_is_cubes::cgmath::EuclideanSpace as _;
+use all_is_cubes::character::Character;
+use all_is_cubes::content::palette;
+use all_is_cubes::listen::ListenableSource;
+use all_is_cubes::math::{GridAab, GridCoordinate, GridPoint, GridVector};
+use all_is_cubes::space::{Space, SpacePhysics, SpaceTransaction};
+use all_is_cubes::time::Tick;
+use all_is_cubes::universe::{URef, Universe};
+
+use crate::vui::{
+    InstallVuiError, LayoutGrant, LayoutRequest, Layoutable, Widget, WidgetController,
+    WidgetTransaction,
+};
+
+/// Widget which displays a character's [`Health`](all_is_cubes::character::Health) as a
+/// horizontal bar, filled from the left in proportion to the health remaining.
+///
+/// Nothing is displayed if the character has no health (cannot be damaged).
+#[derive(Debug)]
+pub(crate) struct HealthBar {
+    width_in_hud: GridCoordinate,
+    character_source: ListenableSource<Option<URef<Character>>>,
+    /// Space we draw the bar into.
+    bar_space: URef<Space>,
+}
+
+impl HealthBar {
+    const RESOLUTION: Resolution = Resolution::R16;
+
+    /// Creates a health bar `width_in_hud` cubes wide and one cube high.
+    pub(crate) fn new(
+        width_in_hud: GridCoordinate,
+        character_source: ListenableSource<Option<URef<Character>>>,
+        universe: &mut Universe,
+    ) -> Arc<Self> {
+        let resolution = GridCoordinate::from(Self::RESOLUTION);
+        let bar_space = Space::builder(GridAab::from_lower_size(
+            GridPoint::origin(),
+            GridVector::new(width_in_hud * resolution, resolution, 1),
+        ))
+        .physics(SpacePhysics::DEFAULT_FOR_BLOCK)
+        .build();
+        Arc::new(Self {
+            width_in_hud,
+            character_source,
+            bar_space: universe.insert_anonymous(bar_space),
+        })
+    }
+
+    /// Computes what should be drawn: [`Some`] number of filled voxel columns, or
+    /// [`None`] if there is no bar to draw. Returns [`Err`] if the character cannot
+    /// currently be read.
+    fn filled_columns(&self) -> Result<Option<GridCoordinate>, ()> {
+        let Some(character_ref) = self.character_source.snapshot() else {
+            return Ok(None);
+        };
+        let character = character_ref.read().map_err(|_| ())?;
+        let width = self.width_in_hud * GridCoordinate::from(Self::RESOLUTION);
+        Ok(character.health().map(|health| {
+            // Round up so that any remaining health is visible.
+            (health.fraction() * width as f32).ceil() as GridCoordinate
+        }))
+    }
+}
+
+impl Layoutable for HealthBar {
+    fn requirements(&self) -> LayoutRequest {
+        LayoutRequest {
+            minimum: GridVector::new(self.width_in_hud, 1, 1),
+        }
+    }
+}
+
+impl Widget for HealthBar {
+    fn controller(self: Arc<Self>, grant: &LayoutGrant) -> Box<dyn WidgetController> {
+        Box::new(HealthBarController {
+            position: grant
+                .shrink_to(GridVector::new(self.width_in_hud, 1, 1), false)
+                .bounds,
+            definition: self,
+            drawn: None,
+        })
+    }
+}
+
+#[derive(Debug)]
+struct HealthBarController {
+    definition: Arc<HealthBar>,
+    position: GridAab,
+    /// What was last drawn, or [`None`] if nothing has been drawn yet.
+    drawn: Option<Option<GridCoordinate>>,
+}
+
+impl HealthBarController {
+    fn draw(&self, filled: Option<GridCoordinate>) -> Result<(), Box<dyn Error + Send + Sync>> {
+        self.definition.bar_space.try_modify(|bar_space| {
+            let bounds = bar_space.bounds();
+            bar_space.fill_uniform(bounds, &AIR)?;
+            if let Some(filled) = filled {
+                let size = bounds.size();
+                let bar = |x| GridAab::from_lower_upper([0, 4, 0], [x, size.y - 4, 1]);
+                bar_space.fill_uniform(bar(size.x), &Block::from(palette::HUD_HEALTH_BACK))?;
+                if filled > 0 {
+                    bar_space.fill_uniform(
+                        bar(filled.min(size.x)),
+                        &Block::from(palette::HUD_HEALTH_FILL),
+                    )?;
+                }
+            }
+            Ok::<(), Box<dyn Error + Send + Sync>>(())
+        })??;
+        Ok(())
+    }
+}
+
+impl WidgetController for HealthBarController {
+    fn initialize(&mut self) -> Result<WidgetTransaction, InstallVuiError> {
+        let mut txn = SpaceTransaction::default();
+        let bar_blocks = space_to_blocks(
+            HealthBar::RESOLUTION,
+            BlockAttributes {
+                animation_hint: AnimationHint::CONTINUOUS,
+                ..BlockAttributes::default()
+            },
+            self.definition.bar_space.clone(),
+        )
+        .unwrap(); // TODO: should be InstallVuiError but we don't have a good way of constructing it
+        let origin: GridPoint = self.position.lower_bounds();
+        for cube in bar_blocks.bounds().interior_iter() {
+            txn.set_overwrite(origin + cube.to_vec(), bar_blocks[cube].clone());
+        }
+        Ok(txn)
+    }
+
+    fn step(&mut self, _tick: Tick) -> Result<WidgetTransaction, Box<dyn Error + Send + Sync>> {
+        // If the character is busy, try again next step.
+        if let Ok(filled) = self.definition.filled_columns() {
+            if self.drawn != Some(filled) {
+                self.draw(filled)?;
+                self.drawn = Some(filled);
+            }
+        }
+        Ok(WidgetTransaction::default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use all_is_cubes::listen::ListenableCell;
+
+    #[test]
+    fn draws_health_fraction() {
+        let mut universe = Universe::new();
+        let space = universe.insert_anonymous(Space::empty_positive(10, 10, 10));
+        let mut spawn = space.read().unwrap().spawn().clone();
+        spawn.set_health(Some(4.0));
+        let character = universe.insert_anonymous(Character::spawn(&spawn, space.clone()));
+        let invulnerable = universe.insert_anonymous(Character::spawn_default(space));
+        let character_cell = ListenableCell::new(Some(character));
+
+        let bar = HealthBar::new(1, character_cell.as_source(), &mut universe);
+        let grant = LayoutGrant::new(GridAab::from_lower_size([0, 0, 0], [1, 1, 1]));
+        let mut controller = bar.clone().controller(&grant);
+        controller.initialize().unwrap();
+        controller.step(Tick::arbitrary()).unwrap();
+        {
+            let bar_space = bar.bar_space.read().unwrap();
+            assert_eq!(bar_space[[15, 8, 0]], Block::from(palette::HUD_HEALTH_FILL));
+            assert_eq!(bar_space[[15, 0, 0]], AIR);
+        }
+
+        character_cell.set(Some(invulnerable));
+        controller.step(Tick::arbitrary()).unwrap();
+        let bar_space = bar.bar_space.read().unwrap();
+        assert_eq!(bar_space[[15, 8, 0]], AIR);
+    }
+}
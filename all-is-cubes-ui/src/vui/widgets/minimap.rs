@@ -0,0 +1,224 @@
+use std::error::Error;
+use std::sync::Arc;
+
+use all_is_cubes::block::{space_to_blocks, AnimationHint, Block, BlockAttributes, Resolution};
+use all_is_cubes::cgmath::EuclideanSpace as _;
+use all_is_cubes::character::Character;
+use all_is_cubes::content::palette;
+use all_is_cubes::listen::ListenableSource;
+use all_is_cubes::math::{GridAab, GridCoordinate, GridPoint, GridVector, Rgba};
+use all_is_cubes::space::{Space, SpacePhysics, SpaceTransaction, TopDownMap};
+use all_is_cubes::time::Tick;
+use all_is_cubes::universe::{URef, Universe};
+
+use crate::vui::{
+    InstallVuiError, LayoutGrant, LayoutRequest, Layoutable, Widget, WidgetController,
+    WidgetTransaction,
+};
+
+/// Widget which displays a [`TopDownMap`] of the area around a character, with the
+/// character at the center and −Z up.
+#[derive(Debug)]
+pub(crate) struct Minimap {
+    size_in_hud: GridCoordinate,
+    character_source: ListenableSource<Option<URef<Character>>>,
+    /// Space we draw the map into, one voxel per column of the character's space.
+    map_space: URef<Space>,
+}
+
+impl Minimap {
+    const RESOLUTION: Resolution = Resolution::R32;
+
+    /// Creates a minimap `size_in_hud` cubes wide and high.
+    pub(crate) fn new(
+        size_in_hud: GridCoordinate,
+        character_source: ListenableSource<Option<URef<Character>>>,
+        universe: &mut Universe,
+    ) -> Arc<Self> {
+        let size_in_voxels = size_in_hud * GridCoordinate::from(Self::RESOLUTION);
+        let map_space = Space::builder(GridAab::from_lower_size(
+            GridPoint::origin(),
+            GridVector::new(size_in_voxels, size_in_voxels, 1),
+        ))
+        .physics(SpacePhysics::DEFAULT_FOR_BLOCK)
+        .build();
+        Arc::new(Self {
+            size_in_hud,
+            character_source,
+            map_space: universe.insert_anonymous(map_space),
+        })
+    }
+}
+
+impl Layoutable for Minimap {
+    fn requirements(&self) -> LayoutRequest {
+        LayoutRequest {
+            minimum: GridVector::new(self.size_in_hud, self.size_in_hud, 1),
+        }
+    }
+}
+
+impl Widget for Minimap {
+    fn controller(self: Arc<Self>, grant: &LayoutGrant) -> Box<dyn WidgetController> {
+        Box::new(MinimapController {
+            position: grant
+                .shrink_to(
+                    GridVector::new(self.size_in_hud, self.size_in_hud, 1),
+                    false,
+                )
+                .bounds,
+            definition: self,
+            map: None,
+            drawn_center: None,
+        })
+    }
+}
+
+#[derive(Debug)]
+struct MinimapController {
+    definition: Arc<Minimap>,
+    position: GridAab,
+    /// Map of the space the character is in, if there is a character.
+    map: Option<TopDownMap>,
+    /// Column the map was last drawn centered on, or [`None`] if it has not been drawn
+    /// or was drawn empty.
+    drawn_center: Option<[GridCoordinate; 2]>,
+}
+
+impl MinimapController {
+    /// Returns the space and current column of the character, or [`None`] if there is
+    /// no character or it cannot currently be read.
+    fn character_location(&self) -> Option<(URef<Space>, [GridCoordinate; 2])> {
+        let character_ref = self.definition.character_source.snapshot()?;
+        let character = character_ref.read().ok()?;
+        let position = character.body.position;
+        Some((
+            character.space.clone(),
+            [
+                position.x.floor() as GridCoordinate,
+                position.z.floor() as GridCoordinate,
+            ],
+        ))
+    }
+
+    /// Redraws the map space, centered on `center`, or empty if `center` is [`None`].
+    fn draw(
+        &self,
+        center: Option<[GridCoordinate; 2]>,
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        self.definition.map_space.try_modify(|map_space| {
+            let bounds = map_space.bounds();
+            let background = Block::from(palette::HUD_MINIMAP_BACK);
+            map_space.fill_uniform(bounds, &background)?;
+            let (Some(map), Some([center_x, center_z])) = (&self.map, center) else {
+                return Ok(());
+            };
+
+            let size = bounds.size().x;
+            let half = size / 2;
+            for y in 0..size {
+                for x in 0..size {
+                    // Voxel +Y is up on screen, which is map −Z.
+                    let column_x = center_x + x - half;
+                    let column_z = center_z + (size - 1 - y) - half;
+                    let Some([px, py]) = map.pixel_for_column(column_x, column_z) else {
+                        continue;
+                    };
+                    let pixel = map.image().get_pixel(px, py).0;
+                    if pixel[3] > 0 {
+                        map_space.set([x, y, 0], Block::from(Rgba::from_srgb8(pixel)))?;
+                    }
+                }
+            }
+            map_space.set(
+                [half, size - 1 - half, 0],
+                Block::from(palette::HUD_MINIMAP_MARKER),
+            )?;
+            Ok::<(), Box<dyn Error + Send + Sync>>(())
+        })??;
+        Ok(())
+    }
+}
+
+impl WidgetController for MinimapController {
+    fn initialize(&mut self) -> Result<WidgetTransaction, InstallVuiError> {
+        let mut txn = SpaceTransaction::default();
+        let map_blocks = space_to_blocks(
+            Minimap::RESOLUTION,
+            BlockAttributes {
+                animation_hint: AnimationHint::CONTINUOUS,
+                ..BlockAttributes::default()
+            },
+            self.definition.map_space.clone(),
+        )
+        .unwrap(); // TODO: should be InstallVuiError but we don't have a good way of constructing it
+        let origin: GridPoint = self.position.lower_bounds();
+        for cube in map_blocks.bounds().interior_iter() {
+            txn.set_overwrite(origin + cube.to_vec(), map_blocks[cube].clone());
+        }
+        Ok(txn)
+    }
+
+    fn step(&mut self, _tick: Tick) -> Result<WidgetTransaction, Box<dyn Error + Send + Sync>> {
+        let Some((space, center)) = self.character_location() else {
+            if self.drawn_center.is_some() || self.map.is_some() {
+                self.map = None;
+                self.drawn_center = None;
+                self.draw(None)?;
+            }
+            return Ok(WidgetTransaction::default());
+        };
+
+        if self.map.as_ref().map(TopDownMap::space) != Some(&space) {
+            self.map = Some(TopDownMap::new(space));
+            self.drawn_center = None;
+        }
+        let map_changed = match self.map.as_mut().unwrap().update() {
+            Ok(changed) => changed,
+            // The space may be temporarily busy; try again next step.
+            Err(_) => return Ok(WidgetTransaction::default()),
+        };
+
+        if map_changed || self.drawn_center != Some(center) {
+            self.draw(Some(center))?;
+            self.drawn_center = Some(center);
+        }
+        Ok(WidgetTransaction::default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use all_is_cubes::cgmath::Point3;
+    use all_is_cubes::listen::ListenableCell;
+
+    #[test]
+    fn draws_character_surroundings() {
+        let mut universe = Universe::new();
+        let block = Block::from(Rgba::new(1.0, 0.0, 0.0, 1.0));
+        let mut space = Space::empty_positive(3, 1, 3);
+        space.set([0, 0, 0], &block).unwrap();
+        let space = universe.insert_anonymous(space);
+        let mut character = Character::spawn_default(space);
+        character.body.position = Point3::new(1.5, 0.5, 1.5);
+        let character = universe.insert_anonymous(character);
+        let character_cell = ListenableCell::new(Some(character));
+
+        let minimap = Minimap::new(1, character_cell.as_source(), &mut universe);
+        let grant = LayoutGrant::new(GridAab::from_lower_size([0, 0, 0], [1, 1, 1]));
+        let mut controller = minimap.clone().controller(&grant);
+        controller.initialize().unwrap();
+        controller.step(Tick::arbitrary()).unwrap();
+
+        let map_space = minimap.map_space.read().unwrap();
+        // The character is at the center, and the block is one cube west (−X) and
+        // north (−Z) of it.
+        assert_eq!(
+            map_space[[16, 15, 0]],
+            Block::from(palette::HUD_MINIMAP_MARKER)
+        );
+        assert_eq!(map_space[[15, 16, 0]], block);
+        assert_eq!(map_space[[0, 0, 0]], Block::from(palette::HUD_MINIMAP_BACK));
+    }
+}
@@ -13,7 +13,7 @@ use all_is_cubes::drawing::embedded_graphics::{
     text::{Alignment, Baseline, Text, TextStyleBuilder},
     Drawable,
 };
-use all_is_cubes::inv::{Slot, TOOL_SELECTIONS};
+use all_is_cubes::inv::{Hotbar, Slot, TOOL_SELECTIONS};
 use all_is_cubes::listen::{DirtyFlag, Gate, Listen as _, ListenableSource, Listener};
 use all_is_cubes::math::{FaceMap, GridAab, GridCoordinate, GridMatrix, GridPoint, GridVector};
 use all_is_cubes::space::{Space, SpacePhysics, SpaceTransaction};
@@ -28,7 +28,10 @@ use crate::vui::{
     WidgetController, WidgetTransaction,
 };
 
-/// Widget that displays inventory contents in toolbar format.
+/// Widget that displays the inventory slots chosen by a [`Hotbar`] in toolbar format.
+///
+/// The number of positions is taken from the hotbar when the widget is constructed;
+/// the widget must be rebuilt if that changes.
 ///
 /// TODO: We may or may not want to expand this to handle general inventory viewing
 #[derive(Debug)]
@@ -37,6 +40,8 @@ pub(crate) struct Toolbar {
     /// Which character we display the inventory of
     character_source: ListenableSource<Option<URef<Character>>>,
     cue_channel: CueNotifier,
+    /// Which inventory slots we display at each position
+    hotbar: ListenableSource<Hotbar>,
 
     slot_count: usize,
     /// Space for drawing per-slot text labels
@@ -51,10 +56,11 @@ impl Toolbar {
     pub fn new(
         character_source: ListenableSource<Option<URef<Character>>>,
         hud_blocks: Arc<HudBlocks>,
-        slot_count: usize,
+        hotbar: ListenableSource<Hotbar>,
         universe: &mut Universe,
         cue_channel: CueNotifier,
     ) -> Arc<Self> {
+        let slot_count = hotbar.get().len();
         let slot_text_resolution = Resolution::R32;
         let slot_text_space = universe.insert_anonymous(
             Space::builder(GridAab::from_lower_size(
@@ -73,6 +79,7 @@ impl Toolbar {
             hud_blocks,
             character_source,
             cue_channel,
+            hotbar,
             slot_text_resolution,
             slot_text_space,
             slot_count,
@@ -98,6 +105,7 @@ impl Widget for Toolbar {
 
         let todo_change_character = DirtyFlag::listening(false, &self.character_source);
         let todo_inventory = DirtyFlag::new(true);
+        let todo_hotbar = DirtyFlag::listening(false, &self.hotbar);
         let todo_more = Arc::new(Mutex::new(ToolbarTodo {
             button_pressed_decay: [Duration::ZERO; TOOL_SELECTIONS],
         }));
@@ -115,6 +123,7 @@ impl Widget for Toolbar {
         Box::new(ToolbarController {
             todo_change_character,
             todo_inventory,
+            todo_hotbar,
             todo_more,
             character,
             character_listener_gate,
@@ -136,6 +145,7 @@ struct ToolbarController {
     definition: Arc<Toolbar>,
     todo_change_character: DirtyFlag,
     todo_inventory: DirtyFlag,
+    todo_hotbar: DirtyFlag,
     todo_more: Arc<Mutex<ToolbarTodo>>,
     /// Latest character we've fetched from character_source,
     /// and the character whose inventory changes todo_inventory is tracking
@@ -146,16 +156,25 @@ struct ToolbarController {
 }
 
 impl ToolbarController {
-    fn slot_position(&self, slot_index: usize) -> GridPoint {
-        self.first_slot_position + GridVector::unit_x() * 2 * slot_index as GridCoordinate
+    fn slot_position(&self, position: usize) -> GridPoint {
+        self.first_slot_position + GridVector::unit_x() * 2 * position as GridCoordinate
     }
 
     /// Returns a transaction to draw items and their stack counts, without using self.character
     /// but only the given inputs.
     fn write_items(
         &self,
+        hotbar: &Hotbar,
         slots: &[Slot],
     ) -> Result<WidgetTransaction, Box<dyn Error + Send + Sync>> {
+        // Positions beyond the end of the hotbar or inventory are drawn as empty.
+        let stack_at = |position: usize| {
+            hotbar
+                .slot(position)
+                .and_then(|index| slots.get(index))
+                .unwrap_or(&Slot::Empty)
+        };
+
         // Update stack count text.
         // TODO: This needs to stop being direct modification, eventually, at least if
         // we want to have parallel updates.
@@ -169,7 +188,7 @@ impl ToolbarController {
             let plane = &mut text_space.draw_target(GridMatrix::FLIP_Y);
             for index in 0..self.definition.slot_count {
                 Text::with_text_style(
-                    &match slots.get(index).unwrap_or(&Slot::Empty).count() {
+                    &match stack_at(index).count() {
                         0 | 1 => String::default(),
                         count => format!("{count}"),
                     },
@@ -192,18 +211,16 @@ impl ToolbarController {
         })?;
 
         let mut txn = SpaceTransaction::default();
-        for (index, stack) in slots.iter().enumerate() {
-            if index >= self.definition.slot_count {
-                // TODO: must clear nonexistent positions, eventually
-                break;
-            }
-
-            let position = self.slot_position(index);
+        for index in 0..self.definition.slot_count {
             // Draw icon
             txn.set(
-                position,
+                self.slot_position(index),
                 None,
-                Some(stack.icon(&self.definition.hud_blocks.icons).into_owned()),
+                Some(
+                    stack_at(index)
+                        .icon(&self.definition.hud_blocks.icons)
+                        .into_owned(),
+                ),
             )?;
         }
 
@@ -213,18 +230,16 @@ impl ToolbarController {
     /// Returns a transaction to draw the selected-slot pointers.
     fn write_pointers(
         &self,
+        hotbar: &Hotbar,
         selected_slots: &[usize],
         pressed: [bool; TOOL_SELECTIONS],
     ) -> Result<WidgetTransaction, Box<dyn Error + Send + Sync>> {
         let mut txn = SpaceTransaction::default();
         for index in 0..self.definition.slot_count {
             let position = self.slot_position(index);
+            let slot = hotbar.slot(index);
             let this_slot_selected_mask = std::array::from_fn(|sel| {
-                if selected_slots
-                    .get(sel)
-                    .map(|&i| i == index)
-                    .unwrap_or(false)
-                {
+                if slot.is_some() && selected_slots.get(sel).copied() == slot {
                     if pressed[sel] {
                         ToolbarButtonState::Pressed
                     } else {
@@ -331,6 +346,10 @@ impl WidgetController for ToolbarController {
             self.character_listener_gate = gate;
             self.todo_inventory.set();
         }
+        if self.todo_hotbar.get_and_clear() {
+            self.todo_inventory.set();
+        }
+        let hotbar = self.definition.hotbar.get();
 
         // Extract button pressed state from todo (don't hold the lock more than necessary)
         let mut pressed_buttons: [bool; TOOL_SELECTIONS] = [false; TOOL_SELECTIONS];
@@ -353,7 +372,7 @@ impl WidgetController for ToolbarController {
         let slots_txn = if should_update_inventory {
             if let Some(inventory_source) = &self.character {
                 let character = inventory_source.read().unwrap();
-                self.write_items(&character.inventory().slots)?
+                self.write_items(&hotbar, &character.inventory().slots)?
             } else {
                 // TODO: clear toolbar ... once self.inventory_source can transition from Some to None at all
                 WidgetTransaction::default()
@@ -367,9 +386,9 @@ impl WidgetController for ToolbarController {
         let pointers_txn = if should_update_inventory || should_update_pointers {
             if let Some(inventory_source) = &self.character {
                 let character = inventory_source.read().unwrap();
-                self.write_pointers(&character.selected_slots(), pressed_buttons)?
+                self.write_pointers(&hotbar, &character.selected_slots(), pressed_buttons)?
             } else {
-                self.write_pointers(&[], pressed_buttons)?
+                self.write_pointers(&hotbar, &[], pressed_buttons)?
             }
         } else {
             WidgetTransaction::default()
@@ -19,6 +19,7 @@ use all_is_cubes::space::{Space, SpacePhysics, SpaceTransaction};
 use all_is_cubes::time::Tick;
 use all_is_cubes::universe::{URef, Universe};
 
+use crate::i18n::Catalog;
 use crate::vui::hud::{HudBlocks, HudFont};
 use crate::vui::{LayoutRequest, Layoutable, Widget, WidgetController, WidgetTransaction};
 
@@ -53,6 +54,7 @@ impl TooltipState {
                         this.dirty_inventory = true;
                     }
                 }
+                CharacterChange::Space => {}
             })
             .gate();
 
@@ -71,6 +73,23 @@ impl TooltipState {
         self.set_contents(TooltipContents::Message(text))
     }
 
+    /// Shows the text being typed into the command console, until this is called with
+    /// [`None`]. Unlike other contents, it does not time out.
+    pub fn set_console_line(&mut self, line: Option<&str>) {
+        match (line, &self.current_contents) {
+            (Some(line), TooltipContents::Console(shown)) if **shown == *line => {}
+            (Some(line), _) => {
+                self.set_contents(TooltipContents::Console(line.into()));
+                self.age = None;
+            }
+            (None, TooltipContents::Console(_)) => {
+                self.set_contents(TooltipContents::Blanked);
+                self.age = None;
+            }
+            (None, _) => {}
+        }
+    }
+
     fn set_contents(&mut self, contents: TooltipContents) {
         self.dirty_text = true;
         self.current_contents = contents;
@@ -78,7 +97,7 @@ impl TooltipState {
     }
 
     /// Advances time and returns the string that should be newly written to the screen, if different than the previous call.
-    fn step(&mut self, hud_blocks: &HudBlocks, tick: Tick) -> Option<Arc<str>> {
+    fn step(&mut self, hud_blocks: &HudBlocks, catalog: &Catalog, tick: Tick) -> Option<Arc<str>> {
         if let Some(ref mut age) = self.age {
             *age += tick.delta_t();
             if *age > Duration::from_secs(1) {
@@ -102,7 +121,11 @@ impl TooltipState {
                         .icon(&hud_blocks.icons)
                         .evaluate()
                         .ok()
-                        .map(|ev_block| ev_block.attributes.display_name.into_owned().into())
+                        .map(|ev_block| {
+                            catalog
+                                .display_name(&ev_block.attributes.display_name)
+                                .into()
+                        })
                         .unwrap_or_else(|| EMPTY_ARC_STR.clone());
                     let new_contents = TooltipContents::InventoryItem {
                         source_slot: selected_slot,
@@ -118,7 +141,9 @@ impl TooltipState {
                         //     self.last_inventory_message,
                         //     new_contents
                         // );
-                        if self.last_inventory_message != TooltipContents::JustStartedExisting {
+                        if self.last_inventory_message != TooltipContents::JustStartedExisting
+                            && !matches!(self.current_contents, TooltipContents::Console(_))
+                        {
                             self.set_contents(new_contents.clone());
                         }
                         self.last_inventory_message = new_contents;
@@ -162,6 +187,8 @@ enum TooltipContents {
     JustStartedExisting,
     Blanked,
     Message(Arc<str>),
+    /// Text being typed into the command console.
+    Console(Arc<str>),
     InventoryItem {
         source_slot: usize,
         text: Arc<str>,
@@ -172,7 +199,7 @@ impl TooltipContents {
     fn text(&self) -> &Arc<str> {
         match self {
             TooltipContents::JustStartedExisting | TooltipContents::Blanked => &EMPTY_ARC_STR,
-            TooltipContents::Message(m) => m,
+            TooltipContents::Message(m) | TooltipContents::Console(m) => m,
             TooltipContents::InventoryItem { text, .. } => text,
         }
     }
@@ -182,6 +209,8 @@ impl TooltipContents {
 pub(crate) struct Tooltip {
     width_in_hud: GridCoordinate,
     hud_blocks: Arc<HudBlocks>,
+    /// Language to display the names of inventory items in.
+    catalog: &'static Catalog,
     /// Tracks what we should be displaying and serves as dirty flag.
     state: Arc<Mutex<TooltipState>>,
     /// Space we write the text into.
@@ -194,6 +223,7 @@ impl Tooltip {
     pub(crate) fn new(
         state: Arc<Mutex<TooltipState>>,
         hud_blocks: Arc<HudBlocks>,
+        catalog: &'static Catalog,
         universe: &mut Universe,
     ) -> Arc<Self> {
         let width_in_hud = 25; // TODO: magic number
@@ -210,6 +240,7 @@ impl Tooltip {
         Arc::new(Self {
             width_in_hud,
             hud_blocks,
+            catalog,
             state,
             text_space: universe.insert_anonymous(text_space),
         })
@@ -275,12 +306,10 @@ impl WidgetController for TooltipController {
 
     fn step(&mut self, tick: Tick) -> Result<WidgetTransaction, Box<dyn Error + Send + Sync>> {
         // None if no update is needed
-        let text_update: Option<Arc<str>> = self
-            .definition
-            .state
-            .try_lock()
-            .ok()
-            .and_then(|mut state| state.step(&self.definition.hud_blocks, tick));
+        let text_update: Option<Arc<str>> =
+            self.definition.state.try_lock().ok().and_then(|mut state| {
+                state.step(&self.definition.hud_blocks, self.definition.catalog, tick)
+            });
 
         if let Some(text) = text_update {
             self.definition.text_space.try_modify(|text_space| {
@@ -311,13 +340,21 @@ impl WidgetController for TooltipController {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::i18n::Language;
+    use all_is_cubes::camera::UiPaletteOption;
     use all_is_cubes::util::YieldProgress;
 
     #[tokio::test]
     async fn tooltip_timeout_and_dirty_text() {
         // TODO: reduce boilerplate
         let mut universe = Universe::new();
-        let hud_blocks = &HudBlocks::new(&mut universe, YieldProgress::noop()).await;
+        let hud_blocks = &HudBlocks::new(
+            &mut universe,
+            Language::English.catalog(),
+            &UiPaletteOption::Standard,
+            YieldProgress::noop(),
+        )
+        .await;
 
         // Initial state: no update.
         let mut t = TooltipState::default();
@@ -344,4 +381,36 @@ mod tests {
         // Empty string is only emitted from step() once.
         assert_eq!(t.step(hud_blocks, Tick::from_seconds(2.00)), None);
     }
+
+    #[tokio::test]
+    async fn tooltip_console_line() {
+        let mut universe = Universe::new();
+        let hud_blocks = &HudBlocks::new(
+            &mut universe,
+            Language::English.catalog(),
+            &UiPaletteOption::Standard,
+            YieldProgress::noop(),
+        )
+        .await;
+        let mut t = TooltipState::default();
+
+        t.set_console_line(Some("/t"));
+        assert_eq!(
+            t.step(hud_blocks, Tick::from_seconds(0.5)),
+            Some("/t".into())
+        );
+        // Unchanged text is not redrawn, and does not time out.
+        t.set_console_line(Some("/t"));
+        assert_eq!(t.step(hud_blocks, Tick::from_seconds(5.0)), None);
+
+        t.set_console_line(None);
+        assert_eq!(t.step(hud_blocks, Tick::from_seconds(0.5)), Some("".into()));
+        // Closing the console does not blank other messages.
+        t.set_message("Hello world".into());
+        t.set_console_line(None);
+        assert_eq!(
+            t.step(hud_blocks, Tick::from_seconds(0.5)),
+            Some("Hello world".into())
+        );
+    }
 }
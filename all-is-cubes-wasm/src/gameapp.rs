@@ -3,27 +3,31 @@ use std::rc::{Rc, Weak};
 use std::sync::Arc;
 use std::time::Duration;
 
-use js_sys::{ArrayBuffer, Error, Uint8Array};
+use js_sys::{Error, Uint8Array};
 use rand::{thread_rng, Rng as _};
 use send_wrapper::SendWrapper;
 use wasm_bindgen::prelude::{wasm_bindgen, Closure, JsValue};
 use wasm_bindgen::JsCast; // dyn_into()
 use wasm_bindgen_futures::{spawn_local, JsFuture};
 use web_sys::{
-    console, AddEventListenerOptions, DataTransferItem, Document, DragEvent, Element, Event,
-    FocusEvent, HtmlElement, HtmlProgressElement, KeyboardEvent, MouseEvent, Text,
+    console, AddEventListenerOptions, DataTransferItem, Document, DragEvent, Element, Event, File,
+    FocusEvent, HtmlElement, HtmlProgressElement, KeyboardEvent, MouseEvent, Response, Text,
+    WheelEvent,
 };
 
 use all_is_cubes::camera::{GraphicsOptions, StandardCameras, Viewport};
 use all_is_cubes::cgmath::{Point2, Vector2};
 use all_is_cubes::listen::ListenableCell;
-use all_is_cubes::universe::UniverseStepInfo;
+use all_is_cubes::universe::{Universe, UniverseStepInfo};
 use all_is_cubes::util::YieldProgress;
+use all_is_cubes_content::UniverseTemplate;
 use all_is_cubes_gpu::in_wgpu;
 use all_is_cubes_port::file::NonDiskFile;
-use all_is_cubes_ui::apps::{CursorIcon, Key, Session};
+use all_is_cubes_port::{ExportFormat, ExportSet};
+use all_is_cubes_ui::apps::{CursorIcon, Key, Session, UniverseLoadError};
 
 use crate::js_bindings::{make_all_static_gui_helpers, GuiHelpers};
+use crate::storage::{self, SaveStore};
 use crate::url_params::{options_from_query_string, OptionsInUrl, RendererOption};
 use crate::web_glue::{
     add_event_listener, get_mandatory_element, replace_children_with_one_text_node,
@@ -105,11 +109,7 @@ async fn start_game_with_dom(
     gui_helpers: GuiHelpers,
     static_dom: &StaticDom,
 ) -> Result<(), Box<dyn std::error::Error>> {
-    let progress = YieldProgress::new(yield_to_event_loop, {
-        let progress_bar = SendWrapper::new(static_dom.progress_bar.clone());
-        // TODO: hook up label
-        move |fraction, _label| progress_bar.set_value(fraction.into())
-    });
+    let progress = static_dom.progress();
     let [app_progress, progress] = progress.split(0.1);
     let [universe_progress, post_universe_progress] = progress.split(0.98);
 
@@ -118,9 +118,20 @@ async fn start_game_with_dom(
         .map_or_else(String::new, |q| q.search().unwrap_or_default());
     let OptionsInUrl {
         template,
+        universe_url,
+        save_name,
         graphics_options,
         renderer: renderer_option,
     } = options_from_query_string(query_string.trim_start_matches('?').as_bytes());
+    // A page may also specify a universe itself, as pages written by
+    // `aic-server export-static` do.
+    let universe_url = universe_url.or_else(|| {
+        document
+            .query_selector(r#"meta[name="all-is-cubes-universe"]"#)
+            .ok()
+            .flatten()
+            .and_then(|meta| meta.get_attribute("content"))
+    });
 
     static_dom.append_to_loading_log("\nInitializing application...");
     app_progress.progress(0.2).await;
@@ -131,6 +142,7 @@ async fn start_game_with_dom(
     app_progress.progress(0.4).await;
 
     let cameras = session.create_cameras(viewport_cell.as_source());
+    let graphics_description;
     let renderer = match renderer_option {
         RendererOption::Wgpu => {
             let wgpu_instance = wgpu::Instance::new(wgpu::InstanceDescriptor::default());
@@ -146,11 +158,31 @@ async fn start_game_with_dom(
                 })
                 .await
                 .ok_or("Could not request suitable graphics adapter")?;
+            let adapter_info = adapter.get_info();
+            log::debug!("Adapter: {adapter_info:?}");
+            graphics_description = format!(
+                "Graphics: {:?} on {}",
+                adapter_info.backend, adapter_info.name
+            );
             let renderer = in_wgpu::SurfaceRenderer::new(cameras, surface, &adapter).await?;
             WebRenderer::Wgpu(renderer)
         }
     };
 
+    static_dom.append_to_loading_log("\nOpening saved universes...");
+    app_progress.progress(0.7).await;
+    let saves = match SaveStore::open().await {
+        Ok(saves) => Some(Rc::new(saves)),
+        Err(e) => {
+            console::warn_2(
+                &JsValue::from_str("saving universes in the browser is unavailable:"),
+                &e,
+            );
+            static_dom.saves_overlay.set_hidden(true);
+            None
+        }
+    };
+
     static_dom.append_to_loading_log("\nStarting game loop...");
     app_progress.progress(0.8).await;
     let root = WebGameRoot::new(
@@ -160,22 +192,61 @@ async fn start_game_with_dom(
         renderer,
         viewport_cell,
         fullscreen_cell,
+        graphics_description,
+        saves.clone(),
     );
     root.borrow().start_loop();
+    if let Some(saves) = &saves {
+        spawn_local(update_saves_list(Rc::downgrade(&root), saves.clone()));
+    }
+
+    // If no other universe was asked for, continue with the one last used.
+    let save_is_remembered = save_name.is_none() && template.is_none();
+    let mut save_name = save_name.or_else(|| {
+        if template.is_none() {
+            storage::last_save_name()
+        } else {
+            None
+        }
+    });
 
-    static_dom.append_to_loading_log("\nConstructing universe...");
     app_progress.finish().await;
-    let universe = template
-        .build(
+    let universe = if let Some(universe_url) = universe_url {
+        static_dom.append_to_loading_log("\nLoading universe...");
+        let data = fetch_bytes(&universe_url).await?;
+        all_is_cubes_port::load_universe_from_file(
             universe_progress,
-            all_is_cubes_content::TemplateParameters {
-                seed: thread_rng().gen(),
-                size: None,
-            },
+            &NonDiskFile::from_name_and_data_source(universe_url, move || Ok(data.clone())),
         )
-        .await
-        .expect("universe template error");
-    root.borrow_mut().session.set_universe(universe);
+        .await?
+    } else if let (Some(name), Some(saves)) = (save_name.clone(), &saves) {
+        static_dom.append_to_loading_log(&format!("\nLoading saved universe {name:?}..."));
+        if save_is_remembered {
+            // The user didn't ask for this save, so failing to load it should not
+            // prevent starting at all.
+            let [load_progress, fallback_progress] = universe_progress.split(0.5);
+            match load_saved_universe(load_progress, saves, &name).await {
+                Ok(universe) => universe,
+                Err(e) => {
+                    log::error!("failed to load last saved universe {name:?}: {e}");
+                    storage::forget_last_save_name();
+                    save_name = None;
+                    static_dom.append_to_loading_log("\nConstructing universe instead...");
+                    build_template_universe(fallback_progress, template).await
+                }
+            }
+        } else {
+            load_saved_universe(universe_progress, saves, &name).await?
+        }
+    } else {
+        static_dom.append_to_loading_log("\nConstructing universe...");
+        build_template_universe(universe_progress, template).await
+    };
+    {
+        let mut root = root.borrow_mut();
+        root.session.set_universe(universe);
+        root.save_name = Some(save_name.unwrap_or_else(|| String::from(AUTOSAVE_NAME)));
+    }
 
     // Explicitly keep the game loop alive.
     Box::leak(Box::new(root));
@@ -193,6 +264,86 @@ async fn start_game_with_dom(
     Ok(())
 }
 
+/// Fetches the contents of `url`, which may be relative to the page.
+async fn fetch_bytes(url: &str) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+    let fetch_error = |e: JsValue| format!("failed to fetch {url:?}: {e:?}");
+    let window = web_sys::window().ok_or("no window")?;
+    let response: Response = JsFuture::from(window.fetch_with_str(url))
+        .await
+        .map_err(fetch_error)?
+        .dyn_into()
+        .map_err(fetch_error)?;
+    if !response.ok() {
+        return Err(format!(
+            "failed to fetch {url:?}: {} {}",
+            response.status(),
+            response.status_text()
+        )
+        .into());
+    }
+    let buffer = JsFuture::from(response.array_buffer().map_err(fetch_error)?)
+        .await
+        .map_err(fetch_error)?;
+    Ok(Uint8Array::new(&buffer).to_vec())
+}
+
+/// Name under which a universe is saved if the user has not chosen one.
+const AUTOSAVE_NAME: &str = "Autosave";
+
+/// Loads the universe saved in `saves` under `name`.
+async fn load_saved_universe(
+    progress: YieldProgress,
+    saves: &SaveStore,
+    name: &str,
+) -> Result<Universe, Box<dyn std::error::Error>> {
+    let data = saves
+        .get(name)
+        .await
+        .map_err(|e| format!("failed to read saved universe {name:?}: {e:?}"))?
+        .ok_or_else(|| format!("there is no saved universe named {name:?}"))?;
+    Ok(all_is_cubes_port::load_universe_from_file(
+        progress,
+        &NonDiskFile::from_name_and_data_source(name.to_owned(), move || Ok(data.clone())),
+    )
+    .await?)
+}
+
+/// Builds a new universe from `template`, or the default template if [`None`].
+async fn build_template_universe(
+    progress: YieldProgress,
+    template: Option<UniverseTemplate>,
+) -> Universe {
+    template
+        .unwrap_or_default()
+        .build(
+            progress,
+            all_is_cubes_content::TemplateParameters {
+                seed: thread_rng().gen(),
+                size: None,
+            },
+        )
+        .await
+        .expect("universe template error")
+}
+
+/// Fetches the names of the saves in `saves` and displays them.
+async fn update_saves_list(weak_self_ref: Weak<RefCell<WebGameRoot>>, saves: Rc<SaveStore>) {
+    match saves.names().await {
+        Ok(names) => {
+            WebGameRoot::upgrade_in_callback(&weak_self_ref, |this| this.show_saves(names))
+        }
+        Err(e) => console::error_2(&JsValue::from_str("failed to list saved universes"), &e),
+    }
+}
+
+thread_local! {
+    /// While [`WebGameRoot::step_task()`] is running, callbacks which need the
+    /// [`WebGameRoot`], which it has borrowed, are queued here to be run when it finishes.
+    #[allow(clippy::type_complexity)]
+    static DEFERRED_CALLBACKS: RefCell<Option<Vec<Box<dyn FnOnce(&mut WebGameRoot)>>>> =
+        RefCell::new(None);
+}
+
 struct WebGameRoot {
     /// In order to be able to set up callbacks to ourselves, we need to live in a mutable
     /// heap-allocated location, and we need to have a reference to that location. In
@@ -208,11 +359,18 @@ struct WebGameRoot {
     renderer: WebRenderer,
     viewport_cell: ListenableCell<Viewport>,
     fullscreen_cell: ListenableCell<Option<bool>>,
+    /// Which graphics API and adapter are in use, to be shown in the info text.
+    graphics_description: String,
     raf_callback: Closure<dyn FnMut(f64)>,
     step_callback: Closure<dyn FnMut()>,
     step_callback_scheduled: bool,
     last_raf_timestamp: f64,
     last_step_info: UniverseStepInfo,
+    /// Storage for saving universes, if the browser allows it.
+    saves: Option<Rc<SaveStore>>,
+    /// Name under which the current universe was last saved or loaded, and will be
+    /// saved again when the page is hidden. [`None`] until loading is complete.
+    save_name: Option<String>,
 }
 
 impl WebGameRoot {
@@ -223,6 +381,8 @@ impl WebGameRoot {
         renderer: WebRenderer,
         viewport_cell: ListenableCell<Viewport>,
         fullscreen_cell: ListenableCell<Option<bool>>,
+        graphics_description: String,
+        saves: Option<Rc<SaveStore>>,
     ) -> Rc<RefCell<WebGameRoot>> {
         // Construct a non-self-referential initial mutable object.
         let self_cell_ref = Rc::new(RefCell::new(Self {
@@ -234,11 +394,14 @@ impl WebGameRoot {
             renderer,
             viewport_cell,
             fullscreen_cell,
+            graphics_description,
             raf_callback: Closure::wrap(Box::new(|_| { /* dummy no-op for initialization */ })),
             step_callback: Closure::wrap(Box::new(|| { /* dummy no-op for initialization */ })),
             step_callback_scheduled: false,
             last_raf_timestamp: 0.0, // TODO better initial value or special case
             last_step_info: UniverseStepInfo::default(),
+            saves,
+            save_name: None,
         }));
 
         // Add the self-references.
@@ -266,35 +429,39 @@ impl WebGameRoot {
 
     /// This method is broken out of new() so we can just use `self`. Well, some of the time.
     fn init_dom(&self) {
+        // Keyboard events are cancelled by separate listeners, because the listeners
+        // which need `self` may be deferred until after cancelling is possible.
+        for event_name in ["keydown", "keyup"] {
+            add_event_listener(
+                &self.gui_helpers.canvas_helper().canvas(),
+                event_name,
+                move |event: KeyboardEvent| {
+                    // TODO: return for keys we don't bind
+                    if map_keyboard_event(&event).is_some() {
+                        let event: &Event = event.as_ref();
+                        event.stop_propagation();
+                        event.prevent_default();
+                    }
+                },
+                &AddEventListenerOptions::new(),
+            );
+        }
+
         self.add_canvas_to_self_event_listener(
             "keydown",
-            false,
+            true,
             move |this, event: KeyboardEvent| {
                 if let Some(key) = map_keyboard_event(&event) {
                     this.session.input_processor.key_down(key);
-
-                    // TODO: return for keys we don't bind
-                    let event: &Event = event.as_ref();
-                    event.stop_propagation(); // only if we didn't return already
-                    event.prevent_default();
                 }
             },
         );
 
-        self.add_canvas_to_self_event_listener(
-            "keyup",
-            false,
-            move |this, event: KeyboardEvent| {
-                if let Some(key) = map_keyboard_event(&event) {
-                    this.session.input_processor.key_up(key);
-
-                    // TODO: return for keys we don't bind
-                    let event: &Event = event.as_ref();
-                    event.stop_propagation(); // only if we didn't return already
-                    event.prevent_default();
-                }
-            },
-        );
+        self.add_canvas_to_self_event_listener("keyup", true, move |this, event: KeyboardEvent| {
+            if let Some(key) = map_keyboard_event(&event) {
+                this.session.input_processor.key_up(key);
+            }
+        });
 
         self.add_canvas_to_self_event_listener("focus", true, move |this, _: FocusEvent| {
             this.session.input_processor.key_focus(true);
@@ -342,6 +509,25 @@ impl WebGameRoot {
             },
         );
 
+        self.add_canvas_to_self_event_listener("wheel", true, move |this, event: WheelEvent| {
+            // Convert to lines; there is no true line height for pixel deltas, so pick one
+            // typical of text.
+            let lines = match event.delta_mode() {
+                WheelEvent::DOM_DELTA_LINE => event.delta_y(),
+                WheelEvent::DOM_DELTA_PAGE => event.delta_y() * 10.0,
+                _ => event.delta_y() / 20.0,
+            };
+            this.session.input_processor.mouse_wheel(lines);
+        });
+        add_event_listener(
+            &self.gui_helpers.canvas_helper().canvas(),
+            "wheel",
+            move |event: WheelEvent| {
+                event.prevent_default(); // don't scroll the page
+            },
+            &AddEventListenerOptions::new(),
+        );
+
         add_event_listener(
             &self.gui_helpers.canvas_helper().canvas(),
             "contextmenu",
@@ -358,7 +544,8 @@ impl WebGameRoot {
             let ch = self.gui_helpers.canvas_helper();
             let target = &ch.canvas().owner_document().unwrap();
             let listener = move |_event: Event| {
-                Self::upgrade_in_callback(&weak_self_ref, |this| {
+                let ch = ch.clone();
+                Self::upgrade_in_callback(&weak_self_ref, move |this| {
                     let state = Some(ch.is_fullscreen());
                     log::warn!("got fullscreenchange {state:?}");
                     this.fullscreen_cell.set(state);
@@ -371,90 +558,147 @@ impl WebGameRoot {
             add_event_listener(target, "webkitfullscreenchange", listener, &options);
         }
 
-        // File drop listener.
-        // TODO: This code does not ever run. Probably need some additional handlers to
-        // register as being a drop target.
-        self.add_canvas_to_self_event_listener("drop", false, move |this, event: DragEvent| {
-            let mut found_file = None;
-            if let Some(data_transfer) = event.data_transfer() {
-                let items = data_transfer.items();
-                for i in 0..items.length() {
-                    let item: DataTransferItem = items.get(i).unwrap();
-                    match item.kind().as_str() {
-                        "string" => {}
-                        "file" => {
-                            if let Ok(Some(file)) = item.get_as_file() {
-                                found_file = Some(file);
+        // Saving listeners.
+        {
+            let weak_self_ref = self.self_ref.clone();
+            add_event_listener(
+                &self.static_dom.save_button,
+                "click",
+                move |_: Event| {
+                    Self::upgrade_in_callback(&weak_self_ref, |this| {
+                        let default_name = this.save_name.as_deref().unwrap_or(AUTOSAVE_NAME);
+                        if let Ok(Some(name)) = web_sys::window()
+                            .unwrap()
+                            .prompt_with_message_and_default("Save universe as:", default_name)
+                        {
+                            let name = name.trim();
+                            if !name.is_empty() {
+                                this.save_universe(name.to_owned());
                             }
                         }
-                        other_kind => {
-                            console::warn_1(&JsValue::from(format!(
-                                "unrecognized drag item kind: {other_kind:?}"
-                            )));
-                        }
+                    })
+                },
+                &AddEventListenerOptions::new(),
+            );
+        }
+        {
+            // Save automatically when the page is hidden, since it might be about to be
+            // closed or discarded, and we get no reliable opportunity after that.
+            let weak_self_ref = self.self_ref.clone();
+            let document = self.static_dom.app_root.owner_document().unwrap();
+            let mut options = AddEventListenerOptions::new();
+            options.passive(true);
+            add_event_listener(
+                &document.clone(),
+                "visibilitychange",
+                move |_: Event| {
+                    if document.hidden() {
+                        Self::upgrade_in_callback(&weak_self_ref, |this| {
+                            if let Some(name) = this.save_name.clone() {
+                                this.save_universe(name);
+                            }
+                        })
                     }
-                }
-            }
+                },
+                &options,
+            );
+        }
 
-            if let Some(found_file) = found_file {
-                // We've found a file to use. Further steps must be async.
+        // File drop listeners.
+        // The browser only lets files be dropped on elements that cancel `dragover`.
+        add_event_listener(
+            &self.gui_helpers.canvas_helper().canvas(),
+            "dragover",
+            move |event: DragEvent| {
                 event.prevent_default();
-
-                let this = this.self_ref.upgrade().unwrap();
-                spawn_local(async move {
-                    match JsFuture::from(found_file.array_buffer()).await {
-                        Ok(buffer) => {
-                            let buffer: ArrayBuffer = buffer.dyn_into().unwrap();
-                            // TODO: error reporting
-                            let universe = all_is_cubes_port::load_universe_from_file(
-                                YieldProgress::noop(),
-                                &NonDiskFile::from_name_and_data_source(found_file.name(), || {
-                                    Ok(Uint8Array::new(&buffer).to_vec())
-                                }),
-                            )
-                            .await
-                            .unwrap();
-                            this.borrow_mut().session.set_universe(universe);
-                        }
-                        Err(e) => {
-                            // TODO: present error to UI
-                            console::error_2(&JsValue::from_str("failed to load file"), &e);
+                if let Some(data_transfer) = event.data_transfer() {
+                    data_transfer.set_drop_effect("copy");
+                }
+            },
+            &AddEventListenerOptions::new(),
+        );
+        let weak_self_ref = self.self_ref.clone();
+        add_event_listener(
+            &self.gui_helpers.canvas_helper().canvas(),
+            "drop",
+            move |event: DragEvent| {
+                let mut found_file = None;
+                if let Some(data_transfer) = event.data_transfer() {
+                    let items = data_transfer.items();
+                    for i in 0..items.length() {
+                        let item: DataTransferItem = items.get(i).unwrap();
+                        match item.kind().as_str() {
+                            "string" => {}
+                            "file" => {
+                                if let Ok(Some(file)) = item.get_as_file() {
+                                    found_file = Some(file);
+                                }
+                            }
+                            other_kind => {
+                                console::warn_1(&JsValue::from(format!(
+                                    "unrecognized drag item kind: {other_kind:?}"
+                                )));
+                            }
                         }
                     }
-                });
-            }
-        });
+                }
+
+                if let Some(found_file) = found_file {
+                    // We've found a file to use; don't let the browser navigate to it instead.
+                    event.prevent_default();
+                    Self::upgrade_in_callback(&weak_self_ref, move |this| {
+                        this.load_file(found_file)
+                    });
+                }
+            },
+            &AddEventListenerOptions::new(),
+        );
     }
 
     fn add_canvas_to_self_event_listener<E, F>(&self, event_name: &str, passive: bool, callback: F)
     where
-        E: JsCast,
+        E: JsCast + 'static,
         F: Fn(&mut Self, E) + 'static,
     {
         let weak_self_ref = self.self_ref.clone();
+        let callback = Rc::new(callback);
         add_event_listener(
             &self.gui_helpers.canvas_helper().canvas(),
             event_name,
             move |event: E| {
-                Self::upgrade_in_callback(&weak_self_ref, |this| callback(&mut *this, event))
+                let callback = callback.clone();
+                Self::upgrade_in_callback(&weak_self_ref, move |this| callback(&mut *this, event))
             },
             AddEventListenerOptions::new().passive(passive),
         );
     }
 
+    /// Runs `body` with the [`WebGameRoot`], if it still exists.
+    ///
+    /// If a step is in progress, `body` is run after it finishes instead.
     fn upgrade_in_callback<F>(weak_self_ref: &Weak<RefCell<WebGameRoot>>, body: F)
     where
-        F: FnOnce(&mut Self),
+        F: FnOnce(&mut Self) + 'static,
     {
         if let Some(strong_self_ref) = weak_self_ref.upgrade() {
             match strong_self_ref.try_borrow_mut() {
                 Ok(mut this) => body(&mut this),
                 Err(BorrowMutError { .. }) => {
-                    // We probably left the cell borrowed in a previous panic.
-                    // Log, but don't panic again because it will only create log spam.
-                    console::warn_1(&JsValue::from_str(
-                        "WebGameRoot is borrowed at event handler (check previous errors)",
-                    ));
+                    let deferred = DEFERRED_CALLBACKS.with(|deferred| {
+                        if let Some(queue) = &mut *deferred.borrow_mut() {
+                            queue.push(Box::new(body));
+                            true
+                        } else {
+                            false
+                        }
+                    });
+                    if !deferred {
+                        // We probably left the cell borrowed in a previous panic.
+                        // Log, but don't panic again because it will only create log spam.
+                        console::warn_1(&JsValue::from_str(
+                            "WebGameRoot is borrowed at event handler (check previous errors)",
+                        ));
+                    }
                 }
             }
         } else {
@@ -464,6 +708,151 @@ impl WebGameRoot {
         }
     }
 
+    /// Replaces the current universe with one loaded from `file`, showing progress in
+    /// the progress bar meanwhile.
+    fn load_file(&mut self, file: File) {
+        let static_dom = self.static_dom.clone();
+        static_dom.set_loading_file(true);
+        let progress = static_dom.progress();
+        // Don't overwrite the previous universe's save with this one.
+        self.save_name = Some(String::from(AUTOSAVE_NAME));
+
+        self.session
+            .set_universe_async(SendWrapper::new(async move {
+                let name = file.name();
+                let result = match JsFuture::from(file.array_buffer()).await {
+                    Ok(buffer) => all_is_cubes_port::load_universe_from_file(
+                        progress,
+                        &NonDiskFile::from_name_and_data_source(name.clone(), move || {
+                            Ok(Uint8Array::new(&buffer).to_vec())
+                        }),
+                    )
+                    .await
+                    .map_err(|e| UniverseLoadError::File {
+                        name: name.clone(),
+                        source: Box::new(e),
+                    }),
+                    Err(e) => Err(UniverseLoadError::Other(format!(
+                        "failed to read file {name:?}: {e:?}"
+                    ))),
+                };
+                static_dom.set_loading_file(false);
+                result
+            }));
+    }
+
+    /// Serializes the current universe and stores it under `name`, replacing any
+    /// existing save with that name.
+    fn save_universe(&mut self, name: String) {
+        let Some(saves) = self.saves.clone() else {
+            return;
+        };
+        self.save_name = Some(name.clone());
+        let export = all_is_cubes_port::export_to_bytes(
+            YieldProgress::noop(),
+            ExportFormat::AicBinary,
+            ExportSet::all_of_universe(self.session.universe_mut()),
+        );
+        let weak_self_ref = self.self_ref.clone();
+        spawn_local(async move {
+            let result = match export.await {
+                Ok(data) => saves.put(&name, &data).await,
+                Err(e) => Err(JsValue::from(format!(
+                    "{}",
+                    all_is_cubes::util::ErrorChain(&e)
+                ))),
+            };
+            match result {
+                Ok(()) => log::info!("Saved universe {name:?}"),
+                Err(e) => console::error_2(
+                    &JsValue::from(format!("failed to save universe {name:?}:")),
+                    &e,
+                ),
+            }
+            update_saves_list(weak_self_ref, saves).await;
+        });
+    }
+
+    /// Replaces the current universe with the one saved under `name`.
+    fn load_saved(&self, name: String) {
+        let Some(saves) = self.saves.clone() else {
+            return;
+        };
+        let weak_self_ref = self.self_ref.clone();
+        spawn_local(async move {
+            match load_saved_universe(YieldProgress::noop(), &saves, &name).await {
+                Ok(universe) => Self::upgrade_in_callback(&weak_self_ref, |this| {
+                    this.session.set_universe(universe);
+                    this.save_name = Some(name);
+                }),
+                // TODO: present error to UI
+                Err(e) => console::error_1(&JsValue::from(format!(
+                    "failed to load saved universe: {}",
+                    all_is_cubes::util::ErrorChain(&*e)
+                ))),
+            }
+        });
+    }
+
+    /// Deletes the save named `name`, after asking the user to confirm.
+    fn delete_saved(&self, name: String) {
+        let Some(saves) = self.saves.clone() else {
+            return;
+        };
+        let confirmed = web_sys::window()
+            .unwrap()
+            .confirm_with_message(&format!("Delete the saved universe {name:?}?"))
+            .unwrap_or(false);
+        if !confirmed {
+            return;
+        }
+        let weak_self_ref = self.self_ref.clone();
+        spawn_local(async move {
+            if let Err(e) = saves.delete(&name).await {
+                console::error_2(
+                    &JsValue::from(format!("failed to delete saved universe {name:?}:")),
+                    &e,
+                );
+            }
+            update_saves_list(weak_self_ref, saves).await;
+        });
+    }
+
+    /// Replaces the contents of the saves list with entries for `names`.
+    fn show_saves(&self, names: Vec<String>) {
+        let list = &self.static_dom.saves_list;
+        let document = list.owner_document().unwrap();
+        list.set_text_content(None);
+        for name in names {
+            let item = document.create_element("li").unwrap();
+            item.append_with_str_1(&name).unwrap();
+
+            let actions: [(&str, fn(&mut Self, String)); 2] = [
+                ("Load", |this, name| this.load_saved(name)),
+                ("Delete", |this, name| this.delete_saved(name)),
+            ];
+            for (label, action) in actions {
+                let button = document.create_element("button").unwrap();
+                button.set_attribute("type", "button").unwrap();
+                button.set_text_content(Some(label));
+                let weak_self_ref = self.self_ref.clone();
+                let name = name.clone();
+                add_event_listener(
+                    &button,
+                    "click",
+                    move |_: Event| {
+                        let name = name.clone();
+                        Self::upgrade_in_callback(&weak_self_ref, move |this| action(this, name))
+                    },
+                    &AddEventListenerOptions::new(),
+                );
+                item.append_with_node_1(&button).unwrap();
+            }
+
+            list.append_with_node_1(&item).unwrap();
+        }
+    }
+
     pub fn start_loop(&self) {
         // This strategy from https://rustwasm.github.io/docs/wasm-bindgen/examples/request-animation-frame.html
         web_sys::window()
@@ -511,15 +900,18 @@ impl WebGameRoot {
                         .expect("error in render_frame")
                 }
             };
+            self.session.record_render_time(render_info.frame_time());
 
             // Update info text
             let cameras: &StandardCameras = match &self.renderer {
                 WebRenderer::Wgpu(renderer) => renderer.cameras(),
             };
             if cameras.cameras().world.options().debug_info_text {
-                self.static_dom
-                    .scene_info_text_node
-                    .set_data(&format!("{}", self.session.info_text(render_info)));
+                self.static_dom.scene_info_text_node.set_data(&format!(
+                    "{}\n{}",
+                    self.graphics_description,
+                    self.session.info_text(render_info)
+                ));
             } else {
                 self.static_dom.scene_info_text_node.set_data("");
             }
@@ -553,9 +945,36 @@ impl WebGameRoot {
     }
 
     fn step_callback_impl(&mut self) {
-        self.step_callback_scheduled = false;
-        if let Some(universe_step_info) = self.session.maybe_step_universe() {
-            self.last_step_info = universe_step_info;
+        spawn_local(Self::step_task(self.self_ref.clone()));
+    }
+
+    /// Steps the session. This may yield to the event loop if the step is slow, in which
+    /// case callbacks which need the [`WebGameRoot`] are deferred until it finishes.
+    #[allow(clippy::await_holding_refcell_ref)] // other borrowers defer; see upgrade_in_callback()
+    async fn step_task(weak_self_ref: Weak<RefCell<WebGameRoot>>) {
+        let Some(strong_self_ref) = weak_self_ref.upgrade() else {
+            return;
+        };
+        let Ok(mut this) = strong_self_ref.try_borrow_mut() else {
+            console::warn_1(&JsValue::from_str(
+                "WebGameRoot is borrowed at step (check previous errors)",
+            ));
+            return;
+        };
+
+        DEFERRED_CALLBACKS.with(|deferred| *deferred.borrow_mut() = Some(Vec::new()));
+        let step_info = this
+            .session
+            .maybe_step_universe_async(YieldProgress::new(yield_to_event_loop, |_, _| {}))
+            .await;
+        if let Some(universe_step_info) = step_info {
+            this.last_step_info = universe_step_info;
+        }
+        this.step_callback_scheduled = false;
+
+        let deferred = DEFERRED_CALLBACKS.with(|deferred| deferred.borrow_mut().take());
+        for callback in deferred.into_iter().flatten() {
+            callback(&mut this);
         }
     }
 
@@ -593,8 +1012,13 @@ struct StaticDom {
     /// Usually the document element.
     app_root: HtmlElement,
     progress_bar: HtmlProgressElement,
+    /// Describes the stage of work the progress bar is showing the progress of.
+    loading_stage: Text,
     loading_log: Text,
     scene_info_text_node: Text,
+    saves_overlay: HtmlElement,
+    save_button: HtmlElement,
+    saves_list: HtmlElement,
 }
 
 impl StaticDom {
@@ -602,6 +1026,10 @@ impl StaticDom {
         Ok(Self {
             app_root: get_mandatory_element(document, "app-root")?,
             progress_bar: get_mandatory_element(document, "loading-progress-bar")?,
+            loading_stage: replace_children_with_one_text_node(get_mandatory_element(
+                document,
+                "loading-stage",
+            )?),
             loading_log: replace_children_with_one_text_node(get_mandatory_element(
                 document,
                 "loading-log",
@@ -610,12 +1038,35 @@ impl StaticDom {
                 document,
                 "scene-info-text",
             )?),
+            saves_overlay: get_mandatory_element(document, "saves-overlay")?,
+            save_button: get_mandatory_element(document, "save-button")?,
+            saves_list: get_mandatory_element(document, "saves-list")?,
         })
     }
 
     fn append_to_loading_log(&self, text: &str) {
         let _ = self.loading_log.append_data(text);
     }
+
+    /// Returns a [`YieldProgress`] which displays its progress in the progress bar.
+    fn progress(&self) -> YieldProgress {
+        YieldProgress::new_staged(yield_to_event_loop, {
+            let progress_bar = SendWrapper::new(self.progress_bar.clone());
+            let loading_stage = SendWrapper::new(self.loading_stage.clone());
+            move |info| {
+                progress_bar.set_value(info.fraction().into());
+                loading_stage.set_data(&info.to_string());
+            }
+        })
+    }
+
+    /// Shows or hides the progress bar after the game has loaded, for loading a file.
+    fn set_loading_file(&self, loading: bool) {
+        let _ = self
+            .app_root
+            .class_list()
+            .toggle_with_force("loading-file", loading);
+    }
 }
 
 async fn create_session(
@@ -652,6 +1103,8 @@ fn map_keyboard_event(event: &KeyboardEvent) -> Option<Key> {
         return None;
     }
     Some(match event.key_code() as u8 as char {
+        '\x08' => Key::Backspace,
+        '\x0D' => Key::Character('\r'),
         '\x1B' => Key::Escape,
         '\x25' => Key::Left,
         '\x26' => Key::Up,
@@ -43,6 +43,8 @@ pub mod gameapp;
 #[cfg(target_family = "wasm")]
 #[doc(hidden)] // public for testing
 pub mod js_bindings;
+#[cfg(target_family = "wasm")]
+mod storage;
 #[cfg(any(target_family = "wasm", test))]
 mod url_params;
 #[cfg(target_family = "wasm")]
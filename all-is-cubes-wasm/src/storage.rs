@@ -0,0 +1,166 @@
+//! Saving universes in the browser's own storage (IndexedDB), so that they persist
+//! across page loads without any server being involved.
+
+use js_sys::{Array, Promise, Uint8Array};
+use wasm_bindgen::prelude::{Closure, JsValue};
+use wasm_bindgen::JsCast; // unchecked_into()
+use wasm_bindgen_futures::JsFuture;
+use web_sys::{IdbDatabase, IdbObjectStore, IdbRequest, IdbTransactionMode, Storage};
+
+const DATABASE_NAME: &str = "all-is-cubes";
+const DATABASE_VERSION: u32 = 1;
+const SAVES_STORE_NAME: &str = "saves";
+
+/// `localStorage` key whose value is the name of the save most recently written or
+/// loaded, which is the one to load on startup.
+const LAST_SAVE_KEY: &str = "all-is-cubes-last-save";
+
+/// Universes saved in this browser, each stored under a name chosen by the user.
+///
+/// The data stored is whatever the caller provides; in practice, it is
+/// [`ExportFormat::AicBinary`](all_is_cubes_port::ExportFormat::AicBinary).
+#[derive(Debug)]
+pub(crate) struct SaveStore {
+    db: IdbDatabase,
+}
+
+impl SaveStore {
+    /// Opens the database, creating it if this browser has never used it before.
+    pub(crate) async fn open() -> Result<Self, JsValue> {
+        let factory = web_sys::window()
+            .ok_or("no window")?
+            .indexed_db()?
+            .ok_or("IndexedDB is not available")?;
+        let request = factory.open_with_u32(DATABASE_NAME, DATABASE_VERSION)?;
+
+        // Called if the database did not exist or has an older version. There is only
+        // one version so far, so there is nothing to migrate.
+        let upgrade: Closure<dyn FnMut()> = Closure::wrap(Box::new({
+            let request = request.clone();
+            move || {
+                let result = request.result().and_then(|db| {
+                    db.unchecked_into::<IdbDatabase>()
+                        .create_object_store(SAVES_STORE_NAME)
+                });
+                if let Err(e) = result {
+                    log::error!("failed to create save storage: {e:?}");
+                }
+            }
+        }));
+        request.set_onupgradeneeded(Some(upgrade.as_ref().unchecked_ref()));
+        let result = await_request(&request).await;
+        request.set_onupgradeneeded(None);
+        drop(upgrade);
+
+        Ok(Self {
+            db: result?.unchecked_into(),
+        })
+    }
+
+    /// Returns the names of all saves, in sorted order.
+    pub(crate) async fn names(&self) -> Result<Vec<String>, JsValue> {
+        let keys =
+            await_request(&self.store(IdbTransactionMode::Readonly)?.get_all_keys()?).await?;
+        Ok(Array::from(&keys)
+            .iter()
+            .filter_map(|key| key.as_string())
+            .collect())
+    }
+
+    /// Returns the data saved under `name`, or [`None`] if there is no such save.
+    pub(crate) async fn get(&self, name: &str) -> Result<Option<Vec<u8>>, JsValue> {
+        let value = await_request(
+            &self
+                .store(IdbTransactionMode::Readonly)?
+                .get(&JsValue::from_str(name))?,
+        )
+        .await?;
+        if value.is_undefined() {
+            Ok(None)
+        } else {
+            set_last_save_name(Some(name));
+            Ok(Some(Uint8Array::new(&value).to_vec()))
+        }
+    }
+
+    /// Stores `data` under `name`, replacing any existing save with that name.
+    pub(crate) async fn put(&self, name: &str, data: &[u8]) -> Result<(), JsValue> {
+        await_request(
+            &self
+                .store(IdbTransactionMode::Readwrite)?
+                .put_with_key(&Uint8Array::from(data), &JsValue::from_str(name))?,
+        )
+        .await?;
+        set_last_save_name(Some(name));
+        Ok(())
+    }
+
+    /// Deletes the save named `name`, if it exists.
+    pub(crate) async fn delete(&self, name: &str) -> Result<(), JsValue> {
+        await_request(
+            &self
+                .store(IdbTransactionMode::Readwrite)?
+                .delete(&JsValue::from_str(name))?,
+        )
+        .await?;
+        if last_save_name().as_deref() == Some(name) {
+            set_last_save_name(None);
+        }
+        Ok(())
+    }
+
+    /// Starts a transaction on the saves object store.
+    fn store(&self, mode: IdbTransactionMode) -> Result<IdbObjectStore, JsValue> {
+        self.db
+            .transaction_with_str_and_mode(SAVES_STORE_NAME, mode)?
+            .object_store(SAVES_STORE_NAME)
+    }
+}
+
+/// Returns the name of the save most recently written or loaded in this browser.
+///
+/// The save might have been deleted since, by another tab.
+pub(crate) fn last_save_name() -> Option<String> {
+    local_storage()?.get_item(LAST_SAVE_KEY).ok().flatten()
+}
+
+/// Stops remembering the last save, so that it will not be loaded on the next startup.
+///
+/// This is used when that save could not be loaded.
+pub(crate) fn forget_last_save_name() {
+    set_last_save_name(None);
+}
+
+fn set_last_save_name(name: Option<&str>) {
+    // Failure only means that the wrong save, or none, will be loaded on startup.
+    if let Some(storage) = local_storage() {
+        let _ = match name {
+            Some(name) => storage.set_item(LAST_SAVE_KEY, name),
+            None => storage.remove_item(LAST_SAVE_KEY),
+        };
+    }
+}
+
+fn local_storage() -> Option<Storage> {
+    web_sys::window()?.local_storage().ok().flatten()
+}
+
+/// Waits for `request` to complete, and returns its result.
+async fn await_request(request: &IdbRequest) -> Result<JsValue, JsValue> {
+    // The promise's resolve and reject functions can serve directly as the request's
+    // event handlers, since their arguments are ignored.
+    let promise = Promise::new(&mut |resolve, reject| {
+        request.set_onsuccess(Some(&resolve));
+        request.set_onerror(Some(&reject));
+    });
+    let outcome = JsFuture::from(promise).await;
+    request.set_onsuccess(None);
+    request.set_onerror(None);
+    match outcome {
+        Ok(_event) => request.result(),
+        Err(_event) => Err(match request.error() {
+            Ok(Some(exception)) => exception.into(),
+            _ => JsValue::from_str("IndexedDB request failed"),
+        }),
+    }
+}
@@ -13,7 +13,13 @@ use all_is_cubes_content::UniverseTemplate;
 #[derive(Clone, Debug, PartialEq)]
 #[allow(clippy::derive_partial_eq_without_eq)]
 pub struct OptionsInUrl {
-    pub template: UniverseTemplate,
+    /// Template to build a new universe from. If [`None`], the most recently used
+    /// saved universe is loaded instead, if there is one.
+    pub template: Option<UniverseTemplate>,
+    /// URL, relative to the page, of a universe file to load instead of using `template`.
+    pub universe_url: Option<String>,
+    /// Name of a universe saved in the browser to load instead of using `template`.
+    pub save_name: Option<String>,
     pub graphics_options: GraphicsOptions,
     pub renderer: RendererOption,
 }
@@ -37,17 +43,16 @@ where
     S: Ord + Borrow<str>,
 {
     OptionsInUrl {
-        template: params
-            .get("template")
-            .and_then(|s| {
-                let s = s.borrow();
-                let t = s.parse::<UniverseTemplate>();
-                if t.is_err() {
-                    log::warn!("Unrecognized value for template=: {:?}", s);
-                }
-                t.ok()
-            })
-            .unwrap_or_default(),
+        template: params.get("template").and_then(|s| {
+            let s = s.borrow();
+            let t = s.parse::<UniverseTemplate>();
+            if t.is_err() {
+                log::warn!("Unrecognized value for template=: {:?}", s);
+            }
+            t.ok()
+        }),
+        universe_url: params.get("universe").map(|s| s.borrow().to_owned()),
+        save_name: params.get("save").map(|s| s.borrow().to_owned()),
         graphics_options: GraphicsOptions::default(), // TODO: offer graphics options
         renderer: params
             .get("renderer")
@@ -74,7 +79,9 @@ mod tests {
         assert_eq!(
             options_from_query_string(b""),
             OptionsInUrl {
-                template: UniverseTemplate::default(),
+                template: None,
+                universe_url: None,
+                save_name: None,
                 graphics_options: GraphicsOptions::default(),
                 renderer: RendererOption::Wgpu,
             },
@@ -85,7 +92,23 @@ mod tests {
     fn parse_specified_template() {
         assert_eq!(
             options_from_query_string(b"template=cornell-box").template,
-            UniverseTemplate::CornellBox,
+            Some(UniverseTemplate::CornellBox),
+        )
+    }
+
+    #[test]
+    fn parse_specified_universe() {
+        assert_eq!(
+            options_from_query_string(b"universe=worlds%2Fcastle.alliscubesjson").universe_url,
+            Some(String::from("worlds/castle.alliscubesjson")),
+        )
+    }
+
+    #[test]
+    fn parse_specified_save() {
+        assert_eq!(
+            options_from_query_string(b"save=my+castle").save_name,
+            Some(String::from("my castle")),
         )
     }
 
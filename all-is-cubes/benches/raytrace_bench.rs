@@ -2,13 +2,17 @@ use cgmath::{Vector2, Vector3};
 use criterion::measurement::WallTime;
 use criterion::{criterion_group, criterion_main, Bencher, Criterion};
 
+use all_is_cubes::block::{Block, Resolution::R32, AIR};
 use all_is_cubes::camera::{
-    GraphicsOptions, LightingOption, StandardCameras, TransparencyOption, UiViewState, Viewport,
+    CameraMode, GraphicsOptions, LightingOption, StandardCameras, TransparencyOption, UiViewState,
+    Viewport,
 };
-use all_is_cubes::character::Character;
+use all_is_cubes::character::{Character, Spawn};
 use all_is_cubes::content::testing::lighting_bench_space;
 use all_is_cubes::listen::ListenableSource;
+use all_is_cubes::math::{GridAab, Rgba};
 use all_is_cubes::raytracer::RtRenderer;
+use all_is_cubes::space::Space;
 use all_is_cubes::universe::{URef, Universe};
 
 /// Non-mutated test data shared between benches
@@ -18,9 +22,9 @@ struct TestData {
     character: URef<Character>,
 }
 impl TestData {
-    fn new() -> Self {
+    fn new(space_fn: impl FnOnce(&mut Universe) -> Space) -> Self {
         let mut universe = Universe::new();
-        let space = lighting_bench_space(&mut universe, Vector3::new(54, 16, 54)).unwrap();
+        let space = space_fn(&mut universe);
         let space = universe.insert_anonymous(space);
         let character = universe.insert_anonymous(Character::spawn_default(space));
         Self {
@@ -37,7 +41,10 @@ impl TestData {
                 ListenableSource::constant(options),
                 ListenableSource::constant(Viewport::with_scale(1.0, Vector2::new(64, 16))),
                 ListenableSource::constant(Some(self.character.clone())),
+                ListenableSource::constant(CameraMode::default()),
                 ListenableSource::constant(UiViewState::default()),
+                ListenableSource::constant(UiViewState::default()),
+                ListenableSource::constant(1.0),
             ),
             Box::new(std::convert::identity),
             ListenableSource::constant(()),
@@ -52,8 +59,32 @@ impl TestData {
     }
 }
 
+/// A space full of high-resolution blocks which are mostly empty, so that the raytracer
+/// must traverse many transparent voxels.
+fn sparse_recursive_space(universe: &mut Universe) -> Space {
+    let bounds = GridAab::from_lower_size([0, 0, 0], [6, 6, 6]);
+    let dot = Block::from(Rgba::new(0.5, 0.5, 1.0, 1.0));
+    let block = Block::builder()
+        .voxels_fn(universe, R32, |cube| {
+            let centered = cube.map(|c| c - 16);
+            if centered.x * centered.x + centered.y * centered.y + centered.z * centered.z < 16 {
+                &dot
+            } else {
+                &AIR
+            }
+        })
+        .unwrap()
+        .build();
+    Space::builder(bounds)
+        .spawn(Spawn::looking_at_space(bounds, [0., 0.5, 1.]))
+        .filled_with(block)
+        .build()
+}
+
 pub fn raytrace_bench(c: &mut Criterion) {
-    let t = TestData::new();
+    let t =
+        TestData::new(|universe| lighting_bench_space(universe, Vector3::new(54, 16, 54)).unwrap());
+    let recursive = TestData::new(sparse_recursive_space);
 
     let mut group = c.benchmark_group(if cfg!(feature = "threads") {
         "threaded"
@@ -86,7 +117,12 @@ pub fn raytrace_bench(c: &mut Criterion) {
         });
     }
 
-    // TODO: add benchmarks of specifically recursive blocks
+    group.bench_function("sparse-recursive-surface", |b| {
+        recursive.bench(b, |o| {
+            o.transparency = TransparencyOption::Surface;
+            o.lighting_display = LightingOption::Flat;
+        });
+    });
 
     group.finish();
 }
@@ -9,7 +9,7 @@ use downcast_rs::{impl_downcast, Downcast};
 
 use crate::time::Tick;
 use crate::transaction::{self, Merge as _, Transaction};
-use crate::universe::{RefVisitor, UniverseTransaction, VisitRefs};
+use crate::universe::{RefVisitor, URef, UniverseTransaction, VisitRefs};
 
 /// Dynamic add-ons to game objects; we might also have called them “components”.
 /// Each behavior is owned by a “host” of type `H` which determines when the behavior
@@ -50,6 +50,9 @@ pub trait BehaviorHost: transaction::Transactional + 'static {
 pub struct BehaviorContext<'a, H: BehaviorHost> {
     /// The current state of the behavior's host object.
     pub host: &'a H,
+    /// Reference to the behavior's host object, for transactions which involve it along
+    /// with other universe members.
+    pub host_ref: &'a URef<H>,
     /// Additional data about “where” the behavior is attached to the host; what part of
     /// the host should be affected by the behavior.
     pub attachment: &'a H::Attachment,
@@ -137,6 +140,7 @@ impl<H: BehaviorHost> BehaviorSet<H> {
     pub(crate) fn step(
         &self,
         host: &H,
+        host_ref: &URef<H>,
         host_transaction_binder: &dyn Fn(H::Transaction) -> UniverseTransaction,
         // This is not `dyn` because it doesn't need to be stored, and there's no advantage
         // to monomorphizing because this function is only going to be called once per `H`
@@ -148,6 +152,7 @@ impl<H: BehaviorHost> BehaviorSet<H> {
         for (index, entry) in self.items.iter().enumerate() {
             let context = &BehaviorContext {
                 host,
+                host_ref,
                 attachment: &entry.attachment,
                 host_transaction_binder,
                 self_transaction_binder: &|new_behavior| {
@@ -548,6 +553,7 @@ mod tests {
                 .merge(
                     context.bind_host(CharacterTransaction::body(BodyTransaction {
                         delta_yaw: FreeCoordinate::from(self.foo),
+                        ..Default::default()
                     })),
                 )
                 .unwrap()
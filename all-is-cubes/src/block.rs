@@ -9,11 +9,11 @@ use std::collections::VecDeque;
 use std::fmt;
 use std::sync::Arc;
 
-use cgmath::{EuclideanSpace as _, Point3};
+use cgmath::{EuclideanSpace as _, Point3, Vector4};
 
 use crate::listen::{Listen, Listener};
 use crate::math::{
-    FreeCoordinate, GridAab, GridArray, GridCoordinate, GridPoint, GridRotation, Rgb, Rgba,
+    FaceMap, FreeCoordinate, GridAab, GridArray, GridCoordinate, GridPoint, GridRotation, Rgb, Rgba,
 };
 use crate::raycast::Ray;
 use crate::space::{SetCubeError, Space, SpaceChange};
@@ -97,6 +97,14 @@ pub enum Primitive {
     /// non-solid to physics; in fact, [`AIR`] is such an atom.)
     Atom(BlockAttributes, Rgba),
 
+    /// A block that is a unit cube like [`Primitive::Atom`], but with a separate color
+    /// for each face, such as a grass block with a green top and brown sides.
+    ///
+    /// The block's interior, if visible through transparent faces, has the mean of the
+    /// face colors. Unlike a [`Primitive::Recur`] of the same appearance, this requires
+    /// no voxels to evaluate or draw.
+    Faces(BlockAttributes, FaceMap<Rgba>),
+
     /// A block that is composed of smaller blocks, defined by the referenced [`Space`].
     Recur {
         #[allow(missing_docs)]
@@ -291,6 +299,46 @@ impl Block {
         }
     }
 
+    /// If this block has a [`Modifier::Connect`], returns the block which should be
+    /// displayed in its place when it has a neighbor which is the same block on each face
+    /// for which `connections` is true.
+    ///
+    /// Returns [`None`] if this block has no [`Modifier::Connect`] or the connect modifier
+    /// has no variant for the given connections; in that case the block should be
+    /// displayed as-is.
+    pub fn with_connections(&self, connections: FaceMap<bool>) -> Option<Block> {
+        let (index, connect) = self.connect_modifier()?;
+        connect.variant_block(self, index, connect.variant_index(connections))
+    }
+
+    /// If this block has a [`Modifier::Connect`], returns it together with the blocks
+    /// which should be displayed in place of this block for each of its variants,
+    /// as [`Block::with_connections()`] would return them.
+    /// The vector is indexed by [`Connect::variant_index()`], and contains [`None`] for
+    /// missing variants.
+    ///
+    /// This is intended for precomputing the appearance of all variants, such as meshes.
+    pub fn connection_variants(&self) -> Option<(&Connect, Vec<Option<Block>>)> {
+        let (index, connect) = self.connect_modifier()?;
+        Some((
+            connect,
+            (0..connect.variant_count())
+                .map(|variant_index| connect.variant_block(self, index, variant_index))
+                .collect(),
+        ))
+    }
+
+    /// Find the first [`Modifier::Connect`] and its index.
+    fn connect_modifier(&self) -> Option<(usize, &Connect)> {
+        self.modifiers()
+            .iter()
+            .enumerate()
+            .find_map(|(index, modifier)| match modifier {
+                Modifier::Connect(connect) => Some((index, connect)),
+                _ => None,
+            })
+    }
+
     /// Standardizes any characteristics of this block which may be presumed to be
     /// specific to its usage in its current location, so that it can be used elsewhere
     /// or compared with others. Specifically, it has the following effects:
@@ -369,18 +417,37 @@ impl Block {
     fn evaluate_impl(&self, depth: u8) -> Result<MinEval, EvalBlockError> {
         let mut value: MinEval = match *self.primitive() {
             Primitive::Indirect(ref def_ref) => {
-                def_ref.read()?.evaluate_impl(next_depth(depth)?)?
+                def_ref.read()?.evaluate_cached(next_depth(depth)?)?
             }
 
             Primitive::Atom(ref attributes, color) => MinEval {
                 attributes: attributes.clone(),
                 voxels: Evoxels::One(Evoxel {
                     color,
+                    emission: attributes.light_emission,
                     selectable: attributes.selectable,
                     collision: attributes.collision,
                 }),
             },
 
+            Primitive::Faces(ref attributes, faces) => {
+                let color_sum: Vector4<f32> = faces.values().map(|&c| Vector4::from(c)).sum();
+                let mean_color = Rgba::try_from(color_sum / 6.0)
+                    .expect("Faces block color computation produced NaN");
+                MinEval {
+                    attributes: attributes.clone(),
+                    voxels: Evoxels::Faces(
+                        Evoxel {
+                            color: mean_color,
+                            emission: attributes.light_emission,
+                            selectable: attributes.selectable,
+                            collision: attributes.collision,
+                        },
+                        faces,
+                    ),
+                }
+            }
+
             Primitive::Air => AIR_EVALUATED_MIN,
 
             Primitive::Recur {
@@ -470,7 +537,7 @@ impl Block {
                 // its own internal listening and thus this does not recurse.
                 <BlockDef as Listen>::listen(&*(def_ref.read()?), listener);
             }
-            Primitive::Atom(_, _) | Primitive::Air => {
+            Primitive::Atom(_, _) | Primitive::Faces(_, _) | Primitive::Air => {
                 // Atoms don't refer to anything external and thus cannot change other
                 // than being directly overwritten, which is out of the scope of this
                 // operation.
@@ -495,6 +562,7 @@ impl Block {
                         SpaceChange::BlockValue(_) => Some(BlockChange::new()),
                         SpaceChange::Lighting(_) => None,
                         SpaceChange::Number(_) => None,
+                        SpaceChange::Weather => None,
                     }
                 }));
             }
@@ -509,7 +577,7 @@ impl Block {
         match *self.primitive() {
             Primitive::Atom(_, c) => c,
             Primitive::Air => AIR_EVALUATED.color,
-            Primitive::Indirect(_) | Primitive::Recur { .. } => {
+            Primitive::Indirect(_) | Primitive::Faces(..) | Primitive::Recur { .. } => {
                 panic!("Block::color not defined for non-atom blocks")
             }
         }
@@ -618,7 +686,7 @@ mod arbitrary_block {
     // Manual impl because `GridPoint` doesn't impl Arbitrary.
     impl<'a> Arbitrary<'a> for Primitive {
         fn arbitrary(u: &mut Unstructured<'a>) -> arbitrary::Result<Self> {
-            Ok(match u.int_in_range(0..=3)? {
+            Ok(match u.int_in_range(0..=4)? {
                 0 => Primitive::Air,
                 1 => Primitive::Atom(BlockAttributes::arbitrary(u)?, Rgba::arbitrary(u)?),
                 2 => Primitive::Indirect(URef::arbitrary(u)?),
@@ -628,6 +696,10 @@ mod arbitrary_block {
                     resolution: Resolution::arbitrary(u)?,
                     space: URef::arbitrary(u)?,
                 },
+                4 => Primitive::Faces(
+                    BlockAttributes::arbitrary(u)?,
+                    FaceMap::<Rgba>::arbitrary(u)?,
+                ),
                 _ => unreachable!(),
             })
         }
@@ -643,6 +715,10 @@ mod arbitrary_block {
                         Resolution::size_hint(depth),
                         URef::<Space>::size_hint(depth),
                     ]),
+                    size_hint::and(
+                        BlockAttributes::size_hint(depth),
+                        FaceMap::<Rgba>::size_hint(depth),
+                    ),
                 ])
             })
         }
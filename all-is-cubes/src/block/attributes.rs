@@ -2,13 +2,19 @@
 
 use std::borrow::Cow;
 use std::fmt;
+use std::num::NonZeroU16;
 
+use cgmath::{Decomposed, Transform as _, Vector3};
+
+use crate::camera::ViewTransform;
 use crate::drawing::VoxelBrush;
-use crate::math::{Face6, Rgb};
+use crate::math::{Face6, FreeCoordinate, GridPoint, NotNan, Rgb};
+use crate::physics::CollisionGroups;
 
 #[cfg(doc)]
 use crate::{
     block::{Block, BlockDef},
+    character::Character,
     space::Space,
 };
 
@@ -35,6 +41,12 @@ pub struct BlockAttributes {
     /// The default value is [`BlockCollision::Hard`].
     pub collision: BlockCollision,
 
+    /// Which [`CollisionGroups`] this block belongs to; bodies are obstructed by it only
+    /// if they collide with one of these groups.
+    ///
+    /// The default value is [`CollisionGroups::SOLID`].
+    pub collision_groups: CollisionGroups,
+
     /// Rule about how this block should be rotated, or not, when placed in a [`Space`] by
     /// some agent not otherwise specifying rotation.
     ///
@@ -53,6 +65,33 @@ pub struct BlockAttributes {
     /// TODO: Very placeholder. This needs more possible effects and also time/probability options.
     pub tick_action: Option<VoxelBrush<'static>>,
 
+    /// Number of ticks to wait between placing the block and performing its
+    /// [`tick_action`](Self::tick_action).
+    ///
+    /// The default value is 1, meaning the action is performed on the next tick.
+    pub tick_period: NonZeroU16,
+
+    /// How this block takes part in propagating signals between neighboring cubes,
+    /// such as a switch powering a lamp through a line of wire.
+    ///
+    /// The default value is [`BlockSignal::None`].
+    pub signal: BlockSignal,
+
+    /// If present, the block's faces display the view from another location in the same
+    /// [`Space`], as if they were screens showing what a camera there sees.
+    ///
+    /// Currently, only the GPU renderer displays portals; other renderers draw the block
+    /// as usual.
+    ///
+    /// The default value is [`None`].
+    pub portal: Option<Portal>,
+
+    /// Damage per second dealt to a [`Character`] whose body touches or intersects this
+    /// block, if the character [has health](Character::health).
+    ///
+    /// The default value is zero.
+    pub contact_damage: NotNan<f32>,
+
     /// Advice to the renderer about how to expect this block to change, and hence
     /// what rendering strategy to use.
     pub animation_hint: AnimationHint,
@@ -73,9 +112,14 @@ impl fmt::Debug for BlockAttributes {
                 display_name,
                 selectable,
                 collision,
+                collision_groups,
                 rotation_rule,
                 light_emission,
                 tick_action,
+                tick_period,
+                signal,
+                portal,
+                contact_damage,
                 animation_hint,
             } = self;
 
@@ -90,6 +134,9 @@ impl fmt::Debug for BlockAttributes {
             if *collision != Self::DEFAULT_REF.collision {
                 s.field("collision", collision);
             }
+            if *collision_groups != Self::DEFAULT_REF.collision_groups {
+                s.field("collision_groups", collision_groups);
+            }
             if *rotation_rule != Self::DEFAULT_REF.rotation_rule {
                 s.field("rotation_rule", rotation_rule);
             }
@@ -99,6 +146,18 @@ impl fmt::Debug for BlockAttributes {
             if *tick_action != Self::DEFAULT_REF.tick_action {
                 s.field("tick_action", tick_action);
             }
+            if *tick_period != Self::DEFAULT_REF.tick_period {
+                s.field("tick_period", tick_period);
+            }
+            if *signal != Self::DEFAULT_REF.signal {
+                s.field("signal", signal);
+            }
+            if *portal != Self::DEFAULT_REF.portal {
+                s.field("portal", portal);
+            }
+            if *contact_damage != Self::DEFAULT_REF.contact_damage {
+                s.field("contact_damage", contact_damage);
+            }
             if *animation_hint != Self::DEFAULT_REF.animation_hint {
                 s.field("animation_hint", animation_hint);
             }
@@ -108,13 +167,24 @@ impl fmt::Debug for BlockAttributes {
 }
 
 impl BlockAttributes {
+    pub(crate) const TICK_PERIOD_ONE: NonZeroU16 = {
+        // Safety: is a constant
+        // TODO: when Option::unwrap is stably const, remove unsafe
+        unsafe { NonZeroU16::new_unchecked(1) }
+    };
+
     const DEFAULT: Self = BlockAttributes {
         display_name: Cow::Borrowed(""),
         selectable: true,
         collision: BlockCollision::Hard,
+        collision_groups: CollisionGroups::SOLID,
         rotation_rule: RotationPlacementRule::Never,
         light_emission: Rgb::ZERO,
         tick_action: None,
+        tick_period: Self::TICK_PERIOD_ONE,
+        signal: BlockSignal::None,
+        portal: None,
+        contact_damage: notnan!(0.0),
         animation_hint: AnimationHint::UNCHANGING,
     };
     const DEFAULT_REF: &Self = &Self::DEFAULT;
@@ -144,9 +214,19 @@ impl<'a> arbitrary::Arbitrary<'a> for BlockAttributes {
             display_name: Cow::Owned(u.arbitrary()?),
             selectable: u.arbitrary()?,
             collision: u.arbitrary()?,
+            collision_groups: CollisionGroups::from_bits_retain(u.arbitrary()?),
             rotation_rule: u.arbitrary()?,
             light_emission: u.arbitrary()?,
             tick_action: None, // TODO: need Arbitrary for Block
+            tick_period: u.arbitrary()?,
+            signal: BlockSignal::None, // TODO: need Arbitrary for Block
+            portal: u
+                .arbitrary::<Option<([crate::math::GridCoordinate; 3], Face6)>>()?
+                .map(|(target, direction)| Portal {
+                    target: target.into(),
+                    direction,
+                }),
+            contact_damage: u.arbitrary()?,
             animation_hint: u.arbitrary()?,
         })
     }
@@ -156,8 +236,12 @@ impl<'a> arbitrary::Arbitrary<'a> for BlockAttributes {
             String::size_hint(depth),
             bool::size_hint(depth),
             BlockCollision::size_hint(depth),
+            u32::size_hint(depth),
             RotationPlacementRule::size_hint(depth),
             Rgb::size_hint(depth),
+            NonZeroU16::size_hint(depth),
+            Option::<([crate::math::GridCoordinate; 3], Face6)>::size_hint(depth),
+            NotNan::<f32>::size_hint(depth),
             AnimationHint::size_hint(depth),
         ])
     }
@@ -165,6 +249,9 @@ impl<'a> arbitrary::Arbitrary<'a> for BlockAttributes {
 
 /// Specifies the effect on a [`Body`](crate::physics::Body) of colliding with the
 /// [`Block`] this applies to.
+///
+/// Whether a given body collides with the block at all is determined separately, by
+/// [`BlockAttributes::collision_groups`].
 #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
 #[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[non_exhaustive]
@@ -181,9 +268,79 @@ pub enum BlockCollision {
     ///
     /// If the block does not have voxels then this is equivalent to [`Hard`](Self::Hard).
     Recur,
+    /// Collide with a set of boxes which together cover the block's colliding voxels,
+    /// as found in [`EvaluatedBlock::collision_boxes`](crate::block::EvaluatedBlock::collision_boxes).
+    ///
+    /// This has the same shape as [`Recur`](Self::Recur), but the boxes are computed once
+    /// when the block is evaluated, which makes collision with blocks made of large solid
+    /// regions, such as slabs and stairs, cheaper.
+    Boxes,
     // Future values might include bouncy solid, water-like resistance, force fields, etc.
 }
 
+/// Specifies how the [`Block`] this applies to takes part in signal propagation.
+///
+/// Signals are boolean levels which are recomputed by [`Space::step()`] on every tick:
+/// a cube is powered if it contains a [`Source`](Self::Source), or if it is a
+/// [`Wire`](Self::Wire) or [`Sink`](Self::Sink) adjacent (sharing a face) to a source or
+/// to a powered wire. Sinks do not pass the signal on.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+#[non_exhaustive]
+pub enum BlockSignal {
+    /// The block neither produces, conducts, nor reacts to signals.
+    None,
+    /// The block is always powered, and powers its neighbors.
+    Source,
+    /// The block is powered if any of its neighbors is, and then powers its other
+    /// neighbors.
+    Wire,
+    /// The block reacts to its signal level by painting the corresponding brush, if any,
+    /// at its position.
+    ///
+    /// The brush is painted when the level changes, and when the block is first placed.
+    /// Since a block placed by the brush will itself react to the current level, the
+    /// brush should replace the block with one that does not react to the same level;
+    /// for example, an unlit lamp might be replaced by a lit lamp whose `unpowered` brush
+    /// turns it back into the unlit lamp.
+    Sink {
+        /// Brush painted when the block is powered.
+        powered: Option<VoxelBrush<'static>>,
+        /// Brush painted when the block is not powered.
+        unpowered: Option<VoxelBrush<'static>>,
+    },
+}
+
+/// Specifies the view displayed by a [`Block`] whose [`BlockAttributes::portal`] is set.
+///
+/// The view is that of a camera at the center of the [`target`](Self::target) cube,
+/// looking in the given [`direction`](Self::direction).
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+#[allow(clippy::exhaustive_structs)]
+pub struct Portal {
+    /// The cube, in the same [`Space`] as the portal block, from which the view is seen.
+    pub target: GridPoint,
+    /// The direction the view looks in.
+    pub direction: Face6,
+}
+
+impl Portal {
+    /// Returns the eye-to-world transform of the camera whose view this portal displays,
+    /// suitable for [`Camera::set_view_transform()`](crate::camera::Camera::set_view_transform).
+    ///
+    /// The camera's “up” direction is +Y, or +Z if the `direction` is vertical.
+    pub fn view_transform(&self) -> ViewTransform {
+        let eye = self.target.map(FreeCoordinate::from) + Vector3::new(0.5, 0.5, 0.5);
+        let up = if self.direction.axis_number() == 1 {
+            Vector3::unit_z()
+        } else {
+            Vector3::unit_y()
+        };
+        Decomposed::look_at_rh(eye, eye + self.direction.normal_vector(), up)
+            .inverse_transform()
+            .unwrap()
+    }
+}
+
 /// Rule about how this block should be rotated, or not, when placed in a [`Space`] by
 /// some agent not otherwise specifying rotation.
 ///
@@ -357,6 +514,23 @@ mod tests {
             }),
             "BlockAttributes { light_emission: Rgb(1.0, 2.0, 3.0) }",
         );
+        assert_eq!(
+            &*debug(BlockAttributes {
+                signal: BlockSignal::Wire,
+                ..default()
+            }),
+            "BlockAttributes { signal: Wire }",
+        );
+        assert_eq!(
+            &*debug(BlockAttributes {
+                portal: Some(Portal {
+                    target: GridPoint::new(1, 2, 3),
+                    direction: Face6::PX,
+                }),
+                ..default()
+            }),
+            "BlockAttributes { portal: Some(Portal { target: Point3 [1, 2, 3], direction: PX }) }",
+        );
         assert_eq!(
             &*debug(BlockAttributes {
                 animation_hint: AnimationHint::TEMPORARY,
@@ -376,4 +550,24 @@ mod tests {
             "BlockAttributes { display_name: \"y\", selectable: false }",
         );
     }
+
+    #[test]
+    fn portal_view_transform() {
+        for direction in Face6::ALL {
+            let portal = Portal {
+                target: GridPoint::new(10, 20, 30),
+                direction,
+            };
+            let transform = portal.view_transform();
+            assert_eq!(transform.disp, Vector3::new(10.5, 20.5, 30.5));
+            // Cameras look towards -Z in eye coordinates.
+            assert_eq!(
+                transform
+                    .transform_vector(-Vector3::unit_z())
+                    .map(FreeCoordinate::round),
+                direction.normal_vector(),
+                "{direction:?}"
+            );
+        }
+    }
 }
@@ -1,8 +1,10 @@
+use std::fmt;
 use std::ops::Deref;
-use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
 
-use crate::block::{Block, BlockChange, Primitive};
-use crate::listen::{Gate, Listen, Listener, Notifier};
+use crate::block::{Block, BlockChange, EvalBlockError, MinEval, Primitive};
+use crate::listen::{DirtyFlag, Gate, Listen, Listener, Notifier};
 use crate::transaction::{self, Transaction};
 use crate::universe::{RefVisitor, VisitRefs};
 
@@ -14,13 +16,40 @@ use crate::universe::{RefVisitor, VisitRefs};
 /// will be delivered on any mutation.
 ///
 /// To perform a mutation, use [`BlockDefTransaction`].
-#[derive(Debug)]
+///
+/// The result of evaluating the block is cached, so that the many [`Block`]s which
+/// refer to one [`BlockDef`] do not each repeat the work; the cache is invalidated
+/// whenever this [`BlockDef`] notifies its listeners of a change.
 pub struct BlockDef {
     block: Block,
-    // TODO: It might be a good idea to cache EvaluatedBlock here, since we're doing
-    // mutation tracking anyway.
     notifier: Arc<Notifier<BlockChange>>,
     block_listen_gate: Gate,
+    cache: EvalCache,
+}
+
+/// Cached evaluation result of a [`BlockDef`].
+#[derive(Debug)]
+struct EvalCache {
+    /// Set whenever the [`BlockDef`]'s notifier is, meaning `value` may be stale.
+    dirty: DirtyFlag,
+    /// False if we failed to listen to the block, in which case we would not find out
+    /// about changes, so must not cache.
+    enabled: bool,
+    /// The cached evaluation, and the recursion depth it was evaluated at.
+    value: Mutex<Option<(u8, MinEval)>>,
+    hits: AtomicUsize,
+    misses: AtomicUsize,
+}
+
+/// Statistics about the evaluation cache of a [`BlockDef`], as returned by
+/// [`BlockDef::evaluation_cache_stats()`].
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+#[non_exhaustive]
+pub struct EvalCacheStats {
+    /// Number of evaluations which used the cached value.
+    pub hits: usize,
+    /// Number of evaluations which had to evaluate the block.
+    pub misses: usize,
 }
 
 impl BlockDef {
@@ -30,13 +59,75 @@ impl BlockDef {
         let notifier = Arc::new(Notifier::new());
         let (gate, block_listener) = Notifier::forwarder(Arc::downgrade(&notifier)).gate();
         // TODO: Consider making it an error if listening fails. BlockDefTransaction::check will need to follow.
-        let _ = block.listen(block_listener);
+        let listen_ok = block.listen(block_listener).is_ok();
+        let dirty = DirtyFlag::new(false);
+        notifier.listen(dirty.listener());
         BlockDef {
             block,
             notifier,
             block_listen_gate: gate,
+            cache: EvalCache {
+                dirty,
+                enabled: listen_ok,
+                value: Mutex::new(None),
+                hits: AtomicUsize::new(0),
+                misses: AtomicUsize::new(0),
+            },
+        }
+    }
+
+    /// Returns how many times the cached evaluation of this block definition has been
+    /// used or recomputed, for performance analysis.
+    pub fn evaluation_cache_stats(&self) -> EvalCacheStats {
+        EvalCacheStats {
+            hits: self.cache.hits.load(Ordering::Relaxed),
+            misses: self.cache.misses.load(Ordering::Relaxed),
         }
     }
+
+    /// Evaluates the block, or returns the cached result of a previous evaluation if
+    /// there has been no change since then.
+    ///
+    /// Errors are not cached, since they may depend on `depth` or be transient.
+    /// A successful result is only reused at the same or a shallower `depth` than it
+    /// was evaluated at, since evaluating deeper might exceed the recursion limit.
+    pub(super) fn evaluate_cached(&self, depth: u8) -> Result<MinEval, EvalBlockError> {
+        let cache = &self.cache;
+        if cache.enabled {
+            let mut value = cache.value.lock().unwrap();
+            if cache.dirty.get_and_clear() {
+                *value = None;
+            }
+            if let Some((cached_depth, value)) = &*value {
+                if depth <= *cached_depth {
+                    cache.hits.fetch_add(1, Ordering::Relaxed);
+                    return Ok(value.clone());
+                }
+            }
+        }
+
+        // Evaluate without holding the lock, because evaluation may be slow, and also
+        // a block which (erroneously) refers to itself would deadlock.
+        cache.misses.fetch_add(1, Ordering::Relaxed);
+        let result = self.block.evaluate_impl(depth)?;
+        if cache.enabled {
+            // If a change happened during evaluation, then the dirty flag is now set and
+            // the next evaluation will discard this value.
+            *cache.value.lock().unwrap() = Some((depth, result.clone()));
+        }
+        Ok(result)
+    }
+}
+
+impl fmt::Debug for BlockDef {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // The cache is omitted because it is redundant with the block.
+        f.debug_struct("BlockDef")
+            .field("block", &self.block)
+            .field("notifier", &self.notifier)
+            .field("block_listen_gate", &self.block_listen_gate)
+            .finish_non_exhaustive()
+    }
 }
 
 impl Listen for BlockDef {
@@ -81,7 +172,7 @@ impl VisitRefs for Primitive {
     fn visit_refs(&self, visitor: &mut dyn RefVisitor) {
         match self {
             Primitive::Indirect(block_ref) => visitor.visit(block_ref),
-            Primitive::Atom(_, _) | Primitive::Air => {}
+            Primitive::Atom(_, _) | Primitive::Faces(_, _) | Primitive::Air => {}
             Primitive::Recur { space, .. } => visitor.visit(space),
         }
     }
@@ -173,7 +264,7 @@ impl Transaction<BlockDef> for BlockDefTransaction {
             let (gate, block_listener) =
                 Notifier::forwarder(Arc::downgrade(&target.notifier)).gate();
             // TODO: Instead of ignoring the error from listen() here, we can fail the transaction by preparing the listener in check().
-            let _ = target.block.listen(block_listener);
+            target.cache.enabled = target.block.listen(block_listener).is_ok();
             target.block_listen_gate = gate; // old gate is now dropped
 
             target.notifier.notify(BlockChange::new());
@@ -2,14 +2,16 @@
 
 use cgmath::EuclideanSpace as _;
 use std::borrow::Cow;
+use std::num::NonZeroU16;
 use std::sync::Arc;
 
 use crate::block::{
     AnimationHint, Block, BlockAttributes, BlockCollision, BlockDef, BlockParts, BlockPtr,
-    Modifier, Primitive, Resolution, RotationPlacementRule, AIR,
+    BlockSignal, Modifier, Portal, Primitive, Resolution, RotationPlacementRule, AIR,
 };
 use crate::drawing::VoxelBrush;
-use crate::math::{GridPoint, Rgb, Rgba};
+use crate::math::{FaceMap, GridPoint, NotNan, Rgb, Rgba};
+use crate::physics::CollisionGroups;
 use crate::space::{SetCubeError, Space};
 use crate::universe::{Name, URef, Universe};
 
@@ -90,6 +92,12 @@ impl<C> BlockBuilder<C> {
         self
     }
 
+    /// Sets the value for [`BlockAttributes::collision_groups`].
+    pub const fn collision_groups(mut self, value: CollisionGroups) -> Self {
+        self.attributes.collision_groups = value;
+        self
+    }
+
     /// Sets the value for [`BlockAttributes::rotation_rule`].
     pub const fn rotation_rule(mut self, value: RotationPlacementRule) -> Self {
         self.attributes.rotation_rule = value;
@@ -108,6 +116,32 @@ impl<C> BlockBuilder<C> {
         self
     }
 
+    /// Sets the value for [`BlockAttributes::tick_period`].
+    pub fn tick_period(mut self, value: NonZeroU16) -> Self {
+        self.attributes.tick_period = value;
+        self
+    }
+
+    /// Sets the value for [`BlockAttributes::signal`].
+    pub fn signal(mut self, value: BlockSignal) -> Self {
+        self.attributes.signal = value;
+        self
+    }
+
+    /// Sets the value for [`BlockAttributes::portal`].
+    pub fn portal(mut self, value: Option<Portal>) -> Self {
+        self.attributes.portal = value;
+        self
+    }
+
+    /// Sets the value for [`BlockAttributes::contact_damage`].
+    ///
+    /// A NaN value is treated as zero.
+    pub fn contact_damage(mut self, value: f32) -> Self {
+        self.attributes.contact_damage = NotNan::new(value).unwrap_or_else(|_| notnan!(0.0));
+        self
+    }
+
     /// Sets the value for [`BlockAttributes::animation_hint`].
     pub fn animation_hint(mut self, value: AnimationHint) -> Self {
         self.attributes.animation_hint = value;
@@ -133,6 +167,17 @@ impl<C> BlockBuilder<C> {
         }
     }
 
+    /// Sets the color of each face for building a [`Primitive::Faces`].
+    ///
+    /// This will replace any previous color **or voxels.**
+    pub fn face_colors(self, colors: FaceMap<Rgba>) -> BlockBuilder<FaceMap<Rgba>> {
+        BlockBuilder {
+            attributes: self.attributes,
+            primitive_builder: colors,
+            modifiers: Vec::new(),
+        }
+    }
+
     /// Sets the space for building a [`Primitive::Recur`].
     ///
     /// This will replace any previous voxels **or color.**
@@ -280,6 +325,13 @@ impl BuildPrimitiveIndependent for Rgba {
     }
 }
 
+/// Used by [`BlockBuilder::face_colors`].
+impl BuildPrimitiveIndependent for FaceMap<Rgba> {
+    fn build_i(self, attributes: BlockAttributes) -> Primitive {
+        Primitive::Faces(attributes, self)
+    }
+}
+
 /// Concrete type for a [`BlockBuilder`] that is building a voxel block.
 #[derive(Clone, Debug, Eq, Hash, PartialEq)]
 pub struct BlockBuilderVoxels {
@@ -329,25 +381,40 @@ mod tests {
         let light_emission = Rgb::new(0.1, 3.0, 0.1);
         let rotation_rule = RotationPlacementRule::Attach { by: Face6::NZ };
         let tick_action = Some(VoxelBrush::single(AIR));
+        let tick_period = NonZeroU16::new(3).unwrap();
+        let portal = Some(Portal {
+            target: GridPoint::new(1, 2, 3),
+            direction: Face6::PY,
+        });
         assert_eq!(
             Block::builder()
                 .color(color)
                 .display_name("hello world")
                 .collision(BlockCollision::Recur)
+                .collision_groups(CollisionGroups::FLUID)
                 .rotation_rule(rotation_rule)
                 .selectable(false)
                 .light_emission(light_emission)
                 .tick_action(tick_action.clone())
+                .tick_period(tick_period)
+                .signal(BlockSignal::Wire)
+                .portal(portal)
+                .contact_damage(2.5)
                 .animation_hint(AnimationHint::TEMPORARY)
                 .build(),
             Block::from_primitive(Primitive::Atom(
                 BlockAttributes {
                     display_name: "hello world".into(),
                     collision: BlockCollision::Recur,
+                    collision_groups: CollisionGroups::FLUID,
                     rotation_rule,
                     selectable: false,
                     light_emission,
                     tick_action,
+                    tick_period,
+                    signal: BlockSignal::Wire,
+                    portal,
+                    contact_damage: notnan!(2.5),
                     animation_hint: AnimationHint::TEMPORARY,
                 },
                 color
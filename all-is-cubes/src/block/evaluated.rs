@@ -6,7 +6,10 @@ use cgmath::{Vector4, Zero as _};
 
 use crate::block::{self, BlockAttributes, Resolution, Resolution::R1};
 use crate::content::palette;
-use crate::math::{FaceMap, GridAab, GridArray, GridPoint, OpacityCategory, Rgb, Rgba};
+use crate::math::{
+    Face6, FaceMap, GridAab, GridArray, GridCoordinate, GridPoint, GridVector, OpacityCategory,
+    Rgb, Rgba,
+};
 use crate::universe::RefError;
 
 // Things mentioned in doc comments only
@@ -52,6 +55,14 @@ pub struct EvaluatedBlock {
     /// It doesn't harm normal operation because the point of having this is to compare
     /// block shapes, which is trivial if the block is invisible.)
     pub voxel_opacity_mask: Option<GridArray<OpacityCategory>>,
+
+    /// Non-overlapping boxes, in the coordinate system of [`Self::voxels`], which
+    /// together cover exactly the voxels whose collision is not
+    /// [`BlockCollision::None`](block::BlockCollision::None).
+    ///
+    /// This is computed only if [`BlockAttributes::collision`] is
+    /// [`BlockCollision::Boxes`](block::BlockCollision::Boxes), and is empty otherwise.
+    pub collision_boxes: Vec<GridAab>,
 }
 
 impl fmt::Debug for EvaluatedBlock {
@@ -63,6 +74,7 @@ impl fmt::Debug for EvaluatedBlock {
             opaque,
             visible,
             voxel_opacity_mask,
+            collision_boxes,
         } = self;
         let mut ds = fmt.debug_struct("EvaluatedBlock");
         ds.field("attributes", attributes);
@@ -74,6 +86,10 @@ impl fmt::Debug for EvaluatedBlock {
             Evoxels::One(evoxel) => {
                 ds.field("voxel", evoxel);
             }
+            Evoxels::Faces(evoxel, faces) => {
+                ds.field("voxel", evoxel);
+                ds.field("faces", faces);
+            }
             Evoxels::Many(_, array) => {
                 // Not printing the entire array which could be huge.
                 ds.field("voxels", &array.bounds());
@@ -83,6 +99,9 @@ impl fmt::Debug for EvaluatedBlock {
             "voxel_opacity_mask",
             &voxel_opacity_mask.as_ref().map(GridArray::bounds),
         );
+        if !collision_boxes.is_empty() {
+            ds.field("collision_boxes", collision_boxes);
+        }
         ds.finish()
     }
 }
@@ -94,16 +113,23 @@ impl EvaluatedBlock {
     ///
     /// This is also available as `impl From<MinEval> for EvaluatedBlock`.
     pub(crate) fn from_voxels(attributes: BlockAttributes, voxels: Evoxels) -> EvaluatedBlock {
+        let collision_boxes = if attributes.collision == block::BlockCollision::Boxes {
+            compute_collision_boxes(&voxels)
+        } else {
+            Vec::new()
+        };
+
         // Optimization for single voxels:
         // don't allocate any `GridArray`s or perform any generalized scans.
         if let Some(evoxel) = voxels.single_voxel() {
             let color = evoxel.color;
-            let visible = !color.fully_transparent();
+            let face_colors = voxels.single_voxel_face_colors().unwrap();
+            let visible = face_colors.values().any(|c| !c.fully_transparent());
             return EvaluatedBlock {
                 attributes,
                 color,
                 voxels,
-                opaque: FaceMap::repeat(color.fully_opaque()),
+                opaque: face_colors.map(|_, c| c.fully_opaque()),
                 visible,
                 // Note an edge case shenanigan:
                 // `AIR_EVALUATED` cannot allocate a mask, and we want this to match the
@@ -115,6 +141,7 @@ impl EvaluatedBlock {
                 } else {
                     Some(GridArray::from_element(color.opacity_category()))
                 },
+                collision_boxes,
             };
         }
 
@@ -172,6 +199,7 @@ impl EvaluatedBlock {
             }),
             visible,
             voxel_opacity_mask,
+            collision_boxes,
             voxels,
         }
     }
@@ -216,6 +244,48 @@ impl EvaluatedBlock {
     }
 }
 
+/// Computes [`EvaluatedBlock::collision_boxes`] by greedily merging colliding voxels
+/// into boxes, growing each box along X, then Y, then Z for as long as it stays solid.
+fn compute_collision_boxes(voxels: &Evoxels) -> Vec<GridAab> {
+    let bounds = voxels.bounds();
+    let upper_bounds = bounds.upper_bounds();
+    let mut uncovered = GridArray::from_fn(bounds, |p| {
+        voxels[p].collision != block::BlockCollision::None
+    });
+
+    let mut boxes = Vec::new();
+    for lower in bounds.interior_iter() {
+        if !uncovered[lower] {
+            continue;
+        }
+        let mut upper = lower + GridVector::new(1, 1, 1);
+        for axis in 0..3 {
+            while upper[axis] < upper_bounds[axis] {
+                // The layer of voxels that growing by one along this axis would add.
+                let mut layer_lower = lower;
+                layer_lower[axis] = upper[axis];
+                let mut layer_upper = upper;
+                layer_upper[axis] += 1;
+                if GridAab::from_lower_upper(layer_lower, layer_upper)
+                    .interior_iter()
+                    .all(|p| uncovered[p])
+                {
+                    upper[axis] += 1;
+                } else {
+                    break;
+                }
+            }
+        }
+
+        let collision_box = GridAab::from_lower_upper(lower, upper);
+        for p in collision_box.interior_iter() {
+            uncovered[p] = false;
+        }
+        boxes.push(collision_box);
+    }
+    boxes
+}
+
 /// Errors resulting from [`Block::evaluate`].
 #[derive(Clone, Debug, Eq, Hash, PartialEq, thiserror::Error)]
 #[non_exhaustive]
@@ -276,6 +346,13 @@ pub struct Evoxel {
     // These are frequently going to be copied into 32-bit texture color anyway.
     pub color: Rgba,
 
+    /// Light emitted (not reflected) by the voxel, in the same units as
+    /// [`BlockAttributes::light_emission`].
+    ///
+    /// Unlike the block attribute, this does not illuminate the surroundings; it only
+    /// affects the appearance of the voxel itself, such as to display a glowing pattern.
+    pub emission: Rgb,
+
     /// Whether players' [cursors](crate::character::Cursor) target this voxel's containing
     /// block or pass through it.
     pub selectable: bool,
@@ -290,6 +367,7 @@ impl Evoxel {
     /// TODO: Write a test for that.
     pub const AIR: Self = Self {
         color: Rgba::TRANSPARENT,
+        emission: Rgb::ZERO,
         selectable: false,
         collision: block::BlockCollision::None,
     };
@@ -300,6 +378,7 @@ impl Evoxel {
     pub fn from_block(block: &EvaluatedBlock) -> Self {
         Self {
             color: block.color,
+            emission: block.attributes.light_emission,
             selectable: block.attributes.selectable,
             collision: block.attributes.collision,
         }
@@ -314,6 +393,7 @@ impl Evoxel {
         const DA: &BlockAttributes = &BlockAttributes::default();
         Self {
             color,
+            emission: DA.light_emission,
             selectable: DA.selectable,
             collision: DA.collision,
         }
@@ -341,6 +421,12 @@ impl Evoxel {
 pub enum Evoxels {
     /// Compact representation of exactly one voxel. The resolution is implicitly 1.
     One(Evoxel),
+    /// Exactly one voxel whose faces have the given colors rather than the voxel's
+    /// color, which should be their mean. The resolution is implicitly 1.
+    ///
+    /// This is the result of evaluating a [`Primitive::Faces`], and allows blocks such
+    /// as grass (with a different top and sides) to be drawn without voxels.
+    Faces(Evoxel, FaceMap<Rgba>),
     /// The [`GridArray`] should not have any data outside of the expected bounds
     /// `GridAab::for_block(resolution)`.
     Many(Resolution, GridArray<Evoxel>),
@@ -352,7 +438,7 @@ impl Evoxels {
     #[inline]
     pub fn resolution(&self) -> Resolution {
         match *self {
-            Evoxels::One(_) => R1,
+            Evoxels::One(_) | Evoxels::Faces(..) => R1,
             Evoxels::Many(resolution, _) => resolution,
         }
     }
@@ -361,7 +447,7 @@ impl Evoxels {
     #[inline]
     pub fn single_voxel(&self) -> Option<Evoxel> {
         match *self {
-            Evoxels::One(v) => Some(v),
+            Evoxels::One(v) | Evoxels::Faces(v, _) => Some(v),
             Evoxels::Many(resolution, ref voxels) if resolution == R1 => {
                 Some(voxels.get([0, 0, 0]).copied().unwrap_or(Evoxel::AIR))
             }
@@ -369,6 +455,59 @@ impl Evoxels {
         }
     }
 
+    /// If this has a resolution of 1, then return the color of each face of that single
+    /// voxel. This differs from the voxel's color only for [`Evoxels::Faces`].
+    #[inline]
+    pub fn single_voxel_face_colors(&self) -> Option<FaceMap<Rgba>> {
+        match *self {
+            Evoxels::Faces(_, faces) => Some(faces),
+            _ => self.single_voxel().map(|v| FaceMap::repeat(v.color)),
+        }
+    }
+
+    /// If this has a resolution of 1, then return the voxel at `cube` of an equivalent
+    /// set of voxels with the given `resolution`.
+    ///
+    /// For [`Evoxels::Faces`], voxels on the surface of the block take the color of the
+    /// face they are on (preferring the top and bottom faces at edges), so that the block
+    /// looks the same from outside. Returns [`None`] if `self` is [`Evoxels::Many`] or
+    /// `cube` is out of bounds.
+    pub(crate) fn synthesize_voxel(
+        &self,
+        resolution: Resolution,
+        cube: GridPoint,
+    ) -> Option<Evoxel> {
+        if !GridAab::for_block(resolution).contains_cube(cube) {
+            return None;
+        }
+        match *self {
+            Evoxels::One(voxel) => Some(voxel),
+            Evoxels::Faces(voxel, faces) => {
+                let max = GridCoordinate::from(resolution) - 1;
+                const PRIORITY: [Face6; 6] = [
+                    Face6::PY,
+                    Face6::NY,
+                    Face6::NX,
+                    Face6::PX,
+                    Face6::NZ,
+                    Face6::PZ,
+                ];
+                let surface_face = PRIORITY.into_iter().find(|&face| {
+                    let edge = if face.is_positive() { max } else { 0 };
+                    cube[face.axis_number()] == edge
+                });
+                Some(match surface_face {
+                    Some(face) => Evoxel {
+                        color: faces[face],
+                        ..voxel
+                    },
+                    None => voxel,
+                })
+            }
+            Evoxels::Many(..) => None,
+        }
+    }
+
     /// Get the single voxel at the specified position, or [`None`] if the position is
     /// out of bounds of the data (which is not necessarily out of bounds of the block;
     /// missing data should be taken as [`Evoxel::AIR`]).
@@ -379,8 +518,10 @@ impl Evoxels {
     #[inline]
     pub fn get(&self, position: GridPoint) -> Option<Evoxel> {
         match (self, position) {
-            (&Evoxels::One(voxel), GridPoint { x: 0, y: 0, z: 0 }) => Some(voxel),
-            (Evoxels::One(_), _) => None,
+            (&(Evoxels::One(voxel) | Evoxels::Faces(voxel, _)), GridPoint { x: 0, y: 0, z: 0 }) => {
+                Some(voxel)
+            }
+            (Evoxels::One(_) | Evoxels::Faces(..), _) => None,
             (Evoxels::Many(_, ref voxels), position) => voxels.get(position).copied(),
         }
     }
@@ -389,7 +530,7 @@ impl Evoxels {
     #[inline]
     pub fn bounds(&self) -> GridAab {
         match *self {
-            Evoxels::One(_) => GridAab::ORIGIN_CUBE,
+            Evoxels::One(_) | Evoxels::Faces(..) => GridAab::ORIGIN_CUBE,
             Evoxels::Many(_, ref voxels) => voxels.bounds(),
         }
     }
@@ -402,8 +543,10 @@ impl std::ops::Index<GridPoint> for Evoxels {
     #[track_caller]
     fn index(&self, position: GridPoint) -> &Self::Output {
         match (self, position) {
-            (Evoxels::One(voxel), GridPoint { x: 0, y: 0, z: 0 }) => voxel,
-            (Evoxels::One(_), _) => panic!("out of bounds of Evoxels::One"),
+            (Evoxels::One(voxel) | Evoxels::Faces(voxel, _), GridPoint { x: 0, y: 0, z: 0 }) => {
+                voxel
+            }
+            (Evoxels::One(_) | Evoxels::Faces(..), _) => panic!("out of bounds of Evoxels::One"),
             (Evoxels::Many(_, voxels), position) => &voxels[position],
         }
     }
@@ -449,6 +592,7 @@ pub const AIR_EVALUATED: EvaluatedBlock = EvaluatedBlock {
     opaque: FaceMap::repeat_copy(false),
     visible: false,
     voxel_opacity_mask: None,
+    collision_boxes: Vec::new(),
 };
 
 pub(super) const AIR_EVALUATED_MIN: MinEval = MinEval {
@@ -465,9 +609,14 @@ const AIR_ATTRIBUTES: BlockAttributes = BlockAttributes {
     display_name: std::borrow::Cow::Borrowed("<air>"),
     selectable: false,
     collision: block::BlockCollision::None,
+    collision_groups: crate::physics::CollisionGroups::empty(),
     rotation_rule: block::RotationPlacementRule::Never,
     light_emission: Rgb::ZERO,
     tick_action: None,
+    tick_period: BlockAttributes::TICK_PERIOD_ONE,
+    signal: block::BlockSignal::None,
+    portal: None,
+    contact_damage: notnan!(0.0),
     animation_hint: block::AnimationHint::UNCHANGING,
 };
 
@@ -501,7 +650,8 @@ impl MinEval {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::block::{AnimationHint, Block, Resolution, Resolution::R2, AIR};
+    use crate::block::{AnimationHint, Block, Resolution, Resolution::*, AIR};
+    use crate::universe::Universe;
     use pretty_assertions::assert_eq;
 
     #[test]
@@ -534,11 +684,56 @@ mod tests {
                 voxels: Evoxels::Many(resolution, GridArray::from_elements(bounds, []).unwrap()),
                 opaque: FaceMap::repeat(false),
                 visible: false,
-                voxel_opacity_mask: None
+                voxel_opacity_mask: None,
+                collision_boxes: Vec::new(),
             }
         );
     }
 
+    #[test]
+    fn collision_boxes_of_stairs() {
+        let mut universe = Universe::new();
+        let block = Block::builder()
+            .collision(block::BlockCollision::Boxes)
+            .voxels_fn(&mut universe, R4, |cube| {
+                if cube.y < 2 || cube.z < 2 {
+                    Block::from(Rgba::WHITE)
+                } else {
+                    AIR
+                }
+            })
+            .unwrap()
+            .build();
+
+        assert_eq!(
+            block.evaluate().unwrap().collision_boxes,
+            vec![
+                GridAab::from_lower_upper([0, 0, 0], [4, 4, 2]),
+                GridAab::from_lower_upper([0, 0, 2], [4, 2, 4]),
+            ]
+        );
+    }
+
+    #[test]
+    fn collision_boxes_only_if_requested() {
+        let mut universe = Universe::new();
+        let block = Block::builder()
+            .collision(block::BlockCollision::Recur)
+            .voxels_fn(&mut universe, R4, |_| Block::from(Rgba::WHITE))
+            .unwrap()
+            .build();
+        assert_eq!(block.evaluate().unwrap().collision_boxes, vec![]);
+
+        let atom = Block::builder()
+            .color(Rgba::WHITE)
+            .collision(block::BlockCollision::Boxes)
+            .build();
+        assert_eq!(
+            atom.evaluate().unwrap().collision_boxes,
+            vec![GridAab::ORIGIN_CUBE]
+        );
+    }
+
     #[test]
     fn solid_block_equivalent_at_any_resolution() {
         let mut attributes = BlockAttributes::default();
@@ -1,10 +1,14 @@
-use crate::block::{Block, BlockChange, EvalBlockError, Evoxels, MinEval};
+use crate::block::{Block, BlockChange, BlockSignal, EvalBlockError, Evoxels, MinEval};
 use crate::listen::Listener;
-use crate::math::{GridArray, GridRotation, Rgb};
+use crate::math::{FaceMap, GridArray, GridRotation, Rgb};
 use crate::universe::{RefVisitor, VisitRefs};
 
+mod animate;
+pub use animate::*;
 mod composite;
 pub use composite::*;
+mod connect;
+pub use connect::*;
 mod r#move;
 pub use r#move::*;
 mod quote;
@@ -70,6 +74,13 @@ pub enum Modifier {
 
     /// Displace the block out of the grid, cropping it.
     Move(Move),
+
+    /// Display one of several variant blocks depending on which neighbors are the same
+    /// block, so that adjacent blocks such as fences or pipes visually connect.
+    Connect(Connect),
+
+    /// Display each of a sequence of blocks in turn, advancing on a fixed tick schedule.
+    Animate(Animate),
 }
 
 impl Modifier {
@@ -95,47 +106,47 @@ impl Modifier {
         Ok(match *self {
             Modifier::Quote(Quote { suppress_ambient }) => {
                 value.attributes.tick_action = None;
+                value.attributes.signal = BlockSignal::None;
                 if suppress_ambient {
                     value.attributes.light_emission = Rgb::ZERO;
                 }
                 value
             }
 
-            Modifier::Rotate(rotation) => {
-                if matches!(value.voxels, Evoxels::One(_)) {
-                    // Skip computation of transforms
-                    value
-                } else {
-                    // TODO: Add a shuffle-in-place rotation operation to GridArray and try implementing this using that, which should have less arithmetic involved than these matrix ops
-                    let resolution = value.resolution();
-                    let inner_to_outer = rotation.to_positive_octant_matrix(resolution.into());
-                    let outer_to_inner = rotation
-                        .inverse()
-                        .to_positive_octant_matrix(resolution.into());
-
+            Modifier::Rotate(rotation) => match value.voxels {
+                // Skip computation of transforms
+                Evoxels::One(_) => value,
+                Evoxels::Faces(voxel, faces) => {
+                    // Only the faces need to be permuted
+                    let inverse = rotation.inverse();
                     MinEval {
-                        voxels: Evoxels::Many(
-                            resolution,
-                            GridArray::from_fn(
-                                value.voxels.bounds().transform(inner_to_outer).unwrap(),
-                                |cube| {
-                                    value
-                                        .voxels
-                                        .get(outer_to_inner.transform_cube(cube))
-                                        .unwrap()
-                                },
-                            ),
+                        voxels: Evoxels::Faces(
+                            voxel,
+                            FaceMap::from_fn(|face| faces[inverse.transform(face)]),
                         ),
                         attributes: value.attributes,
                     }
                 }
-            }
+                Evoxels::Many(resolution, ref array) => MinEval {
+                    voxels: Evoxels::Many(
+                        resolution,
+                        array
+                            .transform(rotation.to_positive_octant_matrix(resolution.into()))
+                            .expect("rotation matrix unexpectedly not a rotation"),
+                    ),
+                    attributes: value.attributes,
+                },
+            },
 
             Modifier::Composite(ref c) => c.evaluate(value, depth)?,
 
             Modifier::Zoom(ref z) => z.evaluate(value)?,
 
             Modifier::Move(ref m) => m.evaluate(block, this_modifier_index, value, depth)?,
+
+            Modifier::Connect(ref c) => c.evaluate(value)?,
+
+            Modifier::Animate(ref a) => a.evaluate(block, this_modifier_index, value, depth)?,
         })
     }
 
@@ -158,6 +169,13 @@ impl Modifier {
             // TODO: Implement deletion of moving blocks.
             // This is essentially a 2-block multiblock situation.
             Modifier::Move(_) => ModifierUnspecialize::Keep,
+
+            // The connections are a function of the block's surroundings, so the
+            // modifier stays with the block.
+            Modifier::Connect(_) => ModifierUnspecialize::Keep,
+
+            // The animation is part of the block's identity, like a texture.
+            Modifier::Animate(_) => ModifierUnspecialize::Keep,
         }
     }
 
@@ -178,6 +196,8 @@ impl Modifier {
             }) => source.listen_impl(listener.clone(), super::next_depth(depth)?)?,
             Modifier::Zoom(_) => {}
             Modifier::Move { .. } => {}
+            Modifier::Connect(c) => c.listen_impl(listener, depth)?,
+            Modifier::Animate(a) => a.listen_impl(listener, depth)?,
         }
         Ok(())
     }
@@ -191,6 +211,8 @@ impl VisitRefs for Modifier {
             Modifier::Composite(m) => m.visit_refs(visitor),
             Modifier::Zoom(m) => m.visit_refs(visitor),
             Modifier::Move(m) => m.visit_refs(visitor),
+            Modifier::Connect(m) => m.visit_refs(visitor),
+            Modifier::Animate(m) => m.visit_refs(visitor),
         }
     }
 }
@@ -216,6 +238,17 @@ mod tests {
     use crate::universe::Universe;
     use pretty_assertions::assert_eq;
 
+    #[test]
+    fn rotate_faces() {
+        let rotation = GridRotation::RYXZ;
+        let faces = FaceMap::repeat(Rgba::WHITE).with(Face6::PY, Rgba::BLACK);
+        let block = Block::builder().face_colors(faces).build().rotate(rotation);
+        assert_eq!(
+            block.evaluate().unwrap().voxels.single_voxel_face_colors(),
+            Some(FaceMap::repeat(Rgba::WHITE).with(rotation.transform(Face6::PY), Rgba::BLACK))
+        );
+    }
+
     #[test]
     fn rotate_evaluation() {
         let resolution = R2;
@@ -262,6 +295,7 @@ mod tests {
                     GridArray::from_fn(block_bounds, |cube| {
                         Evoxel {
                             color: rotated_color_fn(cube),
+                            emission: Rgb::ZERO,
                             selectable: true,
                             collision: BlockCollision::Hard,
                         }
@@ -276,6 +310,7 @@ mod tests {
                         OpacityCategory::Invisible
                     }
                 })),
+                collision_boxes: Vec::new(),
             }
         );
     }
@@ -0,0 +1,316 @@
+use std::num::NonZeroU16;
+use std::sync::Arc;
+
+use crate::block::{self, AnimationChange, Block, BlockChange, Evoxels, MinEval, Modifier};
+use crate::drawing::VoxelBrush;
+use crate::listen::Listener;
+use crate::universe;
+
+/// Data for [`Modifier::Animate`]; replaces the block with each of a sequence of
+/// frame blocks in turn, advancing on a fixed tick schedule.
+///
+/// The block's primitive and any modifiers preceding this one are not displayed; the
+/// current frame is used in their place, and modifiers following this one are applied
+/// to the frame.
+///
+/// # Animation
+///
+/// While the block is in a [`Space`](crate::space::Space), at the end of each frame it
+/// replaces itself with a copy whose [`phase`](Self::phase) is the start of the next
+/// frame, wrapping around to the first frame after the last; the waiting is done by
+/// [`BlockAttributes::tick_period`](block::BlockAttributes::tick_period), so the block
+/// is not replaced on ticks where the frame does not change. The evaluated block's
+/// [`AnimationHint::replacement`](block::AnimationHint::replacement) describes whether
+/// the next frame has the same shape as the current one, so that renderers may update
+/// the existing texture instead of allocating a new one.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub struct Animate {
+    frames: Arc<[Block]>,
+    ticks_per_frame: NonZeroU16,
+    /// Number of ticks since the start of the first frame.
+    phase: u32,
+}
+
+impl Animate {
+    /// Construct an [`Animate`] modifier which starts at the beginning of the first frame.
+    ///
+    /// If `frames` is empty, the modifier has no effect.
+    pub fn new(frames: impl Into<Arc<[Block]>>, ticks_per_frame: NonZeroU16) -> Self {
+        Self {
+            frames: frames.into(),
+            ticks_per_frame,
+            phase: 0,
+        }
+    }
+
+    /// Returns a copy of this modifier at the given phase instead, which is taken
+    /// modulo the length of the animation.
+    ///
+    /// This may be used to start multiple animated blocks at different points
+    /// in the cycle.
+    #[must_use]
+    pub fn with_phase(mut self, phase: u32) -> Self {
+        self.phase = (u64::from(phase) % self.cycle_length()) as u32;
+        self
+    }
+
+    /// Returns the blocks which are displayed in sequence.
+    pub fn frames(&self) -> &[Block] {
+        &self.frames
+    }
+
+    /// Returns the number of ticks for which each frame is displayed.
+    pub fn ticks_per_frame(&self) -> NonZeroU16 {
+        self.ticks_per_frame
+    }
+
+    /// Returns the number of ticks since the start of the first frame.
+    pub fn phase(&self) -> u32 {
+        self.phase
+    }
+
+    /// Returns the index in [`Animate::frames()`] of the frame currently displayed.
+    pub fn frame_index(&self) -> usize {
+        self.phase as usize / usize::from(self.ticks_per_frame.get())
+    }
+
+    /// Number of ticks in one full cycle of the animation; never zero.
+    fn cycle_length(&self) -> u64 {
+        (self.frames.len() as u64 * u64::from(self.ticks_per_frame.get())).max(1)
+    }
+
+    /// Returns the number of ticks remaining until the next frame starts.
+    fn ticks_until_next_frame(&self) -> NonZeroU16 {
+        let tpf = u32::from(self.ticks_per_frame.get());
+        NonZeroU16::new((tpf - self.phase % tpf) as u16)
+            .expect("phase within frame is less than ticks_per_frame")
+    }
+
+    /// Returns the state of this modifier at the start of the next frame.
+    fn advanced(&self) -> Self {
+        Self {
+            phase: ((u64::from(self.phase) + u64::from(self.ticks_until_next_frame().get()))
+                % self.cycle_length()) as u32,
+            ..self.clone()
+        }
+    }
+
+    /// Called by [`Modifier::evaluate`].
+    pub(super) fn evaluate(
+        &self,
+        block: &Block,
+        this_modifier_index: usize,
+        value: MinEval,
+        depth: u8,
+    ) -> Result<MinEval, block::EvalBlockError> {
+        let frame_index = self.frame_index();
+        let Some(frame) = self.frames.get(frame_index) else {
+            return Ok(value);
+        };
+        let mut value = frame.evaluate_impl(block::next_depth(depth)?)?;
+
+        // A single frame never changes, so it needs no animation.
+        if self.frames.len() > 1 {
+            assert!(
+                matches!(&block.modifiers()[this_modifier_index], Modifier::Animate(m) if m == self)
+            );
+            let next = self.advanced();
+            let next_frame_index = next.frame_index();
+
+            // Tell renderers whether the replacement can reuse the current mesh.
+            let same_shape_as_next = {
+                let next_value =
+                    self.frames[next_frame_index].evaluate_impl(block::next_depth(depth)?)?;
+                same_shape(&value.voxels, &next_value.voxels)
+            };
+            value.attributes.animation_hint.replacement = if same_shape_as_next {
+                AnimationChange::ColorSameCategory
+            } else {
+                AnimationChange::Shape
+            };
+
+            let mut next_block = block.clone();
+            next_block.modifiers_mut()[this_modifier_index] = Modifier::Animate(next);
+            value.attributes.tick_action = Some(VoxelBrush::single(next_block));
+            value.attributes.tick_period = self.ticks_until_next_frame();
+        }
+
+        Ok(value)
+    }
+
+    /// Called by [`Modifier::listen_impl()`].
+    pub(super) fn listen_impl(
+        &self,
+        listener: &(impl Listener<BlockChange> + Clone + Send + Sync + 'static),
+        depth: u8,
+    ) -> Result<(), block::EvalBlockError> {
+        for frame in self.frames.iter() {
+            frame.listen_impl(listener.clone(), block::next_depth(depth)?)?;
+        }
+        Ok(())
+    }
+}
+
+/// Returns whether every voxel of `a` and `b` has the same opacity category, so that
+/// a mesh of one can be reused for the other with only a texture change.
+fn same_shape(a: &Evoxels, b: &Evoxels) -> bool {
+    a.resolution() == b.resolution()
+        && a.bounds() == b.bounds()
+        && a.bounds()
+            .interior_iter()
+            .all(|p| a[p].color.opacity_category() == b[p].color.opacity_category())
+}
+
+impl From<Animate> for block::Modifier {
+    fn from(value: Animate) -> Self {
+        Modifier::Animate(value)
+    }
+}
+
+impl universe::VisitRefs for Animate {
+    fn visit_refs(&self, visitor: &mut dyn universe::RefVisitor) {
+        let Animate {
+            frames,
+            ticks_per_frame: _,
+            phase: _,
+        } = self;
+        for frame in frames.iter() {
+            frame.visit_refs(visitor);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::block::{AnimationHint, AIR};
+    use crate::content::make_some_blocks;
+    use crate::math::Rgba;
+    use crate::space::Space;
+    use crate::time::{practically_infinite_deadline, Tick};
+    use pretty_assertions::assert_eq;
+
+    fn ticks(n: u16) -> NonZeroU16 {
+        NonZeroU16::new(n).unwrap()
+    }
+
+    #[test]
+    fn evaluates_current_frame() {
+        let [base, f0, f1] = make_some_blocks();
+        let animate = Animate::new(vec![f0.clone(), f1.clone()], ticks(1));
+
+        let block = base.clone().with_modifier(animate.clone());
+        assert_eq!(
+            block.evaluate().unwrap().color,
+            f0.evaluate().unwrap().color
+        );
+
+        let block = base.with_modifier(animate.with_phase(1));
+        assert_eq!(
+            block.evaluate().unwrap().color,
+            f1.evaluate().unwrap().color
+        );
+    }
+
+    #[test]
+    fn tick_action_advances_and_wraps() {
+        let [base, f0, f1] = make_some_blocks();
+        let animate = Animate::new(vec![f0, f1], ticks(2));
+        let at_phase = |phase: u32| {
+            base.clone()
+                .with_modifier(animate.clone().with_phase(phase))
+        };
+
+        for (phase, next_phase, period) in [(0, 2, 2), (1, 2, 1), (2, 0, 2), (3, 0, 1)] {
+            let attributes = at_phase(phase).evaluate().unwrap().attributes;
+            assert_eq!(
+                (attributes.tick_action, attributes.tick_period.get()),
+                (Some(VoxelBrush::single(at_phase(next_phase))), period),
+                "phase {phase}"
+            );
+        }
+    }
+
+    #[test]
+    fn single_frame_is_not_animated() {
+        let [base, f0] = make_some_blocks();
+        let block = base.with_modifier(Animate::new(vec![f0.clone()], ticks(1)));
+        assert_eq!(block.evaluate().unwrap(), f0.evaluate().unwrap());
+    }
+
+    #[test]
+    fn no_frames_is_unchanged() {
+        let [base] = make_some_blocks();
+        let block = base
+            .clone()
+            .with_modifier(Animate::new(Vec::new(), ticks(1)));
+        assert_eq!(block.evaluate().unwrap(), base.evaluate().unwrap());
+    }
+
+    #[test]
+    fn animation_hint_reflects_next_frame() {
+        let [base, f0, f1] = make_some_blocks();
+        let animate = Animate::new(vec![f0, f1, AIR], ticks(1));
+        let hint_at_phase = |phase: u32| {
+            base.clone()
+                .with_modifier(animate.clone().with_phase(phase))
+                .evaluate()
+                .unwrap()
+                .attributes
+                .animation_hint
+        };
+
+        assert_eq!(
+            hint_at_phase(0),
+            AnimationHint {
+                redefinition: AnimationChange::None,
+                replacement: AnimationChange::ColorSameCategory,
+            }
+        );
+        assert_eq!(hint_at_phase(1).replacement, AnimationChange::Shape);
+        assert_eq!(hint_at_phase(2).replacement, AnimationChange::Shape);
+    }
+
+    #[test]
+    fn animates_in_space() {
+        let f0 = Block::from(Rgba::new(1.0, 0.0, 0.0, 1.0));
+        let f1 = Block::from(Rgba::new(0.0, 1.0, 0.0, 1.0));
+        let animate = Animate::new(vec![f0.clone(), f1.clone()], ticks(1));
+        let block = AIR.with_modifier(animate);
+
+        let mut space = Space::empty_positive(1, 1, 1);
+        space.set([0, 0, 0], &block).unwrap();
+        assert_eq!(
+            space.get_evaluated([0, 0, 0]).color,
+            f0.evaluate().unwrap().color
+        );
+
+        space.step(None, Tick::arbitrary(), practically_infinite_deadline());
+        assert_eq!(
+            space.get_evaluated([0, 0, 0]).color,
+            f1.evaluate().unwrap().color
+        );
+
+        space.step(None, Tick::arbitrary(), practically_infinite_deadline());
+        assert_eq!(&space[[0, 0, 0]], &block);
+    }
+
+    #[test]
+    fn replaced_only_when_frame_changes() {
+        let [f0, f1] = make_some_blocks();
+        let block = AIR.with_modifier(Animate::new(vec![f0, f1], ticks(3)));
+
+        let mut space = Space::empty_positive(1, 1, 1);
+        space.set([0, 0, 0], &block).unwrap();
+        let mut phases = Vec::new();
+        for _ in 0..6 {
+            space.step(None, Tick::arbitrary(), practically_infinite_deadline());
+            match space[[0, 0, 0]].modifiers() {
+                [Modifier::Animate(a)] => phases.push(a.phase()),
+                other => panic!("unexpected modifiers {other:?}"),
+            }
+        }
+        assert_eq!(phases, vec![0, 0, 3, 3, 3, 0]);
+    }
+}
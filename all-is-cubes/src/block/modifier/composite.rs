@@ -5,7 +5,7 @@ use ordered_float::NotNan;
 use crate::block::{
     self, Block, BlockCollision, Evoxel, Evoxels, MinEval, Modifier, Resolution::R1, AIR,
 };
-use crate::math::{GridAab, GridArray, GridCoordinate, GridRotation, Rgba};
+use crate::math::{FaceMap, GridAab, GridArray, GridCoordinate, GridPoint, GridRotation, Rgba};
 use crate::universe;
 
 /// Data for [`Modifier::Composite`], describing how to combine the voxels of another
@@ -59,8 +59,11 @@ impl Composite {
 
     /// Compose `self` and `destination`, except that:
     ///
-    /// * If `destination` is [`AIR`], then the `self.source` block will be returned.
-    /// * If `self.source` is [`AIR`], then `destination` will be returned.
+    /// * If `destination` is [`AIR`], then the `self.source` block will be returned,
+    ///   or [`AIR`] if the operator is [`CompositeOperator::Subtract`] or
+    ///   [`CompositeOperator::Intersect`].
+    /// * If `self.source` is [`AIR`], then `destination` will be returned,
+    ///   or [`AIR`] if the operator is [`CompositeOperator::Intersect`].
     /// * If `destination` has a rotation modifier, it will be rearranged to be last.
     ///   (In this way, there won't be any unequal-but-equivalent blocks generated due
     ///   to rotation.)
@@ -89,7 +92,12 @@ impl Composite {
             // Note: Since we removed rotation, this is currently equivalent to
             // testing against Block::unspecialize(), but it might not be in the future.
             // We could use a better solution.
-            self.source
+            match self.operator {
+                CompositeOperator::Over => self.source,
+                CompositeOperator::Subtract | CompositeOperator::Intersect => AIR,
+            }
+        } else if self.source == AIR && self.operator == CompositeOperator::Intersect {
+            AIR
         } else if self.source == AIR {
             // If the source is AIR, produce the original destination block.
             destination.rotate(dest_rot)
@@ -145,12 +153,27 @@ impl Composite {
             GridCoordinate::from(effective_resolution) / GridCoordinate::from(dst_resolution);
 
         Ok(if effective_resolution == R1 {
+            let voxel = operator.blend_evoxel(
+                src_voxels.single_voxel().unwrap(),
+                dst_voxels.single_voxel().unwrap(),
+            );
             MinEval {
                 attributes,
-                voxels: Evoxels::One(operator.blend_evoxel(
-                    src_voxels.single_voxel().unwrap(),
-                    dst_voxels.single_voxel().unwrap(),
-                )),
+                voxels: if matches!(src_voxels, Evoxels::Faces(..))
+                    || matches!(dst_voxels, Evoxels::Faces(..))
+                {
+                    // Preserve the separate face colors.
+                    let src_faces = src_voxels.single_voxel_face_colors().unwrap();
+                    let dst_faces = dst_voxels.single_voxel_face_colors().unwrap();
+                    Evoxels::Faces(
+                        voxel,
+                        FaceMap::from_fn(|face| {
+                            operator.blend_color(src_faces[face], dst_faces[face])
+                        }),
+                    )
+                } else {
+                    Evoxels::One(voxel)
+                },
             }
         } else {
             MinEval {
@@ -161,8 +184,8 @@ impl Composite {
                     effective_resolution,
                     GridArray::from_fn(GridAab::for_block(effective_resolution), |p| {
                         operator.blend_evoxel(
-                            src_voxels.get(p / src_scale).unwrap_or(Evoxel::AIR),
-                            dst_voxels.get(p / dst_scale).unwrap_or(Evoxel::AIR),
+                            scaled_voxel(&src_voxels, effective_resolution, src_scale, p),
+                            scaled_voxel(&dst_voxels, effective_resolution, dst_scale, p),
                         )
                     }),
                 ),
@@ -185,6 +208,22 @@ impl Composite {
     }
 }
 
+/// Returns the voxel of `voxels` at `cube` when they are enlarged by `scale` to fill a
+/// block of `resolution`.
+fn scaled_voxel(
+    voxels: &Evoxels,
+    resolution: block::Resolution,
+    scale: GridCoordinate,
+    cube: GridPoint,
+) -> Evoxel {
+    match voxels {
+        Evoxels::Many(..) => voxels.get(cube / scale),
+        // Synthesize voxels so that `Evoxels::Faces` keeps its face colors.
+        Evoxels::One(_) | Evoxels::Faces(..) => voxels.synthesize_voxel(resolution, cube),
+    }
+    .unwrap_or(Evoxel::AIR)
+}
+
 impl From<Composite> for Modifier {
     fn from(value: Composite) -> Self {
         Modifier::Composite(value)
@@ -214,6 +253,18 @@ pub enum CompositeOperator {
     /// Porter-Duff “over”. If both source and destination are opaque, the source is taken;
     /// otherwise the destination is taken.
     Over,
+
+    /// Constructive solid geometry difference: the destination with the source's shape
+    /// cut out of it. The source's colors are not used, only its opacity.
+    ///
+    /// This is Porter-Duff “destination out”.
+    Subtract,
+
+    /// Constructive solid geometry intersection: the destination, except only where the
+    /// source is also present. The source's colors are not used, only its opacity.
+    ///
+    /// This is Porter-Duff “destination in”.
+    Intersect,
     // /// Split the volume in half on the plane perpendicular to `[1, 0, 1]`; all voxels
     // /// on the side nearer to the origin are taken from the destination, and all voxels
     // /// on the farther side or exactly on the plane are taken from the source.
@@ -233,19 +284,58 @@ impl CompositeOperator {
                 let rgb = source.to_rgb() * sa + destination.to_rgb() * sa_complement;
                 rgb.with_alpha(sa + sa_complement * destination.alpha())
             }
+            Self::Subtract => {
+                let sa_complement = NotNan::new(1. - source.alpha().into_inner()).unwrap();
+                destination
+                    .to_rgb()
+                    .with_alpha(destination.alpha() * sa_complement)
+            }
+            Self::Intersect => destination
+                .to_rgb()
+                .with_alpha(destination.alpha() * source.alpha()),
         }
     }
 
     fn blend_evoxel(&self, src_ev: Evoxel, dst_ev: Evoxel) -> Evoxel {
         use BlockCollision as Coll;
-        Evoxel {
-            color: self.blend_color(src_ev.color, dst_ev.color),
-            // TODO: specific operator should control all of these; we need an idea of what mask to
-            // apply to discrete attributes.
-            selectable: src_ev.selectable | dst_ev.selectable,
-            collision: match (src_ev.collision, dst_ev.collision) {
-                (Coll::Hard | Coll::Recur, _) | (_, Coll::Hard | Coll::Recur) => Coll::Hard,
-                (Coll::None, Coll::None) => Coll::None,
+        let color = self.blend_color(src_ev.color, dst_ev.color);
+        match self {
+            Self::Over => Evoxel {
+                color,
+                // Emission is covered by the source in proportion to its opacity.
+                emission: {
+                    let sa = src_ev.color.clamp().alpha();
+                    let sa_complement = NotNan::new(1. - sa.into_inner()).unwrap();
+                    src_ev.emission * sa + dst_ev.emission * sa_complement
+                },
+                // TODO: specific operator should control all of these; we need an idea of
+                // what mask to apply to discrete attributes.
+                selectable: src_ev.selectable | dst_ev.selectable,
+                collision: match (src_ev.collision, dst_ev.collision) {
+                    (Coll::Hard | Coll::Recur | Coll::Boxes, _)
+                    | (_, Coll::Hard | Coll::Recur | Coll::Boxes) => Coll::Hard,
+                    (Coll::None, Coll::None) => Coll::None,
+                },
+            },
+            // For the CSG operators, the discrete attributes of the destination are kept
+            // or removed according to whether the source is solid.
+            Self::Subtract => Evoxel {
+                color,
+                emission: dst_ev.emission,
+                selectable: dst_ev.selectable & !src_ev.selectable,
+                collision: match src_ev.collision {
+                    Coll::None => dst_ev.collision,
+                    Coll::Hard | Coll::Recur | Coll::Boxes => Coll::None,
+                },
+            },
+            Self::Intersect => Evoxel {
+                color,
+                emission: dst_ev.emission,
+                selectable: dst_ev.selectable & src_ev.selectable,
+                collision: match src_ev.collision {
+                    Coll::None => Coll::None,
+                    Coll::Hard | Coll::Recur | Coll::Boxes => dst_ev.collision,
+                },
             },
         }
     }
@@ -254,7 +344,10 @@ impl CompositeOperator {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::block::Resolution::R2;
     use crate::content::make_some_blocks;
+    use crate::math::{Face6, OpacityCategory};
+    use crate::universe::Universe;
     use pretty_assertions::assert_eq;
 
     #[test]
@@ -266,6 +359,98 @@ mod tests {
         );
     }
 
+    /// Construct a pair of blocks for testing the CSG operators: a completely solid
+    /// destination block, and a source block which is solid only in its lower half.
+    fn csg_test_blocks(universe: &mut Universe) -> (Block, Block) {
+        let destination = Block::builder()
+            .voxels_fn(universe, R2, |_| Block::from(Rgba::WHITE))
+            .unwrap()
+            .build();
+        let source = Block::builder()
+            .voxels_fn(universe, R2, |cube| {
+                if cube.y == 0 {
+                    Block::from(Rgba::BLACK)
+                } else {
+                    AIR
+                }
+            })
+            .unwrap()
+            .build();
+        (destination, source)
+    }
+
+    fn opacity_mask_by_y(
+        lower: OpacityCategory,
+        upper: OpacityCategory,
+    ) -> GridArray<OpacityCategory> {
+        GridArray::from_fn(GridAab::for_block(R2), |cube| {
+            if cube.y == 0 {
+                lower
+            } else {
+                upper
+            }
+        })
+    }
+
+    #[test]
+    fn subtract_opacity_mask() {
+        let mut universe = Universe::new();
+        let (destination, source) = csg_test_blocks(&mut universe);
+        let ev = destination
+            .with_modifier(Composite::new(source, CompositeOperator::Subtract))
+            .evaluate()
+            .unwrap();
+        assert_eq!(
+            ev.voxel_opacity_mask,
+            Some(opacity_mask_by_y(
+                OpacityCategory::Invisible,
+                OpacityCategory::Opaque
+            ))
+        );
+        // The remaining voxels keep the destination's color.
+        assert_eq!(ev.voxels.single_voxel(), None);
+        assert_eq!(ev.voxels.get([0, 1, 0].into()).unwrap().color, Rgba::WHITE);
+        assert_eq!(
+            ev.voxels.get([0, 0, 0].into()).unwrap().collision,
+            BlockCollision::None
+        );
+    }
+
+    #[test]
+    fn intersect_opacity_mask() {
+        let mut universe = Universe::new();
+        let (destination, source) = csg_test_blocks(&mut universe);
+        let ev = destination
+            .with_modifier(Composite::new(source, CompositeOperator::Intersect))
+            .evaluate()
+            .unwrap();
+        assert_eq!(
+            ev.voxel_opacity_mask,
+            Some(opacity_mask_by_y(
+                OpacityCategory::Opaque,
+                OpacityCategory::Invisible
+            ))
+        );
+        assert_eq!(ev.voxels.get([0, 0, 0].into()).unwrap().color, Rgba::WHITE);
+        assert_eq!(
+            ev.voxels.get([0, 1, 0].into()).unwrap().collision,
+            BlockCollision::None
+        );
+    }
+
+    #[test]
+    fn subtract_reversed_opacity_mask() {
+        let mut universe = Universe::new();
+        let (destination, source) = csg_test_blocks(&mut universe);
+        // With reverse, the solid block is subtracted from the half block,
+        // leaving nothing.
+        let mut composite = Composite::new(source, CompositeOperator::Subtract);
+        composite.reverse = true;
+        let ev = destination.with_modifier(composite).evaluate().unwrap();
+        assert_eq!(ev.voxel_opacity_mask, None);
+        assert!(!ev.visible);
+    }
+
     #[test]
     fn compose_or_replace_source_is_air() {
         let [block] = make_some_blocks();
@@ -301,4 +486,40 @@ mod tests {
         );
         assert_eq!(composed.unspecialize(), vec![b2, b1]);
     }
+
+    #[test]
+    fn faces_preserved_at_resolution_1() {
+        let faces = FaceMap::repeat(Rgba::WHITE).with(Face6::PY, Rgba::BLACK);
+        let block = Block::builder()
+            .face_colors(faces)
+            .build()
+            .with_modifier(Composite::new(
+                Block::from(Rgba::TRANSPARENT),
+                CompositeOperator::Over,
+            ));
+        assert_eq!(
+            block.evaluate().unwrap().voxels.single_voxel_face_colors(),
+            Some(faces)
+        );
+    }
+
+    #[test]
+    fn faces_expanded_to_voxels() {
+        let mut universe = Universe::new();
+        let transparent = Block::from(Rgba::TRANSPARENT);
+        let source = Block::builder()
+            .voxels_fn(&mut universe, R2, |_| &transparent)
+            .unwrap()
+            .build();
+        let faces = FaceMap::repeat(Rgba::WHITE).with(Face6::PY, Rgba::BLACK);
+        let block = Block::builder()
+            .face_colors(faces)
+            .build()
+            .with_modifier(Composite::new(source, CompositeOperator::Over));
+
+        let ev = block.evaluate().unwrap();
+        assert_eq!(ev.resolution(), R2);
+        assert_eq!(ev.voxels[GridPoint::new(0, 1, 0)].color, Rgba::BLACK);
+        assert_eq!(ev.voxels[GridPoint::new(0, 0, 0)].color, Rgba::WHITE);
+    }
 }
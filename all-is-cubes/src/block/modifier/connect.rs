@@ -0,0 +1,193 @@
+use std::sync::Arc;
+
+use crate::block::{self, Block, BlockChange, MinEval, Modifier};
+use crate::listen::Listener;
+use crate::math::{Face6, FaceMap};
+use crate::universe;
+
+/// Data for [`Modifier::Connect`], which makes a block change its appearance depending on
+/// which of its neighbors are the same block, so that, for example, fences, glass panes,
+/// and pipes visually join together.
+///
+/// Evaluating a block with this modifier does not change it; the variants are only used
+/// when the block is placed in a [`Space`](crate::space::Space) and the block's neighbors
+/// are known, such as when meshing. In that case, the block is displayed as
+/// [`Block::with_connections()`] returns.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub struct Connect {
+    /// Which faces of the block may connect to a neighbor.
+    faces: FaceMap<bool>,
+
+    /// Blocks to display instead of the original block, indexed by
+    /// [`Connect::variant_index()`].
+    variants: Arc<[Block]>,
+}
+
+impl Connect {
+    /// Construct a [`Connect`] modifier.
+    ///
+    /// * `faces` specifies which faces of the block may connect to a neighboring block.
+    /// * `variants` are the blocks to display for each combination of connections,
+    ///   indexed by [`Connect::variant_index()`]. Normally, there should be
+    ///   [`Connect::variant_count()`] of them; any which are missing are displayed as
+    ///   the block without connections.
+    ///   Typically, `variants[0]`, with no connections, should look the same as the block
+    ///   without this modifier.
+    pub fn new(faces: FaceMap<bool>, variants: impl Into<Arc<[Block]>>) -> Self {
+        Self {
+            faces,
+            variants: variants.into(),
+        }
+    }
+
+    /// Returns which faces of the block may connect to a neighbor.
+    pub fn faces(&self) -> FaceMap<bool> {
+        self.faces
+    }
+
+    /// Returns the blocks to display for each combination of connections.
+    pub fn variants(&self) -> &[Block] {
+        &self.variants
+    }
+
+    /// Returns the number of distinct combinations of connections, which is
+    /// 2<sup>n</sup> where n is the number of faces which may connect.
+    pub fn variant_count(&self) -> usize {
+        1 << self.faces.values().filter(|&&connects| connects).count()
+    }
+
+    /// Returns the index into [`Connect::variants()`] which should be used when the block
+    /// has a neighbor which is the same block on each face for which `connections` is true.
+    ///
+    /// Each face for which [`Connect::faces()`] is true is assigned one bit of the index,
+    /// in the order of [`Face6::ALL`], starting from the least significant bit.
+    /// Other faces are ignored.
+    ///
+    /// ```
+    /// use all_is_cubes::block::{Connect, AIR};
+    /// use all_is_cubes::math::{Face6, FaceMap};
+    ///
+    /// // Connects only horizontally, like a fence.
+    /// let connect = Connect::new(
+    ///     FaceMap::repeat(true).with(Face6::NY, false).with(Face6::PY, false),
+    ///     vec![AIR; 16],
+    /// );
+    /// assert_eq!(connect.variant_index(FaceMap::repeat(false)), 0);
+    /// assert_eq!(connect.variant_index(FaceMap::repeat(false).with(Face6::NZ, true)), 0b0010);
+    /// assert_eq!(connect.variant_index(FaceMap::repeat(true)), 0b1111);
+    /// ```
+    pub fn variant_index(&self, connections: FaceMap<bool>) -> usize {
+        let mut index = 0;
+        let mut bit = 0;
+        for face in Face6::ALL {
+            if self.faces[face] {
+                if connections[face] {
+                    index |= 1 << bit;
+                }
+                bit += 1;
+            }
+        }
+        index
+    }
+
+    /// Returns the block which `block`, whose modifier at `this_modifier_index` is `self`,
+    /// should be displayed as, or [`None`] if the variant is missing.
+    ///
+    /// Modifiers following this one are applied to the variant.
+    pub(crate) fn variant_block(
+        &self,
+        block: &Block,
+        this_modifier_index: usize,
+        variant_index: usize,
+    ) -> Option<Block> {
+        let mut variant = self.variants.get(variant_index)?.clone();
+        variant
+            .modifiers_mut()
+            .extend(block.modifiers()[this_modifier_index + 1..].iter().cloned());
+        Some(variant)
+    }
+
+    pub(super) fn evaluate(&self, value: MinEval) -> Result<MinEval, block::EvalBlockError> {
+        // Without knowledge of the neighbors, the block is unchanged.
+        Ok(value)
+    }
+
+    /// Called by [`Modifier::listen_impl()`].
+    pub(super) fn listen_impl(
+        &self,
+        listener: &(impl Listener<BlockChange> + Clone + Send + Sync + 'static),
+        depth: u8,
+    ) -> Result<(), block::EvalBlockError> {
+        // The variants do not affect evaluation, but they do affect how the block is
+        // displayed, so changes to them should be reported.
+        for variant in self.variants.iter() {
+            variant.listen_impl(listener.clone(), block::next_depth(depth)?)?;
+        }
+        Ok(())
+    }
+}
+
+impl From<Connect> for block::Modifier {
+    fn from(value: Connect) -> Self {
+        Modifier::Connect(value)
+    }
+}
+
+impl universe::VisitRefs for Connect {
+    fn visit_refs(&self, visitor: &mut dyn universe::RefVisitor) {
+        let Connect { faces: _, variants } = self;
+        for variant in variants.iter() {
+            variant.visit_refs(visitor);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::block::AIR;
+    use crate::content::make_some_blocks;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn evaluation_is_unchanged() {
+        let [base, variant] = make_some_blocks();
+        let connected = base
+            .clone()
+            .with_modifier(Connect::new(FaceMap::repeat(true), vec![variant; 64]));
+        assert_eq!(
+            connected.evaluate().unwrap().color,
+            base.evaluate().unwrap().color
+        );
+    }
+
+    #[test]
+    fn with_connections_applies_following_modifiers() {
+        let [base, v0, v1] = make_some_blocks();
+        let rotate = Modifier::Rotate(crate::math::GridRotation::RXZY);
+        let connected = base
+            .with_modifier(Connect::new(
+                FaceMap::repeat(false).with(Face6::PX, true),
+                vec![v0.clone(), v1.clone()],
+            ))
+            .with_modifier(rotate.clone());
+
+        assert_eq!(
+            connected.with_connections(FaceMap::repeat(false)),
+            Some(v0.with_modifier(rotate.clone()))
+        );
+        assert_eq!(
+            connected.with_connections(FaceMap::repeat(true)),
+            Some(v1.with_modifier(rotate))
+        );
+    }
+
+    #[test]
+    fn with_connections_missing_variant() {
+        let [base] = make_some_blocks();
+        let connected = base.with_modifier(Connect::new(FaceMap::repeat(true), vec![AIR]));
+        assert_eq!(connected.with_connections(FaceMap::repeat(true)), None);
+        assert_eq!(AIR.with_connections(FaceMap::repeat(true)), None);
+    }
+}
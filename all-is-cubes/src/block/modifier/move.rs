@@ -5,7 +5,8 @@ use crate::block::{
     Resolution::R16, AIR,
 };
 use crate::drawing::VoxelBrush;
-use crate::math::{Face6, GridAab, GridArray, GridCoordinate};
+use crate::math::{Face6, GridAab, GridArray, GridCoordinate, GridPoint};
+use crate::space::SpaceTransaction;
 use crate::universe;
 
 /// Data for [`Modifier::Move`]; displaces the block out of the grid, cropping it.
@@ -64,6 +65,42 @@ impl Move {
         ]
     }
 
+    /// Returns a [`SpaceTransaction`] which starts `block`, currently in `cube`, moving
+    /// into the adjacent cube in `direction`, travelling `velocity`/256 of a cube per
+    /// tick until it has arrived.
+    ///
+    /// The transaction will fail if `cube` does not contain `block` or the destination
+    /// does not contain [`AIR`]. While the move is in progress, both cubes contain
+    /// displaced parts of `block`, which collide exactly where the block is drawn, and
+    /// [`Body`](crate::physics::Body)s caught in the way are pushed out as they would be
+    /// for any other change to the space.
+    ///
+    /// This is the intended way for behaviors and tools to move blocks, such as for
+    /// pistons or elevators.
+    ///
+    /// Panics if `velocity` is not positive.
+    pub fn start_transaction(
+        block: &Block,
+        cube: GridPoint,
+        direction: Face6,
+        velocity: i16,
+    ) -> SpaceTransaction {
+        assert!(velocity > 0, "velocity must be positive");
+        let [move_out, move_in] = Self::paired_move(direction, 0, velocity);
+        let mut txn = SpaceTransaction::set_cube(
+            cube,
+            Some(block.clone()),
+            Some(block.clone().with_modifier(move_out)),
+        );
+        txn.set(
+            cube + direction.normal_vector(),
+            Some(AIR),
+            Some(block.clone().with_modifier(move_in)),
+        )
+        .unwrap(/* cannot conflict since the cubes differ */);
+        txn
+    }
+
     /// Note that `Modifier::Move` does some preprocessing to keep this simpler.
     pub(super) fn evaluate(
         &self,
@@ -92,7 +129,7 @@ impl Move {
         let (original_bounds, effective_resolution) = match input.voxels {
             Evoxels::Many(resolution, ref array) => (array.bounds(), resolution),
             // Treat color blocks as having a resolution of 16. TODO: Improve on this hardcoded constant
-            Evoxels::One(_) => (GridAab::for_block(R16), R16),
+            Evoxels::One(_) | Evoxels::Faces(..) => (GridAab::for_block(R16), R16),
         };
 
         // For now, our strategy is to work in units of the block's resolution.
@@ -145,16 +182,20 @@ impl Move {
             // values of the individual voxels to preserve expected behavior.
             collision: match input.attributes.collision {
                 BlockCollision::None => BlockCollision::None,
-                BlockCollision::Hard | BlockCollision::Recur => {
-                    if displaced_bounds.is_some() {
-                        BlockCollision::Recur
-                    } else {
+                BlockCollision::Hard | BlockCollision::Recur | BlockCollision::Boxes => {
+                    if displaced_bounds.is_none() {
                         // Recur treats no-voxels as Hard, which is not what we want
                         BlockCollision::None
+                    } else if input.attributes.collision == BlockCollision::Boxes {
+                        // Boxes are recomputed from the displaced voxels.
+                        BlockCollision::Boxes
+                    } else {
+                        BlockCollision::Recur
                     }
                 }
             },
             tick_action: animation_action,
+            tick_period: BlockAttributes::TICK_PERIOD_ONE,
             ..input.attributes
         };
 
@@ -167,13 +208,20 @@ impl Move {
                             voxels[cube - translation_in_res]
                         }),
                     ),
-                    &Evoxels::One(voxel) => {
-                        // Input block is a solid color; synthesize voxels.
+                    voxels @ (Evoxels::One(_) | Evoxels::Faces(..)) => {
+                        // Input block is a solid color or has face colors; synthesize voxels.
                         // TODO: Also synthesize if the resolution is merely low
                         // compared to the velocity.
                         Evoxels::Many(
                             effective_resolution,
-                            GridArray::from_fn(displaced_bounds, |_| voxel),
+                            GridArray::from_fn(displaced_bounds, |cube| {
+                                voxels
+                                    .synthesize_voxel(
+                                        effective_resolution,
+                                        cube - translation_in_res,
+                                    )
+                                    .unwrap_or(Evoxel::AIR)
+                            }),
                         )
                     }
                 };
@@ -215,10 +263,31 @@ mod tests {
     use crate::math::{FaceMap, GridPoint, OpacityCategory, Rgba};
     use crate::space::Space;
     use crate::time::{practically_infinite_deadline, Tick};
+    use crate::transaction::Transaction as _;
     use crate::universe::Universe;
 
     use super::*;
 
+    #[test]
+    fn move_faces_block_evaluation() {
+        let faces = FaceMap::repeat(Rgba::WHITE).with(Face6::NY, Rgba::BLACK);
+        let moved = Block::builder()
+            .face_colors(faces)
+            .build()
+            .with_modifier(Move {
+                direction: Face6::PY,
+                distance: 128, // distance 1/2 block × scale factor of 256
+                velocity: 0,
+            });
+
+        let ev = moved.evaluate().unwrap();
+        assert_eq!(ev.resolution(), R16);
+        // Bottom face, moved up to the middle of the block
+        assert_eq!(ev.voxels[GridPoint::new(8, 8, 8)].color, Rgba::BLACK);
+        // Side face
+        assert_eq!(ev.voxels[GridPoint::new(0, 12, 8)].color, Rgba::WHITE);
+    }
+
     #[test]
     fn move_atom_block_evaluation() {
         let color = rgba_const!(1.0, 0.0, 0.0, 1.0);
@@ -250,6 +319,7 @@ mod tests {
                     expected_bounds,
                     OpacityCategory::Opaque
                 )),
+                collision_boxes: Vec::new(),
             }
         );
     }
@@ -291,6 +361,7 @@ mod tests {
                     expected_bounds,
                     OpacityCategory::Opaque
                 )),
+                collision_boxes: Vec::new(),
             }
         );
     }
@@ -336,6 +407,47 @@ mod tests {
         checker(&space.read().unwrap(), &block);
     }
 
+    #[test]
+    fn start_transaction_moves_block() {
+        let [block] = make_some_blocks();
+        let mut space = Space::empty_positive(2, 1, 1);
+        space.set([0, 0, 0], &block).unwrap();
+
+        Move::start_transaction(&block, GridPoint::new(0, 0, 0), Face6::PX, 64)
+            .execute(&mut space, &mut drop)
+            .unwrap();
+        for _ in 0..2 {
+            let _ = space.step(None, Tick::arbitrary(), practically_infinite_deadline());
+        }
+
+        // Halfway through, both cubes contain half of the block, and both collide.
+        for cube in [[0, 0, 0], [1, 0, 0]] {
+            let ev = space.get_evaluated(cube);
+            assert!(ev.visible);
+            assert_eq!(ev.attributes.collision, BlockCollision::Recur);
+        }
+
+        for _ in 0..4 {
+            let _ = space.step(None, Tick::arbitrary(), practically_infinite_deadline());
+        }
+        assert_eq!(&space[[0, 0, 0]], &AIR);
+        assert_eq!(&space[[1, 0, 0]], &block);
+    }
+
+    #[test]
+    fn start_transaction_requires_empty_destination() {
+        let [block, obstacle] = make_some_blocks();
+        let mut space = Space::empty_positive(2, 1, 1);
+        space.set([0, 0, 0], &block).unwrap();
+        space.set([1, 0, 0], &obstacle).unwrap();
+
+        assert!(
+            Move::start_transaction(&block, GridPoint::new(0, 0, 0), Face6::PX, 64)
+                .check(&space)
+                .is_err()
+        );
+    }
+
     #[test]
     fn velocity_zero() {
         move_block_test(Face6::PX, 0, |space, block| {
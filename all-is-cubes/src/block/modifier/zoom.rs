@@ -4,7 +4,7 @@ use crate::block::{
     self, Evoxel, Evoxels, MinEval, Modifier,
     Resolution::{self, R1},
 };
-use crate::math::{GridAab, GridArray, GridCoordinate, GridPoint};
+use crate::math::{FaceMap, GridAab, GridArray, GridCoordinate, GridPoint};
 use crate::universe;
 
 /// Data for [`Modifier::Zoom`], describing a portion of the original block that is scaled
@@ -79,6 +79,26 @@ impl Zoom {
                 // so we can just unconditionally return the original color.
                 MinEval { attributes, voxels }
             }
+            Evoxels::Faces(voxel, faces) => {
+                // Block has resolution 1, but the zoomed part shows only the faces it
+                // touches; its other faces are cut through the interior.
+                let max = GridCoordinate::from(scale) - 1;
+                let offset = offset_in_zoomed_blocks.map(GridCoordinate::from);
+                MinEval {
+                    attributes,
+                    voxels: Evoxels::Faces(
+                        voxel,
+                        FaceMap::from_fn(|face| {
+                            let edge = if face.is_positive() { max } else { 0 };
+                            if offset[face.axis_number()] == edge {
+                                faces[face]
+                            } else {
+                                voxel.color
+                            }
+                        }),
+                    ),
+                }
+            }
             Evoxels::Many(_, voxels) => {
                 let voxel_offset = offset_in_zoomed_blocks.map(GridCoordinate::from).to_vec()
                     * GridCoordinate::from(zoom_resolution);
@@ -148,7 +168,7 @@ mod tests {
     use super::*;
     use crate::block::{EvaluatedBlock, Resolution::R2};
     use crate::content::{make_some_blocks, make_some_voxel_blocks};
-    use crate::math::{GridAab, GridVector, Rgba};
+    use crate::math::{Face6, GridAab, GridVector, Rgba};
     use crate::universe::Universe;
     use pretty_assertions::assert_eq;
 
@@ -218,4 +238,23 @@ mod tests {
         }));
         assert_eq!(zoomed.evaluate().unwrap().color, original.color());
     }
+
+    #[test]
+    fn faces_only_where_touched() {
+        let faces = FaceMap::repeat(Rgba::WHITE).with(Face6::PY, Rgba::BLACK);
+        let original = Block::builder().face_colors(faces).build();
+        let interior = original.evaluate().unwrap().color;
+        let zoomed = original.with_modifier(Zoom::new(R2, Point3::new(0, 1, 0)));
+        assert_eq!(
+            zoomed.evaluate().unwrap().voxels.single_voxel_face_colors(),
+            Some(FaceMap {
+                nx: Rgba::WHITE,
+                ny: interior,
+                nz: Rgba::WHITE,
+                px: interior,
+                py: Rgba::BLACK,
+                pz: interior,
+            })
+        );
+    }
 }
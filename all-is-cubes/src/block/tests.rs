@@ -13,8 +13,9 @@ use cgmath::EuclideanSpace as _;
 use pretty_assertions::assert_eq;
 
 use crate::block::{
-    Block, BlockAttributes, BlockCollision, BlockDef, BlockDefTransaction, EvalBlockError, Evoxel,
-    Evoxels, Modifier, Primitive, Resolution, Resolution::*, AIR, AIR_EVALUATED,
+    Block, BlockAttributes, BlockCollision, BlockDef, BlockDefTransaction, EvalBlockError,
+    EvalCacheStats, Evoxel, Evoxels, Modifier, Primitive, Resolution, Resolution::*, AIR,
+    AIR_EVALUATED,
 };
 use crate::content::make_some_blocks;
 use crate::listen::{NullListener, Sink};
@@ -149,6 +150,61 @@ fn evaluate_invisible_atom() {
     assert_eq!(e.voxel_opacity_mask, None)
 }
 
+#[test]
+fn evaluate_faces() {
+    let side = Rgba::new(1.0, 0.0, 0.0, 1.0);
+    let top = Rgba::new(0.0, 1.0, 0.0, 0.5);
+    let faces = FaceMap::repeat(side).with(Face6::PY, top);
+    let block = Block::builder().face_colors(faces).build();
+    let e = block.evaluate().unwrap();
+    assert_eq!(e.color, Rgba::new(5. / 6., 1. / 6., 0., 5.5 / 6.));
+    assert_eq!(e.voxels.single_voxel_face_colors(), Some(faces));
+    assert_eq!(e.resolution(), R1);
+    assert_eq!(e.opaque, FaceMap::repeat(true).with(Face6::PY, false));
+    assert_eq!(e.visible, true);
+    assert_eq!(
+        e.voxel_opacity_mask,
+        Some(GridArray::from_element(OpacityCategory::Partial))
+    );
+    e.consistency_check();
+}
+
+#[test]
+fn evaluate_voxels_emission() {
+    let mut universe = Universe::new();
+    let emission = Rgb::new(1.0, 0.5, 0.0);
+    let glowing = Block::builder()
+        .color(Rgba::WHITE)
+        .light_emission(emission)
+        .build();
+    let block = Block::builder()
+        .voxels_fn(
+            &mut universe,
+            R2,
+            |cube| {
+                if cube.x == 0 {
+                    &glowing
+                } else {
+                    &AIR
+                }
+            },
+        )
+        .unwrap()
+        .build();
+
+    let e = block.evaluate().unwrap();
+    assert_eq!(
+        e.voxels.get(GridPoint::new(0, 1, 0)).unwrap().emission,
+        emission
+    );
+    assert_eq!(
+        e.voxels.get(GridPoint::new(1, 1, 0)).unwrap().emission,
+        Rgb::ZERO
+    );
+    // The voxels' emission does not become the block's light emission.
+    assert_eq!(e.attributes.light_emission, Rgb::ZERO);
+}
+
 #[test]
 fn evaluate_voxels_checked_individually() {
     let resolution = R2;
@@ -177,6 +233,7 @@ fn evaluate_voxels_checked_individually() {
                 let point = point.cast::<f32>().unwrap();
                 Evoxel {
                     color: Rgba::new(point.x, point.y, point.z, 1.0),
+                    emission: Rgb::ZERO,
                     selectable: true,
                     collision: BlockCollision::Hard,
                 }
@@ -318,6 +375,7 @@ fn recur_with_offset() {
                 let point = (point + offset).cast::<f32>().unwrap();
                 Evoxel {
                     color: Rgba::new(point.x, point.y, point.z, 1.0),
+                    emission: Rgb::ZERO,
                     selectable: true,
                     collision: BlockCollision::Hard,
                 }
@@ -369,6 +427,66 @@ fn indirect_equivalence() {
     assert_eq!(eval_bare, eval_def);
 }
 
+/// Evaluating an indirect block uses the [`BlockDef`]'s cache, which is invalidated when
+/// either the block definition or something it depends on changes.
+#[test]
+fn indirect_evaluation_cached() {
+    let mut universe = Universe::new();
+    let [block_0, block_1] = make_some_blocks();
+    let space_ref = universe.insert_anonymous(Space::empty_positive(1, 1, 1));
+    let block_def_ref = universe.insert_anonymous(BlockDef::new(
+        Block::builder().voxels_ref(R1, space_ref.clone()).build(),
+    ));
+    let indirect = Block::from_primitive(Primitive::Indirect(block_def_ref.clone()));
+    let stats = || block_def_ref.read().unwrap().evaluation_cache_stats();
+
+    let e1 = indirect.evaluate().unwrap();
+    let e2 = indirect.evaluate().unwrap();
+    assert_eq!(e1, e2);
+    assert_eq!(stats(), EvalCacheStats { hits: 1, misses: 1 });
+
+    // Changing the space the definition refers to invalidates the cache.
+    space_ref
+        .execute(
+            &SpaceTransaction::set_cube([0, 0, 0], None, Some(block_0.clone())),
+            &mut transaction::no_outputs,
+        )
+        .unwrap();
+    assert_eq!(
+        indirect.evaluate().unwrap().color,
+        block_0.evaluate().unwrap().color
+    );
+    assert_eq!(stats(), EvalCacheStats { hits: 1, misses: 2 });
+
+    // Replacing the definition invalidates the cache.
+    block_def_ref
+        .execute(
+            &BlockDefTransaction::overwrite(block_1.clone()),
+            &mut transaction::no_outputs,
+        )
+        .unwrap();
+    assert_eq!(indirect.evaluate().unwrap(), block_1.evaluate().unwrap());
+    assert_eq!(indirect.evaluate().unwrap(), block_1.evaluate().unwrap());
+    assert_eq!(stats(), EvalCacheStats { hits: 2, misses: 3 });
+}
+
+/// A cached evaluation of a [`BlockDef`] is not reused when the block is reached at a
+/// greater recursion depth, where evaluation should fail.
+#[test]
+fn indirect_evaluation_cache_respects_depth() {
+    let mut universe = Universe::new();
+    let [base] = make_some_blocks();
+    let mut chain = vec![base];
+    for _ in 0..40 {
+        let def_ref = universe.insert_anonymous(BlockDef::new(chain.last().unwrap().clone()));
+        chain.push(Block::from_primitive(Primitive::Indirect(def_ref)));
+    }
+
+    // Evaluating the short chain fills the caches while near the top of the stack.
+    chain[20].evaluate().unwrap();
+    assert_eq!(chain[40].evaluate(), Err(EvalBlockError::StackOverflow));
+}
+
 #[test]
 fn listen_atom() {
     let block = Block::from(Rgba::WHITE);
@@ -12,6 +12,9 @@ use crate::chunking::OctantMask;
 use crate::math::{Aab, FreeCoordinate, GridAab, Rgba};
 use crate::raycast::Ray;
 
+mod color_pipeline;
+pub use color_pipeline::*;
+
 mod flaws;
 pub use flaws::*;
 
@@ -149,6 +152,15 @@ impl Camera {
         self.options.view_distance.into_inner()
     }
 
+    /// Returns the distance within which chunks should be prepared for drawing;
+    /// see [`GraphicsOptions::chunk_loading_distance`].
+    pub fn chunk_loading_distance(&self) -> FreeCoordinate {
+        self.options
+            .chunk_loading_distance
+            .unwrap_or(self.options.view_distance)
+            .into_inner()
+    }
+
     /// Sets the view transform.
     ///
     /// Besides controlling rendering, this is used to determine world coordinates for purposes
@@ -307,8 +319,17 @@ impl Camera {
     ///
     /// 1. Multiply the input by this camera's exposure value.
     /// 2. Apply the tone mapping operator specified in [`Camera::options()`].
+    /// 3. Apply the gamma adjustment specified in [`Camera::options()`].
+    ///
+    /// This is equivalent to `self.color_pipeline().apply(color)`.
     pub fn post_process_color(&self, color: Rgba) -> Rgba {
-        color.map_rgb(|rgb| self.options.tone_mapping.apply(rgb * self.exposure()))
+        self.color_pipeline().apply(color)
+    }
+
+    /// Returns the [`ColorPipeline`] which renderers should use to convert scene colors
+    /// to image colors, given this camera's options and exposure.
+    pub fn color_pipeline(&self) -> ColorPipeline {
+        ColorPipeline::new(&self.options, self.exposure())
     }
 
     /// Returns the current exposure value for scaling luminance.
@@ -0,0 +1,116 @@
+use ordered_float::NotNan;
+
+use crate::camera::{GraphicsOptions, ToneMappingOperator};
+use crate::math::{Rgb, Rgba};
+
+#[cfg(doc)]
+use crate::camera::Camera;
+
+/// The steps by which a renderer converts the colors of a scene, which are linear and
+/// have unlimited dynamic range, into the colors of an image to display.
+///
+/// Every renderer applies these steps, in this order:
+///
+/// 1. Multiply the scene color by the [exposure](Self::exposure).
+/// 2. Apply the [tone mapping operator](Self::tone_mapping), producing colors in the
+///    range 0 to 1.
+/// 3. Apply the [gamma adjustment](Self::gamma).
+/// 4. Encode the result for the output, which is usually sRGB.
+///
+/// [`ColorPipeline::apply()`] performs steps 1 through 3 and
+/// [`ColorPipeline::to_srgb8()`] performs all of them; renderers which do the work
+/// elsewhere, such as on a GPU, should use the accessors to obtain the parameters.
+///
+/// Obtain one from [`Camera::color_pipeline()`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct ColorPipeline {
+    exposure: NotNan<f32>,
+    tone_mapping: ToneMappingOperator,
+    gamma: NotNan<f32>,
+}
+
+impl ColorPipeline {
+    /// Constructs the pipeline specified by `options`, with the given exposure value
+    /// (which may be determined automatically, rather than specified by the options).
+    pub fn new(options: &GraphicsOptions, exposure: NotNan<f32>) -> Self {
+        Self {
+            exposure,
+            tone_mapping: options.tone_mapping.clone(),
+            gamma: options.gamma,
+        }
+    }
+
+    /// Returns the factor by which scene colors are multiplied before tone mapping.
+    pub fn exposure(&self) -> NotNan<f32> {
+        self.exposure
+    }
+
+    /// Returns the tone mapping operator to apply after exposure.
+    pub fn tone_mapping(&self) -> &ToneMappingOperator {
+        &self.tone_mapping
+    }
+
+    /// Returns the gamma adjustment: tone mapped colors are raised to the power of
+    /// the reciprocal of this value, so values greater than 1 brighten midtones.
+    pub fn gamma(&self) -> NotNan<f32> {
+        self.gamma
+    }
+
+    /// Converts a scene color to a linear image color, by applying exposure, tone
+    /// mapping, and gamma adjustment. Alpha is unchanged.
+    pub fn apply(&self, color: Rgba) -> Rgba {
+        color.map_rgb(|rgb| {
+            let rgb = self.tone_mapping.apply(rgb * self.exposure);
+            if self.gamma.into_inner() == 1.0 {
+                rgb
+            } else {
+                let exponent = self.gamma.into_inner().recip();
+                let adjust = |c: NotNan<f32>| c.into_inner().max(0.0).powf(exponent);
+                Rgb::new(adjust(rgb.red()), adjust(rgb.green()), adjust(rgb.blue()))
+            }
+        })
+    }
+
+    /// Converts a scene color to an 8-bit sRGB image color, as [`Self::apply()`] does
+    /// followed by sRGB encoding.
+    pub fn to_srgb8(&self, color: Rgba) -> [u8; 4] {
+        self.apply(color).to_srgb8()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{notnan, rgba_const};
+
+    #[test]
+    fn default_is_identity_for_ldr_colors() {
+        let pipeline = ColorPipeline::new(&GraphicsOptions::default(), notnan!(1.0));
+        let color = rgba_const!(0.1, 0.2, 0.3, 0.4);
+        assert_eq!(pipeline.apply(color), color);
+    }
+
+    #[test]
+    fn gamma() {
+        let mut options = GraphicsOptions::default();
+        options.gamma = notnan!(2.0);
+        let pipeline = ColorPipeline::new(&options, notnan!(1.0));
+        assert_eq!(
+            pipeline.apply(rgba_const!(0.25, 0.0, 1.0, 0.5)),
+            rgba_const!(0.5, 0.0, 1.0, 0.5)
+        );
+    }
+
+    #[test]
+    fn exposure_before_tone_mapping_and_gamma() {
+        let mut options = GraphicsOptions::default();
+        options.gamma = notnan!(2.0);
+        let pipeline = ColorPipeline::new(&options, notnan!(0.5));
+        // Exposure halves 0.5 to 0.25 and 4 to 2, which is clamped to 1, and then gamma
+        // takes the square root.
+        assert_eq!(
+            pipeline.apply(rgba_const!(0.5, 4.0, 0.0, 1.0)),
+            rgba_const!(0.5, 1.0, 0.0, 1.0)
+        );
+    }
+}
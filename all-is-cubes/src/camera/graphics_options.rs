@@ -28,6 +28,15 @@ pub struct GraphicsOptions {
     /// [`tone_mapping`](ToneMappingOperator).
     pub exposure: ExposureOption,
 
+    /// Gamma adjustment applied after tone mapping: image colors, in the range 0 to 1,
+    /// are raised to the power of the reciprocal of this value before being encoded for
+    /// display. Values greater than 1 brighten dark and middle tones, and values less than
+    /// 1 darken them; 1 leaves colors unchanged.
+    ///
+    /// See [`ColorPipeline`](crate::camera::ColorPipeline) for how this fits with the
+    /// other color options.
+    pub gamma: NotNan<f32>,
+
     /// Proportion of bloom (blurred image) to mix into the original image.
     /// 0.0 is no bloom and 1.0 is no original image.
     pub bloom_intensity: NotNan<f32>,
@@ -37,6 +46,18 @@ pub struct GraphicsOptions {
     /// TODO: Implement view distance limit (and fog) in raytracer.
     pub view_distance: NotNan<FreeCoordinate>,
 
+    /// Distance, in unit cubes, from the camera within which renderers should prepare
+    /// chunks of the world for drawing. If [`None`], it is equal to
+    /// [`view_distance`](Self::view_distance).
+    ///
+    /// A larger value prepares chunks before they come into view, reducing the chance of
+    /// seeing chunks which are not ready while moving. A smaller value reduces the
+    /// effective view distance.
+    ///
+    /// While chunks are not yet ready, renderers which implement fog draw it closer, so
+    /// that missing chunks are hidden instead of suddenly appearing.
+    pub chunk_loading_distance: Option<NotNan<FreeCoordinate>>,
+
     /// Style in which to draw the lighting of [`Space`](crate::space::Space)s.
     /// This does not affect the *computation* of lighting.
     pub lighting_display: LightingOption,
@@ -57,9 +78,31 @@ pub struct GraphicsOptions {
     /// [renderer]: crate::camera::HeadlessRenderer
     pub show_ui: bool,
 
+    /// Size of the HUD, menus, and info text, as a multiple of their default size.
+    ///
+    /// Values greater than 1 are limited by the need to fit the HUD within the
+    /// viewport, so the UI may be drawn smaller than requested; the info text is
+    /// enlarged only by whole multiples of its font's pixels.
+    pub ui_scale: NotNan<f32>,
+
+    /// Colors to use for the HUD, menus, and info text.
+    pub ui_palette: UiPaletteOption,
+
     /// Whether to apply antialiasing techniques.
     pub antialiasing: AntialiasingOption,
 
+    /// How to sample block textures that are smaller or larger on screen than their
+    /// voxels.
+    pub texture_filtering: TextureFilteringOption,
+
+    /// Whether to automatically reduce other options, such as
+    /// [`view_distance`](Self::view_distance), when rendering is too slow to maintain
+    /// the target frame rate, and restore them when it is fast enough.
+    ///
+    /// This is performed by the application (such as `all_is_cubes_ui::apps::Session`),
+    /// not by renderers; the values of the other options are the upper limits of quality.
+    pub auto_quality: bool,
+
     /// Whether to use frustum culling for drawing only in-view chunks and objects.
     ///
     /// This option is for debugging and performance testing and should not have any
@@ -96,6 +139,7 @@ impl GraphicsOptions {
     /// * [`Self::bloom_intensity`] = `0`
     /// * [`Self::fog`] = [`FogOption::None`]
     /// * [`Self::lighting_display`] = [`LightingOption::None`]
+    /// * [`Self::texture_filtering`] = [`TextureFilteringOption::Nearest`]
     /// * [`Self::tone_mapping`] = [`ToneMappingOperator::Clamp`]
     ///
     /// Future versions may set other options as necessary to maintain the intended
@@ -106,12 +150,18 @@ impl GraphicsOptions {
         // TODO: Change tone mapping default once we have a good implementation.
         tone_mapping: ToneMappingOperator::Clamp,
         exposure: ExposureOption::Fixed(notnan!(1.)),
+        gamma: notnan!(1.),
         bloom_intensity: notnan!(0.),
         view_distance: notnan!(200.),
+        chunk_loading_distance: None,
         lighting_display: LightingOption::None,
         transparency: TransparencyOption::Volumetric,
         show_ui: true,
+        ui_scale: notnan!(1.),
+        ui_palette: UiPaletteOption::Standard,
         antialiasing: AntialiasingOption::None,
+        texture_filtering: TextureFilteringOption::Nearest,
+        auto_quality: false,
         use_frustum_culling: true,
         debug_info_text: true,
         debug_behaviors: false,
@@ -124,10 +174,15 @@ impl GraphicsOptions {
     #[must_use]
     pub fn repair(mut self) -> Self {
         self.fov_y = self.fov_y.clamp(NotNan::from(1), NotNan::from(189));
+        self.gamma = self.gamma.clamp(notnan!(0.25), notnan!(4.0));
         self.bloom_intensity = self.bloom_intensity.clamp(notnan!(0.0), notnan!(1.0));
+        self.ui_scale = self.ui_scale.clamp(notnan!(0.5), notnan!(4.0));
         self.view_distance = self
             .view_distance
             .clamp(NotNan::from(1), NotNan::from(10000));
+        self.chunk_loading_distance = self
+            .chunk_loading_distance
+            .map(|d| d.clamp(NotNan::from(1), NotNan::from(10000)));
         self
     }
 }
@@ -144,12 +199,18 @@ impl Default for GraphicsOptions {
             // TODO: Change tone mapping default once we have a good implementation.
             tone_mapping: ToneMappingOperator::Clamp,
             exposure: ExposureOption::default(),
+            gamma: notnan!(1.),
             bloom_intensity: notnan!(0.125),
             view_distance: NotNan::from(200),
+            chunk_loading_distance: None,
             lighting_display: LightingOption::Smooth,
             transparency: TransparencyOption::Volumetric,
             show_ui: true,
+            ui_scale: notnan!(1.),
+            ui_palette: UiPaletteOption::default(),
             antialiasing: AntialiasingOption::default(),
+            texture_filtering: TextureFilteringOption::default(),
+            auto_quality: false,
             use_frustum_culling: true,
             debug_info_text: true,
             debug_behaviors: false,
@@ -267,6 +328,14 @@ pub enum TransparencyOption {
     /// Accounts for the thickness of material passed through; colors' alpha values are
     /// interpreted as the opacity of a unit thickness of the material.
     Volumetric,
+    /// Like [`Volumetric`](Self::Volumetric), and additionally, light passing through a
+    /// material is tinted by the material's color, as in colored glass.
+    ///
+    /// Each unit thickness of a material with color (_r_, _g_, _b_, _α_) transmits
+    /// the fraction _r_<sup>_α_</sup> of the red light passing through it, and so on
+    /// (the Beer–Lambert law). Renderers which cannot trace the path of light
+    /// approximate this as [`Volumetric`](Self::Volumetric).
+    Tinted,
     /// Alpha above or below the given threshold value will be rounded to fully opaque
     /// or fully transparent, respectively.
     Threshold(NotNan<f32>),
@@ -325,6 +394,40 @@ impl AntialiasingOption {
     }
 }
 
+/// Choices for [`GraphicsOptions::ui_palette`].
+#[derive(Clone, Debug, Default, Eq, PartialEq, serde::Deserialize, serde::Serialize)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[non_exhaustive]
+pub enum UiPaletteOption {
+    /// The usual colors of the user interface.
+    #[default]
+    Standard,
+    /// Colors chosen for maximum contrast between text and its background, and
+    /// between the states of buttons, which are distinguished by brightness rather
+    /// than hue so that they can be told apart with any kind of color vision.
+    /// Info text is drawn on a solid outline instead of a soft shadow.
+    HighContrast,
+}
+
+/// Choices for [`GraphicsOptions::texture_filtering`].
+#[derive(Clone, Debug, Default, Eq, PartialEq, serde::Deserialize, serde::Serialize)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[non_exhaustive]
+pub enum TextureFilteringOption {
+    /// Every pixel shows the color of exactly one voxel. Distant textured surfaces
+    /// may shimmer as the view moves.
+    Nearest,
+    /// Distant textured surfaces are drawn using reduced-resolution copies of their
+    /// textures (mipmaps), which averages the colors of voxels too small to see
+    /// individually. Nearby voxels are still drawn with sharp edges.
+    #[default]
+    Mipmapped,
+    /// Like [`Mipmapped`](Self::Mipmapped), and additionally, surfaces seen at
+    /// glancing angles are filtered anisotropically so they stay sharper.
+    /// This requires blending adjacent voxels, so nearby voxel edges are blurred.
+    Anisotropic,
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -359,6 +462,7 @@ mod tests {
                 bloom_intensity: NotNan::from(0u8),
                 lighting_display: LightingOption::None,
                 antialiasing: AntialiasingOption::None,
+                texture_filtering: TextureFilteringOption::Nearest,
                 ..GraphicsOptions::default()
             }
         )
@@ -1,12 +1,14 @@
+use embedded_graphics::draw_target::DrawTarget;
 use embedded_graphics::mono_font::iso_8859_1::FONT_7X13_BOLD;
 use embedded_graphics::mono_font::MonoTextStyle;
-use embedded_graphics::prelude::{PixelColor, Point};
+use embedded_graphics::prelude::{Dimensions, PixelColor, Point, Size};
+use embedded_graphics::primitives::Rectangle;
 use embedded_graphics::text::{Baseline, Text};
-use embedded_graphics::Drawable;
+use embedded_graphics::{Drawable, Pixel};
 use futures_core::future::BoxFuture;
 use image::RgbaImage;
 
-use crate::camera::Flaws;
+use crate::camera::{Flaws, GraphicsOptions, Viewport};
 use crate::character::Cursor;
 use crate::universe::RefError;
 
@@ -79,3 +81,70 @@ pub fn info_text_drawable<C: PixelColor + 'static>(
         Baseline::Top,
     )
 }
+
+/// Returns the factor by which the [`info_text_drawable()`] should be enlarged, using
+/// [`InfoTextScale`], when it is drawn into an image of `image_size` pixels covering
+/// the given viewport.
+///
+/// This accounts for [`GraphicsOptions::ui_scale`] and for high-DPI displays, whose
+/// framebuffers have more pixels than their nominal size.
+#[doc(hidden)] // TODO: decide whether to make public
+pub fn info_text_scale(options: &GraphicsOptions, viewport: Viewport, image_width: u32) -> u32 {
+    let pixels_per_nominal = f64::from(image_width) / viewport.nominal_size.x;
+    let scale = f64::from(options.ui_scale.into_inner()) * pixels_per_nominal;
+    if scale.is_finite() {
+        // Text can only be enlarged by whole pixels, so round to the nearest.
+        scale.round().max(1.0) as u32
+    } else {
+        1
+    }
+}
+
+/// [`DrawTarget`] adapter which draws each pixel as a square of `scale` × `scale` pixels
+/// of the underlying target, for enlarging the [`info_text_drawable()`].
+#[doc(hidden)] // TODO: decide whether to make public
+#[derive(Debug)]
+pub struct InfoTextScale<'a, D> {
+    target: &'a mut D,
+    scale: u32,
+}
+
+impl<'a, D: DrawTarget> InfoTextScale<'a, D> {
+    /// Wraps `target`; a `scale` of zero is treated as 1.
+    pub fn new(target: &'a mut D, scale: u32) -> Self {
+        Self {
+            target,
+            scale: scale.max(1),
+        }
+    }
+}
+
+impl<D: DrawTarget> Dimensions for InfoTextScale<'_, D> {
+    fn bounding_box(&self) -> Rectangle {
+        let bounds = self.target.bounding_box();
+        Rectangle::new(
+            bounds.top_left / self.scale as i32,
+            bounds.size / self.scale,
+        )
+    }
+}
+
+impl<D: DrawTarget> DrawTarget for InfoTextScale<'_, D> {
+    type Color = D::Color;
+    type Error = D::Error;
+
+    fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Pixel<Self::Color>>,
+    {
+        if self.scale == 1 {
+            return self.target.draw_iter(pixels);
+        }
+        let size = Size::new(self.scale, self.scale);
+        for Pixel(point, color) in pixels {
+            self.target
+                .fill_solid(&Rectangle::new(point * self.scale as i32, size), color)?;
+        }
+        Ok(())
+    }
+}
@@ -1,10 +1,13 @@
-use cgmath::{One, Point2};
+use std::sync::Arc;
+
+use cgmath::{EuclideanSpace as _, One, Point2, Point3, Rotation as _, Vector3};
 
 use crate::camera::{Camera, GraphicsOptions, ViewTransform, Viewport};
-use crate::character::{cursor_raycast, Character, Cursor};
+use crate::character::{cursor_raycast, Character, Cursor, CursorPolicy, PosedPart};
 use crate::listen::{DirtyFlag, ListenableCell, ListenableSource};
 use crate::math::FreeCoordinate;
-use crate::space::Space;
+use crate::raycast::Ray;
+use crate::space::{Selection, Space};
 use crate::universe::{URef, Universe};
 
 /// A collection of values associated with each of the layers of graphics that
@@ -19,6 +22,9 @@ use crate::universe::{URef, Universe};
 pub struct Layers<T> {
     /// The game world.
     pub world: T,
+    /// The first-person view of the character's selected tool, drawn in front of the
+    /// world and behind the UI.
+    pub hand: T,
     /// The user interface, or HUD, drawn in front of the world.
     pub ui: T,
 }
@@ -28,6 +34,7 @@ impl<T> Layers<T> {
     pub(crate) fn as_refs(&self) -> Layers<&T> {
         Layers {
             world: &self.world,
+            hand: &self.hand,
             ui: &self.ui,
         }
     }
@@ -36,6 +43,7 @@ impl<T> Layers<T> {
     pub(crate) fn map<U, F: FnMut(T) -> U>(self, mut f: F) -> Layers<U> {
         Layers {
             world: f(self.world),
+            hand: f(self.hand),
             ui: f(self.ui),
         }
     }
@@ -44,6 +52,7 @@ impl<T> Layers<T> {
     pub fn try_map_ref<U, E>(&self, mut f: impl FnMut(&T) -> Result<U, E>) -> Result<Layers<U>, E> {
         Ok(Layers {
             world: f(&self.world)?,
+            hand: f(&self.hand)?,
             ui: f(&self.ui)?,
         })
     }
@@ -58,7 +67,15 @@ impl<T> Layers<T> {
 /// * A [`Viewport`] specifying the dimensions of image to render.
 /// * A [`URef`] to the [`Character`] whose eyes we look through to render the “world”
 ///   [`Space`].
+/// * The [`CameraMode`], which determines whether we look from the character's eyes or
+///   from behind the character.
 /// * A [`URef`] to the UI/HUD [`Space`] overlaid on the world, if any.
+/// * A [`URef`] to the [`Space`] containing the character's selected tool, shown in
+///   front of the world as if held in hand, if any.
+/// * The fraction of a step which has elapsed since the last step, used to interpolate
+///   the character's movement (see [`Character::view_interpolated()`]).
+/// * Optionally, the posed avatars of other characters to draw in the world
+///   (see [`StandardCameras::with_remote_avatars()`]).
 ///
 /// When [`StandardCameras::update()`] is called, all of these data sources are read
 /// and used to update the [`Camera`] data. Those cameras, and copies of the input
@@ -85,10 +102,38 @@ pub struct StandardCameras {
     /// TODO: This should be in a Layers along with ui_state...?
     world_space: ListenableCell<Option<URef<Space>>>,
 
+    camera_mode_source: ListenableSource<CameraMode>,
+    /// Value of `camera_mode_source` as of the last [`Self::update()`].
+    camera_mode: CameraMode,
+    /// Parts of the avatars to draw in the world: the character's own, if it is
+    /// visible, followed by those from `remote_avatars_source`.
+    avatars: Vec<PosedPart>,
+    /// Distance the world camera was moved back from the character's eye.
+    pullback: FreeCoordinate,
+
     ui_source: ListenableSource<UiViewState>,
     ui_dirty: DirtyFlag,
     ui_space: Option<URef<Space>>,
 
+    hand_source: ListenableSource<UiViewState>,
+    hand_dirty: DirtyFlag,
+    hand_space: Option<URef<Space>>,
+
+    step_interpolation_source: ListenableSource<f64>,
+
+    /// Avatars of other characters to draw in the world; see
+    /// [`Self::with_remote_avatars()`].
+    remote_avatars_source: ListenableSource<Vec<PosedPart>>,
+
+    /// Which blocks [`Self::project_cursor()`] selects in the world; see
+    /// [`Self::with_cursor_policy()`].
+    cursor_policy_source: ListenableSource<CursorPolicy>,
+
+    /// See [`Self::with_selection()`].
+    selection_source: ListenableSource<Option<Selection>>,
+    /// Value of `selection_source` as of the last [`Self::update()`].
+    selection: Arc<Option<Selection>>,
+
     viewport_source: ListenableSource<Viewport>,
     viewport_dirty: DirtyFlag,
 
@@ -103,7 +148,10 @@ impl StandardCameras {
         graphics_options: ListenableSource<GraphicsOptions>,
         viewport_source: ListenableSource<Viewport>,
         character_source: ListenableSource<Option<URef<Character>>>,
+        camera_mode_source: ListenableSource<CameraMode>,
         ui_source: ListenableSource<UiViewState>,
+        hand_source: ListenableSource<UiViewState>,
+        step_interpolation_source: ListenableSource<f64>,
     ) -> Self {
         // TODO: Add a unit test that each of these listeners works as intended.
         // TODO: This is also an awful lot of repetitive code; we should design a pattern
@@ -115,10 +163,12 @@ impl StandardCameras {
         let initial_viewport: Viewport = *viewport_source.get();
 
         let ui_state = ui_source.get();
+        let hand_state = hand_source.get();
 
         let mut new_self = Self {
             cameras: Layers {
                 ui: Camera::new(ui_state.graphics_options.clone(), initial_viewport),
+                hand: Camera::new(hand_state.graphics_options.clone(), initial_viewport),
                 world: Camera::new(initial_options.clone(), initial_viewport),
             },
 
@@ -130,10 +180,25 @@ impl StandardCameras {
             character: None, // update() will fix these up
             world_space: ListenableCell::new(None),
 
+            camera_mode: *camera_mode_source.get(),
+            camera_mode_source,
+            avatars: Vec::new(),
+            pullback: 0.0,
+
             ui_space: ui_state.space.clone(),
             ui_dirty: DirtyFlag::listening(true, &ui_source),
             ui_source,
 
+            hand_space: hand_state.space.clone(),
+            hand_dirty: DirtyFlag::listening(true, &hand_source),
+            hand_source,
+
+            step_interpolation_source,
+            remote_avatars_source: ListenableSource::constant(Vec::new()),
+            cursor_policy_source: ListenableSource::constant(CursorPolicy::default()),
+            selection_source: ListenableSource::constant(None),
+            selection: Arc::new(None),
+
             viewport_dirty,
             viewport_source,
         };
@@ -152,7 +217,10 @@ impl StandardCameras {
             ListenableSource::constant(graphics_options),
             ListenableSource::constant(viewport),
             ListenableSource::constant(universe.get_default_character()),
+            ListenableSource::constant(CameraMode::default()),
             ListenableSource::constant(UiViewState::default()),
+            ListenableSource::constant(UiViewState::default()),
+            ListenableSource::constant(1.0),
         )
     }
 
@@ -184,6 +252,17 @@ impl StandardCameras {
             self.cameras.ui.set_view_transform(ui_transform);
         }
 
+        if self.hand_dirty.get_and_clear() {
+            let UiViewState {
+                space,
+                view_transform,
+                graphics_options,
+            } = self.hand_source.snapshot();
+            self.hand_space = space;
+            self.cameras.hand.set_options(graphics_options);
+            self.cameras.hand.set_view_transform(view_transform);
+        }
+
         // Update viewports.
         // Note: The UI does its own independent re-layout when the viewport aspect ratio
         // changes.
@@ -192,6 +271,7 @@ impl StandardCameras {
             let viewport: Viewport = self.viewport_source.snapshot();
             // TODO: this should be a Layers::iter_mut() or something
             self.cameras.world.set_viewport(viewport);
+            self.cameras.hand.set_viewport(viewport);
             self.cameras.ui.set_viewport(viewport);
         }
 
@@ -204,12 +284,27 @@ impl StandardCameras {
             }
         }
 
+        self.camera_mode = *self.camera_mode_source.get();
+        self.avatars.clear();
+        self.pullback = 0.0;
+
         if let Some(character_ref) = &self.character {
             match character_ref.read() {
                 Ok(character) => {
                     // TODO: Shouldn't we also grab the character's Space while we
                     // have the access? Renderers could use that.
-                    self.cameras.world.set_view_transform(character.view());
+                    let mut view =
+                        character.view_interpolated(*self.step_interpolation_source.get());
+                    if self.camera_mode == CameraMode::ThirdPerson {
+                        self.avatars = character.posed_avatar(Point3::from_vec(view.disp));
+                        // If the space is unavailable, we cannot tell how far it is safe
+                        // to pull back, so don't.
+                        if let Ok(space) = character.space.read() {
+                            self.pullback = third_person_pullback(&view, &space);
+                            view.disp += view.rot.rotate_vector(Vector3::unit_z()) * self.pullback;
+                        }
+                    }
+                    self.cameras.world.set_view_transform(view);
 
                     // TODO: ListenableCell should make this easier and cheaper
                     if Option::as_ref(&*self.world_space.get()) != Some(&character.space) {
@@ -229,6 +324,43 @@ impl StandardCameras {
                 self.world_space.set(None);
             }
         }
+
+        self.avatars
+            .extend_from_slice(&self.remote_avatars_source.get());
+
+        self.selection = self.selection_source.get();
+    }
+
+    /// Adds a source of the posed avatars of other characters to draw in the world
+    /// space, such as [`RemoteCharacter`](crate::character::RemoteCharacter)s, which
+    /// will be reported by [`Self::world_avatars()`]. By default, there are none.
+    ///
+    /// The same source may also carry other free-moving things which are drawn as
+    /// posed blocks, such as [`Entity::posed_part()`](crate::entity::Entity::posed_part).
+    #[must_use]
+    pub fn with_remote_avatars(mut self, source: ListenableSource<Vec<PosedPart>>) -> Self {
+        self.remote_avatars_source = source;
+        self.update();
+        self
+    }
+
+    /// Sets the source of the [`CursorPolicy`] with which [`Self::project_cursor()`]
+    /// selects blocks in the world space. By default, it is [`CursorPolicy::default()`].
+    ///
+    /// The UI space is always raycast with the default policy.
+    #[must_use]
+    pub fn with_cursor_policy(mut self, source: ListenableSource<CursorPolicy>) -> Self {
+        self.cursor_policy_source = source;
+        self
+    }
+
+    /// Adds a source of the [`Selection`] to be outlined in the world space, which will
+    /// be reported by [`Self::selection()`]. By default, there is none.
+    #[must_use]
+    pub fn with_selection(mut self, source: ListenableSource<Option<Selection>>) -> Self {
+        self.selection_source = source;
+        self.update();
+        self
     }
 
     /// Returns current graphics options as of the last [`update()`](Self::update).
@@ -253,6 +385,27 @@ impl StandardCameras {
         self.character.as_ref()
     }
 
+    /// Returns the [`CameraMode`] as of the last [`update()`](Self::update).
+    pub fn camera_mode(&self) -> CameraMode {
+        self.camera_mode
+    }
+
+    /// Returns the [`Selection`] to outline, from the source given to
+    /// [`Self::with_selection()`], as of the last [`update()`](Self::update).
+    ///
+    /// Renderers should draw it only if it is in the world space.
+    pub fn selection(&self) -> Option<&Selection> {
+        Option::as_ref(&self.selection)
+    }
+
+    /// Returns the parts of all [`Avatar`](crate::character::Avatar)s, posed in the
+    /// world space, which should be drawn: the character's own, if it is visible in the
+    /// current [`CameraMode`], and those from the source given to
+    /// [`Self::with_remote_avatars()`].
+    pub fn world_avatars(&self) -> &[PosedPart] {
+        &self.avatars
+    }
+
     /// Returns the space that should be drawn as the game world, using `self.cameras().world`.
     ///
     /// This is a [`ListenableSource`] to make it simple to cache the Space rendering data and
@@ -272,6 +425,17 @@ impl StandardCameras {
         self.ui_space.as_ref()
     }
 
+    /// Returns the space containing the character's selected tool, that should be drawn
+    /// on top of the world and beneath the UI using `self.cameras().hand`.
+    ///
+    /// This is always [`None`] in [`CameraMode::ThirdPerson`].
+    pub fn hand_space(&self) -> Option<&URef<Space>> {
+        match self.camera_mode {
+            CameraMode::FirstPerson => self.hand_space.as_ref(),
+            CameraMode::ThirdPerson => None,
+        }
+    }
+
     /// Returns the current viewport.
     ///
     /// This is always equal to the viewports of all managed [`Camera`]s,
@@ -292,7 +456,12 @@ impl StandardCameras {
     pub fn project_cursor(&self, ndc_pos: Point2<FreeCoordinate>) -> Option<Cursor> {
         if let Some(ui_space_ref) = self.ui_space.as_ref() {
             let ray = self.cameras.ui.project_ndc_into_world(ndc_pos);
-            if let Some(cursor) = cursor_raycast(ray, ui_space_ref, FreeCoordinate::INFINITY) {
+            if let Some(cursor) = cursor_raycast(
+                ray,
+                ui_space_ref,
+                FreeCoordinate::INFINITY,
+                CursorPolicy::default(),
+            ) {
                 return Some(cursor);
             }
         }
@@ -300,7 +469,13 @@ impl StandardCameras {
         if let Some(character_ref) = self.character.as_ref() {
             let ray = self.cameras.world.project_ndc_into_world(ndc_pos);
             // TODO: maximum distance should be determined by character/universe parameters instead of hardcoded
-            if let Some(cursor) = cursor_raycast(ray, &character_ref.read().unwrap().space, 6.0) {
+            let max_distance = 6.0 + self.pullback;
+            if let Some(cursor) = cursor_raycast(
+                ray,
+                &character_ref.read().unwrap().space,
+                max_distance,
+                *self.cursor_policy_source.get(),
+            ) {
                 return Some(cursor);
             }
         }
@@ -318,20 +493,70 @@ impl Clone for StandardCameras {
             self.graphics_options.clone(),
             self.viewport_source.clone(),
             self.character_source.clone(),
+            self.camera_mode_source.clone(),
             self.ui_source.clone(),
+            self.hand_source.clone(),
+            self.step_interpolation_source.clone(),
         )
+        .with_remote_avatars(self.remote_avatars_source.clone())
+        .with_cursor_policy(self.cursor_policy_source.clone())
+        .with_selection(self.selection_source.clone())
+    }
+}
+
+/// Where the world layer of [`StandardCameras`] is viewed from, relative to the
+/// character.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+#[non_exhaustive]
+pub enum CameraMode {
+    /// Look through the character's eyes. The character's body is not drawn.
+    #[default]
+    FirstPerson,
+    /// Look from behind the character, whose body is drawn.
+    ///
+    /// The camera is moved back from the character's eyes, but not so far as to be
+    /// within or behind the nearest visible block.
+    ThirdPerson,
+}
+
+/// Distance behind the character's eyes at which [`CameraMode::ThirdPerson`] places the
+/// camera when nothing is in the way.
+const THIRD_PERSON_DISTANCE: FreeCoordinate = 4.0;
+
+/// Minimum distance kept between a [`CameraMode::ThirdPerson`] camera and the blocks
+/// behind it, so that the near plane does not clip into them.
+const THIRD_PERSON_MARGIN: FreeCoordinate = 0.25;
+
+/// Casts a ray backward from the eye of `eye_view` and returns how far the camera
+/// may be moved in that direction, up to [`THIRD_PERSON_DISTANCE`].
+fn third_person_pullback(eye_view: &ViewTransform, space: &Space) -> FreeCoordinate {
+    // The camera looks toward -Z, so +Z is behind it.
+    let ray = Ray::new(
+        Point3::from_vec(eye_view.disp),
+        eye_view.rot.rotate_vector(Vector3::unit_z()),
+    );
+    for step in ray.cast().within(space.bounds()) {
+        let t = step.t_distance();
+        if t >= THIRD_PERSON_DISTANCE + THIRD_PERSON_MARGIN {
+            break;
+        }
+        if space.get_evaluated(step.cube_ahead()).visible {
+            return (t - THIRD_PERSON_MARGIN).clamp(0.0, THIRD_PERSON_DISTANCE);
+        }
     }
+    THIRD_PERSON_DISTANCE
 }
 
-/// Specifies what to render for the UI layer in front of the world.
+/// Specifies what to render for the UI layer in front of the world, or for the
+/// [hand](Layers::hand) layer.
 ///
 /// This struct contains all the information needed to know how to render the UI
 /// *specifically* (distinct from the world). It differs from [`Camera`] in that it
 /// includes the [`Space`] and excludes the viewport.
 ///
-/// TODO: This struct needs a better name. And is it good for non-UI, too?
-/// Note that we may wish to revise this bundle if we start having continuously changing
-/// `view_transform`.
+/// TODO: This struct needs a better name, now that it is not only for the UI.
+/// Note that we may wish to revise this bundle, since the hand's `view_transform`
+/// changes continuously while it is animating.
 #[derive(Clone, Debug)]
 #[allow(clippy::exhaustive_structs)]
 pub struct UiViewState {
@@ -357,7 +582,11 @@ impl Default for UiViewState {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::block::Block;
+    use crate::character::{Avatar, CharacterTransaction};
+    use crate::math::Rgba;
     use crate::space::Space;
+    use crate::transaction::{self, Transaction as _};
     use crate::universe::Universe;
 
     #[test]
@@ -367,7 +596,10 @@ mod tests {
             ListenableSource::constant(GraphicsOptions::default()),
             ListenableSource::constant(Viewport::ARBITRARY),
             character_cell.as_source(),
+            ListenableSource::constant(CameraMode::default()),
             ListenableSource::constant(UiViewState::default()),
+            ListenableSource::constant(UiViewState::default()),
+            ListenableSource::constant(1.0),
         );
 
         let world_source = cameras.world_space();
@@ -387,7 +619,7 @@ mod tests {
                 Character::spawn_default(space_ref.clone()),
             )
             .unwrap();
-        character_cell.set(Some(character));
+        character_cell.set(Some(character.clone()));
 
         // Now the world_source should be reporting the new space
         assert!(!flag.get_and_clear());
@@ -399,7 +631,15 @@ mod tests {
         cameras.update();
         assert!(!flag.get_and_clear());
 
-        // TODO: test further changes
+        // When the character moves to another space, the world_source follows it
+        let space_ref_2 = universe.insert_anonymous(Space::empty_positive(1, 1, 1));
+        CharacterTransaction::move_to_space(space_ref_2.clone(), [0.5, 0.5, 0.5])
+            .bind(character)
+            .execute(&mut universe, &mut transaction::no_outputs)
+            .unwrap();
+        cameras.update();
+        assert!(flag.get_and_clear());
+        assert_eq!(world_source.snapshot().as_ref(), Some(&space_ref_2));
     }
 
     #[test]
@@ -409,7 +649,10 @@ mod tests {
             options_cell.as_source(),
             ListenableSource::constant(Viewport::ARBITRARY),
             ListenableSource::constant(None),
+            ListenableSource::constant(CameraMode::default()),
             ListenableSource::constant(UiViewState::default()),
+            ListenableSource::constant(UiViewState::default()),
+            ListenableSource::constant(1.0),
         );
         let mut cameras2 = cameras.clone();
 
@@ -428,4 +671,38 @@ mod tests {
         assert_eq!(cameras.cameras().world.options(), &different_o);
         assert_eq!(cameras2.cameras().world.options(), &different_o);
     }
+
+    #[test]
+    fn third_person_pullback_stops_before_blocks() {
+        let mut space = Space::empty_positive(1, 1, 10);
+        let mut view = ViewTransform::one();
+        view.disp = Vector3::new(0.5, 0.5, 1.5);
+        assert_eq!(third_person_pullback(&view, &space), THIRD_PERSON_DISTANCE);
+
+        space.set([0, 0, 3], Block::from(Rgba::WHITE)).unwrap();
+        assert_eq!(
+            third_person_pullback(&view, &space),
+            1.5 - THIRD_PERSON_MARGIN
+        );
+    }
+
+    #[test]
+    fn remote_avatars_follow_source() {
+        let universe = Universe::new();
+        let avatars_cell = ListenableCell::new(Vec::new());
+        let mut cameras = StandardCameras::from_constant_for_test(
+            GraphicsOptions::default(),
+            Viewport::ARBITRARY,
+            &universe,
+        )
+        .with_remote_avatars(avatars_cell.as_source());
+        assert!(cameras.world_avatars().is_empty());
+
+        let parts = Avatar::default().pose(Point3::new(0., 0., 0.), 0., 0., 0.);
+        avatars_cell.set(parts.clone());
+        cameras.update();
+        assert_eq!(cameras.world_avatars(), &parts[..]);
+        // Clones follow the same source.
+        assert_eq!(cameras.clone().world_avatars(), &parts[..]);
+    }
 }
@@ -117,3 +117,18 @@ fn exposure_automatic_disabled_when_lighting_is_disabled() {
     camera.set_measured_exposure(7.0);
     assert_eq!(camera.exposure(), notnan!(1.0)); // ignoring measured
 }
+
+#[test]
+fn info_text_scale_follows_ui_scale_and_dpi() {
+    let viewport = Viewport::with_scale(2.0, Vector2::new(800, 600));
+    let mut options = GraphicsOptions::default();
+    // Drawing into the framebuffer, which has twice as many pixels as the nominal size.
+    assert_eq!(info_text_scale(&options, viewport, 800), 2);
+    // Drawing into an image of the nominal size.
+    assert_eq!(info_text_scale(&options, viewport, 400), 1);
+
+    options.ui_scale = notnan!(1.5);
+    assert_eq!(info_text_scale(&options, viewport, 800), 3);
+    options.ui_scale = notnan!(0.5);
+    assert_eq!(info_text_scale(&options, viewport, 400), 1);
+}
@@ -1,40 +1,65 @@
 //! Player-character stuff.
 
 use std::collections::HashSet;
+use std::f64::consts::TAU;
 use std::fmt;
 use std::sync::Arc;
 
 use cgmath::{
-    Angle as _, Basis3, Decomposed, Deg, ElementWise as _, EuclideanSpace as _, Matrix3, Point3,
-    Rotation3, Transform, Vector3,
+    Angle as _, Basis3, Decomposed, Deg, ElementWise as _, EuclideanSpace as _, InnerSpace as _,
+    Matrix3, Point3, Rotation3, Transform, Vector3,
 };
 use num_traits::identities::Zero;
 use ordered_float::NotNan;
 
 use crate::behavior::{Behavior, BehaviorSet, BehaviorSetTransaction};
 use crate::camera::ViewTransform;
+use crate::entity::Entity;
+use crate::event::GameEvent;
 use crate::inv::{
-    Inventory, InventoryChange, InventoryTransaction, Slot, Tool, ToolError, TOOL_SELECTIONS,
+    Inventory, InventoryChange, InventoryTransaction, Slot, StackLimits, Tool, ToolError,
+    TOOL_SELECTIONS,
 };
 use crate::listen::{Listen, Listener, Notifier};
-use crate::math::{Aab, Face6, Face7, FreeCoordinate, Rgb};
-use crate::physics::{Body, BodyStepInfo, BodyTransaction, Contact};
+use crate::math::{Aab, Face6, Face7, FreeCoordinate, GridAab, Rgb};
+use crate::physics::{
+    collide_along_ray, Body, BodyStepInfo, BodyTransaction, CollisionGroups, Contact, StopAt,
+    VELOCITY_MAGNITUDE_LIMIT,
+};
 use crate::raycast::Ray;
 use crate::save::schema;
-use crate::space::Space;
-use crate::time::Tick;
+use crate::space::{space_transition_for, Space};
+use crate::time::{Duration, Tick};
 use crate::transaction::{
     self, CommitError, Merge, PreconditionFailed, Transaction, TransactionConflict, Transactional,
 };
-use crate::universe::{RefVisitor, URef, UniverseTransaction, VisitRefs};
+use crate::universe::{RefError, RefVisitor, URef, UniverseTransaction, VisitRefs};
 use crate::util::{ConciseDebug, CustomFormat, StatusText};
 
+mod avatar;
+pub use avatar::*;
+
 mod cursor;
 pub use cursor::*;
 
+mod health;
+pub use health::{DamageSource, Health};
+
+mod npc;
+pub use npc::*;
+
+mod prediction;
+pub use prediction::*;
+
+mod remote;
+pub use remote::*;
+
 mod spawn;
 pub use spawn::*;
 
+mod stats;
+pub use stats::*;
+
 #[cfg(test)]
 mod tests;
 
@@ -43,6 +68,11 @@ const WALKING_SPEED: FreeCoordinate = 4.0;
 const FLYING_SPEED: FreeCoordinate = 10.0;
 const JUMP_SPEED: FreeCoordinate = 8.0;
 
+/// Distance from a character's body within which blocks with
+/// [`BlockAttributes::contact_damage`](crate::block::BlockAttributes::contact_damage)
+/// count as touching it.
+const CONTACT_DAMAGE_DISTANCE: FreeCoordinate = 1e-3;
+
 /// A `Character`:
 ///
 /// * knows what [`Space`] it is looking at, by reference,
@@ -68,6 +98,14 @@ pub struct Character {
     eye_displacement_pos: Vector3<FreeCoordinate>,
     /// Velocity of the `eye_displacement_pos` point (relative to body).
     eye_displacement_vel: Vector3<FreeCoordinate>,
+    /// Drawn eye position as of the start of the most recent step, used by
+    /// [`Character::view_interpolated()`]. [`None`] if there has been no step yet.
+    previous_eye_position: Option<Point3<FreeCoordinate>>,
+
+    /// How the character appears to others, or to itself in a third-person view.
+    avatar: Avatar,
+    /// Progress through the walk animation of [`Self::avatar`], in radians.
+    walk_phase: FreeCoordinate,
 
     // TODO: Does this belong here? Or in the Space?
     #[doc(hidden)] // pub to be used by all-is-cubes-gpu
@@ -84,6 +122,9 @@ pub struct Character {
     /// Computed camera exposure value based on light samples; converted to natural logarithm.
     exposure_log: f32,
 
+    /// How much more damage the character can take, or [`None`] if it cannot be damaged.
+    health: Option<Health>,
+
     // TODO: Figure out what access is needed and add accessors
     inventory: Inventory,
 
@@ -109,6 +150,7 @@ impl fmt::Debug for Character {
             .field("colliding_cubes", &self.colliding_cubes)
             // TODO: report light samples
             .field("exposure", &self.exposure_log.exp())
+            .field("health", &self.health)
             .field("inventory", &self.inventory)
             .field("behaviors", &self.behaviors)
             .finish()
@@ -164,45 +206,35 @@ impl Character {
             INVISIBLE_SLOT,
         ];
 
-        let look_direction = spawn.look_direction.map(|c| c.into_inner());
-        let yaw = Deg::atan2(look_direction.x, -look_direction.z);
-        let pitch = Deg::atan2(-look_direction.y, look_direction.z.hypot(look_direction.x));
-
         // TODO: This should be configurable, possibly in some more 'template' way
         // than per-spawn?
         let collision_box = Aab::new(-0.35, 0.35, -1.75, 0.15, -0.35, 0.35);
 
-        // Choose position.
-        // TODO: Should also check if the chosen position is intersecting with the contents
-        // of the Space, and avoid that.
-        let position = match spawn.eye_position {
-            Some(pos) => pos.map(NotNan::into_inner),
-            None => {
-                // Stand on the floor of the spawn bounds.
-                // TODO: Account for different gravity.
-                let mut pos = spawn.bounds.center();
-                pos.y = collision_box.face_coordinate(Face6::NY)
-                    - Aab::from(spawn.bounds).face_coordinate(Face6::NY);
-                pos
-            }
-        };
+        let (position, yaw, pitch) = spawn_placement(spawn, collision_box);
 
         Self {
             body: Body {
                 flying: false, // will be overriden anyway
-                yaw: yaw.0,
-                pitch: pitch.0,
+                collision_groups: CollisionGroups::CHARACTER,
+                yaw,
+                pitch,
                 ..Body::new_minimal(position, collision_box)
             },
             space,
             velocity_input: Vector3::zero(),
             eye_displacement_pos: Vector3::zero(),
             eye_displacement_vel: Vector3::zero(),
+            previous_eye_position: None,
+            avatar: Avatar::default(),
+            walk_phase: 0.0,
             colliding_cubes: HashSet::new(),
             last_step_info: None,
             light_samples: [Rgb::ONE; 100],
             light_sample_index: 0,
             exposure_log: 0.0,
+            health: spawn
+                .health
+                .map(|maximum| Health::new(maximum.into_inner())),
             inventory: Inventory::from_slots(inventory),
             selected_slots,
             notifier: Notifier::new(),
@@ -222,19 +254,86 @@ impl Character {
     ///
     /// See the documentation for [`ViewTransform`] for the interpretation of this transform.
     pub fn view(&self) -> ViewTransform {
+        self.view_with_eye_position(self.eye_position())
+    }
+
+    /// Computes the view transform as [`Character::view()`] does, but with the eye
+    /// position interpolated between its values before and after the most recent step.
+    ///
+    /// `fraction` is the fraction of a step's duration that has passed since that step,
+    /// as reported by a frame clock; 0.0 gives the position before the step and 1.0 the
+    /// current position. Rendering with this avoids judder when frames are not drawn at
+    /// the same rate as steps occur. Only the position is interpolated; the look
+    /// direction is always current, since it is driven directly by input.
+    pub fn view_interpolated(&self, fraction: f64) -> ViewTransform {
+        let current = self.eye_position();
+        let eye_position = match self.previous_eye_position {
+            Some(previous) if fraction.is_finite() => {
+                let fraction = fraction.clamp(0.0, 1.0);
+                Point3::from_vec(previous.to_vec() * (1.0 - fraction) + current.to_vec() * fraction)
+            }
+            _ => current,
+        };
+        self.view_with_eye_position(eye_position)
+    }
+
+    fn view_with_eye_position(&self, eye_position: Point3<FreeCoordinate>) -> ViewTransform {
         Decomposed {
             scale: 1.0,
             rot: Basis3::from_angle_y(Deg(-self.body.yaw))
                 * Basis3::from_angle_x(Deg(-self.body.pitch)),
-            disp: self.body.position.to_vec() + self.eye_displacement_pos,
+            disp: eye_position.to_vec(),
         }
     }
 
+    /// Returns how the character appears when it is seen.
+    pub fn avatar(&self) -> &Avatar {
+        &self.avatar
+    }
+
+    /// Replaces how the character appears when it is seen.
+    pub fn set_avatar(&mut self, avatar: Avatar) {
+        self.avatar = avatar;
+    }
+
+    /// Computes the placement of the parts of the character's [`Avatar`], animated
+    /// according to how it is walking.
+    ///
+    /// `position` is the position of the character's body, which may be specified
+    /// separately so as to match an interpolated view such as that returned by
+    /// [`Character::view_interpolated()`].
+    pub fn posed_avatar(&self, position: Point3<FreeCoordinate>) -> Vec<PosedPart> {
+        let walk_amount = if self.is_on_ground() {
+            (horizontal_speed(self.body.velocity) / WALKING_SPEED).min(1.0)
+        } else {
+            0.0
+        };
+        self.avatar
+            .pose(position, self.body.yaw, self.walk_phase, walk_amount)
+    }
+
+    /// Position of the eye, including the effects of `eye_displacement_pos`.
+    fn eye_position(&self) -> Point3<FreeCoordinate> {
+        self.body.position + self.eye_displacement_pos
+    }
+
     /// Returns the character's current inventory.
     pub fn inventory(&self) -> &Inventory {
         &self.inventory
     }
 
+    /// Called by the containing [`Universe`](crate::universe::Universe) to apply its
+    /// stacking rules to this character's inventory.
+    pub(crate) fn set_stack_limits(&mut self, limits: &Arc<StackLimits>) {
+        self.inventory.share_stack_limits(limits);
+    }
+
+    /// Returns how much more damage the character can take, or [`None`] if it cannot be
+    /// damaged at all.
+    pub fn health(&self) -> Option<Health> {
+        self.health
+    }
+
     // TODO: delete this and stick to BehaviorSetTransactions
     #[allow(missing_docs)]
     #[doc(hidden)]
@@ -274,6 +373,7 @@ impl Character {
         tick: Tick,
     ) -> (Option<BodyStepInfo>, UniverseTransaction) {
         let mut result_transaction = UniverseTransaction::default();
+        self.previous_eye_position = Some(self.eye_position());
         if tick.paused() {
             return (None, result_transaction);
         }
@@ -282,43 +382,50 @@ impl Character {
         // TODO: Eliminate body.flying flag entirely, in favor of an external context?
         // (The idea being that Body should have no more things in it than are necessary
         // for, say, a single particle in a particle system.)
-        let flying = find_jetpacks(&self.inventory).any(|(_slot_index, active)| active);
-        self.body.flying = flying;
+        self.body.flying = find_jetpacks(&self.inventory).any(|(_slot_index, active)| active);
 
         let dt = tick.delta_t.as_secs_f64();
-        let control_orientation: Matrix3<FreeCoordinate> =
-            Matrix3::from_angle_y(-Deg(self.body.yaw));
-        // TODO: apply pitch too, but only if wanted for flying (once we have not-flying)
         let initial_body_velocity = self.body.velocity;
 
-        let speed = if flying { FLYING_SPEED } else { WALKING_SPEED };
-        let mut velocity_target = control_orientation * self.velocity_input * speed;
-        if !flying {
-            velocity_target.y = 0.0;
-        }
-        // TODO should have an on-ground condition...
-        let stiffness = if flying {
-            Vector3::new(10.8, 10.8, 10.8)
-        } else {
-            Vector3::new(10.8, 0., 10.8)
-        }; // TODO constants/tables...
-
-        self.body.velocity +=
-            (velocity_target - self.body.velocity).mul_element_wise(stiffness) * dt;
+        self.apply_velocity_input(dt);
 
         let body_step_info = if let Ok(space) = self.space.read() {
             self.update_exposure(&space, dt);
-
-            let colliding_cubes = &mut self.colliding_cubes;
-            colliding_cubes.clear();
-            Some(self.body.step(tick, Some(&*space), |cube| {
-                colliding_cubes.insert(cube);
-            }))
+            let velocity_before_body_step = self.body.velocity;
+            let body_step_info = self.step_body(tick, &space);
+            let impact_speed = (self.body.velocity - velocity_before_body_step).magnitude();
+            if let Some(cause) = self.step_health(&space, dt, impact_speed) {
+                if let Some(self_ref) = self_ref {
+                    result_transaction = result_transaction
+                        .merge(UniverseTransaction::event(GameEvent::Died {
+                            character: self_ref.clone(),
+                            cause,
+                        }))
+                        .unwrap()
+                        .merge(UniverseTransaction::event(GameEvent::Respawned {
+                            character: self_ref.clone(),
+                        }))
+                        .unwrap();
+                }
+            }
+            if let Some(self_ref) = self_ref {
+                if let Some(t) = space_transition_for(self, self_ref, &space) {
+                    result_transaction = result_transaction.merge(t).unwrap();
+                }
+            }
+            Some(body_step_info)
         } else {
             // TODO: set a warning flag
             None
         };
 
+        // Advance the walk animation by the distance walked.
+        if self.is_on_ground() {
+            self.walk_phase = (self.walk_phase
+                + horizontal_speed(self.body.velocity) * dt * TAU / STRIDE_LENGTH)
+                .rem_euclid(TAU);
+        }
+
         // Automatic flying controls
         // TODO: lazy clone
         if let Some(self_ref) = self_ref.cloned() {
@@ -345,6 +452,7 @@ impl Character {
         if let Some(self_ref) = self_ref {
             let t = self.behaviors.step(
                 self,
+                self_ref,
                 &(|t: CharacterTransaction| t.bind(self_ref.clone())),
                 CharacterTransaction::behaviors,
                 tick,
@@ -372,6 +480,38 @@ impl Character {
         (body_step_info, result_transaction)
     }
 
+    /// Adjusts the body's velocity towards that requested by the velocity input.
+    fn apply_velocity_input(&mut self, dt: f64) {
+        let flying = self.body.flying;
+        let control_orientation: Matrix3<FreeCoordinate> =
+            Matrix3::from_angle_y(-Deg(self.body.yaw));
+        // TODO: apply pitch too, but only if wanted for flying (once we have not-flying)
+
+        let speed = if flying { FLYING_SPEED } else { WALKING_SPEED };
+        let mut velocity_target = control_orientation * self.velocity_input * speed;
+        if !flying {
+            velocity_target.y = 0.0;
+        }
+        // TODO should have an on-ground condition...
+        let stiffness = if flying {
+            Vector3::new(10.8, 10.8, 10.8)
+        } else {
+            Vector3::new(10.8, 0., 10.8)
+        }; // TODO constants/tables...
+
+        self.body.velocity +=
+            (velocity_target - self.body.velocity).mul_element_wise(stiffness) * dt;
+    }
+
+    /// Moves the body, colliding with `space`, and records what it collided with.
+    fn step_body(&mut self, tick: Tick, space: &Space) -> BodyStepInfo {
+        let colliding_cubes = &mut self.colliding_cubes;
+        colliding_cubes.clear();
+        self.body.step(tick, Some(space), |cube| {
+            colliding_cubes.insert(cube);
+        })
+    }
+
     /// Returns the character's current automatic-exposure calculation based on the light
     /// around it.
     pub fn exposure(&self) -> f32 {
@@ -411,7 +551,8 @@ impl Character {
                     // just take the first valid value, then we'll trivially pick the same cube
                     // every time if our eye is within a cube with valid light.
                     if !bounds.contains_cube(step.cube_ahead()) {
-                        self.light_samples[self.light_sample_index] = space.physics().sky_color;
+                        self.light_samples[self.light_sample_index] =
+                            space.physics().sky.sample(ray.direction);
                         continue 'rays;
                     } else if space.get_evaluated(step.cube_ahead()).visible {
                         let l = space.get_lighting(step.cube_behind());
@@ -422,7 +563,8 @@ impl Character {
                     }
                 }
                 // If we got here, nothing was hit
-                self.light_samples[self.light_sample_index] = space.physics().sky_color;
+                self.light_samples[self.light_sample_index] =
+                    space.physics().sky.sample(ray.direction);
             }
         }
 
@@ -448,6 +590,66 @@ impl Character {
         }
     }
 
+    /// Checks whether a report, such as from a multiplayer client, that this character
+    /// moved from its current position to `position` over the duration `elapsed` is
+    /// plausible, so that the authority over the character's state (a server) can decide
+    /// whether to accept it.
+    ///
+    /// Returns an error if the motion is faster than the character can move, or if its
+    /// collision box would have passed through blocks it collides with (unless
+    /// [`Body::noclip`] is set). In that case, the report should be rejected, and the
+    /// character's actual [`Body::position`] sent back as a correction.
+    pub fn check_reported_motion(
+        &self,
+        position: Point3<FreeCoordinate>,
+        elapsed: Duration,
+    ) -> Result<(), MotionError> {
+        /// Allowance for the imprecision of the client's and our clocks.
+        const SPEED_TOLERANCE: FreeCoordinate = 1.5;
+        /// Allowance for distances too small to matter, such as rounding error.
+        const DISTANCE_TOLERANCE: FreeCoordinate = 0.01;
+
+        let seconds = elapsed.as_secs_f64();
+        let displacement = position - self.body.position;
+        let check_speed = |distance: FreeCoordinate, limit: FreeCoordinate| {
+            if distance > limit * seconds * SPEED_TOLERANCE + DISTANCE_TOLERANCE {
+                Err(MotionError::TooFast {
+                    speed: distance / seconds,
+                    limit,
+                })
+            } else {
+                Ok(())
+            }
+        };
+        let flying = self.body.flying;
+        check_speed(
+            horizontal_speed(displacement),
+            if flying { FLYING_SPEED } else { WALKING_SPEED },
+        )?;
+        // Falling is limited only by the body's velocity limit, but rising requires
+        // jumping or flying.
+        check_speed(
+            displacement.y,
+            if flying { FLYING_SPEED } else { JUMP_SPEED },
+        )?;
+        check_speed(displacement.magnitude(), VELOCITY_MAGNITUDE_LIMIT)?;
+
+        if !self.body.noclip {
+            let space = self.space.read()?;
+            if let Some(end) = collide_along_ray(
+                &*space,
+                Ray::new(self.body.position, displacement),
+                self.body.collision_box,
+                self.body.collides_with,
+                |_| {},
+                StopAt::NotAlreadyColliding,
+            ) {
+                return Err(MotionError::Obstructed(end.contact));
+            }
+        }
+        Ok(())
+    }
+
     /// Maximum range for normal keyboard input should be -1 to 1
     pub fn set_velocity_input(&mut self, velocity: Vector3<FreeCoordinate>) {
         self.velocity_input = velocity;
@@ -500,6 +702,111 @@ impl Character {
         }
     }
 
+    /// Returns a transaction which moves the item [`Entity`]s among `entities` which are
+    /// near the character into its inventory, as many as fit, or [`None`] if there are
+    /// none to pick up.
+    ///
+    /// This is not part of [`Character::step()`]'s transaction, so that if another
+    /// character picks up the same item first, only the pickup fails.
+    pub(crate) fn pick_up_items(
+        &self,
+        self_ref: &URef<Character>,
+        entities: impl IntoIterator<Item = URef<Entity>>,
+    ) -> Option<UniverseTransaction> {
+        let mut items: Vec<Tool> = Vec::new();
+        let mut deletions = UniverseTransaction::default();
+        for entity_ref in entities {
+            let Ok(entity) = entity_ref.read() else {
+                continue;
+            };
+            if !entity.can_be_picked_up_from(&self.space, self.body.position) {
+                continue;
+            }
+            items.extend(entity.item().cloned());
+            if InventoryTransaction::insert(items.iter().cloned())
+                .check(&self.inventory)
+                .is_err()
+            {
+                // No room for this item; leave it for later.
+                items.pop();
+                continue;
+            }
+            deletions = deletions
+                .merge(UniverseTransaction::delete(entity_ref.clone()))
+                .unwrap();
+        }
+        if items.is_empty() {
+            return None;
+        }
+
+        let insert = InventoryTransaction::insert(items.iter().cloned());
+        let mut txn = deletions
+            .merge(CharacterTransaction::inventory(insert).bind(self_ref.clone()))
+            .unwrap();
+        for item in items {
+            txn = txn
+                .merge(UniverseTransaction::event(GameEvent::ItemPickedUp {
+                    character: self_ref.clone(),
+                    item,
+                }))
+                .unwrap();
+        }
+        Some(txn)
+    }
+
+    /// Applies damage from an impact at `impact_speed` during the latest body step and
+    /// from touching harmful blocks for `dt` seconds, if the character has health.
+    ///
+    /// If this kills the character, it is respawned at `space`'s [`Spawn`] with full
+    /// health, and the cause of the final damage is returned.
+    fn step_health(
+        &mut self,
+        space: &Space,
+        dt: f64,
+        impact_speed: FreeCoordinate,
+    ) -> Option<DamageSource> {
+        let health = self.health.as_mut()?;
+        let mut cause = None;
+
+        let damage = health::impact_damage(impact_speed);
+        if damage > 0.0 {
+            health.damage(damage);
+            cause = Some(DamageSource::Impact);
+        }
+
+        let touching = self
+            .body
+            .collision_box_abs()
+            .expand(CONTACT_DAMAGE_DISTANCE)
+            .round_up_to_grid()
+            .intersection(space.bounds());
+        let most_harmful = touching
+            .into_iter()
+            .flat_map(GridAab::interior_iter)
+            .map(|cube| (cube, space.get_evaluated(cube).attributes.contact_damage))
+            .max_by_key(|&(_, rate)| rate);
+        if let Some((cube, rate)) = most_harmful {
+            if rate.into_inner() > 0.0 {
+                health.damage(rate.into_inner() * dt as f32);
+                cause = Some(DamageSource::Block(space[cube].clone()));
+            }
+        }
+
+        let cause = cause?;
+        if !health.is_dead() {
+            return None;
+        }
+
+        health.restore();
+        let (position, yaw, pitch) = spawn_placement(space.spawn(), self.body.collision_box);
+        self.body.position = position;
+        self.body.velocity = Vector3::zero();
+        self.body.yaw = yaw;
+        self.body.pitch = pitch;
+        self.previous_eye_position = None;
+        Some(cause)
+    }
+
     fn is_on_ground(&self) -> bool {
         self.body.velocity.y <= 0.0
             && self
@@ -519,6 +826,9 @@ impl VisitRefs for Character {
             velocity_input: _,
             eye_displacement_pos: _,
             eye_displacement_vel: _,
+            previous_eye_position: _,
+            avatar,
+            walk_phase: _,
             colliding_cubes: _,
             last_step_info: _,
             light_samples: _,
@@ -530,6 +840,7 @@ impl VisitRefs for Character {
             behaviors,
         } = self;
         visitor.visit(space);
+        avatar.visit_refs(visitor);
         inventory.visit_refs(visitor);
         behaviors.visit_refs(visitor);
     }
@@ -564,13 +875,17 @@ impl serde::Serialize for Character {
                     collision_box,
                     flying,
                     noclip,
+                    collision_groups: _, // TODO: should be persisted
+                    collides_with: _,    // TODO: should be persisted
                     yaw,
                     pitch,
                 },
             ref space,
             ref inventory,
             selected_slots,
+            health,
             behaviors: _, // TODO: should be persisted
+            avatar: _,    // TODO: should be persisted
 
             // Not persisted - run-time connections to other things
             notifier: _,
@@ -579,6 +894,8 @@ impl serde::Serialize for Character {
             // Not persisted - decorative simulation
             eye_displacement_pos: _,
             eye_displacement_vel: _,
+            previous_eye_position: _,
+            walk_phase: _,
             colliding_cubes: _,
             last_step_info: _,
             light_samples: _,
@@ -598,6 +915,10 @@ impl serde::Serialize for Character {
 
             inventory: inventory.clone(),
             selected_slots,
+            health: health.map(|health| schema::HealthSer {
+                current: health.current(),
+                maximum: health.maximum(),
+            }),
         }
         .serialize(serializer)
     }
@@ -620,6 +941,7 @@ impl<'de> serde::Deserialize<'de> for Character {
                 pitch,
                 inventory,
                 selected_slots,
+                health,
             } => Ok(Character {
                 body: Body {
                     position: position.into(),
@@ -627,13 +949,17 @@ impl<'de> serde::Deserialize<'de> for Character {
                     collision_box,
                     flying,
                     noclip,
+                    collision_groups: CollisionGroups::CHARACTER,
+                    collides_with: CollisionGroups::default(),
                     yaw,
                     pitch,
                 },
                 space,
                 inventory,
                 selected_slots,
+                health: health.map(|h| Health::from_parts(h.current, h.maximum)),
                 behaviors: BehaviorSet::new(),
+                avatar: Avatar::default(),
 
                 // Not persisted - run-time connections to other things
                 notifier: Notifier::new(),
@@ -642,6 +968,8 @@ impl<'de> serde::Deserialize<'de> for Character {
                 // Not persisted - decorative simulation
                 eye_displacement_pos: Vector3::zero(),
                 eye_displacement_vel: Vector3::zero(),
+                previous_eye_position: None,
+                walk_phase: 0.0,
                 colliding_cubes: HashSet::new(),
                 last_step_info: None,
                 light_samples: [Rgb::ONE; 100],
@@ -659,6 +987,12 @@ pub struct CharacterTransaction {
     body: BodyTransaction,
     inventory: InventoryTransaction,
     behaviors: BehaviorSetTransaction<Character>,
+    /// If not [`None`], replaces the character's velocity input.
+    velocity_input: Option<Vector3<FreeCoordinate>>,
+    /// Whether to [`jump_if_able()`](Character::jump_if_able).
+    jump: bool,
+    /// If not [`None`], replaces the character's [`Character::space`].
+    space: Option<URef<Space>>,
 }
 
 impl CharacterTransaction {
@@ -678,6 +1012,34 @@ impl CharacterTransaction {
         }
     }
 
+    /// Replace the character's velocity input, as [`Character::set_velocity_input()`]
+    /// would.
+    pub fn set_velocity_input(velocity: Vector3<FreeCoordinate>) -> Self {
+        CharacterTransaction {
+            velocity_input: Some(velocity),
+            ..Default::default()
+        }
+    }
+
+    /// Make the character jump if it is able to, as [`Character::jump_if_able()`] would.
+    pub fn jump() -> Self {
+        CharacterTransaction {
+            jump: true,
+            ..Default::default()
+        }
+    }
+
+    /// Move the character into `space`, at `position` in that space's coordinates.
+    ///
+    /// Its velocity is set to zero, as by [`BodyTransaction::teleport()`].
+    pub fn move_to_space(space: URef<Space>, position: impl Into<Point3<FreeCoordinate>>) -> Self {
+        CharacterTransaction {
+            body: BodyTransaction::teleport(position),
+            space: Some(space),
+            ..Default::default()
+        }
+    }
+
     /// Modify the character's [`BehaviorSet`].
     fn behaviors(t: BehaviorSetTransaction<Character>) -> Self {
         Self {
@@ -724,6 +1086,21 @@ impl Transaction<Character> for CharacterTransaction {
             .commit(&mut target.behaviors, behaviors_check, outputs)
             .map_err(|e| e.context("behaviors".into()))?;
 
+        if let Some(velocity) = self.velocity_input {
+            target.set_velocity_input(velocity);
+        }
+        if self.jump {
+            target.jump_if_able();
+        }
+        if let Some(space) = &self.space {
+            if *space != target.space {
+                target.space = space.clone();
+                // Don't interpolate the view between positions in different spaces.
+                target.previous_eye_position = None;
+                target.notifier.notify(CharacterChange::Space);
+            }
+        }
+
         Ok(())
     }
 }
@@ -736,6 +1113,12 @@ impl Merge for CharacterTransaction {
     );
 
     fn check_merge(&self, other: &Self) -> Result<Self::MergeCheck, TransactionConflict> {
+        if self.velocity_input.is_some() && other.velocity_input.is_some() {
+            return Err(TransactionConflict {});
+        }
+        if matches!((&self.space, &other.space), (Some(a), Some(b)) if a != b) {
+            return Err(TransactionConflict {});
+        }
         Ok((
             self.body.check_merge(&other.body)?,
             self.inventory.check_merge(&other.inventory)?,
@@ -756,6 +1139,9 @@ impl Merge for CharacterTransaction {
             behaviors: self
                 .behaviors
                 .commit_merge(other.behaviors, behaviors_check),
+            velocity_input: self.velocity_input.or(other.velocity_input),
+            jump: self.jump || other.jump,
+            space: self.space.or(other.space),
         }
     }
 }
@@ -768,6 +1154,29 @@ pub enum CharacterChange {
     Inventory(InventoryChange),
     /// Which inventory slots are selected.
     Selections,
+    /// Which [`Space`] the character is in ([`Character::space`]), as changed by a
+    /// [`CharacterTransaction`].
+    Space,
+}
+
+/// Reasons for rejecting motion checked by [`Character::check_reported_motion()`].
+#[derive(Clone, Debug, PartialEq, thiserror::Error)]
+#[non_exhaustive]
+pub enum MotionError {
+    /// The motion was faster than the character can move.
+    #[error("moved at {speed} cubes/s, faster than the limit of {limit} cubes/s")]
+    TooFast {
+        /// The speed of the reported motion, in cubes per second.
+        speed: FreeCoordinate,
+        /// The exceeded limit, in cubes per second.
+        limit: FreeCoordinate,
+    },
+    /// The motion passed through something the character collides with.
+    #[error("passed through an obstacle at {0:?}")]
+    Obstructed(Contact),
+    /// The character's space could not be accessed to check for obstacles.
+    #[error("error accessing space: {0}")]
+    SpaceRef(#[from] RefError),
 }
 
 fn find_jetpacks(inventory: &Inventory) -> impl Iterator<Item = (usize, bool)> + '_ {
@@ -783,3 +1192,36 @@ fn find_jetpacks(inventory: &Inventory) -> impl Iterator<Item = (usize, bool)> +
             }
         })
 }
+
+/// Speed of motion in the horizontal plane, which is what walking animates.
+fn horizontal_speed(velocity: Vector3<FreeCoordinate>) -> FreeCoordinate {
+    velocity.x.hypot(velocity.z)
+}
+
+/// Computes the body position, yaw, and pitch specified by `spawn` for a body with the
+/// given collision box.
+fn spawn_placement(
+    spawn: &Spawn,
+    collision_box: Aab,
+) -> (Point3<FreeCoordinate>, FreeCoordinate, FreeCoordinate) {
+    let look_direction = spawn.look_direction.map(|c| c.into_inner());
+    let yaw = Deg::atan2(look_direction.x, -look_direction.z);
+    let pitch = Deg::atan2(-look_direction.y, look_direction.z.hypot(look_direction.x));
+
+    // Choose position.
+    // TODO: Should also check if the chosen position is intersecting with the contents
+    // of the Space, and avoid that.
+    let position = match spawn.eye_position {
+        Some(pos) => pos.map(NotNan::into_inner),
+        None => {
+            // Stand on the floor of the spawn bounds.
+            // TODO: Account for different gravity.
+            let mut pos = spawn.bounds.center();
+            pos.y = collision_box.face_coordinate(Face6::NY)
+                - Aab::from(spawn.bounds).face_coordinate(Face6::NY);
+            pos
+        }
+    };
+
+    (position, yaw.0, pitch.0)
+}
@@ -0,0 +1,222 @@
+//! [`Avatar`]: the visible form of a character.
+
+use cgmath::{Deg, EuclideanSpace as _, Matrix4, Point3, Vector3};
+
+use crate::block::Block;
+use crate::content::palette;
+use crate::math::FreeCoordinate;
+use crate::universe::{RefVisitor, VisitRefs};
+
+#[cfg(doc)]
+use crate::{character::Character, physics::Body};
+
+/// Angle, in degrees, by which parts with a [`swing`](AvatarPart::swing) of 1 swing
+/// forward and back at full walking speed.
+const SWING_ANGLE: FreeCoordinate = 35.0;
+
+/// Distance, in cubes, that a character must walk to complete one cycle of the walk
+/// animation (two steps).
+pub(crate) const STRIDE_LENGTH: FreeCoordinate = 2.0;
+
+/// The visible form of a [`Character`]: a rig of [`AvatarPart`]s, each of which is a
+/// [`Block`] stretched to fill a box, which swing about their joints as the character
+/// walks.
+///
+/// Renderers should obtain the boxes to draw from [`Character::posed_avatar()`] and
+/// triangulate each part's block as they would any other block.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Avatar {
+    parts: Vec<AvatarPart>,
+}
+
+/// One rigid part of an [`Avatar`], such as the head or a limb.
+///
+/// Coordinates are relative to the character's [`Body::position`] (which is also its eye
+/// position), and oriented as if the character were facing −Z.
+#[derive(Clone, Debug, PartialEq)]
+#[allow(clippy::exhaustive_structs)]
+pub struct AvatarPart {
+    /// The block drawn for this part; its cube is stretched to [`size`](Self::size).
+    pub block: Block,
+    /// Size of the part's box.
+    pub size: Vector3<FreeCoordinate>,
+    /// Point about which the part swings.
+    pub joint: Point3<FreeCoordinate>,
+    /// Position of the lowest corner of the box relative to [`joint`](Self::joint), before
+    /// any swinging.
+    pub offset: Vector3<FreeCoordinate>,
+    /// How far the part swings about the X axis while walking, as a multiple of the
+    /// standard swing. Parts with opposite signs swing opposite ways, and 0 means the
+    /// part does not move.
+    pub swing: FreeCoordinate,
+}
+
+/// An [`AvatarPart`] in a particular pose, as returned by [`Avatar::pose()`].
+#[derive(Clone, Debug, PartialEq)]
+#[non_exhaustive]
+pub struct PosedPart {
+    /// The block to draw.
+    pub block: Block,
+    /// Transform from the block's unit cube to the coordinates of the character's space.
+    pub transform: Matrix4<FreeCoordinate>,
+}
+
+impl Avatar {
+    /// Constructs an [`Avatar`] from its parts.
+    pub fn new(parts: Vec<AvatarPart>) -> Self {
+        Self { parts }
+    }
+
+    /// Returns the parts of this avatar.
+    pub fn parts(&self) -> &[AvatarPart] {
+        &self.parts
+    }
+
+    /// Computes the placement of each part of the avatar.
+    ///
+    /// * `position` and `yaw` are the character's position and facing, as in [`Body`].
+    /// * `walk_phase` is the progress through the walk cycle, in radians.
+    /// * `walk_amount` scales the walk animation, from 0 (standing still) to 1 (walking
+    ///   at full speed).
+    pub fn pose(
+        &self,
+        position: Point3<FreeCoordinate>,
+        yaw: FreeCoordinate,
+        walk_phase: FreeCoordinate,
+        walk_amount: FreeCoordinate,
+    ) -> Vec<PosedPart> {
+        let body_transform =
+            Matrix4::from_translation(position.to_vec()) * Matrix4::from_angle_y(Deg(-yaw));
+        let swing_angle = SWING_ANGLE * walk_phase.sin() * walk_amount;
+        self.parts
+            .iter()
+            .map(|part| PosedPart {
+                block: part.block.clone(),
+                transform: body_transform
+                    * Matrix4::from_translation(part.joint.to_vec())
+                    * Matrix4::from_angle_x(Deg(swing_angle * part.swing))
+                    * Matrix4::from_translation(part.offset)
+                    * Matrix4::from_nonuniform_scale(part.size.x, part.size.y, part.size.z),
+            })
+            .collect()
+    }
+}
+
+impl Default for Avatar {
+    /// A simple humanoid figure of plain colored blocks, which fits within the default
+    /// collision box of a [`Character`].
+    fn default() -> Self {
+        let body = Block::from(palette::CHARACTER_BODY);
+        let legs = Block::from(palette::CHARACTER_LEGS);
+        let mut parts = vec![
+            AvatarPart {
+                block: Block::from(palette::CHARACTER_HEAD),
+                size: Vector3::new(0.4, 0.4, 0.4),
+                joint: Point3::new(0., -0.25, 0.),
+                offset: Vector3::new(-0.2, 0., -0.2),
+                swing: 0.,
+            },
+            AvatarPart {
+                block: body.clone(),
+                size: Vector3::new(0.5, 0.75, 0.25),
+                joint: Point3::new(0., -1., 0.),
+                offset: Vector3::new(-0.25, 0., -0.125),
+                swing: 0.,
+            },
+        ];
+        // Each arm swings opposite to the leg on the same side.
+        for side in [-1., 1.] {
+            parts.push(AvatarPart {
+                block: body.clone(),
+                size: Vector3::new(0.125, 0.75, 0.125),
+                joint: Point3::new(0.3125 * side, -0.25, 0.),
+                offset: Vector3::new(-0.0625, -0.75, -0.0625),
+                swing: side,
+            });
+            parts.push(AvatarPart {
+                block: legs.clone(),
+                size: Vector3::new(0.25, 0.75, 0.25),
+                joint: Point3::new(0.125 * side, -1., 0.),
+                offset: Vector3::new(-0.125, -0.75, -0.125),
+                swing: -side,
+            });
+        }
+        Self::new(parts)
+    }
+}
+
+impl VisitRefs for Avatar {
+    fn visit_refs(&self, visitor: &mut dyn RefVisitor) {
+        for part in &self.parts {
+            part.block.visit_refs(visitor);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cgmath::{InnerSpace as _, Transform as _};
+    use std::f64::consts::FRAC_PI_2;
+
+    /// Returns the lower and upper corners of the box containing all of the parts.
+    fn posed_bounds(parts: &[PosedPart]) -> (Point3<FreeCoordinate>, Point3<FreeCoordinate>) {
+        let corners = parts.iter().flat_map(|part| {
+            [[0., 0., 0.], [1., 1., 1.]].map(|p| part.transform.transform_point(p.into()))
+        });
+        let mut lower = Point3::new(f64::INFINITY, f64::INFINITY, f64::INFINITY);
+        let mut upper = lower.map(|c| -c);
+        for corner in corners {
+            lower = lower.zip(corner, f64::min);
+            upper = upper.zip(corner, f64::max);
+        }
+        (lower, upper)
+    }
+
+    #[test]
+    fn default_fits_collision_box() {
+        let parts = Avatar::default().pose(Point3::new(0., 0., 0.), 0., 0., 0.);
+        let (lower, upper) = posed_bounds(&parts);
+        assert!((lower.y - -1.75).abs() < 1e-9, "{lower:?}");
+        assert!((upper.y - 0.15).abs() < 1e-9, "{upper:?}");
+        assert!(lower.x >= -0.375 && upper.x <= 0.375, "{lower:?} {upper:?}");
+    }
+
+    #[test]
+    fn pose_follows_position_and_yaw() {
+        let avatar = Avatar::default();
+        let position = Point3::new(10., 20., 30.);
+        let at_origin = avatar.pose(Point3::new(0., 0., 0.), 0., 0., 0.);
+        let moved = avatar.pose(position, 0., 0., 0.);
+        let turned = avatar.pose(Point3::new(0., 0., 0.), 180., 0., 0.);
+
+        let head_center = |parts: &[PosedPart]| {
+            parts[0]
+                .transform
+                .transform_point(Point3::new(0.5, 0.5, 0.5))
+        };
+        let origin_head = head_center(&at_origin);
+        assert!((head_center(&moved) - (origin_head + position.to_vec())).magnitude() < 1e-9);
+        // The head is centered on the axis of rotation, so turning does not move it.
+        assert!((head_center(&turned) - origin_head).magnitude() < 1e-9);
+    }
+
+    #[test]
+    fn walking_swings_legs_oppositely() {
+        let avatar = Avatar::default();
+        let origin = Point3::new(0., 0., 0.);
+        let foot = Point3::new(0.5, 0., 0.5);
+        let standing = avatar.pose(origin, 0., FRAC_PI_2, 0.);
+        let walking = avatar.pose(origin, 0., FRAC_PI_2, 1.);
+
+        // Parts are the head, the torso, and then an arm and a leg for each side.
+        let left_foot = walking[3].transform.transform_point(foot);
+        let right_foot = walking[5].transform.transform_point(foot);
+        let standing_foot = standing[3].transform.transform_point(foot);
+        assert!(
+            left_foot.z * right_foot.z < 0.0,
+            "{left_foot:?} {right_foot:?}"
+        );
+        assert!(left_foot.y > standing_foot.y);
+    }
+}
@@ -16,12 +16,13 @@ use crate::space::{PackedLight, Space};
 use crate::universe::URef;
 use crate::util::MapExtend;
 
-/// Find the first selectable block the ray strikes and express the result in a [`Cursor`]
-/// value, or [`None`] if nothing was struck within the distance limit.
+/// Find the first block the ray strikes which `policy` accepts, and express the result
+/// in a [`Cursor`] value, or [`None`] if nothing was struck within the distance limit.
 pub fn cursor_raycast(
     mut ray: Ray,
     space_ref: &URef<Space>,
     maximum_distance: FreeCoordinate,
+    policy: CursorPolicy,
 ) -> Option<Cursor> {
     ray.direction = ray.direction.normalize();
     let space = space_ref.read().ok()?;
@@ -34,14 +35,14 @@ pub fn cursor_raycast(
         let evaluated = space.get_evaluated(cube);
         let mut face_selected = None;
 
-        if !evaluated.attributes.selectable {
+        if !policy.accepts_block(evaluated) {
             continue;
         }
 
         // Check intersection with recursive block
         match evaluated.voxels {
-            Evoxels::One(evoxel) => {
-                if !evoxel.selectable {
+            Evoxels::One(evoxel) | Evoxels::Faces(evoxel, _) => {
+                if !policy.accepts_voxel(&evoxel) {
                     continue;
                 }
                 face_selected = Some(step.face());
@@ -64,7 +65,7 @@ pub fn cursor_raycast(
                                 .get(voxel_step.cube_ahead())
                                 .map(|v| (voxel_step.cube_ahead(), v))
                         })
-                        .find(|(_, v)| v.selectable);
+                        .find(|(_, v)| policy.accepts_voxel(v));
                 if recursive_hit.is_none() {
                     continue;
                 }
@@ -98,6 +99,44 @@ pub fn cursor_raycast(
     }
     None
 }
+
+/// Which blocks [`cursor_raycast()`] should stop at, and which it should pass through.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+#[non_exhaustive]
+pub enum CursorPolicy {
+    /// Stop at the first visible block or voxel, whether or not it is selectable.
+    FirstVisible,
+    /// Stop at the first block or voxel which is
+    /// [selectable](crate::block::BlockAttributes::selectable).
+    #[default]
+    Selectable,
+    /// Stop at the first selectable block or voxel which is fully opaque, passing
+    /// through glass, water, and other transparent materials so that blocks behind them
+    /// may be targeted.
+    SelectableOpaque,
+}
+
+impl CursorPolicy {
+    /// Whether a block with these attributes might be hit, before considering its voxels.
+    fn accepts_block(self, evaluated: &EvaluatedBlock) -> bool {
+        match self {
+            CursorPolicy::FirstVisible => evaluated.visible,
+            CursorPolicy::Selectable | CursorPolicy::SelectableOpaque => {
+                evaluated.attributes.selectable
+            }
+        }
+    }
+
+    /// Whether the ray should stop at this voxel.
+    fn accepts_voxel(self, voxel: &Evoxel) -> bool {
+        match self {
+            CursorPolicy::FirstVisible => !voxel.color.fully_transparent(),
+            CursorPolicy::Selectable => voxel.selectable,
+            CursorPolicy::SelectableOpaque => voxel.selectable && voxel.color.fully_opaque(),
+        }
+    }
+}
+
 /// Data collected by [`cursor_raycast`] about the blocks struck by the ray; intended to be
 /// sufficient for various player interactions with blocks.
 ///
@@ -327,7 +366,8 @@ mod tests {
         let [block] = make_some_blocks();
         let space_ref = test_space(universe, [&AIR, &block]);
 
-        let cursor = cursor_raycast(X_RAY, &space_ref, f64::INFINITY).unwrap();
+        let cursor =
+            cursor_raycast(X_RAY, &space_ref, f64::INFINITY, CursorPolicy::default()).unwrap();
         assert_eq!(cursor.hit().block, block);
         assert_eq!(cursor.cube(), GridPoint::new(1, 0, 0));
         assert_eq!(cursor.face_selected(), Face7::NX);
@@ -339,7 +379,10 @@ mod tests {
         let [block] = make_some_blocks();
         let space_ref = test_space(universe, [&AIR, &block]);
 
-        assert_eq!(cursor_raycast(X_RAY, &space_ref, 1.0), None);
+        assert_eq!(
+            cursor_raycast(X_RAY, &space_ref, 1.0, CursorPolicy::default()),
+            None
+        );
     }
 
     #[test]
@@ -352,7 +395,8 @@ mod tests {
             .build();
         let space_ref = test_space(universe, [&not_selectable, &block]);
 
-        let cursor = cursor_raycast(X_RAY, &space_ref, f64::INFINITY).unwrap();
+        let cursor =
+            cursor_raycast(X_RAY, &space_ref, f64::INFINITY, CursorPolicy::default()).unwrap();
         // If the non-selectable block was hit, this would be [0, 0, 0]
         assert_eq!(cursor.cube(), GridPoint::new(1, 0, 0));
         assert_eq!(cursor.hit().block, block);
@@ -365,7 +409,8 @@ mod tests {
         let not_selectable = make_slab(universe, 1, R2); // Upper half is nonselectable air
         let space_ref = test_space(universe, [&not_selectable, &block]);
 
-        let cursor = cursor_raycast(X_RAY, &space_ref, f64::INFINITY).unwrap();
+        let cursor =
+            cursor_raycast(X_RAY, &space_ref, f64::INFINITY, CursorPolicy::default()).unwrap();
         assert_eq!(cursor.cube(), GridPoint::new(1, 0, 0));
         assert_eq!(cursor.hit().block, block);
     }
@@ -377,11 +422,50 @@ mod tests {
         let selectable_voxels = make_slab(universe, 3, R4);
         let space_ref = test_space(universe, [&AIR, &selectable_voxels, &other_block]);
 
-        let cursor = cursor_raycast(X_RAY, &space_ref, f64::INFINITY).unwrap();
+        let cursor =
+            cursor_raycast(X_RAY, &space_ref, f64::INFINITY, CursorPolicy::default()).unwrap();
         assert_eq!(cursor.cube(), GridPoint::new(1, 0, 0));
         assert_eq!(cursor.hit().block, selectable_voxels);
     }
 
+    #[test]
+    fn policy_first_visible_hits_not_selectable() {
+        let universe = &mut Universe::new();
+        let [block] = make_some_blocks();
+        let not_selectable = Block::builder()
+            .color(Rgba::WHITE)
+            .selectable(false)
+            .build();
+        let space_ref = test_space(universe, [&AIR, &not_selectable, &block]);
+
+        let cursor =
+            cursor_raycast(X_RAY, &space_ref, f64::INFINITY, CursorPolicy::FirstVisible).unwrap();
+        assert_eq!(cursor.cube(), GridPoint::new(1, 0, 0));
+        assert_eq!(cursor.hit().block, not_selectable);
+    }
+
+    #[test]
+    fn policy_selectable_opaque_passes_through_glass() {
+        let universe = &mut Universe::new();
+        let [block] = make_some_blocks();
+        let glass = Block::from(Rgba::new(0.5, 0.5, 1.0, 0.25));
+        let space_ref = test_space(universe, [&glass, &block]);
+
+        let cursor =
+            cursor_raycast(X_RAY, &space_ref, f64::INFINITY, CursorPolicy::Selectable).unwrap();
+        assert_eq!(cursor.hit().block, glass);
+
+        let cursor = cursor_raycast(
+            X_RAY,
+            &space_ref,
+            f64::INFINITY,
+            CursorPolicy::SelectableOpaque,
+        )
+        .unwrap();
+        assert_eq!(cursor.cube(), GridPoint::new(1, 0, 0));
+        assert_eq!(cursor.hit().block, block);
+    }
+
     /// A [`Ray`] which will pass through the left face and then the middle Y plane of a
     /// block located at [0, 0, 0].
     ///
@@ -408,7 +492,13 @@ mod tests {
         let [block] = make_some_blocks();
         let space_ref = test_space(universe, [&block]);
 
-        let cursor = cursor_raycast(SLOPING_RAY, &space_ref, f64::INFINITY).unwrap();
+        let cursor = cursor_raycast(
+            SLOPING_RAY,
+            &space_ref,
+            f64::INFINITY,
+            CursorPolicy::default(),
+        )
+        .unwrap();
         assert_eq!(cursor.face_entered, Face7::NX);
         assert_eq!(cursor.face_selected(), Face7::NX);
     }
@@ -421,7 +511,13 @@ mod tests {
         let slab = make_slab(universe, 1, R2);
         let space_ref = test_space(universe, [&slab]);
 
-        let cursor = cursor_raycast(SLOPING_RAY, &space_ref, f64::INFINITY).unwrap();
+        let cursor = cursor_raycast(
+            SLOPING_RAY,
+            &space_ref,
+            f64::INFINITY,
+            CursorPolicy::default(),
+        )
+        .unwrap();
         dbg!(&cursor);
         assert_eq!(cursor.face_entered, Face7::NX);
         assert_eq!(cursor.face_selected(), Face7::PY);
@@ -0,0 +1,140 @@
+//! [`Health`] of characters, and the [`DamageSource`]s which reduce it.
+
+use crate::block::Block;
+use crate::math::FreeCoordinate;
+
+#[cfg(doc)]
+use crate::{block::BlockAttributes, character::Character, character::Spawn, event::GameEvent};
+
+/// Impact speed, in cubes per second, at or below which hitting an obstacle does no
+/// damage. Falling from a height of about 5 cubes under the default gravity reaches it.
+const SAFE_IMPACT_SPEED: FreeCoordinate = 14.0;
+
+/// Damage dealt per cube per second of impact speed above [`SAFE_IMPACT_SPEED`].
+const IMPACT_DAMAGE_PER_SPEED: f32 = 1.0;
+
+/// How much more damage a [`Character`] can take before it dies.
+///
+/// Characters which have no [`Health`] cannot be damaged; whether a character has health
+/// is determined by the [`Spawn`] it was created from ([`Spawn::set_health()`]), so that
+/// universes intended purely for building can leave it disabled.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Health {
+    current: f32,
+    maximum: f32,
+}
+
+impl Health {
+    /// Constructs full [`Health`] with the given maximum.
+    ///
+    /// Non-finite or negative maxima are replaced with zero.
+    pub fn new(maximum: f32) -> Self {
+        let maximum = if maximum.is_finite() {
+            maximum.max(0.0)
+        } else {
+            0.0
+        };
+        Self {
+            current: maximum,
+            maximum,
+        }
+    }
+
+    /// Returns the amount of damage that may be taken before dying.
+    pub fn current(&self) -> f32 {
+        self.current
+    }
+
+    /// Returns the amount of health the character has when undamaged.
+    pub fn maximum(&self) -> f32 {
+        self.maximum
+    }
+
+    /// Returns the current health as a fraction of the maximum, from 0 to 1.
+    pub fn fraction(&self) -> f32 {
+        if self.maximum > 0.0 {
+            self.current / self.maximum
+        } else {
+            0.0
+        }
+    }
+
+    /// Returns whether the health has been reduced to zero.
+    pub fn is_dead(&self) -> bool {
+        self.current <= 0.0
+    }
+
+    /// Reduces the current health by `amount`, but not below zero.
+    pub(crate) fn damage(&mut self, amount: f32) {
+        if amount > 0.0 {
+            self.current = (self.current - amount).max(0.0);
+        }
+    }
+
+    /// Restores the current health to the maximum.
+    pub(crate) fn restore(&mut self) {
+        self.current = self.maximum;
+    }
+
+    /// Constructs [`Health`] with the given current value, as when loading a saved game.
+    pub(crate) fn from_parts(current: f32, maximum: f32) -> Self {
+        let mut health = Self::new(maximum);
+        health.current = if current.is_finite() {
+            current.clamp(0.0, health.maximum)
+        } else {
+            health.maximum
+        };
+        health
+    }
+}
+
+/// Something which damaged a [`Character`], as reported in [`GameEvent::Died`].
+#[derive(Clone, Debug, PartialEq)]
+#[non_exhaustive]
+pub enum DamageSource {
+    /// Hitting the ground, or another obstacle, too fast.
+    Impact,
+    /// Touching a block whose [`BlockAttributes::contact_damage`] is nonzero.
+    Block(Block),
+}
+
+/// Returns the damage dealt by an impact which changed a body's velocity by `speed`
+/// cubes per second.
+pub(crate) fn impact_damage(speed: FreeCoordinate) -> f32 {
+    ((speed - SAFE_IMPACT_SPEED).max(0.0) as f32) * IMPACT_DAMAGE_PER_SPEED
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn damage_and_restore() {
+        let mut health = Health::new(10.0);
+        assert_eq!(health.fraction(), 1.0);
+        health.damage(4.0);
+        assert_eq!(health.current(), 6.0);
+        assert!(!health.is_dead());
+        health.damage(-4.0);
+        assert_eq!(health.current(), 6.0);
+        health.damage(100.0);
+        assert_eq!(health.current(), 0.0);
+        assert!(health.is_dead());
+        health.restore();
+        assert_eq!(health, Health::new(10.0));
+    }
+
+    #[test]
+    fn from_parts_clamps() {
+        assert_eq!(Health::from_parts(20.0, 10.0).current(), 10.0);
+        assert_eq!(Health::from_parts(f32::NAN, 10.0).current(), 10.0);
+        assert_eq!(Health::from_parts(3.0, f32::INFINITY), Health::new(0.0));
+    }
+
+    #[test]
+    fn impact_damage_threshold() {
+        assert_eq!(impact_damage(0.0), 0.0);
+        assert_eq!(impact_damage(SAFE_IMPACT_SPEED), 0.0);
+        assert_eq!(impact_damage(SAFE_IMPACT_SPEED + 5.0), 5.0);
+    }
+}
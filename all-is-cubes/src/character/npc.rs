@@ -0,0 +1,375 @@
+//! Characters which move by themselves: pathfinding and [`NpcController`].
+
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+
+use cgmath::{Angle as _, Deg, Vector3, Zero as _};
+use rand::{Rng as _, SeedableRng as _};
+
+use crate::behavior::{Behavior, BehaviorContext};
+use crate::block::BlockCollision;
+use crate::character::{Character, CharacterTransaction};
+use crate::math::{Face6, FreeCoordinate, GridCoordinate, GridPoint, GridVector};
+use crate::physics::{Body, BodyTransaction};
+use crate::space::Space;
+use crate::time::Tick;
+use crate::transaction::Merge as _;
+use crate::universe::{RefVisitor, URef, UniverseTransaction, VisitRefs};
+
+/// Maximum number of cubes [`NpcController`] will examine when looking for a path.
+const PATH_SEARCH_LIMIT: usize = 4096;
+
+/// Returns whether a walking character, which is assumed to be two cubes tall, can stand
+/// with its feet in `cube` of `space`.
+///
+/// That is, `cube` and the cube above it must have [`BlockCollision::None`], and the
+/// cube below it must not.
+pub fn is_walkable(space: &Space, cube: GridPoint) -> bool {
+    space.bounds().contains_cube(cube)
+        && passable(space, cube)
+        && passable(space, cube + GridVector::unit_y())
+        && !passable(space, cube - GridVector::unit_y())
+}
+
+fn passable(space: &Space, cube: GridPoint) -> bool {
+    // Out-of-bounds cubes are AIR, and hence passable.
+    space.get_evaluated(cube).attributes.collision == BlockCollision::None
+}
+
+/// Finds a shortest path by which a walking character could get from `start` to `goal`,
+/// using the A* algorithm.
+///
+/// The path consists of the cubes the character's feet occupy, starting with `start`
+/// and ending with `goal`; each step moves one cube horizontally, and optionally one
+/// cube up or down. All cubes other than `start` must be [walkable](is_walkable).
+///
+/// Returns [`None`] if there is no such path, or if none was found after examining
+/// `max_visited` cubes.
+pub fn find_path(
+    space: &Space,
+    start: GridPoint,
+    goal: GridPoint,
+    max_visited: usize,
+) -> Option<Vec<GridPoint>> {
+    fn key(cube: GridPoint) -> [GridCoordinate; 3] {
+        cube.into()
+    }
+    // Admissible heuristic: each step changes the horizontal distance by at most 1,
+    // and the vertical distance by at most 1.
+    let heuristic = |cube: GridPoint| -> u32 {
+        let d = goal - cube;
+        (d.x.unsigned_abs() + d.z.unsigned_abs()).max(d.y.unsigned_abs())
+    };
+
+    if start != goal && !is_walkable(space, goal) {
+        return None;
+    }
+
+    let mut came_from: HashMap<[GridCoordinate; 3], GridPoint> = HashMap::new();
+    let mut cost_so_far: HashMap<[GridCoordinate; 3], u32> = HashMap::new();
+    // Ordered by estimated total cost, then by the coordinates for determinism.
+    let mut open = BinaryHeap::new();
+    cost_so_far.insert(key(start), 0);
+    open.push(Reverse((heuristic(start), key(start))));
+
+    let mut visited = 0;
+    while let Some(Reverse((_, current_key))) = open.pop() {
+        let current = GridPoint::from(current_key);
+        if current == goal {
+            let mut path = vec![current];
+            let mut cube = current;
+            while let Some(&previous) = came_from.get(&key(cube)) {
+                path.push(previous);
+                cube = previous;
+            }
+            path.reverse();
+            return Some(path);
+        }
+
+        visited += 1;
+        if visited > max_visited {
+            return None;
+        }
+
+        let current_cost = cost_so_far[&current_key];
+        for face in [Face6::NX, Face6::PX, Face6::NZ, Face6::PZ] {
+            let beside = current + face.normal_vector();
+            for dy in [0, 1, -1] {
+                let neighbor = beside + GridVector::new(0, dy, 0);
+                let clear = match dy {
+                    // Need headroom to jump up.
+                    1 => passable(space, current + GridVector::new(0, 2, 0)),
+                    // Need headroom to walk over the edge.
+                    -1 => passable(space, beside + GridVector::unit_y()),
+                    _ => true,
+                };
+                if !clear || !is_walkable(space, neighbor) {
+                    continue;
+                }
+                let new_cost = current_cost + 1;
+                if cost_so_far
+                    .get(&key(neighbor))
+                    .map_or(true, |&old_cost| new_cost < old_cost)
+                {
+                    cost_so_far.insert(key(neighbor), new_cost);
+                    came_from.insert(key(neighbor), current);
+                    open.push(Reverse((new_cost + heuristic(neighbor), key(neighbor))));
+                }
+            }
+        }
+    }
+    None
+}
+
+/// Returns the cube containing the bottom center of the body, i.e. where its feet are.
+fn feet_cube(body: &Body) -> GridPoint {
+    let aab = body.collision_box_abs();
+    let center = aab.center();
+    // Nudge upward so that standing exactly on a floor counts as above it.
+    let bottom = aab.face_coordinate(Face6::NY) + 0.01;
+    GridPoint::new(
+        center.x.floor() as GridCoordinate,
+        bottom.floor() as GridCoordinate,
+        center.z.floor() as GridCoordinate,
+    )
+}
+
+/// What an [`NpcController`] should try to do.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum NpcGoal {
+    /// Stand still.
+    Idle,
+    /// Walk to randomly chosen destinations no more than `radius` cubes away
+    /// horizontally.
+    Wander {
+        /// Maximum distance along each horizontal axis to a destination.
+        radius: GridCoordinate,
+    },
+    /// Walk to wherever the given character is standing.
+    Follow(URef<Character>),
+}
+
+/// A [`Behavior`] which makes the [`Character`] it is attached to walk by itself,
+/// pursuing an [`NpcGoal`] by way of [`find_path()`].
+///
+/// It controls the character the same way a player would, by turning it and setting
+/// its velocity input, so the character is still subject to ordinary physics.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct NpcController {
+    goal: NpcGoal,
+    /// Current wandering destination.
+    destination: Option<GridPoint>,
+    /// Seed for choosing the next wandering destination.
+    seed: u64,
+}
+
+impl NpcController {
+    /// Constructs an [`NpcController`] with the given goal.
+    ///
+    /// `seed` determines the random choices it makes, such as where to wander to.
+    pub fn new(goal: NpcGoal, seed: u64) -> Self {
+        Self {
+            goal,
+            destination: None,
+            seed,
+        }
+    }
+
+    /// Returns the goal this controller is pursuing.
+    pub fn goal(&self) -> &NpcGoal {
+        &self.goal
+    }
+
+    /// Computes the transaction to move `character` one step along `path`.
+    fn walk(character: &Character, path: &[GridPoint]) -> CharacterTransaction {
+        let (feet, next) = match *path {
+            [feet, next, ..] => (feet, next),
+            _ => return CharacterTransaction::set_velocity_input(Vector3::zero()),
+        };
+
+        let position = character.body.position;
+        let direction = Vector3::new(
+            FreeCoordinate::from(next.x) + 0.5 - position.x,
+            0.0,
+            FreeCoordinate::from(next.z) + 0.5 - position.z,
+        );
+        // Same convention as Character::spawn() uses.
+        let target_yaw = Deg::atan2(direction.x, -direction.z).0;
+        let delta_yaw = (target_yaw - character.body.yaw + 180.0).rem_euclid(360.0) - 180.0;
+
+        let mut txn = CharacterTransaction::body(BodyTransaction {
+            delta_yaw,
+            ..Default::default()
+        })
+        .merge(CharacterTransaction::set_velocity_input(Vector3::new(
+            0.0, 0.0, -1.0,
+        )))
+        .unwrap();
+        if next.y > feet.y {
+            txn = txn.merge(CharacterTransaction::jump()).unwrap();
+        }
+        txn
+    }
+}
+
+impl Behavior<Character> for NpcController {
+    fn step(&self, context: &BehaviorContext<'_, Character>, _tick: Tick) -> UniverseTransaction {
+        let character = context.host;
+        let feet = feet_cube(&character.body);
+
+        let mut replacement = None;
+        let destination = match self.goal {
+            NpcGoal::Idle => None,
+            NpcGoal::Follow(ref target) => target.read().ok().map(|c| feet_cube(&c.body)),
+            NpcGoal::Wander { radius } => match self.destination {
+                Some(destination) => Some(destination),
+                None => {
+                    let mut rng = rand_xoshiro::Xoshiro256Plus::seed_from_u64(self.seed);
+                    let destination = feet
+                        + GridVector::new(
+                            rng.gen_range(-radius..=radius),
+                            0,
+                            rng.gen_range(-radius..=radius),
+                        );
+                    replacement = Some(Self {
+                        destination: Some(destination),
+                        seed: rng.gen(),
+                        ..self.clone()
+                    });
+                    Some(destination)
+                }
+            },
+        };
+
+        let path = match (destination, character.space.read()) {
+            (Some(destination), Ok(space)) => {
+                find_path(&space, feet, destination, PATH_SEARCH_LIMIT)
+            }
+            _ => None,
+        };
+
+        if matches!(self.goal, NpcGoal::Wander { .. })
+            && path.as_ref().map_or(true, |path| path.len() <= 1)
+        {
+            // Arrived or unreachable; pick a new destination next time.
+            replacement = Some(Self {
+                destination: None,
+                ..replacement.unwrap_or_else(|| self.clone())
+            });
+        }
+
+        let mut txn = context.bind_host(Self::walk(character, path.as_deref().unwrap_or(&[])));
+        if let Some(replacement) = replacement {
+            txn = txn.merge(context.replace_self(replacement)).unwrap();
+        }
+        txn
+    }
+
+    fn alive(&self, _context: &BehaviorContext<'_, Character>) -> bool {
+        true
+    }
+
+    fn ephemeral(&self) -> bool {
+        false
+    }
+}
+
+impl VisitRefs for NpcController {
+    fn visit_refs(&self, visitor: &mut dyn RefVisitor) {
+        match &self.goal {
+            NpcGoal::Idle | NpcGoal::Wander { .. } => {}
+            NpcGoal::Follow(target) => visitor.visit(target),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::content::make_some_blocks;
+    use crate::math::GridAab;
+
+    /// A floor at y = 0 with a wall in the middle that has a gap at one end.
+    fn test_space() -> Space {
+        let [floor, wall] = make_some_blocks();
+        let mut space = Space::empty(GridAab::from_lower_size([0, 0, 0], [5, 4, 5]));
+        space
+            .fill_uniform(GridAab::from_lower_size([0, 0, 0], [5, 1, 5]), &floor)
+            .unwrap();
+        space
+            .fill_uniform(GridAab::from_lower_size([2, 1, 0], [1, 3, 4]), &wall)
+            .unwrap();
+        space
+    }
+
+    #[test]
+    fn walkable() {
+        let space = test_space();
+        assert!(is_walkable(&space, GridPoint::new(0, 1, 0)));
+        assert!(!is_walkable(&space, GridPoint::new(2, 1, 0)), "inside wall");
+        assert!(!is_walkable(&space, GridPoint::new(0, 2, 0)), "in midair");
+        assert!(
+            !is_walkable(&space, GridPoint::new(0, 0, 0)),
+            "inside floor"
+        );
+    }
+
+    #[test]
+    fn path_around_wall() {
+        let space = test_space();
+        let path = find_path(
+            &space,
+            GridPoint::new(0, 1, 0),
+            GridPoint::new(4, 1, 0),
+            1000,
+        )
+        .unwrap();
+        assert_eq!(path.first(), Some(&GridPoint::new(0, 1, 0)));
+        assert_eq!(path.last(), Some(&GridPoint::new(4, 1, 0)));
+        // Must go around through the gap at z = 4.
+        assert!(path.contains(&GridPoint::new(2, 1, 4)));
+        assert_eq!(path.len(), 13);
+        for pair in path.windows(2) {
+            let step = pair[1] - pair[0];
+            assert_eq!(step.x.abs() + step.z.abs(), 1, "{path:?}");
+        }
+    }
+
+    #[test]
+    fn path_up_step() {
+        let [block] = make_some_blocks();
+        let mut space = Space::empty_positive(3, 4, 1);
+        space
+            .fill_uniform(GridAab::from_lower_size([0, 0, 0], [3, 1, 1]), &block)
+            .unwrap();
+        space.set([2, 1, 0], &block).unwrap();
+        assert_eq!(
+            find_path(
+                &space,
+                GridPoint::new(0, 1, 0),
+                GridPoint::new(2, 2, 0),
+                1000
+            ),
+            Some(vec![
+                GridPoint::new(0, 1, 0),
+                GridPoint::new(1, 1, 0),
+                GridPoint::new(2, 2, 0),
+            ])
+        );
+    }
+
+    #[test]
+    fn no_path() {
+        let space = test_space();
+        assert_eq!(
+            find_path(
+                &space,
+                GridPoint::new(0, 1, 0),
+                GridPoint::new(0, 3, 0),
+                1000
+            ),
+            None
+        );
+    }
+}
@@ -0,0 +1,169 @@
+//! [`Prediction`]: client-side prediction of a [`Character`]'s movement, reconciled with
+//! the state reported by an authoritative server.
+
+use std::collections::VecDeque;
+
+use cgmath::{InnerSpace as _, Vector3};
+
+use crate::character::Character;
+use crate::math::FreeCoordinate;
+use crate::physics::Body;
+use crate::time::{Duration, Tick};
+use crate::universe::RefError;
+
+/// Maximum number of unacknowledged inputs kept for replay; older ones are discarded, so
+/// that an unresponsive server does not cause unbounded memory use.
+const MAX_PENDING: usize = 600;
+
+/// Corrections to the character's position no larger than this are smoothed by
+/// displacing the eye, rather than shown as a sudden jump.
+const MAX_SMOOTHED_CORRECTION: FreeCoordinate = 2.0;
+
+/// The movement controls applied to a [`Character`] for one step.
+///
+/// A client using [`Prediction`] sends these to the server, which applies each of them
+/// with [`Character::apply_input()`] before stepping its copy of the character.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[allow(clippy::exhaustive_structs)]
+pub struct CharacterInput {
+    /// Number identifying this input, one greater than the previous input's, by which the
+    /// server acknowledges it in [`AuthoritativeState::last_input_sequence`].
+    pub sequence: u64,
+    /// As [`Character::set_velocity_input()`].
+    pub velocity_input: Vector3<FreeCoordinate>,
+    /// As [`Body::yaw`].
+    pub yaw: FreeCoordinate,
+    /// As [`Body::pitch`].
+    pub pitch: FreeCoordinate,
+    /// Whether to jump, as [`Character::jump_if_able()`].
+    pub jump: bool,
+    /// Duration of the step the input applies to.
+    pub delta_t: Duration,
+}
+
+/// The state of a [`Character`] as computed by the server, for reconciling with the
+/// client's prediction using [`Prediction::reconcile()`].
+#[derive(Clone, Debug, PartialEq)]
+#[allow(clippy::exhaustive_structs)]
+pub struct AuthoritativeState {
+    /// The [`CharacterInput::sequence`] of the last input the server applied before
+    /// computing this state, or [`None`] if it has applied none.
+    pub last_input_sequence: Option<u64>,
+    /// The character's body.
+    pub body: Body,
+}
+
+/// Client-side prediction of a [`Character`]'s movement in a multiplayer game.
+///
+/// The user's controls are applied to the local character immediately, rather than
+/// waiting for the server to respond, and are also recorded with
+/// [`record_input()`](Self::record_input) to be sent to the server. When the server's
+/// [`AuthoritativeState`] arrives, it describes the past, since inputs sent after it was
+/// computed have not yet been applied; [`reconcile()`](Self::reconcile) replaces the
+/// character's body with the server's and replays those inputs on top of it, so that the
+/// character stays responsive yet ends up wherever the server says.
+#[derive(Clone, Debug, Default)]
+pub struct Prediction {
+    next_sequence: u64,
+    /// Inputs applied locally and not yet acknowledged by the server, in order.
+    pending: VecDeque<CharacterInput>,
+}
+
+impl Prediction {
+    /// Constructs a [`Prediction`] with no inputs recorded.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records the controls currently set on `character` as the input for the step of
+    /// duration `tick`, and returns it to be sent to the server.
+    ///
+    /// This should be called after the controls are set, and before the character is
+    /// stepped. `jump` is whether [`Character::jump_if_able()`] was called.
+    pub fn record_input(
+        &mut self,
+        character: &Character,
+        jump: bool,
+        tick: Tick,
+    ) -> CharacterInput {
+        let input = CharacterInput {
+            sequence: self.next_sequence,
+            velocity_input: character.velocity_input,
+            yaw: character.body.yaw,
+            pitch: character.body.pitch,
+            jump,
+            delta_t: tick.delta_t(),
+        };
+        self.next_sequence += 1;
+        if self.pending.len() >= MAX_PENDING {
+            self.pending.pop_front();
+        }
+        self.pending.push_back(input);
+        input
+    }
+
+    /// Returns the number of inputs which have been recorded but not yet acknowledged.
+    pub fn pending_count(&self) -> usize {
+        self.pending.len()
+    }
+
+    /// Replaces `character`'s body with the server's `state`, then replays the inputs the
+    /// server had not yet applied, so that the character's position is the server's
+    /// plus the effect of the user's more recent input.
+    ///
+    /// The character's look direction and current controls are left unchanged, since
+    /// they are determined by the user. Small corrections to its position are smoothed.
+    ///
+    /// Returns an error, without modifying the character, if its space cannot be read.
+    pub fn reconcile(
+        &mut self,
+        character: &mut Character,
+        state: &AuthoritativeState,
+    ) -> Result<(), RefError> {
+        if let Some(acknowledged) = state.last_input_sequence {
+            while matches!(self.pending.front(), Some(input) if input.sequence <= acknowledged) {
+                self.pending.pop_front();
+            }
+        }
+
+        let space_ref = character.space.clone();
+        let space = space_ref.read()?;
+
+        let predicted_position = character.body.position;
+        let yaw = character.body.yaw;
+        let pitch = character.body.pitch;
+        let velocity_input = character.velocity_input;
+
+        character.body = state.body.clone();
+        for input in &self.pending {
+            character.apply_input(input);
+            character.apply_velocity_input(input.delta_t.as_secs_f64());
+            character.last_step_info =
+                Some(character.step_body(Tick::from_duration(input.delta_t), &space));
+        }
+
+        character.body.yaw = yaw;
+        character.body.pitch = pitch;
+        character.velocity_input = velocity_input;
+        let correction = character.body.position - predicted_position;
+        if correction.magnitude() <= MAX_SMOOTHED_CORRECTION {
+            character.eye_displacement_pos -= correction;
+        }
+        Ok(())
+    }
+}
+
+impl Character {
+    /// Sets the character's controls as specified by `input`, as the character whose
+    /// movement was recorded by a [`Prediction`] did.
+    ///
+    /// A server should call this for each input received, then step the character.
+    pub fn apply_input(&mut self, input: &CharacterInput) {
+        self.velocity_input = input.velocity_input;
+        self.body.yaw = input.yaw;
+        self.body.pitch = input.pitch;
+        if input.jump {
+            self.jump_if_able();
+        }
+    }
+}
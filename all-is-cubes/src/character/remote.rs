@@ -0,0 +1,320 @@
+//! [`RemoteCharacter`]: characters controlled elsewhere, as seen by a client.
+
+use std::collections::VecDeque;
+use std::f64::consts::TAU;
+
+use cgmath::{Basis3, Decomposed, Deg, EuclideanSpace as _, Point3, Rotation3 as _, Vector3};
+
+use crate::camera::ViewTransform;
+use crate::character::{horizontal_speed, Avatar, PosedPart, STRIDE_LENGTH, WALKING_SPEED};
+use crate::math::FreeCoordinate;
+use crate::physics::Body;
+use crate::space::Space;
+use crate::time::{Duration, Tick};
+use crate::universe::URef;
+
+/// How far behind the newest update a [`RemoteCharacter`] is shown, so that there is
+/// usually a later update to interpolate toward even if updates arrive irregularly.
+const INTERPOLATION_DELAY: Duration = Duration::from_millis(100);
+
+/// How far past the newest update a [`RemoteCharacter`]'s movement is extrapolated
+/// before it is shown as stopped, so that it does not drift away if updates cease.
+const MAX_EXTRAPOLATION: Duration = Duration::from_millis(250);
+
+/// If playback falls further than this behind the newest update (for example, after
+/// being paused), it skips ahead rather than showing stale movement.
+const MAX_LAG: Duration = Duration::from_millis(500);
+
+/// The state of a character at one moment, as sent over the network to update a
+/// [`RemoteCharacter`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[non_exhaustive]
+pub struct RemoteCharacterUpdate {
+    /// Time at which the state was observed, on the sender's clock (which need not be
+    /// related to the receiver's, as long as it increases steadily).
+    pub time: Duration,
+    /// As [`Body::position`].
+    pub position: Point3<FreeCoordinate>,
+    /// As [`Body::velocity`].
+    pub velocity: Vector3<FreeCoordinate>,
+    /// As [`Body::yaw`].
+    pub yaw: FreeCoordinate,
+    /// As [`Body::pitch`].
+    pub pitch: FreeCoordinate,
+}
+
+impl RemoteCharacterUpdate {
+    /// Captures the state of `body` as of `time`.
+    pub fn from_body(time: Duration, body: &Body) -> Self {
+        Self {
+            time,
+            position: body.position,
+            velocity: body.velocity,
+            yaw: body.yaw,
+            pitch: body.pitch,
+        }
+    }
+
+    /// Returns the state `fraction` of the way from `self` to `other`.
+    fn lerp(&self, other: &Self, fraction: FreeCoordinate) -> Self {
+        // Turn the short way around.
+        let yaw_change = (other.yaw - self.yaw + 180.0).rem_euclid(360.0) - 180.0;
+        Self {
+            time: self.time + (other.time - self.time).mul_f64(fraction),
+            position: self.position + (other.position - self.position) * fraction,
+            velocity: self.velocity + (other.velocity - self.velocity) * fraction,
+            yaw: (self.yaw + yaw_change * fraction).rem_euclid(360.0),
+            pitch: self.pitch + (other.pitch - self.pitch) * fraction,
+        }
+    }
+}
+
+/// A character whose movement is controlled elsewhere, such as another player in a
+/// multiplayer game, and is known only from [`RemoteCharacterUpdate`]s received from
+/// the network.
+///
+/// Updates arrive at a lower rate than frames are drawn, and irregularly, so a
+/// [`RemoteCharacter`] is shown slightly in the past, interpolating between the
+/// updates before and after that time; if no later update has arrived yet, its last
+/// known movement is extrapolated for a short time. Unlike a [`Character`], it is not
+/// a member of a [`Universe`] and does not collide with anything; it is only drawn.
+///
+/// [`Character`]: super::Character
+/// [`Universe`]: crate::universe::Universe
+#[derive(Clone, Debug)]
+pub struct RemoteCharacter {
+    space: URef<Space>,
+    avatar: Avatar,
+    /// Received updates in increasing order of time, excluding those too old to be
+    /// needed for interpolation.
+    updates: VecDeque<RemoteCharacterUpdate>,
+    /// Time, on the sender's clock, of the state currently shown.
+    /// [`None`] until the first update is received.
+    playback_time: Option<Duration>,
+    /// Progress through the walk animation of [`Self::avatar`], in radians.
+    walk_phase: FreeCoordinate,
+}
+
+impl RemoteCharacter {
+    /// Constructs a [`RemoteCharacter`] in `space`, which will not be visible until its
+    /// first update is received.
+    pub fn new(space: URef<Space>, avatar: Avatar) -> Self {
+        Self {
+            space,
+            avatar,
+            updates: VecDeque::new(),
+            playback_time: None,
+            walk_phase: 0.0,
+        }
+    }
+
+    /// Returns the space the character is in.
+    pub fn space(&self) -> &URef<Space> {
+        &self.space
+    }
+
+    /// Records a newly received state of the character.
+    ///
+    /// Updates which are not newer than every previously received update (that is,
+    /// which arrived out of order) are ignored.
+    pub fn receive_update(&mut self, update: RemoteCharacterUpdate) {
+        if matches!(self.updates.back(), Some(newest) if update.time <= newest.time) {
+            return;
+        }
+        self.updates.push_back(update);
+        match self.playback_time {
+            Some(t) if update.time.saturating_sub(t) <= MAX_LAG => {}
+            _ => {
+                // Start, or restart, playback from this update alone.
+                self.updates.drain(..self.updates.len() - 1);
+                self.playback_time = Some(update.time.saturating_sub(INTERPOLATION_DELAY));
+            }
+        }
+        self.discard_old_updates();
+    }
+
+    /// Advances the shown state by the duration of `tick`.
+    pub fn step(&mut self, tick: Tick) {
+        if tick.paused() {
+            return;
+        }
+        let Some(playback_time) = &mut self.playback_time else {
+            return;
+        };
+        *playback_time += tick.delta_t();
+        self.discard_old_updates();
+
+        if let Some(state) = self.state() {
+            self.walk_phase = (self.walk_phase
+                + horizontal_speed(state.velocity) * tick.delta_t().as_secs_f64() * TAU
+                    / STRIDE_LENGTH)
+                .rem_euclid(TAU);
+        }
+    }
+
+    /// Computes the view transform for this character's eye, as
+    /// [`Character::view()`](super::Character::view) does, or [`None`] if no update has
+    /// been received.
+    pub fn view(&self) -> Option<ViewTransform> {
+        let state = self.state()?;
+        Some(Decomposed {
+            scale: 1.0,
+            rot: Basis3::from_angle_y(Deg(-state.yaw)) * Basis3::from_angle_x(Deg(-state.pitch)),
+            disp: state.position.to_vec(),
+        })
+    }
+
+    /// Computes the placement of the parts of the character's [`Avatar`] as of the
+    /// current playback time. This is empty if no update has been received.
+    pub fn posed_avatar(&self) -> Vec<PosedPart> {
+        match self.state() {
+            Some(state) => self.avatar.pose(
+                state.position,
+                state.yaw,
+                self.walk_phase,
+                (horizontal_speed(state.velocity) / WALKING_SPEED).min(1.0),
+            ),
+            None => Vec::new(),
+        }
+    }
+
+    /// Returns the interpolated or extrapolated state as of the playback time.
+    fn state(&self) -> Option<RemoteCharacterUpdate> {
+        let t = self.playback_time?;
+        let first = self.updates.front()?;
+        if t <= first.time {
+            return Some(*first);
+        }
+        match self.updates.iter().find(|update| update.time > t) {
+            Some(next) => {
+                // discard_old_updates() ensures that the first update is the latest one
+                // at or before the playback time.
+                let fraction =
+                    (t - first.time).as_secs_f64() / (next.time - first.time).as_secs_f64();
+                Some(first.lerp(next, fraction))
+            }
+            None => {
+                let newest = self.updates.back()?;
+                let elapsed = t - newest.time;
+                if elapsed > MAX_EXTRAPOLATION {
+                    Some(RemoteCharacterUpdate {
+                        time: t,
+                        position: newest.position
+                            + newest.velocity * MAX_EXTRAPOLATION.as_secs_f64(),
+                        velocity: Vector3::new(0.0, 0.0, 0.0),
+                        ..*newest
+                    })
+                } else {
+                    Some(RemoteCharacterUpdate {
+                        time: t,
+                        position: newest.position + newest.velocity * elapsed.as_secs_f64(),
+                        ..*newest
+                    })
+                }
+            }
+        }
+    }
+
+    /// Discards updates that precede the latest update at or before the playback time,
+    /// since they are no longer needed for interpolation.
+    fn discard_old_updates(&mut self) {
+        let Some(t) = self.playback_time else {
+            return;
+        };
+        while self.updates.len() >= 2 && self.updates[1].time <= t {
+            self.updates.pop_front();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math::Aab;
+    use crate::universe::Universe;
+
+    fn update(millis: u64, x: FreeCoordinate, velocity_x: FreeCoordinate) -> RemoteCharacterUpdate {
+        let mut body = Body::new_minimal([x, 0., 0.], Aab::ZERO);
+        body.velocity = Vector3::new(velocity_x, 0., 0.);
+        RemoteCharacterUpdate::from_body(Duration::from_millis(millis), &body)
+    }
+
+    fn position(remote: &RemoteCharacter) -> Point3<FreeCoordinate> {
+        Point3::from_vec(remote.view().unwrap().disp)
+    }
+
+    fn tick_millis(millis: u64) -> Tick {
+        Tick::from_duration(Duration::from_millis(millis))
+    }
+
+    fn new_remote() -> RemoteCharacter {
+        let mut universe = Universe::new();
+        let space = universe.insert_anonymous(Space::empty_positive(1, 1, 1));
+        RemoteCharacter::new(space, Avatar::default())
+    }
+
+    #[test]
+    fn invisible_until_updated() {
+        let remote = new_remote();
+        assert!(remote.view().is_none());
+        assert_eq!(remote.posed_avatar(), vec![]);
+    }
+
+    #[test]
+    fn interpolates_between_updates() {
+        let mut remote = new_remote();
+        remote.receive_update(update(1000, 0., 10.));
+        remote.receive_update(update(1100, 1., 10.));
+        // Playback starts INTERPOLATION_DELAY behind the first update.
+        assert_eq!(position(&remote), Point3::new(0., 0., 0.));
+        remote.step(tick_millis(150));
+        let x = position(&remote).x;
+        assert!((x - 0.5).abs() < 1e-9, "{x}");
+    }
+
+    #[test]
+    fn extrapolates_then_stops() {
+        let mut remote = new_remote();
+        remote.receive_update(update(1000, 0., 10.));
+        remote.step(tick_millis(150));
+        let x = position(&remote).x;
+        assert!((x - 0.5).abs() < 1e-9, "{x}");
+
+        remote.step(tick_millis(1000));
+        let x = position(&remote).x;
+        assert!((x - 2.5).abs() < 1e-9, "{x}");
+    }
+
+    #[test]
+    fn ignores_out_of_order_updates() {
+        let mut remote = new_remote();
+        remote.receive_update(update(1000, 0., 0.));
+        remote.receive_update(update(900, 5., 0.));
+        remote.step(tick_millis(200));
+        assert_eq!(position(&remote), Point3::new(0., 0., 0.));
+    }
+
+    #[test]
+    fn skips_ahead_when_lagging() {
+        let mut remote = new_remote();
+        remote.receive_update(update(1000, 0., 0.));
+        remote.receive_update(update(3000, 1., 0.));
+        remote.receive_update(update(3100, 2., 0.));
+        // Playback skipped to shortly before the second update, without interpolating
+        // from the first.
+        assert_eq!(position(&remote), Point3::new(1., 0., 0.));
+    }
+
+    #[test]
+    fn yaw_turns_the_short_way() {
+        let a = RemoteCharacterUpdate {
+            yaw: 350.,
+            ..update(0, 0., 0.)
+        };
+        let b = RemoteCharacterUpdate {
+            yaw: 10.,
+            ..update(100, 0., 0.)
+        };
+        assert!((a.lerp(&b, 0.75).yaw - 5.).abs() < 1e-9);
+    }
+}
@@ -27,6 +27,11 @@ pub struct Spawn {
 
     /// Initial inventory contents, created from nothing.
     pub(super) inventory: Vec<Slot>,
+
+    /// Maximum [`Health`] of the character, or [`None`] if it cannot be damaged.
+    ///
+    /// [`Health`]: super::Health
+    pub(super) health: Option<NotNan<f32>>,
 }
 
 impl Spawn {
@@ -42,6 +47,7 @@ impl Spawn {
             eye_position: None,
             look_direction: Vector3::new(NotNan::from(0), NotNan::from(0), NotNan::from(-1)),
             inventory: vec![],
+            health: None,
         }
     }
 
@@ -97,6 +103,16 @@ impl Spawn {
     pub fn set_inventory(&mut self, inventory: Vec<Slot>) {
         self.inventory = inventory;
     }
+
+    /// Sets the maximum [`Health`] of the character, or [`None`] (the default) if it
+    /// should not be able to take damage at all.
+    ///
+    /// A NaN maximum is treated as [`None`].
+    ///
+    /// [`Health`]: super::Health
+    pub fn set_health(&mut self, maximum: Option<f32>) {
+        self.health = maximum.and_then(|m| NotNan::new(m).ok());
+    }
 }
 
 fn notnan_or_zero(value: FreeCoordinate) -> NotNan<FreeCoordinate> {
@@ -110,6 +126,7 @@ impl VisitRefs for Spawn {
             bounds: _,
             eye_position: _,
             look_direction: _,
+            health: _,
         } = self;
         inventory.visit_refs(visitor);
     }
@@ -128,17 +145,17 @@ impl<'a> arbitrary::Arbitrary<'a> for Spawn {
             },
             look_direction: Vector3::new(u.arbitrary()?, u.arbitrary()?, u.arbitrary()?),
             inventory: vec![], // TODO: need impl Arbitrary for Tool
+            health: u.arbitrary()?,
         })
     }
 
     fn size_hint(depth: usize) -> (usize, Option<usize>) {
-        use arbitrary::{
-            size_hint::{and, and_all},
-            Arbitrary,
-        };
-        and(
-            and(GridAab::size_hint(depth), bool::size_hint(depth)),
+        use arbitrary::{size_hint::and_all, Arbitrary};
+        and_all(&[
+            GridAab::size_hint(depth),
+            bool::size_hint(depth),
             and_all(&[<f64 as Arbitrary>::size_hint(depth); 6]),
-        )
+            <Option<NotNan<f32>> as Arbitrary>::size_hint(depth),
+        ])
     }
 }
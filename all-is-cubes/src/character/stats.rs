@@ -0,0 +1,272 @@
+//! [`PlayerStats`]: statistics about a character's play.
+
+use std::collections::HashMap;
+use std::fmt;
+
+use cgmath::{MetricSpace as _, Point3};
+
+use crate::block::Block;
+use crate::character::Character;
+use crate::event::GameEvent;
+use crate::math::FreeCoordinate;
+use crate::time::{Duration, Tick};
+use crate::universe::{RefVisitor, URef, VisitRefs};
+
+/// Movements of a character farther than this in one step are assumed to be teleports,
+/// and do not count towards [`PlayerStats::distance_traveled()`].
+const MAX_STEP_DISTANCE: FreeCoordinate = 10.0;
+
+/// Statistics about the play of a [`Character`], stored in the same
+/// [`Universe`](crate::universe::Universe) and saved with it.
+///
+/// The statistics are updated automatically: block counts from the
+/// [`GameEvent`]s that transactions emit on behalf of the character, and the others
+/// each time the universe is stepped.
+#[derive(Clone)]
+pub struct PlayerStats {
+    pub(crate) character: URef<Character>,
+    pub(crate) blocks_placed: HashMap<Block, u64>,
+    pub(crate) blocks_removed: HashMap<Block, u64>,
+    pub(crate) distance_traveled: FreeCoordinate,
+    pub(crate) time_played: Duration,
+    /// Position of the character as of the last step, for measuring distance.
+    /// Not persisted.
+    pub(crate) last_position: Option<Point3<FreeCoordinate>>,
+}
+
+impl PlayerStats {
+    /// Constructs a [`PlayerStats`] for the given character, with all counts zero.
+    pub fn new(character: URef<Character>) -> Self {
+        Self {
+            character,
+            blocks_placed: HashMap::new(),
+            blocks_removed: HashMap::new(),
+            distance_traveled: 0.0,
+            time_played: Duration::ZERO,
+            last_position: None,
+        }
+    }
+
+    /// Returns the character these statistics are about.
+    pub fn character(&self) -> &URef<Character> {
+        &self.character
+    }
+
+    /// Returns the number of times the character has placed the given block.
+    pub fn blocks_placed(&self, block: &Block) -> u64 {
+        self.blocks_placed.get(block).copied().unwrap_or(0)
+    }
+
+    /// Returns the number of times the character has removed the given block.
+    pub fn blocks_removed(&self, block: &Block) -> u64 {
+        self.blocks_removed.get(block).copied().unwrap_or(0)
+    }
+
+    /// Returns each block the character has placed and how many times, in no
+    /// particular order.
+    pub fn iter_blocks_placed(&self) -> impl Iterator<Item = (&Block, u64)> + '_ {
+        self.blocks_placed
+            .iter()
+            .map(|(block, &count)| (block, count))
+    }
+
+    /// Returns each block the character has removed and how many times, in no
+    /// particular order.
+    pub fn iter_blocks_removed(&self) -> impl Iterator<Item = (&Block, u64)> + '_ {
+        self.blocks_removed
+            .iter()
+            .map(|(block, &count)| (block, count))
+    }
+
+    /// Returns the total number of blocks the character has placed.
+    pub fn total_blocks_placed(&self) -> u64 {
+        self.blocks_placed.values().sum()
+    }
+
+    /// Returns the total number of blocks the character has removed.
+    pub fn total_blocks_removed(&self) -> u64 {
+        self.blocks_removed.values().sum()
+    }
+
+    /// Returns the distance the character has moved, not counting teleportation.
+    pub fn distance_traveled(&self) -> FreeCoordinate {
+        self.distance_traveled
+    }
+
+    /// Returns how much game time has passed, while not paused, since these statistics
+    /// were created.
+    pub fn time_played(&self) -> Duration {
+        self.time_played
+    }
+
+    /// Updates the counts affected by `event`, if it is an action of this character.
+    pub(crate) fn record_event(&mut self, event: &GameEvent) {
+        match event {
+            GameEvent::BlockPlaced {
+                block,
+                character: Some(character),
+                ..
+            } if *character == self.character => {
+                *self.blocks_placed.entry(block.clone()).or_insert(0) += 1;
+            }
+            GameEvent::BlockBroken {
+                block,
+                character: Some(character),
+                ..
+            } if *character == self.character => {
+                *self.blocks_removed.entry(block.clone()).or_insert(0) += 1;
+            }
+            _ => {}
+        }
+    }
+
+    /// Updates the time played and distance traveled.
+    pub(crate) fn step(&mut self, tick: Tick) {
+        if tick.paused() {
+            return;
+        }
+        self.time_played += tick.delta_t();
+
+        let Ok(character) = self.character.read() else {
+            return;
+        };
+        let position = character.body.position;
+        if let Some(last_position) = self.last_position {
+            let distance = position.distance(last_position);
+            if distance <= MAX_STEP_DISTANCE {
+                self.distance_traveled += distance;
+            }
+        }
+        self.last_position = Some(position);
+    }
+}
+
+impl fmt::Debug for PlayerStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // Not showing the per-block counts, since blocks are verbose.
+        f.debug_struct("PlayerStats")
+            .field("character", &self.character)
+            .field("total_blocks_placed", &self.total_blocks_placed())
+            .field("total_blocks_removed", &self.total_blocks_removed())
+            .field("distance_traveled", &self.distance_traveled)
+            .field("time_played", &self.time_played)
+            .finish_non_exhaustive()
+    }
+}
+
+impl VisitRefs for PlayerStats {
+    fn visit_refs(&self, visitor: &mut dyn RefVisitor) {
+        let Self {
+            character,
+            blocks_placed,
+            blocks_removed,
+            distance_traveled: _,
+            time_played: _,
+            last_position: _,
+        } = self;
+        visitor.visit(character);
+        for block in blocks_placed.keys().chain(blocks_removed.keys()) {
+            block.visit_refs(visitor);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::block::AIR;
+    use crate::character::{cursor_raycast, CursorPolicy};
+    use crate::content::make_some_blocks;
+    use crate::inv::{Tool, ToolError, ToolInput};
+    use crate::math::GridPoint;
+    use crate::raycast::Ray;
+    use crate::space::Space;
+    use crate::time::practically_infinite_deadline;
+    use crate::transaction::{self, Transaction as _};
+    use crate::universe::{Universe, UniverseTransaction};
+
+    #[test]
+    fn counts_events_of_own_character() {
+        let [block] = make_some_blocks();
+        let mut universe = Universe::new();
+        let space = universe.insert_anonymous(Space::empty_positive(1, 1, 1));
+        let character = universe.insert_anonymous(Character::spawn_default(space.clone()));
+        let other = universe.insert_anonymous(Character::spawn_default(space.clone()));
+        let stats = universe.insert_anonymous(PlayerStats::new(character.clone()));
+
+        let event = |character: &URef<Character>| GameEvent::BlockPlaced {
+            space: space.clone(),
+            cube: GridPoint::new(0, 0, 0),
+            block: block.clone(),
+            character: Some(character.clone()),
+        };
+        for event in [event(&character), event(&character), event(&other)] {
+            UniverseTransaction::event(event)
+                .execute(&mut universe, &mut transaction::no_outputs)
+                .unwrap();
+        }
+
+        let stats = stats.read().unwrap();
+        assert_eq!(stats.blocks_placed(&block), 2);
+        assert_eq!(stats.blocks_placed(&AIR), 0);
+        assert_eq!(stats.total_blocks_placed(), 2);
+        assert_eq!(stats.total_blocks_removed(), 0);
+    }
+
+    #[test]
+    fn counts_tool_use() -> Result<(), ToolError> {
+        let [block] = make_some_blocks();
+        let mut universe = Universe::new();
+        let mut space = Space::empty_positive(3, 1, 1);
+        space.set([0, 0, 0], &block).unwrap();
+        let space = universe.insert_anonymous(space);
+        let character = universe.insert_anonymous(Character::spawn_default(space.clone()));
+        let stats = universe.insert_anonymous(PlayerStats::new(character.clone()));
+
+        let cursor = cursor_raycast(
+            Ray::new([0.5, 0.5, -10.], [0., 0., 1.]),
+            &space,
+            FreeCoordinate::INFINITY,
+            CursorPolicy::default(),
+        )
+        .unwrap();
+        let (_, transaction) = Tool::RemoveBlock { keep: false }.use_tool(&ToolInput {
+            cursor: Some(cursor),
+            character: Some(character),
+        })?;
+        transaction
+            .execute(&mut universe, &mut transaction::no_outputs)
+            .unwrap();
+
+        assert_eq!(stats.read().unwrap().blocks_removed(&block), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn time_and_distance() {
+        let mut universe = Universe::new();
+        let space = universe.insert_anonymous(Space::empty_positive(1, 1, 1));
+        let mut character = Character::spawn_default(space);
+        character.body.flying = true;
+        character.body.position = Point3::new(0., 0., 0.);
+        let character = universe.insert_anonymous(character);
+        let stats = universe.insert_anonymous(PlayerStats::new(character.clone()));
+
+        let tick = Tick::from_seconds(0.5);
+        universe.step(tick, practically_infinite_deadline());
+        character
+            .try_modify(|c| c.body.position = Point3::new(3., 4., 0.))
+            .unwrap();
+        universe.step(tick, practically_infinite_deadline());
+        // A teleport does not count.
+        character
+            .try_modify(|c| c.body.position = Point3::new(100., 4., 0.))
+            .unwrap();
+        universe.step(tick.pause(), practically_infinite_deadline());
+        universe.step(tick, practically_infinite_deadline());
+
+        let stats = stats.read().unwrap();
+        assert_eq!(stats.time_played(), Duration::from_secs(1) + tick.delta_t());
+        assert_eq!(stats.distance_traveled(), 5.0);
+    }
+}
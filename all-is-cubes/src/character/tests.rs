@@ -1,17 +1,21 @@
 use std::sync::Arc;
 
-use cgmath::{Angle as _, Deg, Point3, Vector3};
+use cgmath::{Angle as _, Deg, InnerSpace as _, Point3, Vector3};
 
-use crate::block::{Block, AIR};
-use crate::character::{cursor_raycast, Character, CharacterChange, CharacterTransaction, Spawn};
+use crate::block::{Block, BlockCollision, AIR};
+use crate::character::{
+    cursor_raycast, AuthoritativeState, Character, CharacterChange, CharacterTransaction,
+    CursorPolicy, DamageSource, MotionError, Prediction, Spawn,
+};
+use crate::event::GameEvent;
 use crate::inv::{InventoryChange, InventoryTransaction, Slot, Tool, ToolError};
 use crate::listen::{Listen as _, Sink};
-use crate::math::{Aab, Face6, GridAab, Rgb};
+use crate::math::{Aab, Face6, GridAab, Rgb, Rgba};
 use crate::physics::BodyTransaction;
 use crate::raycast::Ray;
 use crate::space::Space;
-use crate::time::Tick;
-use crate::transaction::{self, Transaction as _, TransactionTester};
+use crate::time::{practically_infinite_deadline, Duration, Tick};
+use crate::transaction::{self, Merge as _, Transaction as _, TransactionTester};
 use crate::universe::Universe;
 
 fn test_spawn(f: impl Fn(&mut Space) -> Spawn) -> Character {
@@ -22,6 +26,45 @@ fn test_spawn(f: impl Fn(&mut Space) -> Spawn) -> Character {
     Character::spawn(&spawn, space)
 }
 
+/// A transfer between characters' inventories happens entirely or not at all.
+#[test]
+fn inventory_transfer_between_characters() {
+    let mut universe = Universe::new();
+    let space = universe.insert_anonymous(Space::empty_positive(1, 1, 1));
+    let source = universe
+        .insert("source".into(), Character::spawn_default(space.clone()))
+        .unwrap();
+    let destination = universe
+        .insert("destination".into(), Character::spawn_default(space))
+        .unwrap();
+    let item = Tool::Block(Block::from(Rgba::WHITE));
+    CharacterTransaction::inventory(InventoryTransaction::insert([Slot::stack(3, item.clone())]))
+        .bind(source.clone())
+        .execute(&mut universe, &mut drop)
+        .unwrap();
+    let transfer = |count: u16| {
+        let (take, give) = InventoryTransaction::transfer(item.clone(), count);
+        CharacterTransaction::inventory(take)
+            .bind(source.clone())
+            .merge(CharacterTransaction::inventory(give).bind(destination.clone()))
+            .unwrap()
+    };
+    let counts = || {
+        (
+            source.read().unwrap().inventory().count_of(&item),
+            destination.read().unwrap().inventory().count_of(&item),
+        )
+    };
+
+    transfer(5)
+        .execute(&mut universe, &mut drop)
+        .expect_err("should have failed");
+    assert_eq!(counts(), (3, 0));
+
+    transfer(2).execute(&mut universe, &mut drop).unwrap();
+    assert_eq!(counts(), (1, 2));
+}
+
 #[test]
 fn spawn_inferred_position() {
     let bounds = GridAab::from_lower_size([0, 17, 0], [3, 3, 3]);
@@ -122,9 +165,31 @@ fn transaction_systematic() {
             |_, _| Ok(()),
         )
         .transaction(
-            CharacterTransaction::body(BodyTransaction { delta_yaw: 1.0 }),
+            CharacterTransaction::body(BodyTransaction {
+                delta_yaw: 1.0,
+                ..Default::default()
+            }),
             |_, _| Ok(()),
         )
+        // Velocity input transactions; these conflict with each other
+        .transaction(
+            CharacterTransaction::set_velocity_input(Vector3::new(1.0, 0.0, 0.0)),
+            |_, after| {
+                if after.velocity_input != Vector3::new(1.0, 0.0, 0.0) {
+                    return Err("did not set velocity input".into());
+                }
+                Ok(())
+            },
+        )
+        .transaction(
+            CharacterTransaction::set_velocity_input(Vector3::new(0.0, 0.0, -1.0)),
+            |_, after| {
+                if after.velocity_input != Vector3::new(0.0, 0.0, -1.0) {
+                    return Err("did not set velocity input".into());
+                }
+                Ok(())
+            },
+        )
         // Inventory transactions
         // Note: Inventory transactions are tested separately from inventory.rs; these are just
         // for checking the integration with Character.
@@ -199,6 +264,207 @@ fn no_superjumping() {
     assert_eq!(character.body.velocity, velocity);
 }
 
+/// Fall damage is taken only by characters with health.
+#[test]
+fn impact_damage() {
+    let mut universe = Universe::new();
+    let space = universe.insert_anonymous({
+        let mut space = Space::empty_positive(1, 1, 1);
+        space.set([0, 0, 0], Block::from(Rgb::ONE)).unwrap();
+        space
+    });
+    let fall = |health: Option<f32>| {
+        let mut spawn = space.read().unwrap().spawn().clone();
+        spawn.set_health(health);
+        let mut character = Character::spawn(&spawn, space.clone());
+        character.body.position = Point3::new(
+            0.5,
+            character.body.collision_box.face_coordinate(Face6::NY) + 1.1,
+            0.5,
+        );
+        character.body.velocity = Vector3::new(0., -20., 0.);
+        let _ = character.step(None, Tick::from_seconds(1.0 / 60.0));
+        assert_eq!(character.body.velocity.y, 0.0, "should have landed");
+        character.health()
+    };
+
+    assert_eq!(fall(None), None);
+    // Landing at 20 cubes/s deals 6 damage.
+    assert_eq!(fall(Some(10.0)).map(|h| h.current()), Some(4.0));
+}
+
+#[test]
+fn contact_damage_kills_and_respawns() {
+    let hazard = Block::builder()
+        .color(Rgba::new(1.0, 0.5, 0.0, 0.5))
+        .collision(BlockCollision::None)
+        .contact_damage(1000.0)
+        .build();
+    let mut universe = Universe::new();
+    let space = universe.insert_anonymous({
+        let mut space = Space::empty_positive(10, 10, 10);
+        space
+            .fill_uniform(GridAab::from_lower_size([0, 0, 0], [2, 10, 10]), &hazard)
+            .unwrap();
+        let mut spawn = space.spawn().clone();
+        spawn.set_eye_position([5.5, 5.5, 5.5]);
+        spawn.set_health(Some(10.0));
+        space.set_spawn(spawn);
+        space
+    });
+    let character = universe.insert_anonymous(Character::spawn_default(space));
+    let sink = Sink::new();
+    universe.listen(sink.listener());
+
+    // Stepping at the spawn point does no damage.
+    universe.step(Tick::from_seconds(0.1), practically_infinite_deadline());
+    assert_eq!(character.read().unwrap().health().unwrap().fraction(), 1.0);
+    assert_eq!(sink.drain(), vec![]);
+
+    CharacterTransaction::body(BodyTransaction::teleport([1.0, 5.5, 5.5]))
+        .bind(character.clone())
+        .execute(&mut universe, &mut transaction::no_outputs)
+        .unwrap();
+    universe.step(Tick::from_seconds(0.1), practically_infinite_deadline());
+    assert_eq!(
+        sink.drain(),
+        vec![
+            GameEvent::Died {
+                character: character.clone(),
+                cause: DamageSource::Block(hazard),
+            },
+            GameEvent::Respawned {
+                character: character.clone(),
+            },
+        ]
+    );
+    let character = character.read().unwrap();
+    assert_eq!(character.body.position, Point3::new(5.5, 5.5, 5.5));
+    assert_eq!(character.health().unwrap().fraction(), 1.0);
+}
+
+#[test]
+fn view_interpolated() {
+    let mut character = test_spawn(|space| space.spawn().clone());
+    let before = character.view();
+    assert_eq!(character.view_interpolated(0.5), before, "no step yet");
+
+    // Fall under gravity, so that the position changes.
+    let _ = character.step(None, Tick::from_seconds(0.5));
+    let after = character.view();
+    assert_ne!(before.disp, after.disp);
+
+    assert_eq!(character.view_interpolated(0.0), before);
+    assert_eq!(character.view_interpolated(1.0), after);
+    assert_eq!(
+        character.view_interpolated(0.5).disp,
+        (before.disp + after.disp) / 2.0
+    );
+    assert_eq!(character.view_interpolated(f64::NAN), after);
+}
+
+#[test]
+fn check_reported_motion() {
+    let mut universe = Universe::new();
+    let mut space = Space::empty(GridAab::from_lower_size([0, 0, 0], [10, 3, 1]));
+    // A wall at x = 5.
+    space
+        .fill_uniform(
+            GridAab::from_lower_size([5, 0, 0], [1, 3, 1]),
+            Block::from(Rgb::ONE),
+        )
+        .unwrap();
+    let mut spawn = Spawn::default_for_new_space(space.bounds());
+    spawn.set_eye_position([2.5, 2.0, 0.5]);
+    let space = universe.insert_anonymous(space);
+    let mut character = Character::spawn(&spawn, space);
+    let second = Duration::from_secs(1);
+
+    assert_eq!(
+        character.check_reported_motion(Point3::new(3.5, 2.0, 0.5), second),
+        Ok(())
+    );
+    assert!(matches!(
+        character.check_reported_motion(Point3::new(4.0, 2.0, 0.5), Duration::from_millis(10)),
+        Err(MotionError::TooFast { .. })
+    ));
+    assert!(matches!(
+        character.check_reported_motion(Point3::new(2.5, 22.0, 0.5), second),
+        Err(MotionError::TooFast { .. })
+    ));
+    assert!(matches!(
+        character.check_reported_motion(Point3::new(6.5, 2.0, 0.5), second),
+        Err(MotionError::Obstructed(_))
+    ));
+
+    character.body.noclip = true;
+    assert_eq!(
+        character.check_reported_motion(Point3::new(6.5, 2.0, 0.5), second),
+        Ok(())
+    );
+}
+
+#[test]
+fn prediction_reconcile() {
+    let mut universe = Universe::new();
+    let mut space = Space::empty(GridAab::from_lower_size([0, 0, 0], [20, 4, 3]));
+    space
+        .fill_uniform(
+            GridAab::from_lower_size([0, 0, 0], [20, 1, 3]),
+            Block::from(Rgb::ONE),
+        )
+        .unwrap();
+    let mut spawn = Spawn::default_for_new_space(space.bounds());
+    spawn.set_eye_position([2.5, 2.8, 1.5]);
+    let space = universe.insert_anonymous(space);
+    let mut character = Character::spawn(&spawn, space);
+    let initial_body = character.body.clone();
+    let tick = Tick::from_seconds(1.0 / 60.0);
+
+    let mut prediction = Prediction::new();
+    let mut inputs = Vec::new();
+    for _ in 0..10 {
+        character.set_velocity_input(Vector3::new(1., 0., 0.));
+        inputs.push(prediction.record_input(&character, false, tick));
+        let _ = character.step(None, tick);
+    }
+    assert_eq!(inputs[3].sequence, 3);
+    assert_eq!(prediction.pending_count(), 10);
+    let predicted_position = character.body.position;
+    assert!(predicted_position.x > 2.6, "{predicted_position:?}");
+
+    // If the server has applied none of the inputs, replaying all of them reproduces
+    // the prediction.
+    prediction
+        .reconcile(
+            &mut character,
+            &AuthoritativeState {
+                last_input_sequence: None,
+                body: initial_body.clone(),
+            },
+        )
+        .unwrap();
+    assert!((character.body.position - predicted_position).magnitude() < 1e-9);
+    assert_eq!(prediction.pending_count(), 10);
+
+    // If the server put the character elsewhere, the unacknowledged inputs are applied
+    // starting from there.
+    let mut server_body = initial_body.clone();
+    server_body.position.z += 0.25;
+    prediction
+        .reconcile(
+            &mut character,
+            &AuthoritativeState {
+                last_input_sequence: Some(4),
+                body: server_body,
+            },
+        )
+        .unwrap();
+    assert_eq!(prediction.pending_count(), 5);
+    assert_eq!(character.body.position.z, initial_body.position.z + 0.25);
+    assert!(character.body.position.x < predicted_position.x);
+}
+
 #[test]
 fn click_wrong_space_or_correct_space() {
     let mut universe = Universe::new();
@@ -216,13 +482,23 @@ fn click_wrong_space_or_correct_space() {
     let character = universe.insert_anonymous(Character::spawn_default(sp2.clone()));
 
     // Click in wrong space
-    let cursor = cursor_raycast(Ray::new([0.5, 0.5, 0.5], [1., 0., 0.]), &sp1, 10.);
+    let cursor = cursor_raycast(
+        Ray::new([0.5, 0.5, 0.5], [1., 0., 0.]),
+        &sp1,
+        10.,
+        CursorPolicy::default(),
+    );
     assert!(cursor.is_some());
     let error = Character::click(character.clone(), cursor.as_ref(), 0).unwrap_err();
     assert!(matches!(error, ToolError::Internal(_)));
 
     // Click in right space
-    let cursor = cursor_raycast(Ray::new([0.5, 0.5, 0.5], [1., 0., 0.]), &sp2, 10.);
+    let cursor = cursor_raycast(
+        Ray::new([0.5, 0.5, 0.5], [1., 0., 0.]),
+        &sp2,
+        10.,
+        CursorPolicy::default(),
+    );
     assert!(cursor.is_some());
     let error = Character::click(character, cursor.as_ref(), 0).unwrap_err();
     assert!(matches!(error, ToolError::NoTool));
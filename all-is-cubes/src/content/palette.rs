@@ -104,6 +104,14 @@ palette! {
     PLANK = srgb[0xE8 0xCC 0x95];
 }
 
+palette! {
+    // Weather
+    /// Raindrops drawn for [`Precipitation::Rain`](crate::space::Precipitation::Rain).
+    RAIN = srgb[0xA8 0xB8 0xD0 0xFF];
+    /// Snowflakes drawn for [`Precipitation::Snow`](crate::space::Precipitation::Snow).
+    SNOW = srgb[0xFA 0xFA 0xFF 0xFF];
+}
+
 palette! {
     // All is Cubes logo components
     // TODO: Decide what we want *actual* logo(type) colors to be.
@@ -111,9 +119,21 @@ palette! {
     LOGO_STROKE = srgb[0x33 0x33 0x33];
 }
 
+palette! {
+    // Characters
+    /// Head of the default [`Avatar`](crate::character::Avatar).
+    CHARACTER_HEAD = srgb[0xE0 0xAC 0x88 0xFF];
+    /// Torso and arms of the default [`Avatar`](crate::character::Avatar).
+    CHARACTER_BODY = srgb[0x3D 0x6E 0xB4 0xFF];
+    /// Legs of the default [`Avatar`](crate::character::Avatar).
+    CHARACTER_LEGS = srgb[0x4A 0x40 0x38 0xFF];
+}
+
 palette! {
     // UI elements
     CURSOR_OUTLINE = srgb[0x00 0x00 0x00 0xFF];
+    /// Outline of a [`Selection`](crate::space::Selection).
+    SELECTION_OUTLINE = srgb[0xFF 0xD0 0x00 0xFF];
     /// Illumination color in the HUD.
     HUD_SKY = srgb[0xFF 0xFF 0xFF];
     HUD_TEXT_FILL = srgb[0x00 0x00 0x00 0xFF];
@@ -127,9 +147,35 @@ palette! {
     BUTTON_LABEL = srgb[0x3d 0x3d 0x3d 0xFF];
     BUTTON_ACTIVATED_BACK = srgb[0xE1 0xE1 0xE1 0xFF];
     BUTTON_ACTIVATED_LABEL = srgb[0x63 0x63 0x63 0xFF];
+    HUD_GRAPH_BACK = srgb[0x00 0x00 0x00 0x60];
+    HUD_GRAPH_FRAME_TIME = srgb[0x3C 0xB4 0x4B 0xFF];
+    HUD_GRAPH_STEP_TIME = srgb[0x43 0x63 0xD8 0xFF];
+    HUD_GRAPH_LIGHT_QUEUE = srgb[0xF5 0x82 0x31 0xFF];
+    HUD_MINIMAP_BACK = srgb[0x00 0x00 0x00 0x60];
+    HUD_MINIMAP_MARKER = srgb[0xFF 0xFF 0xFF 0xFF];
+    HUD_COMPASS_BACK = srgb[0x00 0x00 0x00 0x60];
+    HUD_COMPASS_TICK = srgb[0xFF 0xFF 0xFF 0xFF];
+    HUD_COMPASS_NORTH = srgb[0xE6 0x19 0x4B 0xFF];
+    /// Marker for a [`Waypoint`](crate::space::Waypoint) which has no icon.
+    HUD_WAYPOINT_MARKER = srgb[0xFF 0xE1 0x19 0xFF];
+    /// Background of the health bar, showing the health a character has lost.
+    HUD_HEALTH_BACK = srgb[0x00 0x00 0x00 0x60];
+    /// Portion of the health bar representing the health a character has left.
+    HUD_HEALTH_FILL = srgb[0xE6 0x19 0x4B 0xFF];
 }
 pub const BUTTON_ACTIVATED_GLOW: Rgb = rgb_const!(2.0, 0.4, 0.4); // not representable as integer srgb
 
+palette! {
+    // UI elements when GraphicsOptions::ui_palette is UiPaletteOption::HighContrast
+    HIGH_CONTRAST_BUTTON_FRAME = srgb[0x00 0x00 0x00 0xFF];
+    HIGH_CONTRAST_BUTTON_BACK = srgb[0xFF 0xFF 0xFF 0xFF];
+    HIGH_CONTRAST_BUTTON_LABEL = srgb[0x00 0x00 0x00 0xFF];
+    /// Background of a toggle button which is on; darker than
+    /// [`HIGH_CONTRAST_BUTTON_BACK`] so that it can be distinguished without
+    /// seeing its hue.
+    HIGH_CONTRAST_BUTTON_ACTIVATED_BACK = srgb[0xE6 0x9F 0x00 0xFF];
+}
+
 palette! {
     // In-world debug UI elements (all wireframe)
     // TODO: these have no reason to be public
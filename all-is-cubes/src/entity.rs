@@ -0,0 +1,439 @@
+//! [`Entity`]: objects which move freely in a [`Space`], rather than occupying its cubes.
+
+use std::fmt;
+
+use cgmath::{EuclideanSpace as _, InnerSpace as _, Matrix4, Point3, Vector3};
+
+use crate::behavior::{BehaviorSet, BehaviorSetTransaction};
+use crate::block::Block;
+use crate::character::PosedPart;
+use crate::inv::Tool;
+use crate::math::{Aab, FreeCoordinate, GridPoint, Rgba};
+use crate::physics::{Body, BodyStepInfo, BodyTransaction, CollisionGroups};
+use crate::space::Space;
+use crate::time::{Duration, Tick};
+use crate::transaction::{
+    self, CommitError, Merge, PreconditionFailed, Transaction, TransactionConflict, Transactional,
+};
+use crate::universe::{Name, RefVisitor, URef, UniverseTransaction, VisitRefs};
+
+#[cfg(doc)]
+use crate::{behavior::Behavior, character::Character, universe::Universe};
+
+/// How long an item entity lasts before it disappears, if nobody picks it up.
+const ITEM_LIFETIME: Duration = Duration::from_secs(300);
+
+/// Distance from a character's position within which it picks up item entities.
+const PICKUP_DISTANCE: FreeCoordinate = 1.5;
+
+/// An object which moves freely in a [`Space`], colliding with its blocks, such as a
+/// moving platform, a projectile, or a creature; unlike a [`Character`], it has no
+/// viewpoint or inventory.
+///
+/// Entities are members of a [`Universe`] and are stepped along with it. Their motion is
+/// determined by their [`Body`], which falls and collides as characters' bodies do, and
+/// by any [`Behavior`]s attached to them. They are drawn as their
+/// [appearance](Self::appearance) block stretched to fit their collision box.
+///
+/// An entity may also be an *item entity*, holding an [`item`](Self::item) which a
+/// [`Character`] coming near it picks up; item entities disappear after a while if
+/// nobody does.
+pub struct Entity {
+    /// Position, velocity, and collision volume.
+    pub body: Body,
+
+    /// The space the entity is in, which its body collides with.
+    pub space: URef<Space>,
+
+    /// Block which is drawn to represent the entity.
+    pub(crate) appearance: Block,
+
+    /// Behaviors attached to the entity.
+    pub(crate) behaviors: BehaviorSet<Entity>,
+
+    /// Item which a character picking up this entity receives, if it is an item entity.
+    pub(crate) item: Option<Tool>,
+
+    /// How long the entity has existed.
+    pub(crate) age: Duration,
+}
+
+impl fmt::Debug for Entity {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.debug_struct("Entity")
+            .field("body", &self.body)
+            .field("space", &self.space)
+            .field("appearance", &self.appearance)
+            .field("behaviors", &self.behaviors)
+            .field("item", &self.item)
+            .field("age", &self.age)
+            .finish()
+    }
+}
+
+impl Entity {
+    /// Constructs an [`Entity`] in `space` with the given body and appearance.
+    ///
+    /// The body is added to [`CollisionGroups::ENTITY`] if it is not already.
+    pub fn new(space: URef<Space>, mut body: Body, appearance: Block) -> Self {
+        body.collision_groups.insert(CollisionGroups::ENTITY);
+        Self {
+            body,
+            space,
+            appearance,
+            behaviors: BehaviorSet::new(),
+            item: None,
+            age: Duration::ZERO,
+        }
+    }
+
+    /// Constructs an item entity holding `item`, in the middle of `cube` of `space` and
+    /// moving slightly upward as if popped out of a broken block.
+    pub fn new_item(space: URef<Space>, cube: GridPoint, item: Tool) -> Self {
+        let mut body = Body::new_minimal(
+            cube.map(FreeCoordinate::from) + Vector3::new(0.5, 0.5, 0.5),
+            Aab::new(-0.125, 0.125, -0.125, 0.125, -0.125, 0.125),
+        );
+        body.velocity = Vector3::new(0.0, 4.0, 0.0);
+        let appearance = match &item {
+            Tool::Block(block) | Tool::InfiniteBlocks(block) => block.clone(),
+            Tool::ExternalAction { icon, .. } => icon.clone(),
+            // TODO: Use the tool's icon, once there is a way to get at the icons here.
+            _ => Block::from(Rgba::new(0.5, 0.5, 0.5, 1.0)),
+        };
+        Self {
+            item: Some(item),
+            ..Self::new(space, body, appearance)
+        }
+    }
+
+    /// Returns a transaction which adds an item entity holding `item` to the universe,
+    /// in the middle of `cube` of `space`; see [`Entity::new_item()`].
+    pub fn drop_item(
+        space: URef<Space>,
+        cube: impl Into<GridPoint>,
+        item: Tool,
+    ) -> UniverseTransaction {
+        UniverseTransaction::insert(URef::new_pending(
+            Name::Pending,
+            Self::new_item(space, cube.into(), item),
+        ))
+    }
+
+    /// Returns the block which is drawn to represent the entity.
+    pub fn appearance(&self) -> &Block {
+        &self.appearance
+    }
+
+    /// Returns the behaviors attached to this entity.
+    pub fn behaviors(&self) -> &BehaviorSet<Entity> {
+        &self.behaviors
+    }
+
+    /// Returns the item which a character picking up this entity receives, or [`None`]
+    /// if it is not an item entity.
+    pub fn item(&self) -> Option<&Tool> {
+        self.item.as_ref()
+    }
+
+    /// Returns how long this entity has existed.
+    pub fn age(&self) -> Duration {
+        self.age
+    }
+
+    /// Returns whether this is an item entity in `space` close enough to `position` to
+    /// be picked up.
+    pub(crate) fn can_be_picked_up_from(
+        &self,
+        space: &URef<Space>,
+        position: Point3<FreeCoordinate>,
+    ) -> bool {
+        self.item.is_some()
+            && self.space == *space
+            && (self.body.position - position).magnitude2() <= PICKUP_DISTANCE.powi(2)
+    }
+
+    /// Returns the entity's appearance placed to fill its collision box, for renderers
+    /// to draw as they do the parts of character avatars.
+    pub fn posed_part(&self) -> PosedPart {
+        let collision_box = self.body.collision_box;
+        let size = collision_box.size();
+        PosedPart {
+            block: self.appearance.clone(),
+            transform: Matrix4::from_translation(
+                self.body.position.to_vec() + collision_box.lower_bounds_v(),
+            ) * Matrix4::from_nonuniform_scale(size.x, size.y, size.z),
+        }
+    }
+
+    /// Advances time for the entity: moves its body and steps its behaviors.
+    ///
+    /// `self_ref` should be the reference to this entity, which is needed to step its
+    /// behaviors and to remove expired item entities; if it is [`None`], neither happens.
+    pub fn step(
+        &mut self,
+        self_ref: Option<&URef<Entity>>,
+        tick: Tick,
+    ) -> (Option<BodyStepInfo>, UniverseTransaction) {
+        if tick.paused() {
+            return (None, UniverseTransaction::default());
+        }
+
+        self.age += tick.delta_t();
+
+        let body_step_info = match self.space.read() {
+            Ok(space) => Some(self.body.step(tick, Some(&space), |_| {})),
+            // TODO: set a warning flag
+            Err(_) => None,
+        };
+
+        let transaction = match self_ref {
+            Some(self_ref) => {
+                let transaction = self.behaviors.step(
+                    self,
+                    self_ref,
+                    &(|t: EntityTransaction| t.bind(self_ref.clone())),
+                    EntityTransaction::behaviors,
+                    tick,
+                );
+                if self.item.is_some() && self.age >= ITEM_LIFETIME {
+                    transaction
+                        .merge(UniverseTransaction::delete(self_ref.clone()))
+                        .unwrap()
+                } else {
+                    transaction
+                }
+            }
+            None => UniverseTransaction::default(),
+        };
+
+        (body_step_info, transaction)
+    }
+}
+
+impl VisitRefs for Entity {
+    fn visit_refs(&self, visitor: &mut dyn RefVisitor) {
+        let Self {
+            body: _,
+            space,
+            appearance,
+            behaviors,
+            item,
+            age: _,
+        } = self;
+        visitor.visit(space);
+        appearance.visit_refs(visitor);
+        behaviors.visit_refs(visitor);
+        if let Some(item) = item {
+            item.visit_refs(visitor);
+        }
+    }
+}
+
+impl crate::behavior::BehaviorHost for Entity {
+    type Attachment = ();
+}
+
+impl Transactional for Entity {
+    type Transaction = EntityTransaction;
+}
+
+/// A [`Transaction`] that modifies an [`Entity`].
+#[derive(Clone, Debug, Default, PartialEq)]
+#[must_use]
+pub struct EntityTransaction {
+    body: BodyTransaction,
+    behaviors: BehaviorSetTransaction<Entity>,
+}
+
+impl EntityTransaction {
+    /// Modify the entity's [`Body`].
+    pub fn body(t: BodyTransaction) -> Self {
+        Self {
+            body: t,
+            ..Default::default()
+        }
+    }
+
+    /// Modify the entity's [`BehaviorSet`].
+    pub fn behaviors(t: BehaviorSetTransaction<Entity>) -> Self {
+        Self {
+            behaviors: t,
+            ..Default::default()
+        }
+    }
+}
+
+impl Transaction<Entity> for EntityTransaction {
+    type CommitCheck = (
+        <BodyTransaction as Transaction<Body>>::CommitCheck,
+        <BehaviorSetTransaction<Entity> as Transaction<BehaviorSet<Entity>>>::CommitCheck,
+    );
+    type Output = transaction::NoOutput;
+
+    fn check(&self, target: &Entity) -> Result<Self::CommitCheck, PreconditionFailed> {
+        Ok((
+            self.body.check(&target.body)?,
+            self.behaviors.check(&target.behaviors)?,
+        ))
+    }
+
+    fn commit(
+        &self,
+        target: &mut Entity,
+        (body_check, behaviors_check): Self::CommitCheck,
+        outputs: &mut dyn FnMut(Self::Output),
+    ) -> Result<(), CommitError> {
+        self.body
+            .commit(&mut target.body, body_check, outputs)
+            .map_err(|e| e.context("body".into()))?;
+        self.behaviors
+            .commit(&mut target.behaviors, behaviors_check, outputs)
+            .map_err(|e| e.context("behaviors".into()))?;
+        Ok(())
+    }
+}
+
+impl Merge for EntityTransaction {
+    type MergeCheck = (
+        <BodyTransaction as Merge>::MergeCheck,
+        <BehaviorSetTransaction<Entity> as Merge>::MergeCheck,
+    );
+
+    fn check_merge(&self, other: &Self) -> Result<Self::MergeCheck, TransactionConflict> {
+        Ok((
+            self.body.check_merge(&other.body)?,
+            self.behaviors.check_merge(&other.behaviors)?,
+        ))
+    }
+
+    fn commit_merge(self, other: Self, (body_check, behaviors_check): Self::MergeCheck) -> Self {
+        Self {
+            body: self.body.commit_merge(other.body, body_check),
+            behaviors: self
+                .behaviors
+                .commit_merge(other.behaviors, behaviors_check),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::behavior::{Behavior, BehaviorContext};
+    use crate::content::make_some_blocks;
+    use crate::math::{Aab, GridAab};
+    use crate::time::practically_infinite_deadline;
+    use crate::universe::Universe;
+    use cgmath::{Point3, Transform as _, Vector3};
+    use std::sync::Arc;
+
+    fn test_entity(universe: &mut Universe) -> URef<Entity> {
+        let [block] = make_some_blocks();
+        let mut space = Space::empty_positive(3, 10, 3);
+        space
+            .fill_uniform(GridAab::from_lower_size([0, 0, 0], [3, 1, 3]), &block)
+            .unwrap();
+        let space = universe.insert_anonymous(space);
+        universe.insert_anonymous(Entity::new(
+            space,
+            Body::new_minimal([1.5, 5.0, 1.5], Aab::new(-0.5, 0.5, 0.0, 1.0, -0.5, 0.5)),
+            block,
+        ))
+    }
+
+    #[test]
+    fn entity_falls_and_lands() {
+        let mut universe = Universe::new();
+        let entity = test_entity(&mut universe);
+        for _ in 0..100 {
+            universe.step(Tick::from_seconds(0.05), practically_infinite_deadline());
+        }
+        let entity = entity.read().unwrap();
+        assert!(
+            (entity.body.position.y - 1.0).abs() < 0.01,
+            "{:?}",
+            entity.body
+        );
+        assert_eq!(entity.body.velocity.y, 0.0);
+    }
+
+    #[test]
+    fn posed_part_fills_collision_box() {
+        let mut universe = Universe::new();
+        let entity = test_entity(&mut universe);
+        let part = entity.read().unwrap().posed_part();
+        assert_eq!(
+            part.transform.transform_point(Point3::new(0.0, 0.0, 0.0)),
+            Point3::new(1.0, 5.0, 1.0)
+        );
+        assert_eq!(
+            part.transform.transform_point(Point3::new(1.0, 1.0, 1.0)),
+            Point3::new(2.0, 6.0, 2.0)
+        );
+    }
+
+    #[derive(Clone, Debug, Eq, PartialEq)]
+    struct Push;
+    impl Behavior<Entity> for Push {
+        fn step(&self, context: &BehaviorContext<'_, Entity>, _: Tick) -> UniverseTransaction {
+            context.bind_host(EntityTransaction::body(BodyTransaction::teleport([
+                1.5, 8.0, 1.5,
+            ])))
+        }
+        fn alive(&self, _: &BehaviorContext<'_, Entity>) -> bool {
+            true
+        }
+        fn ephemeral(&self) -> bool {
+            true
+        }
+    }
+    impl VisitRefs for Push {
+        fn visit_refs(&self, _: &mut dyn RefVisitor) {}
+    }
+
+    #[test]
+    fn behaviors_are_stepped() {
+        let mut universe = Universe::new();
+        let entity = test_entity(&mut universe);
+        let insert = BehaviorSetTransaction::insert((), Arc::new(Push));
+        EntityTransaction::behaviors(insert)
+            .bind(entity.clone())
+            .execute(&mut universe, &mut transaction::no_outputs)
+            .unwrap();
+        universe.step(Tick::arbitrary(), practically_infinite_deadline());
+        let entity = entity.read().unwrap();
+        assert_eq!(entity.body.position, Point3::new(1.5, 8.0, 1.5));
+        assert_eq!(entity.body.velocity, Vector3::new(0.0, 0.0, 0.0));
+    }
+    #[test]
+    fn item_falls_and_expires() {
+        let mut universe = Universe::new();
+        let [block] = make_some_blocks();
+        let mut space = Space::empty_positive(3, 10, 3);
+        space
+            .fill_uniform(GridAab::from_lower_size([0, 0, 0], [3, 1, 3]), &block)
+            .unwrap();
+        let space = universe.insert_anonymous(space);
+        let item = universe.insert_anonymous(Entity::new_item(
+            space,
+            GridPoint::new(1, 5, 1),
+            Tool::Block(block),
+        ));
+
+        for _ in 0..100 {
+            universe.step(Tick::from_seconds(0.05), practically_infinite_deadline());
+        }
+        {
+            let item = item.read().unwrap();
+            // Resting on the floor.
+            assert!((item.body.position.y - 1.125).abs() < 0.01, "{item:?}");
+            assert_eq!(item.age(), Duration::from_secs(5));
+        }
+
+        for _ in 0..30 {
+            universe.step(Tick::from_seconds(10.0), practically_infinite_deadline());
+        }
+        item.read().expect_err("item should have expired");
+    }
+}
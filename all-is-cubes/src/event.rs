@@ -0,0 +1,237 @@
+//! [`GameEvent`]s: notable occurrences in the game world, reported to listeners on the
+//! [`Universe`].
+
+use std::fmt;
+use std::sync::Arc;
+
+use crate::block::Block;
+use crate::character::{Character, DamageSource};
+use crate::inv::Tool;
+use crate::math::GridPoint;
+use crate::space::Space;
+use crate::universe::URef;
+
+#[cfg(doc)]
+use crate::universe::{Universe, UniverseTransaction};
+
+/// Something that happened in the game which players or administrators might want to
+/// know about, emitted by game logic via [`UniverseTransaction::event()`].
+///
+/// Listeners registered with [`Universe`]'s [`Listen`](crate::listen::Listen)
+/// implementation receive each event as the transaction emitting it is committed; for
+/// example, a user interface may show some of them as notifications, and a server may
+/// record them in an activity log. The [`Display`](fmt::Display) form of an event is a
+/// one-line description suitable for such logs.
+#[derive(Clone, Debug, PartialEq)]
+#[non_exhaustive]
+pub enum GameEvent {
+    /// A block was placed in a space.
+    BlockPlaced {
+        /// The space the block was placed in.
+        space: URef<Space>,
+        /// The cube the block was placed in.
+        cube: GridPoint,
+        /// The block that was placed.
+        block: Block,
+        /// The character who placed it, if any.
+        character: Option<URef<Character>>,
+    },
+    /// A block was removed from a space.
+    BlockBroken {
+        /// The space the block was in.
+        space: URef<Space>,
+        /// The cube the block was in.
+        cube: GridPoint,
+        /// The block that was removed.
+        block: Block,
+        /// The character who removed it, if any.
+        character: Option<URef<Character>>,
+    },
+    /// An item was put into a character's inventory from the world.
+    ItemPickedUp {
+        /// The character who picked up the item.
+        character: URef<Character>,
+        /// The item.
+        item: Tool,
+    },
+    /// A chat message was sent.
+    Chat {
+        /// The character who sent the message, or [`None`] if it came from the game
+        /// itself or an administrator.
+        sender: Option<URef<Character>>,
+        /// Text of the message.
+        message: Arc<str>,
+    },
+    /// A character's health was reduced to zero.
+    ///
+    /// This is immediately followed by [`GameEvent::Respawned`] for the same character.
+    Died {
+        /// The character who died.
+        character: URef<Character>,
+        /// What dealt the final damage.
+        cause: DamageSource,
+    },
+    /// A character was returned to its space's spawn point with full health, after dying.
+    Respawned {
+        /// The character who respawned.
+        character: URef<Character>,
+    },
+    /// Something went wrong which the players should be told about.
+    Error {
+        /// Description of the error.
+        message: Arc<str>,
+    },
+}
+
+impl fmt::Display for GameEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GameEvent::BlockPlaced {
+                space,
+                cube,
+                block: _,
+                character,
+            } => write_block_event(f, "placed", space, *cube, character.as_ref()),
+            GameEvent::BlockBroken {
+                space,
+                cube,
+                block: _,
+                character,
+            } => write_block_event(f, "broke", space, *cube, character.as_ref()),
+            GameEvent::ItemPickedUp { character, item: _ } => {
+                write!(f, "{} picked up an item", character.name())
+            }
+            GameEvent::Chat {
+                sender: Some(sender),
+                message,
+            } => write!(f, "<{}> {message}", sender.name()),
+            GameEvent::Chat {
+                sender: None,
+                message,
+            } => write!(f, "{message}"),
+            GameEvent::Died {
+                character,
+                cause: DamageSource::Impact,
+            } => write!(f, "{} hit the ground too hard", character.name()),
+            GameEvent::Died {
+                character,
+                cause: DamageSource::Block(block),
+            } => {
+                write!(f, "{} was killed by ", character.name())?;
+                match block.evaluate() {
+                    Ok(ev) if !ev.attributes.display_name.is_empty() => {
+                        write!(f, "{}", ev.attributes.display_name)
+                    }
+                    _ => write!(f, "a block"),
+                }
+            }
+            GameEvent::Respawned { character } => write!(f, "{} respawned", character.name()),
+            GameEvent::Error { message } => write!(f, "Error: {message}"),
+        }
+    }
+}
+
+fn write_block_event(
+    f: &mut fmt::Formatter<'_>,
+    verb: &str,
+    space: &URef<Space>,
+    cube: GridPoint,
+    character: Option<&URef<Character>>,
+) -> fmt::Result {
+    match character {
+        Some(character) => write!(f, "{} {verb}", character.name())?,
+        None => write!(f, "Something {verb}")?,
+    }
+    write!(
+        f,
+        " a block at ({}, {}, {}) in {}",
+        cube.x,
+        cube.y,
+        cube.z,
+        space.name()
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::block::AIR;
+    use crate::math::Rgba;
+    use crate::universe::Universe;
+
+    #[test]
+    fn display() {
+        let mut universe = Universe::new();
+        let space = universe
+            .insert("space".into(), Space::empty_positive(1, 1, 1))
+            .unwrap();
+        let character = universe
+            .insert("alice".into(), Character::spawn_default(space.clone()))
+            .unwrap();
+
+        assert_eq!(
+            GameEvent::BlockPlaced {
+                space: space.clone(),
+                cube: GridPoint::new(0, 0, 0),
+                block: AIR,
+                character: None,
+            }
+            .to_string(),
+            "Something placed a block at (0, 0, 0) in 'space'"
+        );
+        assert_eq!(
+            GameEvent::BlockBroken {
+                space,
+                cube: GridPoint::new(1, 2, -3),
+                block: AIR,
+                character: Some(character.clone()),
+            }
+            .to_string(),
+            "'alice' broke a block at (1, 2, -3) in 'space'"
+        );
+        assert_eq!(
+            GameEvent::ItemPickedUp {
+                character: character.clone(),
+                item: Tool::Activate,
+            }
+            .to_string(),
+            "'alice' picked up an item"
+        );
+        assert_eq!(
+            GameEvent::Died {
+                character: character.clone(),
+                cause: DamageSource::Impact,
+            }
+            .to_string(),
+            "'alice' hit the ground too hard"
+        );
+        assert_eq!(
+            GameEvent::Died {
+                character: character.clone(),
+                cause: DamageSource::Block(
+                    Block::builder()
+                        .display_name("lava")
+                        .color(Rgba::WHITE)
+                        .build()
+                ),
+            }
+            .to_string(),
+            "'alice' was killed by lava"
+        );
+        assert_eq!(
+            GameEvent::Chat {
+                sender: Some(character),
+                message: "hello".into(),
+            }
+            .to_string(),
+            "<'alice'> hello"
+        );
+        assert_eq!(
+            GameEvent::Error {
+                message: "oops".into(),
+            }
+            .to_string(),
+            "Error: oops"
+        );
+    }
+}
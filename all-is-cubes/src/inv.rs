@@ -3,6 +3,8 @@
 //! TODO: This module needs a better name; I'd be calling it `inventory` if that weren't
 //! also the name of one of its internal modules.
 
+mod hotbar;
+pub use hotbar::*;
 mod icons;
 pub use icons::*;
 mod inventory;
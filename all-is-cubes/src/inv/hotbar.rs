@@ -0,0 +1,168 @@
+//! [`Hotbar`].
+
+/// A row of quick-access positions, each of which shows one slot of an
+/// [`Inventory`](super::Inventory), and one of which is selected.
+///
+/// A hotbar does not hold any items itself; it refers to inventory slots by index, so that
+/// user interfaces can offer a few slots for quick selection without assuming which or how
+/// many slots those are.
+///
+/// ```
+/// use all_is_cubes::inv::Hotbar;
+///
+/// let mut hotbar = Hotbar::new(vec![4, 5, 0]);
+/// assert_eq!(hotbar.selected_slot(), Some(4));
+///
+/// hotbar.cycle(-1);
+/// assert_eq!(hotbar.selected(), 2);
+/// assert_eq!(hotbar.selected_slot(), Some(0));
+/// ```
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct Hotbar {
+    /// Inventory slot index shown at each position.
+    slots: Vec<usize>,
+    /// Selected position; always less than `slots.len()` unless `slots` is empty.
+    selected: usize,
+}
+
+impl Hotbar {
+    /// Number of positions in [`Hotbar::default()`].
+    pub const DEFAULT_LEN: usize = 10;
+
+    /// Constructs a hotbar whose positions show the given inventory slots, in order,
+    /// with the first position selected.
+    pub fn new(slots: Vec<usize>) -> Self {
+        Self { slots, selected: 0 }
+    }
+
+    /// Constructs a hotbar whose positions show the first `len` inventory slots.
+    pub fn contiguous(len: usize) -> Self {
+        Self::new((0..len).collect())
+    }
+
+    /// Returns the number of positions in the hotbar.
+    pub fn len(&self) -> usize {
+        self.slots.len()
+    }
+
+    /// Returns whether the hotbar has no positions at all.
+    pub fn is_empty(&self) -> bool {
+        self.slots.is_empty()
+    }
+
+    /// Returns the inventory slot index shown at each position.
+    pub fn slots(&self) -> &[usize] {
+        &self.slots
+    }
+
+    /// Returns the inventory slot index shown at `position`, if there is such a position.
+    pub fn slot(&self, position: usize) -> Option<usize> {
+        self.slots.get(position).copied()
+    }
+
+    /// Returns the first position showing the inventory slot `slot`, if any.
+    pub fn position_of(&self, slot: usize) -> Option<usize> {
+        self.slots.iter().position(|&s| s == slot)
+    }
+
+    /// Returns the selected position.
+    pub fn selected(&self) -> usize {
+        self.selected
+    }
+
+    /// Returns the inventory slot index shown at the selected position, or [`None`] if
+    /// the hotbar is empty.
+    pub fn selected_slot(&self) -> Option<usize> {
+        self.slot(self.selected)
+    }
+
+    /// Selects `position`. Returns false, and does nothing, if there is no such position.
+    pub fn select(&mut self, position: usize) -> bool {
+        if position < self.slots.len() {
+            self.selected = position;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Selects the first position showing the inventory slot `slot`. Returns false, and
+    /// does nothing, if no position shows it.
+    pub fn select_slot(&mut self, slot: usize) -> bool {
+        match self.position_of(slot) {
+            Some(position) => self.select(position),
+            None => false,
+        }
+    }
+
+    /// Moves the selection by `delta` positions, wrapping around at either end.
+    pub fn cycle(&mut self, delta: isize) {
+        if let Ok(len) = isize::try_from(self.slots.len()) {
+            if len > 0 {
+                // `selected < len`, so this cast does not wrap.
+                self.selected = (self.selected as isize + delta).rem_euclid(len) as usize;
+            }
+        }
+    }
+}
+
+impl Default for Hotbar {
+    /// Shows the first [`Hotbar::DEFAULT_LEN`] slots, as used by
+    /// [`Character::spawn_default()`](crate::character::Character::spawn_default).
+    fn default() -> Self {
+        Self::contiguous(Self::DEFAULT_LEN)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_is_contiguous() {
+        let hotbar = Hotbar::default();
+        assert_eq!(hotbar.len(), Hotbar::DEFAULT_LEN);
+        assert_eq!(hotbar.slots(), (0..10).collect::<Vec<_>>());
+        assert_eq!(hotbar.selected(), 0);
+        assert_eq!(hotbar.selected_slot(), Some(0));
+    }
+
+    #[test]
+    fn select_out_of_range() {
+        let mut hotbar = Hotbar::new(vec![3, 1]);
+        assert!(hotbar.select(1));
+        assert!(!hotbar.select(2));
+        assert_eq!(hotbar.selected_slot(), Some(1));
+    }
+
+    #[test]
+    fn select_slot() {
+        let mut hotbar = Hotbar::new(vec![3, 1, 3]);
+        assert!(hotbar.select_slot(3));
+        assert_eq!(hotbar.selected(), 0);
+        assert!(!hotbar.select_slot(2));
+        assert_eq!(hotbar.selected(), 0);
+        assert_eq!(hotbar.position_of(1), Some(1));
+    }
+
+    #[test]
+    fn cycle_wraps() {
+        let mut hotbar = Hotbar::contiguous(3);
+        hotbar.cycle(-1);
+        assert_eq!(hotbar.selected(), 2);
+        hotbar.cycle(2);
+        assert_eq!(hotbar.selected(), 1);
+        hotbar.cycle(7);
+        assert_eq!(hotbar.selected(), 2);
+    }
+
+    #[test]
+    fn empty() {
+        let mut hotbar = Hotbar::new(vec![]);
+        assert!(hotbar.is_empty());
+        hotbar.cycle(1);
+        assert_eq!(hotbar.selected(), 0);
+        assert_eq!(hotbar.selected_slot(), None);
+        assert!(!hotbar.select(0));
+    }
+}
@@ -7,7 +7,7 @@ use std::sync::Arc;
 
 use crate::block::Block;
 use crate::character::{Character, CharacterTransaction, Cursor};
-use crate::inv::{Icons, Tool, ToolError, ToolInput};
+use crate::inv::{Icons, Tool, ToolError, ToolInput, ToolKind};
 use crate::linking::BlockProvider;
 use crate::transaction::{
     CommitError, Merge, PreconditionFailed, Transaction, TransactionConflict,
@@ -27,6 +27,11 @@ use crate::universe::{RefVisitor, URef, UniverseTransaction, VisitRefs};
 pub struct Inventory {
     /// TODO: This probably shouldn't be public forever.
     pub slots: Vec<Slot>,
+
+    /// Limits obeyed when items are added to this inventory. These are a copy of the
+    /// [`Universe::stack_limits()`](crate::universe::Universe::stack_limits) of the
+    /// universe containing the inventory's owner, if any.
+    stack_limits: Arc<StackLimits>,
 }
 
 impl Inventory {
@@ -34,15 +39,38 @@ impl Inventory {
     ///
     /// Ordinary user actions cannot change the number of slots.
     pub fn new(size: usize) -> Self {
-        Inventory {
-            slots: vec![Slot::Empty; size],
-        }
+        Self::from_slots(vec![Slot::Empty; size])
     }
 
     /// TODO: temporary interface, reevaluate design
     pub(crate) fn from_slots(mut items: Vec<Slot>) -> Self {
         items.shrink_to_fit();
-        Inventory { slots: items }
+        Inventory {
+            slots: items,
+            stack_limits: Arc::default(),
+        }
+    }
+
+    /// Returns the limits obeyed when items are added to this inventory.
+    pub fn stack_limits(&self) -> &StackLimits {
+        &self.stack_limits
+    }
+
+    /// Replaces the limits obeyed when items are added to this inventory. Existing
+    /// stacks which exceed the new limits are left as they are.
+    ///
+    /// The inventories of characters receive their universe's limits automatically;
+    /// the owner of any other inventory should call this with
+    /// [`Universe::stack_limits()`](crate::universe::Universe::stack_limits).
+    pub fn set_stack_limits(&mut self, limits: StackLimits) {
+        self.stack_limits = Arc::new(limits);
+    }
+
+    /// Like [`Self::set_stack_limits()`], but shares the universe's copy of the limits.
+    pub(crate) fn share_stack_limits(&mut self, limits: &Arc<StackLimits>) {
+        if !Arc::ptr_eq(&self.stack_limits, limits) {
+            self.stack_limits = limits.clone();
+        }
     }
 
     /// Use a tool stored in this inventory.
@@ -131,7 +159,10 @@ impl Inventory {
 
 impl VisitRefs for Inventory {
     fn visit_refs(&self, visitor: &mut dyn RefVisitor) {
-        let Self { slots } = self;
+        let Self {
+            slots,
+            stack_limits: _,
+        } = self;
         slots.visit_refs(visitor);
     }
 }
@@ -200,12 +231,12 @@ impl Slot {
     }
 
     /// Moves as many items as possible from `self` to `destination` while obeying item
-    /// stacking rules.
+    /// stacking rules, as specified by `limits`.
     ///
     /// Does nothing if `self` and `destination` contain different items.
     ///
     /// Returns whether anything was moved.
-    fn unload_to(&mut self, destination: &mut Self) -> bool {
+    fn unload_to(&mut self, destination: &mut Self, limits: &StackLimits) -> bool {
         // First, handle the simple cases, or decide how many to move.
         // This has to be multiple passes to satisfy the borrow checker.
         let count_to_move = match (&mut *self, &mut *destination) {
@@ -214,14 +245,24 @@ impl Slot {
                 return false;
             }
             (source @ Slot::Stack(_, _), destination @ Slot::Empty) => {
-                // Destination is empty (and source isn't); just swap.
-                std::mem::swap(source, destination);
+                let Slot::Stack(s_count, source_item) = source else {
+                    unreachable!()
+                };
+                let max_stack = limits.get(source_item);
+                if s_count.get() <= max_stack {
+                    // Destination is empty (and source isn't); just swap.
+                    std::mem::swap(source, destination);
+                } else {
+                    // The source stack is larger than a stack may be; split it.
+                    *s_count = NonZeroU16::new(s_count.get() - max_stack).unwrap();
+                    *destination = Slot::stack(max_stack, source_item.clone());
+                }
                 return true;
             }
             (Slot::Stack(s_count, source_item), Slot::Stack(d_count, destination_item)) => {
                 if source_item == destination_item {
                     // Stacks of identical items; figure out how much to move.
-                    let max_stack = destination_item.stack_limit().get();
+                    let max_stack = limits.get(destination_item);
                     let count_to_move = s_count.get().min(max_stack.saturating_sub(d_count.get()));
                     if count_to_move == 0 {
                         return false;
@@ -279,9 +320,7 @@ impl VisitRefs for Slot {
 /// Specifies a limit on the number of a particular item that should be combined in a
 /// single [`Slot`].
 ///
-/// Each value of this enum is currently equivalent to a particular number, but (TODO:)
-/// in the future, it may be possible for inventories or universes to specify a normal
-/// stack size and specific deviations from it.
+/// The number each value stands for is determined by [`StackLimits`].
 #[non_exhaustive]
 #[derive(Copy, Clone, Debug, Eq, Hash, PartialEq)]
 pub(crate) enum StackLimit {
@@ -289,18 +328,70 @@ pub(crate) enum StackLimit {
     Standard,
 }
 
-impl StackLimit {
-    /// TODO: This is not public because we don't know what environment parameters it
-    /// should need yet.
-    pub(crate) fn get(self) -> u16 {
-        match self {
+/// The numbers of each item that may be combined in a single [`Slot`], which apply to
+/// all inventories in a [`Universe`](crate::universe::Universe).
+///
+/// Items which are stackable at all are limited to the standard stack size unless their
+/// [`ToolKind`] has an override; other items are never stacked unless overridden.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct StackLimits {
+    standard: NonZeroU16,
+    overrides: BTreeMap<ToolKind, NonZeroU16>,
+}
+
+impl StackLimits {
+    /// The standard stack size of [`StackLimits::default()`].
+    pub const DEFAULT_STANDARD: NonZeroU16 = {
+        // Safety: is a constant
+        // TODO: when Option::unwrap is stably const, remove unsafe
+        unsafe { NonZeroU16::new_unchecked(100) }
+    };
+
+    /// Constructs [`StackLimits`] with the given standard stack size and no overrides.
+    pub fn new(standard: NonZeroU16) -> Self {
+        Self {
+            standard,
+            overrides: BTreeMap::new(),
+        }
+    }
+
+    /// Returns a copy of `self` which limits stacks of tools of the given kind to
+    /// `limit`, instead of what they would otherwise be.
+    #[must_use]
+    pub fn with_override(mut self, kind: ToolKind, limit: NonZeroU16) -> Self {
+        self.overrides.insert(kind, limit);
+        self
+    }
+
+    /// Returns the standard stack size, which applies to stackable items without an
+    /// override.
+    pub fn standard(&self) -> NonZeroU16 {
+        self.standard
+    }
+
+    /// Returns the overridden stack sizes, in order of [`ToolKind`].
+    pub fn overrides(&self) -> impl Iterator<Item = (ToolKind, NonZeroU16)> + '_ {
+        self.overrides.iter().map(|(&kind, &limit)| (kind, limit))
+    }
+
+    /// Returns the maximum number of `tool` which may be in a single [`Slot`].
+    pub fn get(&self, tool: &Tool) -> u16 {
+        if let Some(limit) = self.overrides.get(&tool.kind()) {
+            return limit.get();
+        }
+        match tool.stack_limit() {
             StackLimit::One => 1,
-            // TODO: This should be a per-universe (at least) configuration.
-            StackLimit::Standard => 100,
+            StackLimit::Standard => self.standard.get(),
         }
     }
 }
 
+impl Default for StackLimits {
+    fn default() -> Self {
+        Self::new(Self::DEFAULT_STANDARD)
+    }
+}
+
 /// Transaction type for [`Inventory`].
 ///
 /// The output type is the change notification which should be passed on after commit,
@@ -310,6 +401,8 @@ impl StackLimit {
 #[must_use]
 pub struct InventoryTransaction {
     replace: BTreeMap<usize, (Slot, Slot)>,
+    /// Items to take from whichever slots contain them. Always [`Slot::Stack`]s.
+    remove: Vec<Slot>,
     insert: Vec<Slot>,
 }
 
@@ -320,15 +413,79 @@ impl InventoryTransaction {
         // TODO: Should we coalesce identical insertions? Or leave that for when the
         // transaction is executed?
         Self {
-            replace: BTreeMap::default(),
             insert: stacks
                 .into_iter()
                 .map(|s| -> Slot { s.into() })
                 .filter(|s| s.count() > 0)
                 .collect(),
+            ..Default::default()
+        }
+    }
+
+    /// Transaction to remove `count` of `item` from an inventory, taking them from any
+    /// slots containing that item, which will fail if there are not that many.
+    ///
+    /// Items are taken from later slots first, so that the first slots, which are
+    /// usually the ones selected for use, are the last to run out.
+    pub fn remove(item: Tool, count: u16) -> Self {
+        Self {
+            remove: match Slot::stack(count, item) {
+                Slot::Empty => vec![],
+                stack => vec![stack],
+            },
+            ..Default::default()
         }
     }
 
+    /// Returns a pair of transactions which, when both are committed, move `count` of
+    /// `item` from one inventory to another: the first removes them, as by
+    /// [`InventoryTransaction::remove()`], and the second inserts them, as by
+    /// [`InventoryTransaction::insert()`].
+    ///
+    /// The transactions are typically bound to their respective inventories' owners and
+    /// merged, so that the items are moved only if both succeed:
+    ///
+    /// ```
+    /// use all_is_cubes::block::Block;
+    /// use all_is_cubes::character::{Character, CharacterTransaction};
+    /// use all_is_cubes::inv::{InventoryTransaction, Slot, Tool};
+    /// use all_is_cubes::math::Rgba;
+    /// use all_is_cubes::space::Space;
+    /// use all_is_cubes::transaction::{Merge as _, Transaction as _};
+    /// use all_is_cubes::universe::Universe;
+    ///
+    /// let mut universe = Universe::new();
+    /// let space = universe.insert_anonymous(Space::empty_positive(1, 1, 1));
+    /// let source = universe.insert("source".into(), Character::spawn_default(space.clone()))?;
+    /// let destination = universe.insert("destination".into(), Character::spawn_default(space))?;
+    ///
+    /// let item = Tool::Block(Block::from(Rgba::WHITE));
+    /// CharacterTransaction::inventory(InventoryTransaction::insert([Slot::stack(10, item.clone())]))
+    ///     .bind(source.clone())
+    ///     .execute(&mut universe, &mut drop)?;
+    ///
+    /// let (take, give) = InventoryTransaction::transfer(item.clone(), 4);
+    /// CharacterTransaction::inventory(take)
+    ///     .bind(source.clone())
+    ///     .merge(CharacterTransaction::inventory(give).bind(destination.clone()))?
+    ///     .execute(&mut universe, &mut drop)?;
+    ///
+    /// let count_in = |character: &all_is_cubes::universe::URef<Character>| -> u32 {
+    ///     character.read().unwrap().inventory().slots.iter()
+    ///         .filter(|slot| matches!(slot, Slot::Stack(_, tool) if *tool == item))
+    ///         .map(|slot| u32::from(slot.count()))
+    ///         .sum()
+    /// };
+    /// assert_eq!((count_in(&source), count_in(&destination)), (6, 4));
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn transfer(item: Tool, count: u16) -> (Self, Self) {
+        (
+            Self::remove(item.clone(), count),
+            Self::insert([Slot::stack(count, item)]),
+        )
+    }
+
     /// Transaction to replace the contents of an existing slot in an inventory, which
     /// will fail if the existing slot is not as expected.
     ///
@@ -339,7 +496,7 @@ impl InventoryTransaction {
         replace.insert(slot, (old, new));
         InventoryTransaction {
             replace,
-            insert: vec![],
+            ..Default::default()
         }
     }
 }
@@ -350,7 +507,7 @@ impl Transaction<Inventory> for InventoryTransaction {
 
     fn check(&self, inventory: &Inventory) -> Result<Self::CommitCheck, PreconditionFailed> {
         // Don't do the expensive copy if we have one already
-        if self.replace.is_empty() && self.insert.is_empty() {
+        if self.replace.is_empty() && self.remove.is_empty() && self.insert.is_empty() {
             return Ok(None);
         }
 
@@ -385,6 +542,33 @@ impl Transaction<Inventory> for InventoryTransaction {
             }
         }
 
+        // Take .remove items, from the last slots first
+        for stack in self.remove.iter() {
+            let Slot::Stack(count, item) = stack else {
+                unreachable!("InventoryTransaction::remove contained an empty slot")
+            };
+            let mut needed = count.get();
+            for (index, slot) in slots.iter_mut().enumerate().rev() {
+                if needed == 0 {
+                    break;
+                }
+                if let Slot::Stack(slot_count, slot_item) = slot {
+                    if *slot_item == *item {
+                        let taken = needed.min(slot_count.get());
+                        needed -= taken;
+                        *slot = Slot::stack(slot_count.get() - taken, item.clone());
+                        changed.push(index);
+                    }
+                }
+            }
+            if needed > 0 {
+                return Err(PreconditionFailed {
+                    location: "Inventory",
+                    problem: "insufficient items to remove",
+                });
+            }
+        }
+
         // Find locations for .insert items
         for new_stack in self.insert.iter() {
             let mut new_stack = new_stack.clone();
@@ -392,7 +576,7 @@ impl Transaction<Inventory> for InventoryTransaction {
                 if new_stack == Slot::Empty {
                     break;
                 }
-                if new_stack.unload_to(slot) {
+                if new_stack.unload_to(slot, &inventory.stack_limits) {
                     changed.push(index);
                 }
             }
@@ -403,6 +587,8 @@ impl Transaction<Inventory> for InventoryTransaction {
                 });
             }
         }
+        changed.sort_unstable();
+        changed.dedup();
 
         Ok(Some(InventoryCheck {
             new: slots,
@@ -443,6 +629,7 @@ impl Merge for InventoryTransaction {
 
     fn commit_merge(mut self, other: Self, (): Self::MergeCheck) -> Self {
         self.replace.extend(other.replace);
+        self.remove.extend(other.remove);
         self.insert.extend(other.insert);
         self
     }
@@ -466,6 +653,7 @@ pub struct InventoryChange {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::block::AIR;
     use crate::content::make_some_blocks;
     use crate::math::Rgba;
     use crate::transaction::TransactionTester;
@@ -571,6 +759,147 @@ mod tests {
         );
     }
 
+    #[test]
+    fn txn_insert_splits_oversized_stack() {
+        let [block] = make_some_blocks();
+        let item = Tool::Block(block);
+        let mut inventory = Inventory::new(3);
+        InventoryTransaction::insert([Slot::stack(250, item.clone())])
+            .execute(&mut inventory, &mut drop)
+            .unwrap();
+        assert_eq!(
+            inventory.slots,
+            vec![
+                Slot::stack(100, item.clone()),
+                Slot::stack(100, item.clone()),
+                Slot::stack(50, item),
+            ]
+        );
+    }
+
+    #[test]
+    fn txn_remove_zero() {
+        assert_eq!(
+            InventoryTransaction::remove(Tool::Activate, 0),
+            InventoryTransaction::default()
+        );
+    }
+
+    #[test]
+    fn txn_remove_from_later_slots_first() {
+        let [this, other] = make_some_blocks();
+        let this = Tool::Block(this);
+        let other = Tool::Block(other);
+        let mut inventory = Inventory::from_slots(vec![
+            Slot::stack(5, this.clone()),
+            Slot::stack(3, other.clone()),
+            Slot::stack(4, this.clone()),
+            Slot::Empty,
+        ]);
+
+        let mut outputs = Vec::new();
+        InventoryTransaction::remove(this.clone(), 6)
+            .execute(&mut inventory, &mut |x| outputs.push(x))
+            .unwrap();
+
+        assert_eq!(
+            outputs,
+            vec![InventoryChange {
+                slots: Arc::new([0, 2])
+            }]
+        );
+        assert_eq!(
+            inventory.slots,
+            vec![
+                Slot::stack(3, this),
+                Slot::stack(3, other),
+                Slot::Empty,
+                Slot::Empty,
+            ]
+        );
+    }
+
+    #[test]
+    fn txn_remove_insufficient() {
+        let [block] = make_some_blocks();
+        let item = Tool::Block(block);
+        let inventory = Inventory::from_slots(vec![
+            Slot::stack(4, item.clone()),
+            Slot::stack(4, item.clone()),
+        ]);
+        InventoryTransaction::remove(item.clone(), 9)
+            .check(&inventory)
+            .expect_err("should have failed");
+        InventoryTransaction::remove(Tool::Activate, 1)
+            .check(&inventory)
+            .expect_err("should have failed");
+    }
+
+    /// Removals do not conflict when merged, but the merged transaction must find
+    /// enough items for all of them.
+    #[test]
+    fn txn_remove_merged() {
+        let [block] = make_some_blocks();
+        let item = Tool::Block(block);
+        let inventory = Inventory::from_slots(vec![Slot::stack(9, item.clone())]);
+        let remove_5 = InventoryTransaction::remove(item.clone(), 5);
+        remove_5.check(&inventory).unwrap();
+
+        let merged = remove_5.clone().merge(remove_5).unwrap();
+        merged.check(&inventory).expect_err("should have failed");
+
+        let merged = InventoryTransaction::remove(item.clone(), 4)
+            .merge(InventoryTransaction::remove(item, 5))
+            .unwrap();
+        let mut inventory = inventory;
+        merged.execute(&mut inventory, &mut drop).unwrap();
+        assert_eq!(inventory.slots, vec![Slot::Empty]);
+    }
+
+    /// Replacements are applied before removals, so a removal cannot take items which a
+    /// replacement in the same transaction also takes.
+    #[test]
+    fn txn_remove_after_replace() {
+        let [block] = make_some_blocks();
+        let item = Tool::Block(block);
+        let inventory = Inventory::from_slots(vec![Slot::stack(5, item.clone()), Slot::Empty]);
+        InventoryTransaction::replace(0, Slot::stack(5, item.clone()), Slot::Empty)
+            .merge(InventoryTransaction::remove(item, 1))
+            .unwrap()
+            .check(&inventory)
+            .expect_err("should have failed");
+    }
+
+    /// Removals are applied before insertions, so a full inventory can exchange items.
+    #[test]
+    fn txn_remove_then_insert() {
+        let mut inventory = Inventory::from_slots(vec![Slot::from(Tool::Activate)]);
+        InventoryTransaction::insert([Tool::CopyFromSpace])
+            .check(&inventory)
+            .expect_err("should have failed");
+        InventoryTransaction::insert([Tool::CopyFromSpace])
+            .merge(InventoryTransaction::remove(Tool::Activate, 1))
+            .unwrap()
+            .execute(&mut inventory, &mut drop)
+            .unwrap();
+        assert_eq!(inventory.slots, vec![Slot::from(Tool::CopyFromSpace)]);
+    }
+
+    #[test]
+    fn txn_transfer() {
+        let [block] = make_some_blocks();
+        let item = Tool::Block(block);
+        let mut source = Inventory::from_slots(vec![Slot::stack(10, item.clone())]);
+        let mut destination = Inventory::from_slots(vec![Slot::Empty, Slot::Empty]);
+
+        let (take, give) = InventoryTransaction::transfer(item.clone(), 4);
+        take.execute(&mut source, &mut drop).unwrap();
+        give.execute(&mut destination, &mut drop).unwrap();
+
+        assert_eq!(source.slots, vec![Slot::stack(6, item.clone())]);
+        assert_eq!(destination.slots, vec![Slot::stack(4, item), Slot::Empty]);
+    }
+
     #[test]
     fn txn_systematic() {
         let old_item = Tool::InfiniteBlocks(Block::from(rgb_const!(1.0, 0.0, 0.0)));
@@ -629,6 +958,36 @@ mod tests {
             .test();
     }
 
+    #[test]
+    fn txn_insert_obeys_stack_limits() {
+        let [block] = make_some_blocks();
+        let item = Tool::Block(block);
+        let mut inventory = Inventory::from_slots(vec![Slot::stack(10, item.clone()), Slot::Empty]);
+        inventory.set_stack_limits(StackLimits::new(NonZeroU16::new(12).unwrap()));
+
+        InventoryTransaction::insert([Slot::stack(5, item.clone())])
+            .execute(&mut inventory, &mut drop)
+            .unwrap();
+        assert_eq!(
+            inventory.slots,
+            vec![Slot::stack(12, item.clone()), Slot::stack(3, item)]
+        );
+    }
+
+    #[test]
+    fn stack_limits_overrides() {
+        let [block] = make_some_blocks();
+        let limits = StackLimits::new(NonZeroU16::new(50).unwrap())
+            .with_override(ToolKind::InfiniteBlocks, NonZeroU16::new(5).unwrap());
+        assert_eq!(limits.get(&Tool::Block(block.clone())), 50);
+        assert_eq!(limits.get(&Tool::InfiniteBlocks(block)), 5);
+        assert_eq!(limits.get(&Tool::Activate), 1);
+        assert_eq!(
+            StackLimits::default().get(&Tool::Block(AIR)),
+            StackLimits::DEFAULT_STANDARD.get()
+        );
+    }
+
     #[test]
     fn slot_unload_systematic() {
         let [block1, block2] = make_some_blocks();
@@ -662,7 +1021,7 @@ mod tests {
 
                 let mut slot1_out = slot1_in.clone();
                 let mut slot2_out = slot2_in.clone();
-                slot1_out.unload_to(&mut slot2_out);
+                slot1_out.unload_to(&mut slot2_out, &StackLimits::default());
 
                 assert_eq!(
                     u64::from(slot1_in.count()) + u64::from(slot2_in.count()),
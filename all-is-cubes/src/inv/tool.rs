@@ -6,10 +6,13 @@ use std::{fmt, hash};
 
 use crate::block::{self, Block, Primitive, RotationPlacementRule, AIR};
 use crate::character::{Character, CharacterTransaction, Cursor};
+use crate::entity::Entity;
+use crate::event::GameEvent;
 use crate::fluff::Fluff;
 use crate::inv::{self, Icons, InventoryTransaction, StackLimit};
 use crate::linking::BlockProvider;
-use crate::math::{Face6, GridPoint, GridRotation};
+use crate::math::{cube_to_midpoint, Face6, GridPoint, GridRotation};
+use crate::sound::SoundEvent;
 use crate::space::{Space, SpaceTransaction};
 use crate::transaction::{Merge, Transaction};
 use crate::universe::{RefError, RefVisitor, URef, UniverseTransaction, VisitRefs};
@@ -110,24 +113,55 @@ impl Tool {
             }
             Self::RemoveBlock { keep } => {
                 let cursor = input.cursor()?;
-                let deletion = input.set_cube(cursor.cube(), cursor.hit().block.clone(), AIR)?;
+                let deletion = input
+                    .set_cube(cursor.cube(), cursor.hit().block.clone(), AIR)?
+                    .merge(UniverseTransaction::event(GameEvent::BlockBroken {
+                        space: cursor.space().clone(),
+                        cube: cursor.cube(),
+                        block: cursor.hit().block.clone(),
+                        character: input.character.clone(),
+                    }))
+                    .unwrap()
+                    .merge(cube_sound(Fluff::RemoveBlockGeneric, cursor.cube()))
+                    .unwrap();
+                let items = || -> Vec<Tool> {
+                    cursor
+                        .hit()
+                        .block
+                        .unspecialize()
+                        .into_iter()
+                        .map(Tool::Block)
+                        .collect()
+                };
+                let drops_items = keep && cursor.space().read()?.item_drops();
                 Ok((
                     Some(self),
-                    if keep {
+                    if !keep {
                         deletion
-                            .merge(
-                                input.produce_items(
-                                    cursor
-                                        .hit()
-                                        .block
-                                        .unspecialize()
-                                        .into_iter()
-                                        .map(Tool::Block),
-                                )?,
-                            )
-                            .unwrap()
+                    } else if drops_items {
+                        // The items will be picked up when the character walks over them.
+                        items()
+                            .into_iter()
+                            .map(|item| {
+                                Entity::drop_item(cursor.space().clone(), cursor.cube(), item)
+                            })
+                            .fold(deletion, |txn, drop| txn.merge(drop).unwrap())
                     } else {
-                        deletion
+                        let items = items();
+                        let mut transaction = deletion
+                            .merge(input.produce_items(items.iter().cloned())?)
+                            .unwrap();
+                        if let Some(character) = &input.character {
+                            for item in items {
+                                transaction = transaction
+                                    .merge(UniverseTransaction::event(GameEvent::ItemPickedUp {
+                                        character: character.clone(),
+                                        item,
+                                    }))
+                                    .unwrap();
+                            }
+                        }
+                        transaction
                     },
                 ))
             }
@@ -163,7 +197,7 @@ impl Tool {
                 fn find_space(block: &Block) -> Result<Option<URef<Space>>, RefError> {
                     match block.primitive() {
                         Primitive::Indirect(r) => find_space(&**r.read()?),
-                        Primitive::Atom(_, _) | Primitive::Air => Ok(None),
+                        Primitive::Atom(_, _) | Primitive::Faces(_, _) | Primitive::Air => Ok(None),
                         Primitive::Recur { space, .. } => Ok(Some(space.clone())),
                     }
                 }
@@ -185,31 +219,35 @@ impl Tool {
                     .try_into()
                     .map_err(|_| ToolError::NotUsable)?;
 
+                let space = cursor.space().read().map_err(ToolError::SpaceRef)?;
+
                 // If we can't push, then try pulling.
                 // TODO: Tool should have user-controllable modes
-                if cursor.space().read().unwrap()[cursor.cube() + direction.normal_vector()] != AIR
-                {
+                if space[cursor.cube() + direction.normal_vector()] != AIR {
                     direction = direction.opposite();
                 }
 
+                let destination = cursor.cube() + direction.normal_vector();
+                if space[destination] != AIR {
+                    return Err(ToolError::Obstacle);
+                }
+                if !space.permits_modification(cursor.cube(), input.character.as_ref())
+                    || !space.permits_modification(destination, input.character.as_ref())
+                {
+                    return Err(ToolError::Protected);
+                }
+
                 let velocity = 8;
-                let [move_out, move_in] = block::Move::paired_move(direction, 0, velocity);
-                let leaving = input.set_cube(
+                let mut txn = block::Move::start_transaction(
+                    &cursor.hit().block,
                     cursor.cube(),
-                    cursor.hit().block.clone(),
-                    cursor.hit().block.clone().with_modifier(move_out),
-                )?;
-                let entering = input.set_cube(
-                    cursor.cube() + direction.normal_vector(),
-                    AIR,
-                    cursor.hit().block.clone().with_modifier(move_in),
-                )?;
-                Ok((
-                    Some(self),
-                    entering
-                        .merge(leaving)
-                        .expect("Push transactions conflicted???"),
-                ))
+                    direction,
+                    velocity,
+                );
+                if let Some(character) = &input.character {
+                    txn = txn.on_behalf_of(character.clone());
+                }
+                Ok((Some(self), txn.bind(cursor.space().clone())))
             }
             Self::Jetpack { active } => Ok((
                 Some(Self::Jetpack { active: !active }),
@@ -268,8 +306,23 @@ impl Tool {
         }
     }
 
+    /// Returns which kind of tool this is, disregarding its parameters.
+    pub fn kind(&self) -> ToolKind {
+        match self {
+            Tool::Activate => ToolKind::Activate,
+            Tool::RemoveBlock { .. } => ToolKind::RemoveBlock,
+            Tool::Block(_) => ToolKind::Block,
+            Tool::InfiniteBlocks(_) => ToolKind::InfiniteBlocks,
+            Tool::CopyFromSpace => ToolKind::CopyFromSpace,
+            Tool::EditBlock => ToolKind::EditBlock,
+            Tool::PushPull => ToolKind::PushPull,
+            Tool::Jetpack { .. } => ToolKind::Jetpack,
+            Tool::ExternalAction { .. } => ToolKind::ExternalAction,
+        }
+    }
+
     /// Specifies a limit on the number of this item that should be combined in a single
-    /// [`Slot`].
+    /// [`Slot`], unless [`StackLimits`](inv::StackLimits) override it.
     pub(crate) fn stack_limit(&self) -> StackLimit {
         use StackLimit::{One, Standard};
         match self {
@@ -286,6 +339,23 @@ impl Tool {
     }
 }
 
+/// The kinds of [`Tool`], without their parameters; used to specify
+/// [`StackLimits`](inv::StackLimits) overrides.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+#[non_exhaustive]
+#[allow(missing_docs)] // each variant corresponds to the `Tool` variant of the same name
+pub enum ToolKind {
+    Activate,
+    RemoveBlock,
+    Block,
+    InfiniteBlocks,
+    CopyFromSpace,
+    EditBlock,
+    PushPull,
+    Jetpack,
+    ExternalAction,
+}
+
 impl VisitRefs for Tool {
     fn visit_refs(&self, visitor: &mut dyn RefVisitor) {
         match self {
@@ -337,11 +407,15 @@ impl ToolInput {
         if space[cube] != old_block {
             return Err(ToolError::Obstacle);
         }
+        if !space.permits_modification(cube, self.character.as_ref()) {
+            return Err(ToolError::Protected);
+        }
 
-        Ok(
-            SpaceTransaction::set_cube(cube, Some(old_block), Some(new_block))
-                .bind(space_ref.clone()),
-        )
+        let mut txn = SpaceTransaction::set_cube(cube, Some(old_block), Some(new_block));
+        if let Some(character) = &self.character {
+            txn = txn.on_behalf_of(character.clone());
+        }
+        Ok(txn.bind(space_ref.clone()))
     }
 
     /// As [`Self::set_cube`] but also applying rotation (or other transformations
@@ -372,11 +446,19 @@ impl ToolInput {
                     .unwrap_or(GridRotation::IDENTITY)
             }
         };
-        self.set_cube(
-            cursor.cube() + cursor.face_selected().normal_vector(),
-            old_block,
-            new_block.rotate(rotation),
-        )
+        let cube = cursor.cube() + cursor.face_selected().normal_vector();
+        let new_block = new_block.rotate(rotation);
+        Ok(self
+            .set_cube(cube, old_block, new_block.clone())?
+            .merge(UniverseTransaction::event(GameEvent::BlockPlaced {
+                space: cursor.space().clone(),
+                cube,
+                block: new_block,
+                character: self.character.clone(),
+            }))
+            .unwrap()
+            .merge(cube_sound(Fluff::PlaceBlockGeneric, cube))
+            .unwrap())
     }
 
     /// Returns a [`Cursor`] indicating what blocks the tool should act on, if it is
@@ -407,6 +489,11 @@ impl ToolInput {
     }
 }
 
+/// Returns a transaction which emits `sound` from the middle of `cube`.
+fn cube_sound(sound: Fluff, cube: GridPoint) -> UniverseTransaction {
+    UniverseTransaction::sound(SoundEvent::new(sound, Some(cube_to_midpoint(cube))))
+}
+
 /// Ways that a tool can fail.
 #[derive(Clone, Debug, Eq, Hash, PartialEq, thiserror::Error)]
 #[non_exhaustive]
@@ -422,6 +509,10 @@ pub enum ToolError {
     /// Cannot place a block or similar because there's a block occupying the space.
     #[error("there's something in the way")]
     Obstacle,
+    /// The target cube is in a [`ProtectedRegion`](crate::space::ProtectedRegion) which
+    /// does not permit the character to modify it.
+    #[error("this area is protected")]
+    Protected,
     /// The tool requires a target cube and none was present.
     #[error("nothing is selected")]
     NothingSelected,
@@ -516,13 +607,15 @@ impl<'a, T: arbitrary::Arbitrary<'a>> arbitrary::Arbitrary<'a> for EphemeralOpaq
 mod tests {
     use super::*;
     use crate::block::Primitive;
-    use crate::character::cursor_raycast;
+    use crate::character::{cursor_raycast, CursorPolicy};
     use crate::content::{make_some_blocks, make_some_voxel_blocks};
     use crate::inv::Slot;
-    use crate::math::{FreeCoordinate, GridRotation};
+    use crate::math::{FreeCoordinate, GridAab, GridRotation};
+    use crate::physics::BodyTransaction;
     use crate::raycast::Ray;
     use crate::raytracer::print_space;
-    use crate::space::Space;
+    use crate::space::{ProtectedRegion, RegionAccess, Space};
+    use crate::time::{practically_infinite_deadline, Tick};
     use crate::transaction;
     use crate::universe::{UBorrow, URef, Universe};
     use crate::util::YieldProgress;
@@ -559,6 +652,7 @@ mod tests {
                     Ray::new([0., 0.5, 0.5], [1., 0., 0.]),
                     &self.space_ref,
                     FreeCoordinate::INFINITY,
+                    CursorPolicy::default(),
                 ),
                 character: Some(self.character_ref.clone()),
             }
@@ -610,6 +704,13 @@ mod tests {
         fn character(&self) -> UBorrow<Character> {
             self.character_ref.read().unwrap()
         }
+        /// Returns the items held by all item entities in the universe.
+        fn items(&self) -> Vec<Tool> {
+            self.universe
+                .iter_by_type::<Entity>()
+                .filter_map(|(_, entity)| entity.read().unwrap().item().cloned())
+                .collect()
+        }
     }
 
     async fn dummy_icons() -> BlockProvider<Icons> {
@@ -667,18 +768,36 @@ mod tests {
 
             let expected_delete =
                 SpaceTransaction::set_cube([1, 0, 0], Some(existing.clone()), Some(AIR))
-                    .bind(tester.space_ref.clone());
+                    .on_behalf_of(tester.character_ref.clone())
+                    .bind(tester.space_ref.clone())
+                    .merge(UniverseTransaction::event(GameEvent::BlockBroken {
+                        space: tester.space_ref.clone(),
+                        cube: GridPoint::new(1, 0, 0),
+                        block: existing.clone(),
+                        character: Some(tester.character_ref.clone()),
+                    }))
+                    .unwrap()
+                    .merge(cube_sound(
+                        Fluff::RemoveBlockGeneric,
+                        GridPoint::new(1, 0, 0),
+                    ))
+                    .unwrap();
             assert_eq!(
                 actual_transaction,
                 if keep {
                     expected_delete
                         .merge(
                             CharacterTransaction::inventory(InventoryTransaction::insert([
-                                Tool::Block(existing),
+                                Tool::Block(existing.clone()),
                             ]))
                             .bind(tester.character_ref.clone()),
                         )
                         .unwrap()
+                        .merge(UniverseTransaction::event(GameEvent::ItemPickedUp {
+                            character: tester.character_ref.clone(),
+                            item: Tool::Block(existing),
+                        }))
+                        .unwrap()
                 } else {
                     expected_delete
                 }
@@ -692,6 +811,33 @@ mod tests {
         }
     }
 
+    #[test]
+    fn use_remove_block_drops_items() {
+        let [existing] = make_some_blocks();
+        let mut tester = ToolTester::new(|space| {
+            space.set((1, 0, 0), &existing).unwrap();
+            space.set_item_drops(true);
+        });
+        let item = Tool::Block(existing.clone());
+        tester
+            .equip_use_commit(Tool::RemoveBlock { keep: true })
+            .unwrap();
+        assert_eq!(&tester.space()[(1, 0, 0)], &AIR);
+        assert_eq!(tester.items(), vec![item.clone()]);
+        assert_eq!(tester.character().inventory().count_of(&item), 0);
+
+        // Walking up to the item picks it up.
+        CharacterTransaction::body(BodyTransaction::teleport([1.5, 1.5, 0.5]))
+            .bind(tester.character_ref.clone())
+            .execute(&mut tester.universe, &mut transaction::no_outputs)
+            .unwrap();
+        tester
+            .universe
+            .step(Tick::arbitrary(), practically_infinite_deadline());
+        assert_eq!(tester.items(), vec![]);
+        assert_eq!(tester.character().inventory().count_of(&item), 1);
+    }
+
     #[test]
     fn use_remove_block_without_target() {
         let tester = ToolTester::new(|_space| {});
@@ -727,7 +873,20 @@ mod tests {
 
             let expected_cube_transaction =
                 SpaceTransaction::set_cube([0, 0, 0], Some(AIR), Some(tool_block.clone()))
-                    .bind(tester.space_ref.clone());
+                    .on_behalf_of(tester.character_ref.clone())
+                    .bind(tester.space_ref.clone())
+                    .merge(UniverseTransaction::event(GameEvent::BlockPlaced {
+                        space: tester.space_ref.clone(),
+                        cube: GridPoint::new(0, 0, 0),
+                        block: tool_block.clone(),
+                        character: Some(tester.character_ref.clone()),
+                    }))
+                    .unwrap()
+                    .merge(cube_sound(
+                        Fluff::PlaceBlockGeneric,
+                        GridPoint::new(0, 0, 0),
+                    ))
+                    .unwrap();
             assert_eq!(
                 transaction,
                 if expect_consume {
@@ -778,14 +937,24 @@ mod tests {
         let transaction = tester
             .equip_and_use_tool(Tool::InfiniteBlocks(tool_block.clone()))
             .unwrap();
+        let rotated_block = tool_block.clone().rotate(GridRotation::CLOCKWISE);
         assert_eq!(
             transaction,
-            SpaceTransaction::set_cube(
-                [0, 0, 0],
-                Some(AIR),
-                Some(tool_block.clone().rotate(GridRotation::CLOCKWISE))
-            )
-            .bind(tester.space_ref.clone())
+            SpaceTransaction::set_cube([0, 0, 0], Some(AIR), Some(rotated_block.clone()))
+                .on_behalf_of(tester.character_ref.clone())
+                .bind(tester.space_ref.clone())
+                .merge(UniverseTransaction::event(GameEvent::BlockPlaced {
+                    space: tester.space_ref.clone(),
+                    cube: GridPoint::new(0, 0, 0),
+                    block: rotated_block,
+                    character: Some(tester.character_ref.clone()),
+                }))
+                .unwrap()
+                .merge(cube_sound(
+                    Fluff::PlaceBlockGeneric,
+                    GridPoint::new(0, 0, 0)
+                ))
+                .unwrap()
         );
     }
 
@@ -832,6 +1001,74 @@ mod tests {
         }
     }
 
+    #[test]
+    fn use_block_in_protected_region() {
+        let [existing, tool_block] = make_some_blocks();
+        let tester = ToolTester::new(|space| {
+            space.set((1, 0, 0), &existing).unwrap();
+            space.add_protected_region(ProtectedRegion::new(
+                GridAab::single_cube(GridPoint::new(0, 0, 0)),
+                RegionAccess::ReadOnly,
+            ));
+        });
+        assert_eq!(
+            tester.equip_and_use_tool(Tool::InfiniteBlocks(tool_block)),
+            Err(ToolError::Protected)
+        );
+        assert_eq!(
+            tester.equip_and_use_tool(Tool::RemoveBlock { keep: false }),
+            Ok(
+                SpaceTransaction::set_cube([1, 0, 0], Some(existing.clone()), Some(AIR))
+                    .on_behalf_of(tester.character_ref.clone())
+                    .bind(tester.space_ref.clone())
+                    .merge(UniverseTransaction::event(GameEvent::BlockBroken {
+                        space: tester.space_ref.clone(),
+                        cube: GridPoint::new(1, 0, 0),
+                        block: existing,
+                        character: Some(tester.character_ref.clone()),
+                    }))
+                    .unwrap()
+                    .merge(cube_sound(
+                        Fluff::RemoveBlockGeneric,
+                        GridPoint::new(1, 0, 0)
+                    ))
+                    .unwrap()
+            )
+        );
+    }
+
+    #[test]
+    fn push_pull_acts_on_behalf_of_character() {
+        let [existing] = make_some_blocks();
+        let tester = ToolTester::new(|space| {
+            space.set((1, 0, 0), &existing).unwrap();
+        });
+        assert_eq!(
+            tester.equip_and_use_tool(Tool::PushPull),
+            Ok(
+                block::Move::start_transaction(&existing, GridPoint::new(1, 0, 0), Face6::PX, 8)
+                    .on_behalf_of(tester.character_ref.clone())
+                    .bind(tester.space_ref.clone())
+            )
+        );
+    }
+
+    #[test]
+    fn push_pull_into_protected_region() {
+        let [existing] = make_some_blocks();
+        let tester = ToolTester::new(|space| {
+            space.set((1, 0, 0), &existing).unwrap();
+            space.add_protected_region(ProtectedRegion::new(
+                GridAab::single_cube(GridPoint::new(2, 0, 0)),
+                RegionAccess::ReadOnly,
+            ));
+        });
+        assert_eq!(
+            tester.equip_and_use_tool(Tool::PushPull),
+            Err(ToolError::Protected)
+        );
+    }
+
     #[test]
     fn use_block_without_target() {
         let [tool_block] = make_some_blocks();
@@ -87,6 +87,8 @@
 //!   This feature does not affect the public API, only performance and dependencies.
 //! * `arbitrary`: Adds implementations of the [`arbitrary::Arbitrary`] trait for
 //!   fuzzing / property testing on types defined by this crate.
+//! * `scripting`: Adds `script::ScriptBehavior`, which runs [`script::Script`]s
+//!   written in the [Rhai](https://rhai.rs/) language.
 //!
 //! ## Platform compatibility
 //!
@@ -176,6 +178,8 @@ pub mod chunking;
 #[doc(hidden)] // Exported only for use by all_is_cubes_content
 pub mod content;
 pub mod drawing;
+pub mod entity;
+pub mod event;
 pub mod fluff;
 #[doc(hidden)] // Exported only for use by all_is_cubes_gpu
 pub mod intalloc;
@@ -186,6 +190,8 @@ pub mod physics;
 pub mod raycast;
 pub mod raytracer;
 mod save; // nothing exported; all impls and tests
+pub mod script;
+pub mod sound;
 pub mod space;
 pub mod time;
 pub mod transaction;
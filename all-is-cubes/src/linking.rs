@@ -20,7 +20,7 @@ use crate::block::{Block, BlockDef, Primitive};
 use crate::space::SetCubeError;
 use crate::transaction::ExecuteError;
 use crate::universe::{InsertError, Name, URef, Universe};
-use crate::util::YieldProgress;
+use crate::util::{Cancelled, YieldProgress};
 
 fn name_in_module<E: BlockModule>(key: &E) -> Name {
     Name::from(format!("{ns}/{key}", ns = E::namespace()).as_str())
@@ -256,6 +256,16 @@ impl GenError {
     }
 }
 
+impl From<Cancelled> for GenError {
+    fn from(error: Cancelled) -> Self {
+        // Cancellation is not the fault of any particular object.
+        Self {
+            detail: InGenError::Cancelled(error),
+            for_object: None,
+        }
+    }
+}
+
 impl fmt::Display for GenError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         // Don't include `detail` because that's our `Error::source()`.
@@ -320,6 +330,12 @@ pub enum InGenError {
     // TODO: This isn't very coherent; we're just aggregating various errors
     #[error(transparent)]
     Transaction(#[from] ExecuteError),
+
+    /// Generation was stopped early by cancelling the
+    /// [`CancellationToken`](crate::util::CancellationToken) attached to its
+    /// [`YieldProgress`].
+    #[error(transparent)]
+    Cancelled(#[from] Cancelled),
 }
 
 impl InGenError {
@@ -10,7 +10,9 @@
 //! `Weak<Mutex<...>>` or similar multiply-owned mutable structure to aggregate incoming
 //! messages, which will then be read and cleared by a later task.
 
+use std::collections::BTreeMap;
 use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, RwLock, Weak};
 
 mod cell;
@@ -61,14 +63,29 @@ impl<T: Listen> Listen for Arc<T> {
 /// references *some* of the time — making `M` be a reference type can't have a
 /// satisfactory lifetime.
 pub struct Notifier<M> {
-    listeners: RwLock<Vec<DynListener<M>>>,
+    listeners: RwLock<Vec<NotifierEntry<M>>>,
+    /// Whether the warning about having too many listeners has been logged.
+    warned: AtomicBool,
 }
 
+/// A listener registered with a [`Notifier`], and the name of its original type for
+/// diagnostics.
+struct NotifierEntry<M> {
+    listener: DynListener<M>,
+    type_name: &'static str,
+}
+
+/// When a [`Notifier`] has at least this many listeners, it logs a warning, since that
+/// is likely due to listeners being leaked (never becoming not [alive](Listener::alive)),
+/// and makes every notification slow. The warning is logged only once per notifier.
+const LISTENER_COUNT_WARNING_THRESHOLD: usize = 1024;
+
 impl<M: Clone + Send> Notifier<M> {
     /// Constructs a new empty [`Notifier`].
     pub fn new() -> Self {
         Self {
             listeners: Default::default(),
+            warned: AtomicBool::new(false),
         }
     }
 
@@ -105,8 +122,8 @@ impl<M: Clone + Send> Notifier<M> {
 
     /// Deliver a message to all [`Listener`]s.
     pub fn notify(&self, message: M) {
-        for listener in self.listeners.read().unwrap().iter() {
-            listener.receive(message.clone());
+        for entry in self.listeners.read().unwrap().iter() {
+            entry.listener.receive(message.clone());
         }
     }
 
@@ -120,11 +137,24 @@ impl<M: Clone + Send> Notifier<M> {
         listeners.len()
     }
 
+    /// Returns the number of current listeners of each type, after discarding those
+    /// which are not [`alive()`](Listener::alive).
+    ///
+    /// The type names are those given by [`std::any::type_name()`] for the listeners
+    /// as they were passed to [`Listen::listen()`], and are not guaranteed to be stable.
+    /// This operation is intended for diagnostic purposes, such as finding the source
+    /// of listeners which are never dropped.
+    pub fn listener_type_counts(&self) -> BTreeMap<&'static str, usize> {
+        let mut listeners = self.listeners.write().unwrap();
+        Self::cleanup(&mut listeners);
+        type_counts(&listeners)
+    }
+
     /// Discard all dead weak pointers in `listeners`.
-    fn cleanup(listeners: &mut Vec<DynListener<M>>) {
+    fn cleanup(listeners: &mut Vec<NotifierEntry<M>>) {
         let mut i = 0;
         while i < listeners.len() {
-            if listeners[i].alive() {
+            if listeners[i].listener.alive() {
                 i += 1;
             } else {
                 listeners.swap_remove(i);
@@ -140,10 +170,36 @@ impl<M: Clone + Send> Listen for Notifier<M> {
         if !listener.alive() {
             return;
         }
+        let type_name = std::any::type_name::<L>();
         let mut listeners = self.listeners.write().unwrap();
         Self::cleanup(&mut listeners);
-        listeners.push(listener.erased());
+        listeners.push(NotifierEntry {
+            listener: listener.erased(),
+            type_name,
+        });
+
+        let count = listeners.len();
+        if count >= LISTENER_COUNT_WARNING_THRESHOLD && !self.warned.swap(true, Ordering::Relaxed) {
+            let (most_common_type, most_common_count) = type_counts(&listeners)
+                .into_iter()
+                .max_by_key(|&(_, n)| n)
+                .unwrap_or_default();
+            log::warn!(
+                "Notifier<{message_type}> has {count} listeners, which may be leaked; \
+                {most_common_count} are of type {most_common_type}",
+                message_type = std::any::type_name::<M>(),
+            );
+        }
+    }
+}
+
+/// Helper for [`Notifier::listener_type_counts()`].
+fn type_counts<M>(listeners: &[NotifierEntry<M>]) -> BTreeMap<&'static str, usize> {
+    let mut counts = BTreeMap::new();
+    for entry in listeners {
+        *counts.entry(entry.type_name).or_insert(0) += 1;
     }
+    counts
 }
 
 impl<M: Clone + Send> Default for Notifier<M> {
@@ -296,6 +352,29 @@ mod tests {
         assert_eq!(format!("{cn:?}"), "Notifier(1)");
     }
 
+    #[test]
+    fn notifier_listener_type_counts() {
+        let cn: Notifier<u8> = Notifier::new();
+        let sink = Sink::new();
+        cn.listen(sink.listener());
+        cn.listen(sink.listener());
+        let flag = DirtyFlag::new(false);
+        cn.listen(flag.listener());
+        assert_eq!(
+            cn.listener_type_counts(),
+            BTreeMap::from([
+                (std::any::type_name::<SinkListener<u8>>(), 2),
+                (std::any::type_name::<DirtyFlagListener>(), 1),
+            ])
+        );
+
+        drop(sink);
+        assert_eq!(
+            cn.listener_type_counts(),
+            BTreeMap::from([(std::any::type_name::<DirtyFlagListener>(), 1)])
+        );
+    }
+
     #[test]
     #[allow(clippy::vtable_address_comparisons)]
     fn erased_listener() {
@@ -972,6 +972,92 @@ impl<V> GridArray<V> {
             .map(|index| &mut self.contents[index])
     }
 
+    /// Iterates over all the cubes and values in this array, in the ordering used by
+    /// [`GridAab::interior_iter()`].
+    pub fn iter(&self) -> impl Iterator<Item = (GridPoint, &V)> + '_ {
+        self.bounds.interior_iter().zip(self.contents.iter())
+    }
+
+    /// Iterates by mutable reference over all the cubes and values in this array, in the
+    /// ordering used by [`GridAab::interior_iter()`].
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (GridPoint, &mut V)> + '_ {
+        self.bounds.interior_iter().zip(self.contents.iter_mut())
+    }
+
+    /// Returns a copy of this array rearranged by `transform`, so that each element is at
+    /// the position that [`GridMatrix::transform_cube()`] maps its original position to.
+    ///
+    /// Returns [`None`] if the matrix has any scaling or skew, i.e. it is not a rotation
+    /// or reflection and translation.
+    ///
+    /// This is more efficient than transforming each cube individually, because it only
+    /// needs to compute the position of one corner and then steps through the original
+    /// elements.
+    ///
+    /// ```
+    /// use all_is_cubes::math::{GridAab, GridArray, GridRotation};
+    ///
+    /// let array = GridArray::from_fn(GridAab::from_lower_size([0, 0, 0], [2, 1, 1]), |p| p.x);
+    /// let rotated = array
+    ///     .transform(GridRotation::CLOCKWISE.to_positive_octant_matrix(2))
+    ///     .unwrap();
+    /// assert_eq!(rotated.bounds(), GridAab::from_lower_size([1, 0, 0], [1, 1, 2]));
+    /// assert_eq!(rotated[(1, 0, 0)], 0);
+    /// assert_eq!(rotated[(1, 0, 1)], 1);
+    /// ```
+    #[must_use]
+    pub fn transform(&self, transform: GridMatrix) -> Option<GridArray<V>>
+    where
+        V: Clone,
+    {
+        let (rotation, translation) = transform.decompose()?;
+        let new_bounds = self.bounds.transform(transform)?;
+        if new_bounds.is_empty() {
+            return Some(GridArray {
+                bounds: new_bounds,
+                contents: Box::new([]),
+            });
+        }
+
+        // The linear part of the inverse transform tells us how a step along each axis
+        // of the output corresponds to a step through the input, which we then convert
+        // to steps through the linear contents.
+        let inverse_rotation = rotation.inverse().to_rotation_matrix();
+        let inverse = inverse_rotation * GridMatrix::from_translation(-translation);
+        let start_index = self
+            .bounds
+            .index(inverse.transform_cube(new_bounds.lower_bounds()))
+            .expect("shouldn't happen: transformed bounds inconsistent");
+        let sizes = self.bounds.sizes.map(|c| c as isize);
+        let index_step = |axis_in_input: GridVector| -> isize {
+            (axis_in_input.x as isize * sizes.y + axis_in_input.y as isize) * sizes.z
+                + axis_in_input.z as isize
+        };
+        let x_step = index_step(inverse_rotation.x);
+        let y_step = index_step(inverse_rotation.y);
+        let z_step = index_step(inverse_rotation.z);
+
+        let mut contents = Vec::with_capacity(new_bounds.volume());
+        let mut x_index = start_index as isize;
+        for _ in new_bounds.x_range() {
+            let mut y_index = x_index;
+            for _ in new_bounds.y_range() {
+                let mut z_index = y_index;
+                for _ in new_bounds.z_range() {
+                    contents.push(self.contents[z_index as usize].clone());
+                    z_index += z_step;
+                }
+                y_index += y_step;
+            }
+            x_index += x_step;
+        }
+
+        Some(GridArray {
+            bounds: new_bounds,
+            contents: contents.into_boxed_slice(),
+        })
+    }
+
     /// Adds to the origin of the array without affecting the contents.
     ///
     /// Panics if this would cause numeric overflow.
@@ -1101,6 +1187,7 @@ impl fmt::Debug for RangeWithLength {
 mod tests {
     use super::*;
     use crate::block::Resolution::*;
+    use crate::math::GridRotation;
     use indoc::indoc;
 
     #[test]
@@ -1380,6 +1467,45 @@ mod tests {
         );
     }
 
+    #[test]
+    fn array_iter_mut() {
+        let bounds = GridAab::from_lower_size([10, 0, 0], [2, 2, 1]);
+        let mut array = GridArray::repeat(bounds, 0);
+        for (cube, value) in array.iter_mut() {
+            *value = cube.x * 10 + cube.y;
+        }
+        assert_eq!(array, GridArray::from_fn(bounds, |p| p.x * 10 + p.y));
+        assert_eq!(
+            array.iter().map(|(_, &v)| v).collect::<Vec<_>>(),
+            vec![100, 101, 110, 111]
+        );
+    }
+
+    /// Compare [`GridArray::transform()`] with transforming each cube individually.
+    #[test]
+    fn array_transform_matches_transform_cube() {
+        let bounds = GridAab::from_lower_size([-3, 1, 5], [2, 3, 4]);
+        let array = GridArray::from_fn(bounds, |p| p);
+        for rotation in GridRotation::ALL {
+            let matrix = GridMatrix::from_translation([7, -2, 3]) * rotation.to_rotation_matrix();
+            let transformed = array.transform(matrix).unwrap();
+            assert_eq!(transformed.bounds(), bounds.transform(matrix).unwrap());
+            for (cube, &value) in array.iter() {
+                assert_eq!(
+                    transformed[matrix.transform_cube(cube)],
+                    value,
+                    "{rotation:?} {cube:?}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn array_transform_scale_fails() {
+        let array = GridArray::from_element(1);
+        assert_eq!(array.transform(GridMatrix::from_scale(2)), None);
+    }
+
     #[cfg(feature = "arbitrary")]
     #[test]
     fn arbitrary_grid_aab_size_hint() {
@@ -13,9 +13,9 @@ pub(crate) const POSITION_EPSILON: FreeCoordinate = 1e-6 * 1e-6;
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::block::{Resolution, AIR};
+    use crate::block::{Block, Resolution, AIR};
     use crate::content::{make_slab, make_some_blocks};
-    use crate::math::{Aab, CubeFace, Face7, Geometry, GridAab, GridPoint};
+    use crate::math::{Aab, CubeFace, Face7, Geometry, GridAab, GridPoint, Rgba};
     use crate::space::{Space, SpacePhysics};
     use crate::time::Tick;
     use crate::universe::Universe;
@@ -66,6 +66,43 @@ mod tests {
         assert_eq!(body.position, Point3::new(6.0, -133.0, 0.0));
     }
 
+    #[test]
+    fn terminal_velocity_limits_falling() {
+        let mut space = Space::empty_positive(1, 1, 1);
+        space.set_physics(SpacePhysics {
+            terminal_velocity: Some(NotNan::from(5)),
+            ..SpacePhysics::default()
+        });
+        let mut body = Body {
+            velocity: Vector3::new(2.0, 0.0, 0.0),
+            flying: false,
+            ..test_body()
+        };
+        body.step(Tick::from_seconds(1.0), Some(&space), collision_noop);
+        // Only the component along gravity is limited.
+        assert_eq!(body.velocity, Vector3::new(2.0, -5.0, 0.0));
+    }
+
+    #[test]
+    fn air_drag_slows() {
+        let mut space = Space::empty_positive(1, 1, 1);
+        space.set_physics(SpacePhysics {
+            gravity: Vector3::zero(),
+            air_drag: NotNan::from(1),
+            ..SpacePhysics::default()
+        });
+        let mut body = Body {
+            velocity: Vector3::new(2.0, 0.0, 0.0),
+            ..test_body()
+        };
+        body.step(Tick::from_seconds(1.0), Some(&space), collision_noop);
+        assert!(
+            (body.velocity.x - 2.0 / std::f64::consts::E).abs() < 1e-9,
+            "{:?}",
+            body.velocity
+        );
+    }
+
     #[test]
     fn paused_does_not_move() {
         let mut body = Body {
@@ -159,6 +196,67 @@ mod tests {
         }
     }
 
+    #[test]
+    fn collision_filter_excludes_groups() {
+        let [block] = make_some_blocks();
+        let mut space = Space::empty_positive(1, 1, 1);
+        space.set((0, 0, 0), &block).unwrap();
+        let mut body = Body {
+            velocity: Vector3::new(2.0, 0.0, 0.0),
+            flying: false,
+            collides_with: CollisionGroups::empty(),
+            ..test_body()
+        };
+
+        let mut contacts = Vec::new();
+        body.step(Tick::from_seconds(1.0), Some(&space), |c| contacts.push(c));
+
+        assert!(body.position.y < 0.0, "{:?}", body.position);
+        assert_eq!(contacts, vec![]);
+    }
+
+    #[test]
+    fn colliding_cubes_filter() {
+        let [block] = make_some_blocks();
+        let fluid = Block::builder()
+            .color(Rgba::new(0.0, 0.0, 1.0, 0.5))
+            .collision_groups(CollisionGroups::FLUID)
+            .build();
+        let mut space = Space::empty_positive(2, 1, 1);
+        space.set((0, 0, 0), &block).unwrap();
+        space.set((1, 0, 0), &fluid).unwrap();
+        let aab = Aab::new(0.0, 2.0, 0.0, 1.0, 0.0, 1.0);
+
+        let cubes = |filter| colliding_cubes(&space, aab, filter).collect::<Vec<_>>();
+        assert_eq!(cubes(CollisionGroups::SOLID), vec![GridPoint::new(0, 0, 0)]);
+        assert_eq!(cubes(CollisionGroups::FLUID), vec![GridPoint::new(1, 0, 0)]);
+        assert_eq!(cubes(CollisionGroups::empty()), vec![]);
+    }
+
+    #[test]
+    fn fluid_drags_without_blocking() {
+        let fluid = Block::builder()
+            .color(Rgba::new(0.0, 0.0, 1.0, 0.5))
+            .collision_groups(CollisionGroups::FLUID)
+            .build();
+        let mut space = Space::empty_positive(4, 4, 4);
+        space.fill_uniform(space.bounds(), &fluid).unwrap();
+        let mut body = Body {
+            position: Point3::new(1.5, 1.5, 1.5),
+            velocity: Vector3::new(1.0, 0.0, 0.0),
+            flying: true,
+            ..test_body()
+        };
+
+        let mut contacts = Vec::new();
+        body.step(Tick::from_seconds(0.5), Some(&space), |c| contacts.push(c));
+
+        let expected_velocity = (-1.0f64).exp();
+        assert!((body.velocity.x - expected_velocity).abs() < 1e-9);
+        assert!((body.position.x - (1.5 + expected_velocity * 0.5)).abs() < 1e-9);
+        assert_eq!(contacts, vec![]);
+    }
+
     #[test]
     fn push_out_simple() {
         let [block] = make_some_blocks();
@@ -2,14 +2,12 @@ use cgmath::{EuclideanSpace as _, InnerSpace as _, Point3, Vector3, Zero};
 use ordered_float::NotNan;
 use std::fmt;
 
-use super::collision::{
-    aab_raycast, collide_along_ray, find_colliding_cubes, nudge_on_ray, Contact,
-};
+use super::collision::{collide_along_ray, find_colliding_cubes, CollisionGroups, Contact};
 use crate::block::{BlockCollision, Resolution};
 use crate::math::{Aab, Face7, FreeCoordinate, Geometry as _};
 use crate::physics::{StopAt, POSITION_EPSILON};
-use crate::raycast::Ray;
-use crate::space::Space;
+use crate::raycast::{nudge_on_ray, AabRaycaster, Ray};
+use crate::space::{Space, SpacePhysics};
 use crate::time::Tick;
 use crate::transaction::{self, Transaction};
 use crate::util::{ConciseDebug, CustomFormat, StatusText};
@@ -25,6 +23,10 @@ pub(crate) const VELOCITY_MAGNITUDE_LIMIT: FreeCoordinate = 1e4_f64;
 pub(crate) const VELOCITY_MAGNITUDE_LIMIT_SQUARED: FreeCoordinate =
     VELOCITY_MAGNITUDE_LIMIT * VELOCITY_MAGNITUDE_LIMIT;
 
+/// Rate (per second) at which a body's velocity decays while it is within a
+/// [`CollisionGroups::FLUID`] block that it does not collide with.
+const FLUID_DRAG: FreeCoordinate = 2.0;
+
 /// An object with a position, velocity, and collision volume.
 /// What it collides with is determined externally.
 #[derive(Clone, PartialEq)]
@@ -46,6 +48,18 @@ pub struct Body {
     /// Is this body not subject to collision?
     pub noclip: bool,
 
+    /// Which [`CollisionGroups`] this body belongs to.
+    ///
+    /// Bodies do not yet collide with each other, so this is currently used only by
+    /// queries which look for particular kinds of bodies.
+    pub collision_groups: CollisionGroups,
+
+    /// Which [`CollisionGroups`] of blocks obstruct this body's movement.
+    ///
+    /// Blocks in [`CollisionGroups::FLUID`] which are not in these groups slow the body
+    /// instead.
+    pub collides_with: CollisionGroups,
+
     /// Yaw of the camera look direction, in degrees clockwise from looking towards -Z.
     ///
     /// The preferred range is 0 inclusive to 360 exclusive.
@@ -71,6 +85,8 @@ impl fmt::Debug for Body {
             collision_box,
             flying,
             noclip,
+            collision_groups,
+            collides_with,
             yaw,
             pitch,
         } = self;
@@ -80,6 +96,8 @@ impl fmt::Debug for Body {
             .field("collision_box", &collision_box)
             .field("flying", &flying)
             .field("noclip", &noclip)
+            .field("collision_groups", &collision_groups)
+            .field("collides_with", &collides_with)
             .field("yaw", &yaw)
             .field("pitch", &pitch)
             .finish()
@@ -119,6 +137,8 @@ impl Body {
             collision_box: collision_box.into(),
             flying: false,
             noclip: false,
+            collision_groups: CollisionGroups::empty(),
+            collides_with: CollisionGroups::default(),
             yaw: 0.0,
             pitch: 0.0,
         }
@@ -127,9 +147,9 @@ impl Body {
     /// Advances time for the body.
     ///
     /// If `colliding_space` is present then the body may collide with blocks in that space
-    /// (constraining possible movement) and `collision_callback` will be called with all
-    /// such blocks. It is not guaranteed that `collision_callback` will be called only once
-    /// per block.
+    /// which are in its [`collides_with`](Self::collides_with) groups (constraining
+    /// possible movement) and `collision_callback` will be called with all such blocks.
+    /// It is not guaranteed that `collision_callback` will be called only once per block.
     pub fn step<CC>(
         &mut self,
         tick: Tick,
@@ -167,6 +187,17 @@ impl Body {
         if !self.flying && !tick.paused() {
             if let Some(space) = colliding_space {
                 self.velocity += space.physics().gravity.map(|c| c.into_inner()) * dt;
+                self.apply_terminal_velocity(space.physics());
+            }
+        }
+
+        if !tick.paused() {
+            if let Some(space) = colliding_space {
+                let air_drag = space.physics().air_drag.into_inner();
+                if air_drag > 0.0 {
+                    self.velocity *= (-air_drag * dt).exp();
+                }
+                self.apply_fluid_drag(space, dt);
             }
         }
 
@@ -242,6 +273,7 @@ impl Body {
             space,
             movement_ignoring_collision,
             self.collision_box,
+            self.collides_with,
             collision_callback,
             StopAt::NotAlreadyColliding,
         );
@@ -292,9 +324,40 @@ impl Body {
         }
     }
 
+    /// Limits the body's speed in the direction of gravity to the space's
+    /// [`SpacePhysics::terminal_velocity`], if it has one.
+    fn apply_terminal_velocity(&mut self, physics: &SpacePhysics) {
+        let Some(terminal_velocity) = physics.terminal_velocity else {
+            return;
+        };
+        let gravity = physics.gravity.map(NotNan::into_inner);
+        if gravity.magnitude2() == 0.0 {
+            return;
+        }
+        let down = gravity.normalize();
+        let excess = self.velocity.dot(down) - terminal_velocity.into_inner().max(0.0);
+        if excess > 0.0 {
+            self.velocity -= down * excess;
+        }
+    }
+
+    /// Slow the body if it is within a fluid it does not collide with.
+    fn apply_fluid_drag(&mut self, space: &Space, dt: f64) {
+        let fluid = CollisionGroups::FLUID;
+        if self.collides_with.contains(fluid) {
+            return;
+        }
+        let in_fluid = find_colliding_cubes(space, self.collision_box_abs(), fluid)
+            .next()
+            .is_some();
+        if in_fluid {
+            self.velocity *= (-FLUID_DRAG * dt).exp();
+        }
+    }
+
     /// Check if we're intersecting any blocks and fix that if so.
     fn push_out(&mut self, space: &Space) -> Option<Vector3<FreeCoordinate>> {
-        let colliding = find_colliding_cubes(space, self.collision_box_abs())
+        let colliding = find_colliding_cubes(space, self.collision_box_abs(), self.collides_with)
             .next()
             .is_some();
         if colliding {
@@ -341,8 +404,14 @@ impl Body {
             let direction = direction.normalize(); // TODO: set this to a max distance
             let ray = Ray::new(self.position, direction);
 
-            let end =
-                collide_along_ray(space, ray, self.collision_box, |_| {}, StopAt::EmptySpace)?;
+            let end = collide_along_ray(
+                space,
+                ray,
+                self.collision_box,
+                self.collides_with,
+                |_| {},
+                StopAt::EmptySpace,
+            )?;
 
             let nudged_distance = end.t_distance + POSITION_EPSILON;
             Some((
@@ -352,10 +421,10 @@ impl Body {
         } else {
             let ray = Ray::new(self.position, direction);
             // TODO: upper bound on distance to try
-            'raycast: for ray_step in aab_raycast(self.collision_box, ray, true) {
+            'raycast: for ray_step in AabRaycaster::new_reversed(self.collision_box, ray) {
                 let adjusted_segment = nudge_on_ray(
                     self.collision_box,
-                    ray.scale_direction(ray_step.t_distance()),
+                    ray_step.segment(),
                     ray_step.face(),
                     Resolution::R1,
                     true,
@@ -365,13 +434,17 @@ impl Body {
                     .translate(adjusted_segment.unit_endpoint().to_vec());
                 for cube in step_aab.round_up_to_grid().interior_iter() {
                     // TODO: refactor to combine this with other collision attribute tests
-                    match space.get_evaluated(cube).attributes.collision {
+                    let attributes = &space.get_evaluated(cube).attributes;
+                    if !self.collides_with.intersects(attributes.collision_groups) {
+                        continue;
+                    }
+                    match attributes.collision {
                         BlockCollision::Hard => {
                             // Not a clear space
                             continue 'raycast;
                         }
                         BlockCollision::None => {}
-                        BlockCollision::Recur => {
+                        BlockCollision::Recur | BlockCollision::Boxes => {
                             // TODO: Either check collision, or continue
                             //continue 'raycast;
                         }
@@ -507,6 +580,21 @@ pub struct BodyTransaction {
     // TODO: Better strategy than just having public fields
     #[allow(missing_docs)]
     pub delta_yaw: FreeCoordinate,
+    /// If not [`None`], the body is moved to this position and stopped.
+    pub set_position: Option<Point3<FreeCoordinate>>,
+}
+
+impl BodyTransaction {
+    /// Moves the body to `position`, and sets its velocity to zero so that it does not
+    /// carry its previous motion to the new location.
+    ///
+    /// The transaction fails if `position` is not finite.
+    pub fn teleport(position: impl Into<Point3<FreeCoordinate>>) -> Self {
+        Self {
+            set_position: Some(position.into()),
+            ..Default::default()
+        }
+    }
 }
 
 impl transaction::Transactional for Body {
@@ -518,7 +606,14 @@ impl Transaction<Body> for BodyTransaction {
     type Output = transaction::NoOutput;
 
     fn check(&self, _body: &Body) -> Result<Self::CommitCheck, transaction::PreconditionFailed> {
-        // No conflicts currently possible.
+        if let Some(position) = self.set_position {
+            if !(position.x.is_finite() && position.y.is_finite() && position.z.is_finite()) {
+                return Err(transaction::PreconditionFailed {
+                    location: "BodyTransaction",
+                    problem: "teleport position is not finite",
+                });
+            }
+        }
         Ok(())
     }
 
@@ -529,6 +624,10 @@ impl Transaction<Body> for BodyTransaction {
         _outputs: &mut dyn FnMut(Self::Output),
     ) -> Result<(), transaction::CommitError> {
         body.yaw += self.delta_yaw;
+        if let Some(position) = self.set_position {
+            body.position = position;
+            body.velocity = Vector3::zero();
+        }
         Ok(())
     }
 }
@@ -538,13 +637,17 @@ impl transaction::Merge for BodyTransaction {
 
     fn check_merge(
         &self,
-        _other: &Self,
+        other: &Self,
     ) -> Result<Self::MergeCheck, transaction::TransactionConflict> {
+        if self.set_position.is_some() && other.set_position.is_some() {
+            return Err(transaction::TransactionConflict {});
+        }
         Ok(())
     }
 
     fn commit_merge(mut self, other: Self, (): Self::MergeCheck) -> Self {
         self.delta_yaw += other.delta_yaw;
+        self.set_position = self.set_position.or(other.set_position);
         self
     }
 }
@@ -595,16 +698,31 @@ mod tests {
         // additive rather than conflicting transactions well
         TransactionTester::new()
             .transaction(BodyTransaction::default(), |_, _| Ok(()))
-            .transaction(BodyTransaction { delta_yaw: 10.0 }, |before, after| {
-                if false {
-                    // TODO: figure out how to make this assert work in the presence of more transactions
-                    let expected = &Body {
-                        yaw: before.yaw + 10.0,
-                        ..before.clone()
-                    };
-                    if after != expected {
-                        return Err(format!("unequal to {expected:#?}").into());
+            .transaction(
+                BodyTransaction {
+                    delta_yaw: 10.0,
+                    ..Default::default()
+                },
+                |before, after| {
+                    if false {
+                        // TODO: figure out how to make this assert work in the presence of more transactions
+                        let expected = &Body {
+                            yaw: before.yaw + 10.0,
+                            ..before.clone()
+                        };
+                        if after != expected {
+                            return Err(format!("unequal to {expected:#?}").into());
+                        }
                     }
+                    Ok(())
+                },
+            )
+            .transaction(BodyTransaction::teleport([1., 2., 3.]), |_, after| {
+                if after.position != Point3::new(1., 2., 3.) {
+                    return Err("position not set".into());
+                }
+                if after.velocity != Vector3::zero() {
+                    return Err("velocity not zeroed".into());
                 }
                 Ok(())
             })
@@ -3,16 +3,15 @@
 use std::collections::HashSet;
 use std::fmt;
 
-use cgmath::{EuclideanSpace as _, InnerSpace as _, Vector3, Zero as _};
+use cgmath::{EuclideanSpace as _, InnerSpace as _, Vector3};
 
-use super::POSITION_EPSILON;
 use crate::block::Evoxels;
 use crate::block::{BlockCollision, EvaluatedBlock, Evoxel, Resolution, Resolution::R1};
 use crate::math::{
-    Aab, CubeFace, Face6, Face7, FreeCoordinate, Geometry, GridAab, GridArray, GridCoordinate,
-    GridPoint, LineVertex,
+    Aab, CubeFace, Face7, FreeCoordinate, Geometry, GridAab, GridArray, GridCoordinate, GridPoint,
+    LineVertex,
 };
-use crate::raycast::{Ray, Raycaster};
+use crate::raycast::{AabRaycaster, Ray};
 use crate::space::Space;
 use crate::util::{ConciseDebug, CustomFormat, MapExtend};
 
@@ -141,6 +140,47 @@ impl Geometry for Contact {
     }
 }
 
+bitflags::bitflags! {
+    /// Categories of things that take part in collision, used to decide which things
+    /// obstruct which others.
+    ///
+    /// Each block belongs to the groups given by its
+    /// [`BlockAttributes::collision_groups`](crate::block::BlockAttributes::collision_groups),
+    /// and each [`Body`](crate::physics::Body) is obstructed only by blocks belonging to at
+    /// least one of its [`collides_with`](crate::physics::Body::collides_with) groups.
+    /// For example, a ghostly preview can be given an empty `collides_with` so that it
+    /// passes through everything, and water belongs only to [`FLUID`](Self::FLUID) so
+    /// that bodies move through it (slowed by drag) rather than standing on it.
+    ///
+    /// It is a [`bitflags`] generated bit-flag type. Bits which are not named here are
+    /// available for game-specific groups, such as the members of a team whose
+    /// projectiles should pass through each other; construct them with
+    /// [`CollisionGroups::from_bits_retain()`].
+    #[derive(Clone, Copy, Debug, Hash, Eq, Ord, PartialEq, PartialOrd)]
+    pub struct CollisionGroups: u32 {
+        /// Ordinary solid matter. Blocks belong to this group by default, and bodies
+        /// collide with it by default.
+        const SOLID = 1 << 0;
+
+        /// Liquids and other matter which slow bodies moving through them, rather than
+        /// obstructing them, unless the body also collides with this group.
+        const FLUID = 1 << 1;
+
+        /// Characters' bodies.
+        const CHARACTER = 1 << 2;
+
+        /// [`Entity`](crate::entity::Entity) bodies.
+        const ENTITY = 1 << 3;
+    }
+}
+
+impl Default for CollisionGroups {
+    /// Returns [`CollisionGroups::SOLID`].
+    fn default() -> Self {
+        Self::SOLID
+    }
+}
+
 /// Result of [`collide_along_ray`] which specifies a collision point possibly inside the cube.
 #[derive(Clone, Debug, PartialEq)]
 pub(crate) struct CollisionRayEnd {
@@ -179,12 +219,15 @@ impl StopAt {
 /// The return value specifies the distance achieved and the normal (face) of the surface collided
 ///  with; if [`None`], then no obstacles were met along the full length of the line segment.
 ///
+/// Only blocks which belong to at least one of the `filter` groups are collided with.
+///
 /// `collision_callback` is called once for each colliding cube — any one of them would have been
 /// sufficient to stop the ray, but all are reported.
 pub(crate) fn collide_along_ray<Sp, CC>(
     space: &Sp,
     ray: Ray,
     aab: Aab,
+    filter: CollisionGroups,
     mut collision_callback: CC,
     stop_at: StopAt,
 ) -> Option<CollisionRayEnd>
@@ -204,15 +247,12 @@ where
     // cases), but this would be an optimization which only affects the unusual case of
     // being out of bounds, so it's not worth doing unless we specifically expect to have
     // many bodies outside a space and occasionally inside.
-    for ray_step in aab_raycast(aab, ray, stop_at.reversed()) {
-        let offset_segment = nudge_on_ray(
-            aab,
-            ray.scale_direction(ray_step.t_distance()),
-            ray_step.face().opposite(),
-            R1,
-            stop_at.reversed(),
-        );
-        let step_aab = aab.translate(offset_segment.unit_endpoint().to_vec());
+    let caster = if stop_at.reversed() {
+        AabRaycaster::new_reversed(aab, ray)
+    } else {
+        AabRaycaster::new(aab, ray)
+    };
+    for ray_step in caster {
         if ray_step.t_distance() >= 1.0 {
             // Movement is unobstructed in this timestep.
             break;
@@ -220,11 +260,10 @@ where
 
         // Compute the AAB of the potential intersection, excluding the exterior of the
         // space.
-        let potential_intersection_bounds =
-            match step_aab.round_up_to_grid().intersection(space.bounds()) {
-                Some(aab) => aab,
-                None => continue,
-            };
+        let potential_intersection_bounds = match ray_step.cubes().intersection(space.bounds()) {
+            Some(aab) => aab,
+            None => continue,
+        };
 
         // Loop over all the cubes that our AAB is just now intersecting and check if
         // any of them are solid, and if so, how far into their volume is a hit.
@@ -241,13 +280,17 @@ where
                     face: ray_step.face(),
                 }),
             };
+            if !filter.intersects(Sp::collision_groups(cell)) {
+                // Filtered out; the block might as well be absent.
+                continue;
+            }
             let found_end = match Sp::collision(cell) {
                 BlockCollision::None => {
                     // No collision for this block
                     continue;
                 }
                 BlockCollision::Hard => full_cube_end,
-                BlockCollision::Recur => {
+                BlockCollision::Recur | BlockCollision::Boxes => {
                     if let Some(found_end) = Sp::recurse(
                         full_cube_end.clone(),
                         aab,
@@ -320,9 +363,24 @@ where
     None
 }
 
+/// Returns the cubes of `space` whose blocks intersect `aab` and belong to at least one
+/// of the `filter` groups, as they would obstruct a [`Body`](super::Body) with that
+/// [`collides_with`](super::Body::collides_with) filter.
+pub fn colliding_cubes(
+    space: &Space,
+    aab: Aab,
+    filter: CollisionGroups,
+) -> impl Iterator<Item = GridPoint> + '_ {
+    find_colliding_cubes(space, aab, filter)
+}
+
 /// Returns an iterator over all blocks in `space` which intersect `aab`, accounting for
-/// collision options.
-pub(crate) fn find_colliding_cubes<Sp>(space: &Sp, aab: Aab) -> impl Iterator<Item = GridPoint> + '_
+/// collision options and considering only blocks in the `filter` groups.
+pub(crate) fn find_colliding_cubes<Sp>(
+    space: &Sp,
+    aab: Aab,
+    filter: CollisionGroups,
+) -> impl Iterator<Item = GridPoint> + '_
 where
     Sp: CollisionSpace,
 {
@@ -333,6 +391,7 @@ where
         space,
         Ray::new([0., 0., 0.], [0., 0., 0.]),
         aab,
+        filter,
         |contact| {
             points.push(contact.cube());
         },
@@ -356,6 +415,9 @@ pub(crate) trait CollisionSpace {
     /// Retrieve a cell's collision behavior option.
     fn collision(cell: &Self::Cell) -> BlockCollision;
 
+    /// Retrieve the [`CollisionGroups`] a cell belongs to.
+    fn collision_groups(cell: &Self::Cell) -> CollisionGroups;
+
     /// TODO: document
     fn get_voxels(cell: &Self::Cell) -> Option<&Evoxels>;
 
@@ -388,6 +450,11 @@ impl CollisionSpace for Space {
         cell.attributes.collision
     }
 
+    #[inline]
+    fn collision_groups(cell: &Self::Cell) -> CollisionGroups {
+        cell.attributes.collision_groups
+    }
+
     #[inline]
     fn get_voxels(evaluated: &EvaluatedBlock) -> Option<&Evoxels> {
         Some(&evaluated.voxels)
@@ -401,48 +468,71 @@ impl CollisionSpace for Space {
         evaluated: &EvaluatedBlock,
         stop_at: StopAt,
     ) -> Option<CollisionRayEnd> {
-        match &evaluated.voxels {
+        match (evaluated.attributes.collision, &evaluated.voxels) {
+            (BlockCollision::Boxes, voxels) => recurse_into_voxels(
+                &CollisionBoxes::new(&evaluated.collision_boxes),
+                voxels.resolution(),
+                entry_end,
+                space_aab,
+                space_ray,
+                stop_at,
+            ),
             // Plain non-recursive collision
-            Evoxels::One(_) => Some(entry_end),
-            &Evoxels::Many(resolution, ref voxels) => {
-                let cube_translation = entry_end
-                    .contact
-                    .cube()
-                    .to_vec()
-                    .map(|s| -FreeCoordinate::from(s));
-                let scale = FreeCoordinate::from(resolution);
-                // Transform our original AAB and ray so that it is in the coordinate system of the block voxels.
-                // Note: aab is not translated since it's relative to the ray anyway.
-                let voxel_aab = space_aab.scale(scale);
-                let voxel_ray = space_ray.translate(cube_translation).scale_all(scale);
-                if let Some(hit_voxel) =
-                    collide_along_ray(voxels, voxel_ray, voxel_aab, |_| {}, stop_at)
-                {
-                    let CollisionRayEnd {
-                        t_distance: voxel_t_distance,
-                        contact,
-                    } = hit_voxel;
-
-                    match contact {
-                        Contact::Block(voxel) => Some(CollisionRayEnd {
-                            t_distance: voxel_t_distance,
-                            contact: Contact::Voxel {
-                                cube: entry_end.contact.cube(),
-                                resolution,
-                                voxel,
-                            },
-                        }),
-                        Contact::Voxel { .. } => panic!("encountered 3-level voxel recursion"),
-                    }
-                } else {
-                    // Didn't hit anything within this block.
-                    None
-                }
+            (_, Evoxels::One(_) | Evoxels::Faces(..)) => Some(entry_end),
+            (_, &Evoxels::Many(resolution, ref voxels)) => {
+                recurse_into_voxels(voxels, resolution, entry_end, space_aab, space_ray, stop_at)
             }
         }
     }
 }
 
+/// Helper for [`Space`]'s [`CollisionSpace::recurse`]: collide with `voxel_space`, which
+/// is the contents of the cube `entry_end` entered, scaled down by `resolution`.
+fn recurse_into_voxels<Sp: CollisionSpace>(
+    voxel_space: &Sp,
+    resolution: Resolution,
+    entry_end: CollisionRayEnd,
+    space_aab: Aab,
+    space_ray: Ray,
+    stop_at: StopAt,
+) -> Option<CollisionRayEnd> {
+    let cube_translation = entry_end
+        .contact
+        .cube()
+        .to_vec()
+        .map(|s| -FreeCoordinate::from(s));
+    let scale = FreeCoordinate::from(resolution);
+    // Transform our original AAB and ray so that it is in the coordinate system of the block voxels.
+    // Note: aab is not translated since it's relative to the ray anyway.
+    let voxel_aab = space_aab.scale(scale);
+    let voxel_ray = space_ray.translate(cube_translation).scale_all(scale);
+    // The block as a whole has already passed the filter, and its voxels are part of it.
+    let filter = CollisionGroups::all();
+    if let Some(hit_voxel) =
+        collide_along_ray(voxel_space, voxel_ray, voxel_aab, filter, |_| {}, stop_at)
+    {
+        let CollisionRayEnd {
+            t_distance: voxel_t_distance,
+            contact,
+        } = hit_voxel;
+
+        match contact {
+            Contact::Block(voxel) => Some(CollisionRayEnd {
+                t_distance: voxel_t_distance,
+                contact: Contact::Voxel {
+                    cube: entry_end.contact.cube(),
+                    resolution,
+                    voxel,
+                },
+            }),
+            Contact::Voxel { .. } => panic!("encountered 3-level voxel recursion"),
+        }
+    } else {
+        // Didn't hit anything within this block.
+        None
+    }
+}
+
 impl CollisionSpace for GridArray<Evoxel> {
     type Cell = Evoxel;
 
@@ -460,6 +550,12 @@ impl CollisionSpace for GridArray<Evoxel> {
         cell.collision
     }
 
+    /// Voxels are part of a block which has already been filtered.
+    #[inline]
+    fn collision_groups(_cell: &Self::Cell) -> CollisionGroups {
+        CollisionGroups::all()
+    }
+
     #[inline]
     fn get_voxels(_cell: &Self::Cell) -> Option<&Evoxels> {
         // TODO: can't just return Evoxels::One because it would have to be owned (unless we
@@ -479,84 +575,68 @@ impl CollisionSpace for GridArray<Evoxel> {
     }
 }
 
-/// Given a ray describing movement of the origin of an AAB, perform a raycast to find
-/// the positions where the AAB moves into new cubes. The returned ray steps' `t_distance`
-/// values report how far to move the AAB to meet the edge.
-///
-/// If `reversed` is true, find positions where it leaves cubes.
-///
-/// Note that due to the nature of floating-point arithmetic, it is uncertain whether the
-/// resulting new AAB position will have the AAB's forward face land before, after, or
-/// exactly on the boundary. The caller must compute an appropriate nudge (using TODO:
-/// provide a function for this) to serve its needs.
-pub(crate) fn aab_raycast(aab: Aab, origin_ray: Ray, reversed: bool) -> Raycaster {
-    let leading_corner = aab.leading_corner(if reversed {
-        -origin_ray.direction
-    } else {
-        origin_ray.direction
-    });
-    let leading_ray = origin_ray.translate(leading_corner);
-    leading_ray.cast()
+/// [`EvaluatedBlock::collision_boxes`] viewed as a [`CollisionSpace`], in which every
+/// voxel inside one of the boxes is [`BlockCollision::Hard`].
+struct CollisionBoxes<'a> {
+    bounds: GridAab,
+    boxes: &'a [GridAab],
 }
 
-/// Given an [`Aab`] and a [`Ray`] such that the given face of
-/// `aab.translate(segment.unit_endpoint().to_vec())`
-/// lands almost but not quite on a unit cell boundary along the `plane` axis,
-/// nudge the endpoint of the ray
-/// infinitesimally so that it lands definitely beyond (or before if `backward`)
-/// the cell boundary.
-///
-/// Note that the required `face` is the opposite of the face produced by a raycast.
-/// (This may be confusing but we feel that it would be more confusing to use a face
-/// other than the relevant face of the [`Aab`]).
-pub(crate) fn nudge_on_ray(
-    aab: Aab,
-    segment: Ray,
-    face: Face7,
-    subdivision: Resolution,
-    backward: bool,
-) -> Ray {
-    if segment.direction.is_zero() {
-        return segment;
-    }
-    let face: Face6 = match face.try_into() {
-        Ok(f) => f,
-        Err(_) => return segment,
-    };
+impl<'a> CollisionBoxes<'a> {
+    fn new(boxes: &'a [GridAab]) -> Self {
+        Self {
+            bounds: boxes
+                .iter()
+                .copied()
+                .reduce(|a, b| a.union(b).expect("collision boxes overflowed"))
+                .unwrap_or(GridAab::from_lower_size([0, 0, 0], [0, 0, 0])),
+            boxes,
+        }
+    }
+}
 
-    // This is the depth by which the un-nudged face penetrates the plane, which we are going to subtract.
-    let penetration_depth = {
-        let subdivision = FreeCoordinate::from(subdivision);
-        let fc_scaled = aab
-            .translate(segment.unit_endpoint().to_vec())
-            .face_coordinate(face)
-            * subdivision;
-        (fc_scaled - fc_scaled.round()) / subdivision
-    };
+impl CollisionSpace for CollisionBoxes<'_> {
+    type Cell = BlockCollision;
 
-    // This is the length of the direction vector projected along the face normal — thus, the reciprocal of the scale factor by which to adjust the distance.
-    let direction_projection = face.dot(segment.direction);
+    fn bounds(&self) -> GridAab {
+        self.bounds
+    }
 
-    debug_assert!(direction_projection != 0.0);
+    #[inline]
+    fn get_cell(&self, cube: GridPoint) -> &Self::Cell {
+        if self.boxes.iter().any(|b| b.contains_cube(cube)) {
+            &BlockCollision::Hard
+        } else {
+            &BlockCollision::None
+        }
+    }
 
-    // `translation` is how far we want to translate the AAB linearly along the face normal.
-    let epsilon_nudge = if backward {
-        -POSITION_EPSILON
-    } else {
-        POSITION_EPSILON
-    };
-    let translation = epsilon_nudge - penetration_depth;
-
-    if false {
-        dbg!(
-            face,
-            aab.face_coordinate(face),
-            penetration_depth,
-            translation,
-            direction_projection,
-        );
+    #[inline]
+    fn collision(cell: &Self::Cell) -> BlockCollision {
+        *cell
+    }
+
+    /// Boxes are part of a block which has already been filtered.
+    #[inline]
+    fn collision_groups(_cell: &Self::Cell) -> CollisionGroups {
+        CollisionGroups::all()
+    }
+
+    #[inline]
+    fn get_voxels(_cell: &Self::Cell) -> Option<&Evoxels> {
+        None
+    }
+
+    #[inline(always)]
+    fn recurse(
+        entry_end: CollisionRayEnd,
+        _aab: Aab,
+        _local_ray: Ray,
+        _cell: &Self::Cell,
+        _stop_at: StopAt,
+    ) -> Option<CollisionRayEnd> {
+        Some(entry_end)
     }
-    segment.scale_direction(1.0 + translation / direction_projection)
 }
 
 #[cfg(test)]
@@ -564,7 +644,9 @@ mod tests {
     use crate::block::Resolution::*;
     use crate::block::{Block, AIR};
     use crate::content::{make_slab, make_some_blocks};
-    use crate::math::{point_to_enclosing_cube, GridAab};
+    use crate::math::{point_to_enclosing_cube, Face6, GridAab, Rgba};
+    use crate::physics::POSITION_EPSILON;
+    use crate::raycast::nudge_on_ray;
     use crate::raytracer::print_space;
     use crate::universe::Universe;
 
@@ -619,6 +701,36 @@ mod tests {
         );
     }
 
+    /// [`BlockCollision::Boxes`] collides with the same shape as [`BlockCollision::Recur`].
+    #[test]
+    fn collide_along_ray_boxes_from_outside() {
+        collide_along_ray_tester(
+            1.5,
+            |u| {
+                let slab = Block::builder()
+                    .collision(BlockCollision::Boxes)
+                    .voxels_fn(u, R2, |cube| {
+                        if cube.y < 1 {
+                            Block::from(Rgba::WHITE)
+                        } else {
+                            AIR
+                        }
+                    })
+                    .unwrap()
+                    .build();
+                [AIR, slab]
+            },
+            Some(CollisionRayEnd {
+                t_distance: 0.5, // half of a ray with magnitude 2
+                contact: Contact::Voxel {
+                    cube: GridPoint::new(1, 0, 0),
+                    resolution: R2,
+                    voxel: CubeFace::new([0, 0, 0], Face7::PY),
+                },
+            }),
+        );
+    }
+
     /// Check that colliding against two recursive blocks correctly picks the taller one,
     /// in either ordering.
     #[test]
@@ -671,7 +783,14 @@ mod tests {
         let aab = Aab::from_cube(GridPoint::new(0, 0, 0));
         let ray = Ray::new([0.5, initial_y, 0.], [0., -2., 0.]);
 
-        let result = collide_along_ray(&space, ray, aab, |_| {}, StopAt::NotAlreadyColliding);
+        let result = collide_along_ray(
+            &space,
+            ray,
+            aab,
+            CollisionGroups::SOLID,
+            |_| {},
+            StopAt::NotAlreadyColliding,
+        );
         assert_eq!(result, expected_end);
     }
 
@@ -694,6 +813,7 @@ mod tests {
             &space,
             ray,
             aab,
+            CollisionGroups::SOLID,
             |c| contacts.push(c),
             StopAt::NotAlreadyColliding,
         );
@@ -726,11 +846,11 @@ mod tests {
                     .random_point(&mut rng)
                     .to_vec(),
             );
-            let step = aab_raycast(moving_aab, ray, false)
+            let step = AabRaycaster::new(moving_aab, ray)
                 .nth(rng.gen_range(1..10))
                 .unwrap();
             let axis = step.face().axis_number().expect("should have an axis");
-            let segment = ray.scale_direction(step.t_distance()); // TODO: this should be a function? Should aab_raycast return a special step type with these features?
+            let segment = step.segment();
             let unnudged_aab = moving_aab.translate(segment.unit_endpoint().to_vec());
             let face_to_nudge: Face6 = Face6::try_from(step.face().opposite()).unwrap();
 
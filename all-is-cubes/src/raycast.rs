@@ -12,6 +12,9 @@ use crate::math::{
     GridPoint, LineVertex,
 };
 
+mod aab;
+pub use aab::*;
+
 /// A ray; a half-infinite line segment (sometimes used as finite by the length of the
 /// direction vector).
 #[allow(clippy::exhaustive_structs)]
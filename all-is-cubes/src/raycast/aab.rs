@@ -0,0 +1,208 @@
+//! Casting of boxes ([`Aab`]s) rather than points through the grid, also known as
+//! “swept AABB” collision.
+
+use cgmath::{EuclideanSpace as _, Zero as _};
+
+use crate::block::Resolution::{self, R1};
+use crate::math::{Aab, CubeFace, Face6, Face7, FreeCoordinate, Geometry as _, GridAab};
+use crate::physics::POSITION_EPSILON;
+use crate::raycast::{Ray, RaycastStep, Raycaster};
+
+/// Iterator over the positions at which an [`Aab`], moving along a [`Ray`], begins to
+/// intersect new cubes of the grid.
+///
+/// The box's coordinates are relative to the ray origin, so that the box at distance
+/// `t` is `aab.translate(ray.origin + t * ray.direction)`. The first step is the
+/// box's starting position; each later step is the position at which its leading
+/// face reaches a new layer of cubes.
+///
+/// ```
+/// use all_is_cubes::math::{Aab, Face7, GridAab};
+/// use all_is_cubes::raycast::{AabRaycaster, Ray};
+///
+/// let aab = Aab::new(-0.25, 0.25, -0.25, 0.25, -0.25, 0.25);
+/// let mut caster = AabRaycaster::new(aab, Ray::new([0.5, 0.5, 0.5], [1.0, 0.0, 0.0]));
+///
+/// let start = caster.next().unwrap();
+/// assert_eq!(start.face(), Face7::Within);
+/// assert_eq!(start.cubes(), GridAab::from_lower_size([0, 0, 0], [1, 1, 1]));
+///
+/// // The leading face of the box reaches x = 1 after moving 0.25.
+/// let step = caster.next().unwrap();
+/// assert_eq!(step.t_distance(), 0.25);
+/// assert_eq!(step.face(), Face7::NX);
+/// assert_eq!(step.cubes(), GridAab::from_lower_size([0, 0, 0], [2, 1, 1]));
+/// ```
+#[derive(Clone, Debug)]
+pub struct AabRaycaster {
+    aab: Aab,
+    ray: Ray,
+    reversed: bool,
+    /// Raycaster following the leading corner of the box.
+    corner_raycaster: Raycaster,
+}
+
+impl AabRaycaster {
+    /// Prepares to cast `aab`, whose coordinates are relative to the origin of `ray`,
+    /// along `ray`.
+    pub fn new(aab: Aab, ray: Ray) -> Self {
+        Self::new_impl(aab, ray, false)
+    }
+
+    /// Like [`Self::new()`], but finds the positions at which the box stops
+    /// intersecting cubes instead of those at which it starts.
+    pub(crate) fn new_reversed(aab: Aab, ray: Ray) -> Self {
+        Self::new_impl(aab, ray, true)
+    }
+
+    fn new_impl(aab: Aab, ray: Ray, reversed: bool) -> Self {
+        let leading_corner = aab.leading_corner(if reversed {
+            -ray.direction
+        } else {
+            ray.direction
+        });
+        Self {
+            aab,
+            ray,
+            reversed,
+            corner_raycaster: ray.translate(leading_corner).cast(),
+        }
+    }
+}
+
+impl Iterator for AabRaycaster {
+    type Item = AabRaycastStep;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        let corner_step = self.corner_raycaster.next()?;
+        Some(AabRaycastStep {
+            corner_step,
+            aab: self.aab,
+            ray: self.ray,
+            reversed: self.reversed,
+        })
+    }
+}
+
+impl std::iter::FusedIterator for AabRaycaster {}
+
+/// A step of an [`AabRaycaster`]: a position at which the moving box meets a new layer
+/// of cubes.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct AabRaycastStep {
+    corner_step: RaycastStep,
+    aab: Aab,
+    ray: Ray,
+    reversed: bool,
+}
+
+impl AabRaycastStep {
+    /// The distance the box has moved, as measured in multiples of the ray's direction
+    /// vector.
+    #[inline]
+    pub fn t_distance(&self) -> FreeCoordinate {
+        self.corner_step.t_distance()
+    }
+
+    /// The face of the newly met cubes which the box is touching, or [`Face7::Within`]
+    /// for the starting position.
+    #[inline]
+    pub fn face(&self) -> Face7 {
+        self.corner_step.face()
+    }
+
+    /// The cube which the leading corner of the box has entered, and the face through
+    /// which it entered.
+    #[inline]
+    pub fn cube_face(&self) -> CubeFace {
+        self.corner_step.cube_face()
+    }
+
+    /// The part of the ray which the box's origin has traveled.
+    #[inline]
+    pub fn segment(&self) -> Ray {
+        self.ray.scale_direction(self.t_distance())
+    }
+
+    /// The box at this step's position.
+    ///
+    /// Because its leading face would otherwise be only approximately on the cube
+    /// boundary, due to floating-point error, it is moved by a very small distance so
+    /// that it definitely intersects the newly met cubes.
+    pub fn aab(&self) -> Aab {
+        let nudged_segment = nudge_on_ray(
+            self.aab,
+            self.segment(),
+            self.face().opposite(),
+            R1,
+            self.reversed,
+        );
+        self.aab.translate(nudged_segment.unit_endpoint().to_vec())
+    }
+
+    /// All of the cubes which [`Self::aab()`] intersects.
+    pub fn cubes(&self) -> GridAab {
+        self.aab().round_up_to_grid()
+    }
+}
+
+/// Given an [`Aab`] and a [`Ray`] such that the given face of
+/// `aab.translate(segment.unit_endpoint().to_vec())`
+/// lands almost but not quite on a unit cell boundary along the `plane` axis,
+/// nudge the endpoint of the ray
+/// infinitesimally so that it lands definitely beyond (or before if `backward`)
+/// the cell boundary.
+///
+/// Note that the required `face` is the opposite of the face produced by a raycast.
+/// (This may be confusing but we feel that it would be more confusing to use a face
+/// other than the relevant face of the [`Aab`]).
+pub(crate) fn nudge_on_ray(
+    aab: Aab,
+    segment: Ray,
+    face: Face7,
+    subdivision: Resolution,
+    backward: bool,
+) -> Ray {
+    if segment.direction.is_zero() {
+        return segment;
+    }
+    let face: Face6 = match face.try_into() {
+        Ok(f) => f,
+        Err(_) => return segment,
+    };
+
+    // This is the depth by which the un-nudged face penetrates the plane, which we are going to subtract.
+    let penetration_depth = {
+        let subdivision = FreeCoordinate::from(subdivision);
+        let fc_scaled = aab
+            .translate(segment.unit_endpoint().to_vec())
+            .face_coordinate(face)
+            * subdivision;
+        (fc_scaled - fc_scaled.round()) / subdivision
+    };
+
+    // This is the length of the direction vector projected along the face normal — thus, the reciprocal of the scale factor by which to adjust the distance.
+    let direction_projection = face.dot(segment.direction);
+
+    debug_assert!(direction_projection != 0.0);
+
+    // `translation` is how far we want to translate the AAB linearly along the face normal.
+    let epsilon_nudge = if backward {
+        -POSITION_EPSILON
+    } else {
+        POSITION_EPSILON
+    };
+    let translation = epsilon_nudge - penetration_depth;
+
+    if false {
+        dbg!(
+            face,
+            aab.face_coordinate(face),
+            penetration_depth,
+            translation,
+            direction_projection,
+        );
+    }
+    segment.scale_direction(1.0 + translation / direction_projection)
+}
@@ -23,7 +23,7 @@ use crate::math::{
     Rgba,
 };
 use crate::raycast::Ray;
-use crate::space::{BlockIndex, PackedLight, Space, SpaceBlockData};
+use crate::space::{BlockIndex, PackedLight, Sky, Space, SpaceBlockData};
 use crate::util::{CustomFormat, StatusText};
 
 mod pixel_buf;
@@ -31,7 +31,10 @@ pub use pixel_buf::*;
 mod renderer;
 pub use renderer::*;
 mod surface;
-use surface::{DepthIter, DepthStep, Span, Surface, SurfaceIter, TraceStep};
+use surface::{
+    brick_occupancy, DepthIter, DepthStep, Span, Surface, SurfaceIter, TraceStep, TracingPart,
+    TracingParticle,
+};
 // TODO: pub use surface::*;
 mod text;
 pub use text::*;
@@ -44,6 +47,8 @@ pub struct SpaceRaytracer<D: RtBlockData> {
 
     graphics_options: GraphicsOptions,
     custom_options: D::Options,
+    sky: Sky,
+    /// Mean color of `sky`, used as the light level outside the space.
     sky_color: Rgb,
     sky_data: D,
     packed_sky_color: PackedLight,
@@ -60,7 +65,8 @@ impl<D: RtBlockData> SpaceRaytracer<D> {
             graphics_options: &graphics_options,
             custom_options: &custom_options,
         };
-        let sky_color = space.physics().sky_color;
+        let sky = space.physics().sky.clone();
+        let sky_color = sky.mean();
         SpaceRaytracer {
             blocks: space
                 .block_data()
@@ -68,6 +74,7 @@ impl<D: RtBlockData> SpaceRaytracer<D> {
                 .map(|sbd| TracingBlock::<D>::from_block(options, sbd))
                 .collect(),
             cubes: prepare_cubes(space),
+            sky,
             sky_color,
             sky_data: D::sky(options),
             packed_sky_color: sky_color.into(),
@@ -91,6 +98,7 @@ impl<D: RtBlockData> SpaceRaytracer<D> {
             blocks: vec![],
             cubes: GridArray::from_elements(GridAab::from_lower_upper([0, 0, 0], [0, 0, 0]), [])
                 .unwrap(),
+            sky: Sky::Uniform(sky_color),
             sky_color,
             sky_data: D::sky(options),
             packed_sky_color: sky_color.into(),
@@ -105,6 +113,18 @@ impl<D: RtBlockData> SpaceRaytracer<D> {
         &self,
         ray: Ray,
         include_sky: bool,
+    ) -> (P, RaytraceInfo) {
+        self.trace_ray_with_avatars(ray, include_sky, &[], &[])
+    }
+
+    /// As [`Self::trace_ray()`], but also draws the given avatar parts and weather
+    /// particles, which are not part of the space.
+    pub(crate) fn trace_ray_with_avatars<P: PixelBuf<BlockData = D>>(
+        &self,
+        ray: Ray,
+        include_sky: bool,
+        avatars: &[TracingPart],
+        particles: &[TracingParticle],
     ) -> (P, RaytraceInfo) {
         let options = RtOptionsRef {
             graphics_options: &self.graphics_options,
@@ -117,10 +137,23 @@ impl<D: RtBlockData> SpaceRaytracer<D> {
             pixel_buf: P::default(),
         };
         let surface_iter = SurfaceIter::new(self, ray);
+        // Only the nearest surface of any avatar or particle is drawn, hiding everything
+        // behind it.
+        // TODO: Avatars and particles have no block data of their own, so borrow the sky's.
+        let mut avatar_surface = avatars
+            .iter()
+            .filter_map(|part| part.surface(ray, &self.sky_data))
+            .chain(
+                particles
+                    .iter()
+                    .filter_map(|particle| particle.surface(ray, &self.sky_data)),
+            )
+            .min_by(|a, b| a.t_distance.total_cmp(&b.t_distance));
 
         // Use the more expensive volumetric tracing strategy only if we need it.
         match self.graphics_options.transparency {
-            TransparencyOption::Volumetric => {
+            TransparencyOption::Volumetric | TransparencyOption::Tinted => {
+                let tint = self.graphics_options.transparency == TransparencyOption::Tinted;
                 for step in DepthIter::new(surface_iter) {
                     if state.count_step_should_stop(options) {
                         break;
@@ -132,7 +165,14 @@ impl<D: RtBlockData> SpaceRaytracer<D> {
                         }
                         DepthStep::Span(span) => {
                             debug_assert!(!span.surface.diffuse_color.fully_transparent());
-                            state.trace_through_span(span, self);
+                            if avatar_surface
+                                .as_ref()
+                                .map_or(false, |a| a.t_distance <= span.surface.t_distance)
+                            {
+                                state.trace_through_surface(avatar_surface.take().unwrap(), self);
+                                break;
+                            }
+                            state.trace_through_span(span, self, tint);
                         }
                     }
                 }
@@ -150,15 +190,29 @@ impl<D: RtBlockData> SpaceRaytracer<D> {
                         }
                         EnterSurface(surface) => {
                             debug_assert!(!surface.diffuse_color.fully_transparent());
+                            if avatar_surface
+                                .as_ref()
+                                .map_or(false, |a| a.t_distance <= surface.t_distance)
+                            {
+                                state.trace_through_surface(avatar_surface.take().unwrap(), self);
+                                break;
+                            }
                             state.trace_through_surface(surface, self);
                         }
                     }
                 }
             }
         }
+        // If nothing in the space hid the avatar or particle, draw it behind everything
+        // that was in front of it.
+        if let Some(surface) = avatar_surface {
+            if !state.pixel_buf.opaque() {
+                state.trace_through_surface(surface, self);
+            }
+        }
         state.finish(
             if include_sky {
-                self.sky_color.with_alpha_one()
+                self.sky.sample(ray.direction).with_alpha_one()
             } else {
                 Rgba::TRANSPARENT
             },
@@ -375,7 +429,7 @@ where
             .field("cubes.bounds", &self.cubes.bounds())
             .field("graphics_options", &self.graphics_options)
             .field("custom_options", &self.custom_options)
-            .field("sky_color", &self.sky_color)
+            .field("sky", &self.sky)
             .finish_non_exhaustive()
     }
 }
@@ -445,6 +499,9 @@ struct TracingBlock<D> {
     block_data: D,
     // TODO: `Evoxels` carries more data than we actually need (color). Experiment with using a packed format.
     voxels: Evoxels,
+    /// Which regions of `voxels` are nonempty, so that they can be skipped.
+    /// [`None`] if `voxels` is too small to benefit.
+    brick_occupancy: Option<GridArray<bool>>,
 }
 
 impl<D: RtBlockData> TracingBlock<D> {
@@ -452,9 +509,11 @@ impl<D: RtBlockData> TracingBlock<D> {
         options: RtOptionsRef<'_, D::Options>,
         space_block_data: &SpaceBlockData,
     ) -> Self {
+        let voxels = space_block_data.evaluated().voxels.clone();
         TracingBlock {
             block_data: D::from_block(options, space_block_data),
-            voxels: space_block_data.evaluated().voxels.clone(),
+            brick_occupancy: brick_occupancy(&voxels),
+            voxels,
         }
     }
 }
@@ -532,11 +591,16 @@ impl<P: PixelBuf> TracingState<P> {
         }
     }
 
+    /// Apply the effect of a volume of material.
+    ///
+    /// If `tint` is true, light from behind the volume is also filtered by its color,
+    /// as specified by [`TransparencyOption::Tinted`].
     #[inline]
     fn trace_through_span(
         &mut self,
         span: Span<'_, P::BlockData>,
         rt: &SpaceRaytracer<P::BlockData>,
+        tint: bool,
     ) {
         let Span {
             mut surface,
@@ -546,6 +610,19 @@ impl<P: PixelBuf> TracingState<P> {
         let thickness =
             ((exit_t_distance - surface.t_distance) * self.t_to_absolute_distance) as f32;
 
+        // Compute the tint before the color is modified below.
+        let tint_transmittance = if tint {
+            let exponent = surface.diffuse_color.alpha().into_inner() * thickness;
+            let rgb = surface.diffuse_color.to_rgb();
+            Some(Rgb::new(
+                rgb.red().into_inner().powf(exponent),
+                rgb.green().into_inner().powf(exponent),
+                rgb.blue().into_inner().powf(exponent),
+            ))
+        } else {
+            None
+        };
+
         // Convert alpha to transmittance (light transmitted / light received).
         let unit_transmittance = 1.0 - surface.diffuse_color.alpha().into_inner();
         // Adjust transmittance for the thickness relative to an assumed 1.0 thickness.
@@ -558,7 +635,11 @@ impl<P: PixelBuf> TracingState<P> {
             .to_rgb()
             .with_alpha(NotNan::new(1.0 - depth_transmittance).unwrap());
 
+        let block_data = surface.block_data;
         self.trace_through_surface(surface, rt);
+        if let Some(transmittance) = tint_transmittance {
+            self.pixel_buf.absorb(transmittance, block_data);
+        }
     }
 }
 
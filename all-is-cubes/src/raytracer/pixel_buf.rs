@@ -2,10 +2,11 @@
 
 use std::convert::TryFrom as _;
 
-use cgmath::{Vector3, Zero as _};
+use cgmath::{Array as _, ElementWise as _, Vector3, Zero as _};
+use ordered_float::NotNan;
 
 use crate::camera::GraphicsOptions;
-use crate::math::Rgba;
+use crate::math::{Rgb, Rgba};
 use crate::space::SpaceBlockData;
 
 /// Borrowed data which may be used to customize the result of raytracing.
@@ -58,6 +59,20 @@ pub trait PixelBuf: Default {
     /// more future-proof.
     fn add(&mut self, surface_color: Rgba, block_data: &Self::BlockData);
 
+    /// Filters the light from everything not yet traced by `transmittance`, the fraction
+    /// of each color channel which passes through, as when light passes through tinted
+    /// glass. Unlike [`Self::add`], this does not contribute any light of its own.
+    ///
+    /// The default implementation approximates this by adding black with an alpha
+    /// corresponding to the mean transmittance.
+    fn absorb(&mut self, transmittance: Rgb, block_data: &Self::BlockData) {
+        let mean_transmittance: f32 = Vector3::from(transmittance).sum() / 3.0;
+        self.add(
+            Rgb::ZERO.with_alpha(NotNan::new(1.0 - mean_transmittance).unwrap()),
+            block_data,
+        );
+    }
+
     /// Indicates that the trace did not intersect any space that could have contained
     /// anything to draw. May be used for special diagnostic drawing. If used, should
     /// disable the effects of future [`Self::add`] calls.
@@ -132,9 +147,11 @@ pub struct ColorBuf {
     /// Note: Not using the [`Rgb`](crate::math::Rgb) type so as to skip NaN checks.
     color_accumulator: Vector3<f32>,
 
-    /// Fraction of the color value that is to be determined by future, rather than past,
-    /// tracing; starts at 1.0 and decreases as surfaces are encountered.
-    ray_alpha: f32,
+    /// Fraction of the color value, in each channel, that is to be determined by future,
+    /// rather than past, tracing; starts at 1.0 and decreases as surfaces are encountered.
+    ///
+    /// The channels differ only if [`PixelBuf::absorb`] has been used.
+    ray_alpha: Vector3<f32>,
 }
 
 impl PixelBuf for ColorBuf {
@@ -144,16 +161,23 @@ impl PixelBuf for ColorBuf {
     fn opaque(&self) -> bool {
         // Let's suppose that we don't care about differences that can't be represented
         // in 8-bit color...not considering gamma.
-        self.ray_alpha < 1.0 / 256.0
+        let max_alpha = self.ray_alpha.x.max(self.ray_alpha.y).max(self.ray_alpha.z);
+        max_alpha < 1.0 / 256.0
     }
 
     #[inline]
     fn add(&mut self, surface_color: Rgba, _block_data: &Self::BlockData) {
         let color_vector: Vector3<f32> = surface_color.to_rgb().into();
         let surface_alpha = surface_color.alpha().into_inner();
-        let alpha_for_add = surface_alpha * self.ray_alpha;
+        let alpha_for_add = self.ray_alpha * surface_alpha;
         self.ray_alpha *= 1.0 - surface_alpha;
-        self.color_accumulator += color_vector * alpha_for_add;
+        self.color_accumulator += color_vector.mul_element_wise(alpha_for_add);
+    }
+
+    #[inline]
+    fn absorb(&mut self, transmittance: Rgb, _block_data: &Self::BlockData) {
+        self.ray_alpha
+            .mul_assign_element_wise(Vector3::<f32>::from(transmittance));
     }
 
     #[inline]
@@ -164,7 +188,7 @@ impl PixelBuf for ColorBuf {
                 .map(|cb| cb.color_accumulator)
                 .sum::<Vector3<f32>>()
                 / (N as f32),
-            ray_alpha: items.iter().map(|cb| cb.ray_alpha).sum::<f32>() / (N as f32),
+            ray_alpha: items.iter().map(|cb| cb.ray_alpha).sum::<Vector3<f32>>() / (N as f32),
         }
     }
 }
@@ -174,7 +198,7 @@ impl Default for ColorBuf {
     fn default() -> Self {
         Self {
             color_accumulator: Vector3::zero(),
-            ray_alpha: 1.0,
+            ray_alpha: Vector3::new(1.0, 1.0, 1.0),
         }
     }
 }
@@ -186,11 +210,13 @@ impl From<ColorBuf> for Rgba {
     ///
     /// [`Camera::post_process_color()`]: crate::camera::Camera::post_process_color()
     fn from(buf: ColorBuf) -> Rgba {
-        if buf.ray_alpha >= 1.0 {
+        // Alpha cannot vary by channel, so use the mean.
+        let ray_alpha = (buf.ray_alpha.x + buf.ray_alpha.y + buf.ray_alpha.z) / 3.0;
+        if ray_alpha >= 1.0 {
             // Special case to avoid dividing by zero
             Rgba::TRANSPARENT
         } else {
-            let color_alpha = 1.0 - buf.ray_alpha;
+            let color_alpha = 1.0 - ray_alpha;
             let non_premultiplied_color = buf.color_accumulator / color_alpha;
             Rgba::try_from(non_premultiplied_color.extend(color_alpha))
                 .unwrap_or_else(|_| Rgba::new(1.0, 0.0, 0.0, 1.0))
@@ -234,4 +260,15 @@ mod tests {
         //);
         assert!(buf.opaque());
     }
+
+    #[test]
+    fn color_buf_absorb() {
+        let mut buf = ColorBuf::default();
+        buf.absorb(Rgb::new(1.0, 0.0, 0.0), &());
+        assert!(!buf.opaque());
+
+        buf.add(Rgba::WHITE, &());
+        assert!(buf.opaque());
+        assert_eq!(Rgba::from(buf), Rgba::new(1.0, 0.0, 0.0, 1.0));
+    }
 }
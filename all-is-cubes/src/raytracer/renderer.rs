@@ -14,8 +14,8 @@ use crate::content::palette;
 use crate::listen::ListenableSource;
 use crate::math::Rgba;
 use crate::raytracer::{
-    ColorBuf, PixelBuf, RaytraceInfo, RtBlockData, RtOptionsRef, SpaceRaytracer,
-    UpdatingSpaceRaytracer,
+    ColorBuf, PixelBuf, RaytraceInfo, RtBlockData, RtOptionsRef, SpaceRaytracer, TracingPart,
+    TracingParticle, UpdatingSpaceRaytracer,
 };
 use crate::space::Space;
 use crate::universe::URef;
@@ -33,6 +33,13 @@ pub struct RtRenderer<D: RtBlockData = ()> {
 
     custom_options: ListenableSource<D::Options>,
 
+    /// Avatars to draw in the world space, from [`StandardCameras::world_avatars()`].
+    avatars: Vec<TracingPart>,
+
+    /// Weather particles to draw in the world space, from
+    /// [`Space::weather_particles()`].
+    particles: Vec<TracingParticle>,
+
     /// Whether there was a [`Cursor`] to be drawn.
     /// Raytracing doesn't yet support cursors but we need to report that.
     had_cursor: bool,
@@ -57,6 +64,8 @@ where
             cameras,
             size_policy,
             custom_options,
+            avatars: Vec::new(),
+            particles: Vec::new(),
             had_cursor: false,
         }
     }
@@ -74,6 +83,12 @@ where
         // TODO: raytracer needs to implement drawing the cursor
         self.had_cursor = cursor.is_some();
         self.cameras.update();
+        self.avatars = self
+            .cameras
+            .world_avatars()
+            .iter()
+            .filter_map(TracingPart::new)
+            .collect();
 
         fn sync_space<D: RtBlockData>(
             cached_rt: &mut Option<UpdatingSpaceRaytracer<D>>,
@@ -108,9 +123,25 @@ where
             Ok(())
         }
         let gs = self.cameras.graphics_options_source();
+        let world_space = self.cameras.world_space().get();
         sync_space(
             &mut self.rts.world,
-            Option::as_ref(&self.cameras.world_space().get()),
+            Option::as_ref(&world_space),
+            &gs,
+            &self.custom_options,
+        )?;
+        self.particles = match &*world_space {
+            Some(space) => TracingParticle::from_particles(
+                &space
+                    .read()
+                    .map_err(RenderError::Read)?
+                    .weather_particles(self.cameras.cameras().world.view_position()),
+            ),
+            None => Vec::new(),
+        };
+        sync_space(
+            &mut self.rts.hand,
+            self.cameras.hand_space(),
             &gs,
             &self.custom_options,
         )?;
@@ -150,6 +181,7 @@ where
         let mut cameras = self.cameras.cameras().clone();
         let viewport = (self.size_policy)(cameras.world.viewport());
         cameras.world.set_viewport(viewport);
+        cameras.hand.set_viewport(viewport);
         cameras.ui.set_viewport(viewport);
         assert_eq!(
             viewport.pixel_count(),
@@ -168,6 +200,8 @@ where
                 .as_refs()
                 .map(|opt_urt| opt_urt.as_ref().map(|urt| urt.get())),
             cameras: &cameras,
+            avatars: &self.avatars,
+            particles: &self.particles,
             options,
         };
 
@@ -178,6 +212,7 @@ where
             eg::draw_info_text(
                 output,
                 viewport,
+                options.graphics_options,
                 [
                     encoder(P::paint(Rgba::BLACK, options)),
                     encoder(P::paint(Rgba::WHITE, options)),
@@ -206,15 +241,15 @@ where
 }
 
 impl RtRenderer<()> {
-    /// As [`Self::draw()`], but the output is an [`RgbaImage`], and
-    /// [`Camera::post_process_color()`] is applied to the pixels.
+    /// As [`Self::draw()`], but the output is an [`RgbaImage`], and the camera's
+    /// [`ColorPipeline`] is applied to the pixels.
     ///
-    ///  [`Camera::post_process_color()`]: crate::camera::Camera::post_process_color
+    ///  [`ColorPipeline`]: crate::camera::ColorPipeline
     pub fn draw_rgba(
         &self,
         info_text_fn: impl FnOnce(&RaytraceInfo) -> String,
     ) -> (RgbaImage, RaytraceInfo, Flaws) {
-        let camera = self.cameras.cameras().world.clone();
+        let color_pipeline = self.cameras.cameras().world.color_pipeline();
 
         let Vector2 {
             x: width,
@@ -224,7 +259,7 @@ impl RtRenderer<()> {
 
         let info = self.draw::<ColorBuf, _, [u8; 4], _>(
             info_text_fn,
-            |pixel_buf| camera.post_process_color(Rgba::from(pixel_buf)).to_srgb8(),
+            |pixel_buf| color_pipeline.to_srgb8(Rgba::from(pixel_buf)),
             bytemuck::cast_slice_mut::<u8, [u8; 4]>(image.as_mut()),
         );
 
@@ -287,6 +322,10 @@ struct RtScene<'a, P: PixelBuf> {
     rts: Layers<Option<&'a SpaceRaytracer<P::BlockData>>>,
     /// Cameras *with* size_policy applied.
     cameras: &'a Layers<Camera>,
+    /// Avatars to draw in the world layer.
+    avatars: &'a [TracingPart],
+    /// Weather particles to draw in the world layer.
+    particles: &'a [TracingParticle],
     options: RtOptionsRef<'a, <P::BlockData as RtBlockData>::Options>,
 }
 
@@ -304,15 +343,29 @@ impl<P: PixelBuf> RtScene<'_, P> {
     fn trace_patch(&self, patch: NdcRect) -> (P, RaytraceInfo) {
         if let Some(ui) = self.rts.ui {
             let (pixel, info): (P, RaytraceInfo) =
-                trace_patch_in_one_space(ui, &self.cameras.ui, patch, false);
+                trace_patch_in_one_space(ui, &self.cameras.ui, patch, false, &[], &[]);
             if pixel.opaque() {
                 // TODO: We should be doing alpha blending, but doing that requires
                 // having control over the PixelBuf that trace_ray starts with.
                 return (pixel, info);
             }
         }
+        if let Some(hand) = self.rts.hand {
+            let (pixel, info): (P, RaytraceInfo) =
+                trace_patch_in_one_space(hand, &self.cameras.hand, patch, false, &[], &[]);
+            if pixel.opaque() {
+                return (pixel, info);
+            }
+        }
         if let Some(world) = self.rts.world {
-            return trace_patch_in_one_space(world, &self.cameras.world, patch, true);
+            return trace_patch_in_one_space(
+                world,
+                &self.cameras.world,
+                patch,
+                true,
+                self.avatars,
+                self.particles,
+            );
         }
         (
             P::paint(palette::NO_WORLD_TO_SHOW, self.options),
@@ -326,11 +379,16 @@ fn trace_patch_in_one_space<P: PixelBuf>(
     camera: &Camera,
     patch: NdcRect,
     include_sky: bool,
+    avatars: &[TracingPart],
+    particles: &[TracingParticle],
 ) -> (P, RaytraceInfo) {
     match camera.options().antialiasing {
-        AntialiasingOption::None | AntialiasingOption::IfCheap => {
-            space.trace_ray(camera.project_ndc_into_world(patch.center()), include_sky)
-        }
+        AntialiasingOption::None | AntialiasingOption::IfCheap => space.trace_ray_with_avatars(
+            camera.project_ndc_into_world(patch.center()),
+            include_sky,
+            avatars,
+            particles,
+        ),
         AntialiasingOption::Always => {
             const N: usize = 4;
             const SAMPLE_POINTS: [Vector2<f64>; N] = [
@@ -341,9 +399,11 @@ fn trace_patch_in_one_space<P: PixelBuf>(
             ];
             let mut info = RaytraceInfo::default();
             let samples: [P; N] = std::array::from_fn(|i| {
-                let (p, i) = space.trace_ray(
+                let (p, i) = space.trace_ray_with_avatars(
                     camera.project_ndc_into_world(patch.point_within(SAMPLE_POINTS[i])),
                     include_sky,
+                    avatars,
+                    particles,
                 );
                 info += i;
                 p
@@ -489,7 +549,9 @@ mod trace_image {
 
 mod eg {
     use super::*;
-    use crate::camera::info_text_drawable;
+    use crate::camera::{
+        info_text_drawable, info_text_scale, GraphicsOptions, InfoTextScale, UiPaletteOption,
+    };
     use embedded_graphics::draw_target::DrawTarget;
     use embedded_graphics::draw_target::DrawTargetExt;
     use embedded_graphics::pixelcolor::BinaryColor;
@@ -501,10 +563,11 @@ mod eg {
     pub fn draw_info_text<T: Clone>(
         output: &mut [T],
         viewport: Viewport,
+        options: &GraphicsOptions,
         paint: [T; 2],
         info_text: &str,
     ) {
-        let target = &mut eg::EgImageTarget {
+        let image_target = &mut eg::EgImageTarget {
             data: output,
             paint,
             size: Size {
@@ -512,20 +575,32 @@ mod eg {
                 height: viewport.framebuffer_size.y,
             },
         };
+        let target = &mut InfoTextScale::new(
+            image_target,
+            info_text_scale(options, viewport, viewport.framebuffer_size.x),
+        );
+
+        // The high-contrast shadow also covers the diagonals, making a solid outline.
+        let shadow_offsets: &[[i32; 2]] = match options.ui_palette {
+            UiPaletteOption::HighContrast => &[
+                [-1, -1],
+                [0, -1],
+                [1, -1],
+                [-1, 0],
+                [1, 0],
+                [-1, 1],
+                [0, 1],
+                [1, 1],
+            ],
+            _ => &[[0, -1], [0, 1], [-1, 0], [1, 0]],
+        };
         let shadow = info_text_drawable(info_text, BinaryColor::Off);
-        // TODO: use .into_ok() when stable for infallible drawing
-        shadow
-            .draw(&mut target.translated(Point::new(0, -1)))
-            .unwrap();
-        shadow
-            .draw(&mut target.translated(Point::new(0, 1)))
-            .unwrap();
-        shadow
-            .draw(&mut target.translated(Point::new(-1, 0)))
-            .unwrap();
-        shadow
-            .draw(&mut target.translated(Point::new(1, 0)))
-            .unwrap();
+        for &[x, y] in shadow_offsets {
+            // TODO: use .into_ok() when stable for infallible drawing
+            shadow
+                .draw(&mut target.translated(Point::new(x, y)))
+                .unwrap();
+        }
         info_text_drawable(info_text, BinaryColor::On)
             .draw(target)
             .unwrap();
@@ -1,10 +1,15 @@
-use cgmath::{EuclideanSpace as _, Point3, Vector3};
+use cgmath::{EuclideanSpace as _, Matrix4, Point3, Transform as _, Vector3};
 
 use crate::block::{recursive_ray, Evoxel, Evoxels};
 use crate::camera::LightingOption;
-use crate::math::{Face7, FaceMap, FreeCoordinate, GridArray, GridPoint, Rgb, Rgba};
+use crate::character::PosedPart;
+use crate::math::{
+    point_to_enclosing_cube, Face6, Face7, FaceMap, FreeCoordinate, Geometry as _, GridAab,
+    GridArray, GridCoordinate, GridPoint, LineVertex, Rgb, Rgba,
+};
 use crate::raycast::{Ray, Raycaster};
 use crate::raytracer::{RtBlockData, SpaceRaytracer, TracingBlock, TracingCubeData};
+use crate::space::WeatherParticles;
 
 /// Description of a surface the ray passes through (or from the volumetric perspective,
 /// a transition from one material to another).
@@ -14,6 +19,8 @@ pub(crate) struct Surface<'a, D> {
     pub block_data: &'a D,
     // pub voxel_data: ...?,
     pub diffuse_color: Rgba,
+    /// Light emitted by the surface, added to the illuminated `diffuse_color`.
+    pub emission: Rgb,
     /// The cube of the [`Space`] which contains the block this surface belongs to.
     cube: GridPoint,
     /// The distance along the ray, in units of the ray's direction vector,
@@ -39,7 +46,7 @@ impl<D: RtBlockData> Surface<'_, D> {
         if diffuse_color.fully_transparent() {
             return None;
         }
-        let adjusted_rgb = diffuse_color.to_rgb() * self.compute_illumination(rt);
+        let adjusted_rgb = diffuse_color.to_rgb() * self.compute_illumination(rt) + self.emission;
         Some(adjusted_rgb.with_alpha(diffuse_color.alpha()))
     }
 
@@ -58,6 +65,155 @@ impl<D: RtBlockData> Surface<'_, D> {
     }
 }
 
+/// A [`PosedPart`] of an avatar, prepared for raytracing.
+#[derive(Clone, Debug)]
+pub(crate) struct TracingPart {
+    voxels: Evoxels,
+    /// Transform from the part's block to the space, as in [`PosedPart::transform`].
+    transform: Matrix4<FreeCoordinate>,
+    /// Inverse of `transform`.
+    inverse: Matrix4<FreeCoordinate>,
+}
+
+impl TracingPart {
+    /// Evaluates the part's block, or returns [`None`] if it cannot be drawn.
+    pub(crate) fn new(part: &PosedPart) -> Option<Self> {
+        Some(TracingPart {
+            voxels: part.block.evaluate().ok()?.voxels,
+            transform: part.transform,
+            inverse: part.transform.inverse_transform()?,
+        })
+    }
+
+    /// Returns the first visible voxel surface of this part that `ray` enters, or
+    /// [`None`] if it misses them all or starts inside one.
+    pub(crate) fn surface<'a, D>(&self, ray: Ray, block_data: &'a D) -> Option<Surface<'a, D>> {
+        let resolution = self.voxels.resolution();
+        let scale = FreeCoordinate::from(resolution);
+        // Transforming both the origin and direction leaves the ray's t values unchanged.
+        let voxel_ray = Ray::new(
+            self.inverse.transform_point(ray.origin) * scale,
+            self.inverse.transform_vector(ray.direction) * scale,
+        );
+        for step in voxel_ray.cast().within(GridAab::for_block(resolution)) {
+            let Some(voxel) = self.voxels.get(step.cube_ahead()) else {
+                continue;
+            };
+            let local_face = Face6::try_from(step.face()).ok()?;
+            let color = match &self.voxels {
+                Evoxels::Faces(_, faces) => faces[local_face],
+                _ => voxel.color,
+            };
+            if color.fully_transparent() {
+                continue;
+            }
+
+            // Light the surface as if it faced whichever direction of the space's grid is
+            // closest to its actual orientation.
+            let normal_vector = self.transform.transform_vector(local_face.normal_vector());
+            let normal = Face6::ALL
+                .into_iter()
+                .max_by(|a, b| a.dot(normal_vector).total_cmp(&b.dot(normal_vector)))?;
+
+            let t_distance = step.t_distance();
+            let intersection_point = ray.origin + ray.direction * t_distance;
+            return Some(Surface {
+                block_data,
+                diffuse_color: color,
+                emission: voxel.emission,
+                cube: point_to_enclosing_cube(
+                    intersection_point - normal.normal_vector() * (1. / 512.),
+                )?,
+                t_distance,
+                intersection_point,
+                normal: normal.into(),
+            });
+        }
+        None
+    }
+}
+
+/// A single [`WeatherParticles`] streak, prepared for raytracing as a thin vertical
+/// cylinder.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct TracingParticle {
+    top: Point3<FreeCoordinate>,
+    length: FreeCoordinate,
+    color: Rgba,
+}
+
+impl TracingParticle {
+    /// Radius of the cylinder; the GPU renderer draws particles as lines, so this
+    /// should be thin.
+    const RADIUS: FreeCoordinate = 1. / 64.;
+
+    /// Converts the line segments of `particles` into [`TracingParticle`]s.
+    pub(crate) fn from_particles(particles: &WeatherParticles) -> Vec<Self> {
+        let mut points: Vec<LineVertex> = Vec::new();
+        particles.wireframe_points(&mut points);
+        points
+            .chunks_exact(2)
+            .map(|line| TracingParticle {
+                top: line[0].position,
+                length: line[0].position.y - line[1].position.y,
+                color: line[0].color.unwrap_or(Rgba::WHITE),
+            })
+            .collect()
+    }
+
+    /// Returns the surface of this particle that `ray` enters, or [`None`] if it misses.
+    ///
+    /// Rays which are vertical, or which start inside the particle, never hit it.
+    pub(crate) fn surface<'a, D>(&self, ray: Ray, block_data: &'a D) -> Option<Surface<'a, D>> {
+        let (dx, dz) = (ray.direction.x, ray.direction.z);
+        let (ox, oz) = (ray.origin.x - self.top.x, ray.origin.z - self.top.z);
+        let horizontal_speed_squared = dx * dx + dz * dz;
+        if horizontal_speed_squared == 0.0 {
+            return None;
+        }
+
+        // Find the closest horizontal approach, then back up to the cylinder's wall.
+        let t_closest = -(ox * dx + oz * dz) / horizontal_speed_squared;
+        let (cx, cz) = (ox + dx * t_closest, oz + dz * t_closest);
+        let closest_squared = cx * cx + cz * cz;
+        if closest_squared > Self::RADIUS * Self::RADIUS {
+            return None;
+        }
+        let t_distance = t_closest
+            - ((Self::RADIUS * Self::RADIUS - closest_squared) / horizontal_speed_squared).sqrt();
+        if t_distance < 0.0 {
+            return None;
+        }
+        let intersection_point = ray.origin + ray.direction * t_distance;
+        if !(self.top.y - self.length..=self.top.y).contains(&intersection_point.y) {
+            return None;
+        }
+
+        // Light the surface as if it faced back along the ray's main horizontal direction.
+        let normal = if dx.abs() > dz.abs() {
+            if dx > 0.0 {
+                Face6::NX
+            } else {
+                Face6::PX
+            }
+        } else if dz > 0.0 {
+            Face6::NZ
+        } else {
+            Face6::PZ
+        };
+
+        Some(Surface {
+            block_data,
+            diffuse_color: self.color,
+            emission: Rgb::ZERO,
+            cube: point_to_enclosing_cube(intersection_point)?,
+            t_distance,
+            intersection_point,
+            normal: normal.into(),
+        })
+    }
+}
+
 /// Simple directional lighting used to give corners extra definition.
 /// Note that this algorithm is also implemented in the fragment shader for GPU rendering.
 fn fixed_directional_lighting(face: Face7) -> f32 {
@@ -186,7 +342,13 @@ impl<'a, D> Iterator for SurfaceIter<'a, D> {
 
         let tb: &TracingBlock<D> = &self.blocks[cube_data.block_index as usize];
         Some(match tb.voxels {
-            Evoxels::One(Evoxel { color, .. }) => {
+            Evoxels::One(_) | Evoxels::Faces(..) => {
+                let voxel = tb.voxels.single_voxel().unwrap();
+                let color = match (&tb.voxels, Face6::try_from(rc_step.face())) {
+                    (&Evoxels::Faces(_, faces), Ok(face)) => faces[face],
+                    // Evoxels::One, or starting inside the block
+                    _ => voxel.color,
+                };
                 if color.fully_transparent() {
                     // The caller could generically skip transparent, but if we do it then
                     // we can skip some math too.
@@ -197,6 +359,7 @@ impl<'a, D> Iterator for SurfaceIter<'a, D> {
                     TraceStep::EnterSurface(Surface {
                         block_data: &tb.block_data,
                         diffuse_color: color,
+                        emission: voxel.emission,
                         cube: rc_step.cube_ahead(),
                         t_distance: rc_step.t_distance(),
                         intersection_point: rc_step.intersection_point(self.ray),
@@ -209,9 +372,28 @@ impl<'a, D> Iterator for SurfaceIter<'a, D> {
                 let sub_ray = recursive_ray(self.ray, block_cube, resolution);
                 let antiscale = FreeCoordinate::from(resolution).recip();
 
+                // If we have brick occupancy data, then iterate over bricks first, and only
+                // create voxel raycasters for the nonempty ones.
+                let (voxel_raycaster, bricks) = match &tb.brick_occupancy {
+                    Some(occupancy) => (
+                        None,
+                        Some(BrickIter {
+                            brick_raycaster: Ray {
+                                origin: sub_ray.origin / FreeCoordinate::from(BRICK_SIZE),
+                                direction: sub_ray.direction,
+                            }
+                            .cast()
+                            .within(occupancy.bounds()),
+                            occupancy,
+                        }),
+                    ),
+                    None => (Some(sub_ray.cast().within(array.bounds())), None),
+                };
+
                 self.current_block = Some(VoxelSurfaceIter {
                     voxel_ray: sub_ray,
-                    voxel_raycaster: sub_ray.cast().within(array.bounds()),
+                    voxel_raycaster,
+                    bricks,
                     block_data: &tb.block_data,
                     antiscale,
                     array,
@@ -232,7 +414,12 @@ impl<'a, D> Iterator for SurfaceIter<'a, D> {
 #[derive(Clone, Debug)]
 struct VoxelSurfaceIter<'a, D> {
     voxel_ray: Ray,
-    voxel_raycaster: Raycaster,
+    /// Raycaster over the voxels of the current brick, or the entire block if there
+    /// is no brick data.
+    /// [`None`] if there is no current brick.
+    voxel_raycaster: Option<Raycaster>,
+    /// Raycaster over bricks, if this block has [brick occupancy](brick_occupancy) data.
+    bricks: Option<BrickIter<'a>>,
     block_data: &'a D,
     /// Reciprocal of resolution, for scaling back to outer world
     antiscale: FreeCoordinate,
@@ -245,8 +432,34 @@ impl<'a, D> VoxelSurfaceIter<'a, D> {
     /// This is not an  implementation of `Iterator` because it doesn't need to be — it's
     /// purely internal to [`SurfaceIter`].
     fn next(&mut self) -> Option<TraceStep<'a, D>> {
+        let rc_step = loop {
+            if let Some(rc_step) = self.voxel_raycaster.as_mut().and_then(Raycaster::next) {
+                break rc_step;
+            }
+
+            // Current brick (if any) is exhausted; advance to the next one, or stop if
+            // there are no bricks.
+            self.voxel_raycaster = None;
+            let bricks = self.bricks.as_mut()?;
+            let brick_step = bricks.brick_raycaster.next()?;
+            let brick = brick_step.cube_ahead();
+            if bricks.occupancy.get(brick) == Some(&true) {
+                let voxel_bounds = GridAab::single_cube(brick)
+                    .multiply(BRICK_SIZE)
+                    .intersection(self.array.bounds())?;
+                self.voxel_raycaster = Some(self.voxel_ray.cast().within(voxel_bounds));
+            } else {
+                // Skip the entire empty brick in one step. We still must report it,
+                // because its entry is the exit of any preceding visible voxel.
+                return Some(TraceStep::Invisible {
+                    t_distance: brick_step.t_distance()
+                        * FreeCoordinate::from(BRICK_SIZE)
+                        * self.antiscale,
+                });
+            }
+        };
+
         // Fetch data and return None if out of range.
-        let rc_step = self.voxel_raycaster.next()?;
         let voxel = self.array.get(rc_step.cube_ahead())?;
 
         if voxel.color.fully_transparent() {
@@ -258,6 +471,7 @@ impl<'a, D> VoxelSurfaceIter<'a, D> {
         Some(TraceStep::EnterSurface(Surface {
             block_data: self.block_data,
             diffuse_color: voxel.color,
+            emission: voxel.emission,
             cube: self.block_cube,
             // Note: The proper scaling here depends on the direction vector scale, that
             // recursive_ray() _doesn't_ change.
@@ -269,6 +483,39 @@ impl<'a, D> VoxelSurfaceIter<'a, D> {
     }
 }
 
+/// Raycasting state for skipping empty bricks in [`VoxelSurfaceIter`].
+#[derive(Clone, Debug)]
+struct BrickIter<'a> {
+    /// Raycaster in the coordinate system of `occupancy`
+    /// (voxel coordinates divided by [`BRICK_SIZE`]).
+    brick_raycaster: Raycaster,
+    occupancy: &'a GridArray<bool>,
+}
+
+/// Side length, in voxels, of the cubical regions tracked by [`brick_occupancy()`].
+const BRICK_SIZE: GridCoordinate = 4;
+
+/// Computes a coarse map of which `BRICK_SIZE`-sized regions of the given voxels
+/// contain any voxels that are not fully transparent, which allows the raytracer to
+/// skip over empty regions of a block instead of visiting each voxel.
+///
+/// Returns [`None`] if the block is not large enough to benefit.
+pub(crate) fn brick_occupancy(voxels: &Evoxels) -> Option<GridArray<bool>> {
+    let Evoxels::Many(resolution, array) = voxels else {
+        return None;
+    };
+    if GridCoordinate::from(*resolution) <= BRICK_SIZE {
+        return None;
+    }
+    let mut occupancy = GridArray::repeat(array.bounds().divide(BRICK_SIZE), false);
+    for cube in array.bounds().interior_iter() {
+        if !array[cube].color.fully_transparent() {
+            occupancy[cube.map(|c| c.div_euclid(BRICK_SIZE))] = true;
+        }
+    }
+    Some(occupancy)
+}
+
 /// Builds on [`SurfaceIter`] to report spans of transparency along the ray.
 pub(crate) struct DepthIter<'a, D> {
     surface_iter: SurfaceIter<'a, D>,
@@ -369,6 +616,7 @@ mod tests {
                 EnterSurface(Surface {
                     block_data: &(),
                     diffuse_color: solid_test_color,
+                    emission: Rgb::ZERO,
                     cube: GridPoint::new(0, 1, 0),
                     t_distance: 1.5, // half-block starting point + 1 empty block
                     intersection_point: Point3::new(0.5, 1.0, 0.5),
@@ -378,6 +626,7 @@ mod tests {
                 EnterSurface(Surface {
                     block_data: &(),
                     diffuse_color: slab_test_color,
+                    emission: Rgb::ZERO,
                     cube: GridPoint::new(0, 2, 0),
                     t_distance: 2.5,
                     intersection_point: Point3::new(0.5, 2.0, 0.5),
@@ -387,6 +636,7 @@ mod tests {
                 EnterSurface(Surface {
                     block_data: &(),
                     diffuse_color: slab_test_color,
+                    emission: Rgb::ZERO,
                     cube: GridPoint::new(0, 2, 0),
                     t_distance: 2.75, // previous surface + 1/4 block of depth
                     intersection_point: Point3::new(0.5, 2.25, 0.5),
@@ -420,6 +670,7 @@ mod tests {
                 EnterSurface(Surface {
                     block_data: &(),
                     diffuse_color: solid_test_color,
+                    emission: Rgb::ZERO,
                     cube: GridPoint::new(0, 0, 0),
                     t_distance: 0.5, // half-block starting point
                     intersection_point: Point3::new(0.0, 0.5, 0.5),
@@ -430,4 +681,143 @@ mod tests {
             ]
         );
     }
+
+    /// Test that empty bricks of a high-resolution block are each skipped in one step,
+    /// and that the exit from the visible voxels is still reported.
+    #[test]
+    fn surface_iter_skips_empty_bricks() {
+        let universe = &mut Universe::new();
+        let solid_test_color = rgba_const!(1., 0., 0., 1.);
+        let solid_test_color_block = Block::from(solid_test_color);
+        // Only the third layer of bricks is nonempty.
+        let block = Block::builder()
+            .voxels_fn(universe, R16, |cube| {
+                if (8..12).contains(&cube.y) {
+                    &solid_test_color_block
+                } else {
+                    &AIR
+                }
+            })
+            .unwrap()
+            .build();
+        let space = Space::builder(GridAab::from_lower_size([0, 0, 0], [1, 1, 1]))
+            .filled_with(block)
+            .build();
+
+        let rt = SpaceRaytracer::<()>::new(&space, GraphicsOptions::default(), ());
+
+        // Compare only kind and distance, for brevity.
+        let steps: Vec<(&str, FreeCoordinate)> =
+            SurfaceIter::new(&rt, Ray::new([0.25, -0.5, 0.25], [0., 1., 0.]))
+                .map(|step| match step {
+                    EnterSurface(s) => ("surface", s.t_distance),
+                    Invisible { t_distance } => ("invisible", t_distance),
+                    EnterBlock { t_distance } => ("block", t_distance),
+                })
+                .collect();
+        assert_eq!(
+            steps,
+            vec![
+                ("block", 0.5),
+                ("invisible", 0.5),  // brick y = 0
+                ("invisible", 0.75), // brick y = 1
+                ("surface", 1.0),    // voxels y = 8..12
+                ("surface", 1.0625),
+                ("surface", 1.125),
+                ("surface", 1.1875),
+                ("invisible", 1.25), // brick y = 3, which is also the exit of the voxels
+                ("invisible", 1.5),  // exit block
+            ]
+        );
+    }
+
+    #[test]
+    fn emission_is_added_to_lit_color() {
+        let block = Block::builder()
+            .color(rgba_const!(0.5, 0.0, 0.0, 1.0))
+            .light_emission(rgb_const!(0.0, 0.25, 0.0))
+            .build();
+        let space = Space::builder(GridAab::from_lower_size([0, 0, 0], [1, 1, 1]))
+            .filled_with(block)
+            .build();
+        let mut options = GraphicsOptions::default();
+        options.lighting_display = LightingOption::None;
+        let rt = SpaceRaytracer::<()>::new(&space, options, ());
+
+        let surface = SurfaceIter::new(&rt, Ray::new([-0.5, 0.5, 0.5], [1., 0., 0.]))
+            .find_map(|step| match step {
+                EnterSurface(surface) => Some(surface),
+                _ => None,
+            })
+            .unwrap();
+        assert_eq!(surface.emission, rgb_const!(0.0, 0.25, 0.0));
+        assert_eq!(
+            surface.to_lit_color(&rt),
+            Some(rgba_const!(0.5, 0.25, 0.0, 1.0))
+        );
+    }
+
+    #[test]
+    fn avatar_part_surface() {
+        // A part stretched to twice the height of a cube, at [0, 1, 0].
+        let part = TracingPart::new(&PosedPart {
+            block: Block::from(rgba_const!(1.0, 0.0, 0.0, 1.0)),
+            transform: Matrix4::from_translation(Vector3::new(0.0, 1.0, 0.0))
+                * Matrix4::from_nonuniform_scale(1.0, 2.0, 1.0),
+        })
+        .unwrap();
+
+        let surface = part
+            .surface(Ray::new([0.5, 2.5, 5.0], [0., 0., -1.]), &())
+            .unwrap();
+        assert_eq!(surface.t_distance, 4.0);
+        assert_eq!(surface.normal, Face7::PZ);
+        assert_eq!(surface.cube, GridPoint::new(0, 2, 0));
+        assert_eq!(surface.diffuse_color, rgba_const!(1.0, 0.0, 0.0, 1.0));
+
+        // Passing above the part
+        assert_eq!(
+            part.surface(Ray::new([0.5, 3.5, 5.0], [0., 0., -1.]), &()),
+            None
+        );
+        // Starting inside the part
+        assert_eq!(
+            part.surface(Ray::new([0.5, 2.5, 0.5], [0., 0., -1.]), &()),
+            None
+        );
+    }
+
+    #[test]
+    fn particle_surface() {
+        let color = rgba_const!(0.0, 0.0, 1.0, 1.0);
+        let particle = TracingParticle {
+            top: Point3::new(0.5, 1.5, 0.5),
+            length: 1.0,
+            color,
+        };
+
+        let surface = particle
+            .surface(Ray::new([0.5, 1.0, 5.0], [0., 0., -1.]), &())
+            .unwrap();
+        assert_eq!(surface.t_distance, 4.5 - TracingParticle::RADIUS);
+        assert_eq!(surface.normal, Face7::PZ);
+        assert_eq!(surface.cube, GridPoint::new(0, 1, 0));
+        assert_eq!(surface.diffuse_color, color);
+
+        // Passing above the particle
+        assert_eq!(
+            particle.surface(Ray::new([0.5, 1.75, 5.0], [0., 0., -1.]), &()),
+            None
+        );
+        // Passing beside the particle
+        assert_eq!(
+            particle.surface(Ray::new([0.75, 1.0, 5.0], [0., 0., -1.]), &()),
+            None
+        );
+        // Vertical
+        assert_eq!(
+            particle.surface(Ray::new([0.5, 5.0, 0.5], [0., -1., 0.]), &()),
+            None
+        );
+    }
 }
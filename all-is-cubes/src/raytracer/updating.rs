@@ -189,6 +189,8 @@ impl Listener<SpaceChange> for TodoListener {
                     SpaceChange::Number(index) | SpaceChange::BlockValue(index) => {
                         todo.blocks.insert(index);
                     }
+                    // Weather particles are not part of the tracing space.
+                    SpaceChange::Weather => {}
                 }
             }
         }
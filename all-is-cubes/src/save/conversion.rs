@@ -8,7 +8,6 @@ use super::schema;
 /// Implements [`Serialize`] and [`Deserialize`] for `$library_type` using the conversions
 /// * `TryFrom<$schema_type> for $library_type`
 /// * `From<&$library_type> for $schema_type`
-#[allow(unused)] // TODO: use this
 macro_rules! impl_serde_via_schema_by_ref {
     ($library_type:ty, $schema_type:ty) => {
         impl ::serde::Serialize for $library_type {
@@ -37,8 +36,10 @@ macro_rules! impl_serde_via_schema_by_ref {
 
 mod block {
     use super::*;
-    use crate::block::{Block, BlockAttributes, Composite, Modifier, Move, Primitive, Quote, Zoom};
-    use crate::math::Rgba;
+    use crate::block::{
+        Animate, Block, BlockAttributes, Composite, Connect, Modifier, Move, Primitive, Quote, Zoom,
+    };
+    use crate::math::{Face6, FaceMap, NotNan, Rgba};
     use schema::{BlockSer, ModifierSer};
 
     impl Serialize for Block {
@@ -82,6 +83,10 @@ mod block {
                     color: color.into(),
                     attributes: attributes.into(),
                 },
+                &Primitive::Faces(ref attributes, faces) => schema::PrimitiveSer::FacesV1 {
+                    faces: faces.into(),
+                    attributes: attributes.into(),
+                },
                 &Primitive::Recur {
                     ref attributes,
                     ref space,
@@ -105,6 +110,9 @@ mod block {
                 schema::PrimitiveSer::AtomV1 { attributes, color } => {
                     Primitive::Atom(BlockAttributes::from(attributes), Rgba::from(color))
                 }
+                schema::PrimitiveSer::FacesV1 { faces, attributes } => {
+                    Primitive::Faces(BlockAttributes::from(attributes), FaceMap::from(faces))
+                }
                 schema::PrimitiveSer::RecurV1 {
                     attributes,
                     space,
@@ -128,15 +136,21 @@ mod block {
                 ref display_name,
                 selectable,
                 collision: _,
+                collision_groups: _,
                 rotation_rule: _,
                 light_emission,
                 tick_action: _,
+                tick_period: _,
+                signal: _,
+                portal: _,
+                contact_damage,
                 animation_hint: _,
             } = value;
             schema::BlockAttributesV1Ser {
                 display_name: display_name.to_string(),
                 selectable,
                 light_emission: light_emission.into(),
+                contact_damage: contact_damage.into_inner(),
             }
         }
     }
@@ -148,11 +162,13 @@ mod block {
                 display_name,
                 selectable,
                 light_emission,
+                contact_damage,
             } = value;
             Self {
                 display_name: display_name.into(),
                 selectable,
                 light_emission: light_emission.into(),
+                contact_damage: NotNan::new(contact_damage).unwrap_or_else(|_| notnan!(0.0)),
                 ..Default::default()
             }
         }
@@ -186,6 +202,18 @@ mod block {
                     distance,
                     velocity,
                 },
+                Modifier::Connect(ref c) => ModifierSer::ConnectV1 {
+                    faces: Face6::ALL
+                        .into_iter()
+                        .filter(|&face| c.faces()[face])
+                        .collect(),
+                    variants: c.variants().to_vec(),
+                },
+                Modifier::Animate(ref a) => ModifierSer::AnimateV1 {
+                    frames: a.frames().to_vec(),
+                    ticks_per_frame: a.ticks_per_frame(),
+                    phase: a.phase(),
+                },
             }
         }
     }
@@ -216,6 +244,15 @@ mod block {
                     distance,
                     velocity,
                 } => Modifier::Move(Move::new(direction, distance, velocity)),
+                ModifierSer::ConnectV1 { faces, variants } => Modifier::Connect(Connect::new(
+                    FaceMap::from_fn(|face| faces.contains(&face)),
+                    variants,
+                )),
+                ModifierSer::AnimateV1 {
+                    frames,
+                    ticks_per_frame,
+                    phase,
+                } => Modifier::Animate(Animate::new(frames, ticks_per_frame).with_phase(phase)),
             }
         }
     }
@@ -223,9 +260,177 @@ mod block {
 
 // `character::Character` serialization is inside its module for the sake of private fields.
 
+mod character {
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::block::Block;
+    use crate::character::PlayerStats;
+    use crate::time::Duration;
+    use schema::{BlockCountSer, PlayerStatsSer};
+
+    impl Serialize for PlayerStats {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            let &Self {
+                ref character,
+                ref blocks_placed,
+                ref blocks_removed,
+                distance_traveled,
+                time_played,
+                last_position: _,
+            } = self;
+            PlayerStatsSer::PlayerStatsV1 {
+                character: character.clone(),
+                blocks_placed: counts_to_schema(blocks_placed),
+                blocks_removed: counts_to_schema(blocks_removed),
+                distance_traveled,
+                time_played: time_played.as_secs_f64(),
+            }
+            .serialize(serializer)
+        }
+    }
+
+    impl<'de> Deserialize<'de> for PlayerStats {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            match PlayerStatsSer::deserialize(deserializer)? {
+                PlayerStatsSer::PlayerStatsV1 {
+                    character,
+                    blocks_placed,
+                    blocks_removed,
+                    distance_traveled,
+                    time_played,
+                } => {
+                    let time_played = Duration::try_from_secs_f64(time_played).map_err(|e| {
+                        serde::de::Error::custom(format!("invalid time_played {time_played}: {e}"))
+                    })?;
+                    Ok(PlayerStats {
+                        character,
+                        blocks_placed: counts_from_schema(blocks_placed),
+                        blocks_removed: counts_from_schema(blocks_removed),
+                        distance_traveled,
+                        time_played,
+                        last_position: None,
+                    })
+                }
+            }
+        }
+    }
+
+    /// Converts block counts to their serialized form, in a consistent order so that
+    /// the same counts always serialize the same way, though [`HashMap`] iteration
+    /// order varies.
+    fn counts_to_schema(counts: &HashMap<Block, u64>) -> Vec<BlockCountSer> {
+        let mut entries: Vec<BlockCountSer> = counts
+            .iter()
+            .map(|(block, &count)| BlockCountSer {
+                block: block.clone(),
+                count,
+            })
+            .collect();
+        // Blocks have no ordering of their own, so order them by their debug text.
+        entries.sort_by_cached_key(|entry| format!("{:?}", entry.block));
+        entries
+    }
+
+    fn counts_from_schema(counts: Vec<BlockCountSer>) -> HashMap<Block, u64> {
+        let mut map = HashMap::with_capacity(counts.len());
+        for BlockCountSer { block, count } in counts {
+            *map.entry(block).or_insert(0) += count;
+        }
+        map
+    }
+}
+
+mod entity {
+    use super::*;
+    use crate::behavior::BehaviorSet;
+    use crate::entity::Entity;
+    use crate::physics::{Body, CollisionGroups};
+    use crate::time::Duration;
+    use schema::EntitySer;
+
+    impl From<&Entity> for EntitySer {
+        fn from(entity: &Entity) -> Self {
+            let Entity {
+                body:
+                    Body {
+                        position,
+                        velocity,
+                        collision_box,
+                        flying,
+                        noclip,
+                        collision_groups: _, // TODO: should be persisted
+                        collides_with: _,    // TODO: should be persisted
+                        yaw,
+                        pitch,
+                    },
+                space,
+                appearance,
+                behaviors: _, // TODO: should be persisted
+                item,
+                age,
+            } = entity;
+            EntitySer::EntityV1 {
+                space: space.clone(),
+                position: (*position).into(),
+                velocity: (*velocity).into(),
+                collision_box: *collision_box,
+                flying: *flying,
+                noclip: *noclip,
+                yaw: *yaw,
+                pitch: *pitch,
+                appearance: appearance.clone(),
+                item: item.clone(),
+                age: age.as_secs_f64(),
+            }
+        }
+    }
+
+    impl TryFrom<EntitySer> for Entity {
+        type Error = std::time::TryFromFloatSecsError;
+
+        fn try_from(value: EntitySer) -> Result<Self, Self::Error> {
+            match value {
+                EntitySer::EntityV1 {
+                    space,
+                    position,
+                    velocity,
+                    collision_box,
+                    flying,
+                    noclip,
+                    yaw,
+                    pitch,
+                    appearance,
+                    item,
+                    age,
+                } => Ok(Entity {
+                    body: Body {
+                        position: position.into(),
+                        velocity: velocity.into(),
+                        collision_box,
+                        flying,
+                        noclip,
+                        collision_groups: CollisionGroups::ENTITY,
+                        collides_with: CollisionGroups::default(),
+                        yaw,
+                        pitch,
+                    },
+                    space,
+                    appearance,
+                    behaviors: BehaviorSet::new(),
+                    item,
+                    age: Duration::try_from_secs_f64(age)?,
+                }),
+            }
+        }
+    }
+
+    impl_serde_via_schema_by_ref!(Entity, EntitySer);
+}
+
 mod math {
     use super::*;
-    use crate::math::{Aab, GridAab};
+    use crate::math::{Aab, FaceMap, GridAab};
 
     impl Serialize for Aab {
         fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
@@ -251,6 +456,48 @@ mod math {
         }
     }
 
+    impl<T, U: From<T>> From<FaceMap<T>> for schema::FaceMapSer<U> {
+        fn from(value: FaceMap<T>) -> Self {
+            let FaceMap {
+                nx,
+                ny,
+                nz,
+                px,
+                py,
+                pz,
+            } = value;
+            schema::FaceMapSer {
+                nx: nx.into(),
+                ny: ny.into(),
+                nz: nz.into(),
+                px: px.into(),
+                py: py.into(),
+                pz: pz.into(),
+            }
+        }
+    }
+
+    impl<T, U: From<T>> From<schema::FaceMapSer<T>> for FaceMap<U> {
+        fn from(value: schema::FaceMapSer<T>) -> Self {
+            let schema::FaceMapSer {
+                nx,
+                ny,
+                nz,
+                px,
+                py,
+                pz,
+            } = value;
+            FaceMap {
+                nx: nx.into(),
+                ny: ny.into(),
+                nz: nz.into(),
+                px: px.into(),
+                py: py.into(),
+                pz: pz.into(),
+            }
+        }
+    }
+
     impl Serialize for GridAab {
         fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
         where
@@ -278,7 +525,8 @@ mod math {
 
 mod inv {
     use super::*;
-    use crate::inv::{EphemeralOpaque, Inventory, Slot, Tool};
+    use crate::inv::{EphemeralOpaque, Inventory, Slot, StackLimits, Tool, ToolKind};
+    use schema::{StackLimitOverrideSer, StackLimitsSer, ToolKindSer};
 
     impl Serialize for Inventory {
         fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
@@ -308,15 +556,85 @@ mod inv {
             D: Deserializer<'de>,
         {
             match schema::InventorySer::deserialize(deserializer)? {
-                schema::InventorySer::InventoryV1 { slots } => Ok(Inventory {
-                    slots: slots
+                schema::InventorySer::InventoryV1 { slots } => Ok(Inventory::from_slots(
+                    slots
                         .into_iter()
                         .map(|slot| match slot {
                             Some(schema::InvStackSer { count, item }) => Slot::Stack(count, item),
                             None => Slot::Empty,
                         })
                         .collect(),
-                }),
+                )),
+            }
+        }
+    }
+
+    impl Serialize for StackLimits {
+        fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+        where
+            S: Serializer,
+        {
+            StackLimitsSer::StackLimitsV1 {
+                standard: self.standard(),
+                overrides: self
+                    .overrides()
+                    .map(|(kind, limit)| StackLimitOverrideSer {
+                        tool: kind.into(),
+                        limit,
+                    })
+                    .collect(),
+            }
+            .serialize(serializer)
+        }
+    }
+
+    impl<'de> Deserialize<'de> for StackLimits {
+        fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+        where
+            D: Deserializer<'de>,
+        {
+            match StackLimitsSer::deserialize(deserializer)? {
+                StackLimitsSer::StackLimitsV1 {
+                    standard,
+                    overrides,
+                } => Ok(overrides.into_iter().fold(
+                    StackLimits::new(standard),
+                    |limits, StackLimitOverrideSer { tool, limit }| {
+                        limits.with_override(tool.into(), limit)
+                    },
+                )),
+            }
+        }
+    }
+
+    impl From<ToolKind> for ToolKindSer {
+        fn from(kind: ToolKind) -> Self {
+            match kind {
+                ToolKind::Activate => ToolKindSer::Activate,
+                ToolKind::RemoveBlock => ToolKindSer::RemoveBlock,
+                ToolKind::Block => ToolKindSer::Block,
+                ToolKind::InfiniteBlocks => ToolKindSer::InfiniteBlocks,
+                ToolKind::CopyFromSpace => ToolKindSer::CopyFromSpace,
+                ToolKind::EditBlock => ToolKindSer::EditBlock,
+                ToolKind::PushPull => ToolKindSer::PushPull,
+                ToolKind::Jetpack => ToolKindSer::Jetpack,
+                ToolKind::ExternalAction => ToolKindSer::ExternalAction,
+            }
+        }
+    }
+
+    impl From<ToolKindSer> for ToolKind {
+        fn from(kind: ToolKindSer) -> Self {
+            match kind {
+                ToolKindSer::Activate => ToolKind::Activate,
+                ToolKindSer::RemoveBlock => ToolKind::RemoveBlock,
+                ToolKindSer::Block => ToolKind::Block,
+                ToolKindSer::InfiniteBlocks => ToolKind::InfiniteBlocks,
+                ToolKindSer::CopyFromSpace => ToolKind::CopyFromSpace,
+                ToolKindSer::EditBlock => ToolKind::EditBlock,
+                ToolKindSer::PushPull => ToolKind::PushPull,
+                ToolKindSer::Jetpack => ToolKind::Jetpack,
+                ToolKindSer::ExternalAction => ToolKind::ExternalAction,
             }
         }
     }
@@ -371,16 +689,53 @@ mod inv {
     }
 }
 
+mod script {
+    use super::*;
+    use crate::script::Script;
+
+    impl Serialize for Script {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            schema::ScriptSer::ScriptV1 {
+                source: self.source.clone(),
+            }
+            .serialize(serializer)
+        }
+    }
+
+    impl<'de> Deserialize<'de> for Script {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            match schema::ScriptSer::deserialize(deserializer)? {
+                schema::ScriptSer::ScriptV1 { source } => Ok(Script::new(source)),
+            }
+        }
+    }
+}
+
 mod space {
     use super::*;
-    use crate::space::Space;
+    use crate::math::{FaceMap, NotNan, Rgb};
+    use crate::space::{
+        BlockIndex, CubeMap, CubeMapError, LightPhysics, PackedLight, Precipitation,
+        ProtectedRegion, RegionAccess, Sky, SkyGradient, Space, SpaceDelta, SpacePhysics,
+        SpaceSnapshot, Sun, Waypoint, Weather,
+    };
+    use schema::{
+        LightEncodingSer, LightPhysicsSer, PrecipitationSer, ProtectedRegionSer, RegionAccessSer,
+        RunLength, SkySer, SpaceContentsSer, SpaceLightSer, SpacePhysicsSer, SunSer, WaypointSer,
+        WeatherSer,
+    };
 
     impl Serialize for Space {
         fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
         where
             S: Serializer,
         {
-            // TODO: more efficient serialization without extract() and with some kind of compression
+            // TODO: more efficient serialization without extract()
+            let contents = self
+                .extract(self.bounds(), |index, _, _| {
+                    index.expect("shouldn't happen: serialization went out of bounds")
+                })
+                .into_elements();
             schema::SpaceSer::SpaceV1 {
                 bounds: self.bounds(),
                 blocks: self
@@ -388,11 +743,28 @@ mod space {
                     .iter()
                     .map(|bd| bd.block().clone())
                     .collect(),
-                contents: self
-                    .extract(self.bounds(), |index, _, _| {
-                        index.expect("shouldn't happen: serialization went out of bounds")
+                contents: contents_to_schema(contents, serializer.is_human_readable()),
+                light: if self.lighting.is_empty() {
+                    None
+                } else {
+                    let texels: Vec<[u16; 4]> =
+                        self.lighting.iter().map(|light| light.as_texel()).collect();
+                    Some(SpaceLightSer {
+                        encoding: LightEncodingSer::Log16,
+                        complete: self.is_lighting_complete(),
+                        runs: run_length_encode(&texels),
                     })
-                    .into_elements(),
+                },
+                physics: Some(self.physics().into()),
+                weather: (*self.weather() != Weather::CLEAR).then(|| self.weather().into()),
+                target_weather: (self.target_weather() != self.weather())
+                    .then(|| self.target_weather().into()),
+                powered: self.powered_cubes().collect(),
+                protected_regions: self
+                    .protected_regions()
+                    .iter()
+                    .map(ProtectedRegionSer::from)
+                    .collect(),
             }
             .serialize(serializer)
         }
@@ -403,43 +775,510 @@ mod space {
         where
             D: Deserializer<'de>,
         {
-            match schema::SpaceSer::deserialize(deserializer)? {
-                schema::SpaceSer::SpaceV1 {
-                    bounds,
-                    blocks,
-                    contents,
-                } => {
-                    // TODO: more efficient loading that sets blocks by index rather than value
-                    let mut space = Space::builder(bounds).build();
-                    for (cube, &block_index) in bounds.interior_iter().zip(contents.iter()) {
-                        space
-                            .set(
-                                cube,
-                                blocks.get(usize::from(block_index)).ok_or_else(|| {
-                                    serde::de::Error::custom(format!(
+            space_from_schema(schema::SpaceSer::deserialize(deserializer)?)
+        }
+    }
+
+    /// Construct a [`Space`] from its schema form.
+    ///
+    /// This is separate from [`Deserialize`] so that [`Universe`](crate::universe::Universe)
+    /// deserialization can delay it until the blocks in the space can be evaluated.
+    pub(super) fn space_from_schema<E: serde::de::Error>(
+        schema: schema::SpaceSer,
+    ) -> Result<Space, E> {
+        match schema {
+            schema::SpaceSer::SpaceV1 {
+                bounds,
+                blocks,
+                contents,
+                light,
+                physics,
+                weather,
+                target_weather,
+                powered,
+                protected_regions,
+            } => {
+                let contents = contents_to_flat::<E>(contents, bounds.volume())?;
+                let weather = weather.map(Weather::from).unwrap_or_default();
+                let target_weather = target_weather.map(Weather::from).unwrap_or(weather);
+
+                // TODO: more efficient loading that sets blocks by index rather than value
+                let mut space = Space::builder(bounds)
+                    .physics(
+                        physics
+                            .map(SpacePhysics::try_from)
+                            .transpose()
+                            .map_err(|e| E::custom(format!("invalid space physics: {e}")))?
+                            .unwrap_or_default(),
+                    )
+                    .weather(weather)
+                    .build();
+                space.set_weather(target_weather);
+                for (cube, &block_index) in bounds.interior_iter().zip(contents.iter()) {
+                    space
+                        .set(
+                            cube,
+                            blocks.get(usize::from(block_index)).ok_or_else(|| {
+                                E::custom(format!(
                                     "Space contents block index {block_index} out of bounds of \
                                     block table length {len}",
                                     len = blocks.len()
                                 ))
-                                })?,
-                            )
-                            .unwrap();
+                            })?,
+                        )
+                        .map_err(|e| E::custom(format!("failed to place block in space: {e}")))?;
+                }
+
+                if let Some(SpaceLightSer {
+                    encoding,
+                    complete,
+                    runs,
+                }) = light
+                {
+                    let lighting = run_length_decode::<_, E>(runs, bounds.volume(), "light")?
+                        .iter()
+                        .map(|&texel| {
+                            let current_texel = match encoding {
+                                LightEncodingSer::Log8 => migrate_log8_light(texel),
+                                LightEncodingSer::Log16 => Some(texel),
+                            };
+                            current_texel
+                                .and_then(PackedLight::from_texel)
+                                .ok_or_else(|| {
+                                    E::custom(format!("Space light value {texel:?} is invalid"))
+                                })
+                        })
+                        .collect::<Result<Box<[PackedLight]>, E>>()?;
+                    space.restore_lighting(lighting, complete);
+                }
+                space.restore_powered_cubes(powered);
+                for region in protected_regions {
+                    space.add_protected_region(region.into());
+                }
+
+                Ok(space)
+            }
+        }
+    }
+
+    impl From<&ProtectedRegion> for ProtectedRegionSer {
+        fn from(value: &ProtectedRegion) -> Self {
+            ProtectedRegionSer::ProtectedRegionV1 {
+                bounds: value.bounds(),
+                access: match value.access() {
+                    RegionAccess::ReadOnly => RegionAccessSer::ReadOnlyV1,
+                    RegionAccess::Owner(owner) => RegionAccessSer::OwnerV1 {
+                        owner: owner.clone(),
+                    },
+                },
+            }
+        }
+    }
+
+    impl From<ProtectedRegionSer> for ProtectedRegion {
+        fn from(value: ProtectedRegionSer) -> Self {
+            match value {
+                ProtectedRegionSer::ProtectedRegionV1 { bounds, access } => ProtectedRegion::new(
+                    bounds,
+                    match access {
+                        RegionAccessSer::ReadOnlyV1 => RegionAccess::ReadOnly,
+                        RegionAccessSer::OwnerV1 { owner } => RegionAccess::Owner(owner),
+                    },
+                ),
+            }
+        }
+    }
+
+    impl From<&SpacePhysics> for SpacePhysicsSer {
+        fn from(value: &SpacePhysics) -> Self {
+            let &SpacePhysics {
+                gravity,
+                air_drag,
+                terminal_velocity,
+                ref sky,
+                ref light,
+            } = value;
+            SpacePhysicsSer::SpacePhysicsV1 {
+                gravity: gravity.into(),
+                air_drag,
+                terminal_velocity,
+                sky_color: sky.mean().into(),
+                sky: match sky {
+                    Sky::Uniform(_) => None,
+                    Sky::Gradient(SkyGradient {
+                        zenith,
+                        horizon,
+                        nadir,
+                        sun,
+                    }) => Some(SkySer::GradientV1 {
+                        zenith: (*zenith).into(),
+                        horizon: (*horizon).into(),
+                        nadir: (*nadir).into(),
+                        sun: sun.as_ref().map(
+                            |&Sun {
+                                 direction,
+                                 color,
+                                 angular_radius,
+                             }| SunSer {
+                                direction: direction.into(),
+                                color: color.into(),
+                                angular_radius,
+                            },
+                        ),
+                    }),
+                    Sky::CubeMap(cube_map) => Some(SkySer::CubeMapV1 {
+                        resolution: cube_map.resolution(),
+                        faces: FaceMap::from_fn(|face| {
+                            cube_map
+                                .face(face)
+                                .iter()
+                                .map(|&texel| texel.into())
+                                .collect::<Vec<[NotNan<f32>; 3]>>()
+                        })
+                        .into(),
+                    }),
+                },
+                light: match *light {
+                    LightPhysics::None => LightPhysicsSer::NoneV1,
+                    LightPhysics::Rays { maximum_distance } => {
+                        LightPhysicsSer::RaysV1 { maximum_distance }
                     }
-                    Ok(space)
+                },
+            }
+        }
+    }
+
+    impl TryFrom<SpacePhysicsSer> for SpacePhysics {
+        type Error = CubeMapError;
+
+        fn try_from(value: SpacePhysicsSer) -> Result<Self, Self::Error> {
+            match value {
+                SpacePhysicsSer::SpacePhysicsV1 {
+                    gravity,
+                    air_drag,
+                    terminal_velocity,
+                    sky_color,
+                    sky,
+                    light,
+                } => Ok(SpacePhysics {
+                    gravity: gravity.into(),
+                    air_drag,
+                    terminal_velocity,
+                    sky: match sky {
+                        None => Sky::Uniform(sky_color.into()),
+                        Some(SkySer::GradientV1 {
+                            zenith,
+                            horizon,
+                            nadir,
+                            sun,
+                        }) => Sky::Gradient(SkyGradient {
+                            zenith: zenith.into(),
+                            horizon: horizon.into(),
+                            nadir: nadir.into(),
+                            sun: sun.map(
+                                |SunSer {
+                                     direction,
+                                     color,
+                                     angular_radius,
+                                 }| Sun {
+                                    direction: direction.into(),
+                                    color: color.into(),
+                                    angular_radius,
+                                },
+                            ),
+                        }),
+                        Some(SkySer::CubeMapV1 { resolution, faces }) => {
+                            let faces: FaceMap<Vec<[NotNan<f32>; 3]>> = faces.into();
+                            Sky::from(CubeMap::new(
+                                resolution,
+                                faces.map(|_, texels| texels.into_iter().map(Rgb::from).collect()),
+                            )?)
+                        }
+                    },
+                    light: match light {
+                        LightPhysicsSer::NoneV1 => LightPhysics::None,
+                        LightPhysicsSer::RaysV1 { maximum_distance } => {
+                            LightPhysics::Rays { maximum_distance }
+                        }
+                    },
+                }),
+            }
+        }
+    }
+
+    impl From<&Weather> for WeatherSer {
+        fn from(value: &Weather) -> Self {
+            WeatherSer::WeatherV1 {
+                precipitation: value
+                    .precipitation()
+                    .map(|precipitation| match precipitation {
+                        Precipitation::Rain => PrecipitationSer::Rain,
+                        Precipitation::Snow => PrecipitationSer::Snow,
+                    }),
+                intensity: NotNan::new(value.intensity()).unwrap(),
+                fog_density: NotNan::new(value.fog_density()).unwrap(),
+            }
+        }
+    }
+
+    impl From<WeatherSer> for Weather {
+        fn from(value: WeatherSer) -> Self {
+            match value {
+                WeatherSer::WeatherV1 {
+                    precipitation,
+                    intensity,
+                    fog_density,
+                } => Weather::new(
+                    precipitation.map(|precipitation| match precipitation {
+                        PrecipitationSer::Rain => Precipitation::Rain,
+                        PrecipitationSer::Snow => Precipitation::Snow,
+                    }),
+                    intensity.into_inner(),
+                    fog_density.into_inner(),
+                ),
+            }
+        }
+    }
+
+    impl Serialize for SpaceSnapshot {
+        fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+        where
+            S: Serializer,
+        {
+            schema::SpaceSnapshotSer::SpaceSnapshotV1 {
+                tick_number: self.tick_number,
+                bounds: self.bounds,
+                blocks: self.blocks.clone(),
+                contents: contents_to_schema(self.contents.clone(), serializer.is_human_readable()),
+            }
+            .serialize(serializer)
+        }
+    }
+
+    impl<'de> Deserialize<'de> for SpaceSnapshot {
+        fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+        where
+            D: Deserializer<'de>,
+        {
+            match schema::SpaceSnapshotSer::deserialize(deserializer)? {
+                schema::SpaceSnapshotSer::SpaceSnapshotV1 {
+                    tick_number,
+                    bounds,
+                    blocks,
+                    contents,
+                } => {
+                    let contents = contents_to_flat::<D::Error>(contents, bounds.volume())?;
+                    check_block_indices::<D::Error>(contents.iter().copied(), blocks.len())?;
+                    Ok(SpaceSnapshot {
+                        tick_number,
+                        bounds,
+                        blocks,
+                        contents,
+                    })
                 }
             }
         }
     }
+
+    impl Serialize for SpaceDelta {
+        fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+        where
+            S: Serializer,
+        {
+            schema::SpaceDeltaSer::SpaceDeltaV1 {
+                base_tick_number: self.base_tick_number,
+                tick_number: self.tick_number,
+                blocks: self.blocks.clone(),
+                cubes: self
+                    .cubes
+                    .iter()
+                    .map(|&(cube, block_index)| (cube.into(), block_index))
+                    .collect(),
+            }
+            .serialize(serializer)
+        }
+    }
+
+    impl<'de> Deserialize<'de> for SpaceDelta {
+        fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+        where
+            D: Deserializer<'de>,
+        {
+            match schema::SpaceDeltaSer::deserialize(deserializer)? {
+                schema::SpaceDeltaSer::SpaceDeltaV1 {
+                    base_tick_number,
+                    tick_number,
+                    blocks,
+                    cubes,
+                } => {
+                    check_block_indices::<D::Error>(
+                        cubes.iter().map(|&(_, block_index)| block_index),
+                        blocks.len(),
+                    )?;
+                    Ok(SpaceDelta {
+                        base_tick_number,
+                        tick_number,
+                        blocks,
+                        cubes: cubes
+                            .into_iter()
+                            .map(|(cube, block_index)| (cube.into(), block_index))
+                            .collect(),
+                    })
+                }
+            }
+        }
+    }
+
+    /// Convert a light value in [`LightEncodingSer::Log8`] to [`LightEncodingSer::Log16`],
+    /// which represents every such value exactly.
+    fn migrate_log8_light([r, g, b, status]: [u16; 4]) -> Option<[u16; 4]> {
+        fn component(value: u16) -> Option<u16> {
+            match value {
+                0 => Some(0),
+                1..=255 => Some(value * 128 + 16384),
+                _ => None,
+            }
+        }
+        Some([component(r)?, component(g)?, component(b)?, status])
+    }
+
+    /// Choose the representation of space contents suited to the serializer.
+    fn contents_to_schema(contents: Box<[BlockIndex]>, human_readable: bool) -> SpaceContentsSer {
+        if human_readable {
+            SpaceContentsSer::Flat(contents)
+        } else {
+            SpaceContentsSer::Runs(run_length_encode(&contents))
+        }
+    }
+
+    /// Check that every block index refers to an entry of a block table of length `len`.
+    fn check_block_indices<E: serde::de::Error>(
+        indices: impl IntoIterator<Item = BlockIndex>,
+        len: usize,
+    ) -> Result<(), E> {
+        match indices
+            .into_iter()
+            .find(|&block_index| usize::from(block_index) >= len)
+        {
+            Some(block_index) => Err(E::custom(format!(
+                "block index {block_index} out of bounds of block table length {len}"
+            ))),
+            None => Ok(()),
+        }
+    }
+
+    fn run_length_encode<T: Copy + PartialEq>(items: &[T]) -> Vec<(T, RunLength)> {
+        let mut runs: Vec<(T, RunLength)> = Vec::new();
+        for &item in items {
+            match runs.last_mut() {
+                Some((run_item, count)) if *run_item == item && *count < RunLength::MAX => {
+                    *count += 1
+                }
+                _ => runs.push((item, 1)),
+            }
+        }
+        runs
+    }
+
+    /// Expand the output of [`run_length_encode()`], checking that the number of items
+    /// is `volume`. `what` names the data for error messages.
+    fn run_length_decode<T: Clone, E: serde::de::Error>(
+        runs: Vec<(T, RunLength)>,
+        volume: usize,
+        what: &str,
+    ) -> Result<Box<[T]>, E> {
+        // Check the length before expanding, so that malformed data cannot cause
+        // a huge allocation.
+        let length = runs.iter().try_fold(0usize, |sum, &(_, count)| {
+            sum.checked_add(usize::try_from(count).ok()?)
+        });
+        if length != Some(volume) {
+            return Err(E::custom(format!(
+                "Space {what} runs do not add up to the volume {volume}"
+            )));
+        }
+        Ok(runs
+            .into_iter()
+            .flat_map(|(item, count)| std::iter::repeat(item).take(count as usize))
+            .collect())
+    }
+
+    /// Convert either representation of space contents to one index per cube,
+    /// checking that the number of cubes is `volume`.
+    fn contents_to_flat<E: serde::de::Error>(
+        contents: SpaceContentsSer,
+        volume: usize,
+    ) -> Result<Box<[BlockIndex]>, E> {
+        let flat: Box<[BlockIndex]> = match contents {
+            SpaceContentsSer::Flat(flat) => flat,
+            SpaceContentsSer::Runs(runs) => run_length_decode(runs, volume, "contents")?,
+        };
+        if flat.len() != volume {
+            return Err(E::custom(format!(
+                "Space contents length {len} does not match the volume {volume}",
+                len = flat.len()
+            )));
+        }
+        Ok(flat)
+    }
+
+    impl Serialize for Waypoint {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            let Self {
+                space,
+                position,
+                icon,
+            } = self;
+            WaypointSer::WaypointV1 {
+                space: space.clone(),
+                position: (*position).into(),
+                icon: icon.clone(),
+            }
+            .serialize(serializer)
+        }
+    }
+
+    impl<'de> Deserialize<'de> for Waypoint {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            match WaypointSer::deserialize(deserializer)? {
+                WaypointSer::WaypointV1 {
+                    space,
+                    position,
+                    icon,
+                } => Ok(Waypoint {
+                    space,
+                    position: position.into(),
+                    icon,
+                }),
+            }
+        }
+    }
 }
 
 mod universe {
+    use std::cell::RefCell;
+    use std::collections::HashSet;
+
     use super::*;
     use crate::block::{Block, BlockDef};
-    use crate::character::Character;
+    use crate::character::{Character, PlayerStats};
+    use crate::entity::Entity;
+    use crate::inv::StackLimits;
     use crate::save::schema::MemberEntrySer;
-    use crate::space::Space;
-    use crate::universe::{Name, PartialUniverse, UBorrow, URef, Universe};
-    use schema::{MemberDe, NameSer, URefSer};
+    use crate::script::Script;
+    use crate::space::{Space, Waypoint};
+    use crate::universe::{
+        Name, PartialUniverse, UBorrow, URef, URefErased, Universe, UniverseMember, UniverseOps,
+        VisitRefs,
+    };
+    use schema::{BlockIdSer, MemberDe, NameSer, URefSer};
+
+    thread_local! {
+        /// While a [`Universe`] is being deserialized on this thread, this contains it,
+        /// so that [`URef`]s found in its members can be bound to the members they
+        /// refer to, even if those have not been deserialized yet.
+        static DESERIALIZING_UNIVERSE: RefCell<Option<Universe>> = const { RefCell::new(None) };
+    }
 
     impl From<&BlockDef> for schema::MemberSer {
         fn from(block_def: &BlockDef) -> Self {
@@ -448,12 +1287,19 @@ mod universe {
         }
     }
 
-    impl Serialize for PartialUniverse {
-        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+    impl PartialUniverse {
+        /// Converts each of the members to its schema form.
+        pub(crate) fn members_to_schema<E: serde::ser::Error>(
+            &self,
+        ) -> Result<Vec<MemberEntrySer<schema::MemberSer>>, E> {
             let Self {
                 blocks,
                 characters,
+                entities,
+                player_stats,
+                scripts,
                 spaces,
+                waypoints,
             } = self;
 
             let blocks = blocks.iter().map(|member_ref: &URef<BlockDef>| {
@@ -473,18 +1319,71 @@ mod universe {
                     value: schema::MemberSer::Character(schema::SerializeRef(member_ref.clone())),
                 })
             });
+            let entities = entities.iter().map(|member_ref: &URef<Entity>| {
+                let name = member_ref.name();
+                let read_guard: UBorrow<Entity> = member_ref.read().map_err(|e| {
+                    serde::ser::Error::custom(format!("Failed to read universe member {name}: {e}"))
+                })?;
+                Ok(schema::MemberEntrySer {
+                    name,
+                    value: schema::MemberSer::Entity(schema::EntitySer::from(&*read_guard)),
+                })
+            });
+            let player_stats = player_stats.iter().map(|member_ref: &URef<PlayerStats>| {
+                let name = member_ref.name();
+                let read_guard: UBorrow<PlayerStats> = member_ref.read().map_err(|e| {
+                    serde::ser::Error::custom(format!("Failed to read universe member {name}: {e}"))
+                })?;
+                Ok(schema::MemberEntrySer {
+                    name,
+                    value: schema::MemberSer::PlayerStats(PlayerStats::clone(&read_guard)),
+                })
+            });
+            let scripts = scripts.iter().map(|member_ref: &URef<Script>| {
+                let name = member_ref.name();
+                let read_guard: UBorrow<Script> = member_ref.read().map_err(|e| {
+                    serde::ser::Error::custom(format!("Failed to read universe member {name}: {e}"))
+                })?;
+                Ok(schema::MemberEntrySer {
+                    name,
+                    value: schema::MemberSer::Script(Script::clone(&read_guard)),
+                })
+            });
             let spaces = spaces.iter().map(|member_ref: &URef<Space>| {
                 Ok(schema::MemberEntrySer {
                     name: member_ref.name(),
                     value: schema::MemberSer::Space(schema::SerializeRef(member_ref.clone())),
                 })
             });
+            let waypoints = waypoints.iter().map(|member_ref: &URef<Waypoint>| {
+                let name = member_ref.name();
+                let read_guard: UBorrow<Waypoint> = member_ref.read().map_err(|e| {
+                    serde::ser::Error::custom(format!("Failed to read universe member {name}: {e}"))
+                })?;
+                Ok(schema::MemberEntrySer {
+                    name,
+                    value: schema::MemberSer::Waypoint(Waypoint::clone(&read_guard)),
+                })
+            });
+
+            blocks
+                .chain(characters)
+                .chain(entities)
+                .chain(player_stats)
+                .chain(scripts)
+                .chain(spaces)
+                .chain(waypoints)
+                .collect::<Result<Vec<MemberEntrySer<schema::MemberSer>>, E>>()
+        }
+    }
 
+    impl Serialize for PartialUniverse {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
             schema::UniverseSer::UniverseV1 {
-                members: blocks
-                    .chain(characters)
-                    .chain(spaces)
-                    .collect::<Result<Vec<MemberEntrySer<schema::MemberSer>>, S::Error>>()?,
+                members: self.members_to_schema()?,
+                // The registry and stacking rules belong to the whole universe.
+                block_ids: Vec::new(),
+                stack_limits: None,
             }
             .serialize(serializer)
         }
@@ -492,47 +1391,176 @@ mod universe {
 
     impl Serialize for Universe {
         fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
-            PartialUniverse::all_of(self).serialize(serializer)
+            schema::UniverseSer::UniverseV1 {
+                members: PartialUniverse::all_of(self).members_to_schema()?,
+                block_ids: self
+                    .block_registry()
+                    .iter()
+                    .map(|(id, block_def)| BlockIdSer {
+                        id: id.into(),
+                        block_def: block_def.clone(),
+                    })
+                    .collect(),
+                stack_limits: Some(self.stack_limits())
+                    .filter(|&limits| *limits != StackLimits::default())
+                    .cloned(),
+            }
+            .serialize(serializer)
         }
     }
 
     impl<'de> Deserialize<'de> for Universe {
         fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
-            let data = schema::UniverseDe::deserialize(deserializer)?;
-            let mut universe = Universe::new();
-            match data {
-                schema::UniverseDe::UniverseV1 { members } => {
-                    for schema::MemberEntrySer { name, value } in members {
-                        match value {
-                            MemberDe::BlockDef(block) => {
-                                universe.insert(name, BlockDef::new(block)).map(|_| ())
-                            }
-                            MemberDe::Character(character) => {
-                                universe.insert(name, character).map(|_| ())
-                            }
-                            MemberDe::Space(space) => universe.insert(name, space).map(|_| ()),
+            // First pass: deserialize the data of all members. Every `URef` deserialized
+            // meanwhile is bound to a placeholder entry in `universe`.
+            let outer_universe =
+                DESERIALIZING_UNIVERSE.with(|cell| cell.replace(Some(Universe::new())));
+            let data = schema::UniverseDe::deserialize(deserializer);
+            let mut universe = DESERIALIZING_UNIVERSE
+                .with(|cell| cell.replace(outer_universe))
+                .expect("shouldn't happen: deserialization universe missing");
+            let (members, block_ids, stack_limits) = match data? {
+                schema::UniverseDe::UniverseV1 {
+                    members,
+                    block_ids,
+                    stack_limits,
+                } => (members, block_ids, stack_limits),
+            };
+
+            // Every placeholder must be given a value by some member.
+            let defined_names: HashSet<&Name> = members.iter().map(|entry| &entry.name).collect();
+            let unresolved: Vec<Name> = universe
+                .placeholder_names()
+                .into_iter()
+                .filter(|name| !defined_names.contains(name))
+                .collect();
+            if !unresolved.is_empty() {
+                return Err(serde::de::Error::custom(format!(
+                    "universe contains references to members which do not exist: {}",
+                    format_names(&unresolved)
+                )));
+            }
+
+            // Second pass: construct the members and fill in the placeholders.
+            // Constructing a block definition or space reads the members its blocks
+            // refer to, so those must be constructed first.
+            let mut pending: Vec<MemberEntrySer<MemberDe>> = members;
+            while !pending.is_empty() {
+                let pending_names: HashSet<Name> =
+                    pending.iter().map(|entry| entry.name.clone()).collect();
+                let (ready, not_ready): (Vec<_>, Vec<_>) = pending.into_iter().partition(|entry| {
+                    refs_read_during_construction(&entry.value)
+                        .iter()
+                        .all(|name| !pending_names.contains(name))
+                });
+                if ready.is_empty() {
+                    let names: Vec<Name> = not_ready.into_iter().map(|entry| entry.name).collect();
+                    return Err(serde::de::Error::custom(format!(
+                        "universe contains members which refer to each other cyclically: {}",
+                        format_names(&names)
+                    )));
+                }
+
+                for MemberEntrySer { name, value } in ready {
+                    match value {
+                        MemberDe::BlockDef(block) => universe
+                            .insert_deserialized(name, BlockDef::new(block))
+                            .map(|_| ()),
+                        MemberDe::Character(character) => {
+                            universe.insert_deserialized(name, character).map(|_| ())
+                        }
+                        MemberDe::Entity(entity) => universe
+                            .insert_deserialized(name, Entity::from(entity))
+                            .map(|_| ()),
+                        MemberDe::PlayerStats(stats) => {
+                            universe.insert_deserialized(name, stats).map(|_| ())
+                        }
+                        MemberDe::Script(script) => {
+                            universe.insert_deserialized(name, script).map(|_| ())
+                        }
+                        MemberDe::Space(space) => universe
+                            .insert_deserialized(
+                                name,
+                                super::space::space_from_schema::<D::Error>(space)?,
+                            )
+                            .map(|_| ()),
+                        MemberDe::Waypoint(waypoint) => {
+                            universe.insert_deserialized(name, waypoint).map(|_| ())
                         }
-                        .expect("insertion from deserialization failed");
                     }
+                    .map_err(serde::de::Error::custom)?;
                 }
+                pending = not_ready;
+            }
+
+            for BlockIdSer { id, block_def } in block_ids {
+                universe
+                    .register_block(&id, block_def)
+                    .map_err(serde::de::Error::custom)?;
+            }
+            if let Some(stack_limits) = stack_limits {
+                universe.set_stack_limits(stack_limits);
             }
+
             Ok(universe)
         }
     }
 
+    /// Returns the names of the members which must exist before the given member
+    /// can be constructed (because their values are read while doing so).
+    fn refs_read_during_construction(value: &MemberDe) -> Vec<Name> {
+        let mut names = Vec::new();
+        let mut visitor = |r: &dyn URefErased| names.push(r.name());
+        match value {
+            MemberDe::BlockDef(block) => block.visit_refs(&mut visitor),
+            // Characters do not read their space when constructed.
+            MemberDe::Character(_) => {}
+            MemberDe::Entity(_) => {}
+            MemberDe::PlayerStats(_) => {}
+            MemberDe::Script(_) => {}
+            MemberDe::Space(schema::SpaceSer::SpaceV1 { blocks, .. }) => {
+                blocks.visit_refs(&mut visitor)
+            }
+            MemberDe::Waypoint(_) => {}
+        }
+        names
+    }
+
+    /// Format a list of names for an error message.
+    fn format_names(names: &[Name]) -> String {
+        names
+            .iter()
+            .map(Name::to_string)
+            .collect::<Vec<String>>()
+            .join(", ")
+    }
+
     impl<T: 'static> Serialize for URef<T> {
         fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
             URefSer::URefV1 { name: self.name() }.serialize(serializer)
         }
     }
 
-    impl<'de, T: 'static> Deserialize<'de> for URef<T> {
+    impl<'de, T> Deserialize<'de> for URef<T>
+    where
+        T: UniverseMember,
+        Universe: UniverseOps<T>,
+    {
         fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
-            Ok(match URefSer::deserialize(deserializer)? {
-                // TODO: Instead of new_gone(), this needs to be a named ref that can be
-                // hooked up to its definition.
-                URefSer::URefV1 { name } => URef::new_gone(name),
-            })
+            match URefSer::deserialize(deserializer)? {
+                URefSer::URefV1 { name } => {
+                    DESERIALIZING_UNIVERSE.with(|cell| match cell.borrow_mut().as_mut() {
+                        Some(universe) => universe.get_or_insert_placeholder(name).map_err(|e| {
+                            serde::de::Error::custom(format!(
+                                "references to {} have conflicting types",
+                                e.name
+                            ))
+                        }),
+                        // Not inside a universe, so there is nothing the ref could refer to.
+                        None => Ok(URef::new_gone(name)),
+                    })
+                }
+            }
         }
     }
 
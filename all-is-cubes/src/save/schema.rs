@@ -21,7 +21,7 @@ use serde::{Deserialize, Serialize};
 use crate::block::Block;
 use crate::math::{Aab, Face6, GridAab, GridCoordinate, GridRotation};
 use crate::universe::URef;
-use crate::{block, character, inv, space, universe};
+use crate::{block, character, inv, script, space, universe};
 
 /// Placeholder type for when we want to serialize the *contents* of a `URef`,
 /// without cloning or referencing those contents immediately.
@@ -49,6 +49,11 @@ pub(crate) enum PrimitiveSer {
         #[serde(flatten)]
         attributes: BlockAttributesV1Ser,
     },
+    FacesV1 {
+        faces: FaceMapSer<RgbaSer>,
+        #[serde(flatten)]
+        attributes: BlockAttributesV1Ser,
+    },
     RecurV1 {
         #[serde(flatten)]
         attributes: BlockAttributesV1Ser,
@@ -74,6 +79,10 @@ pub(crate) struct BlockAttributesV1Ser {
     #[serde(default, skip_serializing_if = "is_default")]
     pub(crate) light_emission: RgbSer,
     //tick_action: Option<VoxelBrush<'static>>,
+    //signal: BlockSignal,
+    //portal: Option<Portal>,
+    #[serde(default, skip_serializing_if = "is_default")]
+    pub(crate) contact_damage: f32,
     //animation_hint: AnimationHint,
 }
 fn return_true() -> bool {
@@ -110,6 +119,16 @@ pub(crate) enum ModifierSer {
         distance: u16,
         velocity: i16,
     },
+    ConnectV1 {
+        faces: Vec<Face6>,
+        variants: Vec<block::Block>,
+    },
+    AnimateV1 {
+        frames: Vec<block::Block>,
+        ticks_per_frame: std::num::NonZeroU16,
+        #[serde(default, skip_serializing_if = "is_default")]
+        phase: u32,
+    },
 }
 
 //------------------------------------------------------------------------------------------------//
@@ -129,6 +148,58 @@ pub(crate) enum CharacterSer {
         pitch: f64,
         inventory: inv::Inventory,
         selected_slots: [usize; 3],
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        health: Option<HealthSer>,
+    },
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub(crate) struct HealthSer {
+    pub(crate) current: f32,
+    pub(crate) maximum: f32,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(tag = "type")]
+pub(crate) enum PlayerStatsSer {
+    PlayerStatsV1 {
+        character: URef<character::Character>,
+        blocks_placed: Vec<BlockCountSer>,
+        blocks_removed: Vec<BlockCountSer>,
+        distance_traveled: f64,
+        /// Seconds.
+        time_played: f64,
+    },
+}
+
+/// Schema for one entry of the block counts in [`PlayerStatsSer`].
+#[derive(Debug, Deserialize, Serialize)]
+pub(crate) struct BlockCountSer {
+    pub(crate) block: Block,
+    pub(crate) count: u64,
+}
+
+//------------------------------------------------------------------------------------------------//
+// Schema corresponding to the `entity` module
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(tag = "type")]
+pub(crate) enum EntitySer {
+    EntityV1 {
+        space: URef<space::Space>,
+        position: [f64; 3],
+        velocity: [f64; 3],
+        collision_box: Aab,
+        flying: bool,
+        noclip: bool,
+        yaw: f64,
+        pitch: f64,
+        appearance: Block,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        item: Option<inv::Tool>,
+        /// Seconds.
+        #[serde(default, skip_serializing_if = "is_default")]
+        age: f64,
     },
 }
 
@@ -164,6 +235,40 @@ pub(crate) enum ToolSer {
     ExternalActionV1 { icon: Block },
 }
 
+/// Schema for [`inv::StackLimits`].
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(tag = "type")]
+pub(crate) enum StackLimitsSer {
+    StackLimitsV1 {
+        standard: std::num::NonZeroU16,
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        overrides: Vec<StackLimitOverrideSer>,
+    },
+}
+
+/// Schema for one override in [`StackLimitsSer`].
+/// Not tagged since it will only appear inside a [`StackLimitsSer`].
+#[derive(Debug, Deserialize, Serialize)]
+pub(crate) struct StackLimitOverrideSer {
+    pub(crate) tool: ToolKindSer,
+    pub(crate) limit: std::num::NonZeroU16,
+}
+
+/// Schema for [`inv::ToolKind`]. The names are those of the [`ToolSer`] variants
+/// without their versions.
+#[derive(Clone, Copy, Debug, Deserialize, Serialize)]
+pub(crate) enum ToolKindSer {
+    Activate,
+    RemoveBlock,
+    Block,
+    InfiniteBlocks,
+    CopyFromSpace,
+    EditBlock,
+    PushPull,
+    Jetpack,
+    ExternalAction,
+}
+
 //------------------------------------------------------------------------------------------------//
 // Schema corresponding to the `math` module
 
@@ -181,10 +286,30 @@ pub(crate) struct GridAabSer {
     pub(crate) upper: [GridCoordinate; 3],
 }
 
+/// Schema for [`math::FaceMap`](crate::math::FaceMap).
+#[derive(Debug, Deserialize, Serialize)]
+pub(crate) struct FaceMapSer<T> {
+    pub(crate) nx: T,
+    pub(crate) ny: T,
+    pub(crate) nz: T,
+    pub(crate) px: T,
+    pub(crate) py: T,
+    pub(crate) pz: T,
+}
+
 type RgbSer = [ordered_float::NotNan<f32>; 3];
 
 type RgbaSer = [ordered_float::NotNan<f32>; 4];
 
+//------------------------------------------------------------------------------------------------//
+// Schema corresponding to the `script` module
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(tag = "type")]
+pub(crate) enum ScriptSer {
+    ScriptV1 { source: Arc<str> },
+}
+
 //------------------------------------------------------------------------------------------------//
 // Schema corresponding to the `space` module
 
@@ -194,8 +319,192 @@ pub(crate) enum SpaceSer {
     SpaceV1 {
         bounds: GridAab,
         blocks: Vec<block::Block>,
-        contents: Box<[space::BlockIndex]>,
-        // TODO: bounds, behaviors, lighting, spawn, physics
+        contents: SpaceContentsSer,
+        /// Absent if the space has no light data, or if it was saved by an older version;
+        /// in either case the light is computed after loading.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        light: Option<SpaceLightSer>,
+        /// Absent in saves from older versions, in which case the default physics are used.
+        #[serde(default)]
+        physics: Option<SpacePhysicsSer>,
+        /// Absent if the weather is clear.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        weather: Option<WeatherSer>,
+        /// Absent if the weather is not changing.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        target_weather: Option<WeatherSer>,
+        /// Cubes whose signal level is powered. Absent if there are none.
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        powered: Vec<[GridCoordinate; 3]>,
+        /// Absent if there are none.
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        protected_regions: Vec<ProtectedRegionSer>,
+        // TODO: behaviors, spawn
+    },
+}
+
+/// Schema for [`space::ProtectedRegion`].
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(tag = "type")]
+pub(crate) enum ProtectedRegionSer {
+    ProtectedRegionV1 {
+        bounds: GridAab,
+        access: RegionAccessSer,
+    },
+}
+
+/// Schema for [`space::RegionAccess`].
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(tag = "type")]
+pub(crate) enum RegionAccessSer {
+    ReadOnlyV1,
+    OwnerV1 { owner: URef<character::Character> },
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(tag = "type")]
+pub(crate) enum SpacePhysicsSer {
+    SpacePhysicsV1 {
+        gravity: [ordered_float::NotNan<f64>; 3],
+        air_drag: ordered_float::NotNan<f64>,
+        terminal_velocity: Option<ordered_float::NotNan<f64>>,
+        /// Mean color of the sky, which is the entire sky if `sky` is absent.
+        sky_color: RgbSer,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        sky: Option<SkySer>,
+        light: LightPhysicsSer,
+    },
+}
+
+/// Schema for [`space::Sky`] other than [`space::Sky::Uniform`], which is represented
+/// by `sky_color` alone.
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(tag = "type")]
+pub(crate) enum SkySer {
+    GradientV1 {
+        zenith: RgbSer,
+        horizon: RgbSer,
+        nadir: RgbSer,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        sun: Option<SunSer>,
+    },
+    CubeMapV1 {
+        resolution: u16,
+        /// Texels of each face in row-major order.
+        faces: FaceMapSer<Vec<RgbSer>>,
+    },
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub(crate) struct SunSer {
+    pub(crate) direction: [ordered_float::NotNan<f64>; 3],
+    pub(crate) color: RgbSer,
+    pub(crate) angular_radius: ordered_float::NotNan<f64>,
+}
+
+/// Schema for [`space::Weather`].
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(tag = "type")]
+pub(crate) enum WeatherSer {
+    WeatherV1 {
+        precipitation: Option<PrecipitationSer>,
+        intensity: ordered_float::NotNan<f32>,
+        fog_density: ordered_float::NotNan<f32>,
+    },
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub(crate) enum PrecipitationSer {
+    Rain,
+    Snow,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(tag = "type")]
+pub(crate) enum LightPhysicsSer {
+    NoneV1,
+    RaysV1 { maximum_distance: u16 },
+}
+
+/// The contents of a space, as indices into its `blocks` palette,
+/// in the order of [`GridAab::interior_iter()`].
+///
+/// Either representation may be deserialized from any format; the serializer chooses
+/// [`SpaceContentsSer::Flat`] for human-readable formats and
+/// [`SpaceContentsSer::Runs`] for binary formats.
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(untagged)]
+pub(crate) enum SpaceContentsSer {
+    /// One block index per cube.
+    Flat(Box<[space::BlockIndex]>),
+    /// Run-length encoded: each element is a block index and the number of
+    /// consecutive cubes containing it.
+    Runs(Vec<(space::BlockIndex, RunLength)>),
+}
+
+/// Previously computed light data of a space, so that loading it need not recompute
+/// all of the light.
+#[derive(Debug, Deserialize, Serialize)]
+pub(crate) struct SpaceLightSer {
+    /// Absent in saves from versions which had only [`LightEncodingSer::Log8`].
+    #[serde(default)]
+    pub(crate) encoding: LightEncodingSer,
+    /// Whether the light had been fully evaluated when saved. If false, the data is
+    /// only a starting point, and the light is updated further after loading.
+    pub(crate) complete: bool,
+    /// Run-length encoded light values, each as red, green, blue, and status,
+    /// in the order of [`GridAab::interior_iter()`].
+    pub(crate) runs: Vec<([u16; 4], RunLength)>,
+}
+
+/// Number of consecutive items in a run of run-length encoded data.
+///
+/// This is a fixed size rather than `usize` so that the format does not depend on
+/// the platform; longer runs are split.
+pub(crate) type RunLength = u32;
+
+/// How the color components in [`SpaceLightSer::runs`] represent light.
+/// In all encodings, zero is no light.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub(crate) enum LightEncodingSer {
+    /// 16 steps per doubling, with 128 representing 1.0; at most 255.
+    #[default]
+    Log8,
+    /// 2048 steps per doubling, with 32768 representing 1.0.
+    Log16,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(tag = "type")]
+pub(crate) enum SpaceSnapshotSer {
+    SpaceSnapshotV1 {
+        tick_number: u64,
+        bounds: GridAab,
+        blocks: Vec<block::Block>,
+        contents: SpaceContentsSer,
+    },
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(tag = "type")]
+pub(crate) enum SpaceDeltaSer {
+    SpaceDeltaV1 {
+        base_tick_number: u64,
+        tick_number: u64,
+        blocks: Vec<block::Block>,
+        /// Each changed cube and the index into `blocks` of its new block.
+        cubes: Vec<([GridCoordinate; 3], space::BlockIndex)>,
+    },
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(tag = "type")]
+pub(crate) enum WaypointSer {
+    WaypointV1 {
+        space: URef<space::Space>,
+        position: [f64; 3],
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        icon: Option<Block>,
     },
 }
 
@@ -212,11 +521,20 @@ pub(crate) enum UniverseSchema<C, S> {
         /// Note: We are currently targeting JSON output, which cannot use non-string keys.
         /// Therefore, this is not expressed as a map.
         members: Vec<MemberEntrySer<MemberSchema<C, S>>>,
+        /// Contents of the universe's `BlockRegistry`.
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        block_ids: Vec<BlockIdSer>,
+        /// The universe's `StackLimits`, if they are not the default.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        stack_limits: Option<inv::StackLimits>,
     },
 }
 pub(crate) type UniverseSer =
     UniverseSchema<SerializeRef<character::Character>, SerializeRef<space::Space>>;
-pub(crate) type UniverseDe = UniverseSchema<character::Character, space::Space>;
+/// Spaces are not deserialized directly into [`space::Space`], because constructing a
+/// space requires evaluating its blocks, which may refer to other members that have
+/// not been deserialized yet.
+pub(crate) type UniverseDe = UniverseSchema<character::Character, SpaceSer>;
 
 #[derive(Debug, Deserialize, Serialize)]
 pub(crate) struct MemberEntrySer<T> {
@@ -224,16 +542,26 @@ pub(crate) struct MemberEntrySer<T> {
     pub value: T,
 }
 
+#[derive(Debug, Deserialize, Serialize)]
+pub(crate) struct BlockIdSer {
+    pub id: Arc<str>,
+    pub block_def: URef<block::BlockDef>,
+}
+
 #[derive(Debug, Deserialize, Serialize)]
 #[serde(untagged)] // The type-and-version tags of each member suffice
 pub(crate) enum MemberSchema<C, S> {
     BlockDef(block::Block),
     Character(C),
+    Entity(EntitySer),
+    PlayerStats(character::PlayerStats),
+    Script(script::Script),
     Space(S),
+    Waypoint(space::Waypoint),
 }
 pub(crate) type MemberSer =
     MemberSchema<SerializeRef<character::Character>, SerializeRef<space::Space>>;
-pub(crate) type MemberDe = MemberSchema<character::Character, space::Space>;
+pub(crate) type MemberDe = MemberSchema<character::Character, SpaceSer>;
 
 #[derive(Debug, Deserialize, Serialize)]
 #[serde(tag = "type")]
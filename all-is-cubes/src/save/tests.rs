@@ -1,16 +1,26 @@
 //! Tests of serialization and deserialization.
 
 use std::fmt;
+use std::num::NonZeroU16;
 
+use cgmath::Vector3;
 use pretty_assertions::assert_eq;
 use serde_json::{from_value, json, to_value};
 
 use crate::block::{self, Block, BlockDef, Modifier, Resolution};
-use crate::character::Character;
+use crate::character::{Character, PlayerStats};
 use crate::content::make_some_blocks;
-use crate::inv::Tool;
-use crate::math::{GridAab, GridRotation, Rgb, Rgba};
-use crate::space::Space;
+use crate::drawing::VoxelBrush;
+use crate::entity::Entity;
+use crate::inv::{StackLimits, Tool, ToolKind};
+use crate::math::{Aab, Face6, FaceMap, GridAab, GridPoint, GridRotation, NotNan, Rgb, Rgba};
+use crate::physics::Body;
+use crate::script::Script;
+use crate::space::{
+    CubeMap, LightPhysics, PackedLight, Precipitation, ProtectedRegion, RegionAccess, Sky,
+    SkyGradient, Space, SpaceDelta, SpacePhysics, SpaceSnapshot, Sun, Waypoint, Weather,
+};
+use crate::time::{practically_infinite_deadline, Duration, Tick};
 use crate::universe::{Name, PartialUniverse, URef, Universe};
 
 #[track_caller]
@@ -106,6 +116,31 @@ fn block_atom_with_all_attributes() {
     );
 }
 
+#[test]
+fn block_faces() {
+    assert_round_trip_value(
+        &Block::builder()
+            .face_colors(FaceMap::repeat(Rgba::WHITE).with(Face6::PY, Rgba::BLACK))
+            .display_name("foo")
+            .build(),
+        json!({
+            "type": "BlockV1",
+            "primitive": {
+                "type": "FacesV1",
+                "faces": {
+                    "nx": [1.0, 1.0, 1.0, 1.0],
+                    "ny": [1.0, 1.0, 1.0, 1.0],
+                    "nz": [1.0, 1.0, 1.0, 1.0],
+                    "px": [1.0, 1.0, 1.0, 1.0],
+                    "py": [0.0, 0.0, 0.0, 1.0],
+                    "pz": [1.0, 1.0, 1.0, 1.0],
+                },
+                "display_name": "foo",
+            },
+        }),
+    );
+}
+
 #[test]
 fn block_with_modifiers() {
     assert_round_trip_value(
@@ -130,6 +165,88 @@ fn block_with_modifiers() {
 
 // TODO: test serialization of each modifier
 
+#[test]
+fn block_with_connect_modifier() {
+    assert_round_trip_value(
+        &Block::builder()
+            .color(Rgba::WHITE)
+            .modifier(Modifier::Connect(block::Connect::new(
+                FaceMap::repeat(false).with(Face6::PX, true),
+                vec![block::AIR, Block::from(Rgba::BLACK)],
+            )))
+            .build(),
+        json!({
+            "type": "BlockV1",
+            "primitive": {
+                "type": "AtomV1",
+                "color": [1.0, 1.0, 1.0, 1.0],
+            },
+            "modifiers": [
+                {
+                    "type": "ConnectV1",
+                    "faces": ["PX"],
+                    "variants": [
+                        {
+                            "type": "BlockV1",
+                            "primitive": {"type": "AirV1"},
+                        },
+                        {
+                            "type": "BlockV1",
+                            "primitive": {
+                                "type": "AtomV1",
+                                "color": [0.0, 0.0, 0.0, 1.0],
+                            },
+                        },
+                    ],
+                },
+            ]
+        }),
+    );
+}
+
+#[test]
+fn block_with_animate_modifier() {
+    assert_round_trip_value(
+        &Block::builder()
+            .color(Rgba::WHITE)
+            .modifier(Modifier::Animate(
+                block::Animate::new(
+                    vec![block::AIR, Block::from(Rgba::BLACK)],
+                    std::num::NonZeroU16::new(10).unwrap(),
+                )
+                .with_phase(15),
+            ))
+            .build(),
+        json!({
+            "type": "BlockV1",
+            "primitive": {
+                "type": "AtomV1",
+                "color": [1.0, 1.0, 1.0, 1.0],
+            },
+            "modifiers": [
+                {
+                    "type": "AnimateV1",
+                    "frames": [
+                        {
+                            "type": "BlockV1",
+                            "primitive": {"type": "AirV1"},
+                        },
+                        {
+                            "type": "BlockV1",
+                            "primitive": {
+                                "type": "AtomV1",
+                                "color": [0.0, 0.0, 0.0, 1.0],
+                            },
+                        },
+                    ],
+                    "ticks_per_frame": 10,
+                    "phase": 15,
+                },
+            ]
+        }),
+    );
+}
+
 //------------------------------------------------------------------------------------------------//
 // Tests corresponding to the `character` module
 
@@ -186,6 +303,167 @@ fn character() {
     );
 }
 
+#[test]
+fn player_stats() {
+    let mut universe = Universe::new();
+    let space = universe.insert_anonymous(Space::empty_positive(1, 1, 1));
+    let character = universe
+        .insert("a_character".into(), Character::spawn_default(space))
+        .unwrap();
+    let mut stats = PlayerStats::new(character);
+    stats.blocks_removed.insert(Block::from(Rgba::WHITE), 2);
+    stats.distance_traveled = 3.5;
+    stats.time_played = crate::time::Duration::from_millis(1500);
+
+    assert_serdeser(
+        &stats,
+        json!({
+            "type": "PlayerStatsV1",
+            "character": {"type": "URefV1", "Specific": "a_character"},
+            "blocks_placed": [],
+            "blocks_removed": [
+                {
+                    "block": {
+                        "type": "BlockV1",
+                        "primitive": {"type": "AtomV1", "color": [1.0, 1.0, 1.0, 1.0]},
+                    },
+                    "count": 2,
+                }
+            ],
+            "distance_traveled": 3.5,
+            "time_played": 1.5,
+        }),
+    );
+}
+
+#[test]
+fn player_stats_counts_in_consistent_order() {
+    let mut universe = Universe::new();
+    let space = universe.insert_anonymous(Space::empty_positive(1, 1, 1));
+    let character = universe.insert_anonymous(Character::spawn_default(space));
+    let blocks = [
+        Block::from(Rgba::WHITE),
+        Block::from(Rgba::BLACK),
+        block::AIR,
+    ];
+    let serialize_in_order = |order: [usize; 3]| {
+        let mut stats = PlayerStats::new(character.clone());
+        for i in order {
+            stats.blocks_placed.insert(blocks[i].clone(), 1);
+        }
+        to_value(&stats).unwrap()
+    };
+    assert_eq!(serialize_in_order([0, 1, 2]), serialize_in_order([2, 1, 0]));
+}
+
+#[test]
+fn player_stats_de_time_out_of_range() {
+    // 2^64 seconds, which is just beyond the longest Duration.
+    let error = from_value::<PlayerStats>(json!({
+        "type": "PlayerStatsV1",
+        "character": {"type": "URefV1", "Specific": "a_character"},
+        "blocks_placed": [],
+        "blocks_removed": [],
+        "distance_traveled": 0.0,
+        "time_played": 18446744073709551616.0,
+    }))
+    .unwrap_err();
+    assert!(error.to_string().contains("invalid time_played"), "{error}");
+}
+
+//------------------------------------------------------------------------------------------------//
+// Tests corresponding to the `entity` module
+
+#[test]
+fn entity() {
+    let mut universe = Universe::new();
+    let space = universe
+        .insert("a_space".into(), Space::empty_positive(1, 1, 1))
+        .unwrap();
+    let mut body = Body::new_minimal([1.0, 2.0, 3.0], Aab::new(-0.5, 0.5, 0.0, 1.0, -0.5, 0.5));
+    body.velocity = Vector3::new(0.0, -4.0, 0.5);
+
+    assert_serdeser(
+        &Entity::new(space, body, Block::from(Rgba::WHITE)),
+        json!({
+            "type": "EntityV1",
+            "space": {"type": "URefV1", "Specific": "a_space"},
+            "position": [1.0, 2.0, 3.0],
+            "velocity": [0.0, -4.0, 0.5],
+            "collision_box": {
+                "lower": [-0.5, 0.0, -0.5],
+                "upper": [0.5, 1.0, 0.5],
+            },
+            "flying": false,
+            "noclip": false,
+            "yaw": 0.0,
+            "pitch": 0.0,
+            "appearance": {
+                "type": "BlockV1",
+                "primitive": {"type": "AtomV1", "color": [1.0, 1.0, 1.0, 1.0]},
+            },
+        }),
+    );
+}
+
+#[test]
+fn item_entity() {
+    let mut universe = Universe::new();
+    let space = universe
+        .insert("a_space".into(), Space::empty_positive(1, 1, 1))
+        .unwrap();
+    let mut entity = Entity::new_item(
+        space,
+        [0, 0, 0].into(),
+        Tool::Block(Block::from(Rgba::WHITE)),
+    );
+    entity.age = Duration::from_millis(2500);
+
+    assert_serdeser(
+        &entity,
+        json!({
+            "type": "EntityV1",
+            "space": {"type": "URefV1", "Specific": "a_space"},
+            "position": [0.5, 0.5, 0.5],
+            "velocity": [0.0, 4.0, 0.0],
+            "collision_box": {
+                "lower": [-0.125, -0.125, -0.125],
+                "upper": [0.125, 0.125, 0.125],
+            },
+            "flying": false,
+            "noclip": false,
+            "yaw": 0.0,
+            "pitch": 0.0,
+            "appearance": {
+                "type": "BlockV1",
+                "primitive": {"type": "AtomV1", "color": [1.0, 1.0, 1.0, 1.0]},
+            },
+            "item": {
+                "type": "BlockV1",
+                "block": {
+                    "type": "BlockV1",
+                    "primitive": {"type": "AtomV1", "color": [1.0, 1.0, 1.0, 1.0]},
+                },
+            },
+            "age": 2.5,
+        }),
+    );
+}
+
+//------------------------------------------------------------------------------------------------//
+// Tests corresponding to the `script` module
+
+#[test]
+fn script() {
+    assert_round_trip_value(
+        &Script::new("fn step(dt) {}"),
+        json!({
+            "type": "ScriptV1",
+            "source": "fn step(dt) {}",
+        }),
+    );
+}
+
 //------------------------------------------------------------------------------------------------//
 // Tests corresponding to the `space` module
 
@@ -193,7 +471,17 @@ fn character() {
 fn space() {
     // TODO: set more properties and fill contents
     let bounds = GridAab::from_lower_upper([1, 2, 3], [4, 5, 6]);
-    let space = Space::builder(bounds).build();
+    let space = Space::builder(bounds)
+        .physics(SpacePhysics {
+            gravity: Vector3::new(0, -4, 0).map(NotNan::from),
+            air_drag: NotNan::from(1),
+            terminal_velocity: Some(NotNan::from(10)),
+            sky: Sky::Uniform(Rgb::new(0.5, 0.5, 1.0)),
+            light: LightPhysics::Rays {
+                maximum_distance: 20,
+            },
+        })
+        .build();
     assert_serdeser(
         &space,
         json!({
@@ -213,6 +501,472 @@ fn space() {
                 0, 0, 0, 0, 0, 0, 0, 0, 0,
                 0, 0, 0, 0, 0, 0, 0, 0, 0,
             ],
+            "light": {
+                "encoding": "Log16",
+                "complete": true,
+                "runs": [[[0, 0, 0, 1], 27]],
+            },
+            "physics": {
+                "type": "SpacePhysicsV1",
+                "gravity": [0.0, -4.0, 0.0],
+                "air_drag": 1.0,
+                "terminal_velocity": 10.0,
+                "sky_color": [0.5, 0.5, 1.0],
+                "light": {"type": "RaysV1", "maximum_distance": 20},
+            },
+        }),
+    );
+}
+
+/// Saved light is restored rather than recomputed.
+#[test]
+fn space_light_round_trip() {
+    let mut space = Space::empty_positive(3, 1, 1);
+    space.set([1, 0, 0], Block::from(Rgba::WHITE)).unwrap();
+    space.evaluate_light(0, |_| {});
+
+    let loaded: Space = from_value(to_value(&space).unwrap()).unwrap();
+    assert!(loaded.is_lighting_complete());
+    for cube in space.bounds().interior_iter() {
+        assert_eq!(loaded.get_lighting(cube), space.get_lighting(cube));
+    }
+}
+
+#[test]
+fn space_sky_gradient() {
+    let space = Space::builder(GridAab::from_lower_size([0, 0, 0], [1, 1, 1]))
+        .sky(SkyGradient {
+            zenith: Rgb::new(0.0, 0.0, 1.0),
+            horizon: Rgb::new(1.0, 1.0, 1.0),
+            nadir: Rgb::new(0.0, 0.0, 0.0),
+            sun: Some(Sun {
+                direction: Vector3::new(1, 2, 3).map(NotNan::from),
+                color: Rgb::new(4.0, 4.0, 2.0),
+                angular_radius: NotNan::from(1),
+            }),
+        })
+        .light_physics(LightPhysics::None)
+        .build();
+    let json_value = to_value(&space).unwrap();
+    let mean = space.physics().sky.mean();
+    assert_eq!(
+        json_value["physics"],
+        json!({
+            "type": "SpacePhysicsV1",
+            "gravity": [0.0, -20.0, 0.0],
+            "air_drag": 0.0,
+            "terminal_velocity": null,
+            "sky_color": [mean.red(), mean.green(), mean.blue()],
+            "sky": {
+                "type": "GradientV1",
+                "zenith": [0.0, 0.0, 1.0],
+                "horizon": [1.0, 1.0, 1.0],
+                "nadir": [0.0, 0.0, 0.0],
+                "sun": {
+                    "direction": [1.0, 2.0, 3.0],
+                    "color": [4.0, 4.0, 2.0],
+                    "angular_radius": 1.0,
+                },
+            },
+            "light": {"type": "NoneV1"},
+        })
+    );
+    let loaded: Space = from_value(json_value).unwrap();
+    assert_eq!(loaded.physics(), space.physics());
+}
+
+#[test]
+fn space_sky_cube_map() {
+    let cube_map = CubeMap::from_fn(2, |direction| {
+        Rgb::new(direction.x as f32, direction.y as f32, direction.z as f32) * 0.25 + Rgb::ONE
+    });
+    let space = Space::builder(GridAab::from_lower_size([0, 0, 0], [1, 1, 1]))
+        .sky(cube_map)
+        .build();
+    let loaded: Space = from_value(to_value(&space).unwrap()).unwrap();
+    assert_eq!(loaded.physics(), space.physics());
+}
+
+#[test]
+fn space_weather() {
+    let bounds = GridAab::from_lower_size([0, 0, 0], [1, 1, 1]);
+
+    // Clear weather is omitted.
+    let json_value = to_value(&Space::builder(bounds).build()).unwrap();
+    assert_eq!(json_value.get("weather"), None);
+    assert_eq!(json_value.get("target_weather"), None);
+
+    let mut space = Space::builder(bounds)
+        .weather(Weather::new(Some(Precipitation::Rain), 0.5, 0.25))
+        .build();
+    space.set_weather(Weather::new(Some(Precipitation::Snow), 1.0, 0.0));
+    let json_value = to_value(&space).unwrap();
+    assert_eq!(
+        json_value["weather"],
+        json!({
+            "type": "WeatherV1",
+            "precipitation": "Rain",
+            "intensity": 0.5,
+            "fog_density": 0.25,
+        })
+    );
+    assert_eq!(
+        json_value["target_weather"],
+        json!({
+            "type": "WeatherV1",
+            "precipitation": "Snow",
+            "intensity": 1.0,
+            "fog_density": 0.0,
+        })
+    );
+    let loaded: Space = from_value(json_value).unwrap();
+    assert_eq!(loaded.weather(), space.weather());
+    assert_eq!(loaded.target_weather(), space.target_weather());
+}
+
+#[test]
+fn space_protected_regions() {
+    let mut universe = Universe::new();
+    let space_ref = universe
+        .insert("a_space".into(), Space::empty_positive(2, 1, 1))
+        .unwrap();
+    let character_ref = universe
+        .insert(
+            "a_character".into(),
+            Character::spawn_default(space_ref.clone()),
+        )
+        .unwrap();
+    let read_only_bounds = GridAab::from_lower_size([0, 0, 0], [1, 1, 1]);
+    let owned_bounds = GridAab::from_lower_size([1, 0, 0], [1, 1, 1]);
+    space_ref
+        .try_modify(|space| {
+            space.add_protected_region(ProtectedRegion::new(
+                read_only_bounds,
+                RegionAccess::ReadOnly,
+            ));
+            space.add_protected_region(ProtectedRegion::new(
+                owned_bounds,
+                RegionAccess::Owner(character_ref),
+            ));
+        })
+        .unwrap();
+
+    let json_value = to_value(&universe).unwrap();
+    let space_json = json_value["members"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|member| member["name"] == json!({"Specific": "a_space"}))
+        .unwrap();
+    assert_eq!(
+        space_json["value"]["protected_regions"],
+        json!([
+            {
+                "type": "ProtectedRegionV1",
+                "bounds": {"lower": [0, 0, 0], "upper": [1, 1, 1]},
+                "access": {"type": "ReadOnlyV1"},
+            },
+            {
+                "type": "ProtectedRegionV1",
+                "bounds": {"lower": [1, 0, 0], "upper": [2, 1, 1]},
+                "access": {
+                    "type": "OwnerV1",
+                    "owner": {"type": "URefV1", "Specific": "a_character"},
+                },
+            },
+        ])
+    );
+
+    let universe: Universe = from_value(json_value).unwrap();
+    let space_ref: URef<Space> = universe.get(&"a_space".into()).unwrap();
+    let character_ref: URef<Character> = universe.get(&"a_character".into()).unwrap();
+    assert_eq!(
+        space_ref.read().unwrap().protected_regions(),
+        &[
+            ProtectedRegion::new(read_only_bounds, RegionAccess::ReadOnly),
+            ProtectedRegion::new(owned_bounds, RegionAccess::Owner(character_ref)),
+        ]
+    );
+}
+
+/// Saved signal levels are restored, so sinks do not react again to an unchanged level.
+#[test]
+fn space_signals_round_trip() {
+    let [marker] = make_some_blocks();
+    let source = Block::builder()
+        .color(Rgba::WHITE)
+        .signal(block::BlockSignal::Source)
+        .build();
+    let sink = Block::builder()
+        .color(Rgba::BLACK)
+        .signal(block::BlockSignal::Sink {
+            powered: Some(VoxelBrush::new([([1, 0, 0], marker.clone())])),
+            unpowered: None,
+        })
+        .build();
+    let mut space = Space::empty_positive(3, 1, 1);
+    space.set([0, 0, 0], &source).unwrap();
+    space.set([1, 0, 0], &sink).unwrap();
+    let _ = space.step(None, Tick::arbitrary(), practically_infinite_deadline());
+    assert_eq!(space[[2, 0, 0]], marker);
+    space.set([2, 0, 0], block::AIR).unwrap();
+
+    let json_value = to_value(&space).unwrap();
+    assert_eq!(json_value["powered"], json!([[0, 0, 0], [1, 0, 0]]));
+    let mut loaded: Space = from_value(json_value).unwrap();
+    assert!(loaded.is_powered([1, 0, 0]));
+    let _ = loaded.step(None, Tick::arbitrary(), practically_infinite_deadline());
+    assert_eq!(loaded[[2, 0, 0]], block::AIR);
+}
+
+#[test]
+fn space_de_invalid_cube_map() {
+    let error = from_value::<Space>(json!({
+        "type": "SpaceV1",
+        "bounds": {
+            "lower": [0, 0, 0],
+            "upper": [1, 1, 1],
+        },
+        "blocks": [
+            {
+                "type": "BlockV1",
+                "primitive": {"type": "AirV1"},
+            },
+        ],
+        "contents": [0],
+        "physics": {
+            "type": "SpacePhysicsV1",
+            "gravity": [0.0, 0.0, 0.0],
+            "air_drag": 0.0,
+            "terminal_velocity": null,
+            "sky_color": [0.5, 0.5, 0.5],
+            "sky": {
+                "type": "CubeMapV1",
+                "resolution": 1,
+                "faces": {
+                    "nx": [[0.5, 0.5, 0.5]],
+                    "ny": [[0.5, 0.5, 0.5]],
+                    "nz": [[0.5, 0.5, 0.5]],
+                    "px": [[0.5, 0.5, 0.5]],
+                    "py": [],
+                    "pz": [[0.5, 0.5, 0.5]],
+                },
+            },
+            "light": {"type": "NoneV1"},
+        },
+    }))
+    .unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "invalid space physics: cube map face PY has 0 texels instead of 1×1"
+    );
+}
+
+/// Light saved in the older 8-bit encoding is converted.
+#[test]
+fn space_de_light_log8() {
+    let space = from_value::<Space>(json!({
+        "type": "SpaceV1",
+        "bounds": {
+            "lower": [0, 0, 0],
+            "upper": [2, 1, 1],
+        },
+        "blocks": [
+            {
+                "type": "BlockV1",
+                "primitive": {"type": "AirV1"},
+            },
+        ],
+        "contents": [0, 0],
+        "light": {
+            "complete": true,
+            "runs": [[[128, 128, 128, 255], 1], [[144, 0, 112, 255], 1]],
+        },
+    }))
+    .unwrap();
+    assert_eq!(space.get_lighting([0, 0, 0]), PackedLight::ONE);
+    assert_eq!(
+        space.get_lighting([1, 0, 0]).value(),
+        Rgb::new(2.0, 0.0, 0.5)
+    );
+}
+
+#[test]
+fn space_de_invalid_light() {
+    let error = from_value::<Space>(json!({
+        "type": "SpaceV1",
+        "bounds": {
+            "lower": [0, 0, 0],
+            "upper": [1, 1, 1],
+        },
+        "blocks": [
+            {
+                "type": "BlockV1",
+                "primitive": {"type": "AirV1"},
+            },
+        ],
+        "contents": [0],
+        "light": {
+            "complete": true,
+            "runs": [[[0, 0, 0, 7], 1]],
+        },
+    }))
+    .unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "Space light value [0, 0, 0, 7] is invalid"
+    );
+}
+
+/// Run-length encoded contents, as written by binary formats, are accepted too.
+#[test]
+fn space_de_runs() {
+    let space = from_value::<Space>(json!({
+        "type": "SpaceV1",
+        "bounds": {
+            "lower": [0, 0, 0],
+            "upper": [3, 1, 1],
+        },
+        "blocks": [
+            {
+                "type": "BlockV1",
+                "primitive": {"type": "AirV1"},
+            },
+            {
+                "type": "BlockV1",
+                "primitive": {
+                    "type": "AtomV1",
+                    "color": [1.0, 0.5, 0.0, 0.5],
+                },
+            },
+        ],
+        "contents": [[1, 1], [0, 2]],
+    }))
+    .unwrap();
+    assert_eq!(space[[0, 0, 0]], Block::from(Rgba::new(1.0, 0.5, 0.0, 0.5)));
+    assert_eq!(space[[1, 0, 0]], block::AIR);
+    assert_eq!(space[[2, 0, 0]], block::AIR);
+}
+
+#[test]
+fn space_de_runs_wrong_length() {
+    let error = from_value::<Space>(json!({
+        "type": "SpaceV1",
+        "bounds": {
+            "lower": [0, 0, 0],
+            "upper": [3, 1, 1],
+        },
+        "blocks": [
+            {
+                "type": "BlockV1",
+                "primitive": {"type": "AirV1"},
+            },
+        ],
+        "contents": [[0, 4000000000]],
+    }))
+    .unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "Space contents runs do not add up to the volume 3"
+    );
+}
+
+#[test]
+fn space_snapshot() {
+    let mut space = Space::empty_positive(2, 1, 1);
+    space.set([1, 0, 0], Block::from(Rgba::WHITE)).unwrap();
+    assert_round_trip_value(
+        &SpaceSnapshot::new(&space, 7),
+        json!({
+            "type": "SpaceSnapshotV1",
+            "tick_number": 7,
+            "bounds": {
+                "lower": [0, 0, 0],
+                "upper": [2, 1, 1],
+            },
+            "blocks": [
+                {
+                    "type": "BlockV1",
+                    "primitive": {"type": "AirV1"},
+                },
+                {
+                    "type": "BlockV1",
+                    "primitive": {
+                        "type": "AtomV1",
+                        "color": [1.0, 1.0, 1.0, 1.0],
+                    },
+                },
+            ],
+            "contents": [0, 1],
+        }),
+    );
+}
+
+#[test]
+fn space_delta() {
+    let mut space = Space::empty_positive(2, 1, 1);
+    space.set([1, 0, 0], Block::from(Rgba::WHITE)).unwrap();
+    assert_round_trip_value(
+        &SpaceDelta::new(&space, 7, 8, [GridPoint::new(1, 0, 0)]),
+        json!({
+            "type": "SpaceDeltaV1",
+            "base_tick_number": 7,
+            "tick_number": 8,
+            "blocks": [
+                {
+                    "type": "BlockV1",
+                    "primitive": {
+                        "type": "AtomV1",
+                        "color": [1.0, 1.0, 1.0, 1.0],
+                    },
+                },
+            ],
+            "cubes": [[[1, 0, 0], 0]],
+        }),
+    );
+}
+
+#[test]
+fn space_delta_de_bad_index() {
+    let error = from_value::<SpaceDelta>(json!({
+        "type": "SpaceDeltaV1",
+        "base_tick_number": 7,
+        "tick_number": 8,
+        "blocks": [],
+        "cubes": [[[1, 0, 0], 0]],
+    }))
+    .unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "block index 0 out of bounds of block table length 0"
+    );
+}
+
+#[test]
+fn waypoint() {
+    let mut universe = Universe::new();
+    let space = universe
+        .insert("a_space".into(), Space::empty_positive(1, 1, 1))
+        .unwrap();
+
+    assert_serdeser(
+        &Waypoint::new(space.clone(), [1.0, 2.5, -3.0]),
+        json!({
+            "type": "WaypointV1",
+            "space": {"type": "URefV1", "Specific": "a_space"},
+            "position": [1.0, 2.5, -3.0],
+        }),
+    );
+    assert_serdeser(
+        &Waypoint::new(space, [0.0, 0.0, 0.0]).with_icon(Block::from(Rgba::WHITE)),
+        json!({
+            "type": "WaypointV1",
+            "space": {"type": "URefV1", "Specific": "a_space"},
+            "position": [0.0, 0.0, 0.0],
+            "icon": {
+                "type": "BlockV1",
+                "primitive": {"type": "AtomV1", "color": [1.0, 1.0, 1.0, 1.0]},
+            },
         }),
     );
 }
@@ -240,8 +994,34 @@ fn universe_with_one_of_each() -> Universe {
         .unwrap();
     let space_ref = universe.insert("a_space".into(), space).unwrap();
 
+    universe
+        .insert(
+            "a_waypoint".into(),
+            Waypoint::new(space_ref.clone(), [1.0, 1.0, 1.0]),
+        )
+        .unwrap();
+
+    universe
+        .insert(
+            "an_entity".into(),
+            Entity::new(
+                space_ref.clone(),
+                Body::new_minimal([0.5, 0.5, 0.5], Aab::new(0.0, 0.25, 0.0, 0.25, 0.0, 0.25)),
+                Block::from(Rgba::WHITE),
+            ),
+        )
+        .unwrap();
+
     let character = Character::spawn_default(space_ref);
-    universe.insert("a_character".into(), character).unwrap();
+    let character_ref = universe.insert("a_character".into(), character).unwrap();
+
+    universe
+        .insert("a_player_stats".into(), PlayerStats::new(character_ref))
+        .unwrap();
+
+    universe
+        .insert("a_script".into(), Script::new("fn step(dt) {}"))
+        .unwrap();
 
     universe
 }
@@ -299,6 +1079,45 @@ fn universe_with_one_of_each_json() -> serde_json::Value {
                     }
                 }
             },
+            {
+                "name": {"Specific": "an_entity"},
+                "value": {
+                    "type": "EntityV1",
+                    "space": {"type": "URefV1", "Specific": "a_space"},
+                    "position": [0.5, 0.5, 0.5],
+                    "velocity": [0.0, 0.0, 0.0],
+                    "collision_box": {
+                        "lower": [0.0, 0.0, 0.0],
+                        "upper": [0.25, 0.25, 0.25],
+                    },
+                    "flying": false,
+                    "noclip": false,
+                    "yaw": 0.0,
+                    "pitch": 0.0,
+                    "appearance": {
+                        "type": "BlockV1",
+                        "primitive": {"type": "AtomV1", "color": [1.0, 1.0, 1.0, 1.0]},
+                    },
+                }
+            },
+            {
+                "name": {"Specific": "a_player_stats"},
+                "value": {
+                    "type": "PlayerStatsV1",
+                    "character": {"type": "URefV1", "Specific": "a_character"},
+                    "blocks_placed": [],
+                    "blocks_removed": [],
+                    "distance_traveled": 0.0,
+                    "time_played": 0.0,
+                }
+            },
+            {
+                "name": {"Specific": "a_script"},
+                "value": {
+                    "type": "ScriptV1",
+                    "source": "fn step(dt) {}",
+                }
+            },
             {
                 "name": {"Specific": "a_space"},
                 "value": {
@@ -323,6 +1142,22 @@ fn universe_with_one_of_each_json() -> serde_json::Value {
                     "contents": [
                         1, 0, 0, 0, 0, 0, 0, 0,
                     ],
+                    "physics": {
+                        "type": "SpacePhysicsV1",
+                        "gravity": [0.0, 0.0, 0.0],
+                        "air_drag": 0.0,
+                        "terminal_velocity": null,
+                        "sky_color": [0.5, 0.5, 0.5],
+                        "light": {"type": "NoneV1"},
+                    },
+                }
+            },
+            {
+                "name": {"Specific": "a_waypoint"},
+                "value": {
+                    "type": "WaypointV1",
+                    "space": {"type": "URefV1", "Specific": "a_space"},
+                    "position": [1.0, 1.0, 1.0],
                 }
             },
         ],
@@ -330,12 +1165,146 @@ fn universe_with_one_of_each_json() -> serde_json::Value {
 }
 
 #[test]
-fn universe_with_one_of_each_ser() {
-    // TODO: use assert_serdeser; we will need to finish hooking up URefs on deserialization
-    assert_eq!(
-        to_value(&universe_with_one_of_each()).unwrap(),
+fn universe_with_one_of_each_serdeser() {
+    assert_serdeser(
+        &universe_with_one_of_each(),
         universe_with_one_of_each_json(),
-    )
+    );
+}
+
+/// Deserialized `URef`s refer to the deserialized members, not dead placeholders.
+#[test]
+fn universe_de_binds_refs() {
+    let universe: Universe = from_value(universe_with_one_of_each_json()).unwrap();
+    let space_ref: URef<Space> = universe.get(&"a_space".into()).unwrap();
+    let character_ref: URef<Character> = universe.get(&"a_character".into()).unwrap();
+
+    assert_eq!(character_ref.read().unwrap().space, space_ref);
+    assert_eq!(
+        space_ref.read().unwrap()[[0, 0, 0]],
+        Block::from_primitive(block::Primitive::Indirect(
+            universe.get(&"a_block".into()).unwrap()
+        ))
+    );
+}
+
+#[test]
+fn universe_block_registry() {
+    let mut universe = Universe::new();
+    let block_ref = universe
+        .insert("a_block".into(), BlockDef::new(block::AIR))
+        .unwrap();
+    universe.register_block("aic:air", block_ref).unwrap();
+
+    let json = to_value(&universe).unwrap();
+    assert_eq!(
+        json["block_ids"],
+        json!([{
+            "id": "aic:air",
+            "block_def": {"type": "URefV1", "Specific": "a_block"},
+        }])
+    );
+    let universe: Universe = from_value(json).unwrap();
+    assert_eq!(
+        universe.block_registry().get("aic:air"),
+        Some(&universe.get(&"a_block".into()).unwrap())
+    );
+}
+
+#[test]
+fn universe_stack_limits() {
+    let mut universe = Universe::new();
+    let space_ref = universe
+        .insert("a_space".into(), Space::empty_positive(1, 1, 1))
+        .unwrap();
+    universe
+        .insert("a_character".into(), Character::spawn_default(space_ref))
+        .unwrap();
+    let limits = StackLimits::new(NonZeroU16::new(20).unwrap())
+        .with_override(ToolKind::InfiniteBlocks, NonZeroU16::new(5).unwrap());
+    universe.set_stack_limits(limits.clone());
+
+    let json = to_value(&universe).unwrap();
+    assert_eq!(
+        json["stack_limits"],
+        json!({
+            "type": "StackLimitsV1",
+            "standard": 20,
+            "overrides": [{"tool": "InfiniteBlocks", "limit": 5}],
+        })
+    );
+    let universe: Universe = from_value(json).unwrap();
+    assert_eq!(universe.stack_limits(), &limits);
+    let character = universe.get::<Character>(&"a_character".into()).unwrap();
+    assert_eq!(
+        character.read().unwrap().inventory().stack_limits(),
+        &limits
+    );
+
+    // Default limits are not written.
+    assert_eq!(
+        to_value(&Universe::new()).unwrap().get("stack_limits"),
+        None
+    );
+}
+
+#[test]
+fn universe_de_unresolved_refs() {
+    let error = from_value::<Universe>(json!({
+        "type": "UniverseV1",
+        "members": [
+            {
+                "name": {"Specific": "a_block"},
+                "value": {
+                    "type": "BlockV1",
+                    "primitive": {
+                        "type": "IndirectV1",
+                        "definition": {"type": "URefV1", "Specific": "nonexistent_1"},
+                    }
+                }
+            },
+            {
+                "name": {"Specific": "another_block"},
+                "value": {
+                    "type": "BlockV1",
+                    "primitive": {
+                        "type": "IndirectV1",
+                        "definition": {"type": "URefV1", "Specific": "nonexistent_2"},
+                    }
+                }
+            },
+        ],
+    }))
+    .unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "universe contains references to members which do not exist: \
+        'nonexistent_1', 'nonexistent_2'"
+    );
+}
+
+#[test]
+fn universe_de_cyclic_refs() {
+    let error = from_value::<Universe>(json!({
+        "type": "UniverseV1",
+        "members": [
+            {
+                "name": {"Specific": "a_block"},
+                "value": {
+                    "type": "BlockV1",
+                    "primitive": {
+                        "type": "IndirectV1",
+                        "definition": {"type": "URefV1", "Specific": "a_block"},
+                    }
+                }
+            },
+        ],
+    }))
+    .unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "universe contains members which refer to each other cyclically: 'a_block'"
+    );
 }
 
 #[test]
@@ -0,0 +1,58 @@
+//! [`Script`]s: world logic written in a scripting language and stored in a
+//! [`Universe`](crate::universe::Universe).
+//!
+//! Scripts are universe members, so they are saved along with the rest of the world
+//! whether or not they can be run. Running them requires the `scripting` feature, which
+//! provides `ScriptBehavior` to run a script as a [`Behavior`] of a [`Space`],
+//! using the [Rhai](https://rhai.rs/) language.
+//!
+//! [`Behavior`]: crate::behavior::Behavior
+//! [`Space`]: crate::space::Space
+
+use std::fmt;
+use std::sync::Arc;
+
+use crate::universe::{RefVisitor, VisitRefs};
+
+#[cfg(feature = "scripting")]
+mod rhai_behavior;
+#[cfg(feature = "scripting")]
+pub use rhai_behavior::*;
+
+/// Source code of a script, stored in a [`Universe`](crate::universe::Universe).
+///
+/// A script does nothing by itself; see the [module documentation](self) for how
+/// scripts are run.
+#[derive(Clone, Eq, Hash, PartialEq)]
+pub struct Script {
+    pub(crate) source: Arc<str>,
+}
+
+impl Script {
+    /// Constructs a [`Script`] with the given source code.
+    ///
+    /// The source is not checked for errors until the script is run.
+    pub fn new(source: impl Into<Arc<str>>) -> Self {
+        Self {
+            source: source.into(),
+        }
+    }
+
+    /// Returns the source code of the script.
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+}
+
+impl fmt::Debug for Script {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // The source may be long, so only say how long.
+        f.debug_struct("Script")
+            .field("source_len", &self.source.len())
+            .finish_non_exhaustive()
+    }
+}
+
+impl VisitRefs for Script {
+    fn visit_refs(&self, _visitor: &mut dyn RefVisitor) {}
+}
@@ -0,0 +1,604 @@
+//! Running [`Script`]s written in Rhai as [`Behavior`]s.
+
+use std::fmt;
+use std::sync::{Arc, Mutex};
+
+use cgmath::Vector4;
+use rhai::{Dynamic, Engine, EvalAltResult, Map, Scope, AST, FLOAT, INT};
+
+use crate::behavior::{Behavior, BehaviorContext};
+use crate::block::{Block, Primitive, AIR};
+use crate::character::Character;
+use crate::entity::Entity;
+use crate::event::GameEvent;
+use crate::inv::Tool;
+use crate::listen::{DirtyFlag, FnListener, Listen, Listener as _};
+use crate::math::{GridAab, GridArray, GridCoordinate, GridPoint, Rgba};
+use crate::script::Script;
+use crate::space::{Space, SpaceChange, SpaceTransaction};
+use crate::time::Tick;
+use crate::transaction::Merge as _;
+use crate::universe::{BlockRegistry, RefVisitor, URef, UniverseTransaction, VisitRefs};
+
+/// Maximum number of operations a script may perform in one step, so that a script
+/// which loops forever cannot stall the game.
+const MAX_OPERATIONS: u64 = 1_000_000;
+
+/// A [`Behavior`] of a [`Space`] which runs a [`Script`] written in
+/// [Rhai](https://rhai.rs/).
+///
+/// Each step, the script's `on_event(event)` function, if it has one, is called for
+/// each [`GameEvent`] received since the last step (see [`Self::listen_to_events()`]),
+/// and then its `step(dt)` function is called, with `dt` being the duration of the
+/// step in seconds. Scripts act on the space only through these functions, whose
+/// coordinates are those of the space, and only within the bounds of the behavior's
+/// [attachment](crate::space::SpaceBehaviorAttachment):
+///
+/// * `get_block(x, y, z)` returns the block in the given cube, as of the start of
+///   the step.
+/// * `set_block(x, y, z, block)` replaces the block in the given cube.
+/// * `spawn_tool(x, y, z, tool)` drops the given tool as an item in the given cube,
+///   for a character to pick up.
+/// * `air()` returns the empty block, `rgba(r, g, b, a)` returns a block of a single
+///   color, and `block(id)` returns the block with the given identifier in the
+///   [`BlockRegistry`] given to [`Self::with_block_registry()`]. Blocks may be compared
+///   with `==`.
+/// * `block_tool(block)` returns a tool which places the given block once, and
+///   `remove_block_tool()` returns a tool which removes blocks.
+///
+/// All changes are made together, as a transaction, after the step's functions
+/// return. Any error, including exceeding the limit on the number of operations,
+/// abandons the step's changes and is logged.
+///
+/// Events are passed to `on_event` as maps with a `kind` field, one of
+/// `"block_placed"`, `"block_broken"`, `"item_picked_up"`, `"chat"`, `"died"`,
+/// `"respawned"`, or `"error"`, and the other fields of the [`GameEvent`], with
+/// cubes given as `x`, `y`, and `z`, and spaces and characters given by name.
+pub struct ScriptBehavior {
+    script: URef<Script>,
+    /// The engine and compiled script, created on the first step and kept so that
+    /// neither need be rebuilt every step.
+    runner: Mutex<Option<Runner>>,
+    /// Events received since the last step.
+    events: Arc<Mutex<Vec<GameEvent>>>,
+    /// Identifiers which the script's `block()` function looks up.
+    blocks: BlockRegistry,
+}
+
+/// The parts of a [`ScriptBehavior`] which persist from step to step.
+struct Runner {
+    /// The region of the space the behavior is attached to.
+    region: GridAab,
+    engine: Engine,
+    /// The most recently compiled script, and the source it was compiled from.
+    compiled: Option<(Script, AST)>,
+    /// Data shared with the functions registered in `engine`.
+    state: Arc<Mutex<ScriptState>>,
+    /// Set when a block in `state.contents` may have changed in the space.
+    contents_dirty: DirtyFlag,
+}
+
+/// Data shared between a [`ScriptBehavior`] and the functions it provides to the script.
+struct ScriptState {
+    /// The blocks within the behavior's region, as of the start of the step.
+    contents: GridArray<Block>,
+    /// Changes made by the script during the current step.
+    transaction: SpaceTransaction,
+    /// Items spawned by the script during the current step, in the middle of the given
+    /// cubes.
+    spawned: Vec<(GridPoint, Tool)>,
+}
+
+impl ScriptBehavior {
+    /// Constructs a [`ScriptBehavior`] which runs the given script.
+    pub fn new(script: URef<Script>) -> Self {
+        Self {
+            script,
+            runner: Mutex::new(None),
+            events: Arc::new(Mutex::new(Vec::new())),
+            blocks: BlockRegistry::default(),
+        }
+    }
+
+    /// Sets the block identifiers which the script's `block()` function looks up,
+    /// typically a [`Universe`](crate::universe::Universe)'s
+    /// [`block_registry()`](crate::universe::Universe::block_registry).
+    ///
+    /// Identifiers registered later are not seen by the script.
+    #[must_use]
+    pub fn with_block_registry(mut self, registry: BlockRegistry) -> Self {
+        self.blocks = registry;
+        self
+    }
+
+    /// Delivers the events from `source`, such as a
+    /// [`Universe`](crate::universe::Universe), to the script's `on_event` function.
+    ///
+    /// Events are queued until the behavior's next step.
+    pub fn listen_to_events(&self, source: impl Listen<Msg = GameEvent>) {
+        source.listen(FnListener::new(
+            &self.events,
+            |events: &Mutex<Vec<GameEvent>>, event: GameEvent| events.lock().unwrap().push(event),
+        ));
+    }
+
+    /// Runs the script's functions for one step, returning the changes they made and
+    /// the items they spawned.
+    fn run_step(
+        &self,
+        space: &Space,
+        region: GridAab,
+        tick: Tick,
+    ) -> Result<(SpaceTransaction, Vec<(GridPoint, Tool)>), String> {
+        let mut runner_guard = self.runner.lock().unwrap();
+        if runner_guard
+            .as_ref()
+            .map_or(true, |runner| runner.region != region)
+        {
+            *runner_guard = Some(Runner::new(space, region, self.blocks.clone()));
+        }
+        let runner = runner_guard.as_mut().unwrap();
+
+        // Compile the script only if it has changed.
+        let script = self
+            .script
+            .read()
+            .map_err(|e| format!("failed to read script: {e}"))?;
+        if !matches!(&runner.compiled, Some((source, _)) if *source == *script) {
+            let ast = runner
+                .engine
+                .compile(script.source())
+                .map_err(|e| format!("failed to compile script: {e}"))?;
+            runner.compiled = Some((Script::clone(&script), ast));
+        }
+        let (_, ast) = runner.compiled.as_ref().unwrap();
+
+        {
+            let mut state = runner.state.lock().unwrap();
+            if runner.contents_dirty.get_and_clear() {
+                state.contents =
+                    space.extract(region, |_, block_data, _| block_data.block().clone());
+            }
+            state.transaction = SpaceTransaction::default();
+            state.spawned.clear();
+        }
+
+        let events = std::mem::take(&mut *self.events.lock().unwrap());
+        if !events.is_empty()
+            && ast
+                .iter_functions()
+                .any(|f| f.name == "on_event" && f.params.len() == 1)
+        {
+            for event in events {
+                runner
+                    .engine
+                    .call_fn::<Dynamic>(
+                        &mut Scope::new(),
+                        ast,
+                        "on_event",
+                        (event_to_script(&event),),
+                    )
+                    .map_err(|e| format!("script failed: {e}"))?;
+            }
+        }
+        runner
+            .engine
+            .call_fn::<Dynamic>(
+                &mut Scope::new(),
+                ast,
+                "step",
+                (tick.delta_t().as_secs_f64(),),
+            )
+            .map_err(|e| format!("script failed: {e}"))?;
+
+        let mut state = runner.state.lock().unwrap();
+        Ok((
+            std::mem::take(&mut state.transaction),
+            std::mem::take(&mut state.spawned),
+        ))
+    }
+}
+
+impl Runner {
+    /// Constructs a [`Runner`] for a behavior attached to `region` of `space`, whose
+    /// script looks up blocks in `blocks`.
+    fn new(space: &Space, region: GridAab, blocks: BlockRegistry) -> Self {
+        let state = Arc::new(Mutex::new(ScriptState {
+            contents: space.extract(region, |_, block_data, _| block_data.block().clone()),
+            transaction: SpaceTransaction::default(),
+            spawned: Vec::new(),
+        }));
+        let contents_dirty = DirtyFlag::new(false);
+        space.listen(
+            contents_dirty
+                .listener()
+                .filter(move |change| match change {
+                    SpaceChange::Block(cube) if region.contains_cube(cube) => Some(()),
+                    SpaceChange::EveryBlock => Some(()),
+                    SpaceChange::Block(_)
+                    | SpaceChange::Lighting(_)
+                    | SpaceChange::Number(_)
+                    | SpaceChange::BlockValue(_)
+                    | SpaceChange::Weather => None,
+                }),
+        );
+        Self {
+            region,
+            engine: script_engine(state.clone(), blocks),
+            compiled: None,
+            state,
+            contents_dirty,
+        }
+    }
+}
+
+impl Behavior<Space> for ScriptBehavior {
+    fn step(&self, context: &BehaviorContext<'_, Space>, tick: Tick) -> UniverseTransaction {
+        if tick.paused() {
+            return UniverseTransaction::default();
+        }
+        match self.run_step(context.host, context.attachment.bounds(), tick) {
+            Ok((transaction, spawned)) => spawned.into_iter().fold(
+                context.bind_host(transaction),
+                |transaction, (cube, tool)| {
+                    transaction
+                        .merge(Entity::drop_item(context.host_ref.clone(), cube, tool))
+                        .unwrap()
+                },
+            ),
+            Err(message) => {
+                log::error!("{name}: {message}", name = self.script.name());
+                UniverseTransaction::default()
+            }
+        }
+    }
+
+    fn alive(&self, _context: &BehaviorContext<'_, Space>) -> bool {
+        true
+    }
+
+    fn ephemeral(&self) -> bool {
+        false
+    }
+}
+
+impl fmt::Debug for ScriptBehavior {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ScriptBehavior")
+            .field("script", &self.script)
+            .finish_non_exhaustive()
+    }
+}
+
+impl VisitRefs for ScriptBehavior {
+    fn visit_refs(&self, visitor: &mut dyn RefVisitor) {
+        self.script.visit_refs(visitor);
+        for (_, block_def) in self.blocks.iter() {
+            visitor.visit(block_def);
+        }
+    }
+}
+
+/// Constructs an [`Engine`] providing the functions documented at [`ScriptBehavior`],
+/// which read from and write to `state` and look up block identifiers in `blocks`.
+fn script_engine(state: Arc<Mutex<ScriptState>>, blocks: BlockRegistry) -> Engine {
+    let mut engine = Engine::new();
+    engine.set_max_operations(MAX_OPERATIONS);
+
+    engine.register_type_with_name::<Block>("Block");
+    engine.register_fn("==", |a: Block, b: Block| a == b);
+    engine.register_fn("!=", |a: Block, b: Block| a != b);
+    engine.register_fn("air", || AIR);
+    engine.register_fn(
+        "rgba",
+        |r: FLOAT, g: FLOAT, b: FLOAT, a: FLOAT| -> Result<Block, Box<EvalAltResult>> {
+            let color = Rgba::try_from(Vector4::new(r as f32, g as f32, b as f32, a as f32))
+                .map_err(|_| "color components must not be NaN")?;
+            Ok(Block::from(color))
+        },
+    );
+    engine.register_fn(
+        "block",
+        move |id: &str| -> Result<Block, Box<EvalAltResult>> {
+            let block_def = blocks
+                .get(id)
+                .ok_or_else(|| format!("no block has the identifier {id:?}"))?;
+            Ok(Block::from_primitive(Primitive::Indirect(
+                block_def.clone(),
+            )))
+        },
+    );
+
+    engine.register_type_with_name::<Tool>("Tool");
+    engine.register_fn("block_tool", Tool::Block);
+    engine.register_fn("remove_block_tool", || Tool::RemoveBlock { keep: true });
+
+    let get_state = state.clone();
+    engine.register_fn(
+        "get_block",
+        move |x: INT, y: INT, z: INT| -> Result<Block, Box<EvalAltResult>> {
+            let state = get_state.lock().unwrap();
+            let cube = script_cube(x, y, z, state.contents.bounds())?;
+            Ok(state.contents[cube].clone())
+        },
+    );
+    let set_state = state.clone();
+    engine.register_fn(
+        "set_block",
+        move |x: INT, y: INT, z: INT, block: Block| -> Result<(), Box<EvalAltResult>> {
+            let mut state = set_state.lock().unwrap();
+            let cube = script_cube(x, y, z, state.contents.bounds())?;
+            state.transaction.set_overwrite(cube, block);
+            Ok(())
+        },
+    );
+    engine.register_fn(
+        "spawn_tool",
+        move |x: INT, y: INT, z: INT, tool: Tool| -> Result<(), Box<EvalAltResult>> {
+            let mut state = state.lock().unwrap();
+            let cube = script_cube(x, y, z, state.contents.bounds())?;
+            state.spawned.push((cube, tool));
+            Ok(())
+        },
+    );
+
+    engine
+}
+
+/// Converts coordinates given by a script to a cube, checking that it is one the script
+/// may access.
+fn script_cube(x: INT, y: INT, z: INT, region: GridAab) -> Result<GridPoint, Box<EvalAltResult>> {
+    let convert = |c: INT| GridCoordinate::try_from(c).ok();
+    match (convert(x), convert(y), convert(z)) {
+        (Some(x), Some(y), Some(z)) if region.contains_cube([x, y, z]) => {
+            Ok(GridPoint::new(x, y, z))
+        }
+        _ => {
+            Err(format!("cube ({x}, {y}, {z}) is outside of the script's region {region:?}").into())
+        }
+    }
+}
+
+/// Converts a [`GameEvent`] to the form documented at [`ScriptBehavior`].
+fn event_to_script(event: &GameEvent) -> Dynamic {
+    fn name(character: &URef<Character>) -> Dynamic {
+        character.name().to_string().into()
+    }
+
+    let mut map = Map::new();
+    let kind = match event {
+        GameEvent::BlockPlaced {
+            space,
+            cube,
+            block,
+            character,
+        }
+        | GameEvent::BlockBroken {
+            space,
+            cube,
+            block,
+            character,
+        } => {
+            map.insert("space".into(), space.name().to_string().into());
+            map.insert("x".into(), INT::from(cube.x).into());
+            map.insert("y".into(), INT::from(cube.y).into());
+            map.insert("z".into(), INT::from(cube.z).into());
+            map.insert("block".into(), Dynamic::from(block.clone()));
+            map.insert(
+                "character".into(),
+                character.as_ref().map_or(Dynamic::UNIT, name),
+            );
+            if matches!(event, GameEvent::BlockPlaced { .. }) {
+                "block_placed"
+            } else {
+                "block_broken"
+            }
+        }
+        GameEvent::ItemPickedUp { character, item } => {
+            map.insert("character".into(), name(character));
+            map.insert("item".into(), Dynamic::from(item.clone()));
+            "item_picked_up"
+        }
+        GameEvent::Chat { sender, message } => {
+            map.insert("sender".into(), sender.as_ref().map_or(Dynamic::UNIT, name));
+            map.insert("message".into(), message.to_string().into());
+            "chat"
+        }
+        GameEvent::Died {
+            character,
+            cause: _,
+        } => {
+            map.insert("character".into(), name(character));
+            "died"
+        }
+        GameEvent::Respawned { character } => {
+            map.insert("character".into(), name(character));
+            "respawned"
+        }
+        GameEvent::Error { message } => {
+            map.insert("message".into(), message.to_string().into());
+            "error"
+        }
+    };
+    map.insert("kind".into(), kind.into());
+    Dynamic::from_map(map)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::behavior::BehaviorSetTransaction;
+    use crate::block::BlockDef;
+    use crate::content::make_some_blocks;
+    use crate::space::SpaceBehaviorAttachment;
+    use crate::time::practically_infinite_deadline;
+    use crate::transaction;
+    use crate::universe::Universe;
+
+    /// A 3×1×1 space, initially containing one block at the origin, with a script
+    /// attached to the first two cubes.
+    struct Tester {
+        universe: Universe,
+        space: URef<Space>,
+        behavior: Arc<ScriptBehavior>,
+    }
+
+    impl Tester {
+        fn new(source: &str) -> Self {
+            Self::with_universe(Universe::new(), source)
+        }
+
+        /// As [`Tester::new()`], but with a universe which may contain registered
+        /// blocks for the script to use.
+        fn with_universe(mut universe: Universe, source: &str) -> Self {
+            let [block] = make_some_blocks();
+            let script = universe.insert_anonymous(Script::new(source));
+            let mut space = Space::empty_positive(3, 1, 1);
+            space.set([0, 0, 0], &block).unwrap();
+            let space = universe.insert_anonymous(space);
+            let behavior = Arc::new(
+                ScriptBehavior::new(script).with_block_registry(universe.block_registry().clone()),
+            );
+            space
+                .execute(
+                    &SpaceTransaction::behaviors(BehaviorSetTransaction::insert(
+                        SpaceBehaviorAttachment::new(GridAab::from_lower_size(
+                            [0, 0, 0],
+                            [2, 1, 1],
+                        )),
+                        behavior.clone(),
+                    )),
+                    &mut transaction::no_outputs,
+                )
+                .unwrap();
+            Self {
+                universe,
+                space,
+                behavior,
+            }
+        }
+
+        /// Steps the universe and returns the resulting blocks.
+        fn step(&mut self) -> [Block; 3] {
+            self.universe
+                .step(Tick::arbitrary(), practically_infinite_deadline());
+            let space = self.space.read().unwrap();
+            [0, 1, 2].map(|x| space[[x, 0, 0]].clone())
+        }
+    }
+
+    /// Runs `source` for one step and returns the resulting blocks.
+    fn run(source: &str) -> [Block; 3] {
+        Tester::new(source).step()
+    }
+
+    #[test]
+    fn copies_block() {
+        let blocks = run("fn step(dt) { set_block(1, 0, 0, get_block(0, 0, 0)); }");
+        assert_eq!(blocks[1], blocks[0]);
+        assert_ne!(blocks[1], AIR);
+    }
+
+    #[test]
+    fn constructs_blocks() {
+        let blocks = run("fn step(dt) {
+                if get_block(1, 0, 0) == air() { set_block(1, 0, 0, rgba(1.0, 0.0, 0.0, 1.0)); }
+            }");
+        assert_eq!(blocks[1], Block::from(Rgba::new(1.0, 0.0, 0.0, 1.0)));
+    }
+
+    #[test]
+    fn cannot_write_outside_region() {
+        let blocks = run("fn step(dt) { set_block(0, 0, 0, air()); set_block(2, 0, 0, air()); }");
+        // The error abandoned the whole step's changes.
+        assert_ne!(blocks[0], AIR);
+    }
+
+    #[test]
+    fn infinite_loop_is_stopped() {
+        let blocks = run("fn step(dt) { set_block(0, 0, 0, air()); loop {} }");
+        assert_ne!(blocks[0], AIR);
+    }
+
+    #[test]
+    fn sees_changes_made_between_steps() {
+        let mut tester = Tester::new("fn step(dt) { set_block(1, 0, 0, get_block(0, 0, 0)); }");
+        tester.step();
+        let new_block = Block::from(Rgba::new(0.0, 0.0, 1.0, 1.0));
+        tester
+            .space
+            .execute(
+                &SpaceTransaction::set_cube([0, 0, 0], None, Some(new_block.clone())),
+                &mut transaction::no_outputs,
+            )
+            .unwrap();
+        let blocks = tester.step();
+        assert_eq!(blocks[1], new_block);
+    }
+
+    #[test]
+    fn block_by_identifier() {
+        let mut universe = Universe::new();
+        let block_def = universe
+            .insert("stone".into(), BlockDef::new(Block::from(Rgba::WHITE)))
+            .unwrap();
+        universe
+            .register_block("test:stone", block_def.clone())
+            .unwrap();
+        let mut tester = Tester::with_universe(
+            universe,
+            r#"fn step(dt) { set_block(1, 0, 0, block("test:stone")); }"#,
+        );
+        let blocks = tester.step();
+        assert_eq!(
+            blocks[1],
+            Block::from_primitive(Primitive::Indirect(block_def))
+        );
+    }
+
+    #[test]
+    fn block_by_unknown_identifier() {
+        let blocks = run(r#"fn step(dt) { set_block(1, 0, 0, block("test:nope")); }"#);
+        assert_eq!(blocks[1], AIR);
+    }
+
+    #[test]
+    fn spawns_tool() {
+        let mut tester = Tester::new(
+            "fn step(dt) { spawn_tool(1, 0, 0, block_tool(rgba(1.0, 0.0, 0.0, 1.0))); }",
+        );
+        tester.step();
+        let items: Vec<Tool> = tester
+            .universe
+            .iter_by_type::<Entity>()
+            .filter_map(|(_, entity)| entity.read().unwrap().item().cloned())
+            .collect();
+        assert_eq!(
+            items,
+            vec![Tool::Block(Block::from(Rgba::new(1.0, 0.0, 0.0, 1.0)))]
+        );
+    }
+
+    #[test]
+    fn receives_events() {
+        let mut tester = Tester::new(
+            r#"
+            fn on_event(event) {
+                if event.kind == "chat" && event.message == "hello" {
+                    set_block(0, 0, 0, air());
+                }
+            }
+            fn step(dt) {}
+            "#,
+        );
+        tester.behavior.listen_to_events(&tester.universe);
+        UniverseTransaction::event(GameEvent::Chat {
+            sender: None,
+            message: "hello".into(),
+        })
+        .execute(&mut tester.universe, &mut drop)
+        .unwrap();
+        let blocks = tester.step();
+        assert_eq!(blocks[0], AIR);
+    }
+}
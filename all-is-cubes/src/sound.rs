@@ -0,0 +1,79 @@
+//! Sounds emitted from locations in the game world.
+
+use cgmath::Point3;
+
+use crate::fluff::Fluff;
+use crate::math::FreeCoordinate;
+
+#[cfg(doc)]
+use crate::universe::{Universe, UniverseTransaction};
+
+/// A sound to be played, emitted by a behavior, tool, or other game logic via
+/// [`UniverseTransaction::sound()`].
+///
+/// The sounds emitted during each [`Universe::step()`] are collected and may be retrieved
+/// with [`Universe::take_sounds()`], for playback by whatever audio output is available.
+#[derive(Clone, Debug, PartialEq)]
+#[non_exhaustive]
+pub struct SoundEvent {
+    /// Which sound to play. Audio outputs choose a sound for each kind of [`Fluff`].
+    pub sound: Fluff,
+
+    /// Where the sound comes from, or [`None`] if it should be heard equally everywhere.
+    ///
+    /// TODO: This does not specify which [`Space`](crate::space::Space) it is in;
+    /// audio outputs assume it is the one the listener is in.
+    pub position: Option<Point3<FreeCoordinate>>,
+
+    /// Loudness of the sound, before any attenuation due to distance; 1.0 is normal.
+    pub volume: f32,
+}
+
+impl SoundEvent {
+    /// Constructs a [`SoundEvent`] for a sound at normal volume.
+    pub fn new(sound: Fluff, position: Option<Point3<FreeCoordinate>>) -> Self {
+        Self {
+            sound,
+            position,
+            volume: 1.0,
+        }
+    }
+
+    /// Returns this event with its volume multiplied by `factor`.
+    #[must_use]
+    pub fn with_volume(mut self, factor: f32) -> Self {
+        self.volume *= factor;
+        self
+    }
+
+    /// Returns the volume at which this sound should be heard by a listener at
+    /// `listener_position`, taking into account the distance between them.
+    ///
+    /// The attenuation is an inverse-distance law, leaving sounds within 1 unit of the
+    /// listener unaffected.
+    pub fn volume_at(&self, listener_position: Point3<FreeCoordinate>) -> f32 {
+        use cgmath::MetricSpace as _;
+        match self.position {
+            Some(position) => {
+                let distance = position.distance(listener_position) as f32;
+                self.volume / distance.max(1.0)
+            }
+            None => self.volume,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn volume_at() {
+        let unpositioned = SoundEvent::new(Fluff::Beep, None).with_volume(0.5);
+        assert_eq!(unpositioned.volume_at(Point3::new(100., 0., 0.)), 0.5);
+
+        let positioned = SoundEvent::new(Fluff::Beep, Some(Point3::new(0., 0., 0.)));
+        assert_eq!(positioned.volume_at(Point3::new(0.5, 0., 0.)), 1.0);
+        assert_eq!(positioned.volume_at(Point3::new(0., 4., 0.)), 0.25);
+    }
+}
@@ -1,46 +1,79 @@
 //! That which contains many blocks.
 
 use std::borrow::Cow;
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeSet, HashMap, HashSet};
 use std::fmt;
 use std::sync::{Arc, Mutex, Weak};
 
-use cgmath::Vector3;
+use cgmath::{Point3, Vector3};
 use instant::{Duration, Instant};
 
 use crate::behavior::{self, BehaviorSet};
 use crate::block::{
-    Block, BlockChange, EvalBlockError, EvaluatedBlock, Resolution, AIR, AIR_EVALUATED,
+    Block, BlockChange, BlockSignal, EvalBlockError, EvaluatedBlock, Resolution, AIR, AIR_EVALUATED,
 };
-#[cfg(doc)]
-use crate::character::Character;
-use crate::character::Spawn;
+use crate::character::{Character, Spawn};
 use crate::content::palette;
 use crate::drawing::DrawingPlane;
 use crate::inv::EphemeralOpaque;
 use crate::listen::{Gate, Listen, Listener, Notifier};
 use crate::math::{
     point_checked_add, Face6, FreeCoordinate, GridAab, GridArray, GridCoordinate, GridMatrix,
-    GridPoint, GridRotation, NotNan, Rgb,
+    GridPoint, GridRotation, NotNan,
 };
 use crate::time::Tick;
 use crate::transaction::{Merge, Transaction as _};
 use crate::universe::{RefVisitor, URef, UniverseTransaction, VisitRefs};
-use crate::util::{ConciseDebug, TimeStats};
+use crate::util::{CancellationToken, Cancelled, ConciseDebug, TimeStats};
 use crate::util::{CustomFormat, StatusText};
 
 mod builder;
 pub use builder::{SpaceBuilder, SpaceBuilderBounds};
 
+mod contents;
+use contents::SpaceContents;
+
+mod interest;
+pub use interest::{InterestArea, InterestChange};
+
 mod light;
 #[doc(hidden)] // pub only for visualization by all-is-cubes-gpu
 pub use light::LightUpdateCubeInfo;
-use light::{opaque_for_light_computation, LightUpdateQueue, PackedLightScalar};
+use light::{opaque_for_light_computation, LightUpdatePriority, LightUpdateQueue};
 pub use light::{LightUpdatesInfo, PackedLight};
 
+mod signal;
+
+mod sky;
+pub use sky::{CubeMap, CubeMapError, Sky, SkyGradient, Sun};
+
+mod protection;
+pub use protection::{ProtectedRegion, RegionAccess};
+
+mod query;
+
+mod selection;
+pub use selection::{Selection, SelectionError};
+
+mod snapshot;
+pub use snapshot::{SpaceDelta, SpaceSnapshot};
+
 mod space_txn;
 pub use space_txn::*;
 
+mod top_down_map;
+pub use top_down_map::TopDownMap;
+
+mod transition;
+pub(crate) use transition::space_transition_for;
+pub use transition::SpaceTransition;
+
+mod waypoint;
+pub use waypoint::{TeleportError, Waypoint};
+
+mod weather;
+pub use weather::{Precipitation, Weather, WeatherParticles};
+
 #[cfg(test)]
 mod tests;
 
@@ -55,16 +88,11 @@ pub struct Space {
     /// Lookup from arbitrarily assigned indices (used in `contents`) to data for them.
     block_data: Vec<SpaceBlockData>,
 
-    /// The blocks in the space, stored compactly:
-    ///
-    /// * Coordinates are transformed to indices by [`GridAab::index`].
-    /// * Each element is an index into [`Self::block_data`].
-    // TODO: Consider making this use different integer types depending on how
-    // many blocks there are, so we can save memory in simple spaces but not have
-    // a cap on complex ones.
-    contents: Box<[BlockIndex]>,
-
-    /// Parallel array to `contents` for lighting data.
+    /// The blocks in the space, as indices into [`Self::block_data`].
+    contents: SpaceContents,
+
+    /// Lighting data for each cube; coordinates are transformed to indices by
+    /// [`GridAab::index`].
     pub(crate) lighting: Box<[PackedLight]>,
     /// Queue of cubes whose light values should be updated.
     light_update_queue: LightUpdateQueue,
@@ -78,17 +106,40 @@ pub struct Space {
     /// Global characteristics such as the behavior of light and gravity.
     physics: SpacePhysics,
 
-    /// A converted copy of `physics.sky_color`.
+    /// A converted copy of `physics.sky.mean()`.
     packed_sky_color: PackedLight,
 
+    /// Current weather, which changes toward `target_weather` each step.
+    weather: Weather,
+    target_weather: Weather,
+    /// Total time the weather has been stepped for, used to animate particles.
+    weather_time: Duration,
+
     // TODO: Replace this with something that has a spatial index so we can
     // search for behaviors in specific regions
     behaviors: BehaviorSet<Space>,
 
     spawn: Spawn,
 
-    /// Cubes that should be checked on the next call to step()
-    cubes_wanting_ticks: HashSet<GridPoint>,
+    /// Regions in which characters' modifications are restricted.
+    protected_regions: Vec<ProtectedRegion>,
+
+    /// Whether removed blocks are dropped as item entities.
+    item_drops: bool,
+
+    /// Cubes whose blocks have a `tick_action`, and the number of calls to step()
+    /// remaining until it should be performed (per `BlockAttributes::tick_period`).
+    cubes_wanting_ticks: HashMap<GridPoint, u16>,
+
+    /// Cubes whose blocks might have a [`BlockSignal`](crate::block::BlockSignal) other
+    /// than `None`; may contain stale entries, which are removed by the next step().
+    /// Ordered so that signal processing is deterministic.
+    signal_cubes: BTreeSet<[GridCoordinate; 3]>,
+    /// Subset of `signal_cubes` whose blocks were placed since the last step(), so
+    /// sinks among them must react to their signal level even if it did not change.
+    new_signal_cubes: BTreeSet<[GridCoordinate; 3]>,
+    /// Cubes which were powered as of the last step().
+    powered_cubes: BTreeSet<[GridCoordinate; 3]>,
 
     notifier: Notifier<SpaceChange>,
 
@@ -118,7 +169,9 @@ impl fmt::Debug for Space {
             .field("bounds", &self.bounds)
             .field("block_data", &self.block_data)
             .field("physics", &self.physics)
+            .field("weather", &self.weather)
             .field("behaviors", &self.behaviors)
+            .field("protected_regions", &self.protected_regions)
             .field("cubes_wanting_ticks", &self.cubes_wanting_ticks) // TODO: truncate?
             .finish_non_exhaustive()
     }
@@ -169,7 +222,9 @@ impl Space {
             bounds,
             spawn,
             physics,
+            weather,
             initial_fill,
+            item_drops,
         } = builder;
 
         let volume = bounds.volume();
@@ -189,7 +244,7 @@ impl Space {
         block_data.count = volume;
 
         // TODO: light update queue should not necessarily be empty.
-        // TODO: handle the block possibly having a tick_action.
+        // TODO: handle the block possibly having a tick_action or signal.
 
         Space {
             bounds,
@@ -201,18 +256,26 @@ impl Space {
                 }
                 map
             },
-            contents: vec![0; volume].into_boxed_slice(),
+            contents: SpaceContents::new(bounds, 0),
 
             lighting: physics.light.initialize_lighting(bounds),
-            packed_sky_color: physics.sky_color.into(),
+            packed_sky_color: physics.sky.mean().into(),
             light_update_queue: LightUpdateQueue::new(),
             last_light_updates: Vec::new(),
             light_cost_scale: 1e-6,
 
             physics,
+            weather,
+            target_weather: weather,
+            weather_time: Duration::ZERO,
             behaviors: BehaviorSet::new(),
             spawn: spawn.unwrap_or_else(|| Spawn::default_for_new_space(bounds)),
-            cubes_wanting_ticks: HashSet::new(),
+            protected_regions: Vec::new(),
+            item_drops,
+            cubes_wanting_ticks: HashMap::new(),
+            signal_cubes: BTreeSet::new(),
+            new_signal_cubes: BTreeSet::new(),
+            powered_cubes: BTreeSet::new(),
             notifier: Notifier::new(),
             todo,
         }
@@ -239,16 +302,19 @@ impl Space {
     /// may be renumbered after any mutation.
     #[inline(always)]
     pub fn get_block_index(&self, position: impl Into<GridPoint>) -> Option<BlockIndex> {
-        self.bounds
-            .index(position.into())
-            .map(|contents_index| self.contents[contents_index])
+        let position = position.into();
+        if self.bounds.contains_cube(position) {
+            Some(self.contents.get(position))
+        } else {
+            None
+        }
     }
 
     /// Copy data out of a portion of the space in a caller-chosen format.
     ///
     /// If the provided [`GridAab`] contains portions outside of this space's bounds,
     /// those positions in the output will be treated as if they are filled with [`AIR`]
-    /// and lit by [`SpacePhysics::sky_color`].
+    /// and lit by the [mean](Sky::mean) color of [`SpacePhysics::sky`].
     pub fn extract<V>(
         &self,
         subgrid: GridAab,
@@ -259,7 +325,7 @@ impl Space {
             // interior_iter().enumerate() because it's a sub-region).
             match self.bounds.index(cube) {
                 Some(cube_index) => {
-                    let block_index = self.contents[cube_index];
+                    let block_index = self.contents.get(cube);
                     extractor(
                         Some(block_index),
                         &self.block_data[block_index as usize],
@@ -279,8 +345,9 @@ impl Space {
     /// Gets the [`EvaluatedBlock`] of the block in this space at the given position.
     #[inline(always)]
     pub fn get_evaluated(&self, position: impl Into<GridPoint>) -> &EvaluatedBlock {
-        if let Some(index) = self.bounds.index(position) {
-            &self.block_data[self.contents[index] as usize].evaluated
+        let position = position.into();
+        if self.bounds.contains_cube(position) {
+            &self.block_data[self.contents.get(position) as usize].evaluated
         } else {
             &AIR_EVALUATED
         }
@@ -338,7 +405,7 @@ impl Space {
         block: Cow<'_, Block>,
     ) -> Result<bool, SetCubeError> {
         if let Some(contents_index) = self.bounds.index(position) {
-            let old_block_index = self.contents[contents_index];
+            let old_block_index = self.contents.get(position);
             let old_block = &self.block_data[old_block_index as usize].block;
             if *old_block == *block {
                 // No change.
@@ -395,7 +462,7 @@ impl Space {
             self.block_data[new_block_index as usize].count += 1;
 
             // Write actual space change.
-            self.contents[contents_index] = new_block_index;
+            self.contents.set(position, new_block_index);
 
             self.side_effects_of_set(new_block_index, position, contents_index);
             Ok(true)
@@ -420,7 +487,12 @@ impl Space {
         let evaluated = &self.block_data[block_index as usize].evaluated;
 
         if evaluated.attributes.tick_action.is_some() {
-            self.cubes_wanting_ticks.insert(position);
+            self.cubes_wanting_ticks
+                .insert(position, evaluated.attributes.tick_period.get());
+        }
+        if evaluated.attributes.signal != BlockSignal::None {
+            self.signal_cubes.insert(position.into());
+            self.new_signal_cubes.insert(position.into());
         }
 
         // TODO: Move this into a function in the lighting module since it is so tied to lighting
@@ -441,13 +513,13 @@ impl Space {
 
                 self.notifier.notify(SpaceChange::Lighting(position));
             } else {
-                self.light_needs_update(position, PackedLightScalar::MAX);
+                self.light_needs_update(position, LightUpdatePriority::MAX);
             }
             for face in Face6::ALL {
                 if let Some(neighbor) = point_checked_add(position, face.normal_vector()) {
                     // Perform neighbor light updates if they can be affected by us
                     if !self.get_evaluated(neighbor).opaque[face.opposite()] {
-                        self.light_needs_update(neighbor, PackedLightScalar::MAX);
+                        self.light_needs_update(neighbor, LightUpdatePriority::MAX);
                     }
                 }
             }
@@ -553,10 +625,8 @@ impl Space {
                 count: region.volume(),
                 ..new_block_data
             }];
-            for i in self.contents.iter_mut() {
-                *i = new_block_index;
-            }
-            // TODO: also need to reset lighting and activate tick_action.
+            self.contents.fill_all(new_block_index);
+            // TODO: also need to reset lighting and activate tick_action and signals.
             // And see if we can share more of the logic of this with new_from_builder().
             self.notifier.notify(SpaceChange::EveryBlock);
             Ok(())
@@ -567,6 +637,65 @@ impl Space {
         }
     }
 
+    /// Enlarges the space so that its bounds contain `bounds` as well as the current
+    /// bounds, filling the added cubes with [`AIR`].
+    ///
+    /// The existing blocks and light are kept, and listeners are notified with
+    /// [`SpaceChange::EveryBlock`]. If the space already contains `bounds`, nothing
+    /// happens. This allows a space to grow, as when more terrain is generated, without
+    /// copying it into a new space.
+    ///
+    /// Returns [`SetCubeError::OutOfBounds`] if the enlarged bounds would be too large.
+    ///
+    /// ```
+    /// use all_is_cubes::block::{AIR, Block};
+    /// use all_is_cubes::math::{GridAab, Rgba};
+    /// use all_is_cubes::space::Space;
+    ///
+    /// let mut space = Space::empty_positive(1, 1, 1);
+    /// let a_block: Block = Rgba::new(1.0, 0.0, 0.0, 1.0).into();
+    /// space.set([0, 0, 0], &a_block).unwrap();
+    ///
+    /// space.expand(GridAab::from_lower_size([-1, 0, 0], [1, 1, 1])).unwrap();
+    ///
+    /// assert_eq!(space.bounds(), GridAab::from_lower_size([-1, 0, 0], [2, 1, 1]));
+    /// assert_eq!(&space[(0, 0, 0)], &a_block);
+    /// assert_eq!(&space[(-1, 0, 0)], &AIR);
+    /// ```
+    pub fn expand(&mut self, bounds: GridAab) -> Result<(), SetCubeError> {
+        let old_bounds = self.bounds;
+        if old_bounds.contains_box(bounds) {
+            return Ok(());
+        }
+        let new_bounds = old_bounds
+            .union(bounds)
+            .map_err(|_| SetCubeError::OutOfBounds {
+                modification: bounds,
+                space_bounds: old_bounds,
+            })?;
+
+        let air_index = self.ensure_block_index(Cow::Borrowed(&AIR))?;
+        self.block_data[usize::from(air_index)].count += new_bounds.volume() - old_bounds.volume();
+        self.contents.expand(old_bounds, new_bounds, air_index);
+
+        if self.physics.light != LightPhysics::None {
+            let mut lighting = self.physics.light.initialize_lighting(new_bounds);
+            for (old_index, cube) in old_bounds.interior_iter().enumerate() {
+                lighting[new_bounds.index(cube).unwrap()] = self.lighting[old_index];
+            }
+            self.lighting = lighting;
+        }
+
+        self.bounds = new_bounds;
+        for cube in new_bounds.interior_iter() {
+            if !old_bounds.contains_cube(cube) {
+                self.light_needs_update(cube, LightUpdatePriority::MAX);
+            }
+        }
+        self.notifier.notify(SpaceChange::EveryBlock);
+        Ok(())
+    }
+
     /// Provides an [`DrawTarget`](embedded_graphics::prelude::DrawTarget)
     /// adapter for 2.5D drawing.
     ///
@@ -634,8 +763,14 @@ impl Space {
         let mut tick_txn = SpaceTransaction::default();
         // TODO: don't empty the queue until the transaction succeeds
         let cubes_to_tick = std::mem::take(&mut self.cubes_wanting_ticks);
-        let count_cubes_ticked = cubes_to_tick.len();
-        for position in cubes_to_tick {
+        let mut count_cubes_ticked = 0;
+        for (position, ticks_remaining) in cubes_to_tick {
+            if ticks_remaining > 1 {
+                self.cubes_wanting_ticks
+                    .insert(position, ticks_remaining - 1);
+                continue;
+            }
+            count_cubes_ticked += 1;
             if let Some(brush) = self.get_evaluated(position).attributes.tick_action.as_ref() {
                 // TODO: nonconserved should be at the block's choice
                 tick_txn = tick_txn
@@ -650,6 +785,17 @@ impl Space {
         //   determinism since the order is fixed
         let _ignored_failure = tick_txn.execute(self, &mut drop);
 
+        // Propagate signals and let sinks react to them.
+        let signal_txn = self.update_signals();
+        let _ignored_failure = signal_txn.execute(self, &mut drop);
+
+        if !tick.paused() {
+            self.weather = self
+                .weather
+                .step_toward(&self.target_weather, tick.delta_t());
+            self.weather_time += tick.delta_t();
+        }
+
         let cube_ticks_to_space_behaviors = Instant::now();
 
         let mut transaction = UniverseTransaction::default();
@@ -657,6 +803,7 @@ impl Space {
             if !tick.paused() {
                 transaction = self.behaviors.step(
                     &*self,
+                    self_ref,
                     &(|t: SpaceTransaction| t.bind(self_ref.clone())),
                     SpaceTransaction::behaviors,
                     tick,
@@ -694,13 +841,36 @@ impl Space {
     /// `epsilon` specifies a threshold at which to stop doing updates.
     /// Zero means to run to full completion; one is the smallest unit of light level
     /// difference; and so on.
+    ///
+    /// If the `threads` feature is enabled, large amounts of updates are computed
+    /// in parallel.
     pub fn evaluate_light(
         &mut self,
         epsilon: u8,
-        mut progress_callback: impl FnMut(LightUpdatesInfo),
+        progress_callback: impl FnMut(LightUpdatesInfo),
     ) -> usize {
+        self.evaluate_light_cancellable(epsilon, &CancellationToken::new(), progress_callback)
+            .expect("cannot be cancelled")
+    }
+
+    /// Same as [`Self::evaluate_light()`], but stops early and returns [`Cancelled`] if
+    /// `cancellation` is cancelled.
+    ///
+    /// Cancellation is checked before each batch of updates, each of which takes about
+    /// a quarter of a second. The lighting left behind is incomplete but valid, and will
+    /// continue to be updated by [`Self::step()`].
+    pub fn evaluate_light_cancellable(
+        &mut self,
+        epsilon: u8,
+        cancellation: &CancellationToken,
+        mut progress_callback: impl FnMut(LightUpdatesInfo),
+    ) -> Result<usize, Cancelled> {
         let mut total = 0;
         loop {
+            cancellation.check()?;
+            #[cfg(feature = "threads")]
+            let info = self.update_lighting_from_queue_parallel(Duration::from_secs_f32(0.25));
+            #[cfg(not(feature = "threads"))]
             let info = self.update_lighting_from_queue(Duration::from_secs_f32(0.25));
 
             progress_callback(info);
@@ -716,7 +886,7 @@ impl Space {
                 break;
             }
         }
-        total
+        Ok(total)
     }
 
     /// Returns the current [`SpacePhysics`] data, which determines global characteristics
@@ -729,7 +899,7 @@ impl Space {
     ///
     /// This may cause recomputation of lighting.
     pub fn set_physics(&mut self, physics: SpacePhysics) {
-        self.packed_sky_color = physics.sky_color.into();
+        self.packed_sky_color = physics.sky.mean().into();
         let old_physics = std::mem::replace(&mut self.physics, physics);
         if self.physics.light != old_physics.light {
             // TODO: == comparison is too broad once there are parameters -- might be a minor change of color etc.
@@ -749,6 +919,42 @@ impl Space {
         // TODO: Also send out a SpaceChange notification, if anything is different.
     }
 
+    /// Returns the current [`Weather`] in the space.
+    ///
+    /// This changes gradually toward the weather most recently given to
+    /// [`set_weather()`](Self::set_weather).
+    pub fn weather(&self) -> &Weather {
+        &self.weather
+    }
+
+    /// Returns the [`Weather`] which the current weather is changing toward.
+    pub fn target_weather(&self) -> &Weather {
+        &self.target_weather
+    }
+
+    /// Sets the [`Weather`] which the current weather will change toward over the
+    /// following steps.
+    ///
+    /// To change the weather without a transition, use
+    /// [`set_weather_immediately()`](Self::set_weather_immediately).
+    pub fn set_weather(&mut self, weather: Weather) {
+        self.target_weather = weather;
+        self.notifier.notify(SpaceChange::Weather);
+    }
+
+    /// Sets both the current and target [`Weather`].
+    pub fn set_weather_immediately(&mut self, weather: Weather) {
+        self.weather = weather;
+        self.target_weather = weather;
+        self.notifier.notify(SpaceChange::Weather);
+    }
+
+    /// Returns the precipitation particles of the current weather in the vicinity of
+    /// `center`, which is usually the viewpoint, for renderers to draw.
+    pub fn weather_particles(&self, center: Point3<FreeCoordinate>) -> WeatherParticles {
+        WeatherParticles::new(self.weather, center, self.weather_time)
+    }
+
     /// Returns the current default [`Spawn`], which determines where new [`Character`]s
     /// are placed in the space if no alternative applies.
     pub fn spawn(&self) -> &Spawn {
@@ -766,6 +972,55 @@ impl Space {
         &self.behaviors
     }
 
+    /// Returns the [`ProtectedRegion`]s which restrict characters' modifications of this
+    /// space.
+    pub fn protected_regions(&self) -> &[ProtectedRegion] {
+        &self.protected_regions
+    }
+
+    /// Adds a [`ProtectedRegion`], so that [`SpaceTransaction`]s made on behalf of
+    /// characters which it does not permit will fail.
+    ///
+    /// Regions may overlap; a modification must be permitted by every region containing
+    /// the cube it modifies.
+    pub fn add_protected_region(&mut self, region: ProtectedRegion) {
+        self.protected_regions.push(region);
+    }
+
+    /// Removes every [`ProtectedRegion`] equal to `region`, and returns whether there were
+    /// any.
+    pub fn remove_protected_region(&mut self, region: &ProtectedRegion) -> bool {
+        let count = self.protected_regions.len();
+        self.protected_regions.retain(|r| r != region);
+        self.protected_regions.len() != count
+    }
+
+    /// Returns whether blocks removed from this space with
+    /// [`Tool::RemoveBlock { keep: true }`](crate::inv::Tool::RemoveBlock) are dropped as
+    /// item [`Entity`](crate::entity::Entity)s to be picked up, rather than going
+    /// directly into the inventory of the character which removed them.
+    pub fn item_drops(&self) -> bool {
+        self.item_drops
+    }
+
+    /// Sets whether removed blocks are dropped as items, as per
+    /// [`item_drops`](Self::item_drops).
+    pub fn set_item_drops(&mut self, item_drops: bool) {
+        self.item_drops = item_drops;
+    }
+
+    /// Returns whether a modification to `cube` by `actor` is allowed by all of this
+    /// space's [`ProtectedRegion`]s.
+    pub(crate) fn permits_modification(
+        &self,
+        cube: GridPoint,
+        actor: Option<&URef<Character>>,
+    ) -> bool {
+        self.protected_regions
+            .iter()
+            .all(|region| region.permits(cube, actor))
+    }
+
     /// Finds or assigns an index to denote the block.
     ///
     /// The caller is responsible for incrementing `self.block_data[index].count`.
@@ -820,8 +1075,8 @@ impl Space {
         let mut problems = Vec::new();
 
         let mut actual_counts: HashMap<BlockIndex, usize> = HashMap::new();
-        for index in self.contents.iter().copied() {
-            *actual_counts.entry(index).or_insert(0) += 1;
+        for cube in self.bounds.interior_iter() {
+            *actual_counts.entry(self.contents.get(cube)).or_insert(0) += 1;
         }
 
         // Check that block_data has only correct counts.
@@ -894,8 +1149,9 @@ impl<T: Into<GridPoint>> std::ops::Index<T> for Space {
     /// use [`Space::set`] or [`Space::fill`] to modify blocks.
     #[inline(always)]
     fn index(&self, position: T) -> &Self::Output {
-        if let Some(index) = self.bounds.index(position) {
-            &self.block_data[self.contents[index] as usize].block
+        let position = position.into();
+        if self.bounds.contains_cube(position) {
+            &self.block_data[self.contents.get(position) as usize].block
         } else {
             &AIR
         }
@@ -915,9 +1171,17 @@ impl VisitRefs for Space {
             light_cost_scale: _,
             physics: _,
             packed_sky_color: _,
+            weather: _,
+            target_weather: _,
+            weather_time: _,
             behaviors,
             spawn,
+            protected_regions,
+            item_drops: _,
             cubes_wanting_ticks: _,
+            signal_cubes: _,
+            new_signal_cubes: _,
+            powered_cubes: _,
             notifier: _,
             todo: _,
         } = self;
@@ -926,6 +1190,9 @@ impl VisitRefs for Space {
         }
         behaviors.visit_refs(visitor);
         spawn.visit_refs(visitor);
+        for region in protected_regions {
+            region.visit_refs(visitor);
+        }
     }
 }
 
@@ -1048,12 +1315,17 @@ pub struct SpacePhysics {
     /// TODO: Expand this to an enum which allows non-uniform gravity patterns.
     pub gravity: Vector3<NotNan<FreeCoordinate>>,
 
-    /// Color of light arriving from outside the space, used for light calculation
-    /// and rendering.
-    ///
-    /// TODO: Consider replacing this with some sort of cube map, spherical harmonics,
-    /// or some such to allow for non-uniform illumination.
-    pub sky_color: Rgb,
+    /// Rate, per second, at which moving objects lose velocity to the air (or water, or
+    /// whatever else the space is filled with). Zero is no drag.
+    pub air_drag: NotNan<FreeCoordinate>,
+
+    /// Maximum speed, in cubes/s, at which objects can fall in the direction of
+    /// [`gravity`](Self::gravity), or [`None`] for no limit.
+    pub terminal_velocity: Option<NotNan<FreeCoordinate>>,
+
+    /// What is seen beyond the bounds of the space, which is also the color of light
+    /// arriving from outside the space, used for light calculation and rendering.
+    pub sky: Sky,
 
     /// Method used to compute the illumination of individual blocks.
     pub light: LightPhysics,
@@ -1062,7 +1334,9 @@ pub struct SpacePhysics {
 impl SpacePhysics {
     pub(crate) const DEFAULT: Self = Self {
         gravity: Vector3::new(notnan!(0.), notnan!(-20.), notnan!(0.)),
-        sky_color: palette::DAY_SKY_COLOR,
+        air_drag: notnan!(0.),
+        terminal_velocity: None,
+        sky: Sky::Uniform(palette::DAY_SKY_COLOR),
         light: LightPhysics::DEFAULT,
     };
 
@@ -1070,7 +1344,9 @@ impl SpacePhysics {
     /// In particular, disables light since it will not be used.
     pub const DEFAULT_FOR_BLOCK: Self = Self {
         gravity: Vector3::new(notnan!(0.), notnan!(0.), notnan!(0.)),
-        sky_color: rgb_const!(0.5, 0.5, 0.5),
+        air_drag: notnan!(0.),
+        terminal_velocity: None,
+        sky: Sky::Uniform(rgb_const!(0.5, 0.5, 0.5)),
         light: LightPhysics::None,
     };
 }
@@ -1079,7 +1355,9 @@ impl fmt::Debug for SpacePhysics {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         let Self {
             gravity,
-            sky_color,
+            air_drag,
+            terminal_velocity,
+            sky,
             light,
         } = self;
         f.debug_struct("SpacePhysics")
@@ -1087,7 +1365,12 @@ impl fmt::Debug for SpacePhysics {
                 "gravity",
                 &gravity.map(NotNan::into_inner).custom_format(ConciseDebug),
             )
-            .field("sky_color", &sky_color)
+            .field("air_drag", &air_drag.into_inner())
+            .field(
+                "terminal_velocity",
+                &terminal_velocity.map(NotNan::into_inner),
+            )
+            .field("sky", &sky)
             .field("light", &light)
             .finish()
     }
@@ -1105,7 +1388,9 @@ impl<'a> arbitrary::Arbitrary<'a> for SpacePhysics {
     fn arbitrary(u: &mut arbitrary::Unstructured<'a>) -> arbitrary::Result<Self> {
         Ok(Self {
             gravity: Vector3::new(u.arbitrary()?, u.arbitrary()?, u.arbitrary()?),
-            sky_color: u.arbitrary()?,
+            air_drag: u.arbitrary()?,
+            terminal_velocity: u.arbitrary()?,
+            sky: u.arbitrary()?,
             light: u.arbitrary()?,
         })
     }
@@ -1115,7 +1400,9 @@ impl<'a> arbitrary::Arbitrary<'a> for SpacePhysics {
         and_all(&[
             <f64 as Arbitrary>::size_hint(depth),
             <f64 as Arbitrary>::size_hint(depth),
-            <Rgb as Arbitrary>::size_hint(depth),
+            <f64 as Arbitrary>::size_hint(depth),
+            <Option<f64> as Arbitrary>::size_hint(depth),
+            <Sky as Arbitrary>::size_hint(depth),
             <LightPhysics as Arbitrary>::size_hint(depth),
         ])
     }
@@ -1127,7 +1414,7 @@ impl<'a> arbitrary::Arbitrary<'a> for SpacePhysics {
 #[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 pub enum LightPhysics {
     /// No light. All surface colors are taken exactly as displayed colors. The
-    /// [`SpacePhysics::sky_color`] is used solely as a background color.
+    /// [`SpacePhysics::sky`] is used solely as a background.
     None,
     /// Raycast-based light propagation and diffuse reflections.
     ///
@@ -1193,6 +1480,12 @@ pub enum SpaceChange {
     /// Equivalent to [`SpaceChange::Block`] for every cube and [`SpaceChange::Number`]
     /// for every index.
     EveryBlock,
+    /// The weather was changed by [`Space::set_weather()`] or
+    /// [`Space::set_weather_immediately()`].
+    ///
+    /// This is not sent for the gradual changes of [`Space::weather()`] toward
+    /// [`Space::target_weather()`] which happen in each step.
+    Weather,
 }
 
 /// Performance data returned by [`Space::step`]. The exact contents of this structure
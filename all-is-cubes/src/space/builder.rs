@@ -3,7 +3,7 @@ use cgmath::{EuclideanSpace, InnerSpace, Point3};
 use crate::block::{Block, AIR};
 use crate::character::Spawn;
 use crate::math::{FreeCoordinate, Rgb};
-use crate::space::{GridAab, LightPhysics, Space, SpacePhysics};
+use crate::space::{GridAab, LightPhysics, Sky, Space, SpacePhysics, Weather};
 
 /// Tool for constructing new [`Space`]s.
 ///
@@ -20,7 +20,9 @@ pub struct SpaceBuilder<B> {
     pub(super) bounds: B,
     pub(super) spawn: Option<Spawn>,
     pub(super) physics: SpacePhysics,
+    pub(super) weather: Weather,
     pub(super) initial_fill: Block,
+    pub(super) item_drops: bool,
 }
 
 impl<B> SpaceBuilder<B> {
@@ -40,12 +42,18 @@ impl<B> SpaceBuilder<B> {
         self
     }
 
-    /// Sets the value of [`SpacePhysics::sky_color`] for the space.
-    pub fn sky_color(mut self, color: Rgb) -> Self {
-        self.physics.sky_color = color;
+    /// Sets the value of [`SpacePhysics::sky`] for the space.
+    pub fn sky(mut self, sky: impl Into<Sky>) -> Self {
+        self.physics.sky = sky.into();
         self
     }
 
+    /// Sets the value of [`SpacePhysics::sky`] for the space to the given color in every
+    /// direction.
+    pub fn sky_color(self, color: Rgb) -> Self {
+        self.sky(Sky::Uniform(color))
+    }
+
     /// Sets the value of [`SpacePhysics::light`] for the space, which determines the
     /// behavior of light within the space.
     pub fn light_physics(mut self, light_physics: LightPhysics) -> Self {
@@ -53,6 +61,15 @@ impl<B> SpaceBuilder<B> {
         self
     }
 
+    /// Sets the initial value for [`Space::weather`], which determines precipitation
+    /// and fog.
+    ///
+    /// If not set, the default is [`Weather::CLEAR`].
+    pub fn weather(mut self, weather: Weather) -> Self {
+        self.weather = weather;
+        self
+    }
+
     /// Sets the value for [`Space::spawn`], which determines the default circumstances of
     /// new characters.
     ///
@@ -62,6 +79,15 @@ impl<B> SpaceBuilder<B> {
         self.spawn = Some(spawn);
         self
     }
+
+    /// Sets the value for [`Space::item_drops`], which determines whether removed
+    /// blocks are dropped as items to be picked up.
+    ///
+    /// If not set, the default is `false`.
+    pub fn item_drops(mut self, item_drops: bool) -> Self {
+        self.item_drops = item_drops;
+        self
+    }
 }
 
 impl<B: SpaceBuilderBounds> SpaceBuilder<B> {
@@ -79,7 +105,9 @@ impl SpaceBuilder<()> {
             bounds: (),
             spawn: None,
             physics: SpacePhysics::DEFAULT,
+            weather: Weather::CLEAR,
             initial_fill: AIR,
+            item_drops: false,
         }
     }
 
@@ -89,7 +117,9 @@ impl SpaceBuilder<()> {
             bounds,
             spawn: self.spawn,
             physics: self.physics,
+            weather: self.weather,
             initial_fill: self.initial_fill,
+            item_drops: self.item_drops,
         }
     }
 }
@@ -179,6 +209,7 @@ impl<'a> arbitrary::Arbitrary<'a> for Space {
         let bounds = GridAab::arbitrary_with_max_volume(u, 2048)?;
         let mut space = Space::builder(bounds)
             .physics(u.arbitrary()?)
+            .weather(u.arbitrary()?)
             .spawn(u.arbitrary()?)
             .build();
 
@@ -0,0 +1,369 @@
+//! [`SpaceContents`], the storage of which block is in each cube of a [`Space`].
+
+use std::sync::Arc;
+
+use crate::math::{GridAab, GridArray, GridCoordinate, GridPoint};
+use crate::space::BlockIndex;
+
+#[cfg(doc)]
+use crate::space::Space;
+
+/// Side length of the cubical chunks in which [`SpaceContents`] stores blocks.
+const CHUNK_SIZE: GridCoordinate = 16;
+/// Number of cubes in a chunk.
+const CHUNK_VOLUME: usize = (CHUNK_SIZE * CHUNK_SIZE * CHUNK_SIZE) as usize;
+/// Maximum number of palette entries in a [`Chunk::Paletted`].
+const MAX_PALETTE_LEN: usize = 1 << u8::BITS;
+
+/// The block indices of every cube of a [`Space`].
+///
+/// They are stored in chunks, each of which uses the smallest representation which
+/// fits its contents: a single index if the entire chunk is the same block, or else
+/// a palette of the block indices it uses. This saves memory in spaces which are
+/// mostly air or mostly one material.
+///
+/// Chunks are reference-counted and copied on write, so that identical chunks
+/// (in particular, those of a freshly created or filled space) may share memory.
+#[derive(Clone, Debug)]
+pub(crate) struct SpaceContents {
+    /// Indexed by chunk coordinates, i.e. cube coordinates divided by [`CHUNK_SIZE`].
+    /// Chunks may extend past the bounds of the space; the values of cubes outside
+    /// the bounds are unspecified.
+    chunks: GridArray<Arc<Chunk>>,
+}
+
+#[derive(Clone, Debug)]
+enum Chunk {
+    /// Every cube contains the same block.
+    Uniform(BlockIndex),
+    /// Each cube contains an index into `palette`.
+    ///
+    /// `palette` may contain entries which are no longer used by any cube; those are
+    /// the entries whose count in `uses` is zero, and they are reused before the
+    /// palette is grown.
+    Paletted {
+        palette: Vec<BlockIndex>,
+        /// Number of cubes containing each palette index.
+        uses: Vec<u16>,
+        cubes: Box<[u8]>,
+    },
+    /// Each cube contains a block index, because there are too many distinct blocks for
+    /// a palette.
+    Direct(Box<[BlockIndex]>),
+}
+
+impl SpaceContents {
+    /// Constructs contents for the given bounds, in which every cube has the block
+    /// index `fill`.
+    pub fn new(bounds: GridAab, fill: BlockIndex) -> Self {
+        let chunk = Arc::new(Chunk::Uniform(fill));
+        Self {
+            chunks: GridArray::from_fn(bounds.divide(CHUNK_SIZE), |_| chunk.clone()),
+        }
+    }
+
+    /// Returns the block index at `cube`.
+    ///
+    /// Panics if `cube` is outside of the bounds the contents were created with.
+    #[inline]
+    pub fn get(&self, cube: GridPoint) -> BlockIndex {
+        self.chunks[chunk_of(cube)].get(index_within_chunk(cube))
+    }
+
+    /// Sets the block index at `cube`.
+    ///
+    /// Panics if `cube` is outside of the bounds the contents were created with.
+    pub fn set(&mut self, cube: GridPoint, value: BlockIndex) {
+        let chunk = &mut self.chunks[chunk_of(cube)];
+        if chunk.get(index_within_chunk(cube)) != value {
+            Arc::make_mut(chunk).set(index_within_chunk(cube), value);
+        }
+    }
+
+    /// Sets every cube to the block index `value`.
+    pub fn fill_all(&mut self, value: BlockIndex) {
+        *self = Self::new(self.chunks.bounds().multiply(CHUNK_SIZE), value);
+    }
+
+    /// Changes the contents from covering `old_bounds` to covering `new_bounds`, which
+    /// must contain `old_bounds`. Cubes within `old_bounds` keep their block indices, and
+    /// the rest have the block index `fill`.
+    pub fn expand(&mut self, old_bounds: GridAab, new_bounds: GridAab, fill: BlockIndex) {
+        let fill_chunk = Arc::new(Chunk::Uniform(fill));
+        let mut expanded = Self {
+            chunks: GridArray::from_fn(new_bounds.divide(CHUNK_SIZE), |chunk| {
+                if old_bounds.contains_box(chunk_bounds(chunk)) {
+                    self.chunks[chunk].clone()
+                } else {
+                    fill_chunk.clone()
+                }
+            }),
+        };
+        // Chunks which were only partly within the old bounds are copied cube by cube,
+        // since their other cubes had unspecified values.
+        for chunk in old_bounds.divide(CHUNK_SIZE).interior_iter() {
+            let chunk_bounds = chunk_bounds(chunk);
+            if old_bounds.contains_box(chunk_bounds) {
+                continue;
+            }
+            if let Some(part) = chunk_bounds.intersection(old_bounds) {
+                for cube in part.interior_iter() {
+                    expanded.set(cube, self.get(cube));
+                }
+            }
+        }
+        *self = expanded;
+    }
+
+    /// Divides `region` into its intersections with each chunk, and returns each part
+    /// along with what is known about its contents without examining each cube.
+    /// Empty parts are omitted.
+    ///
+    /// Panics if `region` is not within the bounds the contents were created with.
+    pub fn parts(&self, region: GridAab) -> impl Iterator<Item = (GridAab, PartSummary<'_>)> {
+        region
+            .divide(CHUNK_SIZE)
+            .interior_iter()
+            .filter_map(move |chunk| {
+                let part = chunk_bounds(chunk).intersection(region)?;
+                if part.is_empty() {
+                    return None;
+                }
+                let summary = match &*self.chunks[chunk] {
+                    &Chunk::Uniform(value) => PartSummary::Uniform(value),
+                    Chunk::Paletted { palette, .. } => PartSummary::OneOf(palette),
+                    Chunk::Direct(_) => PartSummary::Unknown,
+                };
+                Some((part, summary))
+            })
+    }
+}
+
+/// What is known about the block indices in a part of a [`SpaceContents`], as returned
+/// by [`SpaceContents::parts()`].
+#[derive(Clone, Copy, Debug)]
+pub(crate) enum PartSummary<'a> {
+    /// Every cube has this block index.
+    Uniform(BlockIndex),
+    /// Every cube has one of these block indices, but not necessarily every one of them
+    /// is used.
+    OneOf(&'a [BlockIndex]),
+    /// Any block index may be present.
+    Unknown,
+}
+
+impl PartSummary<'_> {
+    /// Returns whether any cube in the part might have a block index for which
+    /// `matches` is true.
+    pub fn may_contain(&self, matches: impl Fn(BlockIndex) -> bool) -> bool {
+        match *self {
+            PartSummary::Uniform(value) => matches(value),
+            PartSummary::OneOf(palette) => palette.iter().any(|&value| matches(value)),
+            PartSummary::Unknown => true,
+        }
+    }
+}
+
+impl Chunk {
+    #[inline]
+    fn get(&self, index: usize) -> BlockIndex {
+        match self {
+            &Chunk::Uniform(value) => value,
+            Chunk::Paletted { palette, cubes, .. } => palette[usize::from(cubes[index])],
+            Chunk::Direct(cubes) => cubes[index],
+        }
+    }
+
+    fn set(&mut self, index: usize, value: BlockIndex) {
+        match self {
+            &mut Chunk::Uniform(old_value) => {
+                if old_value != value {
+                    let mut cubes = vec![0; CHUNK_VOLUME].into_boxed_slice();
+                    cubes[index] = 1;
+                    *self = Chunk::Paletted {
+                        palette: vec![old_value, value],
+                        uses: vec![(CHUNK_VOLUME - 1) as u16, 1],
+                        cubes,
+                    };
+                }
+            }
+            Chunk::Paletted {
+                palette,
+                uses,
+                cubes,
+            } => {
+                let new_index = if let Some(i) = palette.iter().position(|&v| v == value) {
+                    i
+                } else if let Some(i) = uses.iter().position(|&n| n == 0) {
+                    palette[i] = value;
+                    i
+                } else if palette.len() < MAX_PALETTE_LEN {
+                    palette.push(value);
+                    uses.push(0);
+                    palette.len() - 1
+                } else {
+                    // Every entry is in use, so there is no room for another.
+                    *self = Chunk::Direct(cubes.iter().map(|&c| palette[usize::from(c)]).collect());
+                    return self.set(index, value);
+                };
+                uses[usize::from(cubes[index])] -= 1;
+                uses[new_index] += 1;
+                cubes[index] = new_index as u8;
+            }
+            Chunk::Direct(cubes) => cubes[index] = value,
+        }
+    }
+}
+
+/// Returns the coordinates of the chunk containing `cube`.
+#[inline]
+fn chunk_of(cube: GridPoint) -> GridPoint {
+    cube.map(|c| c.div_euclid(CHUNK_SIZE))
+}
+
+/// Returns the cubes making up the chunk with coordinates `chunk`.
+fn chunk_bounds(chunk: GridPoint) -> GridAab {
+    GridAab::from_lower_size(chunk * CHUNK_SIZE, [CHUNK_SIZE; 3])
+}
+
+/// Returns the index of `cube` within the data of the chunk containing it.
+#[inline]
+fn index_within_chunk(cube: GridPoint) -> usize {
+    let p = cube.map(|c| c.rem_euclid(CHUNK_SIZE) as usize);
+    let size = CHUNK_SIZE as usize;
+    (p.x * size + p.y) * size + p.z
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_bounds() -> GridAab {
+        GridAab::from_lower_size([-20, 0, 5], [40, 3, 17])
+    }
+
+    #[test]
+    fn new_shares_chunks() {
+        let contents = SpaceContents::new(test_bounds(), 7);
+        let first = contents.chunks.get([-2, 0, 0]).unwrap();
+        assert!(contents
+            .chunks
+            .bounds()
+            .interior_iter()
+            .all(|c| Arc::ptr_eq(first, &contents.chunks[c])));
+        for cube in test_bounds().interior_iter() {
+            assert_eq!(contents.get(cube), 7);
+        }
+    }
+
+    #[test]
+    fn set_and_get() {
+        let bounds = test_bounds();
+        let mut contents = SpaceContents::new(bounds, 0);
+        for (i, cube) in bounds.interior_iter().enumerate() {
+            contents.set(cube, (i % 3) as BlockIndex);
+        }
+        for (i, cube) in bounds.interior_iter().enumerate() {
+            assert_eq!(contents.get(cube), (i % 3) as BlockIndex, "{cube:?}");
+        }
+    }
+
+    /// Exercise reuse of palette entries and conversion to [`Chunk::Direct`].
+    #[test]
+    fn many_distinct_values() {
+        let bounds = GridAab::from_lower_size([0, 0, 0], [16, 16, 16]);
+        let mut contents = SpaceContents::new(bounds, 0);
+        // More than MAX_PALETTE_LEN distinct values over time, but not at once.
+        for round in 0..3 {
+            for (i, cube) in bounds.interior_iter().enumerate().take(200) {
+                contents.set(cube, (i + round * 200) as BlockIndex);
+            }
+        }
+        assert!(matches!(
+            *contents.chunks[[0, 0, 0]],
+            Chunk::Paletted { .. }
+        ));
+        // More distinct values than fit in a palette.
+        for (i, cube) in bounds.interior_iter().enumerate() {
+            contents.set(cube, i as BlockIndex);
+        }
+        assert!(matches!(*contents.chunks[[0, 0, 0]], Chunk::Direct(_)));
+        for (i, cube) in bounds.interior_iter().enumerate() {
+            assert_eq!(contents.get(cube), i as BlockIndex);
+        }
+    }
+
+    /// Repeatedly changing one cube does not grow the palette.
+    #[test]
+    fn palette_entries_reused() {
+        let bounds = GridAab::from_lower_size([0, 0, 0], [16, 16, 16]);
+        let mut contents = SpaceContents::new(bounds, 0);
+        for value in 1..1000 {
+            contents.set(GridPoint::new(1, 2, 3), value);
+        }
+        match &*contents.chunks[[0, 0, 0]] {
+            Chunk::Paletted { palette, uses, .. } => {
+                // The original value, the current value, and one unused entry.
+                assert_eq!(palette.len(), 3);
+                assert_eq!(uses.iter().filter(|&&n| n == 0).count(), 1);
+            }
+            chunk => panic!("unexpected chunk {chunk:?}"),
+        }
+        assert_eq!(contents.get(GridPoint::new(1, 2, 3)), 999);
+    }
+
+    #[test]
+    fn set_copies_on_write() {
+        let bounds = test_bounds();
+        let mut contents = SpaceContents::new(bounds, 0);
+        let original = contents.clone();
+        contents.set(GridPoint::new(0, 0, 5), 1);
+        assert_eq!(contents.get(GridPoint::new(0, 0, 5)), 1);
+        assert_eq!(original.get(GridPoint::new(0, 0, 5)), 0);
+    }
+
+    #[test]
+    fn expand_keeps_contents() {
+        let old_bounds = test_bounds();
+        let new_bounds = GridAab::from_lower_size([-40, -5, 0], [100, 10, 30]);
+        let mut contents = SpaceContents::new(old_bounds, 0);
+        for (i, cube) in old_bounds.interior_iter().enumerate() {
+            contents.set(cube, (i % 3) as BlockIndex);
+        }
+        contents.expand(old_bounds, new_bounds, 9);
+        let mut old_cubes = old_bounds.interior_iter().enumerate();
+        for cube in new_bounds.interior_iter() {
+            if old_bounds.contains_cube(cube) {
+                let (i, old_cube) = old_cubes.next().unwrap();
+                assert_eq!(old_cube, cube);
+                assert_eq!(contents.get(cube), (i % 3) as BlockIndex, "{cube:?}");
+            } else {
+                assert_eq!(contents.get(cube), 9, "{cube:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn parts_cover_region() {
+        let bounds = test_bounds();
+        let mut contents = SpaceContents::new(bounds, 0);
+        contents.set(GridPoint::new(-20, 0, 5), 1);
+        let region = GridAab::from_lower_size([-20, 0, 5], [30, 2, 2]);
+
+        let parts: Vec<(GridAab, PartSummary<'_>)> = contents.parts(region).collect();
+        assert_eq!(
+            parts.iter().map(|(part, _)| part.volume()).sum::<usize>(),
+            region.volume()
+        );
+        for (part, summary) in parts {
+            assert!(region.contains_box(part));
+            if part.contains_cube([-20, 0, 5]) {
+                assert!(matches!(summary, PartSummary::OneOf(&[0, 1])));
+                assert!(summary.may_contain(|value| value == 1));
+            } else {
+                assert!(matches!(summary, PartSummary::Uniform(0)));
+                assert!(!summary.may_contain(|value| value == 1));
+            }
+        }
+    }
+}
@@ -0,0 +1,260 @@
+//! [`InterestArea`]: which parts of a [`Space`] are near enough to a viewer to be sent
+//! to them.
+
+use std::collections::HashSet;
+
+use cgmath::Point3;
+
+use crate::chunking::{cube_to_chunk, point_to_chunk, ChunkChart, ChunkPos, OctantMask};
+use crate::math::{FreeCoordinate, GridAab, GridCoordinate, GridPoint};
+use crate::space::{Space, SpaceChange};
+
+/// Tracks the chunks of a [`Space`] which are within a given radius of a viewer, such as
+/// a client's camera, so that a server may send that viewer only the changes and
+/// entity updates it can see, keeping bandwidth bounded however large the space is.
+///
+/// The viewer should be told, using the [`InterestChange`]s returned by
+/// [`set_position()`](Self::set_position) and [`set_radius()`](Self::set_radius), when a
+/// chunk enters the area (so that the viewer can be sent its current contents) and when
+/// a chunk leaves it (so that the viewer can discard it); between those notifications,
+/// [`SpaceChange`]s should be sent only if [`is_relevant()`](Self::is_relevant).
+///
+/// `CHUNK_SIZE` is the edge length, in cubes, of the chunks in which the space is sent.
+#[derive(Clone, Debug)]
+pub struct InterestArea<const CHUNK_SIZE: GridCoordinate> {
+    /// Bounds of the space; chunks not intersecting them are never of interest.
+    space_bounds: GridAab,
+    chart: ChunkChart<CHUNK_SIZE>,
+    radius: FreeCoordinate,
+    /// Chunk containing the viewer, or [`None`] if no position has been set yet.
+    center: Option<ChunkPos<CHUNK_SIZE>>,
+    /// Chunks currently of interest.
+    chunks: HashSet<ChunkPos<CHUNK_SIZE>>,
+}
+
+/// A chunk entering or leaving an [`InterestArea`], as returned by
+/// [`InterestArea::set_position()`] and [`InterestArea::set_radius()`].
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+#[allow(clippy::exhaustive_enums)]
+pub enum InterestChange<const CHUNK_SIZE: GridCoordinate> {
+    /// The chunk is now of interest; the viewer should be sent its entire contents.
+    Enter(ChunkPos<CHUNK_SIZE>),
+    /// The chunk is no longer of interest; the viewer may forget its contents.
+    Leave(ChunkPos<CHUNK_SIZE>),
+}
+
+impl<const CHUNK_SIZE: GridCoordinate> InterestArea<CHUNK_SIZE> {
+    /// Constructs an [`InterestArea`] for a space with the given bounds, containing the
+    /// chunks within `radius` (in cubes) of the viewer.
+    ///
+    /// The area is empty until [`set_position()`](Self::set_position) is called.
+    pub fn new(space_bounds: GridAab, radius: FreeCoordinate) -> Self {
+        Self {
+            space_bounds,
+            chart: ChunkChart::new(radius),
+            radius,
+            center: None,
+            chunks: HashSet::new(),
+        }
+    }
+
+    /// Constructs an [`InterestArea`] for `space`; see [`InterestArea::new()`].
+    pub fn for_space(space: &Space, radius: FreeCoordinate) -> Self {
+        Self::new(space.bounds(), radius)
+    }
+
+    /// Returns the radius of the area, in cubes.
+    pub fn radius(&self) -> FreeCoordinate {
+        self.radius
+    }
+
+    /// Changes the radius of the area, returning the chunks which entered or left it.
+    #[must_use = "the viewer should be informed of chunks entering and leaving"]
+    pub fn set_radius(&mut self, radius: FreeCoordinate) -> Vec<InterestChange<CHUNK_SIZE>> {
+        self.radius = radius;
+        self.chart.resize_if_needed(radius);
+        self.recompute()
+    }
+
+    /// Moves the center of the area to the viewer's new position, returning the chunks
+    /// which entered or left it.
+    ///
+    /// Chunks which left are listed first; chunks which entered are listed in order
+    /// from nearest to farthest, which is a good order in which to send them.
+    #[must_use = "the viewer should be informed of chunks entering and leaving"]
+    pub fn set_position(
+        &mut self,
+        position: Point3<FreeCoordinate>,
+    ) -> Vec<InterestChange<CHUNK_SIZE>> {
+        let center = point_to_chunk(position);
+        if self.center == Some(center) {
+            return Vec::new();
+        }
+        self.center = Some(center);
+        self.recompute()
+    }
+
+    /// Returns the chunks currently of interest, in no particular order.
+    pub fn chunks(&self) -> impl Iterator<Item = ChunkPos<CHUNK_SIZE>> + '_ {
+        self.chunks.iter().copied()
+    }
+
+    /// Returns whether the given cube is in a chunk of interest.
+    pub fn contains_cube(&self, cube: impl Into<GridPoint>) -> bool {
+        self.chunks.contains(&cube_to_chunk(cube.into()))
+    }
+
+    /// Returns whether the given point, such as the position of a character or other
+    /// entity, is in a chunk of interest.
+    pub fn contains_point(&self, point: Point3<FreeCoordinate>) -> bool {
+        self.chunks.contains(&point_to_chunk(point))
+    }
+
+    /// Returns whether the viewer should be sent `change`.
+    ///
+    /// Changes to individual cubes are relevant only if the cube is of interest;
+    /// changes to the block palette or weather of the space are always relevant.
+    pub fn is_relevant(&self, change: &SpaceChange) -> bool {
+        match *change {
+            SpaceChange::Block(cube) | SpaceChange::Lighting(cube) => self.contains_cube(cube),
+            SpaceChange::Number(_)
+            | SpaceChange::BlockValue(_)
+            | SpaceChange::EveryBlock
+            | SpaceChange::Weather => true,
+        }
+    }
+
+    /// Recomputes [`Self::chunks`] from the center and chart, and returns the difference.
+    fn recompute(&mut self) -> Vec<InterestChange<CHUNK_SIZE>> {
+        let new_chunks: Vec<ChunkPos<CHUNK_SIZE>> = match self.center {
+            Some(center) => self
+                .chart
+                .chunks(center, OctantMask::ALL)
+                .filter(|chunk| chunk.bounds().intersection(self.space_bounds).is_some())
+                .collect(),
+            None => Vec::new(),
+        };
+        let new_set: HashSet<ChunkPos<CHUNK_SIZE>> = new_chunks.iter().copied().collect();
+
+        let mut changes: Vec<InterestChange<CHUNK_SIZE>> = self
+            .chunks
+            .difference(&new_set)
+            .map(|&chunk| InterestChange::Leave(chunk))
+            .collect();
+        changes.extend(
+            new_chunks
+                .into_iter()
+                .filter(|chunk| !self.chunks.contains(chunk))
+                .map(InterestChange::Enter),
+        );
+        self.chunks = new_set;
+        changes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    type Area = InterestArea<16>;
+
+    fn entered(changes: &[InterestChange<16>]) -> HashSet<ChunkPos<16>> {
+        changes
+            .iter()
+            .filter_map(|change| match *change {
+                InterestChange::Enter(chunk) => Some(chunk),
+                InterestChange::Leave(_) => None,
+            })
+            .collect()
+    }
+
+    fn left(changes: &[InterestChange<16>]) -> HashSet<ChunkPos<16>> {
+        changes
+            .iter()
+            .filter_map(|change| match *change {
+                InterestChange::Leave(chunk) => Some(chunk),
+                InterestChange::Enter(_) => None,
+            })
+            .collect()
+    }
+
+    /// A space one chunk tall and deep, and many chunks wide.
+    fn wide_bounds() -> GridAab {
+        GridAab::from_lower_size([0, 0, 0], [16 * 20, 16, 16])
+    }
+
+    #[test]
+    fn empty_until_positioned() {
+        let area = Area::new(wide_bounds(), 20.);
+        assert_eq!(area.chunks().count(), 0);
+        assert!(!area.contains_cube([0, 0, 0]));
+    }
+
+    #[test]
+    fn enter_is_limited_to_radius_and_bounds() {
+        let mut area = Area::new(wide_bounds(), 20.);
+        let changes = area.set_position(Point3::new(8., 8., 8.));
+        // Within 20 cubes of some point in chunk 0 are chunks 0, 1, and 2, but not 3, nor
+        // any chunks outside the space.
+        assert_eq!(
+            entered(&changes),
+            HashSet::from([
+                ChunkPos::new(0, 0, 0),
+                ChunkPos::new(1, 0, 0),
+                ChunkPos::new(2, 0, 0)
+            ])
+        );
+        assert_eq!(changes[0], InterestChange::Enter(ChunkPos::new(0, 0, 0)));
+        assert!(area.contains_cube([47, 0, 0]));
+        assert!(!area.contains_cube([48, 0, 0]));
+    }
+
+    #[test]
+    fn moving_reports_enter_and_leave() {
+        let mut area = Area::new(wide_bounds(), 20.);
+        let _ = area.set_position(Point3::new(8., 8., 8.));
+        let changes = area.set_position(Point3::new(16. * 10. + 8., 8., 8.));
+        assert_eq!(
+            left(&changes),
+            HashSet::from([
+                ChunkPos::new(0, 0, 0),
+                ChunkPos::new(1, 0, 0),
+                ChunkPos::new(2, 0, 0)
+            ])
+        );
+        assert_eq!(entered(&changes).len(), 5);
+        assert!(matches!(changes[0], InterestChange::Leave(_)));
+
+        // Moving within the same chunk changes nothing.
+        assert_eq!(
+            area.set_position(Point3::new(16. * 10. + 1., 8., 8.)),
+            vec![]
+        );
+    }
+
+    #[test]
+    fn shrinking_radius_reports_leave() {
+        let mut area = Area::new(wide_bounds(), 20.);
+        let _ = area.set_position(Point3::new(16. * 10. + 8., 8., 8.));
+        let changes = area.set_radius(0.);
+        assert_eq!(entered(&changes), HashSet::new());
+        assert_eq!(
+            left(&changes),
+            HashSet::from([ChunkPos::new(8, 0, 0), ChunkPos::new(12, 0, 0)])
+        );
+        assert_eq!(area.radius(), 0.);
+    }
+
+    #[test]
+    fn relevance_of_changes() {
+        let mut area = Area::new(wide_bounds(), 20.);
+        let _ = area.set_position(Point3::new(8., 8., 8.));
+        assert!(area.is_relevant(&SpaceChange::Block(GridPoint::new(40, 0, 0))));
+        assert!(!area.is_relevant(&SpaceChange::Block(GridPoint::new(100, 0, 0))));
+        assert!(!area.is_relevant(&SpaceChange::Lighting(GridPoint::new(100, 0, 0))));
+        assert!(area.is_relevant(&SpaceChange::Number(0)));
+        assert!(area.is_relevant(&SpaceChange::EveryBlock));
+        assert!(area.contains_point(Point3::new(47.5, 3., 3.)));
+        assert!(!area.contains_point(Point3::new(48.5, 3., 3.)));
+    }
+}
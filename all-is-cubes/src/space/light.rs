@@ -1,6 +1,6 @@
 mod data;
 pub use data::PackedLight;
-pub(crate) use data::{LightUpdateQueue, LightUpdateRequest, PackedLightScalar};
+pub(crate) use data::{LightUpdatePriority, LightUpdateQueue, LightUpdateRequest};
 
 mod debug;
 #[doc(hidden)] // pub only for visualization by all-is-cubes-gpu
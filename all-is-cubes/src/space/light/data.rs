@@ -9,8 +9,12 @@ use cgmath::{Vector3, Vector4};
 use crate::math::*;
 use crate::space::*;
 
-/// One component of a `PackedLight`.
-pub(crate) type PackedLightScalar = u8;
+/// One component of a `PackedLight`, in a logarithmic encoding.
+pub(crate) type PackedLightScalar = u16;
+
+/// Priority of a [`LightUpdateRequest`], derived from
+/// [`PackedLight::difference_priority()`].
+pub(crate) type LightUpdatePriority = u8;
 
 /// Special reasons for a cube having zero light in it.
 /// These may be used to help compute smoothed lighting across blocks.
@@ -38,7 +42,7 @@ pub(crate) enum LightStatus {
 pub struct PackedLight {
     // LightStatus being other than Visible is mutually exclusive with value being nonzero,
     // so we could in theory make this an enum, but that wouldn't actually compact the
-    // representation, and this representation maps to 8-bit-per-component RGBA which is
+    // representation, and this representation maps to 16-bit-per-component RGBA which is
     // what the shader expects.
     value: Vector3<PackedLightScalar>,
     status: LightStatus,
@@ -49,8 +53,13 @@ pub struct PackedLight {
 // representation, or possibly something that GPUs specifically do well with.
 
 impl PackedLight {
-    const LOG_SCALE: f32 = 16.0;
-    const LOG_OFFSET: f32 = 128.0;
+    /// Number of steps per doubling of the light value.
+    const LOG_SCALE: f32 = 2048.0;
+    /// Encoded value of 1.0. The representable range is 2<sup>±16</sup>.
+    const LOG_OFFSET: f32 = 32768.0;
+    /// Size of a change in encoded value which counts as one unit of
+    /// [`LightUpdatePriority`]; this is a change of about 4%.
+    const PRIORITY_UNIT: u32 = 128;
 
     pub(crate) const ZERO: Self = Self::none(LightStatus::Visible);
     pub(crate) const OPAQUE: Self = Self::none(LightStatus::Opaque);
@@ -127,40 +136,66 @@ impl PackedLight {
 
     #[inline]
     #[doc(hidden)] // TODO: used by all_is_cubes_gpu; but it should be doable equivalently using public functions
-    pub fn as_texel(self) -> [u8; 4] {
+    pub fn as_texel(self) -> [PackedLightScalar; 4] {
         let Self {
             value: Vector3 { x, y, z },
             status,
         } = self;
-        [x, y, z, status as u8]
+        [x, y, z, status as PackedLightScalar]
+    }
+
+    /// Inverse of [`Self::as_texel()`], used for loading saved light data.
+    ///
+    /// Returns [`None`] if the status byte is not a [`LightStatus`], or if the status
+    /// is not [`LightStatus::Visible`] but the value is nonzero.
+    pub(crate) fn from_texel([x, y, z, status]: [PackedLightScalar; 4]) -> Option<Self> {
+        let status = match status {
+            0 => LightStatus::Uninitialized,
+            1 => LightStatus::NoRays,
+            128 => LightStatus::Opaque,
+            255 => LightStatus::Visible,
+            _ => return None,
+        };
+        let value = Vector3::new(x, y, z);
+        if status != LightStatus::Visible && value != Vector3::new(0, 0, 0) {
+            return None;
+        }
+        Some(PackedLight { value, status })
     }
 
     /// Computes a degree of difference between two [`PackedLight`] values, used to decide
     /// update priority.
     /// The value is zero if and only if the two inputs are equal.
     #[inline]
-    pub(crate) fn difference_priority(self, other: PackedLight) -> PackedLightScalar {
-        fn abs_diff(a: PackedLightScalar, b: PackedLightScalar) -> PackedLightScalar {
-            a.max(b) - a.min(b)
+    pub(crate) fn difference_priority(self, other: PackedLight) -> LightUpdatePriority {
+        fn abs_diff(a: PackedLightScalar, b: PackedLightScalar) -> u32 {
+            u32::from(a.max(b) - a.min(b))
         }
-        let mut difference = abs_diff(self.value[0], other.value[0])
+        let value_difference = abs_diff(self.value[0], other.value[0])
             .max(abs_diff(self.value[1], other.value[1]))
             .max(abs_diff(self.value[2], other.value[2]));
+        // Even the smallest change must have nonzero priority.
+        let mut difference = if value_difference == 0 {
+            0
+        } else {
+            LightUpdatePriority::try_from((value_difference / Self::PRIORITY_UNIT).max(1))
+                .unwrap_or(LightUpdatePriority::MAX)
+        };
 
         if other.status != self.status {
             // A non-opaque block changing to an opaque one, or similar, changes the
             // results of the rest of the algorithm so should be counted as a difference
             // even if it's still changing zero to zero.
             // TODO: Tune this number for fast settling and good results.
-            difference = difference.saturating_add(PackedLightScalar::MAX / 4);
+            difference = difference.saturating_add(LightUpdatePriority::MAX / 4);
         }
 
         difference
     }
 
     fn scalar_in(value: impl Into<f32>) -> PackedLightScalar {
-        // Note that `as` is a saturating cast.
-        (value.into().log2() * Self::LOG_SCALE + Self::LOG_OFFSET) as PackedLightScalar
+        // Note that `as` is a saturating cast, and converts NaN to zero.
+        (value.into().log2() * Self::LOG_SCALE + Self::LOG_OFFSET).round() as PackedLightScalar
     }
 
     /// Convert a `PackedLightScalar` value to a linear color component value.
@@ -200,7 +235,7 @@ impl From<Rgb> for PackedLight {
 /// An entry in the queue of cubes that need their light updated.
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
 pub(crate) struct LightUpdateRequest {
-    pub(crate) priority: PackedLightScalar,
+    pub(crate) priority: LightUpdatePriority,
     pub(crate) cube: GridPoint,
 }
 impl LightUpdateRequest {
@@ -241,7 +276,7 @@ pub(crate) struct LightUpdateQueue {
     queue: BTreeSet<LightUpdateRequest>,
     /// Maps GridPoint to priority value. This allows deduplicating entries, including
     /// removing low-priority entries in favor of high-priority ones
-    table: HashMap<GridPoint, PackedLightScalar>,
+    table: HashMap<GridPoint, LightUpdatePriority>,
 }
 
 impl LightUpdateQueue {
@@ -302,7 +337,7 @@ impl LightUpdateQueue {
     }
 
     #[inline]
-    pub fn peek_priority(&self) -> PackedLightScalar {
+    pub fn peek_priority(&self) -> LightUpdatePriority {
         self.queue.last().copied().map(|r| r.priority).unwrap_or(0)
     }
 
@@ -318,7 +353,9 @@ mod tests {
     use std::iter::once;
 
     fn packed_light_test_values() -> impl Iterator<Item = PackedLight> {
+        // Not every value, so that pairwise tests are not too slow.
         (PackedLightScalar::MIN..PackedLightScalar::MAX)
+            .step_by(257)
             .flat_map(|s| {
                 vec![
                     PackedLight {
@@ -334,7 +371,7 @@ mod tests {
                         status: LightStatus::Visible,
                     },
                     PackedLight {
-                        value: Vector3::new(s, 127, 255),
+                        value: Vector3::new(s, s + 1, 32768),
                         status: LightStatus::Visible,
                     },
                 ]
@@ -353,6 +390,15 @@ mod tests {
         }
     }
 
+    #[test]
+    fn packed_light_texel_roundtrip() {
+        for light in packed_light_test_values() {
+            assert_eq!(PackedLight::from_texel(light.as_texel()), Some(light));
+        }
+        assert_eq!(PackedLight::from_texel([0, 0, 0, 2]), None);
+        assert_eq!(PackedLight::from_texel([1, 0, 0, 128]), None);
+    }
+
     /// Safety test: we want to skip the NaN checks for constructing `Rgb`
     /// from `PackedLight`, so it had better not be NaN for any possible input.
     #[test]
@@ -371,14 +417,14 @@ mod tests {
                 PackedLight::scalar_in(notnan!(1e-30)),
                 PackedLight::scalar_in(notnan!(1e+30)),
             ],
-            [0, 0, 255],
+            [0, 0, 65535],
         );
     }
 
     #[test]
     fn packed_light_is_packed() {
         // Technically this is not guaranteed by the compiler, but if it's false something probably went wrong.
-        assert_eq!(std::mem::size_of::<PackedLight>(), 4);
+        assert_eq!(std::mem::size_of::<PackedLight>(), 8);
     }
 
     /// Demonstrate what range and step sizes we get out of the encoding.
@@ -387,13 +433,18 @@ mod tests {
         assert_eq!(
             [
                 PackedLight::scalar_out(0),
-                PackedLight::scalar_out(1),
-                PackedLight::scalar_out(2),
-                PackedLight::scalar_out(254),
-                PackedLight::scalar_out(255),
+                PackedLight::scalar_out(2048),
+                PackedLight::scalar_out(30720),
+                PackedLight::scalar_out(32768),
+                PackedLight::scalar_out(34816),
+                PackedLight::scalar_out(63488),
             ],
-            [0.0, 0.0040791943, 0.004259796, 234.75304, 245.14644],
+            [0.0, 1.0 / 32768.0, 0.5, 1.0, 2.0, 32768.0],
         );
+        let smallest = PackedLight::scalar_out(1);
+        assert!(smallest > 0.0 && smallest < 2e-5, "{smallest}");
+        let largest = PackedLight::scalar_out(PackedLightScalar::MAX);
+        assert!(largest > 65000.0, "{largest}");
     }
 
     #[test]
@@ -411,7 +462,7 @@ mod tests {
 
     #[test]
     fn queue_ordering() {
-        fn r(cube: [GridCoordinate; 3], priority: PackedLightScalar) -> LightUpdateRequest {
+        fn r(cube: [GridCoordinate; 3], priority: LightUpdatePriority) -> LightUpdateRequest {
             LightUpdateRequest {
                 cube: GridPoint::from(cube),
                 priority,
@@ -6,7 +6,7 @@ use super::{data::LightStatus, LightUpdatesInfo, PackedLight};
 use crate::block::{AnimationHint, Block, AIR};
 use crate::listen::{Listen as _, Listener, Sink};
 use crate::math::{FaceMap, GridPoint, Rgb, Rgba};
-use crate::space::{GridAab, LightPhysics, Space, SpaceChange, SpacePhysics};
+use crate::space::{GridAab, LightPhysics, Sky, SkyGradient, Space, SpaceChange, SpacePhysics};
 use crate::time::{practically_infinite_deadline, Tick};
 
 #[test]
@@ -19,7 +19,7 @@ fn initial_lighting_value() {
 fn out_of_bounds_lighting_value() {
     let space = Space::empty_positive(1, 1, 1);
     assert_eq!(
-        PackedLight::from(space.physics().sky_color),
+        PackedLight::from(space.physics().sky.mean()),
         space.get_lighting((-1, 0, 0))
     );
 }
@@ -28,10 +28,10 @@ fn out_of_bounds_lighting_value() {
 fn step() {
     let mut space = Space::empty_positive(3, 1, 1);
     space.set_physics(SpacePhysics {
-        sky_color: Rgb::new(1.0, 0.0, 0.0),
+        sky: Sky::Uniform(Rgb::new(1.0, 0.0, 0.0)),
         ..SpacePhysics::default()
     });
-    let sky_light = PackedLight::from(space.physics().sky_color);
+    let sky_light = PackedLight::from(space.physics().sky.mean());
 
     space.set((0, 0, 0), Rgb::ONE).unwrap();
     // Not changed yet... except for the now-opaque block
@@ -66,6 +66,41 @@ fn evaluate_light() {
     // Ideally we'd confirm identical results from repeated step() and single evaluate_light().
 }
 
+/// Check that parallel evaluation (which [`Space::evaluate_light`] uses when the queue
+/// is long) finishes and agrees with sequential evaluation about which cubes are lit.
+#[cfg(feature = "threads")]
+#[test]
+fn evaluate_light_parallel_matches_sequential() {
+    fn make_space() -> Space {
+        let mut space = Space::empty_positive(24, 24, 24);
+        let block = Block::from(Rgb::ONE);
+        space
+            .fill(space.bounds(), |p| {
+                ((p.x * 7 + p.y * 3 + p.z * 5) % 11 == 0).then_some(&block)
+            })
+            .unwrap();
+        space
+    }
+
+    let mut sequential = make_space();
+    while sequential
+        .update_lighting_from_queue(std::time::Duration::from_secs(1))
+        .queue_count
+        > 0
+    {}
+    let mut parallel = make_space();
+    parallel.evaluate_light(0, |_| {});
+
+    assert_eq!(parallel.light_update_queue.len(), 0);
+    for cube in parallel.bounds().interior_iter() {
+        assert_eq!(
+            parallel.get_lighting(cube).status(),
+            sequential.get_lighting(cube).status(),
+            "{cube:?}"
+        );
+    }
+}
+
 // TODO: test evaluate_light's epsilon parameter
 
 /// There's a special case for setting cubes to opaque. That case must do the usual
@@ -93,7 +128,7 @@ fn set_cube_opaque_notification() {
 fn light_source_test_space(block: Block) -> Space {
     let mut space = Space::empty_positive(3, 3, 3);
     space.set_physics(SpacePhysics {
-        sky_color: Rgb::ZERO,
+        sky: Sky::Uniform(Rgb::ZERO),
         ..Default::default()
     });
     space.set([1, 1, 1], block).unwrap();
@@ -195,6 +230,31 @@ fn reflectance_is_clamped() {
     assert!(light.red() <= sky_color.red());
 }
 
+/// Light arriving from outside the space is the color of the sky in its direction.
+#[test]
+fn directional_sky() {
+    let sky = Sky::from(SkyGradient {
+        zenith: Rgb::ONE,
+        horizon: Rgb::ZERO,
+        nadir: Rgb::ZERO,
+        sun: None,
+    });
+    let light_next_to_block = |block_y: i32, air_y: i32| {
+        let mut space = Space::builder(GridAab::from_lower_size([0, 0, 0], [1, 2, 1]))
+            .sky(sky.clone())
+            .build();
+        space.set([0, block_y, 0], Rgb::ONE).unwrap();
+        space.evaluate_light(0, |_| ());
+        space.get_lighting([0, air_y, 0]).value()
+    };
+    let above_floor = light_next_to_block(0, 1);
+    let below_ceiling = light_next_to_block(1, 0);
+    assert!(
+        above_floor.luminance() > 0.1 && below_ceiling.luminance() < 0.01,
+        "above floor {above_floor:?}, below ceiling {below_ceiling:?}"
+    );
+}
+
 /// Helper to construct a space with `LightPhysics` set to None
 fn space_with_disabled_light() -> Space {
     let mut space = Space::empty_positive(1, 1, 1);
@@ -14,7 +14,7 @@ use crate::block::EvaluatedBlock;
 use crate::math::{Face6, FaceMap, FreeCoordinate, Geometry, GridPoint, NotNan, Rgb};
 use crate::raycast::{Ray, RaycastStep};
 use crate::space::light::LightUpdateRayInfo;
-use crate::space::{GridAab, LightPhysics, PackedLight, PackedLightScalar, Space, SpaceChange};
+use crate::space::{GridAab, LightPhysics, LightUpdatePriority, PackedLight, Space, SpaceChange};
 use crate::util::{CustomFormat, StatusText};
 
 /// This parameter determines to what degree absorption of light due to a block surface's
@@ -28,6 +28,12 @@ const RAY_DIRECTION_STEP: isize = 5;
 const RAY_CUBE_EDGE: usize = (RAY_DIRECTION_STEP as usize) * 2 + 1;
 const ALL_RAYS_COUNT: usize = RAY_CUBE_EDGE.pow(3) - (RAY_CUBE_EDGE - 2).pow(3);
 
+/// Number of cubes per thread which [`Space::update_lighting_from_queue_parallel`]
+/// takes from the queue at once. Queues shorter than one batch are processed
+/// sequentially instead.
+#[cfg(feature = "threads")]
+const PARALLEL_BATCH_PER_THREAD: usize = 64;
+
 #[derive(Debug)]
 struct LightRayData {
     ray: Ray,
@@ -68,7 +74,7 @@ static LIGHT_RAYS: Lazy<[LightRayData; ALL_RAYS_COUNT]> = Lazy::new(|| {
 
 /// Methods on Space that specifically implement the lighting algorithm.
 impl Space {
-    pub(crate) fn light_needs_update(&mut self, cube: GridPoint, priority: PackedLightScalar) {
+    pub(crate) fn light_needs_update(&mut self, cube: GridPoint, priority: LightUpdatePriority) {
         if self.physics.light == LightPhysics::None {
             return;
         }
@@ -84,7 +90,7 @@ impl Space {
     pub fn update_lighting_from_queue(&mut self, budget: Duration) -> LightUpdatesInfo {
         let mut light_update_count: usize = 0;
         self.last_light_updates.clear();
-        let mut max_difference: PackedLightScalar = 0;
+        let mut max_difference: LightUpdatePriority = 0;
 
         if self.physics.light != LightPhysics::None {
             let t0 = Instant::now();
@@ -128,15 +134,97 @@ impl Space {
         }
     }
 
+    /// Same as [`Self::update_lighting_from_queue()`], but computes many cubes' new light
+    /// values in parallel, for use when there is a large amount of work to do.
+    ///
+    /// Each batch of cubes is computed from the lighting as it was before the batch,
+    /// and the results are then stored one at a time, so no synchronization of the
+    /// lighting data is needed. The result may take more updates to converge than
+    /// sequential updating would, so this is not worthwhile for a small queue, and
+    /// a queue shorter than one batch is processed sequentially.
+    #[cfg(feature = "threads")]
+    pub(crate) fn update_lighting_from_queue_parallel(
+        &mut self,
+        budget: Duration,
+    ) -> LightUpdatesInfo {
+        use rayon::iter::{IntoParallelRefIterator as _, ParallelIterator as _};
+
+        if self.physics.light == LightPhysics::None {
+            return self.update_lighting_from_queue(budget);
+        }
+
+        let batch_size = rayon::current_num_threads() * PARALLEL_BATCH_PER_THREAD;
+        let mut light_update_count: usize = 0;
+        let mut max_difference: LightUpdatePriority = 0;
+        let mut batch: Vec<GridPoint> = Vec::with_capacity(batch_size);
+        let t0 = Instant::now();
+        while self.light_update_queue.len() >= batch_size {
+            batch.clear();
+            batch.extend(
+                std::iter::from_fn(|| self.light_update_queue.pop())
+                    .take(batch_size)
+                    .map(|request| request.cube),
+            );
+
+            let this: &Space = self;
+            let results: Vec<(PackedLight, Vec<GridPoint>)> = batch
+                .par_iter()
+                .map(|&cube| {
+                    let (light, dependencies, _cost, ()) = this.compute_lighting(cube);
+                    (light, dependencies)
+                })
+                .collect();
+
+            for (&cube, (new_light_value, dependencies)) in batch.iter().zip(results) {
+                light_update_count += 1;
+                max_difference =
+                    max_difference.max(self.store_lighting(cube, new_light_value, dependencies));
+            }
+
+            if t0.elapsed() >= budget {
+                self.last_light_updates.clear();
+                return LightUpdatesInfo {
+                    update_count: light_update_count,
+                    max_update_difference: max_difference,
+                    queue_count: self.light_update_queue.len(),
+                    max_queue_priority: self.light_update_queue.peek_priority(),
+                };
+            }
+        }
+
+        // Finish the rest of the budget on whatever is left in the queue.
+        let mut info = self.update_lighting_from_queue(budget.saturating_sub(t0.elapsed()));
+        info.update_count += light_update_count;
+        info.max_update_difference = info.max_update_difference.max(max_difference);
+        info
+    }
+
     #[inline]
-    fn update_lighting_now_on(&mut self, cube: GridPoint) -> (PackedLightScalar, usize) {
+    fn update_lighting_now_on(&mut self, cube: GridPoint) -> (LightUpdatePriority, usize) {
         let (new_light_value, dependencies, mut cost, ()) = self.compute_lighting(cube);
+        let difference_priority = self.store_lighting(cube, new_light_value, dependencies);
+        if difference_priority > 0 {
+            cost += 200;
+        }
+        (difference_priority, cost)
+    }
+
+    /// Store a light value computed by [`Self::compute_lighting()`] and queue updates of
+    /// the cubes it depended on if it changed significantly.
+    ///
+    /// Returns the priority of the difference from the previous value.
+    #[inline]
+    fn store_lighting(
+        &mut self,
+        cube: GridPoint,
+        new_light_value: PackedLight,
+        dependencies: Vec<GridPoint>,
+    ) -> LightUpdatePriority {
         let old_light_value: PackedLight = self.get_lighting(cube);
         // Compare and set new value. Note that we MUST compare only the packed value so
         // that changes are detected in terms of that rounding, not float values.
         let difference_priority = new_light_value.difference_priority(old_light_value);
         if difference_priority > 0 {
-            cost += 200;
             // TODO: compute index only once
             self.lighting[self.bounds().index(cube).unwrap()] = new_light_value;
             self.notifier.notify(SpaceChange::Lighting(cube));
@@ -154,7 +242,7 @@ impl Space {
                 }
             }
         }
-        (difference_priority, cost)
+        difference_priority
     }
 
     /// Compute the new lighting value for a cube.
@@ -251,7 +339,7 @@ impl Space {
                     let index = bounds.index(cube).unwrap();
 
                     let this_cube_evaluated =
-                        &self.block_data[self.contents[index] as usize].evaluated;
+                        &self.block_data[self.contents.get(cube) as usize].evaluated;
                     self.lighting[index] = if opaque_for_light_computation(this_cube_evaluated) {
                         covered = true;
                         PackedLight::OPAQUE
@@ -266,7 +354,7 @@ impl Space {
                             // so we need to schedule a proper update.
                             // (Bypassing `self.light_needs_update()` to skip bounds checks).
                             self.light_update_queue.insert(LightUpdateRequest {
-                                priority: PackedLightScalar::MAX,
+                                priority: LightUpdatePriority::MAX,
                                 cube,
                             });
 
@@ -283,6 +371,32 @@ impl Space {
             }
         }
     }
+
+    /// Returns whether there are no pending light updates, so that the lighting is as
+    /// complete as [`Space::evaluate_light()`] would make it.
+    pub(crate) fn is_lighting_complete(&self) -> bool {
+        self.light_update_queue.len() == 0
+    }
+
+    /// Replace the light data with previously computed data, such as from a save file.
+    ///
+    /// `lighting` must have one element per cube, in the order of
+    /// [`GridAab::interior_iter()`]. If `complete` is true, pending updates are
+    /// discarded, since the data is taken to already account for them; otherwise they
+    /// are kept, and improve upon the given data.
+    ///
+    /// No change notifications are sent, so this is only suitable for a space which
+    /// has just been constructed.
+    pub(crate) fn restore_lighting(&mut self, lighting: Box<[PackedLight]>, complete: bool) {
+        if self.physics.light == LightPhysics::None {
+            return;
+        }
+        assert_eq!(lighting.len(), self.bounds().volume());
+        self.lighting = lighting;
+        if complete {
+            self.light_update_queue.clear();
+        }
+    }
 }
 
 impl LightPhysics {
@@ -495,7 +609,7 @@ impl LightBuffer {
         if ray_state.ray_weight_by_faces > 0. {
             // Note that if ray_state.alpha has reached zero, the sky color has no effect.
             self.add_weighted_light(
-                space.physics.sky_color * ray_state.alpha,
+                space.physics.sky.sample(ray_state.translated_ray.direction) * ray_state.alpha,
                 ray_state.ray_weight_by_faces,
             );
         }
@@ -0,0 +1,102 @@
+//! [`ProtectedRegion`]: restrictions on which characters may modify parts of a [`Space`].
+
+use crate::character::Character;
+use crate::math::{GridAab, GridPoint};
+#[cfg(doc)]
+use crate::space::{Space, SpaceTransaction};
+use crate::universe::{RefVisitor, URef, VisitRefs};
+
+/// A region of a [`Space`] in which modifications made on behalf of characters are
+/// restricted, so that players sharing a server cannot damage each other's work.
+///
+/// Protection applies only to [`SpaceTransaction`]s which were made
+/// [on behalf of](SpaceTransaction::on_behalf_of) a character; transactions made by the
+/// game itself, such as by behaviors or by an administrator, are not restricted.
+///
+/// Regions are defined using [`Space::add_protected_region()`].
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct ProtectedRegion {
+    bounds: GridAab,
+    access: RegionAccess,
+}
+
+impl ProtectedRegion {
+    /// Constructs a [`ProtectedRegion`] covering the cubes in `bounds`.
+    pub fn new(bounds: GridAab, access: RegionAccess) -> Self {
+        Self { bounds, access }
+    }
+
+    /// Returns the cubes which this region covers.
+    pub fn bounds(&self) -> GridAab {
+        self.bounds
+    }
+
+    /// Returns who may modify the cubes in this region.
+    pub fn access(&self) -> &RegionAccess {
+        &self.access
+    }
+
+    /// Returns whether a modification to `cube` by `actor` is allowed by this region.
+    ///
+    /// If `actor` is [`None`], the modification is not being made on behalf of any
+    /// character, and is always allowed.
+    pub fn permits(&self, cube: GridPoint, actor: Option<&URef<Character>>) -> bool {
+        let Some(actor) = actor else {
+            return true;
+        };
+        !self.bounds.contains_cube(cube)
+            || match &self.access {
+                RegionAccess::ReadOnly => false,
+                RegionAccess::Owner(owner) => owner == actor,
+            }
+    }
+}
+
+impl VisitRefs for ProtectedRegion {
+    fn visit_refs(&self, visitor: &mut dyn RefVisitor) {
+        let Self { bounds: _, access } = self;
+        match access {
+            RegionAccess::ReadOnly => {}
+            RegionAccess::Owner(owner) => visitor.visit(owner),
+        }
+    }
+}
+
+/// Who may modify the cubes in a [`ProtectedRegion`].
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+#[non_exhaustive]
+pub enum RegionAccess {
+    /// No character may modify the region.
+    ReadOnly,
+    /// Only the given character may modify the region.
+    Owner(URef<Character>),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::space::Space;
+    use crate::universe::Universe;
+
+    #[test]
+    fn permits() {
+        let mut universe = Universe::new();
+        let space = universe.insert_anonymous(Space::empty_positive(1, 1, 1));
+        let owner = universe.insert_anonymous(Character::spawn_default(space.clone()));
+        let other = universe.insert_anonymous(Character::spawn_default(space));
+        let bounds = GridAab::from_lower_size([0, 0, 0], [2, 2, 2]);
+        let inside = GridPoint::new(1, 1, 1);
+        let outside = GridPoint::new(2, 1, 1);
+
+        let read_only = ProtectedRegion::new(bounds, RegionAccess::ReadOnly);
+        assert!(read_only.permits(inside, None));
+        assert!(!read_only.permits(inside, Some(&owner)));
+        assert!(read_only.permits(outside, Some(&owner)));
+
+        let owned = ProtectedRegion::new(bounds, RegionAccess::Owner(owner.clone()));
+        assert!(owned.permits(inside, None));
+        assert!(owned.permits(inside, Some(&owner)));
+        assert!(!owned.permits(inside, Some(&other)));
+        assert!(owned.permits(outside, Some(&other)));
+    }
+}
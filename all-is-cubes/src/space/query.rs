@@ -0,0 +1,134 @@
+//! Queries which examine many cubes of a [`Space`] at once.
+//!
+//! These use the chunked storage of the space to skip over groups of cubes which cannot
+//! match, rather than examining every cube.
+
+use std::collections::HashMap;
+
+use crate::block::{Block, AIR};
+use crate::math::{GridAab, GridCoordinate, GridPoint};
+use crate::space::contents::PartSummary;
+use crate::space::{BlockIndex, Space};
+
+impl Space {
+    /// Counts how many cubes within `region` contain each distinct block.
+    ///
+    /// Cubes outside the bounds of the space are not counted, and blocks which do not
+    /// occur in the region are not included.
+    ///
+    /// To count blocks in the entire space, [`Space::block_data()`] is cheaper.
+    pub fn count_blocks(&self, region: GridAab) -> HashMap<Block, usize> {
+        let mut counts = vec![0; self.block_data.len()];
+        if let Some(region) = region.intersection(self.bounds) {
+            for (part, summary) in self.contents.parts(region) {
+                if let PartSummary::Uniform(index) = summary {
+                    counts[usize::from(index)] += part.volume();
+                } else {
+                    for cube in part.interior_iter() {
+                        counts[usize::from(self.contents.get(cube))] += 1;
+                    }
+                }
+            }
+        }
+        counts
+            .into_iter()
+            .enumerate()
+            .filter(|&(_, count)| count > 0)
+            .map(|(index, count)| (self.block_data[index].block.clone(), count))
+            .collect()
+    }
+
+    /// Returns the cube within `region` nearest to `origin` whose block satisfies
+    /// `predicate`, or [`None`] if there is no such cube.
+    ///
+    /// Distance is measured between cube centers. If several cubes are equally near,
+    /// which one is returned is unspecified.
+    ///
+    /// `predicate` is called once for each distinct block in the space, not for each cube.
+    pub fn find_nearest(
+        &self,
+        origin: GridPoint,
+        region: GridAab,
+        mut predicate: impl FnMut(&Block) -> bool,
+    ) -> Option<GridPoint> {
+        let matching: Vec<bool> = self
+            .block_data
+            .iter()
+            .map(|data| data.count > 0 && predicate(&data.block))
+            .collect();
+        let matches = |index: BlockIndex| matching[usize::from(index)];
+        let region = region.intersection(self.bounds)?;
+
+        // Examine parts in order of how near they could possibly be.
+        let mut parts: Vec<(u64, GridAab, PartSummary<'_>)> = self
+            .contents
+            .parts(region)
+            .filter(|(_, summary)| summary.may_contain(matches))
+            .map(|(part, summary)| {
+                let nearest_possible = nearest_cube_in(part, origin);
+                (distance_squared(origin, nearest_possible), part, summary)
+            })
+            .collect();
+        parts.sort_unstable_by_key(|&(distance, ..)| distance);
+
+        let mut best: Option<(u64, GridPoint)> = None;
+        for (lower_bound, part, summary) in parts {
+            if best.map_or(false, |(best_distance, _)| lower_bound >= best_distance) {
+                break;
+            }
+            if let PartSummary::Uniform(_) = summary {
+                // We already know the whole part matches.
+                best = Some((lower_bound, nearest_cube_in(part, origin)));
+                continue;
+            }
+            for cube in part.interior_iter() {
+                if matches(self.contents.get(cube)) {
+                    let distance = distance_squared(origin, cube);
+                    if best.map_or(true, |(best_distance, _)| distance < best_distance) {
+                        best = Some((distance, cube));
+                    }
+                }
+            }
+        }
+        best.map(|(_, cube)| cube)
+    }
+
+    /// Iterates over the cubes within `region` which contain blocks other than [`AIR`],
+    /// along with those blocks.
+    ///
+    /// Cubes outside the bounds of the space are not included.
+    pub fn non_air_cubes(&self, region: GridAab) -> impl Iterator<Item = (GridPoint, &Block)> {
+        let air_index = self.block_to_index.get(&AIR).copied();
+        let is_not_air = move |index: BlockIndex| Some(index) != air_index;
+        region
+            .intersection(self.bounds)
+            .into_iter()
+            .flat_map(move |region| self.contents.parts(region))
+            .filter(move |(_, summary)| summary.may_contain(is_not_air))
+            .flat_map(move |(part, _)| {
+                part.interior_iter().filter_map(move |cube| {
+                    let index = self.contents.get(cube);
+                    is_not_air(index).then(|| (cube, &self.block_data[usize::from(index)].block))
+                })
+            })
+    }
+}
+
+/// Returns the cube within `part` nearest to `origin`.
+fn nearest_cube_in(part: GridAab, origin: GridPoint) -> GridPoint {
+    let lower = part.lower_bounds();
+    let upper = part.upper_bounds();
+    GridPoint::new(
+        origin.x.clamp(lower.x, upper.x - 1),
+        origin.y.clamp(lower.y, upper.y - 1),
+        origin.z.clamp(lower.z, upper.z - 1),
+    )
+}
+
+/// Squared Euclidean distance between two cubes, saturating on overflow.
+fn distance_squared(a: GridPoint, b: GridPoint) -> u64 {
+    let axis = |a: GridCoordinate, b: GridCoordinate| u64::from(a.abs_diff(b)).pow(2);
+    axis(a.x, b.x)
+        .saturating_add(axis(a.y, b.y))
+        .saturating_add(axis(a.z, b.z))
+}
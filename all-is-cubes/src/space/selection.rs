@@ -0,0 +1,327 @@
+//! [`Selection`]: a set of cubes in a [`Space`] to be operated on together.
+
+use std::collections::HashSet;
+
+use crate::block::Block;
+use crate::content::palette;
+use crate::math::{Aab, Geometry, GridAab, GridCoordinate, GridPoint, GridVector, LineVertex};
+use crate::space::{Space, SpaceTransaction};
+use crate::universe::{RefError, URef, UniverseTransaction};
+use crate::util::MapExtend;
+
+/// A set of cubes in a [`Space`], such as one chosen by the user with the cursor, which
+/// bulk operations like [`Selection::fill()`] act on as a single transaction.
+///
+/// A selection is either a box, which grows to include each cube it is
+/// [extended](Self::extend) with, or an arbitrary set of cubes.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Selection {
+    space: URef<Space>,
+    shape: Shape,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+enum Shape {
+    Box(GridAab),
+    Cubes(HashSet<GridPoint>),
+}
+
+impl Selection {
+    /// Constructs a selection of all cubes within `bounds`.
+    pub fn from_box(space: URef<Space>, bounds: GridAab) -> Self {
+        Self {
+            space,
+            shape: Shape::Box(bounds),
+        }
+    }
+
+    /// Constructs a selection of exactly the given cubes.
+    pub fn from_cubes(space: URef<Space>, cubes: impl IntoIterator<Item = GridPoint>) -> Self {
+        Self {
+            space,
+            shape: Shape::Cubes(cubes.into_iter().collect()),
+        }
+    }
+
+    /// Returns the space the selected cubes are in.
+    pub fn space(&self) -> &URef<Space> {
+        &self.space
+    }
+
+    /// Returns whether no cubes are selected.
+    pub fn is_empty(&self) -> bool {
+        match &self.shape {
+            Shape::Box(bounds) => bounds.volume() == 0,
+            Shape::Cubes(cubes) => cubes.is_empty(),
+        }
+    }
+
+    /// Returns whether `cube` is selected.
+    pub fn contains(&self, cube: impl Into<GridPoint>) -> bool {
+        let cube = cube.into();
+        match &self.shape {
+            Shape::Box(bounds) => bounds.contains_cube(cube),
+            Shape::Cubes(cubes) => cubes.contains(&cube),
+        }
+    }
+
+    /// Returns the smallest box containing all selected cubes, or [`None`] if the
+    /// selection is empty.
+    pub fn bounds(&self) -> Option<GridAab> {
+        match &self.shape {
+            _ if self.is_empty() => None,
+            &Shape::Box(bounds) => Some(bounds),
+            Shape::Cubes(cubes) => cubes
+                .iter()
+                .map(|&cube| GridAab::single_cube(cube))
+                .reduce(|a, b| a.union(b).unwrap_or(a)),
+        }
+    }
+
+    /// Iterates over all selected cubes, in no particular order.
+    pub fn cubes(&self) -> impl Iterator<Item = GridPoint> + '_ {
+        let (bounds, cubes) = match &self.shape {
+            &Shape::Box(bounds) => (Some(bounds), None),
+            Shape::Cubes(cubes) => (None, Some(cubes)),
+        };
+        bounds
+            .into_iter()
+            .flat_map(GridAab::interior_iter)
+            .chain(cubes.into_iter().flatten().copied())
+    }
+
+    /// Adds `cube` to the selection. If the selection is a box, it is enlarged to the
+    /// smallest box containing both the previous box and `cube`.
+    pub fn extend(&mut self, cube: impl Into<GridPoint>) {
+        let cube = cube.into();
+        match &mut self.shape {
+            Shape::Box(bounds) if bounds.volume() == 0 => *bounds = GridAab::single_cube(cube),
+            Shape::Box(bounds) => {
+                if let Ok(union) = bounds.union(GridAab::single_cube(cube)) {
+                    *bounds = union;
+                }
+            }
+            Shape::Cubes(cubes) => {
+                cubes.insert(cube);
+            }
+        }
+    }
+
+    /// Returns a transaction which replaces every selected cube's block with `block`.
+    /// Selected cubes outside the bounds of the space are not changed.
+    ///
+    /// Returns an error if the space cannot be read.
+    pub fn fill(&self, block: &Block) -> Result<UniverseTransaction, RefError> {
+        let bounds = self.space.read()?.bounds();
+        let mut txn = SpaceTransaction::default();
+        for cube in self.cubes() {
+            if bounds.contains_cube(cube) {
+                txn.set(cube, None, Some(block.clone()))
+                    .expect("selected cubes are distinct");
+            }
+        }
+        Ok(txn.bind(self.space.clone()))
+    }
+
+    /// Returns a transaction which replaces `old` with `new` in every selected cube
+    /// which currently contains `old`.
+    ///
+    /// Returns an error if the space cannot be read.
+    pub fn replace(&self, old: &Block, new: &Block) -> Result<UniverseTransaction, RefError> {
+        let space = self.space.read()?;
+        let mut txn = SpaceTransaction::default();
+        for cube in self.cubes() {
+            if space.bounds().contains_cube(cube) && space[cube] == *old {
+                txn.set(cube, Some(old.clone()), Some(new.clone()))
+                    .expect("selected cubes are distinct");
+            }
+        }
+        Ok(txn.bind(self.space.clone()))
+    }
+
+    /// Returns a transaction which copies the blocks in the selected cubes to the cubes
+    /// displaced from them by `offset`. Selected cubes outside the bounds of the space
+    /// are not copied.
+    ///
+    /// Returns an error if the space cannot be read, or if a destination cube's
+    /// coordinates would overflow.
+    pub fn copy_to(&self, offset: GridVector) -> Result<UniverseTransaction, SelectionError> {
+        let space = self.space.read()?;
+        let mut txn = SpaceTransaction::default();
+        for cube in self.cubes() {
+            if space.bounds().contains_cube(cube) {
+                let destination = (|| {
+                    Some(GridPoint::new(
+                        cube.x.checked_add(offset.x)?,
+                        cube.y.checked_add(offset.y)?,
+                        cube.z.checked_add(offset.z)?,
+                    ))
+                })()
+                .ok_or(SelectionError::OffsetOutOfRange { cube, offset })?;
+                txn.set(destination, None, Some(space[cube].clone()))
+                    .expect("selected cubes are distinct");
+            }
+        }
+        Ok(txn.bind(self.space.clone()))
+    }
+}
+
+/// Error from [`Selection::copy_to()`].
+#[derive(Clone, Debug, PartialEq, thiserror::Error)]
+#[non_exhaustive]
+pub enum SelectionError {
+    /// The space could not be read.
+    #[error(transparent)]
+    Read(#[from] RefError),
+    /// Displacing a selected cube would produce coordinates which cannot be represented.
+    #[error("cube {cube:?} displaced by {offset:?} is out of range")]
+    #[allow(missing_docs)]
+    OffsetOutOfRange { cube: GridPoint, offset: GridVector },
+}
+
+impl Geometry for Selection {
+    type Coord = GridCoordinate;
+
+    fn translate(mut self, offset: GridVector) -> Self {
+        self.shape = match self.shape {
+            Shape::Box(bounds) => Shape::Box(bounds.translate(offset)),
+            Shape::Cubes(cubes) => Shape::Cubes(cubes.into_iter().map(|c| c + offset).collect()),
+        };
+        self
+    }
+
+    fn wireframe_points<E>(&self, output: &mut E)
+    where
+        E: Extend<LineVertex>,
+    {
+        let output = &mut MapExtend::new(output, |mut v: LineVertex| {
+            v.color = Some(palette::SELECTION_OUTLINE);
+            v
+        });
+        // Slightly enlarged so that the outline is not hidden by the selected blocks.
+        let expansion = 1. / 64.;
+        match &self.shape {
+            _ if self.is_empty() => {}
+            &Shape::Box(bounds) => Aab::from(bounds).expand(expansion).wireframe_points(output),
+            Shape::Cubes(cubes) => {
+                for &cube in cubes {
+                    Aab::from_cube(cube)
+                        .expand(expansion)
+                        .wireframe_points(output);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::content::make_some_blocks;
+    use crate::transaction::{self, Transaction as _};
+    use crate::universe::Universe;
+
+    #[test]
+    fn extend_box() {
+        let mut universe = Universe::new();
+        let space = universe.insert_anonymous(Space::empty_positive(10, 10, 10));
+        let mut selection =
+            Selection::from_box(space, GridAab::from_lower_size([1, 1, 1], [0, 0, 0]));
+        assert!(selection.is_empty());
+        assert_eq!(selection.bounds(), None);
+
+        selection.extend([2, 3, 4]);
+        assert_eq!(
+            selection.bounds(),
+            Some(GridAab::from_lower_size([2, 3, 4], [1, 1, 1]))
+        );
+        selection.extend([0, 3, 5]);
+        assert_eq!(
+            selection.bounds(),
+            Some(GridAab::from_lower_size([0, 3, 4], [3, 1, 2]))
+        );
+        assert!(selection.contains([1, 3, 4]));
+        assert_eq!(selection.cubes().count(), 6);
+    }
+
+    #[test]
+    fn extend_cubes() {
+        let mut universe = Universe::new();
+        let space = universe.insert_anonymous(Space::empty_positive(10, 10, 10));
+        let mut selection = Selection::from_cubes(space, []);
+        selection.extend([2, 3, 4]);
+        selection.extend([0, 3, 5]);
+        assert!(!selection.contains([1, 3, 4]));
+        assert_eq!(selection.cubes().count(), 2);
+        assert_eq!(
+            selection.bounds(),
+            Some(GridAab::from_lower_size([0, 3, 4], [3, 1, 2]))
+        );
+    }
+
+    #[test]
+    fn fill_replace_copy() {
+        let [block1, block2] = make_some_blocks();
+        let mut universe = Universe::new();
+        let space = universe.insert_anonymous(Space::empty_positive(4, 1, 1));
+        let selection = Selection::from_box(
+            space.clone(),
+            GridAab::from_lower_size([0, 0, 0], [2, 1, 1]),
+        );
+        let run = |universe: &mut Universe, txn: UniverseTransaction| {
+            txn.execute(universe, &mut transaction::no_outputs).unwrap()
+        };
+
+        run(&mut universe, selection.fill(&block1).unwrap());
+        run(
+            &mut universe,
+            Selection::from_cubes(space.clone(), [GridPoint::new(1, 0, 0)])
+                .fill(&block2)
+                .unwrap(),
+        );
+        run(&mut universe, selection.replace(&block1, &block2).unwrap());
+        run(
+            &mut universe,
+            selection.copy_to(GridVector::new(2, 0, 0)).unwrap(),
+        );
+
+        let space = space.read().unwrap();
+        for x in 0..4 {
+            assert_eq!(space[[x, 0, 0]], block2, "x = {x}");
+        }
+    }
+
+    #[test]
+    fn fill_outside_space() {
+        let [block] = make_some_blocks();
+        let mut universe = Universe::new();
+        let space = universe.insert_anonymous(Space::empty_positive(2, 1, 1));
+        let selection = Selection::from_box(
+            space.clone(),
+            GridAab::from_lower_size([-1, 0, 0], [4, 1, 1]),
+        );
+        selection
+            .fill(&block)
+            .unwrap()
+            .execute(&mut universe, &mut transaction::no_outputs)
+            .unwrap();
+        let space = space.read().unwrap();
+        assert_eq!(space[[0, 0, 0]], block);
+        assert_eq!(space[[1, 0, 0]], block);
+    }
+
+    #[test]
+    fn copy_out_of_range() {
+        let mut universe = Universe::new();
+        let space = universe.insert_anonymous(Space::empty_positive(2, 1, 1));
+        let selection = Selection::from_cubes(space, [GridPoint::new(1, 0, 0)]);
+        let offset = GridVector::new(GridCoordinate::MAX, 0, 0);
+        assert_eq!(
+            selection.copy_to(offset),
+            Err(SelectionError::OffsetOutOfRange {
+                cube: GridPoint::new(1, 0, 0),
+                offset
+            })
+        );
+    }
+}
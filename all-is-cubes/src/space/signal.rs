@@ -0,0 +1,116 @@
+//! Propagation of boolean signals between cubes, as specified by [`BlockSignal`].
+
+use std::collections::BTreeSet;
+
+use crate::block::BlockSignal;
+use crate::math::{Face6, GridCoordinate, GridPoint};
+use crate::space::{Space, SpaceTransaction};
+use crate::transaction::Merge as _;
+
+impl Space {
+    /// Recomputes which cubes are powered, and returns a transaction which paints the
+    /// brushes of every [`BlockSignal::Sink`] whose level changed (or which was placed
+    /// since the last update) and which reacts to its new level.
+    pub(super) fn update_signals(&mut self) -> SpaceTransaction {
+        let new_signal_cubes = std::mem::take(&mut self.new_signal_cubes);
+        if self.signal_cubes.is_empty() && self.powered_cubes.is_empty() {
+            return SpaceTransaction::default();
+        }
+
+        // Forget cubes whose blocks have since been replaced by non-signal blocks.
+        let mut signal_cubes = std::mem::take(&mut self.signal_cubes);
+        signal_cubes
+            .retain(|&cube| self.get_evaluated(cube).attributes.signal != BlockSignal::None);
+        self.signal_cubes = signal_cubes;
+
+        // Flood fill from the sources through the wires.
+        let mut powered: BTreeSet<[GridCoordinate; 3]> = BTreeSet::new();
+        let mut to_visit: Vec<GridPoint> = self
+            .signal_cubes
+            .iter()
+            .map(|&cube| GridPoint::from(cube))
+            .filter(|&cube| self.get_evaluated(cube).attributes.signal == BlockSignal::Source)
+            .collect();
+        for &cube in &to_visit {
+            powered.insert(cube.into());
+        }
+        while let Some(cube) = to_visit.pop() {
+            for face in Face6::ALL {
+                let neighbor = cube + face.normal_vector();
+                let neighbor_key: [GridCoordinate; 3] = neighbor.into();
+                if powered.contains(&neighbor_key) {
+                    continue;
+                }
+                match self.get_evaluated(neighbor).attributes.signal {
+                    BlockSignal::Wire => {
+                        powered.insert(neighbor_key);
+                        to_visit.push(neighbor);
+                    }
+                    BlockSignal::Sink { .. } => {
+                        powered.insert(neighbor_key);
+                    }
+                    BlockSignal::None | BlockSignal::Source => {}
+                }
+            }
+        }
+
+        // Sinks whose level did not change have already reacted to it, so skip them
+        // rather than repainting every sink on every step.
+        let mut txn = SpaceTransaction::default();
+        for &cube in &self.signal_cubes {
+            let is_powered = powered.contains(&cube);
+            let was_powered = self.powered_cubes.contains(&cube);
+            if is_powered == was_powered && !new_signal_cubes.contains(&cube) {
+                continue;
+            }
+            if let BlockSignal::Sink {
+                powered: ref when_powered,
+                unpowered: ref when_unpowered,
+            } = self.get_evaluated(cube).attributes.signal
+            {
+                let brush = if is_powered {
+                    when_powered
+                } else {
+                    when_unpowered
+                };
+                if let Some(brush) = brush {
+                    let sink_txn = brush.paint_transaction(cube.into()).nonconserved();
+                    // If two sinks' brushes overlap, the one at the lesser cube wins,
+                    // since they are visited in order.
+                    if let Ok(check) = txn.check_merge(&sink_txn) {
+                        txn = txn.commit_merge(sink_txn, check);
+                    }
+                }
+            }
+        }
+
+        self.powered_cubes = powered;
+        txn
+    }
+
+    /// Returns whether the signal level at the given cube is currently powered,
+    /// as of the last [`Space::step()`].
+    ///
+    /// See [`BlockSignal`] for how signals propagate.
+    pub fn is_powered(&self, position: impl Into<GridPoint>) -> bool {
+        let position: GridPoint = position.into();
+        let key: [GridCoordinate; 3] = position.into();
+        self.powered_cubes.contains(&key)
+    }
+
+    /// Returns the cubes which are currently powered, in order, for serialization.
+    pub(crate) fn powered_cubes(&self) -> impl Iterator<Item = [GridCoordinate; 3]> + '_ {
+        self.powered_cubes.iter().copied()
+    }
+
+    /// Restores signal levels saved by serialization. The sinks in the space are taken
+    /// to have already reacted to these levels, so they will next be painted when the
+    /// levels change.
+    pub(crate) fn restore_powered_cubes(
+        &mut self,
+        powered: impl IntoIterator<Item = [GridCoordinate; 3]>,
+    ) {
+        self.powered_cubes = powered.into_iter().collect();
+        self.new_signal_cubes.clear();
+    }
+}
@@ -0,0 +1,474 @@
+//! [`Sky`]: what is seen beyond the bounds of a [`Space`], and lights it.
+
+use std::fmt;
+use std::sync::Arc;
+
+use cgmath::{InnerSpace as _, Vector3};
+
+use crate::math::{Face6, FaceMap, FreeCoordinate, NotNan, Rgb};
+
+#[cfg(doc)]
+use crate::space::{Space, SpacePhysics};
+
+/// The appearance of everything outside of a [`Space`]: renderers draw it behind all of
+/// the space's blocks, and light arriving from outside the space has its color.
+///
+/// A space's sky is stored in [`SpacePhysics::sky`].
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[non_exhaustive]
+pub enum Sky {
+    /// The same color in every direction.
+    Uniform(Rgb),
+    /// Colors which vary with elevation above the horizon, optionally with a sun.
+    Gradient(SkyGradient),
+    /// Colors given by a [`CubeMap`] image, such as one provided by the user.
+    CubeMap(Arc<CubeMap>),
+}
+
+impl Sky {
+    /// Returns the color of the sky seen when looking in the given direction, which
+    /// need not be normalized.
+    ///
+    /// If `direction` is zero, returns the [mean](Self::mean) color.
+    pub fn sample(&self, direction: Vector3<FreeCoordinate>) -> Rgb {
+        match self {
+            &Sky::Uniform(color) => color,
+            _ if direction.magnitude2() == 0.0 || !direction.magnitude2().is_finite() => {
+                self.mean()
+            }
+            Sky::Gradient(gradient) => gradient.sample(direction.normalize()),
+            Sky::CubeMap(cube_map) => cube_map.sample(direction),
+        }
+    }
+
+    /// Returns the average color of the sky over all directions.
+    ///
+    /// This is used where direction is not taken into account, such as for the light of
+    /// cubes outside the space's bounds.
+    pub fn mean(&self) -> Rgb {
+        match self {
+            &Sky::Uniform(color) => color,
+            Sky::Gradient(gradient) => gradient.mean(),
+            Sky::CubeMap(cube_map) => cube_map.mean,
+        }
+    }
+
+    /// Returns the average color of the sky in horizontal directions, not including any
+    /// sun.
+    ///
+    /// This is used as the color of fog, since distant objects fade into the horizon.
+    pub fn horizon(&self) -> Rgb {
+        match self {
+            &Sky::Uniform(color) => color,
+            Sky::Gradient(gradient) => gradient.horizon,
+            Sky::CubeMap(cube_map) => cube_map.horizon,
+        }
+    }
+}
+
+impl From<Rgb> for Sky {
+    fn from(color: Rgb) -> Self {
+        Sky::Uniform(color)
+    }
+}
+
+impl From<SkyGradient> for Sky {
+    fn from(gradient: SkyGradient) -> Self {
+        Sky::Gradient(gradient)
+    }
+}
+
+impl From<CubeMap> for Sky {
+    fn from(cube_map: CubeMap) -> Self {
+        Sky::CubeMap(Arc::new(cube_map))
+    }
+}
+
+/// A procedurally generated [`Sky`] whose color blends linearly, by the vertical
+/// component of the view direction, from the [`horizon`](Self::horizon) color to the
+/// [`zenith`](Self::zenith) color above and the [`nadir`](Self::nadir) color below.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[allow(clippy::exhaustive_structs)]
+pub struct SkyGradient {
+    /// Color straight up.
+    pub zenith: Rgb,
+    /// Color at the horizon.
+    pub horizon: Rgb,
+    /// Color straight down.
+    pub nadir: Rgb,
+    /// A disc of light which is added to the gradient, if any.
+    pub sun: Option<Sun>,
+}
+
+impl SkyGradient {
+    /// `direction` must be normalized.
+    fn sample(&self, direction: Vector3<FreeCoordinate>) -> Rgb {
+        let up = direction.y as f32;
+        let mut color = if up >= 0.0 {
+            self.horizon * (1.0 - up) + self.zenith * up
+        } else {
+            self.horizon * (1.0 + up) + self.nadir * -up
+        };
+        if let Some(sun) = &self.sun {
+            if direction.dot(sun.normalized_direction()) >= sun.angular_radius_cos() {
+                color += sun.color;
+            }
+        }
+        color
+    }
+
+    fn mean(&self) -> Rgb {
+        // Over the sphere, the vertical component of the direction is uniformly
+        // distributed, so each half of the gradient averages to the midpoint of its
+        // colors. A sun adds its color times the fraction of the sphere it covers.
+        let mut mean = self.horizon * 0.5 + self.zenith * 0.25 + self.nadir * 0.25;
+        if let Some(sun) = &self.sun {
+            mean += sun.color * ((1.0 - sun.angular_radius_cos()) / 2.0) as f32;
+        }
+        mean
+    }
+}
+
+/// A disc of light in a [`SkyGradient`].
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+#[allow(clippy::exhaustive_structs)]
+pub struct Sun {
+    /// Direction from the space toward the center of the sun. Need not be normalized.
+    /// If it is zero, the sun is not drawn, but still contributes to the mean color.
+    pub direction: Vector3<NotNan<FreeCoordinate>>,
+    /// Color added to the sky within the sun's disc.
+    pub color: Rgb,
+    /// Angular radius of the sun's disc, in degrees.
+    pub angular_radius: NotNan<FreeCoordinate>,
+}
+
+impl Sun {
+    fn normalized_direction(&self) -> Vector3<FreeCoordinate> {
+        self.direction.map(NotNan::into_inner).normalize()
+    }
+
+    fn angular_radius_cos(&self) -> FreeCoordinate {
+        self.angular_radius.into_inner().to_radians().cos()
+    }
+}
+
+/// Six square images, one for each [`Face6`] direction, which together give a color to
+/// every direction, as used by [`Sky::CubeMap`].
+///
+/// The layout is the conventional one for GPU cube map textures, so that the images can
+/// be used without rearrangement. A direction is assigned to the face along whose axis it
+/// has the largest component; then, dividing the other two components by the magnitude
+/// of that one gives coordinates `s` and `t` in the range −1 to 1, which select a texel
+/// of that face's image, whose rows are stored from `t = -1` to `t = 1` and whose
+/// columns from `s = -1` to `s = 1`:
+///
+/// | Face | `s` | `t` |
+/// |------|-----|-----|
+/// | [`PX`](Face6::PX) | −z | −y |
+/// | [`NX`](Face6::NX) | +z | −y |
+/// | [`PY`](Face6::PY) | +x | +z |
+/// | [`NY`](Face6::NY) | +x | −z |
+/// | [`PZ`](Face6::PZ) | +x | −y |
+/// | [`NZ`](Face6::NZ) | −x | −y |
+#[derive(Clone, Eq, Hash, PartialEq)]
+pub struct CubeMap {
+    resolution: u16,
+    faces: FaceMap<Box<[Rgb]>>,
+    /// Cached mean of all texels.
+    mean: Rgb,
+    /// Cached mean of the texels in horizontal directions.
+    horizon: Rgb,
+}
+
+impl CubeMap {
+    /// Constructs a [`CubeMap`] from images of `resolution` × `resolution` texels, in
+    /// row-major order.
+    ///
+    /// Returns an error if `resolution` is zero or any image has the wrong number of
+    /// texels.
+    pub fn new(resolution: u16, faces: FaceMap<Box<[Rgb]>>) -> Result<Self, CubeMapError> {
+        let expected_length = usize::from(resolution).pow(2);
+        if resolution == 0 {
+            return Err(CubeMapError::ZeroResolution);
+        }
+        for (face, image) in faces.iter() {
+            if image.len() != expected_length {
+                return Err(CubeMapError::WrongLength {
+                    face,
+                    resolution,
+                    length: image.len(),
+                });
+            }
+        }
+
+        let mean = faces
+            .values()
+            .flat_map(|image| image.iter().copied())
+            .sum::<Rgb>()
+            * (1.0 / (expected_length * 6) as f32);
+        let mut cube_map = Self {
+            resolution,
+            faces,
+            mean,
+            horizon: Rgb::ZERO,
+        };
+
+        // Sample enough directions around the horizon to visit every texel it crosses.
+        let samples = u32::from(resolution) * 8;
+        cube_map.horizon = (0..samples)
+            .map(|i| {
+                let angle =
+                    FreeCoordinate::from(i) / FreeCoordinate::from(samples) * std::f64::consts::TAU;
+                cube_map.sample(Vector3::new(angle.cos(), 0.0, angle.sin()))
+            })
+            .sum::<Rgb>()
+            * (1.0 / samples as f32);
+        Ok(cube_map)
+    }
+
+    /// Constructs a [`CubeMap`] by calling `f` with the direction to the center of each
+    /// texel (not normalized).
+    ///
+    /// Panics if `resolution` is zero.
+    pub fn from_fn(resolution: u16, mut f: impl FnMut(Vector3<FreeCoordinate>) -> Rgb) -> Self {
+        let faces = FaceMap::from_fn(|face| {
+            let coordinate = |i: u16| {
+                (FreeCoordinate::from(i) + 0.5) / FreeCoordinate::from(resolution) * 2.0 - 1.0
+            };
+            let mut image = Vec::with_capacity(usize::from(resolution).pow(2));
+            for row in 0..resolution {
+                for column in 0..resolution {
+                    image.push(f(face_direction(face, coordinate(column), coordinate(row))));
+                }
+            }
+            image.into_boxed_slice()
+        });
+        Self::new(resolution, faces).expect("resolution must not be zero")
+    }
+
+    /// Returns the width and height of each face's image.
+    pub fn resolution(&self) -> u16 {
+        self.resolution
+    }
+
+    /// Returns the texels of the given face's image, in row-major order.
+    pub fn face(&self, face: Face6) -> &[Rgb] {
+        &self.faces[face]
+    }
+
+    /// Returns the color of the texel in the given direction, which must not be zero.
+    fn sample(&self, direction: Vector3<FreeCoordinate>) -> Rgb {
+        let Vector3 { x, y, z } = direction;
+        let (face, major, s, t) = if x.abs() >= y.abs() && x.abs() >= z.abs() {
+            if x > 0.0 {
+                (Face6::PX, x, -z, -y)
+            } else {
+                (Face6::NX, x, z, -y)
+            }
+        } else if y.abs() >= z.abs() {
+            if y > 0.0 {
+                (Face6::PY, y, x, z)
+            } else {
+                (Face6::NY, y, x, -z)
+            }
+        } else if z > 0.0 {
+            (Face6::PZ, z, x, -y)
+        } else {
+            (Face6::NZ, z, -x, -y)
+        };
+        let resolution = FreeCoordinate::from(self.resolution);
+        let texel = |c: FreeCoordinate| {
+            // Float-to-int `as` saturates, so this cannot go below zero.
+            (((c / major.abs() + 1.0) / 2.0 * resolution) as usize)
+                .min(usize::from(self.resolution) - 1)
+        };
+        self.faces[face][texel(t) * usize::from(self.resolution) + texel(s)]
+    }
+}
+
+/// Inverse of the table in the documentation of [`CubeMap`].
+fn face_direction(face: Face6, s: FreeCoordinate, t: FreeCoordinate) -> Vector3<FreeCoordinate> {
+    match face {
+        Face6::PX => Vector3::new(1.0, -t, -s),
+        Face6::NX => Vector3::new(-1.0, -t, s),
+        Face6::PY => Vector3::new(s, 1.0, t),
+        Face6::NY => Vector3::new(s, -1.0, -t),
+        Face6::PZ => Vector3::new(s, -t, 1.0),
+        Face6::NZ => Vector3::new(-s, -t, -1.0),
+    }
+}
+
+impl fmt::Debug for CubeMap {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // Don't print all the texels.
+        f.debug_struct("CubeMap")
+            .field("resolution", &self.resolution)
+            .field("mean", &self.mean)
+            .field("horizon", &self.horizon)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(feature = "arbitrary")]
+#[mutants::skip]
+impl<'a> arbitrary::Arbitrary<'a> for Sun {
+    fn arbitrary(u: &mut arbitrary::Unstructured<'a>) -> arbitrary::Result<Self> {
+        Ok(Self {
+            direction: Vector3::new(u.arbitrary()?, u.arbitrary()?, u.arbitrary()?),
+            color: u.arbitrary()?,
+            angular_radius: u.arbitrary()?,
+        })
+    }
+
+    fn size_hint(depth: usize) -> (usize, Option<usize>) {
+        use arbitrary::{size_hint::and_all, Arbitrary};
+        and_all(&[
+            <[f64; 3] as Arbitrary>::size_hint(depth),
+            <Rgb as Arbitrary>::size_hint(depth),
+            <f64 as Arbitrary>::size_hint(depth),
+        ])
+    }
+}
+
+#[cfg(feature = "arbitrary")]
+#[mutants::skip]
+impl<'a> arbitrary::Arbitrary<'a> for CubeMap {
+    fn arbitrary(u: &mut arbitrary::Unstructured<'a>) -> arbitrary::Result<Self> {
+        let resolution = u.int_in_range(1..=4)?;
+        let length = usize::from(resolution).pow(2);
+        let mut image =
+            || -> arbitrary::Result<Box<[Rgb]>> { (0..length).map(|_| u.arbitrary()).collect() };
+        let faces = FaceMap {
+            nx: image()?,
+            ny: image()?,
+            nz: image()?,
+            px: image()?,
+            py: image()?,
+            pz: image()?,
+        };
+        Ok(Self::new(resolution, faces).unwrap())
+    }
+}
+
+/// Error from [`CubeMap::new()`].
+#[derive(Clone, Debug, Eq, Hash, PartialEq, thiserror::Error)]
+#[non_exhaustive]
+pub enum CubeMapError {
+    /// The resolution was zero.
+    #[error("cube map resolution must not be zero")]
+    ZeroResolution,
+    /// An image had the wrong number of texels.
+    #[error("cube map face {face:?} has {length} texels instead of {resolution}×{resolution}")]
+    WrongLength {
+        /// The face whose image was wrong.
+        face: Face6,
+        /// The resolution that was given.
+        resolution: u16,
+        /// The number of texels the image had.
+        length: usize,
+    },
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{notnan, rgb_const};
+
+    #[test]
+    fn gradient_sample_and_mean() {
+        let gradient = SkyGradient {
+            zenith: rgb_const!(0.0, 0.0, 1.0),
+            horizon: rgb_const!(1.0, 1.0, 1.0),
+            nadir: rgb_const!(0.0, 0.0, 0.0),
+            sun: None,
+        };
+        let sky = Sky::from(gradient);
+        assert_eq!(
+            sky.sample(Vector3::new(0.0, 10.0, 0.0)),
+            rgb_const!(0.0, 0.0, 1.0)
+        );
+        assert_eq!(
+            sky.sample(Vector3::new(1.0, 0.0, 0.0)),
+            rgb_const!(1.0, 1.0, 1.0)
+        );
+        assert_eq!(sky.sample(Vector3::new(0.0, -1.0, 0.0)), Rgb::ZERO);
+        assert_eq!(sky.mean(), rgb_const!(0.5, 0.5, 0.75));
+        assert_eq!(sky.sample(Vector3::new(0.0, 0.0, 0.0)), sky.mean());
+        assert_eq!(sky.horizon(), rgb_const!(1.0, 1.0, 1.0));
+    }
+
+    #[test]
+    fn gradient_sun() {
+        let sky = Sky::from(SkyGradient {
+            zenith: Rgb::ZERO,
+            horizon: Rgb::ZERO,
+            nadir: Rgb::ZERO,
+            sun: Some(Sun {
+                direction: Vector3::new(notnan!(1.0), notnan!(1.0), notnan!(0.0)),
+                color: Rgb::ONE,
+                angular_radius: notnan!(10.0),
+            }),
+        });
+        assert_eq!(sky.sample(Vector3::new(2.0, 2.0, 0.1)), Rgb::ONE);
+        assert_eq!(sky.sample(Vector3::new(1.0, 0.5, 0.0)), Rgb::ZERO);
+        let fraction = ((1.0 - 10f64.to_radians().cos()) / 2.0) as f32;
+        assert_eq!(sky.mean(), Rgb::ONE * fraction);
+    }
+
+    /// Each texel's center direction samples that texel.
+    #[test]
+    fn cube_map_round_trip() {
+        let mut index = 0;
+        let cube_map = CubeMap::from_fn(3, |_| {
+            index += 1;
+            Rgb::ONE * index as f32
+        });
+        for face in Face6::ALL {
+            for row in 0..3u16 {
+                for column in 0..3u16 {
+                    let c = |i: u16| (FreeCoordinate::from(i) + 0.5) / 3.0 * 2.0 - 1.0;
+                    let direction = face_direction(face, c(column), c(row));
+                    assert_eq!(
+                        cube_map.sample(direction * 7.0),
+                        cube_map.face(face)[usize::from(row * 3 + column)],
+                        "{face:?} {row} {column}"
+                    );
+                }
+            }
+        }
+        // Mean of 1 through 54
+        assert!((cube_map.mean.red().into_inner() - 27.5).abs() < 1e-4);
+    }
+
+    #[test]
+    fn cube_map_horizon() {
+        let cube_map = CubeMap::from_fn(4, |direction| {
+            if direction.y.abs() < 1.0 {
+                Rgb::ONE
+            } else {
+                Rgb::ZERO
+            }
+        });
+        assert_eq!(Sky::from(cube_map).horizon(), Rgb::ONE);
+    }
+
+    #[test]
+    fn cube_map_errors() {
+        let faces =
+            |length: usize| FaceMap::from_fn(|_| vec![Rgb::ZERO; length].into_boxed_slice());
+        assert_eq!(CubeMap::new(0, faces(0)), Err(CubeMapError::ZeroResolution));
+        let mut wrong = faces(4);
+        wrong.py = vec![Rgb::ZERO; 3].into_boxed_slice();
+        assert_eq!(
+            CubeMap::new(2, wrong),
+            Err(CubeMapError::WrongLength {
+                face: Face6::PY,
+                resolution: 2,
+                length: 3
+            })
+        );
+        assert!(CubeMap::new(2, faces(4)).is_ok());
+    }
+}
@@ -0,0 +1,215 @@
+//! [`SpaceSnapshot`] and [`SpaceDelta`]: compact copies of the blocks of a [`Space`] for
+//! sending to another copy of it, such as a client's.
+
+use std::collections::{HashMap, HashSet};
+
+use crate::block::Block;
+use crate::math::{GridAab, GridPoint};
+use crate::space::{BlockIndex, SetCubeError, Space};
+
+/// A copy of the blocks of a [`Space`] as of a particular tick, for sending to a viewer
+/// which does not yet have a copy, such as a client joining a game.
+///
+/// Each distinct block is stored once, in a palette, so that a viewer reconstructing
+/// the space with [`SpaceSnapshot::to_space()`] evaluates each only once; when serialized
+/// to a binary format, the contents are run-length encoded as in saved spaces. Once a
+/// viewer has a snapshot, it may be kept up to date with [`SpaceDelta`]s.
+///
+/// Only the blocks are included, not lighting, behaviors, or other properties.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SpaceSnapshot {
+    pub(crate) tick_number: u64,
+    pub(crate) bounds: GridAab,
+    pub(crate) blocks: Vec<Block>,
+    /// Indices into `blocks`, in the order of [`GridAab::interior_iter()`].
+    pub(crate) contents: Box<[BlockIndex]>,
+}
+
+/// The changes to the blocks of a [`Space`] between two ticks, for updating a viewer's
+/// copy of the space which was obtained from a [`SpaceSnapshot`] or previous deltas.
+///
+/// Deltas must be applied in order; each should be applied only to a copy which is
+/// up to date as of its [`base_tick_number()`](Self::base_tick_number).
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SpaceDelta {
+    pub(crate) base_tick_number: u64,
+    pub(crate) tick_number: u64,
+    /// Blocks placed by this delta. These are not the same as the space's own block
+    /// indices, which differ between copies of a space.
+    pub(crate) blocks: Vec<Block>,
+    /// Each changed cube and the index into `blocks` of its new block.
+    pub(crate) cubes: Vec<(GridPoint, BlockIndex)>,
+}
+
+impl SpaceSnapshot {
+    /// Copies the blocks of `space`, which is as of tick number `tick_number` on the
+    /// sender's count of ticks.
+    pub fn new(space: &Space, tick_number: u64) -> Self {
+        let bounds = space.bounds();
+        let contents = space
+            .extract(bounds, |index, _, _| {
+                index.expect("shouldn't happen: snapshot went out of bounds")
+            })
+            .into_elements();
+        Self {
+            tick_number,
+            bounds,
+            blocks: space
+                .block_data()
+                .iter()
+                .map(|data| data.block().clone())
+                .collect(),
+            contents,
+        }
+    }
+
+    /// Returns the tick number the snapshot was taken at.
+    pub fn tick_number(&self) -> u64 {
+        self.tick_number
+    }
+
+    /// Returns the bounds of the space.
+    pub fn bounds(&self) -> GridAab {
+        self.bounds
+    }
+
+    /// Constructs a [`Space`] containing the blocks of the snapshot.
+    ///
+    /// Returns an error if a block could not be evaluated or placed.
+    pub fn to_space(&self) -> Result<Space, SetCubeError> {
+        let mut space = Space::builder(self.bounds).build();
+        for (cube, &block_index) in self.bounds.interior_iter().zip(self.contents.iter()) {
+            space.set(cube, &self.blocks[usize::from(block_index)])?;
+        }
+        Ok(space)
+    }
+}
+
+impl SpaceDelta {
+    /// Records the current blocks of `space` in the given cubes, which should be all of
+    /// the cubes that changed between tick numbers `base_tick_number` and `tick_number`,
+    /// as reported by [`SpaceChange::Block`].
+    ///
+    /// Duplicate and out-of-bounds cubes are ignored. If the space reports
+    /// [`SpaceChange::EveryBlock`], a new [`SpaceSnapshot`] should be sent instead.
+    ///
+    /// [`SpaceChange::Block`]: crate::space::SpaceChange::Block
+    /// [`SpaceChange::EveryBlock`]: crate::space::SpaceChange::EveryBlock
+    pub fn new(
+        space: &Space,
+        base_tick_number: u64,
+        tick_number: u64,
+        changed_cubes: impl IntoIterator<Item = GridPoint>,
+    ) -> Self {
+        let block_data = space.block_data();
+        let mut seen: HashSet<GridPoint> = HashSet::new();
+        let mut palette: HashMap<BlockIndex, BlockIndex> = HashMap::new();
+        let mut blocks: Vec<Block> = Vec::new();
+        let mut cubes: Vec<(GridPoint, BlockIndex)> = Vec::new();
+        for cube in changed_cubes {
+            let Some(space_index) = space.get_block_index(cube) else {
+                continue;
+            };
+            if !seen.insert(cube) {
+                continue;
+            }
+            let delta_index = *palette.entry(space_index).or_insert_with(|| {
+                blocks.push(block_data[usize::from(space_index)].block().clone());
+                // Cannot overflow since there are no more distinct blocks than the
+                // space has.
+                (blocks.len() - 1) as BlockIndex
+            });
+            cubes.push((cube, delta_index));
+        }
+        Self {
+            base_tick_number,
+            tick_number,
+            blocks,
+            cubes,
+        }
+    }
+
+    /// Returns the tick number the delta is relative to.
+    pub fn base_tick_number(&self) -> u64 {
+        self.base_tick_number
+    }
+
+    /// Returns the tick number the delta brings a copy of the space up to date with.
+    pub fn tick_number(&self) -> u64 {
+        self.tick_number
+    }
+
+    /// Returns whether the delta contains no changes.
+    pub fn is_empty(&self) -> bool {
+        self.cubes.is_empty()
+    }
+
+    /// Places the changed blocks in `space`.
+    ///
+    /// Returns an error if a block could not be evaluated or placed; in that case, the
+    /// changes before it will have been applied and those after it will not.
+    pub fn apply(&self, space: &mut Space) -> Result<(), SetCubeError> {
+        for &(cube, block_index) in &self.cubes {
+            space.set(cube, &self.blocks[usize::from(block_index)])?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::block::AIR;
+    use crate::content::make_some_blocks;
+
+    #[test]
+    fn snapshot_round_trip() {
+        let [block_0, block_1] = make_some_blocks();
+        let mut space = Space::empty_positive(3, 2, 1);
+        space.set([0, 0, 0], &block_0).unwrap();
+        space.set([2, 1, 0], &block_1).unwrap();
+
+        let snapshot = SpaceSnapshot::new(&space, 10);
+        assert_eq!(snapshot.tick_number(), 10);
+        assert_eq!(snapshot.bounds(), space.bounds());
+        let copy = snapshot.to_space().unwrap();
+        assert_eq!(copy.bounds(), space.bounds());
+        for cube in space.bounds().interior_iter() {
+            assert_eq!(copy[cube], space[cube], "{cube:?}");
+        }
+    }
+
+    #[test]
+    fn delta_updates_copy() {
+        let [block_0, block_1] = make_some_blocks();
+        let mut space = Space::empty_positive(3, 1, 1);
+        space.set([0, 0, 0], &block_0).unwrap();
+        let mut copy = SpaceSnapshot::new(&space, 10).to_space().unwrap();
+
+        space.set([0, 0, 0], AIR).unwrap();
+        space.set([1, 0, 0], &block_1).unwrap();
+        space.set([2, 0, 0], &block_1).unwrap();
+        let delta = SpaceDelta::new(
+            &space,
+            10,
+            11,
+            [[0, 0, 0], [1, 0, 0], [1, 0, 0], [2, 0, 0], [5, 0, 0]].map(GridPoint::from),
+        );
+        assert_eq!(delta.base_tick_number(), 10);
+        assert_eq!(delta.tick_number(), 11);
+        // Duplicate and out-of-bounds cubes are dropped, and blocks stored once.
+        assert_eq!(delta.cubes.len(), 3);
+        assert_eq!(delta.blocks.len(), 2);
+
+        delta.apply(&mut copy).unwrap();
+        assert_eq!(copy[[0, 0, 0]], AIR);
+        assert_eq!(copy[[1, 0, 0]], block_1);
+        assert_eq!(copy[[2, 0, 0]], block_1);
+    }
+
+    #[test]
+    fn empty_delta() {
+        let space = Space::empty_positive(1, 1, 1);
+        assert!(SpaceDelta::new(&space, 0, 1, []).is_empty());
+    }
+}
@@ -6,14 +6,16 @@ use std::sync::Arc;
 use std::{fmt, mem};
 
 use crate::behavior::{self, BehaviorSet, BehaviorSetTransaction};
-use crate::block::Block;
+use crate::block::{Block, AIR};
+use crate::character::Character;
 use crate::drawing::DrawingPlane;
 use crate::math::{GridCoordinate, GridMatrix, GridPoint};
-use crate::space::{ActivatableRegion, GridAab, SetCubeError, Space};
+use crate::space::{ActivatableRegion, GridAab, SetCubeError, Space, Weather};
 use crate::transaction::{
     no_outputs, CommitError, Merge, NoOutput, PreconditionFailed, Transaction, TransactionConflict,
     Transactional,
 };
+use crate::universe::URef;
 use crate::util::{ConciseDebug, CustomFormat as _};
 
 impl Transactional for Space {
@@ -26,6 +28,14 @@ impl Transactional for Space {
 pub struct SpaceTransaction {
     cubes: BTreeMap<[GridCoordinate; 3], CubeTransaction>,
     behaviors: BehaviorSetTransaction<Space>,
+    /// Region the space's bounds are to be enlarged to contain, before any cubes are
+    /// modified.
+    expand: Option<GridAab>,
+    /// Character on whose behalf the modifications are made, which
+    /// [`ProtectedRegion`](super::ProtectedRegion)s are checked against.
+    actor: Option<URef<Character>>,
+    /// New target weather, set after any cubes are modified.
+    weather: Option<Weather>,
 }
 
 impl SpaceTransaction {
@@ -127,6 +137,23 @@ impl SpaceTransaction {
         self
     }
 
+    /// Marks this transaction as being made on behalf of the given character, so that
+    /// it will fail if it modifies any cube in a [`ProtectedRegion`] of the space which
+    /// does not permit that character to do so.
+    ///
+    /// Transactions made on behalf of different characters cannot be merged.
+    ///
+    /// [`ProtectedRegion`]: super::ProtectedRegion
+    pub fn on_behalf_of(mut self, character: URef<Character>) -> Self {
+        self.actor = Some(character);
+        self
+    }
+
+    /// Returns the character this transaction is made on behalf of, if any.
+    pub fn actor(&self) -> Option<&URef<Character>> {
+        self.actor.as_ref()
+    }
+
     fn single(cube: impl Into<GridPoint>, transaction: CubeTransaction) -> Self {
         let cube: GridPoint = cube.into();
         let mut cubes = BTreeMap::new();
@@ -157,6 +184,29 @@ impl SpaceTransaction {
         ))
     }
 
+    /// Enlarge the space so that its bounds contain `bounds`, as [`Space::expand()`] does.
+    ///
+    /// The expansion happens before any cubes are modified, so a transaction merged with
+    /// this one may set cubes within `bounds` even if they are outside the space's
+    /// current bounds; such cubes are considered to contain [`AIR`] when checking the
+    /// transaction.
+    pub fn expand(bounds: GridAab) -> Self {
+        Self {
+            expand: Some(bounds),
+            ..Default::default()
+        }
+    }
+
+    /// Change the weather of the space, as [`Space::set_weather()`] does.
+    ///
+    /// Two transactions which set different weather cannot be merged.
+    pub fn set_weather(weather: Weather) -> Self {
+        Self {
+            weather: Some(weather),
+            ..Default::default()
+        }
+    }
+
     pub(crate) fn activate_block(cube: GridPoint) -> Self {
         Self::single(cube, CubeTransaction::ACTIVATE)
     }
@@ -173,6 +223,9 @@ impl SpaceTransaction {
         let Self {
             cubes,
             behaviors: _,
+            expand: _,
+            actor: _,
+            weather: _,
         } = self;
         let mut bounds: Option<GridAab> = None;
 
@@ -196,9 +249,20 @@ impl SpaceTransaction {
         let Self {
             cubes: _,
             behaviors,
+            expand,
+            actor: _,
+            weather: _,
         } = self;
         let mut bounds: Option<GridAab> = self.bounds_only_cubes();
 
+        if let Some(expand) = *expand {
+            if let Some(bounds) = &mut bounds {
+                *bounds = (*bounds).union(expand).unwrap();
+            } else {
+                bounds = Some(expand);
+            }
+        }
+
         for attachment in behaviors.attachments_affected() {
             if let Some(bounds) = &mut bounds {
                 *bounds = (*bounds).union(attachment.bounds).unwrap();
@@ -221,23 +285,39 @@ impl Transaction<Space> for SpaceTransaction {
             &cube,
             CubeTransaction {
                 old,
-                new: _,
+                new,
                 conserved,
                 activate: _,
             },
         ) in &self.cubes
         {
-            if let Some(cube_index) = space.bounds().index(cube) {
+            // TODO: Also restrict behaviors attached within protected regions.
+            if new.is_some() && !space.permits_modification(cube.into(), self.actor.as_ref()) {
+                return Err(PreconditionFailed {
+                    location: "Space",
+                    problem: "cube is in a protected region",
+                });
+            }
+            if let Some(block_index) = space.get_block_index(cube) {
                 if let Some(old) = old {
-                    // Raw lookup because we already computed the index for a bounds check
-                    // (TODO: Put this in a function, like get_block_index)
-                    if space.block_data[space.contents[cube_index] as usize].block != *old {
+                    if space.block_data[usize::from(block_index)].block != *old {
                         return Err(PreconditionFailed {
                             location: "Space",
                             problem: "existing block not as expected",
                         });
                     }
                 }
+            } else if self
+                .expand
+                .map_or(false, |expand| expand.contains_cube(cube))
+            {
+                // The cube will be added to the space, containing AIR, before it is set.
+                if old.as_ref().map_or(false, |old| *old != AIR) {
+                    return Err(PreconditionFailed {
+                        location: "Space",
+                        problem: "existing block not as expected",
+                    });
+                }
             } else {
                 if *conserved || old.is_some() {
                     // It is an error for conserved cube txns to be out of bounds,
@@ -261,6 +341,11 @@ impl Transaction<Space> for SpaceTransaction {
         check: Self::CommitCheck,
         _outputs: &mut dyn FnMut(Self::Output),
     ) -> Result<(), CommitError> {
+        if let Some(expand) = self.expand {
+            space
+                .expand(expand)
+                .map_err(CommitError::catch::<Self, _>)?;
+        }
         let mut to_activate = Vec::new();
         for (
             &cube,
@@ -290,6 +375,9 @@ impl Transaction<Space> for SpaceTransaction {
         self.behaviors
             .commit(&mut space.behaviors, check, &mut no_outputs)
             .map_err(|e| e.context("behaviors".into()))?;
+        if let Some(weather) = self.weather {
+            space.set_weather(weather);
+        }
         if !to_activate.is_empty() {
             'b: for query_item in space.behaviors.query::<ActivatableRegion>() {
                 // TODO: error return from the function? error report for nonexistence?
@@ -310,6 +398,21 @@ impl Merge for SpaceTransaction {
     type MergeCheck = <BehaviorSetTransaction<Space> as Merge>::MergeCheck;
 
     fn check_merge(&self, other: &Self) -> Result<Self::MergeCheck, TransactionConflict> {
+        if matches!((&self.actor, &other.actor), (Some(a), Some(b)) if a != b) {
+            // The merged transaction could not be checked against both characters'
+            // permissions.
+            return Err(TransactionConflict {});
+        }
+        if let (Some(a), Some(b)) = (self.expand, other.expand) {
+            if a.union(b).is_err() {
+                return Err(TransactionConflict {});
+            }
+        }
+        if let (Some(a), Some(b)) = (self.weather, other.weather) {
+            if a != b {
+                return Err(TransactionConflict {});
+            }
+        }
         let mut cubes1 = &self.cubes;
         let mut cubes2 = &other.cubes;
         if cubes1.len() > cubes2.len() {
@@ -345,6 +448,12 @@ impl Merge for SpaceTransaction {
             }
         }
         self.behaviors = self.behaviors.commit_merge(other.behaviors, check);
+        self.expand = match (self.expand, other.expand) {
+            (Some(a), Some(b)) => Some(a.union(b).unwrap()),
+            (a, b) => a.or(b),
+        };
+        self.actor = self.actor.or(other.actor);
+        self.weather = self.weather.or(other.weather);
         self
     }
 }
@@ -363,6 +472,15 @@ impl fmt::Debug for SpaceTransaction {
         if !self.behaviors.is_empty() {
             ds.field("behaviors", &self.behaviors);
         }
+        if let Some(expand) = &self.expand {
+            ds.field("expand", expand);
+        }
+        if let Some(actor) = &self.actor {
+            ds.field("actor", actor);
+        }
+        if let Some(weather) = &self.weather {
+            ds.field("weather", weather);
+        }
         ds.finish()
     }
 }
@@ -438,13 +556,17 @@ mod tests {
     use std::sync::Arc;
 
     use pretty_assertions::assert_eq;
+    use rand::SeedableRng as _;
+    use rand_xoshiro::Xoshiro256Plus;
 
     use crate::behavior::NoopBehavior;
     use crate::block::AIR;
     use crate::content::make_some_blocks;
     use crate::inv::EphemeralOpaque;
     use crate::math::GridAab;
+    use crate::space::{Precipitation, ProtectedRegion, RegionAccess};
     use crate::transaction::{no_outputs, TransactionTester};
+    use crate::universe::Universe;
 
     use super::*;
 
@@ -466,6 +588,29 @@ mod tests {
             .unwrap();
     }
 
+    #[test]
+    fn expand_then_set() {
+        let [block] = make_some_blocks();
+        let mut space = Space::empty_positive(1, 1, 1);
+        let expand = SpaceTransaction::expand(GridAab::from_lower_size([1, 0, 0], [1, 1, 1]));
+        SpaceTransaction::set_cube([1, 0, 0], Some(block.clone()), Some(block.clone()))
+            .merge(expand.clone())
+            .unwrap()
+            .check(&space)
+            .unwrap_err();
+
+        SpaceTransaction::set_cube([1, 0, 0], Some(AIR), Some(block.clone()))
+            .merge(expand)
+            .unwrap()
+            .execute(&mut space, &mut no_outputs)
+            .unwrap();
+        assert_eq!(
+            space.bounds(),
+            GridAab::from_lower_size([0, 0, 0], [2, 1, 1])
+        );
+        assert_eq!(space[[1, 0, 0]], block);
+    }
+
     #[test]
     fn compare_out_of_bounds_conserved_fails() {
         let [block] = make_some_blocks();
@@ -588,6 +733,102 @@ mod tests {
         assert_eq!(t1.clone(), t1.clone().merge(t2).unwrap());
     }
 
+    #[test]
+    fn merge_rejects_different_actors() {
+        let [b1, b2] = make_some_blocks();
+        let mut universe = Universe::new();
+        let space = universe.insert_anonymous(Space::empty_positive(2, 1, 1));
+        let c1 = universe.insert_anonymous(Character::spawn_default(space.clone()));
+        let c2 = universe.insert_anonymous(Character::spawn_default(space));
+        let t1 = SpaceTransaction::set_cube([0, 0, 0], None, Some(b1));
+        let t2 = SpaceTransaction::set_cube([1, 0, 0], None, Some(b2));
+
+        t1.clone()
+            .on_behalf_of(c1.clone())
+            .merge(t2.clone().on_behalf_of(c2))
+            .unwrap_err();
+        assert_eq!(
+            t1.clone()
+                .on_behalf_of(c1.clone())
+                .merge(t2.clone())
+                .unwrap()
+                .actor(),
+            Some(&c1)
+        );
+        assert_eq!(
+            t1.merge(t2.on_behalf_of(c1.clone())).unwrap().actor(),
+            Some(&c1)
+        );
+    }
+
+    #[test]
+    fn merge_rejects_different_weather() {
+        let rain = Weather::new(Some(Precipitation::Rain), 1.0, 0.0);
+        let t1 = SpaceTransaction::set_weather(rain);
+        let t2 = SpaceTransaction::set_weather(Weather::CLEAR);
+
+        t1.clone().merge(t2).unwrap_err();
+        assert_eq!(t1.clone().merge(t1.clone()).unwrap(), t1);
+    }
+
+    #[test]
+    fn set_weather() {
+        let rain = Weather::new(Some(Precipitation::Rain), 1.0, 0.0);
+        let mut space = Space::empty_positive(1, 1, 1);
+        SpaceTransaction::set_weather(rain)
+            .execute(&mut space, &mut no_outputs)
+            .unwrap();
+        assert_eq!(space.target_weather(), &rain);
+        assert_eq!(space.weather(), &Weather::CLEAR);
+    }
+
+    #[test]
+    fn protected_region() {
+        let [block] = make_some_blocks();
+        let mut universe = Universe::new();
+        let space_ref = universe.insert_anonymous(Space::empty_positive(2, 1, 1));
+        let owner = universe.insert_anonymous(Character::spawn_default(space_ref.clone()));
+        let other = universe.insert_anonymous(Character::spawn_default(space_ref.clone()));
+        let mut space = Space::empty_positive(2, 1, 1);
+        let owned = ProtectedRegion::new(
+            GridAab::single_cube(GridPoint::new(0, 0, 0)),
+            RegionAccess::Owner(owner.clone()),
+        );
+        space.add_protected_region(owned.clone());
+        space.add_protected_region(ProtectedRegion::new(
+            GridAab::single_cube(GridPoint::new(1, 0, 0)),
+            RegionAccess::ReadOnly,
+        ));
+        let set =
+            |cube: [GridCoordinate; 3]| SpaceTransaction::set_cube(cube, None, Some(block.clone()));
+
+        // Unattributed modifications are not restricted.
+        set([0, 0, 0]).check(&space).unwrap();
+        set([1, 0, 0]).check(&space).unwrap();
+        // The owner may modify only their own region.
+        set([0, 0, 0])
+            .on_behalf_of(owner.clone())
+            .check(&space)
+            .unwrap();
+        set([1, 0, 0])
+            .on_behalf_of(owner.clone())
+            .check(&space)
+            .unwrap_err();
+        set([0, 0, 0])
+            .on_behalf_of(other.clone())
+            .check(&space)
+            .unwrap_err();
+        // Preconditions alone are not modifications.
+        SpaceTransaction::set_cube([1, 0, 0], Some(AIR), None)
+            .on_behalf_of(other.clone())
+            .check(&space)
+            .unwrap();
+
+        assert!(space.remove_protected_region(&owned));
+        assert!(!space.remove_protected_region(&owned));
+        set([0, 0, 0]).on_behalf_of(other).check(&space).unwrap();
+    }
+
     #[test]
     fn activate() {
         let mut space = Space::empty_positive(1, 1, 1);
@@ -684,6 +925,43 @@ mod tests {
             .test();
     }
 
+    #[test]
+    fn random_sequences() {
+        let [b1, b2] = make_some_blocks();
+        let expect_block = |cube: [GridCoordinate; 3], block: &'static str, expected: &Block| {
+            let expected = expected.clone();
+            move |_: &Space, after: &Space| {
+                if after[cube] != expected {
+                    return Err(format!("did not set {block}").into());
+                }
+                Ok(())
+            }
+        };
+        TransactionTester::new()
+            .transaction(
+                SpaceTransaction::set_cube([0, 0, 0], None, Some(b1.clone())),
+                expect_block([0, 0, 0], "b1", &b1),
+            )
+            .transaction(
+                SpaceTransaction::set_cube([0, 0, 0], Some(b1.clone()), Some(b2.clone())),
+                expect_block([0, 0, 0], "b2", &b2),
+            )
+            .transaction(
+                SpaceTransaction::set_cube([1, 0, 0], Some(AIR), Some(b2.clone())),
+                expect_block([1, 0, 0], "b2", &b2),
+            )
+            .transaction(
+                SpaceTransaction::set_cube([1, 0, 0], Some(b2.clone()), Some(AIR)),
+                expect_block([1, 0, 0], "AIR", &AIR),
+            )
+            .target(|| Space::empty_positive(2, 1, 1))
+            .invariant(|space| {
+                space.consistency_check();
+                Ok(())
+            })
+            .test_random_sequences(&mut Xoshiro256Plus::seed_from_u64(0), 20, 10);
+    }
+
     #[test]
     fn bounds_empty() {
         assert_eq!(SpaceTransaction::default().bounds(), None);
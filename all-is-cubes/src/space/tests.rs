@@ -6,14 +6,16 @@ use cgmath::EuclideanSpace as _;
 use indoc::indoc;
 
 use crate::block::{
-    Block, BlockDef, BlockDefTransaction, EvalBlockError, Primitive, Resolution::*, AIR,
+    Block, BlockDef, BlockDefTransaction, BlockSignal, EvalBlockError, Primitive, Resolution::*,
+    AIR,
 };
 use crate::content::make_some_blocks;
 use crate::drawing::VoxelBrush;
 use crate::listen::{Listen as _, Sink};
 use crate::math::{GridCoordinate, GridPoint, Rgba};
 use crate::space::{
-    GridAab, LightPhysics, PackedLight, SetCubeError, Space, SpaceChange, SpacePhysics,
+    GridAab, LightPhysics, PackedLight, Precipitation, SetCubeError, Space, SpaceChange,
+    SpacePhysics, Weather,
 };
 use crate::time::{practically_infinite_deadline, Tick};
 use crate::transaction;
@@ -312,6 +314,53 @@ fn fill_uniform_entire_space() {
     }
 }
 
+#[test]
+fn expand_keeps_blocks_and_light() {
+    let [block] = make_some_blocks();
+    let old_bounds = GridAab::from_lower_size([0, 0, 0], [20, 3, 2]);
+    let mut space = Space::empty(old_bounds);
+    space
+        .fill_uniform(GridAab::from_lower_size([0, 0, 0], [20, 1, 2]), &block)
+        .unwrap();
+    space.evaluate_light(0, |_| {});
+    let old_light: Vec<PackedLight> = old_bounds
+        .interior_iter()
+        .map(|cube| space.get_lighting(cube))
+        .collect();
+    let sink = Sink::new();
+    space.listen(sink.listener());
+
+    space
+        .expand(GridAab::from_lower_size([-20, 0, 0], [1, 1, 1]))
+        .unwrap();
+
+    let new_bounds = GridAab::from_lower_size([-20, 0, 0], [40, 3, 2]);
+    assert_eq!(space.bounds(), new_bounds);
+    assert_eq!(sink.drain(), vec![SpaceChange::EveryBlock]);
+    space.consistency_check();
+    for cube in new_bounds.interior_iter() {
+        let expected = if cube.x >= 0 && cube.y == 0 {
+            &block
+        } else {
+            &AIR
+        };
+        assert_eq!(&space[cube], expected, "{cube:?}");
+    }
+    for (cube, light) in old_bounds.interior_iter().zip(old_light) {
+        assert_eq!(space.get_lighting(cube), light, "{cube:?}");
+    }
+    // The new cubes are queued to have their light computed.
+    assert_eq!(
+        space.light_update_queue.len(),
+        new_bounds.volume() - old_bounds.volume()
+    );
+
+    // Expanding to bounds already contained changes nothing.
+    space.expand(old_bounds).unwrap();
+    assert_eq!(space.bounds(), new_bounds);
+    assert_eq!(sink.drain(), vec![]);
+}
+
 /// There was a bug triggered when the last instance of a block was replaced with
 /// a block already in the space. This specifically runs a consistency check in that
 /// case.
@@ -421,9 +470,18 @@ fn space_debug() {
                 ],
                 physics: SpacePhysics {
                     gravity: (+0.000, -20.000, +0.000),
-                    sky_color: Rgb(0.8962694, 0.8962694, 1.0),
+                    air_drag: 0.0,
+                    terminal_velocity: None,
+                    sky: Uniform(
+                        Rgb(0.8962694, 0.8962694, 1.0),
+                    ),
                     light: None,
                 },
+                weather: Weather {
+                    precipitation: None,
+                    intensity: 0.0,
+                    fog_density: 0.0,
+                },
                 behaviors: BehaviorSet([]),
                 cubes_wanting_ticks: {},
                 ..
@@ -452,6 +510,41 @@ fn set_physics_light_none() {
     // TODO: test what change notifications are sent
 }
 
+#[test]
+fn weather_changes_toward_target() {
+    let mut space = Space::empty_positive(1, 1, 1);
+    assert_eq!(space.weather(), &Weather::CLEAR);
+    let sink = Sink::new();
+    space.listen(sink.listener());
+
+    let rain = Weather::new(Some(Precipitation::Rain), 1.0, 0.0);
+    space.set_weather(rain);
+    assert_eq!(space.target_weather(), &rain);
+    assert_eq!(sink.drain(), vec![SpaceChange::Weather]);
+
+    // Paused ticks do not change the weather.
+    let _ = space.step(
+        None,
+        Tick::from_seconds(1.0).pause(),
+        practically_infinite_deadline(),
+    );
+    assert_eq!(space.weather(), &Weather::CLEAR);
+
+    let _ = space.step(
+        None,
+        Tick::from_seconds(1.0),
+        practically_infinite_deadline(),
+    );
+    assert_eq!(space.weather().precipitation(), Some(Precipitation::Rain));
+    assert!(space.weather().intensity() > 0.0 && space.weather().intensity() < 1.0);
+
+    sink.drain();
+    space.set_weather_immediately(Weather::CLEAR);
+    assert_eq!(space.weather(), &Weather::CLEAR);
+    assert_eq!(space.target_weather(), &Weather::CLEAR);
+    assert_eq!(sink.drain(), vec![SpaceChange::Weather]);
+}
+
 #[test]
 fn set_physics_light_rays() {
     let mut space = Space::empty_positive(2, 1, 1);
@@ -496,3 +589,133 @@ fn block_tick_action() {
 
     assert_eq!(&space[[0, 0, 0]], &block2);
 }
+
+#[test]
+fn signal_propagates_through_wire_to_sink() {
+    let [source, wire, lamp_off] = make_some_blocks();
+    let source = Block::builder()
+        .color(source.color())
+        .signal(BlockSignal::Source)
+        .build();
+    let wire = Block::builder()
+        .color(wire.color())
+        .signal(BlockSignal::Wire)
+        .build();
+    let lamp_on = Block::builder()
+        .color(Rgba::WHITE)
+        .signal(BlockSignal::Sink {
+            powered: None,
+            unpowered: Some(VoxelBrush::single(lamp_off.clone())),
+        })
+        .build();
+    let lamp_waiting = Block::builder()
+        .color(lamp_off.color())
+        .signal(BlockSignal::Sink {
+            powered: Some(VoxelBrush::single(lamp_on.clone())),
+            unpowered: None,
+        })
+        .build();
+
+    let mut space = Space::empty_positive(5, 1, 1);
+    space.set([0, 0, 0], &source).unwrap();
+    space.set([1, 0, 0], &wire).unwrap();
+    space.set([2, 0, 0], &wire).unwrap();
+    space.set([3, 0, 0], &lamp_waiting).unwrap();
+    space.set([4, 0, 0], &wire).unwrap();
+
+    let _ = space.step(None, Tick::arbitrary(), practically_infinite_deadline());
+    assert_eq!(&space[[3, 0, 0]], &lamp_on);
+    assert!(space.is_powered([2, 0, 0]));
+    assert!(space.is_powered([3, 0, 0]));
+    assert!(
+        !space.is_powered([4, 0, 0]),
+        "sink should not conduct the signal"
+    );
+
+    // Removing the source turns the lamp off again.
+    space.set([0, 0, 0], AIR).unwrap();
+    let _ = space.step(None, Tick::arbitrary(), practically_infinite_deadline());
+    assert_eq!(&space[[3, 0, 0]], &lamp_off);
+    assert!(!space.is_powered([1, 0, 0]));
+}
+
+#[test]
+fn count_blocks_in_region() {
+    let [block] = make_some_blocks();
+    // Large enough to span several storage chunks.
+    let mut space = Space::empty_positive(40, 4, 40);
+    space
+        .fill_uniform(GridAab::from_lower_size([0, 0, 0], [40, 1, 40]), &block)
+        .unwrap();
+    space.set([5, 2, 5], &block).unwrap();
+
+    let counts = space.count_blocks(GridAab::from_lower_size([0, 0, 0], [20, 3, 10]));
+    assert_eq!(counts.len(), 2);
+    assert_eq!(counts[&block], 20 * 10 + 1);
+    assert_eq!(counts[&AIR], 20 * 3 * 10 - (20 * 10 + 1));
+
+    // Cubes outside the space are not counted.
+    let counts = space.count_blocks(GridAab::from_lower_size([-10, 0, 0], [11, 1, 1]));
+    assert_eq!(counts.len(), 1);
+    assert_eq!(counts[&block], 1);
+}
+
+#[test]
+fn find_nearest() {
+    let [block1, block2] = make_some_blocks();
+    let mut space = Space::empty_positive(40, 40, 40);
+    space.set([30, 30, 30], &block1).unwrap();
+    space.set([3, 4, 5], &block1).unwrap();
+    space.set([1, 1, 1], &block2).unwrap();
+    space
+        .fill_uniform(GridAab::from_lower_size([32, 0, 0], [8, 16, 16]), &block2)
+        .unwrap();
+    let bounds = space.bounds();
+
+    assert_eq!(
+        space.find_nearest(GridPoint::new(0, 0, 0), bounds, |b| *b == block1),
+        Some(GridPoint::new(3, 4, 5))
+    );
+    assert_eq!(
+        space.find_nearest(GridPoint::new(39, 39, 39), bounds, |b| *b == block1),
+        Some(GridPoint::new(30, 30, 30))
+    );
+    assert_eq!(
+        space.find_nearest(GridPoint::new(38, 3, 100), bounds, |b| *b == block2),
+        Some(GridPoint::new(38, 3, 15))
+    );
+    assert_eq!(
+        space.find_nearest(
+            GridPoint::new(0, 0, 0),
+            GridAab::from_lower_size([2, 0, 0], [10, 10, 10]),
+            |b| *b == block2
+        ),
+        None
+    );
+    assert_eq!(
+        space.find_nearest(GridPoint::new(0, 0, 0), bounds, |_| false),
+        None
+    );
+}
+
+#[test]
+fn non_air_cubes() {
+    let [block1, block2] = make_some_blocks();
+    let mut space = Space::empty_positive(40, 2, 2);
+    space.set([1, 0, 0], &block1).unwrap();
+    space.set([35, 1, 1], &block2).unwrap();
+
+    assert_eq!(
+        space.non_air_cubes(space.bounds()).collect::<Vec<_>>(),
+        vec![
+            (GridPoint::new(1, 0, 0), &block1),
+            (GridPoint::new(35, 1, 1), &block2),
+        ]
+    );
+    assert_eq!(
+        space
+            .non_air_cubes(GridAab::from_lower_size([2, 0, 0], [40, 2, 2]))
+            .collect::<Vec<_>>(),
+        vec![(GridPoint::new(35, 1, 1), &block2)]
+    );
+}
@@ -0,0 +1,229 @@
+//! [`TopDownMap`]: a map of a [`Space`] as seen from above.
+
+use std::collections::HashSet;
+use std::fmt;
+use std::mem;
+use std::sync::{Arc, Mutex, Weak};
+
+use image::{Rgba as ImageRgba, RgbaImage};
+
+use crate::listen::{Listen as _, Listener};
+use crate::math::{GridAab, GridCoordinate, Rgb};
+use crate::space::{Space, SpaceChange};
+use crate::universe::{RefError, URef};
+
+/// Color of the lowest cubes, as a fraction of the block's color; the highest cubes are
+/// full brightness and cubes in between are interpolated.
+const LOWEST_BRIGHTNESS: f32 = 0.4;
+
+/// A map of a [`Space`] as seen from above, with one pixel per column of cubes, which
+/// can be cheaply updated when the space is changed.
+///
+/// Each pixel has the color of the highest visible block in its column, darkened the
+/// lower that block is, or is fully transparent if the column contains no visible
+/// blocks. The pixel at (0, 0) is the column at the lowest X and Z coordinates of the
+/// space's bounds, so that, when the image is shown in the usual orientation, −Z is up.
+pub struct TopDownMap {
+    space: URef<Space>,
+    bounds: GridAab,
+    image: RgbaImage,
+    todo: Arc<Mutex<MapTodo>>,
+}
+
+impl fmt::Debug for TopDownMap {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TopDownMap")
+            .field("space", &self.space)
+            .field("bounds", &self.bounds)
+            .field("todo", &self.todo)
+            .finish_non_exhaustive()
+    }
+}
+
+impl TopDownMap {
+    /// Constructs a new [`TopDownMap`] which follows the given `space`.
+    ///
+    /// The space is not accessed (and thus, the map is empty) until the first call to
+    /// [`update()`](Self::update).
+    pub fn new(space: URef<Space>) -> Self {
+        Self {
+            space,
+            bounds: GridAab::from_lower_size([0, 0, 0], [0, 0, 0]),
+            image: RgbaImage::new(0, 0),
+            todo: Arc::new(Mutex::new(MapTodo {
+                listener: true,
+                everything: true,
+                columns: HashSet::new(),
+            })),
+        }
+    }
+
+    /// Returns the [`Space`] this is a map of.
+    pub fn space(&self) -> &URef<Space> {
+        &self.space
+    }
+
+    /// Returns the bounds of the space as of the last [`update()`](Self::update).
+    pub fn bounds(&self) -> GridAab {
+        self.bounds
+    }
+
+    /// Returns the map image. Its contents are current as of the last
+    /// [`update()`](Self::update).
+    pub fn image(&self) -> &RgbaImage {
+        &self.image
+    }
+
+    /// Returns the pixel coordinates of the column containing the cube with the given
+    /// X and Z coordinates, or [`None`] if it is outside the space.
+    pub fn pixel_for_column(&self, x: GridCoordinate, z: GridCoordinate) -> Option<[u32; 2]> {
+        let lower = self.bounds.lower_bounds();
+        let upper = self.bounds.upper_bounds();
+        if (lower.x..upper.x).contains(&x) && (lower.z..upper.z).contains(&z) {
+            Some([(x - lower.x) as u32, (z - lower.z) as u32])
+        } else {
+            None
+        }
+    }
+
+    /// Reads the space and updates the pixels of the columns which have changed.
+    ///
+    /// Returns whether the image changed, or an error if reading the space fails.
+    pub fn update(&mut self) -> Result<bool, RefError> {
+        // Same deadlock safety considerations as `UpdatingSpaceRaytracer::update()`.
+        let mut todo = self.todo.lock().unwrap();
+        if !todo.listener && !todo.everything && todo.columns.is_empty() {
+            return Ok(false);
+        }
+        let space = self.space.read()?;
+
+        if mem::take(&mut todo.listener) {
+            space.listen(TodoListener(Arc::downgrade(&self.todo)));
+        }
+
+        if mem::take(&mut todo.everything) {
+            todo.columns.clear();
+            self.bounds = space.bounds();
+            let size = self.bounds.size();
+            let lower = self.bounds.lower_bounds();
+            self.image = RgbaImage::from_fn(size.x as u32, size.z as u32, |px, py| {
+                column_pixel(
+                    &space,
+                    lower.x + px as GridCoordinate,
+                    lower.z + py as GridCoordinate,
+                )
+            });
+        } else {
+            for [x, z] in todo.columns.drain() {
+                if let Some([px, py]) = self.pixel_for_column(x, z) {
+                    self.image.put_pixel(px, py, column_pixel(&space, x, z));
+                }
+            }
+        }
+
+        Ok(true)
+    }
+}
+
+/// Computes the map pixel for one column of `space`.
+fn column_pixel(space: &Space, x: GridCoordinate, z: GridCoordinate) -> ImageRgba<u8> {
+    let bounds = space.bounds();
+    let lower_y = bounds.lower_bounds().y;
+    let upper_y = bounds.upper_bounds().y;
+    for y in (lower_y..upper_y).rev() {
+        let ev = space.get_evaluated([x, y, z]);
+        if ev.visible {
+            let height_fraction = (y - lower_y + 1) as f32 / (upper_y - lower_y) as f32;
+            let brightness = LOWEST_BRIGHTNESS + (1.0 - LOWEST_BRIGHTNESS) * height_fraction;
+            let color: Rgb = ev.color.to_rgb() * brightness;
+            return ImageRgba(color.with_alpha_one().to_srgb8());
+        }
+    }
+    ImageRgba([0, 0, 0, 0])
+}
+
+#[derive(Debug)]
+struct MapTodo {
+    /// Listener upon the space is not yet installed.
+    listener: bool,
+
+    /// All columns must be updated, and the space's bounds checked.
+    everything: bool,
+
+    /// X and Z coordinates of columns which must be updated.
+    columns: HashSet<[GridCoordinate; 2]>,
+}
+
+/// [`Listener`] adapter for [`MapTodo`].
+#[derive(Clone, Debug)]
+struct TodoListener(Weak<Mutex<MapTodo>>);
+
+impl Listener<SpaceChange> for TodoListener {
+    fn receive(&self, message: SpaceChange) {
+        if let Some(mutex) = self.0.upgrade() {
+            if let Ok(mut todo) = mutex.lock() {
+                match message {
+                    // A change in a block definition might affect any column, so we
+                    // don't try to find which ones.
+                    SpaceChange::EveryBlock | SpaceChange::BlockValue(_) => {
+                        todo.everything = true;
+                        todo.columns.clear();
+                    }
+                    SpaceChange::Block(p) => {
+                        if !todo.everything {
+                            todo.columns.insert([p.x, p.z]);
+                        }
+                    }
+                    SpaceChange::Lighting(_) | SpaceChange::Number(_) | SpaceChange::Weather => {}
+                }
+            }
+        }
+    }
+
+    fn alive(&self) -> bool {
+        self.0.strong_count() > 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::block::{Block, AIR};
+    use crate::math::Rgba;
+    use crate::universe::Universe;
+
+    #[test]
+    fn colors_and_updates() {
+        let red = Block::from(Rgba::new(1.0, 0.0, 0.0, 1.0));
+        let blue = Block::from(Rgba::new(0.0, 0.0, 1.0, 1.0));
+        let mut universe = Universe::new();
+        let mut space = Space::empty(GridAab::from_lower_size([10, 0, 20], [2, 2, 1]));
+        space.set([10, 0, 20], &red).unwrap();
+        space.set([11, 0, 20], &red).unwrap();
+        space.set([11, 1, 20], &blue).unwrap();
+        let space = universe.insert_anonymous(space);
+
+        let mut map = TopDownMap::new(space.clone());
+        assert!(map.update().unwrap());
+        assert!(!map.update().unwrap());
+        assert_eq!(map.image().dimensions(), (2, 1));
+        assert_eq!(map.pixel_for_column(11, 20), Some([1, 0]));
+        assert_eq!(map.pixel_for_column(12, 20), None);
+        let lower_red = *map.image().get_pixel(0, 0);
+        let upper_blue = *map.image().get_pixel(1, 0);
+        assert_eq!(upper_blue, ImageRgba([0, 0, 255, 255]));
+        assert_eq!(lower_red.0[1..], [0, 0, 255]);
+        assert!(lower_red.0[0] > 0 && lower_red.0[0] < 255, "{lower_red:?}");
+
+        // Changing blocks updates only their columns.
+        space
+            .try_modify(|space| {
+                space.set([10, 0, 20], &AIR).unwrap();
+                space.set([11, 1, 20], &AIR).unwrap();
+            })
+            .unwrap();
+        assert!(map.update().unwrap());
+        assert_eq!(*map.image().get_pixel(0, 0), ImageRgba([0, 0, 0, 0]));
+        assert_eq!(*map.image().get_pixel(1, 0), lower_red);
+    }
+}
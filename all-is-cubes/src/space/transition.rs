@@ -0,0 +1,142 @@
+//! [`SpaceTransition`]: regions of a [`Space`] which move characters to another space.
+
+use crate::behavior::{Behavior, BehaviorContext};
+use crate::character::{Character, CharacterTransaction};
+use crate::math::{point_to_enclosing_cube, GridVector};
+use crate::space::Space;
+use crate::universe::{RefVisitor, URef, UniverseTransaction, VisitRefs};
+
+#[cfg(doc)]
+use crate::space::SpaceBehaviorAttachment;
+
+/// A [`Behavior`] which moves every [`Character`] whose body enters the region it is
+/// attached to (the bounds of its [`SpaceBehaviorAttachment`]) into another [`Space`].
+///
+/// The character's position is translated by [`SpaceTransition::offset()`], so that it
+/// arrives at a corresponding doorway or edge of the destination space. That position
+/// should not be inside a transition leading back, or the character will immediately
+/// return.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SpaceTransition {
+    destination: URef<Space>,
+    offset: GridVector,
+}
+
+impl SpaceTransition {
+    /// Constructs a [`SpaceTransition`] to `destination`, which adds `offset` to the
+    /// positions of characters passing through it.
+    pub fn new(destination: URef<Space>, offset: GridVector) -> Self {
+        Self {
+            destination,
+            offset,
+        }
+    }
+
+    /// Returns the space which characters are moved to.
+    pub fn destination(&self) -> &URef<Space> {
+        &self.destination
+    }
+
+    /// Returns the translation from positions in the space this transition is attached
+    /// to, to positions in the destination space.
+    pub fn offset(&self) -> GridVector {
+        self.offset
+    }
+}
+
+impl Behavior<Space> for SpaceTransition {
+    fn alive(&self, _: &BehaviorContext<'_, Space>) -> bool {
+        true
+    }
+
+    fn ephemeral(&self) -> bool {
+        false
+    }
+}
+
+impl VisitRefs for SpaceTransition {
+    fn visit_refs(&self, visitor: &mut dyn RefVisitor) {
+        let Self {
+            destination,
+            offset: _,
+        } = self;
+        visitor.visit(destination);
+    }
+}
+
+/// Returns a transaction which moves `character` through the [`SpaceTransition`] in
+/// `space` which its body is inside, if there is one.
+///
+/// `space` should be the character's current space.
+pub(crate) fn space_transition_for(
+    character: &Character,
+    character_ref: &URef<Character>,
+    space: &Space,
+) -> Option<UniverseTransaction> {
+    let position = character.body.position;
+    let cube = point_to_enclosing_cube(position)?;
+    let transition = space
+        .behaviors()
+        .query::<SpaceTransition>()
+        .find(|item| item.attachment.bounds().contains_cube(cube))?
+        .behavior;
+    Some(
+        CharacterTransaction::move_to_space(
+            transition.destination.clone(),
+            position + transition.offset.map(f64::from),
+        )
+        .bind(character_ref.clone()),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math::{GridAab, GridPoint};
+    use crate::physics::BodyTransaction;
+    use crate::space::SpaceTransaction;
+    use crate::time::{practically_infinite_deadline, Tick};
+    use crate::transaction::{self, Transaction as _};
+    use crate::universe::Universe;
+
+    #[test]
+    fn character_moves_to_destination() {
+        let mut universe = Universe::new();
+        let destination = universe
+            .insert("destination".into(), Space::empty_positive(10, 10, 10))
+            .unwrap();
+        let mut origin = Space::empty_positive(10, 10, 10);
+        SpaceTransaction::add_behavior(
+            GridAab::from_lower_size([8, 0, 0], [2, 10, 10]),
+            SpaceTransition::new(destination.clone(), GridVector::new(-7, 1, 0)),
+        )
+        .execute(&mut origin, &mut transaction::no_outputs)
+        .unwrap();
+        let origin = universe.insert("origin".into(), origin).unwrap();
+        let character = universe
+            .insert("character".into(), Character::spawn_default(origin.clone()))
+            .unwrap();
+
+        // Outside the transition region, nothing happens.
+        CharacterTransaction::body(BodyTransaction::teleport([5.5, 5.5, 5.5]))
+            .bind(character.clone())
+            .execute(&mut universe, &mut transaction::no_outputs)
+            .unwrap();
+        universe.step(Tick::arbitrary(), practically_infinite_deadline());
+        assert_eq!(character.read().unwrap().space, origin);
+
+        // Inside it, the character is moved.
+        CharacterTransaction::body(BodyTransaction::teleport([8.5, 5.5, 5.5]))
+            .bind(character.clone())
+            .execute(&mut universe, &mut transaction::no_outputs)
+            .unwrap();
+        universe.step(Tick::arbitrary(), practically_infinite_deadline());
+        let character = character.read().unwrap();
+        assert_eq!(character.space, destination);
+        // Not exactly, because the body moved a little during the step.
+        assert_eq!(
+            point_to_enclosing_cube(character.body.position),
+            Some(GridPoint::new(1, 6, 5))
+        );
+    }
+}
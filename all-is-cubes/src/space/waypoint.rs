@@ -0,0 +1,141 @@
+//! [`Waypoint`]: named locations which characters may teleport to.
+
+use std::fmt;
+
+use cgmath::Point3;
+
+use crate::block::Block;
+use crate::character::{Character, CharacterTransaction};
+use crate::math::FreeCoordinate;
+use crate::physics::BodyTransaction;
+use crate::space::Space;
+use crate::universe::{RefError, RefVisitor, URef, UniverseTransaction, VisitRefs};
+
+/// A named location in a [`Space`], stored in a [`Universe`](crate::universe::Universe)
+/// under its name, which characters may teleport to and which the user interface may
+/// point out.
+#[derive(Clone, PartialEq)]
+pub struct Waypoint {
+    pub(crate) space: URef<Space>,
+    pub(crate) position: Point3<FreeCoordinate>,
+    pub(crate) icon: Option<Block>,
+}
+
+impl Waypoint {
+    /// Constructs a [`Waypoint`] at the given position in the given space, with no icon.
+    pub fn new(space: URef<Space>, position: impl Into<Point3<FreeCoordinate>>) -> Self {
+        Self {
+            space,
+            position: position.into(),
+            icon: None,
+        }
+    }
+
+    /// Sets the block which the user interface should use to depict this waypoint.
+    /// A block of a single color may be used to give the waypoint a color.
+    #[must_use]
+    pub fn with_icon(mut self, icon: Block) -> Self {
+        self.icon = Some(icon);
+        self
+    }
+
+    /// Returns the space this waypoint is in.
+    pub fn space(&self) -> &URef<Space> {
+        &self.space
+    }
+
+    /// Returns the position of this waypoint.
+    pub fn position(&self) -> Point3<FreeCoordinate> {
+        self.position
+    }
+
+    /// Returns the block which the user interface should use to depict this waypoint,
+    /// if one was given.
+    pub fn icon(&self) -> Option<&Block> {
+        self.icon.as_ref()
+    }
+
+    /// Returns a transaction which moves the given character to this waypoint.
+    ///
+    /// Returns an error if the character cannot be read or is not in the waypoint's
+    /// space; use [`CharacterTransaction::move_to_space()`] to move characters between
+    /// spaces.
+    pub fn teleport(
+        &self,
+        character_ref: &URef<Character>,
+    ) -> Result<UniverseTransaction, TeleportError> {
+        if character_ref.read()?.space != self.space {
+            return Err(TeleportError::DifferentSpace);
+        }
+        let body_txn = BodyTransaction::teleport(self.position);
+        Ok(CharacterTransaction::body(body_txn).bind(character_ref.clone()))
+    }
+}
+
+impl fmt::Debug for Waypoint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Waypoint")
+            .field("space", &self.space)
+            .field("position", &self.position)
+            .field("icon", &self.icon.is_some())
+            .finish()
+    }
+}
+
+impl VisitRefs for Waypoint {
+    fn visit_refs(&self, visitor: &mut dyn RefVisitor) {
+        let Self {
+            space,
+            position: _,
+            icon,
+        } = self;
+        visitor.visit(space);
+        if let Some(icon) = icon {
+            icon.visit_refs(visitor);
+        }
+    }
+}
+
+/// Error from [`Waypoint::teleport()`].
+#[derive(Clone, Debug, PartialEq, thiserror::Error)]
+#[non_exhaustive]
+pub enum TeleportError {
+    /// The character could not be read.
+    #[error(transparent)]
+    Read(#[from] RefError),
+    /// The character is not in the same space as the waypoint.
+    #[error("the waypoint is in a different space")]
+    DifferentSpace,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transaction::{self, Transaction as _};
+    use crate::universe::Universe;
+
+    #[test]
+    fn teleport() {
+        let mut universe = Universe::new();
+        let space = universe.insert_anonymous(Space::empty_positive(10, 10, 10));
+        let other_space = universe.insert_anonymous(Space::empty_positive(1, 1, 1));
+        let character = universe.insert_anonymous(Character::spawn_default(space.clone()));
+
+        Waypoint::new(space, [1.0, 2.0, 3.0])
+            .teleport(&character)
+            .unwrap()
+            .execute(&mut universe, &mut transaction::no_outputs)
+            .unwrap();
+        assert_eq!(
+            character.read().unwrap().body.position,
+            Point3::new(1.0, 2.0, 3.0)
+        );
+
+        assert_eq!(
+            Waypoint::new(other_space, [0.0, 0.0, 0.0])
+                .teleport(&character)
+                .unwrap_err(),
+            TeleportError::DifferentSpace
+        );
+    }
+}
@@ -0,0 +1,319 @@
+//! [`Weather`]: precipitation and fog which change over time.
+
+use cgmath::{Point3, Vector3, Zero as _};
+use instant::Duration;
+use rand::{Rng as _, SeedableRng as _};
+use rand_xoshiro::Xoshiro256Plus;
+
+use crate::content::palette;
+use crate::math::{FreeCoordinate, Geometry, LineVertex, NotNan};
+use crate::notnan;
+
+#[cfg(doc)]
+use crate::space::Space;
+
+/// Maximum amount by which [`Weather::intensity()`] and [`Weather::fog_density()`] change
+/// per second when the weather is changing.
+const CHANGE_PER_SECOND: f32 = 0.1;
+
+/// Number of particles drawn by [`WeatherParticles`] at intensity 1.
+const MAX_PARTICLES: f32 = 600.0;
+
+/// Half the width and depth, and half the height, of the box of [`WeatherParticles`].
+const PARTICLE_BOX_RADIUS: FreeCoordinate = 12.0;
+
+/// Kind of precipitation falling in a [`Space`].
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[non_exhaustive]
+pub enum Precipitation {
+    /// Fast-falling drops.
+    Rain,
+    /// Slowly drifting flakes.
+    Snow,
+}
+
+/// The weather in a [`Space`]: precipitation and fog.
+///
+/// A space has a current weather and a target weather, set by
+/// [`Space::set_weather()`]; each step, the current weather changes gradually toward
+/// the target. Renderers use the current weather to draw precipitation particles and to
+/// shorten the fog distance.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct Weather {
+    precipitation: Option<Precipitation>,
+    intensity: NotNan<f32>,
+    fog_density: NotNan<f32>,
+}
+
+impl Weather {
+    /// No precipitation and no fog.
+    pub const CLEAR: Self = Self {
+        precipitation: None,
+        intensity: notnan!(0.0),
+        fog_density: notnan!(0.0),
+    };
+
+    /// Constructs a [`Weather`].
+    ///
+    /// `intensity` is how heavy the precipitation is, and `fog_density` is how much the
+    /// fog reduces visibility. Both are clamped to the range 0 to 1, and NaN is treated
+    /// as 0. If `precipitation` is [`None`], the intensity is 0.
+    pub fn new(precipitation: Option<Precipitation>, intensity: f32, fog_density: f32) -> Self {
+        fn clamp(value: f32) -> NotNan<f32> {
+            NotNan::new(value.clamp(0.0, 1.0)).unwrap_or(notnan!(0.0))
+        }
+        Self {
+            precipitation,
+            intensity: if precipitation.is_some() {
+                clamp(intensity)
+            } else {
+                notnan!(0.0)
+            },
+            fog_density: clamp(fog_density),
+        }
+    }
+
+    /// Returns the kind of precipitation, or [`None`] if there is none.
+    pub fn precipitation(&self) -> Option<Precipitation> {
+        self.precipitation
+    }
+
+    /// Returns how heavy the precipitation is, from 0 to 1.
+    pub fn intensity(&self) -> f32 {
+        self.intensity.into_inner()
+    }
+
+    /// Returns how much the fog reduces visibility, from 0 to 1.
+    pub fn fog_density(&self) -> f32 {
+        self.fog_density.into_inner()
+    }
+
+    /// Returns the factor by which renderers should multiply the fog distance, from 1
+    /// with no fog down to 1/8 with the densest fog.
+    pub fn fog_distance_scale(&self) -> f32 {
+        1.0 - 0.875 * self.fog_density()
+    }
+
+    /// Returns the weather after changing toward `target` for `dt`.
+    ///
+    /// Changing to a different kind of precipitation happens by first decreasing the
+    /// intensity of the current kind to zero.
+    #[must_use]
+    pub(crate) fn step_toward(&self, target: &Weather, dt: Duration) -> Weather {
+        if self == target {
+            return *self;
+        }
+
+        let max_change = CHANGE_PER_SECOND * dt.as_secs_f32();
+        let approach = |current: NotNan<f32>, goal: NotNan<f32>| -> f32 {
+            let current = current.into_inner();
+            let goal = goal.into_inner();
+            if (goal - current).abs() <= max_change {
+                goal
+            } else {
+                current + max_change.copysign(goal - current)
+            }
+        };
+
+        let precipitation = self.precipitation.or(target.precipitation);
+        let intensity = approach(
+            self.intensity,
+            if precipitation == target.precipitation {
+                target.intensity
+            } else {
+                notnan!(0.0)
+            },
+        );
+        Weather::new(
+            if intensity <= 0.0 {
+                target.precipitation
+            } else {
+                precipitation
+            },
+            intensity,
+            approach(self.fog_density, target.fog_density),
+        )
+    }
+}
+
+#[cfg(feature = "arbitrary")]
+#[mutants::skip]
+impl<'a> arbitrary::Arbitrary<'a> for Weather {
+    fn arbitrary(u: &mut arbitrary::Unstructured<'a>) -> arbitrary::Result<Self> {
+        Ok(Self::new(u.arbitrary()?, u.arbitrary()?, u.arbitrary()?))
+    }
+
+    fn size_hint(depth: usize) -> (usize, Option<usize>) {
+        use arbitrary::{size_hint::and_all, Arbitrary};
+        and_all(&[
+            <Option<Precipitation> as Arbitrary>::size_hint(depth),
+            <f32 as Arbitrary>::size_hint(depth),
+            <f32 as Arbitrary>::size_hint(depth),
+        ])
+    }
+}
+
+impl Default for Weather {
+    /// Returns [`Weather::CLEAR`].
+    fn default() -> Self {
+        Self::CLEAR
+    }
+}
+
+/// Particles of precipitation near a point, as line segments, for renderers to draw.
+/// Obtain this from [`Space::weather_particles()`].
+///
+/// The particles fill a box centered on the given point, but their positions are fixed
+/// in the world (apart from falling), so that moving the center does not move them.
+//
+// TODO: Particles are drawn indoors too; we should check whether each one is under the sky.
+#[derive(Clone, Debug, PartialEq)]
+pub struct WeatherParticles {
+    pub(crate) weather: Weather,
+    pub(crate) center: Point3<FreeCoordinate>,
+    /// Total time the weather has been stepped for, which determines how far the
+    /// particles have fallen.
+    pub(crate) time: Duration,
+    /// Offset from [`Geometry::translate()`].
+    pub(crate) offset: Vector3<FreeCoordinate>,
+}
+
+impl WeatherParticles {
+    pub(crate) fn new(weather: Weather, center: Point3<FreeCoordinate>, time: Duration) -> Self {
+        Self {
+            weather,
+            center,
+            time,
+            offset: Vector3::zero(),
+        }
+    }
+}
+
+impl Geometry for WeatherParticles {
+    type Coord = FreeCoordinate;
+
+    fn translate(mut self, offset: Vector3<FreeCoordinate>) -> Self {
+        self.offset += offset;
+        self
+    }
+
+    fn wireframe_points<E>(&self, output: &mut E)
+    where
+        E: Extend<LineVertex>,
+    {
+        let Some(precipitation) = self.weather.precipitation() else {
+            return;
+        };
+        let count = (self.weather.intensity() * MAX_PARTICLES) as usize;
+        let (fall_speed, length, sway, color) = match precipitation {
+            Precipitation::Rain => (12.0, 0.5, 0.0, palette::RAIN),
+            Precipitation::Snow => (1.0, 0.05, 0.3, palette::SNOW),
+        };
+        let time = self.time.as_secs_f64();
+        let r = PARTICLE_BOX_RADIUS;
+
+        // Choose a position in [center - r, center + r) which is congruent to `position`
+        // modulo the size of the box.
+        let wrap = |position: FreeCoordinate, center: FreeCoordinate| {
+            center - r + (position - (center - r)).rem_euclid(r * 2.0)
+        };
+
+        // Fixed seed so that the same particles are produced every frame.
+        let mut rng = Xoshiro256Plus::seed_from_u64(0);
+        for _ in 0..count {
+            let x: FreeCoordinate = rng.gen_range(0.0..r * 2.0);
+            let y: FreeCoordinate = rng.gen_range(0.0..r * 2.0);
+            let z: FreeCoordinate = rng.gen_range(0.0..r * 2.0);
+            let phase: FreeCoordinate = rng.gen_range(0.0..std::f64::consts::TAU);
+
+            let top = Point3::new(
+                wrap(x + sway * (time + phase).sin(), self.center.x),
+                wrap(y - fall_speed * time, self.center.y),
+                wrap(z, self.center.z),
+            ) + self.offset;
+            output.extend([
+                LineVertex {
+                    position: top,
+                    color: Some(color),
+                },
+                LineVertex {
+                    position: top - Vector3::new(0.0, length, 0.0),
+                    color: Some(color),
+                },
+            ]);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn new_clamps() {
+        let weather = Weather::new(Some(Precipitation::Rain), 2.0, f32::NAN);
+        assert_eq!(weather.intensity(), 1.0);
+        assert_eq!(weather.fog_density(), 0.0);
+        assert_eq!(Weather::new(None, 0.5, 0.5).intensity(), 0.0);
+    }
+
+    #[test]
+    fn step_toward_changes_gradually() {
+        let target = Weather::new(Some(Precipitation::Rain), 1.0, 0.5);
+        let step = Duration::from_secs(1);
+
+        let weather = Weather::CLEAR.step_toward(&target, step);
+        assert_eq!(weather.precipitation(), Some(Precipitation::Rain));
+        assert_eq!(weather.intensity(), 0.1);
+        assert_eq!(weather.fog_density(), 0.1);
+
+        let mut weather = weather;
+        for _ in 0..20 {
+            weather = weather.step_toward(&target, step);
+        }
+        assert_eq!(weather, target);
+    }
+
+    #[test]
+    fn step_toward_other_precipitation_fades_out_first() {
+        let mut weather = Weather::new(Some(Precipitation::Rain), 0.15, 0.0);
+        let target = Weather::new(Some(Precipitation::Snow), 1.0, 0.0);
+        let step = Duration::from_secs(1);
+
+        weather = weather.step_toward(&target, step);
+        assert_eq!(weather.precipitation(), Some(Precipitation::Rain));
+        weather = weather.step_toward(&target, step);
+        assert_eq!(weather.precipitation(), Some(Precipitation::Snow));
+        assert_eq!(weather.intensity(), 0.0);
+        weather = weather.step_toward(&target, step);
+        assert_eq!(weather.precipitation(), Some(Precipitation::Snow));
+        assert_eq!(weather.intensity(), 0.1);
+    }
+
+    #[test]
+    fn particles_stay_in_box() {
+        let center = Point3::new(100.0, -50.0, 3.0);
+        let particles = WeatherParticles::new(
+            Weather::new(Some(Precipitation::Snow), 0.5, 0.0),
+            center,
+            Duration::from_secs(1000),
+        );
+        let mut points: Vec<LineVertex> = Vec::new();
+        particles.wireframe_points(&mut points);
+        assert_eq!(points.len(), 600);
+        for point in points.iter().step_by(2) {
+            let relative = point.position - center;
+            for c in [relative.x, relative.y, relative.z] {
+                assert!(
+                    (-PARTICLE_BOX_RADIUS..PARTICLE_BOX_RADIUS).contains(&c),
+                    "{relative:?}"
+                );
+            }
+        }
+
+        let mut none: Vec<LineVertex> = Vec::new();
+        WeatherParticles::new(Weather::CLEAR, center, Duration::ZERO).wireframe_points(&mut none);
+        assert_eq!(none, vec![]);
+    }
+}
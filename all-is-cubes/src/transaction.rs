@@ -9,9 +9,7 @@ use crate::universe::{URef, UTransactional, UniverseTransaction};
 
 mod generic;
 
-#[cfg(test)]
 mod tester;
-#[cfg(test)]
 pub use tester::*;
 
 /// A `Transaction` is a description of a mutation to an object or collection thereof that
@@ -10,12 +10,17 @@ use std::rc::Rc;
 ///   fail to merge.
 ///
 /// This test utility follows the builder pattern: call methods to add test data, then
-/// finish with [`Self::test`].
+/// finish with [`Self::test`], [`Self::test_random_sequences`], or (with the
+/// `arbitrary` feature) `Self::test_arbitrary_sequence`.
+///
+/// It is public so that crates defining their own transaction types can test them the
+/// same way this crate tests its own.
 #[must_use]
 #[allow(missing_debug_implementations)]
 pub struct TransactionTester<'a, Tr, Ta> {
     transactions: Vec<TransactionAndPredicate<'a, Tr, Ta>>,
     target_factories: Vec<Box<dyn Fn() -> Ta + 'a>>,
+    invariants: Vec<Box<dyn Fn(&Ta) -> PredicateRes + 'a>>,
 }
 
 impl<'a, Tr, Ta> TransactionTester<'a, Tr, Ta>
@@ -23,11 +28,13 @@ where
     Tr: Transaction<Ta> + Clone + Debug + 'a,
     Ta: Debug + 'a,
 {
+    /// Constructs a [`TransactionTester`] with no transactions or targets.
     #[allow(clippy::new_without_default)]
     pub fn new() -> Self {
         Self {
             transactions: Vec::new(),
             target_factories: Vec::new(),
+            invariants: Vec::new(),
         }
     }
 
@@ -60,6 +67,13 @@ where
         self
     }
 
+    /// Add a condition which every target should meet, both as created and after each
+    /// transaction is committed to it, such as the target's own consistency check.
+    pub fn invariant(mut self, invariant: impl Fn(&Ta) -> PredicateRes + 'a) -> Self {
+        self.invariants.push(Box::new(invariant));
+        self
+    }
+
     /// Executes the tests and panics on failure.
     pub fn test(self) {
         assert!(!self.transactions.is_empty());
@@ -69,33 +83,10 @@ where
             for target_factory in self.target_factories.iter() {
                 let before = target_factory();
                 let mut target = target_factory();
+                self.check_invariants(&target, None, &[]);
                 if let Ok(check) = tap.transaction.check(&target) {
-                    let output_callback = &mut |_| {
-                        // TODO: allow assertions about the output
-                    };
-                    match tap.transaction.commit(&mut target, check, output_callback) {
-                        Ok(()) => {}
-                        Err(e) => {
-                            panic!(
-                                "Commit failed after check succeeded: {}\n\
-                                Transaction: {:#?}\n\
-                                Target before: {:#?}\n\
-                                Target after: {:#?}",
-                                e, tap.transaction, before, target
-                            );
-                        }
-                    }
+                    self.commit_and_verify(&tap, check, &before, &mut target, &[]);
                     succeeded_at_least_once = true;
-
-                    if let Err(e) = (tap.predicate)(&before, &target) {
-                        panic!(
-                            "Predicate failed: {}\n\
-                            Transaction: {:#?}\n\
-                            Target before: {:#?}\n\
-                            Target after: {:#?}",
-                            e, tap.transaction, before, target
-                        );
-                    }
                 } // else ignore the inapplicable transaction
             }
             assert!(
@@ -106,6 +97,59 @@ where
         }
     }
 
+    /// Executes `sequence_count` sequences of `sequence_length` transactions, each
+    /// chosen randomly from those given to [`Self::transaction()`] and their merges,
+    /// on targets chosen randomly from those given to [`Self::target()`], and panics on
+    /// failure.
+    ///
+    /// Each transaction which passes its check must commit successfully and meet its
+    /// predicate and the invariants; transactions which do not pass are skipped. Unlike
+    /// [`Self::test()`], this finds bugs which depend on the state left by earlier
+    /// transactions.
+    pub fn test_random_sequences(
+        self,
+        rng: &mut impl rand::Rng,
+        sequence_count: usize,
+        sequence_length: usize,
+    ) {
+        assert!(!self.transactions.is_empty());
+        assert!(!self.target_factories.is_empty());
+        let derived: Vec<_> = self.derived_transactions().collect();
+        for _ in 0..sequence_count {
+            let target_factory =
+                &self.target_factories[rng.gen_range(0..self.target_factories.len())];
+            let choices: Vec<usize> = (0..sequence_length)
+                .map(|_| rng.gen_range(0..derived.len()))
+                .collect();
+            self.run_sequence(&derived, target_factory, choices);
+        }
+    }
+
+    /// Executes one sequence of transactions, with the target and transactions chosen
+    /// using the data in `u`, as [`Self::test_random_sequences()`] does, and panics on
+    /// failure. This is intended for use in fuzz targets.
+    ///
+    /// Returns an error only if `u` does not contain enough data to choose a target.
+    #[cfg(feature = "arbitrary")]
+    pub fn test_arbitrary_sequence(
+        self,
+        u: &mut arbitrary::Unstructured<'_>,
+    ) -> arbitrary::Result<()> {
+        assert!(!self.transactions.is_empty());
+        assert!(!self.target_factories.is_empty());
+        let derived: Vec<_> = self.derived_transactions().collect();
+        let target_factory =
+            &self.target_factories[u.int_in_range(0..=self.target_factories.len() - 1)?];
+        // Choosing from a single transaction consumes no data, so the number of choices
+        // must be decided up front rather than by running out of data.
+        let count = u.arbitrary_len::<u8>()?;
+        let choices = (0..count)
+            .map(|_| u.int_in_range(0..=derived.len() - 1))
+            .collect::<arbitrary::Result<Vec<usize>>>()?;
+        self.run_sequence(&derived, target_factory, choices);
+        Ok(())
+    }
+
     fn derived_transactions<'b: 'a>(
         &'b self,
     ) -> impl Iterator<Item = TransactionAndPredicate<'a, Tr, Ta>> + 'b {
@@ -117,6 +161,87 @@ where
             )
         })
     }
+
+    /// Executes the chosen elements of `derived` one after another on one target.
+    fn run_sequence(
+        &self,
+        derived: &[TransactionAndPredicate<'_, Tr, Ta>],
+        target_factory: &dyn Fn() -> Ta,
+        choices: Vec<usize>,
+    ) {
+        let mut target = target_factory();
+        self.check_invariants(&target, None, &[]);
+        let mut previous: Vec<&Tr> = Vec::new();
+        for tap in choices.into_iter().map(|i| &derived[i]) {
+            let Ok(check) = tap.transaction.check(&target) else {
+                continue;
+            };
+            // The target need not be Clone, so reconstruct its state before this
+            // transaction by replaying the previous ones on a new target.
+            let mut before = target_factory();
+            for transaction in &previous {
+                transaction
+                    .execute(&mut before, &mut |_| {})
+                    .expect("replaying a previously successful transaction failed");
+            }
+            self.commit_and_verify(tap, check, &before, &mut target, &previous);
+            previous.push(&tap.transaction);
+        }
+    }
+
+    /// Commits a transaction which has passed its check, and panics if the commit fails
+    /// or the result does not meet the predicate or the invariants.
+    fn commit_and_verify(
+        &self,
+        tap: &TransactionAndPredicate<'_, Tr, Ta>,
+        check: Tr::CommitCheck,
+        before: &Ta,
+        target: &mut Ta,
+        previous: &[&Tr],
+    ) {
+        let output_callback = &mut |_| {
+            // TODO: allow assertions about the output
+        };
+        match tap.transaction.commit(target, check, output_callback) {
+            Ok(()) => {}
+            Err(e) => {
+                panic!(
+                    "Commit failed after check succeeded: {}\n\
+                    Transaction: {:#?}\n\
+                    Previous transactions: {:#?}\n\
+                    Target before: {:#?}\n\
+                    Target after: {:#?}",
+                    e, tap.transaction, previous, before, target
+                );
+            }
+        }
+
+        if let Err(e) = (tap.predicate)(before, target) {
+            panic!(
+                "Predicate failed: {}\n\
+                Transaction: {:#?}\n\
+                Previous transactions: {:#?}\n\
+                Target before: {:#?}\n\
+                Target after: {:#?}",
+                e, tap.transaction, previous, before, target
+            );
+        }
+        self.check_invariants(target, Some(&tap.transaction), previous);
+    }
+
+    fn check_invariants(&self, target: &Ta, transaction: Option<&Tr>, previous: &[&Tr]) {
+        for invariant in &self.invariants {
+            if let Err(e) = invariant(target) {
+                panic!(
+                    "Invariant failed: {}\n\
+                    Transaction: {:#?}\n\
+                    Previous transactions: {:#?}\n\
+                    Target: {:#?}",
+                    e, transaction, previous, target
+                );
+            }
+        }
+    }
 }
 
 type PredicateRes = Result<(), Box<dyn Error>>;
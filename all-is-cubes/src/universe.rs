@@ -21,16 +21,28 @@ use std::time::Duration;
 use instant::Instant;
 
 use crate::block::BlockDef;
-use crate::character::Character;
-use crate::space::{Space, SpaceStepInfo};
+use crate::character::{Character, PlayerStats};
+use crate::entity::Entity;
+use crate::event::GameEvent;
+use crate::inv::StackLimits;
+use crate::listen::{Listen, Listener, Notifier};
+use crate::script::Script;
+use crate::sound::SoundEvent;
+use crate::space::{Space, SpaceStepInfo, Waypoint};
 use crate::time::Tick;
 use crate::transaction::Transaction as _;
-use crate::util::{CustomFormat, StatusText};
+use crate::util::{CustomFormat, StatusText, YieldProgress};
 
 // Note: Everything in `members` is either an impl, private, or intentionally public-in-private.
+mod determinism;
+pub use determinism::*;
+
 mod members;
 pub(crate) use members::*;
 
+mod registry;
+pub use registry::*;
+
 mod universe_txn;
 pub use universe_txn::*;
 
@@ -114,12 +126,29 @@ pub struct Universe {
     ///
     /// When a step is in progress, this is updated before stepping any members.
     ///
-    /// TODO: This is not *currently* used for anything and should be removed if it never
-    /// is. Its current reason to exist is for some not-yet-merged diagnostic logging.
-    /// It may also serve a purpose when I refine the notion of simulation tick rate.
+    /// It is used to label entries in the [`DeterminismAudit`].
     ///
     /// [`step()`]: Universe::step
     session_step_time: u64,
+
+    /// If enabled, the record of this universe's state after each step.
+    determinism_audit: Option<DeterminismAudit>,
+
+    /// Sounds emitted by transactions since the start of the last [`step()`],
+    /// to be retrieved by [`Universe::take_sounds()`].
+    ///
+    /// [`step()`]: Universe::step
+    sounds: Vec<SoundEvent>,
+
+    /// Notifier of [`GameEvent`]s emitted by transactions.
+    event_notifier: Notifier<GameEvent>,
+
+    /// Stable identifiers of block definitions.
+    block_registry: BlockRegistry,
+
+    /// Stacking rules for the inventories of this universe's characters, which each
+    /// hold a clone of this [`Arc`].
+    stack_limits: Arc<StackLimits>,
 }
 
 impl Universe {
@@ -131,6 +160,11 @@ impl Universe {
             next_anonym: 0,
             wants_gc: false,
             session_step_time: 0,
+            determinism_audit: None,
+            sounds: Vec::new(),
+            event_notifier: Notifier::new(),
+            block_registry: BlockRegistry::default(),
+            stack_limits: Arc::default(),
         }
     }
 
@@ -148,7 +182,11 @@ impl Universe {
         let UniverseTables {
             blocks,
             characters,
+            entities,
+            player_stats,
+            scripts,
             spaces,
+            waypoints,
         } = &self.tables;
 
         if let Some(r) = blocks.get(name) {
@@ -157,9 +195,21 @@ impl Universe {
         if let Some(r) = characters.get(name) {
             return Some(Box::new(r.downgrade()));
         }
+        if let Some(r) = entities.get(name) {
+            return Some(Box::new(r.downgrade()));
+        }
+        if let Some(r) = player_stats.get(name) {
+            return Some(Box::new(r.downgrade()));
+        }
+        if let Some(r) = scripts.get(name) {
+            return Some(Box::new(r.downgrade()));
+        }
         if let Some(r) = spaces.get(name) {
             return Some(Box::new(r.downgrade()));
         }
+        if let Some(r) = waypoints.get(name) {
+            return Some(Box::new(r.downgrade()));
+        }
         None
     }
 
@@ -173,6 +223,87 @@ impl Universe {
         self.get(&"character".into())
     }
 
+    /// Returns the stable identifiers of this universe's block definitions.
+    pub fn block_registry(&self) -> &BlockRegistry {
+        &self.block_registry
+    }
+
+    /// Returns the stacking rules for the inventories of this universe's characters.
+    ///
+    /// Inventories not belonging to a character should be given these with
+    /// [`Inventory::set_stack_limits()`](crate::inv::Inventory::set_stack_limits).
+    pub fn stack_limits(&self) -> &StackLimits {
+        &self.stack_limits
+    }
+
+    /// Replaces the stacking rules for the inventories of this universe's characters.
+    ///
+    /// Existing stacks which exceed the new limits are left as they are, but no more
+    /// items will be added to them.
+    pub fn set_stack_limits(&mut self, limits: StackLimits) {
+        self.stack_limits = Arc::new(limits);
+        self.apply_stack_limits();
+    }
+
+    /// Gives [`Self::stack_limits`] to every character's inventory.
+    ///
+    /// Characters which cannot be modified right now receive the limits when they are
+    /// next stepped instead.
+    fn apply_stack_limits(&self) {
+        for character_root in self.tables.characters.values() {
+            let _ = character_root
+                .downgrade()
+                .try_modify(|ch| ch.set_stack_limits(&self.stack_limits));
+        }
+    }
+
+    /// Gives [`Self::stack_limits`] to a newly inserted member, if it has an inventory,
+    /// so that it obeys them before it is first stepped.
+    pub(crate) fn member_inserted(&self, member: &AnyURef) {
+        match member {
+            AnyURef::Character(character) => {
+                let _ = character.try_modify(|ch| ch.set_stack_limits(&self.stack_limits));
+            }
+            AnyURef::BlockDef(_)
+            | AnyURef::Entity(_)
+            | AnyURef::PlayerStats(_)
+            | AnyURef::Script(_)
+            | AnyURef::Space(_)
+            | AnyURef::Waypoint(_) => {}
+        }
+    }
+
+    /// Assigns the identifier `id` to the block definition `block_def`, which must be a
+    /// member of this universe, so that it may be found with
+    /// [`BlockRegistry::get()`].
+    ///
+    /// Returns an error if `id` is not a valid identifier or is already assigned.
+    ///
+    /// ```
+    /// use all_is_cubes::block::BlockDef;
+    /// use all_is_cubes::content::make_some_blocks;
+    /// use all_is_cubes::universe::{Name, Universe};
+    ///
+    /// let mut universe = Universe::new();
+    /// let [block] = make_some_blocks();
+    /// let block_def = universe.insert(Name::from("grass"), BlockDef::new(block)).unwrap();
+    /// universe.register_block("aic:grass", block_def.clone()).unwrap();
+    ///
+    /// assert_eq!(universe.block_registry().get("aic:grass"), Some(&block_def));
+    /// ```
+    pub fn register_block(
+        &mut self,
+        id: &str,
+        block_def: URef<BlockDef>,
+    ) -> Result<(), RegisterBlockError> {
+        self.block_registry.check_insert(id)?;
+        if block_def.universe_id() != Some(self.id) {
+            return Err(RegisterBlockError::WrongUniverse(block_def.name()));
+        }
+        self.block_registry.insert(id.into(), block_def);
+        Ok(())
+    }
+
     /// Returns a unique identifier for this particular [`Universe`] (within this memory space).
     ///
     /// It may be used to determine whether a given [`URef`] belongs to this universe or not.
@@ -183,10 +314,122 @@ impl Universe {
     /// Advance time for all members.
     ///
     /// * `deadline` is when to stop computing flexible things such as light transport.
+    ///
+    /// See also [`Universe::step_async()`], which does the same work but yields to the
+    /// executor between members if the deadline has passed.
     pub fn step(&mut self, tick: Tick, deadline: Instant) -> UniverseStepInfo {
         let mut info = UniverseStepInfo::default();
         let start_time = Instant::now();
 
+        self.step_begin(tick);
+
+        let mut transactions = StepTransactions::default();
+        for member in self.members_to_step() {
+            self.step_member(member, &mut info, &mut transactions, tick, deadline);
+        }
+
+        self.step_end(&mut info, tick, transactions);
+
+        info.computation_time = Instant::now().duration_since(start_time);
+        info
+    }
+
+    /// Advance time for all members, like [`Universe::step()`], but as a future which
+    /// yields (via `yield_progress`) between members once `deadline` has passed, so that
+    /// stepping a large universe does not block a single-threaded event loop for much
+    /// longer than the time budget.
+    ///
+    /// Members are always stepped to completion; the deadline only limits flexible work,
+    /// as with [`Universe::step()`], and controls when yielding begins. The universe
+    /// cannot be otherwise accessed while the future is pending.
+    ///
+    /// [`UniverseStepInfo::computation_time`] includes time spent yielded.
+    pub async fn step_async(
+        &mut self,
+        tick: Tick,
+        deadline: Instant,
+        yield_progress: YieldProgress,
+    ) -> UniverseStepInfo {
+        let mut info = UniverseStepInfo::default();
+        let start_time = Instant::now();
+
+        self.step_begin(tick);
+
+        let member_count = self.members_to_step().count();
+        let mut transactions = StepTransactions::default();
+        for (index, member) in self.members_to_step().enumerate() {
+            self.step_member(member, &mut info, &mut transactions, tick, deadline);
+            yield_if_late(&yield_progress, deadline, index + 1, member_count).await;
+        }
+
+        self.step_end(&mut info, tick, transactions);
+
+        info.computation_time = Instant::now().duration_since(start_time);
+        yield_progress.finish().await;
+        info
+    }
+
+    /// Returns the members which are stepped by [`Universe::step()`], in the order they
+    /// are stepped.
+    fn members_to_step(&self) -> impl Iterator<Item = MemberToStep<'_>> {
+        let UniverseTables {
+            spaces,
+            characters,
+            entities,
+            ..
+        } = &self.tables;
+        spaces
+            .iter()
+            .map(|(name, root)| MemberToStep::Space(name, root))
+            .chain(
+                characters
+                    .iter()
+                    .map(|(name, root)| MemberToStep::Character(name, root)),
+            )
+            .chain(
+                entities
+                    .iter()
+                    .map(|(name, root)| MemberToStep::Entity(name, root)),
+            )
+    }
+
+    /// Steps one member, as part of both [`Universe::step()`] and
+    /// [`Universe::step_async()`], recording its [`MemberStepInfo`] in `info` and the
+    /// transactions it produced in `transactions`.
+    fn step_member(
+        &self,
+        member: MemberToStep<'_>,
+        info: &mut UniverseStepInfo,
+        transactions: &mut StepTransactions,
+        tick: Tick,
+        deadline: Instant,
+    ) {
+        match member {
+            MemberToStep::Space(name, root) => {
+                let transaction = step_space(info, name, root, tick, deadline);
+                transactions.members.push(transaction);
+            }
+            MemberToStep::Character(name, root) => {
+                let (transaction, pickup) = step_character(
+                    info,
+                    name,
+                    root,
+                    &self.tables.entities,
+                    &self.stack_limits,
+                    tick,
+                );
+                transactions.members.push(transaction);
+                transactions.pickups.extend(pickup);
+            }
+            MemberToStep::Entity(name, root) => {
+                let transaction = step_entity(info, name, root, tick);
+                transactions.members.push(transaction);
+            }
+        }
+    }
+
+    /// Part of stepping that happens before any members are stepped.
+    fn step_begin(&mut self, tick: Tick) {
         if self.wants_gc {
             self.gc();
             self.wants_gc = false;
@@ -196,40 +439,65 @@ impl Universe {
             self.session_step_time += 1;
         }
 
-        let mut transactions = Vec::new();
+        // Discard sounds nobody took, so they don't accumulate.
+        self.sounds.clear();
+    }
 
-        for space_root in self.tables.spaces.values() {
-            let space_ref = space_root.downgrade();
-            let (space_info, transaction) = space_ref
-                .try_modify(|space| {
-                    // TODO(time-budget): fairly divide deadline among members that need it.
-                    // This implicitly implements an unfair "first wins" policy.
-                    space.step(Some(&space_ref), tick, deadline)
-                })
-                .expect("space borrowed during universe.step()");
-            transactions.push(transaction);
-            info.space_step += space_info;
+    /// Part of stepping that happens after all members are stepped: committing their
+    /// transactions, then characters' item pickups, and updating stats.
+    fn step_end(
+        &mut self,
+        info: &mut UniverseStepInfo,
+        tick: Tick,
+        StepTransactions {
+            members: transactions,
+            pickups,
+        }: StepTransactions,
+    ) {
+        // TODO: Quick hack -- we would actually like to execute non-conflicting transactions and skip conflicting ones...
+        for t in transactions {
+            if t == UniverseTransaction::default() {
+                continue;
+            }
+            match t.execute(self, &mut drop) {
+                Ok(()) => info.transactions_committed += 1,
+                Err(e) => {
+                    // TODO: Need to report these failures back to the source
+                    // ... and perhaps in the UniverseStepInfo
+                    log::info!("Transaction failure: {e}");
+                }
+            }
+        }
+        for t in pickups {
+            // A pickup fails if another character already took the same item, or it
+            // expired; either way, it simply doesn't happen.
+            if t.execute(self, &mut drop).is_ok() {
+                info.transactions_committed += 1;
+            }
         }
 
-        for character_root in self.tables.characters.values() {
-            let character_ref = character_root.downgrade();
-            let (_body_step_info, transaction) = character_ref
-                .try_modify(|ch| ch.step(Some(&character_ref), tick))
-                .expect("character borrowed during universe.step()");
-            transactions.push(transaction);
+        for stats_root in self.tables.player_stats.values() {
+            stats_root
+                .downgrade()
+                .try_modify(|stats| stats.step(tick))
+                .expect("player stats borrowed during universe.step()");
         }
 
-        // TODO: Quick hack -- we would actually like to execute non-conflicting transactions and skip conflicting ones...
-        for t in transactions {
-            if let Err(e) = t.execute(self, &mut drop) {
-                // TODO: Need to report these failures back to the source
-                // ... and perhaps in the UniverseStepInfo
-                log::info!("Transaction failure: {}", e);
+        if self.determinism_audit.is_some() {
+            let hash = self.state_hash();
+            if let Some(audit) = &mut self.determinism_audit {
+                audit.push(hash);
             }
         }
+    }
 
-        info.computation_time = Instant::now().duration_since(start_time);
-        info
+    /// Returns the [`SoundEvent`]s emitted by transactions committed during the most
+    /// recent [`step()`](Self::step), and any since, and removes them from the universe.
+    ///
+    /// This should be called after each step by whatever is responsible for playing
+    /// sounds; sounds not taken before the next step are discarded.
+    pub fn take_sounds(&mut self) -> Vec<SoundEvent> {
+        std::mem::take(&mut self.sounds)
     }
 
     /// Inserts a new object without giving it a specific name, and returns
@@ -298,6 +566,104 @@ impl Universe {
         UniverseOps::iter_by_type(self)
     }
 
+    /// Moves all members of `other` into this universe, so that a separately constructed
+    /// universe (such as a library of blocks or a prefabricated structure) can be
+    /// added to an existing one.
+    ///
+    /// * Members with [`Name::Specific`] names are given the same names, with `prefix`
+    ///   prepended.
+    /// * Anonymous members are given new anonymous names.
+    ///
+    /// All [`URef`]s to the members of `other`, whether held by other members or
+    /// anywhere else, will refer to the same members in their new location.
+    ///
+    /// Returns an error, without modifying `self`, if any of the new names is already
+    /// in use in this universe.
+    ///
+    /// ```
+    /// use all_is_cubes::block::{BlockDef, AIR};
+    /// use all_is_cubes::universe::{Name, Universe, URef};
+    ///
+    /// let mut library = Universe::new();
+    /// let block_ref = library.insert("air".into(), BlockDef::new(AIR)).unwrap();
+    ///
+    /// let mut universe = Universe::new();
+    /// universe.merge(library, "library/").unwrap();
+    ///
+    /// assert_eq!(block_ref.name(), Name::from("library/air"));
+    /// assert_eq!(block_ref.universe_id(), Some(universe.universe_id()));
+    /// assert_eq!(universe.get::<BlockDef>(&"library/air".into()), Some(block_ref));
+    /// ```
+    pub fn merge(&mut self, other: Universe, prefix: &str) -> Result<(), InsertError> {
+        let Universe {
+            tables:
+                UniverseTables {
+                    blocks,
+                    characters,
+                    entities,
+                    player_stats,
+                    scripts,
+                    spaces,
+                    waypoints,
+                },
+            id: _,
+            next_anonym: _,
+            wants_gc: _,
+            session_step_time: _,
+            determinism_audit: _,
+            sounds: _,
+            event_notifier: _,
+            block_registry,
+            // The receiving universe's rules apply to the merged characters.
+            stack_limits: _,
+        } = other;
+
+        // Check all names before moving anything, so that failure leaves `self` unchanged.
+        for name in blocks
+            .keys()
+            .chain(characters.keys())
+            .chain(entities.keys())
+            .chain(player_stats.keys())
+            .chain(scripts.keys())
+            .chain(spaces.keys())
+            .chain(waypoints.keys())
+        {
+            if let Some(new_name) = prefixed_name(prefix, name) {
+                if self.get_any(&new_name).is_some() {
+                    return Err(InsertError {
+                        name: new_name,
+                        kind: InsertErrorKind::AlreadyExists,
+                    });
+                }
+            }
+        }
+        // Block identifiers are not prefixed, so they must not conflict either.
+        for (id, _) in block_registry.iter() {
+            if self.block_registry.check_insert(id).is_err() {
+                return Err(InsertError {
+                    name: Name::from(id),
+                    kind: InsertErrorKind::AlreadyExists,
+                });
+            }
+        }
+
+        merge_members(self, blocks, prefix);
+        merge_members(self, characters, prefix);
+        merge_members(self, entities, prefix);
+        merge_members(self, player_stats, prefix);
+        merge_members(self, scripts, prefix);
+        merge_members(self, spaces, prefix);
+        merge_members(self, waypoints, prefix);
+        // The registry's refs have been rebound along with the members they refer to.
+        for (id, block_def) in block_registry.iter() {
+            self.block_registry.insert(id.into(), block_def.clone());
+        }
+        self.apply_stack_limits();
+        self.wants_gc = true;
+
+        Ok(())
+    }
+
     /// Convert a possibly-[pending](Name::Pending) [`Name`] into a name that may be an
     /// actual name in this universe (which is always either [`Name::Specific`] or
     /// [`Name::Anonym`] if it succeeds).
@@ -339,6 +705,57 @@ impl Universe {
         }
     }
 
+    /// Check that a name found in serialized data may be used for a new member, and
+    /// ensure that anonymous names allocated in the future will not collide with it.
+    ///
+    /// Unlike [`Universe::allocate_name()`], this accepts [`Name::Anonym`] but not
+    /// [`Name::Pending`].
+    fn reserve_deserialized_name(&mut self, name: &Name) -> Result<(), InsertError> {
+        match *name {
+            Name::Specific(_) | Name::Anonym(_) => {
+                if self.get_any(name).is_some() {
+                    return Err(InsertError {
+                        name: name.clone(),
+                        kind: InsertErrorKind::AlreadyExists,
+                    });
+                }
+                if let Name::Anonym(index) = *name {
+                    self.next_anonym = self.next_anonym.max(index + 1);
+                }
+                Ok(())
+            }
+            Name::Pending => Err(InsertError {
+                name: name.clone(),
+                kind: InsertErrorKind::InvalidName,
+            }),
+        }
+    }
+
+    /// Returns the names of all members which are placeholders created during
+    /// deserialization and not yet given values, in sorted order.
+    pub(crate) fn placeholder_names(&self) -> Vec<Name> {
+        let UniverseTables {
+            blocks,
+            characters,
+            entities,
+            player_stats,
+            scripts,
+            spaces,
+            waypoints,
+        } = &self.tables;
+
+        let mut names = Vec::new();
+        placeholder_names_in(blocks, &mut names);
+        placeholder_names_in(characters, &mut names);
+        placeholder_names_in(entities, &mut names);
+        placeholder_names_in(player_stats, &mut names);
+        placeholder_names_in(scripts, &mut names);
+        placeholder_names_in(spaces, &mut names);
+        placeholder_names_in(waypoints, &mut names);
+        names.sort();
+        names
+    }
+
     /// Delete a member.
     ///
     /// (Use [`UniverseTransaction::delete()`] as the public interface to this.)
@@ -348,12 +765,23 @@ impl Universe {
         let UniverseTables {
             blocks,
             characters,
+            entities,
+            player_stats,
+            scripts,
             spaces,
+            waypoints,
         } = &mut self.tables;
 
-        blocks.remove(name).is_some()
-            || characters.remove(name).is_some()
+        if blocks.remove(name).is_some() {
+            self.block_registry.remove_name(name);
+            return true;
+        }
+        characters.remove(name).is_some()
+            || entities.remove(name).is_some()
+            || player_stats.remove(name).is_some()
+            || scripts.remove(name).is_some()
             || spaces.remove(name).is_some()
+            || waypoints.remove(name).is_some()
     }
 
     /// Delete all anonymous members which have no references to them.
@@ -364,7 +792,11 @@ impl Universe {
         let UniverseTables {
             blocks,
             characters,
+            entities: _,
+            player_stats: _,
+            scripts,
             spaces,
+            waypoints: _,
         } = &mut self.tables;
 
         // TODO: We need a real GC algorithm. For now, let's perform non-cyclic collection by
@@ -377,6 +809,9 @@ impl Universe {
         // go away at a time that is deterministic with respect to the simulation.
         gc_members(blocks);
         gc_members(characters);
+        // `Entity`s, `PlayerStats`, and `Waypoint`s are not collected, since nothing else
+        // normally refers to them.
+        gc_members(scripts);
         gc_members(spaces);
     }
 }
@@ -389,15 +824,35 @@ impl fmt::Debug for Universe {
             next_anonym: _,
             wants_gc: _,
             session_step_time,
+            determinism_audit: _,
+            sounds: _,
+            event_notifier: _,
+            block_registry,
+            stack_limits,
         } = self;
 
         let mut ds = fmt.debug_struct("Universe");
+        if !block_registry.is_empty() {
+            ds.field("block_registry", &block_registry);
+        }
+        if **stack_limits != StackLimits::default() {
+            ds.field("stack_limits", &stack_limits);
+        }
         ds.field("session_step_time", &session_step_time);
         tables.fmt_members(&mut ds);
         ds.finish()
     }
 }
 
+impl Listen for Universe {
+    type Msg = GameEvent;
+    /// Registers a listener for [`GameEvent`]s emitted by transactions committed to this
+    /// universe.
+    fn listen<L: Listener<GameEvent> + Send + Sync + 'static>(&self, listener: L) {
+        self.event_notifier.listen(listener)
+    }
+}
+
 /// Iterator type for [`Universe::iter_by_type`].
 #[derive(Clone, Debug)]
 pub struct UniverseIter<'u, T>(std::collections::btree_map::Iter<'u, Name, URootRef<T>>);
@@ -458,6 +913,131 @@ impl fmt::Display for InsertError {
     }
 }
 
+/// A member of a [`Universe`] which is stepped by [`Universe::step()`].
+#[derive(Clone, Copy, Debug)]
+enum MemberToStep<'u> {
+    Space(&'u Name, &'u URootRef<Space>),
+    Character(&'u Name, &'u URootRef<Character>),
+    Entity(&'u Name, &'u URootRef<Entity>),
+}
+
+/// The transactions produced by stepping a [`Universe`]'s members, which are committed
+/// once all of them have been stepped.
+#[derive(Debug, Default)]
+struct StepTransactions {
+    /// Transactions produced by the members' own steps.
+    members: Vec<UniverseTransaction>,
+    /// Characters picking up items, which are committed after all of `members`.
+    pickups: Vec<UniverseTransaction>,
+}
+
+/// Steps one space as part of [`Universe::step()`], recording its [`MemberStepInfo`] and
+/// returning the transaction it produced.
+fn step_space(
+    info: &mut UniverseStepInfo,
+    name: &Name,
+    space_root: &URootRef<Space>,
+    tick: Tick,
+    deadline: Instant,
+) -> UniverseTransaction {
+    let member_start_time = Instant::now();
+    let space_ref = space_root.downgrade();
+    let (space_info, transaction, behaviors) = space_ref
+        .try_modify(|space| {
+            // TODO(time-budget): fairly divide deadline among members that need it.
+            // This implicitly implements an unfair "first wins" policy.
+            let (space_info, transaction) = space.step(Some(&space_ref), tick, deadline);
+            (
+                space_info,
+                transaction,
+                space.behaviors().query_any(None).count(),
+            )
+        })
+        .expect("space borrowed during universe.step()");
+    info.members.push(MemberStepInfo {
+        name: name.clone(),
+        computation_time: Instant::now().duration_since(member_start_time),
+        light_updates: space_info.light.update_count,
+        behaviors,
+    });
+    info.space_step += space_info;
+    transaction
+}
+
+/// Steps one character as part of [`Universe::step()`], recording its [`MemberStepInfo`]
+/// and returning the transaction it produced, and the transaction picking up any of
+/// `entities` it is near.
+fn step_character(
+    info: &mut UniverseStepInfo,
+    name: &Name,
+    character_root: &URootRef<Character>,
+    entities: &Storage<Entity>,
+    stack_limits: &Arc<StackLimits>,
+    tick: Tick,
+) -> (UniverseTransaction, Option<UniverseTransaction>) {
+    let member_start_time = Instant::now();
+    let character_ref = character_root.downgrade();
+    let (transaction, pickup, behaviors) = character_ref
+        .try_modify(|ch| {
+            ch.set_stack_limits(stack_limits);
+            let (_body_step_info, transaction) = ch.step(Some(&character_ref), tick);
+            let pickup = if tick.paused() {
+                None
+            } else {
+                ch.pick_up_items(&character_ref, entities.values().map(URootRef::downgrade))
+            };
+            (transaction, pickup, ch.behaviors.query_any(None).count())
+        })
+        .expect("character borrowed during universe.step()");
+    info.members.push(MemberStepInfo {
+        name: name.clone(),
+        computation_time: Instant::now().duration_since(member_start_time),
+        light_updates: 0,
+        behaviors,
+    });
+    (transaction, pickup)
+}
+
+/// Steps one entity as part of [`Universe::step()`], recording its [`MemberStepInfo`]
+/// and returning the transaction it produced.
+fn step_entity(
+    info: &mut UniverseStepInfo,
+    name: &Name,
+    entity_root: &URootRef<Entity>,
+    tick: Tick,
+) -> UniverseTransaction {
+    let member_start_time = Instant::now();
+    let entity_ref = entity_root.downgrade();
+    let (transaction, behaviors) = entity_ref
+        .try_modify(|entity| {
+            let (_body_step_info, transaction) = entity.step(Some(&entity_ref), tick);
+            (transaction, entity.behaviors.query_any(None).count())
+        })
+        .expect("entity borrowed during universe.step()");
+    info.members.push(MemberStepInfo {
+        name: name.clone(),
+        computation_time: Instant::now().duration_since(member_start_time),
+        light_updates: 0,
+        behaviors,
+    });
+    transaction
+}
+
+/// Used by [`Universe::step_async()`] after each member: reports progress, and yields
+/// only if the deadline has passed.
+async fn yield_if_late(
+    yield_progress: &YieldProgress,
+    deadline: Instant,
+    members_done: usize,
+    member_count: usize,
+) {
+    if Instant::now() >= deadline {
+        yield_progress
+            .progress(members_done as f32 / member_count as f32)
+            .await;
+    }
+}
+
 /// Performance data returned by [`Universe::step`]. The exact contents of this structure
 /// are unstable; use only `Debug` formatting to examine its contents unless you have
 /// a specific need for one of the values.
@@ -466,25 +1046,88 @@ impl fmt::Display for InsertError {
 pub struct UniverseStepInfo {
     #[doc(hidden)]
     pub computation_time: Duration,
-    space_step: SpaceStepInfo,
+    #[doc(hidden)]
+    pub space_step: SpaceStepInfo,
+    /// Performance data about each space and character that was stepped, in no
+    /// particular order.
+    pub members: Vec<MemberStepInfo>,
+    /// Number of non-empty transactions produced by stepping members, including item
+    /// pickups, which were committed.
+    pub transactions_committed: usize,
 }
 impl std::ops::AddAssign<UniverseStepInfo> for UniverseStepInfo {
     fn add_assign(&mut self, other: Self) {
         self.space_step += other.space_step;
+        self.transactions_committed += other.transactions_committed;
+        for member in other.members {
+            match self.members.iter_mut().find(|m| m.name == member.name) {
+                Some(existing) => *existing += member,
+                None => self.members.push(member),
+            }
+        }
     }
 }
 impl CustomFormat<StatusText> for UniverseStepInfo {
     fn fmt(&self, fmt: &mut fmt::Formatter<'_>, _: StatusText) -> fmt::Result {
+        /// Only this many of the slowest members are listed, to keep the text short.
+        const MAX_MEMBERS_SHOWN: usize = 5;
+
         writeln!(
             fmt,
             "Step computation: {}",
             self.computation_time.custom_format(StatusText),
         )?;
         write!(fmt, "{}", self.space_step.custom_format(StatusText))?;
+
+        if !self.members.is_empty() {
+            let mut members: Vec<&MemberStepInfo> = self.members.iter().collect();
+            members.sort_by(|a, b| b.computation_time.cmp(&a.computation_time));
+            write!(fmt, "\nSlowest members:  Time     Light Behav")?;
+            for member in members.iter().take(MAX_MEMBERS_SHOWN) {
+                let MemberStepInfo {
+                    name,
+                    computation_time,
+                    light_updates,
+                    behaviors,
+                } = member;
+                write!(
+                    fmt,
+                    "\n  {name:14} {time} {light_updates:5} {behaviors:5}",
+                    name = name.to_string(),
+                    time = computation_time.custom_format(StatusText),
+                )?;
+            }
+            if members.len() > MAX_MEMBERS_SHOWN {
+                write!(fmt, "\n  ...and {} more", members.len() - MAX_MEMBERS_SHOWN)?;
+            }
+        }
         Ok(())
     }
 }
 
+/// Performance data about stepping one [`Space`] or [`Character`] in
+/// [`Universe::step()`], as part of [`UniverseStepInfo`].
+#[derive(Clone, Debug, PartialEq)]
+#[non_exhaustive]
+pub struct MemberStepInfo {
+    /// The name of the member in its universe.
+    pub name: Name,
+    /// Time spent stepping the member.
+    pub computation_time: Duration,
+    /// Number of cubes whose light was updated; always zero for members other than
+    /// spaces.
+    pub light_updates: usize,
+    /// Number of [`Behavior`](crate::behavior::Behavior)s attached to the member.
+    pub behaviors: usize,
+}
+impl std::ops::AddAssign<MemberStepInfo> for MemberStepInfo {
+    fn add_assign(&mut self, other: Self) {
+        self.computation_time += other.computation_time;
+        self.light_updates += other.light_updates;
+        self.behaviors = other.behaviors;
+    }
+}
+
 /// Helper for [`Universe::gc()`].
 fn gc_members<T>(table: &mut Storage<T>) {
     let mut dead: Vec<Name> = Vec::new();
@@ -498,6 +1141,49 @@ fn gc_members<T>(table: &mut Storage<T>) {
     }
 }
 
+/// Helper for [`Universe::placeholder_names()`].
+fn placeholder_names_in<T>(table: &Storage<T>, names: &mut Vec<Name>) {
+    names.extend(
+        table
+            .iter()
+            .filter(|(_, root)| root.is_placeholder())
+            .map(|(name, _)| name.clone()),
+    );
+}
+
+/// Helper for [`Universe::merge()`]: computes the name a merged member will have,
+/// or [`None`] if it should be given a new anonymous name.
+fn prefixed_name(prefix: &str, name: &Name) -> Option<Name> {
+    match name {
+        Name::Specific(s) => Some(Name::Specific(format!("{prefix}{s}").into())),
+        Name::Anonym(_) | Name::Pending => None,
+    }
+}
+
+/// Helper for [`Universe::merge()`]: moves every entry of `table` into `universe`,
+/// rebinding the entries so that existing [`URef`]s follow them.
+///
+/// Names must have already been checked for conflicts.
+fn merge_members<T>(universe: &mut Universe, table: Storage<T>, prefix: &str)
+where
+    Universe: UniverseTable<T, Table = Storage<T>>,
+{
+    for (name, root) in table {
+        let new_name = match prefixed_name(prefix, &name) {
+            Some(new_name) => new_name,
+            None => universe
+                .allocate_name(&Name::Pending)
+                .expect("shouldn't happen: anonym allocation failed"),
+        };
+        root.rebind(universe.id, new_name.clone());
+        let previous = universe.table_mut().insert(new_name, root);
+        assert!(
+            previous.is_none(),
+            "shouldn't happen: merged name already in use"
+        );
+    }
+}
+
 /// A subset of the [`URef`]s in one universe.
 ///
 /// May be serialized as if it was a [`Universe`].
@@ -512,7 +1198,11 @@ pub struct PartialUniverse {
     // exports to be statically exhaustive.
     pub blocks: Vec<URef<BlockDef>>,
     pub characters: Vec<URef<Character>>,
+    pub entities: Vec<URef<Entity>>,
+    pub player_stats: Vec<URef<PlayerStats>>,
+    pub scripts: Vec<URef<Script>>,
     pub spaces: Vec<URef<Space>>,
+    pub waypoints: Vec<URef<Waypoint>>,
 }
 
 impl PartialUniverse {
@@ -520,7 +1210,11 @@ impl PartialUniverse {
         Self {
             blocks: universe.iter_by_type().map(|(_, r)| r).collect(),
             characters: universe.iter_by_type().map(|(_, r)| r).collect(),
+            entities: universe.iter_by_type().map(|(_, r)| r).collect(),
+            player_stats: universe.iter_by_type().map(|(_, r)| r).collect(),
+            scripts: universe.iter_by_type().map(|(_, r)| r).collect(),
             spaces: universe.iter_by_type().map(|(_, r)| r).collect(),
+            waypoints: universe.iter_by_type().map(|(_, r)| r).collect(),
         }
     }
 
@@ -538,8 +1232,18 @@ impl PartialUniverse {
         let Self {
             blocks,
             characters,
+            entities,
+            player_stats,
+            scripts,
             spaces,
+            waypoints,
         } = self;
-        blocks.len() + characters.len() + spaces.len()
+        blocks.len()
+            + characters.len()
+            + entities.len()
+            + player_stats.len()
+            + scripts.len()
+            + spaces.len()
+            + waypoints.len()
     }
 }
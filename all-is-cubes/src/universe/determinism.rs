@@ -0,0 +1,598 @@
+//! Hashing the state of a [`Universe`], to check that stepping it is deterministic.
+
+use std::fmt;
+
+use serde::ser::{self, Serialize};
+
+use crate::universe::{Name, PartialUniverse, Universe};
+
+#[cfg(doc)]
+use crate::{character::Character, physics::Body, space::Space};
+
+/// Hashes of the state of each member of a [`Universe`] at one moment, for detecting
+/// divergence between runs which should behave identically, such as a recording and its
+/// replay, or the same game on different platforms.
+///
+/// The hash of a member covers everything that would be saved about it, such as the
+/// blocks of a [`Space`] and the [`Body`] and inventory of a [`Character`], except for
+/// the light data of spaces, which depends on how much time was available to compute it.
+/// The hash function is fully specified and independent of the platform, so hashes
+/// computed by different builds (such as native and WebAssembly) may be compared.
+/// The order in which the entries of maps are serialized does not affect the hash.
+///
+/// Obtain these from [`Universe::state_hash()`].
+#[derive(Clone, Debug, Eq, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct UniverseStateHash {
+    step: u64,
+    /// Sorted by name.
+    members: Vec<(Name, u64)>,
+}
+
+impl UniverseStateHash {
+    /// Returns the number of steps the universe had taken when the hash was computed,
+    /// as counted since the [`Universe`] was created or loaded.
+    pub fn step(&self) -> u64 {
+        self.step
+    }
+
+    /// Returns the name and hash of each member, sorted by name.
+    pub fn members(&self) -> &[(Name, u64)] {
+        &self.members
+    }
+
+    /// Returns the name of the first member, in sorted order, whose hash differs between
+    /// `self` and `other` or which is present in only one of them.
+    pub fn first_difference(&self, other: &Self) -> Option<Name> {
+        let mut a = self.members.iter().peekable();
+        let mut b = other.members.iter().peekable();
+        loop {
+            match (a.peek(), b.peek()) {
+                (None, None) => return None,
+                (Some((name, _)), None) | (None, Some((name, _))) => return Some(name.clone()),
+                (Some((a_name, a_hash)), Some((b_name, b_hash))) => {
+                    if a_name != b_name {
+                        return Some(a_name.min(b_name).clone());
+                    } else if a_hash != b_hash {
+                        return Some(a_name.clone());
+                    }
+                    a.next();
+                    b.next();
+                }
+            }
+        }
+    }
+}
+
+/// A record of the [`UniverseStateHash`] after each step of a [`Universe`], which can be
+/// compared with the record of another run to find where they diverged.
+///
+/// To record the steps of a universe, use [`Universe::set_determinism_audit()`].
+/// The record may be serialized in order to compare runs in different processes; that
+/// format is intended only for this purpose and is not stable between versions.
+#[derive(Clone, Debug, Default, Eq, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct DeterminismAudit {
+    hashes: Vec<UniverseStateHash>,
+}
+
+impl DeterminismAudit {
+    /// Constructs an empty [`DeterminismAudit`].
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Appends the current state hash of `universe`.
+    pub fn record(&mut self, universe: &Universe) {
+        self.push(universe.state_hash());
+    }
+
+    pub(super) fn push(&mut self, hash: UniverseStateHash) {
+        self.hashes.push(hash);
+    }
+
+    /// Returns the recorded hashes, in the order they were recorded.
+    pub fn hashes(&self) -> &[UniverseStateHash] {
+        &self.hashes
+    }
+
+    /// Compares this record with `other`, entry by entry, and returns the first entry
+    /// at which the states differ.
+    ///
+    /// If one record is longer than the other, the extra entries are not compared.
+    pub fn first_divergence(&self, other: &Self) -> Option<Divergence> {
+        self.hashes
+            .iter()
+            .zip(other.hashes.iter())
+            .find_map(|(a, b)| {
+                Some(Divergence {
+                    step: a.step,
+                    member: a.first_difference(b)?,
+                })
+            })
+    }
+}
+
+/// The first difference between two runs, found by
+/// [`DeterminismAudit::first_divergence()`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[non_exhaustive]
+pub struct Divergence {
+    /// The step, as in [`UniverseStateHash::step()`] of the first record, after which
+    /// the states first differed.
+    pub step: u64,
+    /// The first member, in sorted order, whose state differed.
+    pub member: Name,
+}
+
+impl fmt::Display for Divergence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "universe member {} diverged at step {}",
+            self.member, self.step
+        )
+    }
+}
+
+impl Universe {
+    /// Computes hashes of the current state of each member of the universe.
+    ///
+    /// This is expensive, since it examines the entire contents of the universe.
+    ///
+    /// Panics if a member is currently borrowed mutably.
+    pub fn state_hash(&self) -> UniverseStateHash {
+        let entries = PartialUniverse::all_of(self)
+            .members_to_schema::<HashError>()
+            .expect("universe member borrowed while computing state hash");
+        let mut members: Vec<(Name, u64)> = entries
+            .into_iter()
+            .map(|entry| {
+                let mut hasher = StateHasher::new();
+                if let Err(HashError(message)) = entry.value.serialize(&mut hasher) {
+                    // Still produce a hash; the error is as much a part of the state as
+                    // anything else.
+                    hasher.write_str(&message);
+                }
+                (entry.name, hasher.state)
+            })
+            .collect();
+        members.sort_by(|(a, _), (b, _)| a.cmp(b));
+        UniverseStateHash {
+            step: self.session_step_time,
+            members,
+        }
+    }
+
+    /// Sets whether to record the [`UniverseStateHash`] after every step, for comparing
+    /// this run with others. Enabling the audit discards any previous record.
+    ///
+    /// This makes stepping much slower and should only be used for debugging.
+    pub fn set_determinism_audit(&mut self, enabled: bool) {
+        self.determinism_audit = enabled.then(DeterminismAudit::new);
+    }
+
+    /// Returns the record of state hashes since [`Universe::set_determinism_audit()`]
+    /// was enabled, or [`None`] if it is not enabled.
+    pub fn determinism_audit(&self) -> Option<&DeterminismAudit> {
+        self.determinism_audit.as_ref()
+    }
+}
+
+// Type tags written before each value, so that values of different types and shapes
+// do not produce the same sequence of bytes.
+const TAG_BOOL: u8 = 1;
+const TAG_INT: u8 = 2;
+const TAG_UINT: u8 = 3;
+const TAG_FLOAT: u8 = 4;
+const TAG_CHAR: u8 = 5;
+const TAG_STR: u8 = 6;
+const TAG_BYTES: u8 = 7;
+const TAG_NONE: u8 = 8;
+const TAG_SOME: u8 = 9;
+const TAG_UNIT: u8 = 10;
+const TAG_NAMED: u8 = 11;
+const TAG_VARIANT: u8 = 12;
+const TAG_SEQ: u8 = 13;
+const TAG_MAP: u8 = 14;
+const TAG_END: u8 = 15;
+
+/// [`Serializer`](ser::Serializer) which computes a 64-bit FNV-1a hash of the serialized
+/// form of a value.
+///
+/// FNV-1a is used because it is simple and fully specified, unlike
+/// [`std::collections::hash_map::DefaultHasher`]. All numbers are hashed in little-endian
+/// form at their widest size, so that the hash does not depend on the platform.
+struct StateHasher {
+    state: u64,
+}
+
+impl StateHasher {
+    fn new() -> Self {
+        Self {
+            state: 0xcbf2_9ce4_8422_2325,
+        }
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.state ^= u64::from(byte);
+            self.state = self.state.wrapping_mul(0x0000_0100_0000_01b3);
+        }
+    }
+
+    fn write_tag(&mut self, tag: u8) {
+        self.write(&[tag]);
+    }
+
+    fn write_len(&mut self, len: usize) {
+        self.write(&(len as u64).to_le_bytes());
+    }
+
+    fn write_str(&mut self, value: &str) {
+        self.write_len(value.len());
+        self.write(value.as_bytes());
+    }
+}
+
+#[derive(Debug)]
+struct HashError(String);
+
+impl fmt::Display for HashError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for HashError {}
+
+impl ser::Error for HashError {
+    fn custom<T: fmt::Display>(msg: T) -> Self {
+        HashError(msg.to_string())
+    }
+}
+
+impl<'a> ser::Serializer for &'a mut StateHasher {
+    type Ok = ();
+    type Error = HashError;
+    type SerializeSeq = Compound<'a>;
+    type SerializeTuple = Compound<'a>;
+    type SerializeTupleStruct = Compound<'a>;
+    type SerializeTupleVariant = Compound<'a>;
+    type SerializeMap = Compound<'a>;
+    type SerializeStruct = Compound<'a>;
+    type SerializeStructVariant = Compound<'a>;
+
+    fn is_human_readable(&self) -> bool {
+        false
+    }
+
+    fn serialize_bool(self, v: bool) -> Result<(), HashError> {
+        self.write_tag(TAG_BOOL);
+        self.write(&[u8::from(v)]);
+        Ok(())
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<(), HashError> {
+        self.serialize_i128(v.into())
+    }
+    fn serialize_i16(self, v: i16) -> Result<(), HashError> {
+        self.serialize_i128(v.into())
+    }
+    fn serialize_i32(self, v: i32) -> Result<(), HashError> {
+        self.serialize_i128(v.into())
+    }
+    fn serialize_i64(self, v: i64) -> Result<(), HashError> {
+        self.serialize_i128(v.into())
+    }
+    fn serialize_i128(self, v: i128) -> Result<(), HashError> {
+        self.write_tag(TAG_INT);
+        self.write(&v.to_le_bytes());
+        Ok(())
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<(), HashError> {
+        self.serialize_u128(v.into())
+    }
+    fn serialize_u16(self, v: u16) -> Result<(), HashError> {
+        self.serialize_u128(v.into())
+    }
+    fn serialize_u32(self, v: u32) -> Result<(), HashError> {
+        self.serialize_u128(v.into())
+    }
+    fn serialize_u64(self, v: u64) -> Result<(), HashError> {
+        self.serialize_u128(v.into())
+    }
+    fn serialize_u128(self, v: u128) -> Result<(), HashError> {
+        self.write_tag(TAG_UINT);
+        self.write(&v.to_le_bytes());
+        Ok(())
+    }
+
+    fn serialize_f32(self, v: f32) -> Result<(), HashError> {
+        self.serialize_f64(v.into())
+    }
+    fn serialize_f64(self, v: f64) -> Result<(), HashError> {
+        // NaN payloads may differ between platforms, so treat all NaNs alike.
+        let v = if v.is_nan() { f64::NAN } else { v };
+        self.write_tag(TAG_FLOAT);
+        self.write(&v.to_bits().to_le_bytes());
+        Ok(())
+    }
+
+    fn serialize_char(self, v: char) -> Result<(), HashError> {
+        self.write_tag(TAG_CHAR);
+        self.write(&u32::from(v).to_le_bytes());
+        Ok(())
+    }
+
+    fn serialize_str(self, v: &str) -> Result<(), HashError> {
+        self.write_tag(TAG_STR);
+        self.write_str(v);
+        Ok(())
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<(), HashError> {
+        self.write_tag(TAG_BYTES);
+        self.write_len(v.len());
+        self.write(v);
+        Ok(())
+    }
+
+    fn serialize_none(self) -> Result<(), HashError> {
+        self.write_tag(TAG_NONE);
+        Ok(())
+    }
+
+    fn serialize_some<T: ?Sized + Serialize>(self, value: &T) -> Result<(), HashError> {
+        self.write_tag(TAG_SOME);
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<(), HashError> {
+        self.write_tag(TAG_UNIT);
+        Ok(())
+    }
+
+    fn serialize_unit_struct(self, name: &'static str) -> Result<(), HashError> {
+        self.write_tag(TAG_NAMED);
+        self.write_str(name);
+        self.serialize_unit()
+    }
+
+    fn serialize_unit_variant(
+        self,
+        name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+    ) -> Result<(), HashError> {
+        self.write_variant(name, variant);
+        self.serialize_unit()
+    }
+
+    fn serialize_newtype_struct<T: ?Sized + Serialize>(
+        self,
+        name: &'static str,
+        value: &T,
+    ) -> Result<(), HashError> {
+        self.write_tag(TAG_NAMED);
+        self.write_str(name);
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: ?Sized + Serialize>(
+        self,
+        name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<(), HashError> {
+        self.write_variant(name, variant);
+        value.serialize(self)
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Compound<'a>, HashError> {
+        self.write_tag(TAG_SEQ);
+        Ok(Compound::new(self))
+    }
+
+    fn serialize_tuple(self, _len: usize) -> Result<Compound<'a>, HashError> {
+        self.serialize_seq(None)
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        name: &'static str,
+        _len: usize,
+    ) -> Result<Compound<'a>, HashError> {
+        self.write_tag(TAG_NAMED);
+        self.write_str(name);
+        self.serialize_seq(None)
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        _len: usize,
+    ) -> Result<Compound<'a>, HashError> {
+        self.write_variant(name, variant);
+        self.serialize_seq(None)
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Compound<'a>, HashError> {
+        self.write_tag(TAG_MAP);
+        Ok(Compound::new(self))
+    }
+
+    fn serialize_struct(self, name: &'static str, _len: usize) -> Result<Compound<'a>, HashError> {
+        self.write_tag(TAG_NAMED);
+        self.write_str(name);
+        self.serialize_map(None)
+    }
+
+    fn serialize_struct_variant(
+        self,
+        name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        _len: usize,
+    ) -> Result<Compound<'a>, HashError> {
+        self.write_variant(name, variant);
+        self.write_tag(TAG_MAP);
+        Ok(Compound {
+            // The light data of a space is excluded, as documented on UniverseStateHash.
+            skip_light: variant == "SpaceV1",
+            ..Compound::new(self)
+        })
+    }
+}
+
+impl StateHasher {
+    fn write_variant(&mut self, name: &str, variant: &str) {
+        self.write_tag(TAG_VARIANT);
+        self.write_str(name);
+        self.write_str(variant);
+    }
+}
+
+/// Serializer for the contents of sequences, maps, and structs, for [`StateHasher`].
+struct Compound<'a> {
+    hasher: &'a mut StateHasher,
+    /// Whether to skip a field named `light`.
+    skip_light: bool,
+    /// Hashes of the entries of a map, which are sorted before being written so that
+    /// maps whose iteration order varies, such as [`HashMap`], hash consistently.
+    ///
+    /// [`HashMap`]: std::collections::HashMap
+    map_entries: Vec<u64>,
+    /// Hasher for the map entry currently being serialized.
+    map_entry: StateHasher,
+}
+
+impl<'a> Compound<'a> {
+    fn new(hasher: &'a mut StateHasher) -> Self {
+        Self {
+            hasher,
+            skip_light: false,
+            map_entries: Vec::new(),
+            map_entry: StateHasher::new(),
+        }
+    }
+
+    fn element<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), HashError> {
+        value.serialize(&mut *self.hasher)
+    }
+
+    fn field<T: ?Sized + Serialize>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), HashError> {
+        if self.skip_light && key == "light" {
+            return Ok(());
+        }
+        self.hasher.write_str(key);
+        value.serialize(&mut *self.hasher)
+    }
+
+    fn end(self) -> Result<(), HashError> {
+        self.hasher.write_tag(TAG_END);
+        Ok(())
+    }
+}
+
+impl ser::SerializeSeq for Compound<'_> {
+    type Ok = ();
+    type Error = HashError;
+    fn serialize_element<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), HashError> {
+        self.element(value)
+    }
+    fn end(self) -> Result<(), HashError> {
+        Compound::end(self)
+    }
+}
+
+impl ser::SerializeTuple for Compound<'_> {
+    type Ok = ();
+    type Error = HashError;
+    fn serialize_element<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), HashError> {
+        self.element(value)
+    }
+    fn end(self) -> Result<(), HashError> {
+        Compound::end(self)
+    }
+}
+
+impl ser::SerializeTupleStruct for Compound<'_> {
+    type Ok = ();
+    type Error = HashError;
+    fn serialize_field<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), HashError> {
+        self.element(value)
+    }
+    fn end(self) -> Result<(), HashError> {
+        Compound::end(self)
+    }
+}
+
+impl ser::SerializeTupleVariant for Compound<'_> {
+    type Ok = ();
+    type Error = HashError;
+    fn serialize_field<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), HashError> {
+        self.element(value)
+    }
+    fn end(self) -> Result<(), HashError> {
+        Compound::end(self)
+    }
+}
+
+impl ser::SerializeMap for Compound<'_> {
+    type Ok = ();
+    type Error = HashError;
+    fn serialize_key<T: ?Sized + Serialize>(&mut self, key: &T) -> Result<(), HashError> {
+        self.map_entry = StateHasher::new();
+        key.serialize(&mut self.map_entry)
+    }
+    fn serialize_value<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), HashError> {
+        value.serialize(&mut self.map_entry)?;
+        self.map_entries.push(self.map_entry.state);
+        Ok(())
+    }
+    fn end(mut self) -> Result<(), HashError> {
+        self.map_entries.sort_unstable();
+        self.hasher.write_len(self.map_entries.len());
+        for entry in &self.map_entries {
+            self.hasher.write(&entry.to_le_bytes());
+        }
+        Compound::end(self)
+    }
+}
+
+impl ser::SerializeStruct for Compound<'_> {
+    type Ok = ();
+    type Error = HashError;
+    fn serialize_field<T: ?Sized + Serialize>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), HashError> {
+        self.field(key, value)
+    }
+    fn end(self) -> Result<(), HashError> {
+        Compound::end(self)
+    }
+}
+
+impl ser::SerializeStructVariant for Compound<'_> {
+    type Ok = ();
+    type Error = HashError;
+    fn serialize_field<T: ?Sized + Serialize>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), HashError> {
+        self.field(key, value)
+    }
+    fn end(self) -> Result<(), HashError> {
+        Compound::end(self)
+    }
+}
@@ -8,9 +8,13 @@ use std::collections::BTreeMap;
 use std::fmt;
 
 use crate::block::BlockDef;
-use crate::character::Character;
-use crate::space::Space;
-use crate::universe::{InsertError, Name, PartialUniverse, URef, URootRef, Universe, UniverseIter};
+use crate::character::{Character, PlayerStats};
+use crate::entity::Entity;
+use crate::script::Script;
+use crate::space::{Space, Waypoint};
+use crate::universe::{
+    InsertError, InsertErrorKind, Name, PartialUniverse, URef, URootRef, Universe, UniverseIter,
+};
 use crate::util::CustomFormat as _;
 
 /// A `BTreeMap` is used to ensure that the iteration order is deterministic across
@@ -62,6 +66,17 @@ where
     fn insert(&mut self, name: Name, value: T) -> Result<URef<T>, InsertError>;
 
     fn iter_by_type(&self) -> UniverseIter<'_, T>;
+
+    /// Used by deserialization: returns a [`URef`] to the member with the given name,
+    /// creating a placeholder for it (to be filled by [`Self::insert_deserialized()`])
+    /// if it does not exist yet.
+    fn get_or_insert_placeholder(&mut self, name: Name) -> Result<URef<T>, InsertError>;
+
+    /// Used by deserialization: gives a value to the placeholder with the given name,
+    /// or inserts a new member if there is no placeholder.
+    ///
+    /// Unlike [`Self::insert()`], this accepts [`Name::Anonym`] names.
+    fn insert_deserialized(&mut self, name: Name, value: T) -> Result<URef<T>, InsertError>;
 }
 
 /// Trait implemented by [`PartialUniverse`] once for each type of object that can be
@@ -97,6 +112,7 @@ pub(super) fn ops_insert<T>(
     value: T,
 ) -> Result<URef<T>, InsertError>
 where
+    T: UniverseMember,
     Universe: UniverseTable<T, Table = Storage<T>>,
 {
     use std::collections::btree_map::Entry::*;
@@ -112,11 +128,61 @@ where
             let root_ref = URootRef::new(id, name, value);
             let returned_ref = root_ref.downgrade();
             vacant.insert(root_ref);
+            this.member_inserted(&T::into_any_ref(returned_ref.clone()));
             Ok(returned_ref)
         }
     }
 }
 
+/// Implementation of [`UniverseOps::get_or_insert_placeholder()`].
+pub(super) fn ops_get_or_insert_placeholder<T>(
+    this: &mut Universe,
+    name: Name,
+) -> Result<URef<T>, InsertError>
+where
+    Universe: UniverseTable<T, Table = Storage<T>>,
+{
+    if let Some(root_ref) = <Universe as UniverseTable<T>>::table(this).get(&name) {
+        return Ok(root_ref.downgrade());
+    }
+
+    this.reserve_deserialized_name(&name)?;
+
+    let root_ref = URootRef::new_placeholder(this.id, name.clone());
+    let returned_ref = root_ref.downgrade();
+    this.table_mut().insert(name, root_ref);
+    Ok(returned_ref)
+}
+
+/// Implementation of [`UniverseOps::insert_deserialized()`].
+pub(super) fn ops_insert_deserialized<T>(
+    this: &mut Universe,
+    name: Name,
+    value: T,
+) -> Result<URef<T>, InsertError>
+where
+    Universe: UniverseTable<T, Table = Storage<T>>,
+{
+    if let Some(root_ref) = <Universe as UniverseTable<T>>::table(this).get(&name) {
+        return if root_ref.fill_placeholder(value) {
+            Ok(root_ref.downgrade())
+        } else {
+            Err(InsertError {
+                name,
+                kind: InsertErrorKind::AlreadyExists,
+            })
+        };
+    }
+
+    this.reserve_deserialized_name(&name)?;
+    this.wants_gc = true;
+
+    let root_ref = URootRef::new(this.id, name.clone(), value);
+    let returned_ref = root_ref.downgrade();
+    this.table_mut().insert(name, root_ref);
+    Ok(returned_ref)
+}
+
 /// Generates impls for a specific Universe member type.
 macro_rules! impl_universe_for_member {
     ($member_type:ident, $table:ident) => {
@@ -162,6 +228,19 @@ macro_rules! impl_universe_for_member {
             fn iter_by_type(&self) -> UniverseIter<'_, $member_type> {
                 UniverseIter(UniverseTable::<$member_type>::table(self).iter())
             }
+            fn get_or_insert_placeholder(
+                &mut self,
+                name: Name,
+            ) -> Result<URef<$member_type>, InsertError> {
+                ops_get_or_insert_placeholder(self, name)
+            }
+            fn insert_deserialized(
+                &mut self,
+                name: Name,
+                value: $member_type,
+            ) -> Result<URef<$member_type>, InsertError> {
+                ops_insert_deserialized(self, name, value)
+            }
         }
 
         impl PartialUniverseOps<$member_type> for PartialUniverse {
@@ -232,7 +311,15 @@ macro_rules! member_enums_and_impls {
 //    Universe::gc
 //    Universe::step
 //    transaction::universe_txn::*
-member_enums_and_impls!((BlockDef, blocks), (Character, characters), (Space, spaces),);
+member_enums_and_impls!(
+    (BlockDef, blocks),
+    (Character, characters),
+    (Entity, entities),
+    (PlayerStats, player_stats),
+    (Script, scripts),
+    (Space, spaces),
+    (Waypoint, waypoints),
+);
 
 impl super::URefErased for AnyURef {
     fn name(&self) -> Name {
@@ -0,0 +1,129 @@
+//! [`BlockRegistry`], stable identifiers for block definitions.
+
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use crate::block::BlockDef;
+use crate::universe::{Name, URef};
+
+#[cfg(doc)]
+use crate::universe::Universe;
+
+/// Mapping from stable string identifiers, such as `aic:grass`, to the [`BlockDef`]s of
+/// a [`Universe`].
+///
+/// Unlike the [`Name`]s of universe members, which are chosen by whoever built the
+/// universe and may change as it is edited, identifiers are meant to stay the same so
+/// that importers, scripts, and commands typed by players can refer to blocks by them.
+/// An identifier consists of a namespace and a path separated by a colon; each may
+/// contain ASCII lowercase letters, digits, and `_`, `-`, and `.`, and the path may
+/// also contain `/`.
+///
+/// A universe's registry is obtained with [`Universe::block_registry()`] and
+/// modified with [`Universe::register_block()`], and is saved with the universe.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct BlockRegistry {
+    ids: BTreeMap<Arc<str>, URef<BlockDef>>,
+}
+
+impl BlockRegistry {
+    /// Returns the block definition with the given identifier, if there is one.
+    pub fn get(&self, id: &str) -> Option<&URef<BlockDef>> {
+        self.ids.get(id)
+    }
+
+    /// Returns an identifier of the given block definition, if it has one.
+    ///
+    /// If it has more than one, the first in sorted order is returned.
+    pub fn id_of(&self, block_def: &URef<BlockDef>) -> Option<&str> {
+        self.ids
+            .iter()
+            .find(|(_, r)| *r == block_def)
+            .map(|(id, _)| &**id)
+    }
+
+    /// Iterates over all identifiers and their block definitions, in sorted order of
+    /// identifier.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &URef<BlockDef>)> + '_ {
+        self.ids.iter().map(|(id, r)| (&**id, r))
+    }
+
+    /// Returns the number of identifiers registered.
+    pub fn len(&self) -> usize {
+        self.ids.len()
+    }
+
+    /// Returns whether no identifiers are registered.
+    pub fn is_empty(&self) -> bool {
+        self.ids.is_empty()
+    }
+
+    /// Checks that `id` may be added to the registry.
+    pub(crate) fn check_insert(&self, id: &str) -> Result<(), RegisterBlockError> {
+        if !is_valid_id(id) {
+            Err(RegisterBlockError::InvalidId(id.into()))
+        } else if self.ids.contains_key(id) {
+            Err(RegisterBlockError::AlreadyRegistered(id.into()))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Adds `id` to the registry. The caller must have called [`Self::check_insert()`].
+    pub(crate) fn insert(&mut self, id: Arc<str>, block_def: URef<BlockDef>) {
+        self.ids.insert(id, block_def);
+    }
+
+    /// Removes all identifiers of the block definition with the given name.
+    pub(crate) fn remove_name(&mut self, name: &Name) {
+        self.ids.retain(|_, r| r.name() != *name);
+    }
+}
+
+/// Returns whether `id` has the form described in [`BlockRegistry`].
+fn is_valid_id(id: &str) -> bool {
+    let valid_char =
+        |c: char| c.is_ascii_lowercase() || c.is_ascii_digit() || matches!(c, '_' | '-' | '.');
+    match id.split_once(':') {
+        Some((namespace, path)) => {
+            !namespace.is_empty()
+                && !path.is_empty()
+                && namespace.chars().all(valid_char)
+                && path.chars().all(|c| valid_char(c) || c == '/')
+        }
+        None => false,
+    }
+}
+
+/// Error from [`Universe::register_block()`].
+#[derive(Clone, Debug, Eq, Hash, PartialEq, thiserror::Error)]
+#[non_exhaustive]
+pub enum RegisterBlockError {
+    /// The identifier is already assigned to a block definition.
+    #[error("block identifier '{0}' is already registered")]
+    AlreadyRegistered(Arc<str>),
+    /// The identifier does not have the form described in [`BlockRegistry`].
+    #[error("'{0}' is not a valid block identifier")]
+    InvalidId(Arc<str>),
+    /// The block definition is not a member of the universe.
+    #[error("block definition {0} is not in this universe")]
+    WrongUniverse(Name),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn valid_ids() {
+        assert!(is_valid_id("aic:grass"));
+        assert!(is_valid_id("my-mod.v2:trees/oak_log"));
+        assert!(!is_valid_id("grass"));
+        assert!(!is_valid_id(":grass"));
+        assert!(!is_valid_id("aic:"));
+        assert!(!is_valid_id("aic:Grass"));
+        assert!(!is_valid_id("aic/x:grass"));
+        assert!(!is_valid_id("aic:grass:tall"));
+        assert!(!is_valid_id("aic:tall grass"));
+    }
+}
@@ -1,17 +1,32 @@
 use std::any::TypeId;
+use std::num::NonZeroU16;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
 
+use cgmath::Point3;
+use instant::Instant;
+
+use crate::behavior::NoopBehavior;
 use crate::block::{Block, BlockDef, BlockDefTransaction, Primitive, Resolution, AIR};
-use crate::character::{Character, CharacterTransaction};
+use crate::character::{Character, CharacterTransaction, PlayerStats};
 use crate::content::make_some_blocks;
-use crate::inv::{InventoryTransaction, Tool};
-use crate::math::Rgba;
-use crate::space::Space;
+use crate::entity::Entity;
+use crate::event::GameEvent;
+use crate::fluff::Fluff;
+use crate::inv::{InventoryTransaction, Slot, StackLimits, Tool};
+use crate::listen::{Listen as _, Sink};
+use crate::math::{Aab, Rgba};
+use crate::physics::Body;
+use crate::script::Script;
+use crate::sound::SoundEvent;
+use crate::space::{Space, Waypoint};
 use crate::time::{practically_infinite_deadline, Tick};
 use crate::transaction::{self, Transaction};
 use crate::universe::{
-    list_refs, InsertError, InsertErrorKind, Name, RefError, URef, Universe, UniverseTransaction,
+    list_refs, Divergence, InsertError, InsertErrorKind, Name, RefError, RegisterBlockError, URef,
+    Universe, UniverseTransaction,
 };
-use crate::util::assert_send_sync;
+use crate::util::{assert_send_sync, YieldProgress};
 
 #[test]
 fn thread_safety() {
@@ -63,7 +78,17 @@ fn get_any() {
     let sp = u
         .insert("test_space".into(), Space::empty_positive(1, 1, 1))
         .unwrap();
-    u.insert("test_char".into(), Character::spawn_default(sp))
+    let ch = u
+        .insert("test_char".into(), Character::spawn_default(sp.clone()))
+        .unwrap();
+    u.insert("test_stats".into(), PlayerStats::new(ch)).unwrap();
+    u.insert(
+        "test_entity".into(),
+        Entity::new(sp.clone(), Body::new_minimal([0., 0., 0.], Aab::ZERO), AIR),
+    )
+    .unwrap();
+    u.insert("test_script".into(), Script::new("")).unwrap();
+    u.insert("test_waypoint".into(), Waypoint::new(sp, [0., 0., 0.]))
         .unwrap();
 
     assert!(u.get_any(&"nonexistent".into()).is_none());
@@ -80,6 +105,22 @@ fn get_any() {
         u.get_any(&"test_char".into()).unwrap().type_id(),
         TypeId::of::<URef<Character>>()
     );
+    assert_eq!(
+        u.get_any(&"test_entity".into()).unwrap().type_id(),
+        TypeId::of::<URef<Entity>>()
+    );
+    assert_eq!(
+        u.get_any(&"test_stats".into()).unwrap().type_id(),
+        TypeId::of::<URef<PlayerStats>>()
+    );
+    assert_eq!(
+        u.get_any(&"test_script".into()).unwrap().type_id(),
+        TypeId::of::<URef<Script>>()
+    );
+    assert_eq!(
+        u.get_any(&"test_waypoint".into()).unwrap().type_id(),
+        TypeId::of::<URef<Waypoint>>()
+    );
 }
 
 #[test]
@@ -243,6 +284,18 @@ fn delete_anonymous_fails() {
         .unwrap_err();
 }
 
+/// Entities are not garbage collected, so anonymous ones can be deleted.
+#[test]
+fn delete_anonymous_entity() {
+    let mut u = Universe::new();
+    let space = u.insert_anonymous(Space::empty_positive(1, 1, 1));
+    let entity = u.insert_anonymous(Entity::new_item(space, [0, 0, 0].into(), Tool::Block(AIR)));
+    UniverseTransaction::delete(entity.clone())
+        .execute(&mut u, &mut drop)
+        .unwrap();
+    assert!(matches!(entity.read(), Err(RefError::Gone(_))));
+}
+
 #[test]
 fn delete_twice_fails() {
     let mut u = Universe::new();
@@ -281,6 +334,129 @@ fn step_time() {
     assert_eq!(u.session_step_time, 1);
 }
 
+#[test]
+fn step_info_per_member() {
+    let mut u = Universe::new();
+    let space = u
+        .insert("space".into(), Space::empty_positive(1, 1, 1))
+        .unwrap();
+    let mut character = Character::spawn_default(space);
+    character.add_behavior(NoopBehavior(1));
+    u.insert("character".into(), character).unwrap();
+
+    let mut info = u.step(Tick::arbitrary(), practically_infinite_deadline());
+    info += u.step(Tick::arbitrary(), practically_infinite_deadline());
+
+    let mut members: Vec<(Name, usize)> = info
+        .members
+        .iter()
+        .map(|m| (m.name.clone(), m.behaviors))
+        .collect();
+    members.sort();
+    assert_eq!(members, vec![("character".into(), 1), ("space".into(), 0)]);
+}
+
+/// Two characters reaching the same item in the same step don't cause either
+/// character's step to fail; one of them gets the item.
+#[test]
+fn pickup_by_two_characters() {
+    let mut u = Universe::new();
+    let space = u
+        .insert("space".into(), Space::empty_positive(1, 1, 1))
+        .unwrap();
+    let [block] = make_some_blocks();
+    let item = Tool::Block(block);
+    let c1 = u
+        .insert("c1".into(), Character::spawn_default(space.clone()))
+        .unwrap();
+    let c2 = u
+        .insert("c2".into(), Character::spawn_default(space.clone()))
+        .unwrap();
+    let mut entity = Entity::new_item(space, [0, 0, 0].into(), item.clone());
+    entity.body.position = c1.read().unwrap().body.position;
+    let entity = u.insert_anonymous(entity);
+
+    u.step(Tick::arbitrary(), practically_infinite_deadline());
+
+    assert!(matches!(entity.read(), Err(RefError::Gone(_))));
+    let count = |c: &URef<Character>| c.read().unwrap().inventory().count_of(&item);
+    assert_eq!(count(&c1) + count(&c2), 1);
+}
+
+/// [`Universe::step_async()`] yields between members only once the deadline has passed.
+#[tokio::test]
+async fn step_async_yields_when_late() {
+    let mut u = Universe::new();
+    let space = u
+        .insert("space".into(), Space::empty_positive(1, 1, 1))
+        .unwrap();
+    u.insert("character".into(), Character::spawn_default(space))
+        .unwrap();
+
+    let yields = Arc::new(AtomicUsize::new(0));
+    let counting_yield_progress = || {
+        let yields = yields.clone();
+        YieldProgress::new(
+            move || {
+                yields.fetch_add(1, Ordering::Relaxed);
+                std::future::ready(())
+            },
+            |_, _| {},
+        )
+    };
+
+    // Plenty of time: only the final progress report yields.
+    let info = u
+        .step_async(
+            Tick::arbitrary(),
+            practically_infinite_deadline(),
+            counting_yield_progress(),
+        )
+        .await;
+    assert_eq!(info.members.len(), 2);
+    assert_eq!(yields.swap(0, Ordering::Relaxed), 1);
+
+    // Already late: yields after each member too.
+    let info = u
+        .step_async(Tick::arbitrary(), Instant::now(), counting_yield_progress())
+        .await;
+    assert_eq!(info.members.len(), 2);
+    assert_eq!(yields.swap(0, Ordering::Relaxed), 3);
+}
+
+#[test]
+fn sounds_collected_per_step() {
+    let mut u = Universe::new();
+    let sound = SoundEvent::new(Fluff::Happened, Some(Point3::new(1., 2., 3.)));
+    UniverseTransaction::sound(sound.clone())
+        .execute(&mut u, &mut drop)
+        .unwrap();
+    assert_eq!(u.take_sounds(), vec![sound.clone()]);
+    assert_eq!(u.take_sounds(), vec![]);
+
+    // Sounds not taken are discarded by the next step.
+    UniverseTransaction::sound(sound)
+        .execute(&mut u, &mut drop)
+        .unwrap();
+    u.step(Tick::arbitrary(), practically_infinite_deadline());
+    assert_eq!(u.take_sounds(), vec![]);
+}
+
+#[test]
+fn events_notify_listeners() {
+    let mut u = Universe::new();
+    let sink = Sink::new();
+    u.listen(sink.listener());
+    let event = GameEvent::Chat {
+        sender: None,
+        message: "hello".into(),
+    };
+    UniverseTransaction::event(event.clone())
+        .execute(&mut u, &mut drop)
+        .unwrap();
+    assert_eq!(sink.drain(), vec![event]);
+}
+
 #[test]
 fn gc_explicit() {
     let mut u = Universe::new();
@@ -299,6 +475,176 @@ fn gc_implicit() {
     assert_eq!(0, u.iter_by_type::<BlockDef>().count());
 }
 
+#[test]
+fn merge_renames_and_keeps_refs() {
+    let mut library = Universe::new();
+    let space_ref = library
+        .insert("s".into(), Space::empty_positive(1, 1, 1))
+        .unwrap();
+    let block_ref = library
+        .insert(
+            "b".into(),
+            BlockDef::new(
+                Block::builder()
+                    .voxels_ref(Resolution::R1, space_ref.clone())
+                    .build(),
+            ),
+        )
+        .unwrap();
+    let anon_ref = library.insert_anonymous(BlockDef::new(AIR));
+
+    let mut u = Universe::new();
+    u.insert_anonymous(BlockDef::new(AIR));
+    u.merge(library, "lib:").unwrap();
+
+    assert_eq!(space_ref.name(), "lib:s".into());
+    assert_eq!(block_ref.name(), "lib:b".into());
+    assert_eq!(anon_ref.name(), Name::Anonym(1));
+    assert_eq!(space_ref.universe_id(), Some(u.universe_id()));
+    assert_eq!(u.get::<Space>(&"lib:s".into()), Some(space_ref.clone()));
+    assert_eq!(u.get::<BlockDef>(&"lib:b".into()), Some(block_ref.clone()));
+
+    // The reference inside the block definition is still connected to the space.
+    assert_eq!(list_refs(&*block_ref.read().unwrap()), vec!["lib:s".into()]);
+}
+
+#[test]
+fn merge_conflict_leaves_universe_unchanged() {
+    let mut library = Universe::new();
+    library.insert("a".into(), BlockDef::new(AIR)).unwrap();
+    library
+        .insert("b".into(), Space::empty_positive(1, 1, 1))
+        .unwrap();
+
+    let mut u = Universe::new();
+    u.insert("lib:b".into(), BlockDef::new(AIR)).unwrap();
+    assert_eq!(
+        u.merge(library, "lib:"),
+        Err(InsertError {
+            name: "lib:b".into(),
+            kind: InsertErrorKind::AlreadyExists,
+        })
+    );
+    assert!(u.get_any(&"lib:a".into()).is_none());
+}
+
+#[test]
+fn merge_keeps_block_ids() {
+    let mut library = Universe::new();
+    let block_ref = library.insert("b".into(), BlockDef::new(AIR)).unwrap();
+    library.register_block("lib:b", block_ref.clone()).unwrap();
+
+    let mut u = Universe::new();
+    u.merge(library, "lib:").unwrap();
+    assert_eq!(u.block_registry().get("lib:b"), Some(&block_ref));
+    assert_eq!(block_ref.universe_id(), Some(u.universe_id()));
+}
+
+#[test]
+fn merge_block_id_conflict() {
+    let mut library = Universe::new();
+    let lib_block = library.insert("b".into(), BlockDef::new(AIR)).unwrap();
+    library.register_block("x:b", lib_block).unwrap();
+
+    let mut u = Universe::new();
+    let block_ref = u.insert("b".into(), BlockDef::new(AIR)).unwrap();
+    u.register_block("x:b", block_ref.clone()).unwrap();
+    assert_eq!(
+        u.merge(library, "lib:"),
+        Err(InsertError {
+            name: "x:b".into(),
+            kind: InsertErrorKind::AlreadyExists,
+        })
+    );
+    assert!(u.get_any(&"lib:b".into()).is_none());
+    assert_eq!(u.block_registry().get("x:b"), Some(&block_ref));
+}
+
+#[test]
+fn register_block_errors() {
+    let mut u = Universe::new();
+    let block_ref = u.insert("b".into(), BlockDef::new(AIR)).unwrap();
+    u.register_block("x:b", block_ref.clone()).unwrap();
+
+    assert_eq!(
+        u.register_block("x:b", block_ref.clone()),
+        Err(RegisterBlockError::AlreadyRegistered("x:b".into()))
+    );
+    assert_eq!(
+        u.register_block("b", block_ref),
+        Err(RegisterBlockError::InvalidId("b".into()))
+    );
+
+    let mut other = Universe::new();
+    let other_ref = other.insert("o".into(), BlockDef::new(AIR)).unwrap();
+    assert_eq!(
+        u.register_block("x:o", other_ref),
+        Err(RegisterBlockError::WrongUniverse("o".into()))
+    );
+    assert_eq!(u.block_registry().len(), 1);
+}
+
+#[test]
+fn delete_removes_block_id() {
+    let mut u = Universe::new();
+    let block_ref = u.insert("b".into(), BlockDef::new(AIR)).unwrap();
+    u.register_block("x:b", block_ref.clone()).unwrap();
+    u.register_block("x:b2", block_ref.clone()).unwrap();
+    assert_eq!(u.block_registry().id_of(&block_ref), Some("x:b"));
+
+    UniverseTransaction::delete(block_ref)
+        .execute(&mut u, &mut drop)
+        .unwrap();
+    assert!(u.block_registry().is_empty());
+}
+
+/// Characters inserted after the limits are set obey them immediately, not only once
+/// they have been stepped.
+#[test]
+fn stack_limits_apply_to_inserted_characters() {
+    let mut u = Universe::new();
+    let limits = StackLimits::new(NonZeroU16::new(12).unwrap());
+    u.set_stack_limits(limits.clone());
+    let space = u.insert_anonymous(Space::empty_positive(1, 1, 1));
+
+    let direct = u
+        .insert("direct".into(), Character::spawn_default(space.clone()))
+        .unwrap();
+    let via_txn = URef::new_pending("via_txn".into(), Character::spawn_default(space));
+    UniverseTransaction::insert(via_txn.clone())
+        .execute(&mut u, &mut drop)
+        .unwrap();
+
+    for character in [direct, via_txn] {
+        assert_eq!(
+            character.read().unwrap().inventory().stack_limits(),
+            &limits
+        );
+
+        // Inventory transactions use the limits before any step.
+        let [block] = make_some_blocks();
+        let item = Tool::Block(block);
+        character
+            .execute(
+                &CharacterTransaction::inventory(InventoryTransaction::insert([Slot::stack(
+                    20,
+                    item.clone(),
+                )])),
+                &mut drop,
+            )
+            .unwrap();
+        let character = character.read().unwrap();
+        let stack_sizes: Vec<u16> = character
+            .inventory()
+            .slots
+            .iter()
+            .map(|slot| slot.count_of(&item))
+            .filter(|&count| count > 0)
+            .collect();
+        assert_eq!(stack_sizes, vec![12, 8]);
+    }
+}
+
 #[test]
 fn visit_refs_block_def_no_ref() {
     assert_eq!(list_refs(&BlockDef::new(AIR)), vec![]);
@@ -364,3 +710,66 @@ fn visit_refs_space() {
     // TODO: Also add a behavior and a spawn inventory item containing refs and check those
     assert_eq!(list_refs(&space), vec![block_def_ref.name().clone()]);
 }
+
+#[test]
+fn determinism_audit_finds_divergence() {
+    let [block] = make_some_blocks();
+    let run = |diverge: bool| {
+        let mut universe = Universe::new();
+        let space = universe
+            .insert("space".into(), Space::empty_positive(2, 2, 2))
+            .unwrap();
+        universe.insert("other".into(), BlockDef::new(AIR)).unwrap();
+        universe.set_determinism_audit(true);
+        for i in 0..4 {
+            if diverge && i == 2 {
+                space
+                    .try_modify(|space| space.set([0, 0, 0], &block))
+                    .unwrap()
+                    .unwrap();
+            }
+            universe.step(Tick::arbitrary(), practically_infinite_deadline());
+        }
+        universe.determinism_audit().unwrap().clone()
+    };
+
+    let original = run(false);
+    assert_eq!(original.hashes().len(), 4);
+    assert_eq!(original.first_divergence(&run(false)), None);
+    assert_eq!(
+        original.first_divergence(&run(true)),
+        Some(Divergence {
+            step: 3,
+            member: "space".into(),
+        })
+    );
+}
+
+/// [`PlayerStats`] keeps block counts in [`HashMap`](std::collections::HashMap)s, whose
+/// iteration order differs between instances; the hash must not.
+#[test]
+fn state_hash_independent_of_player_stats_order() {
+    let blocks: [Block; 2] = make_some_blocks();
+    let hash = |order: [usize; 2]| {
+        let mut universe = Universe::new();
+        let space = universe
+            .insert("space".into(), Space::empty_positive(1, 1, 1))
+            .unwrap();
+        let character = universe
+            .insert("character".into(), Character::spawn_default(space))
+            .unwrap();
+        let mut stats = PlayerStats::new(character);
+        for i in order {
+            stats.blocks_placed.insert(blocks[i].clone(), i as u64 + 1);
+            stats.blocks_removed.insert(blocks[i].clone(), 10);
+        }
+        universe.insert("stats".into(), stats).unwrap();
+        universe.state_hash()
+    };
+
+    let expected = hash([0, 1]);
+    for _ in 0..10 {
+        assert_eq!(hash([0, 1]), expected);
+        assert_eq!(hash([1, 0]), expected);
+    }
+}
@@ -4,6 +4,9 @@ use std::fmt::{self, Debug};
 
 use crate::block::BlockDef;
 use crate::character::Character;
+use crate::entity::Entity;
+use crate::event::GameEvent;
+use crate::sound::SoundEvent;
 use crate::space::Space;
 use crate::transaction::{
     self, CommitError, Merge, PreconditionFailed, Transaction, TransactionConflict, Transactional,
@@ -124,6 +127,7 @@ enum AnyTransaction {
     Noop,
     BlockDef(TransactionInUniverse<BlockDef>),
     Character(TransactionInUniverse<Character>),
+    Entity(TransactionInUniverse<Entity>),
     Space(TransactionInUniverse<Space>),
 }
 
@@ -138,6 +142,7 @@ impl AnyTransaction {
             Noop => None,
             BlockDef(t) => Some(t.target.name()),
             Character(t) => Some(t.target.name()),
+            Entity(t) => Some(t.target.name()),
             Space(t) => Some(t.target.name()),
         }
     }
@@ -149,6 +154,7 @@ impl AnyTransaction {
             Noop => &"AnyTransaction::Noop",
             BlockDef(t) => &t.transaction,
             Character(t) => &t.transaction,
+            Entity(t) => &t.transaction,
             Space(t) => &t.transaction,
         }
     }
@@ -159,6 +165,7 @@ impl AnyTransaction {
             Noop => None,
             BlockDef(t) => t.target.universe_id(),
             Character(t) => t.target.universe_id(),
+            Entity(t) => t.target.universe_id(),
             Space(t) => t.target.universe_id(),
         }
     }
@@ -174,6 +181,7 @@ impl Transaction<()> for AnyTransaction {
             Noop => Box::new(()),
             BlockDef(t) => Box::new(t.check(&())?),
             Character(t) => Box::new(t.check(&())?),
+            Entity(t) => Box::new(t.check(&())?),
             Space(t) => Box::new(t.check(&())?),
         })
     }
@@ -205,6 +213,7 @@ impl Transaction<()> for AnyTransaction {
             Noop => Ok(()),
             BlockDef(t) => commit_helper(t, check, outputs),
             Character(t) => commit_helper(t, check, outputs),
+            Entity(t) => commit_helper(t, check, outputs),
             Space(t) => commit_helper(t, check, outputs),
         }
     }
@@ -220,6 +229,7 @@ impl Merge for AnyTransaction {
             (_, Noop) => Ok(Box::new(())),
             (BlockDef(t1), BlockDef(t2)) => Ok(Box::new(t1.check_merge(t2)?)),
             (Character(t1), Character(t2)) => Ok(Box::new(t1.check_merge(t2)?)),
+            (Entity(t1), Entity(t2)) => Ok(Box::new(t1.check_merge(t2)?)),
             (Space(t1), Space(t2)) => Ok(Box::new(t1.check_merge(t2)?)),
             (_, _) => Err(TransactionConflict {}),
         }
@@ -245,6 +255,7 @@ impl Merge for AnyTransaction {
             (Noop, t2) => t2,
             (BlockDef(t1), BlockDef(t2)) => merge_helper(t1, t2, BlockDef, check),
             (Character(t1), Character(t2)) => merge_helper(t1, t2, Character, check),
+            (Entity(t1), Entity(t2)) => merge_helper(t1, t2, Entity, check),
             (Space(t1), Space(t2)) => merge_helper(t1, t2, Space, check),
             (_, _) => panic!("Mismatched transaction target types"),
         }
@@ -277,6 +288,14 @@ mod any_transaction {
             }))
         }
     }
+    impl UTransactional for Entity {
+        fn bind(target: URef<Self>, transaction: Self::Transaction) -> UniverseTransaction {
+            UniverseTransaction::from(AnyTransaction::Entity(TransactionInUniverse {
+                target,
+                transaction,
+            }))
+        }
+    }
     impl UTransactional for Space {
         fn bind(target: URef<Self>, transaction: Self::Transaction) -> UniverseTransaction {
             UniverseTransaction::from(AnyTransaction::Space(TransactionInUniverse {
@@ -311,6 +330,12 @@ pub struct UniverseTransaction {
 
     /// Invariant: Has a universe ID if any of the `members` do.
     universe_id: Option<UniverseId>,
+
+    /// Sounds to emit when the transaction is committed.
+    sounds: Vec<SoundEvent>,
+
+    /// Events to notify the universe's listeners of when the transaction is committed.
+    events: Vec<GameEvent>,
 }
 
 // TODO: Benchmark cheaper HashMaps / using BTreeMap here
@@ -337,6 +362,8 @@ impl UniverseTransaction {
             universe_id: transaction.universe_id(),
             members: HashMap::from([(name, transaction)]),
             anonymous_insertions: Vec::new(),
+            sounds: Vec::new(),
+            events: Vec::new(),
         }
     }
 
@@ -357,6 +384,8 @@ impl UniverseTransaction {
                     reference,
                 ))],
                 universe_id: None,
+                sounds: Vec::new(),
+                events: Vec::new(),
             },
         }
     }
@@ -367,7 +396,7 @@ impl UniverseTransaction {
     /// the same name is later added.
     ///
     /// This transaction will fail if the member is already gone, is anonymous
-    /// (only named entries can be deleted), or belongs to another universe.
+    /// (only named entries and [`Entity`]s can be deleted), or belongs to another universe.
     /// In the future, there may be a policy such that in-use items cannot be deleted.
     ///
     /// [`RefError::Gone`]: crate::universe::RefError::Gone
@@ -375,6 +404,24 @@ impl UniverseTransaction {
         Self::from_member_txn(member_ref.name(), MemberTxn::Delete)
     }
 
+    /// Transaction which emits the given sound when committed, to be reported by
+    /// [`Universe::take_sounds()`].
+    pub fn sound(event: SoundEvent) -> Self {
+        Self {
+            sounds: vec![event],
+            ..Default::default()
+        }
+    }
+
+    /// Transaction which, when committed, notifies the universe's listeners of the given
+    /// event.
+    pub fn event(event: GameEvent) -> Self {
+        Self {
+            events: vec![event],
+            ..Default::default()
+        }
+    }
+
     /// If this transaction contains any operations that are on a specific member of a
     /// universe, then returns the ID of that universe.
     // TODO: make public?
@@ -457,6 +504,21 @@ impl Transaction<Universe> for UniverseTransaction {
             new_member.commit(target, &Name::Pending, check, outputs)?;
         }
 
+        target.sounds.extend(self.sounds.iter().cloned());
+        if !self.events.is_empty() {
+            for stats_root in target.tables.player_stats.values() {
+                // Failing to update statistics should not fail the transaction.
+                let _ = stats_root.downgrade().try_modify(|stats| {
+                    for event in &self.events {
+                        stats.record_event(event);
+                    }
+                });
+            }
+        }
+        for event in &self.events {
+            target.event_notifier.notify(event.clone());
+        }
+
         Ok(())
     }
 }
@@ -481,11 +543,17 @@ impl Merge for UniverseTransaction {
     {
         let mut anonymous_insertions = self.anonymous_insertions;
         anonymous_insertions.extend(other.anonymous_insertions);
+        let mut sounds = self.sounds;
+        sounds.extend(other.sounds);
+        let mut events = self.events;
+        events.extend(other.events);
 
         UniverseTransaction {
             members: self.members.commit_merge(other.members, check),
             universe_id: self.universe_id.or(other.universe_id),
             anonymous_insertions,
+            sounds,
+            events,
         }
     }
 }
@@ -499,6 +567,12 @@ impl Debug for UniverseTransaction {
             // TransactionInUniverse wrapper
             ds.field(&name.to_string(), txn.transaction_as_debug());
         }
+        if !self.sounds.is_empty() {
+            ds.field("sounds", &self.sounds);
+        }
+        if !self.events.is_empty() {
+            ds.field("events", &self.events);
+        }
         ds.finish()
     }
 }
@@ -575,7 +649,13 @@ impl MemberTxn {
                 Ok(MemberCommitCheck(None))
             }
             MemberTxn::Delete => {
-                if let Name::Specific(_) = name {
+                // Anonymous entities are not garbage collected, so they must be deletable.
+                let deletable = match name {
+                    Name::Specific(_) => true,
+                    Name::Anonym(_) => universe.tables.entities.contains_key(name),
+                    Name::Pending => false,
+                };
+                if deletable {
                     if universe.get_any(name).is_some() {
                         Ok(MemberCommitCheck(None))
                     } else {
@@ -637,9 +717,14 @@ impl MemberTxn {
                 match pending_ref {
                     AnyURef::BlockDef(pending_ref) => do_insert(universe, pending_ref),
                     AnyURef::Character(pending_ref) => do_insert(universe, pending_ref),
+                    AnyURef::Entity(pending_ref) => do_insert(universe, pending_ref),
+                    AnyURef::PlayerStats(pending_ref) => do_insert(universe, pending_ref),
+                    AnyURef::Script(pending_ref) => do_insert(universe, pending_ref),
                     AnyURef::Space(pending_ref) => do_insert(universe, pending_ref),
+                    AnyURef::Waypoint(pending_ref) => do_insert(universe, pending_ref),
                 }
                 .map_err(CommitError::catch::<Self, _>)?;
+                universe.member_inserted(pending_ref);
                 Ok(())
             }
             MemberTxn::Delete => {
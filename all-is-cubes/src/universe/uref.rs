@@ -90,7 +90,7 @@ impl<T: 'static> URef<T> {
     /// TODO: Actually inserting these into a [`Universe`] is not yet implemented.
     pub fn new_pending(name: Name, initial_value: T) -> Self {
         let strong_ref = Arc::new(RwLock::new(UEntry {
-            data: initial_value,
+            data: Some(initial_value),
         }));
         URef {
             weak_ref: Arc::downgrade(&strong_ref),
@@ -148,7 +148,13 @@ impl<T: 'static> URef<T> {
     /// TODO: There is not currently any way to block on / wait for read access.
     pub fn read(&self) -> Result<UBorrow<T>, RefError> {
         let inner = UBorrowImpl::try_new(self.upgrade()?, |strong: &Arc<RwLock<UEntry<T>>>| {
-            strong.try_read().map_err(|_| RefError::InUse(self.name()))
+            let guard = strong
+                .try_read()
+                .map_err(|_| RefError::InUse(self.name()))?;
+            if guard.data.is_none() {
+                return Err(RefError::Gone(self.name()));
+            }
+            Ok(guard)
         })?;
         Ok(UBorrow(inner))
     }
@@ -170,7 +176,10 @@ impl<T: 'static> URef<T> {
         let mut borrow = strong
             .try_write()
             .map_err(|_| RefError::InUse(self.name()))?;
-        Ok(function(&mut borrow.data))
+        match borrow.data {
+            Some(ref mut data) => Ok(function(data)),
+            None => Err(RefError::Gone(self.name())),
+        }
     }
 
     /// Gain mutable access but don't use it immediately.
@@ -180,7 +189,13 @@ impl<T: 'static> URef<T> {
     /// purposes.
     pub(crate) fn try_borrow_mut(&self) -> Result<UBorrowMutImpl<T>, RefError> {
         UBorrowMutImpl::try_new(self.upgrade()?, |strong: &Arc<RwLock<UEntry<T>>>| {
-            strong.try_write().map_err(|_| RefError::InUse(self.name()))
+            let guard = strong
+                .try_write()
+                .map_err(|_| RefError::InUse(self.name()))?;
+            if guard.data.is_none() {
+                return Err(RefError::Gone(self.name()));
+            }
+            Ok(guard)
         })
     }
 
@@ -343,7 +358,10 @@ impl<T: fmt::Debug + 'static> fmt::Debug for URef<T> {
                             // TODO: maybe only do it if we are in alternate/prettyprint format.
                             write!(f, " = ")?;
                             match strong.try_read() {
-                                Ok(uentry) => fmt::Debug::fmt(&uentry.data, f)?,
+                                Ok(uentry) => match uentry.data {
+                                    Some(ref data) => fmt::Debug::fmt(data, f)?,
+                                    None => write!(f, "<placeholder>")?,
+                                },
                                 Err(e) => write!(f, "<entry lock error: {e}>")?,
                             }
                         }
@@ -436,7 +454,11 @@ impl<T: fmt::Debug> fmt::Debug for UBorrow<T> {
 impl<T> Deref for UBorrow<T> {
     type Target = T;
     fn deref(&self) -> &T {
-        &self.0.borrow_guard().data
+        self.0
+            .borrow_guard()
+            .data
+            .as_ref()
+            .expect("shouldn't happen: UBorrow of placeholder")
     }
 }
 impl<T> AsRef<T> for UBorrow<T> {
@@ -478,7 +500,14 @@ impl<T> UBorrowMutImpl<T> {
     where
         F: FnOnce(&mut T) -> Out,
     {
-        self.with_guard_mut(|entry| function(&mut entry.data))
+        self.with_guard_mut(|entry| {
+            function(
+                entry
+                    .data
+                    .as_mut()
+                    .expect("shouldn't happen: UBorrowMutImpl of placeholder"),
+            )
+        })
     }
 }
 
@@ -487,7 +516,10 @@ impl<T> UBorrowMutImpl<T> {
 struct UEntry<T> {
     // TODO: It might make more sense for data to be a RwLock<T> (instead of the
     // RwLock containing UEntry), but we don't have enough examples to be certain yet.
-    data: T,
+    /// The value of the entry, or [`None`] if this entry is a placeholder created during
+    /// deserialization whose value has not been deserialized yet. Placeholders are
+    /// treated as [`RefError::Gone`] by all accessors.
+    data: Option<T>,
 }
 
 /// The unique reference to an entry in a [`Universe`] from that `Universe`.
@@ -504,12 +536,46 @@ impl<T> URootRef<T> {
     pub(super) fn new(universe_id: UniverseId, name: Name, initial_value: T) -> Self {
         URootRef {
             strong_ref: Arc::new(RwLock::new(UEntry {
-                data: initial_value,
+                data: Some(initial_value),
             })),
             state: Arc::new(Mutex::new(State::Member { name, universe_id })),
         }
     }
 
+    /// Construct a root ref whose value is not yet known, so that [`URef`]s to it can be
+    /// created before the value is deserialized.
+    /// Use [`URootRef::fill_placeholder()`] to give it a value.
+    pub(super) fn new_placeholder(universe_id: UniverseId, name: Name) -> Self {
+        URootRef {
+            strong_ref: Arc::new(RwLock::new(UEntry { data: None })),
+            state: Arc::new(Mutex::new(State::Member { name, universe_id })),
+        }
+    }
+
+    /// Returns whether this entry was created by [`URootRef::new_placeholder()`] and has
+    /// not yet been given a value.
+    pub(super) fn is_placeholder(&self) -> bool {
+        match self.strong_ref.try_read() {
+            Ok(entry) => entry.data.is_none(),
+            Err(_) => false,
+        }
+    }
+
+    /// Give a value to a placeholder entry.
+    ///
+    /// Returns false and discards the value if the entry already has a value.
+    pub(super) fn fill_placeholder(&self, value: T) -> bool {
+        let mut entry = self
+            .strong_ref
+            .write()
+            .expect("URootRef::strong_ref lock error");
+        if entry.data.is_some() {
+            return false;
+        }
+        entry.data = Some(value);
+        true
+    }
+
     /// Convert to `URef`.
     ///
     /// TODO: As we add graph analysis features, this will need additional arguments
@@ -526,6 +592,16 @@ impl<T> URootRef<T> {
     pub(crate) fn weak_ref_count(&self) -> usize {
         Arc::weak_count(&self.strong_ref)
     }
+
+    /// Change the universe and name of this entry, as seen by all existing [`URef`]s to it.
+    ///
+    /// This is used when moving members from one [`Universe`] into another, so that
+    /// references between the moved members remain valid without needing to be
+    /// individually replaced.
+    pub(super) fn rebind(&self, universe_id: UniverseId, name: Name) {
+        let mut state_guard = self.state.lock().expect("URootRef::state lock error");
+        *state_guard = State::Member { name, universe_id };
+    }
 }
 
 /// Object-safe trait implemented for [`URef`], to allow code to operate on `URef<T>`
@@ -572,8 +648,8 @@ mod tests {
             format!("{r:?}"),
             "URef('foo' in no universe = BlockDef { \
                 block: Block { primitive: Atom(BlockAttributes {}, Rgba(1.0, 1.0, 1.0, 1.0)) }, \
-                notifier: Notifier(0), \
-                block_listen_gate: Gate })"
+                notifier: Notifier(1), \
+                block_listen_gate: Gate, .. })"
         );
         assert_eq!(
             format!("{r:#?}"),
@@ -585,8 +661,9 @@ mod tests {
                         Rgba(1.0, 1.0, 1.0, 1.0),
                     ),
                 },
-                notifier: Notifier(0),
+                notifier: Notifier(1),
                 block_listen_gate: Gate,
+                ..
             })"
             }
         );
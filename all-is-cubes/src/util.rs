@@ -9,6 +9,8 @@ use std::time::Duration;
 use cgmath::{Matrix4, Point3, Vector2, Vector3, Vector4};
 use instant::Instant;
 
+mod cancel;
+pub use cancel::*;
 mod yield_progress;
 pub use yield_progress::*;
 
@@ -0,0 +1,70 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+/// A flag shared between a long-running operation and whoever started it, which the
+/// latter may set to ask the operation to stop early.
+///
+/// Cancellation is cooperative: the operation checks the flag at convenient points,
+/// such as between batches of work, and returns [`Cancelled`] if it is set.
+/// This complements cancelling an async operation by dropping its future, which takes
+/// effect only when the future yields, and cannot reach synchronous work in progress
+/// on another thread.
+///
+/// Clones of a token share the same flag.
+#[derive(Clone, Debug, Default)]
+pub struct CancellationToken(Arc<AtomicBool>);
+
+impl CancellationToken {
+    /// Constructs a new token which is not cancelled.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Requests that operations using this token (or any of its clones) stop.
+    /// This cannot be undone.
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    /// Returns whether [`Self::cancel()`] has been called.
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+
+    /// Returns [`Err`] if [`Self::cancel()`] has been called, for convenient use with `?`.
+    pub fn check(&self) -> Result<(), Cancelled> {
+        if self.is_cancelled() {
+            Err(Cancelled {})
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// Error returned by an operation which stopped early because its [`CancellationToken`]
+/// was cancelled.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq, thiserror::Error)]
+#[error("the operation was cancelled")]
+#[non_exhaustive]
+pub struct Cancelled {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::assert_send_sync;
+
+    #[test]
+    fn token_is_send_sync() {
+        assert_send_sync::<CancellationToken>()
+    }
+
+    #[test]
+    fn cancel_affects_clones() {
+        let token = CancellationToken::new();
+        let clone = token.clone();
+        assert_eq!(clone.check(), Ok(()));
+        token.cancel();
+        assert!(clone.is_cancelled());
+        assert_eq!(clone.check(), Err(Cancelled {}));
+    }
+}
@@ -5,6 +5,8 @@ use std::sync::{Arc, Mutex};
 use futures_core::future::{BoxFuture, Future};
 use instant::{Duration, Instant};
 
+use crate::util::{CancellationToken, Cancelled};
+
 /// Allows a long-running async task to report its progress, while also yielding to the
 /// scheduler (e.g. for single-threaded web environment) and introducing cancellation
 /// points.
@@ -17,23 +19,60 @@ use instant::{Duration, Instant};
 /// reporting the progress of, and yielding periodically within, a fully sequential
 /// operation. This might change in the future, but for now, it will just output
 /// inconsistent results if you try to use it otherwise.
+///
+/// Portions of the work may be given labels using [`YieldProgress::set_label()`] and
+/// [`YieldProgress::start_and_cut()`]. Labeled portions nest, so that a progress callback
+/// given to [`YieldProgress::new_staged()`] can display, for example,
+/// “Generating terrain 40% / Lighting 10%”.
+///
+/// A [`CancellationToken`] may be attached using [`YieldProgress::with_cancellation()`],
+/// so that operations which already take a [`YieldProgress`] can also check whether
+/// they should stop early.
 pub struct YieldProgress {
     start: f32,
     end: f32,
 
-    /// Name given to this specific portion of work. Inherited from the parent if not
-    /// overridden.
+    /// Labeled portions of work which this is part of, outermost first.
+    /// Inherited from the parent, which is the reason this is not a single label.
     ///
     /// TODO: Eventually we will want to have things like "label this segment as a
     /// fallback if it has no better label", which will require some notion of distinguishing
     /// inheritance from having been explicitly set.
-    label: Option<Arc<str>>,
+    stages: Vec<Stage>,
+
+    /// Whether the last element of `stages` was created by [`Self::set_label()`] on this
+    /// [`YieldProgress`] rather than being inherited, and so should be replaced rather than
+    /// nested in by another [`Self::set_label()`].
+    own_stage: bool,
+
+    /// Shared by all [`YieldProgress`]es split from the same original.
+    cancellation: CancellationToken,
 
     yielding: Arc<Yielding<dyn Fn() -> BoxFuture<'static, ()> + Send + Sync>>,
     // TODO: change progress reporting interface to support efficient handling of
     // the label string being the same as last time.
     #[allow(clippy::type_complexity)]
-    progressor: Arc<dyn Fn(f32, &str) + Send + Sync>,
+    progressor: Arc<dyn Fn(&ProgressInfo<'_>) + Send + Sync>,
+}
+
+/// A labeled portion of the work of a [`YieldProgress`], and the range of overall
+/// progress it covers.
+#[derive(Clone, Debug)]
+struct Stage {
+    label: Arc<str>,
+    start: f32,
+    end: f32,
+}
+
+/// Progress being reported by a [`YieldProgress`], as passed to the callback given to
+/// [`YieldProgress::new_staged()`].
+///
+/// Its [`Display`](fmt::Display) implementation describes each of the nested labeled
+/// portions of work, in the format “Generating terrain 40% / Lighting 10%”.
+#[derive(Clone, Copy, Debug)]
+pub struct ProgressInfo<'a> {
+    fraction: f32,
+    stages: &'a [Stage],
 }
 
 /// Piggyback on the `Arc` we need to store the `dyn Fn` anyway to also store some state.
@@ -57,7 +96,7 @@ impl fmt::Debug for YieldProgress {
         f.debug_struct("YieldProgress")
             .field("start", &self.start)
             .field("end", &self.end)
-            .field("label", &self.label)
+            .field("stages", &self.stages)
             .finish_non_exhaustive()
     }
 }
@@ -68,12 +107,33 @@ impl YieldProgress {
     ///
     /// Note that it measures time intervals between yields starting from when this
     /// function is called, as if this is the first yield.
+    ///
+    /// `progressor` is given the overall fraction of work done, and the label of the
+    /// innermost labeled portion of work in progress (or the empty string).
     #[track_caller]
     pub fn new<Y, YFut, P>(yielder: Y, progressor: P) -> Self
     where
         Y: Fn() -> YFut + Send + Sync + 'static,
         YFut: Future<Output = ()> + Send + 'static,
         P: Fn(f32, &str) + Send + Sync + 'static,
+    {
+        Self::new_staged(yielder, move |info: &ProgressInfo<'_>| {
+            progressor(info.fraction(), info.label())
+        })
+    }
+
+    /// Construct a new [`YieldProgress`], which will call `yielder` to yield and
+    /// `progressor` to report progress, including the progress of each of the nested
+    /// labeled portions of work.
+    ///
+    /// Note that it measures time intervals between yields starting from when this
+    /// function is called, as if this is the first yield.
+    #[track_caller]
+    pub fn new_staged<Y, YFut, P>(yielder: Y, progressor: P) -> Self
+    where
+        Y: Fn() -> YFut + Send + Sync + 'static,
+        YFut: Future<Output = ()> + Send + 'static,
+        P: Fn(&ProgressInfo<'_>) + Send + Sync + 'static,
     {
         let yielding: Arc<Yielding<_>> = Arc::new(Yielding {
             state: Mutex::new(YieldState {
@@ -87,7 +147,9 @@ impl YieldProgress {
         Self {
             start: 0.0,
             end: 1.0,
-            label: None,
+            stages: Vec::new(),
+            own_stage: false,
+            cancellation: CancellationToken::new(),
             yielding,
             progressor: Arc::new(progressor),
         }
@@ -100,9 +162,52 @@ impl YieldProgress {
 
     /// Add a name for the portion of work this [`YieldProgress`] covers.
     ///
-    /// If there is already a label, it will be overwritten.
+    /// If a label was already set on this [`YieldProgress`], it will be overwritten.
+    /// A label inherited from the [`YieldProgress`] this one was split from is instead
+    /// kept, and reported as the enclosing stage of work.
     pub fn set_label(&mut self, label: impl fmt::Display) {
-        self.label = Some(Arc::from(label.to_string()))
+        let stage = Stage {
+            label: Arc::from(label.to_string()),
+            start: self.start,
+            end: self.end,
+        };
+        if self.own_stage {
+            if let Some(last) = self.stages.last_mut() {
+                *last = stage;
+            }
+        } else {
+            self.stages.push(stage);
+            self.own_stage = true;
+        }
+    }
+
+    /// Attaches `token` to this [`YieldProgress`] and all those which will be split from
+    /// it, replacing any token previously attached.
+    #[must_use]
+    pub fn with_cancellation(mut self, token: CancellationToken) -> Self {
+        self.cancellation = token;
+        self
+    }
+
+    /// Returns the [`CancellationToken`] attached to this [`YieldProgress`], for passing
+    /// to non-async operations such as [`Space::evaluate_light_cancellable()`].
+    ///
+    /// If none was attached, this is a token which nothing else can cancel.
+    ///
+    /// [`Space::evaluate_light_cancellable()`]: crate::space::Space::evaluate_light_cancellable
+    pub fn cancellation(&self) -> &CancellationToken {
+        &self.cancellation
+    }
+
+    /// Returns [`Err`] if the operation this [`YieldProgress`] reports on has been
+    /// cancelled, for convenient use with `?`.
+    pub fn check_cancelled(&self) -> Result<(), Cancelled> {
+        self.cancellation.check()
+    }
+
+    /// Returns the label of the innermost labeled portion of work, if any.
+    fn label(&self) -> Option<Arc<str>> {
+        self.stages.last().map(|stage| Arc::clone(&stage.label))
     }
 
     /// Map a `0..=1` value to `self.start..=self.end`.
@@ -123,15 +228,12 @@ impl YieldProgress {
     #[track_caller] // This is not an `async fn` because `track_caller` is not compatible
     pub fn progress(&self, progress_fraction: f32) -> impl Future<Output = ()> + Send + 'static {
         let location = Location::caller();
-        let label = self.label.clone();
-        (self.progressor)(
-            self.point_in_range(progress_fraction),
-            self.label
-                .as_ref()
-                .map_or("", |arc_str_ref| -> &str { arc_str_ref }),
-        );
+        (self.progressor)(&ProgressInfo {
+            fraction: self.point_in_range(progress_fraction),
+            stages: &self.stages,
+        });
 
-        self.yielding.clone().yield_only(location, label)
+        self.yielding.clone().yield_only(location, self.label())
     }
 
     /// Report that 100% of progress has been made.
@@ -178,7 +280,7 @@ impl YieldProgress {
         label: impl fmt::Display,
     ) -> impl Future<Output = Self> + Send + 'static {
         let cut_abs = self.point_in_range(cut);
-        let mut portion = self.with_new_range(0.0, cut_abs);
+        let mut portion = self.with_new_range(self.start, cut_abs);
         self.start = cut_abs;
 
         portion.set_label(label);
@@ -192,7 +294,9 @@ impl YieldProgress {
         Self {
             start,
             end,
-            label: self.label.clone(),
+            stages: self.stages.clone(),
+            own_stage: false,
+            cancellation: self.cancellation.clone(),
             yielding: Arc::clone(&self.yielding),
             progressor: Arc::clone(&self.progressor),
         }
@@ -210,6 +314,36 @@ impl YieldProgress {
         ]
     }
 
+    /// Construct new [`YieldProgress`]es which divide the progress value into subranges
+    /// whose sizes are proportional to `weights`.
+    ///
+    /// The returned instances should be used in sequence, but this is not enforced.
+    ///
+    /// ```
+    /// # use all_is_cubes::util::YieldProgress;
+    /// # let progress = YieldProgress::noop();
+    /// // Terrain takes four times as long as lighting.
+    /// let [mut terrain_progress, mut lighting_progress] = progress.split_weighted([4.0, 1.0]);
+    /// terrain_progress.set_label("Generating terrain");
+    /// lighting_progress.set_label("Lighting");
+    /// ```
+    ///
+    /// Panics if any weight is negative or not finite.
+    pub fn split_weighted<const N: usize>(self, weights: [f32; N]) -> [Self; N] {
+        assert!(
+            weights.iter().all(|w| w.is_finite() && *w >= 0.0),
+            "invalid progress weights {weights:?}"
+        );
+        let total: f32 = weights.iter().sum();
+        let to_fraction = |x: f32| if total > 0.0 { x / total } else { 0.0 };
+        let mut done = 0.0;
+        weights.map(|weight| {
+            let start = self.point_in_range(to_fraction(done));
+            done += weight;
+            self.with_new_range(start, self.point_in_range(to_fraction(done)))
+        })
+    }
+
     /// Split into even subdivisions.
     pub fn split_evenly(self, count: usize) -> impl Iterator<Item = YieldProgress> {
         assert!(count < usize::MAX);
@@ -222,6 +356,48 @@ impl YieldProgress {
     }
 }
 
+impl<'a> ProgressInfo<'a> {
+    /// Returns the overall fraction of work done, from 0 to 1.
+    pub fn fraction(&self) -> f32 {
+        self.fraction
+    }
+
+    /// Returns the label of the innermost labeled portion of work in progress, or the
+    /// empty string if there is none.
+    pub fn label(&self) -> &'a str {
+        self.stages.last().map_or("", |stage| &*stage.label)
+    }
+
+    /// Returns the label of each labeled portion of work in progress, outermost first,
+    /// together with the fraction of that portion which is done.
+    pub fn stages(&self) -> impl Iterator<Item = (&'a str, f32)> + 'a {
+        let fraction = self.fraction;
+        self.stages.iter().map(move |stage| {
+            let local = (fraction - stage.start) / (stage.end - stage.start);
+            (
+                &*stage.label,
+                if local.is_finite() {
+                    local.clamp(0.0, 1.0)
+                } else {
+                    0.0
+                },
+            )
+        })
+    }
+}
+
+impl fmt::Display for ProgressInfo<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, (label, fraction)) in self.stages().enumerate() {
+            if i > 0 {
+                write!(f, " / ")?;
+            }
+            write!(f, "{label} {percent:.0}%", percent = fraction * 100.0)?;
+        }
+        Ok(())
+    }
+}
+
 impl<F: ?Sized + Fn() -> BoxFuture<'static, ()> + Send + Sync> Yielding<F> {
     async fn yield_only(
         self: Arc<Self>,
@@ -374,5 +550,51 @@ mod tests {
         assert_eq!(r.drain(), vec![Progress(1.0, "".into()), Yielded, Dropped]);
     }
 
+    #[tokio::test]
+    async fn split_weighted() {
+        let (p, mut r) = logging_yield_progress();
+        let [a, b, c] = p.split_weighted([1.0, 2.0, 1.0]);
+        a.finish().await;
+        assert_eq!(r.drain(), vec![Progress(0.25, "".into()), Yielded]);
+        b.progress(0.5).await;
+        assert_eq!(r.drain(), vec![Progress(0.5, "".into()), Yielded]);
+        c.finish().await;
+        assert_eq!(r.drain(), vec![Progress(1.0, "".into()), Yielded, Dropped]);
+    }
+
+    #[tokio::test]
+    async fn nested_stages() {
+        let (sender, mut receiver) = mpsc::unbounded_channel();
+        let mut p = YieldProgress::new_staged(
+            || std::future::ready(()),
+            move |info| drop(sender.send(info.to_string())),
+        );
+        p.set_label("Building");
+
+        let mut terrain = p.start_and_cut(0.5, "Terrain").await;
+        assert_eq!(receiver.try_recv().unwrap(), "Building 0% / Terrain 0%");
+        terrain.progress(0.4).await;
+        assert_eq!(receiver.try_recv().unwrap(), "Building 20% / Terrain 40%");
+
+        // A label set on the same YieldProgress replaces rather than nests.
+        terrain.set_label("Rocks");
+        terrain.progress(0.4).await;
+        assert_eq!(receiver.try_recv().unwrap(), "Building 20% / Rocks 40%");
+
+        p.progress(0.5).await;
+        assert_eq!(receiver.try_recv().unwrap(), "Building 75%");
+    }
+
+    #[test]
+    fn cancellation_is_inherited() {
+        let token = CancellationToken::new();
+        let p = YieldProgress::noop().with_cancellation(token.clone());
+        let [a, b] = p.split(0.5);
+        assert_eq!(b.check_cancelled(), Ok(()));
+        token.cancel();
+        assert!(a.check_cancelled().is_err());
+        assert!(b.cancellation().is_cancelled());
+    }
+
     // TODO: test split() and split_evenly()
 }
@@ -0,0 +1,44 @@
+#![no_main]
+extern crate all_is_cubes;
+
+use all_is_cubes::block::Block;
+use all_is_cubes::math::Rgba;
+use all_is_cubes::space::{Space, SpaceTransaction};
+use all_is_cubes::transaction::TransactionTester;
+
+use libfuzzer_sys::arbitrary::{self, Arbitrary, Unstructured};
+use libfuzzer_sys::fuzz_target;
+
+/// Parameters of a [`SpaceTransaction::set_cube()`] within a 2×2×2 space.
+#[derive(Arbitrary, Debug)]
+struct SetCube {
+    cube: [bool; 3],
+    old: Option<Rgba>,
+    new: Option<Rgba>,
+}
+
+fuzz_target!(|data: &[u8]| {
+    let mut u = Unstructured::new(data);
+    let Ok(set_cubes) = <Vec<SetCube>>::arbitrary(&mut u) else {
+        return;
+    };
+    if set_cubes.is_empty() {
+        return;
+    }
+
+    let mut tester = TransactionTester::new();
+    for SetCube { cube, old, new } in set_cubes {
+        let cube = cube.map(i32::from);
+        let new = new.map(Block::from);
+        tester = tester.transaction(
+            SpaceTransaction::set_cube(cube, old.map(Block::from), new.clone()),
+            move |_, after: &Space| match &new {
+                Some(new) if after[cube] != *new => Err("did not set new block".into()),
+                _ => Ok(()),
+            },
+        );
+    }
+    let _: arbitrary::Result<()> = tester
+        .target(|| Space::empty_positive(2, 2, 2))
+        .test_arbitrary_sequence(&mut u);
+});
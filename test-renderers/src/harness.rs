@@ -44,6 +44,7 @@ pub struct RenderTestContext {
     comparison_log: Arc<Mutex<Vec<ComparisonRecord>>>,
     universe: Option<Arc<Universe>>,
     image_serial: u64,
+    bless: bool,
 }
 
 impl RenderTestContext {
@@ -111,9 +112,18 @@ impl RenderTestContext {
             },
         };
 
-        let mut outcome = crate::compare_rendered_image(combo, allowed_difference.into(), image);
+        let mut outcome =
+            crate::compare_rendered_image(combo.clone(), allowed_difference.into(), image);
 
-        if matches!(outcome.outcome, ComparisonOutcome::Different { .. })
+        if self.bless
+            && matches!(
+                outcome.outcome,
+                ComparisonOutcome::Different { .. } | ComparisonOutcome::NoExpected
+            )
+        {
+            crate::bless_actual_image(&combo);
+            outcome.outcome = ComparisonOutcome::Blessed;
+        } else if matches!(outcome.outcome, ComparisonOutcome::Different { .. })
             && flaws != Flaws::default()
         {
             outcome.outcome = ComparisonOutcome::Flawed(format!("{flaws:?}"));
@@ -143,6 +153,12 @@ pub struct HarnessArgs {
     #[arg(long, default_value = "pretty")]
     format: Format,
 
+    /// Save the output of each test whose output did not match its expected image
+    /// (or which had none) as its new expected image, in `expected/`.
+    /// Tests whose images are saved this way pass.
+    #[arg(long)]
+    bless: bool,
+
     filters: Vec<String>,
 }
 
@@ -175,6 +191,7 @@ where
     let HarnessArgs {
         list: list_only,
         format,
+        bless,
         filters,
     } = args;
 
@@ -236,6 +253,7 @@ where
                             None => None,
                         },
                         image_serial: 0,
+                        bless,
                     };
 
                     let case_start_time = Instant::now();
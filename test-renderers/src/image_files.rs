@@ -1,5 +1,5 @@
 use std::path::PathBuf;
-use std::{fmt, io};
+use std::{fmt, fs, io};
 
 use crate::TestId;
 
@@ -120,3 +120,19 @@ pub(crate) fn load_and_copy_expected_image(
 }
 
 pub(crate) struct NotFound(pub PathBuf);
+
+/// Copy the actual image for `image_id` into the expected images source directory,
+/// so that it is the expected image in future test runs.
+///
+/// Panics if an IO error occurs.
+pub(crate) fn bless_actual_image(image_id: &ImageId) {
+    let actual_file_path = image_path(image_id, Version::Actual);
+    let expected_file_path = image_path(image_id, Version::ExpectedSrc);
+    if let Err(error) = fs::copy(&actual_file_path, &expected_file_path) {
+        panic!(
+            "Failed to copy actual image '{a}' to '{e}': {error}",
+            a = actual_file_path.display(),
+            e = expected_file_path.display(),
+        );
+    }
+}
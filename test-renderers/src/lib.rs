@@ -41,7 +41,6 @@ use std::path::Path;
 use std::path::PathBuf;
 
 use image::RgbaImage;
-use rendiff::{Histogram, Threshold};
 
 use all_is_cubes::character::Character;
 use all_is_cubes::space::Space;
@@ -57,6 +56,8 @@ mod report;
 pub use report::*;
 pub mod test_cases;
 
+pub use rendiff::{Histogram, Threshold};
+
 pub type TestId = String;
 
 /// Given a [`Space`], create the [`Character`] looking at it, with the default name.
@@ -90,6 +91,9 @@ pub enum ComparisonOutcome {
     /// The images were different, but the renderer signaled a known flaw.
     /// The string is a list of flaws, of unspecified syntax.
     Flawed(String),
+    /// The images were different or there was no expected image, and the actual image
+    /// was saved as the new expected image because the harness was run with `--bless`.
+    Blessed,
 }
 
 impl ComparisonRecord {
@@ -122,7 +126,9 @@ impl ComparisonRecord {
 
     fn panic_if_unsuccessful(&self) {
         match self.outcome {
-            ComparisonOutcome::Equal | ComparisonOutcome::Flawed(_) => {}
+            ComparisonOutcome::Equal
+            | ComparisonOutcome::Flawed(_)
+            | ComparisonOutcome::Blessed => {}
             ComparisonOutcome::Different { amount } => {
                 // TODO: show filenames
                 panic!("Image mismatch! ({amount})");
@@ -137,6 +143,61 @@ impl ComparisonRecord {
     }
 }
 
+/// Result of [`compare_images()`].
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub struct ImageComparison {
+    /// Whether the differences between the images are within the allowed threshold.
+    pub within_threshold: bool,
+    /// The largest difference between corresponding pixels, from 0 to 255.
+    pub max_difference: u8,
+    /// Count of pixels having each amount of difference.
+    pub histogram: Histogram,
+    /// An image highlighting the differences, if there were any.
+    pub diff_image: Option<RgbaImage>,
+}
+
+/// Compares two images, allowing differences up to `allowed_difference`.
+///
+/// This is the comparison used by the test harness, which may also be used on its own,
+/// for example to check images produced by a renderer outside of the harness against
+/// expected images.
+///
+/// If the images are of different sizes, they are not compared pixel by pixel;
+/// the result has an empty histogram, a [`max_difference`](ImageComparison::max_difference)
+/// of 255, and is not within the threshold.
+pub fn compare_images(
+    expected: &RgbaImage,
+    actual: &RgbaImage,
+    allowed_difference: impl Into<Threshold>,
+) -> ImageComparison {
+    if expected.dimensions() != actual.dimensions() {
+        return ImageComparison {
+            within_threshold: false,
+            max_difference: u8::MAX,
+            histogram: Histogram::ZERO,
+            diff_image: None,
+        };
+    }
+
+    let diff_result = rendiff::diff(actual, expected);
+    ImageComparison {
+        within_threshold: allowed_difference.into().allows(diff_result.histogram),
+        max_difference: diff_result
+            .histogram
+            .0
+            .iter()
+            .copied()
+            .enumerate()
+            .rev() // find highest difference
+            .find(|&(_, v)| v > 0)
+            .map(|(i, _)| i as u8)
+            .unwrap_or(0),
+        histogram: diff_result.histogram,
+        diff_image: diff_result.diff_image,
+    }
+}
+
 /// Finish a rendering test by storing/displaying/comparing the output image.
 pub fn compare_rendered_image(
     test: ImageId,
@@ -177,8 +238,8 @@ pub fn compare_rendered_image(
         };
 
     // Compare expected and actual images
-    let diff_result = rendiff::diff(&actual_image, &expected_image);
-    if let Some(image) = &diff_result.diff_image {
+    let comparison = compare_images(&expected_image, &actual_image, allowed_difference);
+    if let Some(image) = &comparison.diff_image {
         image
             .save(&diff_file_path)
             .expect("failed to write renderer diff image");
@@ -199,21 +260,12 @@ pub fn compare_rendered_image(
         &expected_file_path,
         &actual_file_path,
         Some(&diff_file_path),
-        diff_result.histogram,
-        if allowed_difference.allows(diff_result.histogram) {
+        comparison.histogram,
+        if comparison.within_threshold {
             ComparisonOutcome::Equal
         } else {
             ComparisonOutcome::Different {
-                amount: diff_result
-                    .histogram
-                    .0
-                    .iter()
-                    .copied()
-                    .enumerate()
-                    .rev() // find highest difference
-                    .find(|&(_, v)| v > 0)
-                    .map(|(i, _)| i as u8)
-                    .unwrap_or(0),
+                amount: comparison.max_difference,
             }
         },
     )
@@ -8,8 +8,9 @@ use futures_util::FutureExt;
 
 use all_is_cubes::block::{Block, Resolution::R2};
 use all_is_cubes::camera::{
-    AntialiasingOption, ExposureOption, FogOption, GraphicsOptions, LightingOption, RenderError,
-    StandardCameras, ToneMappingOperator, TransparencyOption, UiViewState, ViewTransform, Viewport,
+    AntialiasingOption, CameraMode, ExposureOption, FogOption, GraphicsOptions, LightingOption,
+    RenderError, StandardCameras, ToneMappingOperator, TransparencyOption, UiPaletteOption,
+    UiViewState, ViewTransform, Viewport,
 };
 use all_is_cubes::cgmath::{EuclideanSpace as _, One, Point2, Point3, Vector2, Vector3};
 use all_is_cubes::character::{Character, Spawn};
@@ -312,7 +313,10 @@ async fn follow_character_change(context: RenderTestContext) {
         ListenableSource::constant(GraphicsOptions::UNALTERED_COLORS),
         ListenableSource::constant(COMMON_VIEWPORT),
         character_cell.as_source(),
+        ListenableSource::constant(CameraMode::default()),
         ListenableSource::constant(UiViewState::default()),
+        ListenableSource::constant(UiViewState::default()),
+        ListenableSource::constant(1.0),
     );
     let mut renderer = context.renderer(cameras);
 
@@ -361,7 +365,10 @@ async fn follow_options_change(mut context: RenderTestContext) {
         options_cell.as_source(),
         ListenableSource::constant(COMMON_VIEWPORT),
         ListenableSource::constant(universe.get_default_character()),
+        ListenableSource::constant(CameraMode::default()),
+        ListenableSource::constant(UiViewState::default()),
         ListenableSource::constant(UiViewState::default()),
+        ListenableSource::constant(1.0),
     );
 
     // Render the image once. This isn't that interesting a comparison test,
@@ -400,10 +407,15 @@ async fn icons(mut context: RenderTestContext) {
         .await
         .install(universe)
         .unwrap();
-    let ui_blocks_p = UiBlocks::new(universe, YieldProgress::noop())
-        .await
-        .install(universe)
-        .unwrap();
+    let ui_blocks_p = UiBlocks::new(
+        universe,
+        all_is_cubes_ui::i18n::Language::English.catalog(),
+        &UiPaletteOption::Standard,
+        YieldProgress::noop(),
+    )
+    .await
+    .install(universe)
+    .unwrap();
 
     fn get_blocks<E: BlockModule + 'static>(
         universe: &Universe,
@@ -547,11 +559,14 @@ async fn layers_all_show_ui(mut context: RenderTestContext, show_ui: bool) {
         ListenableSource::constant(options.clone()),
         ListenableSource::constant(COMMON_VIEWPORT),
         ListenableSource::constant(universe.get_default_character()),
+        ListenableSource::constant(CameraMode::default()),
         ListenableSource::constant(UiViewState {
             space: Some(ui_space(&mut universe)),
             view_transform: ViewTransform::one(),
             graphics_options: options,
         }),
+        ListenableSource::constant(UiViewState::default()),
+        ListenableSource::constant(1.0),
     );
 
     context
@@ -594,11 +609,14 @@ async fn layers_ui_only(mut context: RenderTestContext) {
         ListenableSource::constant(GraphicsOptions::UNALTERED_COLORS),
         ListenableSource::constant(COMMON_VIEWPORT),
         ListenableSource::constant(None),
+        ListenableSource::constant(CameraMode::default()),
         ListenableSource::constant(UiViewState {
             space: Some(ui_space(&mut universe)),
             view_transform: ViewTransform::one(),
             graphics_options: GraphicsOptions::UNALTERED_COLORS,
         }),
+        ListenableSource::constant(UiViewState::default()),
+        ListenableSource::constant(1.0),
     );
 
     context
@@ -718,7 +736,10 @@ async fn zero_viewport(mut context: RenderTestContext) {
         ListenableSource::constant(GraphicsOptions::default()),
         viewport_cell.as_source(),
         ListenableSource::constant(universe.get_default_character()),
+        ListenableSource::constant(CameraMode::default()),
+        ListenableSource::constant(UiViewState::default()),
         ListenableSource::constant(UiViewState::default()),
+        ListenableSource::constant(1.0),
     );
     let overlays = Overlays {
         cursor: None,